@@ -0,0 +1,209 @@
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{header, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use rand::Rng;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::config::{ChaosConfig, ChaosMode};
+
+/// Fault selected for a single request
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChaosFault {
+    Delay(Duration),
+    Error,
+    Drop,
+}
+
+/// Injects failures into task requests according to a runtime-adjustable config
+pub struct ChaosInjector {
+    config: RwLock<ChaosConfig>,
+}
+
+impl ChaosInjector {
+    /// Create a new injector from configuration
+    pub fn new(config: ChaosConfig) -> Self {
+        if config.enabled {
+            warn!(
+                "Chaos mode ENABLED: injecting {:?} on {:.1}% of task requests",
+                config.mode,
+                config.probability * 100.0
+            );
+        }
+        Self {
+            config: RwLock::new(config),
+        }
+    }
+
+    /// Get a copy of the current configuration
+    pub fn config(&self) -> ChaosConfig {
+        self.config.read().unwrap().clone()
+    }
+
+    /// Replace the current configuration (used by the admin toggle)
+    pub fn update(&self, config: ChaosConfig) {
+        info!(
+            "Chaos config updated: enabled={}, mode={:?}, probability={}",
+            config.enabled, config.mode, config.probability
+        );
+        *self.config.write().unwrap() = config;
+    }
+
+    /// Decide whether to inject a fault into the current request
+    pub fn roll(&self) -> Option<ChaosFault> {
+        let config = self.config.read().unwrap();
+        if !config.enabled || config.probability <= 0.0 {
+            return None;
+        }
+
+        // gen::<f64>() yields [0, 1), so probability 1.0 always fires
+        if rand::thread_rng().gen::<f64>() >= config.probability {
+            return None;
+        }
+
+        Some(match config.mode {
+            ChaosMode::Delay => ChaosFault::Delay(Duration::from_millis(config.delay_ms)),
+            ChaosMode::Error => ChaosFault::Error,
+            ChaosMode::Drop => ChaosFault::Drop,
+        })
+    }
+}
+
+/// Middleware applied to task routes that injects configured failures
+pub async fn chaos_middleware(
+    State(injector): State<Arc<ChaosInjector>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    match injector.roll() {
+        None => next.run(req).await,
+        Some(ChaosFault::Delay(delay)) => {
+            info!(
+                "Chaos: delaying {} by {}ms",
+                req.uri().path(),
+                delay.as_millis()
+            );
+            tokio::time::sleep(delay).await;
+            next.run(req).await
+        }
+        Some(ChaosFault::Error) => {
+            info!("Chaos: returning 503 for {}", req.uri().path());
+            (
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(serde_json::json!({
+                    "error": "Injected failure (chaos mode)",
+                })),
+            )
+                .into_response()
+        }
+        Some(ChaosFault::Drop) => {
+            info!("Chaos: dropping connection for {}", req.uri().path());
+            // A body stream that fails immediately makes hyper abort the connection
+            // before a complete response is written
+            let stream = futures_util::stream::once(async {
+                Err::<axum::body::Bytes, std::io::Error>(std::io::Error::new(
+                    std::io::ErrorKind::ConnectionAborted,
+                    "chaos: connection dropped",
+                ))
+            });
+            Response::builder()
+                .status(StatusCode::OK)
+                .header(header::CONNECTION, "close")
+                .body(Body::from_stream(stream))
+                .unwrap_or_else(|_| StatusCode::SERVICE_UNAVAILABLE.into_response())
+        }
+    }
+}
+
+/// GET /admin/chaos - current chaos configuration
+pub async fn get_chaos(State(injector): State<Arc<ChaosInjector>>) -> Json<ChaosConfig> {
+    Json(injector.config())
+}
+
+/// POST /admin/chaos - replace chaos configuration at runtime
+pub async fn set_chaos(
+    State(injector): State<Arc<ChaosInjector>>,
+    Json(config): Json<ChaosConfig>,
+) -> Result<Json<ChaosConfig>, (StatusCode, Json<serde_json::Value>)> {
+    if let Err(e) = config.validate() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "error": e })),
+        ));
+    }
+    injector.update(config);
+    Ok(Json(injector.config()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(probability: f64, mode: ChaosMode) -> ChaosConfig {
+        ChaosConfig {
+            enabled: true,
+            probability,
+            mode,
+            delay_ms: 250,
+        }
+    }
+
+    #[test]
+    fn test_probability_one_always_injects() {
+        let injector = ChaosInjector::new(config(1.0, ChaosMode::Error));
+        for _ in 0..1000 {
+            assert_eq!(injector.roll(), Some(ChaosFault::Error));
+        }
+
+        injector.update(config(1.0, ChaosMode::Delay));
+        assert_eq!(
+            injector.roll(),
+            Some(ChaosFault::Delay(Duration::from_millis(250)))
+        );
+    }
+
+    #[test]
+    fn test_probability_zero_never_injects() {
+        let injector = ChaosInjector::new(config(0.0, ChaosMode::Drop));
+        for _ in 0..1000 {
+            assert_eq!(injector.roll(), None);
+        }
+    }
+
+    #[test]
+    fn test_probability_out_of_range_rejected_at_load() {
+        assert!(config(1.0, ChaosMode::Error).validate().is_ok());
+        assert!(config(1.5, ChaosMode::Error).validate().is_err());
+        assert!(config(-0.1, ChaosMode::Error).validate().is_err());
+        assert!(config(f64::NAN, ChaosMode::Error).validate().is_err());
+
+        let path =
+            std::env::temp_dir().join(format!("neutrino-chaos-{}.yaml", uuid::Uuid::new_v4()));
+        let yaml = "orchestrator:
+  app_module: app
+  http: {host: 0.0.0.0, port: 8080}
+  worker: {max_tasks_per_worker: 1, max_memory_mb: 1, startup_timeout_secs: 1}
+  tasks: {default_timeout_secs: 1}
+  chaos: {enabled: true, probability: 2.0}
+";
+        std::fs::write(&path, yaml).unwrap();
+        let loaded = crate::config::Config::from_file(&path);
+        std::fs::remove_file(&path).unwrap();
+        let err = loaded.unwrap_err().to_string();
+        assert!(err.contains("between 0.0 and 1.0"), "{}", err);
+    }
+
+    #[test]
+    fn test_disabled_never_injects() {
+        let mut cfg = config(1.0, ChaosMode::Error);
+        cfg.enabled = false;
+        let injector = ChaosInjector::new(cfg);
+        assert_eq!(injector.roll(), None);
+    }
+}
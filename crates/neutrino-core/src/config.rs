@@ -1,6 +1,12 @@
+use crate::header_limits::{
+    HeaderLimits, DEFAULT_MAX_RESPONSE_HEADERS, DEFAULT_MAX_RESPONSE_HEADER_BYTES,
+};
+use crate::protocol::{ResourceCapabilities, WireFormat};
+use crate::serde_convert::NonFiniteFloats;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::Path;
-use crate::protocol::ResourceCapabilities;
+use std::time::Duration;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
@@ -23,20 +29,382 @@ pub struct OrchestratorConfig {
     /// Worker pools with different resource configurations
     #[serde(default)]
     pub worker_pools: Vec<WorkerPoolConfig>,
+    /// How a task picks among the workers able to take it
+    #[serde(default)]
+    pub scheduling: SchedulingStrategy,
+    /// Optional failure injection for resilience testing (never enabled by default)
+    #[serde(default)]
+    pub chaos: Option<ChaosConfig>,
+    /// Startup check of total pool resources against host capacity
+    #[serde(default)]
+    pub overcommit: OvercommitConfig,
+    /// Refuse to start when a GPU pool's `gpu_devices` names a device that
+    /// nvidia-smi doesn't list (skipped on hosts without nvidia-smi)
+    #[serde(default = "default_validate_gpu_devices")]
+    pub validate_gpu_devices: bool,
+    /// JSON file metrics and worker state are saved to on graceful shutdown;
+    /// the previous run's file is loaded at startup (GET /admin/snapshot).
+    /// Tasks in flight aren't saved.
+    #[serde(default)]
+    pub snapshot_path: Option<String>,
+    /// OpenTelemetry export of spans and metrics (needs the `otel` build feature)
+    #[serde(default)]
+    pub otel: Option<OtelConfig>,
+    /// Handlers invoked on cron schedules
+    #[serde(default)]
+    pub schedules: Vec<ScheduleConfig>,
+}
+
+/// A handler call run on a cron schedule
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ScheduleConfig {
+    /// Names the schedule in logs and in its runs' task ids
+    pub name: String,
+    pub handler: String,
+    /// Five-field cron expression in UTC (e.g., "*/15 * * * *"), or @hourly,
+    /// @daily, @weekly, @monthly, @yearly
+    pub cron: String,
+    /// Args the handler is called with
+    #[serde(default)]
+    pub args: serde_json::Value,
+}
+
+/// Where to ship OpenTelemetry spans and metrics
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OtelConfig {
+    /// OTLP/HTTP collector base URL (e.g., "http://otel-collector:4318");
+    /// spans go to /v1/traces and metrics to /v1/metrics
+    pub endpoint: String,
+    /// service.name resource attribute reported with every span and metric
+    #[serde(default = "default_otel_service_name")]
+    pub service_name: String,
+}
+
+fn default_otel_service_name() -> String {
+    "neutrino".to_string()
+}
+
+fn default_validate_gpu_devices() -> bool {
+    true
+}
+
+/// What to do when worker pools claim more resources than the host has
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OvercommitConfig {
+    /// Action on over-commitment: "ignore", "warn", or "error" (refuse to start)
+    #[serde(default)]
+    pub action: OvercommitAction,
+    /// Allowed ratio of claimed to available resources (1.0 = no over-commitment)
+    #[serde(default = "default_max_overcommit_ratio")]
+    pub max_ratio: f64,
+}
+
+impl Default for OvercommitConfig {
+    fn default() -> Self {
+        Self {
+            action: OvercommitAction::default(),
+            max_ratio: default_max_overcommit_ratio(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum OvercommitAction {
+    Ignore,
+    #[default]
+    Warn,
+    Error,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum SchedulingStrategy {
+    /// Shortest worker queue, ties broken in round-robin order
+    #[default]
+    RoundRobin,
+    /// At random, weighted by how many more such tasks each worker has room for
+    WeightedRandom,
+    /// Least-loaded worker first, minimizing per-worker load
+    Spread,
+    /// Most-loaded worker that still fits first, keeping other workers free
+    /// for large (e.g. multi-GPU) tasks
+    Pack,
+}
+
+fn default_max_overcommit_ratio() -> f64 {
+    1.0
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HttpConfig {
     pub host: String,
     pub port: u16,
+    /// OpenAPI spec file, or an http(s) URL fetched at startup
     #[serde(default)]
     pub openapi_spec: Option<String>,
+    /// How specs given as URLs are fetched and cached
+    #[serde(default)]
+    pub spec_fetch: SpecFetchConfig,
+    /// Further OpenAPI specs whose routes are served alongside `openapi_spec`
+    #[serde(default)]
+    pub extra_specs: Vec<ExtraSpec>,
+    /// What to do when two specs declare the same method and path
+    #[serde(default)]
+    pub spec_conflicts: SpecConflictPolicy,
+    /// Most routes the specs may declare before startup fails (None = no limit)
+    #[serde(default)]
+    pub max_routes: Option<usize>,
+    /// Sidecar resource policy (YAML) merged over the spec's x-neutrino-resources
+    #[serde(default)]
+    pub resource_policy: Option<String>,
+    /// Validate path params, query params, and body against the OpenAPI schema
+    #[serde(default)]
+    pub validate_requests: bool,
+    /// With validate_requests, 400 on top-level body fields the schema doesn't
+    /// declare instead of passing them to the handler
+    #[serde(default)]
+    pub reject_unknown_fields: bool,
+    /// Strip result fields not declared in the operation's response schema
+    #[serde(default)]
+    pub enforce_response_schema: bool,
+    /// End-to-end health probe at /health/deep (disabled when unset)
+    #[serde(default)]
+    pub deep_health: Option<DeepHealthConfig>,
+    /// Seconds without an idle worker before /ready reports 503 (0 = immediately)
+    #[serde(default = "default_ready_grace_secs")]
+    pub ready_grace_secs: u64,
+    /// Async mode (`Prefer: respond-async` -> 202, poll /tasks/:id/result); disabled when unset
+    #[serde(default)]
+    pub async_results: Option<AsyncResultsConfig>,
+    /// Bind with SO_REUSEPORT so a new orchestrator can take over the port during an upgrade
+    #[serde(default)]
+    pub reuse_port: bool,
+    /// File the PID is written to once serving, for supervisors coordinating a handoff
+    #[serde(default)]
+    pub ready_file: Option<String>,
+    /// Seconds to let in-flight requests finish after a shutdown signal
+    #[serde(default = "default_shutdown_timeout_secs")]
+    pub shutdown_timeout_secs: u64,
+    /// Largest accepted request body; routes may override it with x-neutrino-max-body-bytes
+    #[serde(default = "default_max_body_bytes")]
+    pub max_body_bytes: usize,
+    /// Accept PATCH /admin/routes to add or update a task route at runtime
+    #[serde(default)]
+    pub allow_route_patching: bool,
+    /// Accept POST /admin/pools/{name}/scale to resize a worker pool at runtime
+    #[serde(default)]
+    pub allow_pool_scaling: bool,
+    /// Accept POST /admin/workers/restart to replace every worker in turn
+    #[serde(default)]
+    pub allow_worker_restart: bool,
+    /// Accept POST /batch, running several tasks from one request
+    #[serde(default)]
+    pub allow_batch: bool,
+    /// Most tasks in one POST /batch request
+    #[serde(default = "default_max_batch_tasks")]
+    pub max_batch_tasks: usize,
+    /// Accept POST /dag, running a graph of tasks from one request
+    #[serde(default)]
+    pub allow_dag: bool,
+    /// Most nodes in one POST /dag task graph
+    #[serde(default = "default_max_dag_nodes")]
+    pub max_dag_nodes: usize,
+    /// Most results kept for routes with x-neutrino-cache-ttl
+    #[serde(default = "default_max_cached_results")]
+    pub max_cached_results: usize,
+    /// Most bytes of results kept for routes with x-neutrino-cache-ttl;
+    /// larger results aren't cached
+    #[serde(default = "default_max_cached_result_bytes")]
+    pub max_cached_result_bytes: usize,
+    /// Concurrent connections allowed per client IP; more are closed on accept (None = unlimited)
+    #[serde(default)]
+    pub max_connections_per_ip: Option<usize>,
+    /// Fraction of the open-file limit past which new connections are refused
+    /// (with a warning) before accept starts failing; 0 (the default) disables
+    #[serde(default)]
+    pub fd_soft_limit_ratio: f64,
+    /// Stop accepting connections while too many requests are in flight
+    /// (disabled when unset)
+    #[serde(default)]
+    pub admission: Option<AdmissionConfig>,
+    /// Clients shown worker tracebacks in error responses; others get a generic
+    /// message (when unset, error messages are returned but tracebacks never are)
+    #[serde(default)]
+    pub error_details: Option<ErrorDetailsConfig>,
+    /// Tag each task with the tenant it was made for, for logs, per-tenant
+    /// metrics, and routing to tenant pools (disabled when unset)
+    #[serde(default)]
+    pub tenants: Option<TenantConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdmissionConfig {
+    /// Requests in flight at which accepting pauses
+    pub high_watermark: usize,
+    /// Requests in flight at which accepting resumes (below `high_watermark`)
+    pub low_watermark: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorDetailsConfig {
+    /// Source networks trusted with error details (e.g. "10.0.0.0/8")
+    #[serde(default)]
+    pub trusted_cidrs: Vec<ipnet::IpNet>,
+    /// Requests with `X-Neutrino-Debug: <token>` are trusted from anywhere
+    #[serde(default)]
+    pub debug_token: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TenantConfig {
+    /// Request header carrying the tenant ID, believed only from `trusted_cidrs`
+    #[serde(default = "default_tenant_header")]
+    pub header: String,
+    /// Networks of the proxies that authenticate clients and set the tenant
+    /// header (e.g. the gateway); the header is ignored from anywhere else
+    #[serde(default)]
+    pub trusted_cidrs: Vec<ipnet::IpNet>,
+    /// Claim of the bearer token (JWT) holding the tenant ID, preferred over the
+    /// header when present. Read only from tokens signed with `jwt_secret`.
+    #[serde(default)]
+    pub jwt_claim: Option<String>,
+    /// HS256 secret bearer tokens must be signed with for `jwt_claim` to be read
+    #[serde(default)]
+    pub jwt_secret: Option<String>,
+}
+
+fn default_tenant_header() -> String {
+    "x-tenant-id".to_string()
+}
+
+/// An OpenAPI spec loaded in addition to `openapi_spec`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtraSpec {
+    /// Spec file, or an http(s) URL fetched at startup
+    pub path: String,
+    /// Prefix for this spec's conflicting routes under `prefix-required`
+    #[serde(default)]
+    pub prefix: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum SpecConflictPolicy {
+    /// Refuse to start
+    #[default]
+    Error,
+    /// Keep the route from the spec listed first, dropping later ones
+    FirstWins,
+    /// Serve the later spec's route under that spec's `prefix`; refuse to
+    /// start if it has none
+    PrefixRequired,
+}
+
+fn default_shutdown_timeout_secs() -> u64 {
+    30
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpecFetchConfig {
+    /// Timeout for each fetch attempt
+    #[serde(default = "default_spec_fetch_timeout_secs")]
+    pub timeout_secs: u64,
+    /// Further attempts after a failed fetch, with backoff
+    #[serde(default = "default_spec_fetch_retries")]
+    pub retries: u32,
+    /// Where fetched specs are cached for when the URL can't be reached at
+    /// startup (default: neutrino-specs in the system temp directory)
+    #[serde(default)]
+    pub cache_dir: Option<String>,
+}
+
+impl Default for SpecFetchConfig {
+    fn default() -> Self {
+        Self {
+            timeout_secs: default_spec_fetch_timeout_secs(),
+            retries: default_spec_fetch_retries(),
+            cache_dir: None,
+        }
+    }
+}
+
+fn default_spec_fetch_timeout_secs() -> u64 {
+    10
+}
+
+fn default_spec_fetch_retries() -> u32 {
+    3
+}
+
+fn default_max_batch_tasks() -> usize {
+    100
+}
+
+fn default_max_dag_nodes() -> usize {
+    32
+}
+
+fn default_max_cached_results() -> usize {
+    10_000
+}
+
+fn default_max_cached_result_bytes() -> usize {
+    64 * 1024 * 1024
+}
+
+fn default_max_body_bytes() -> usize {
+    2 * 1024 * 1024
+}
+
+/// Storage for async task results
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AsyncResultsConfig {
+    /// SQLite database path; results survive restarts (None = in-memory only)
+    #[serde(default)]
+    pub store_path: Option<String>,
+    /// Seconds a result stays retrievable after it is written
+    #[serde(default = "default_result_ttl_secs")]
+    pub ttl_secs: u64,
+    /// SQLite file journaling accepted tasks until they finish; tasks a restart
+    /// interrupted are dispatched again at startup (None = not journaled)
+    #[serde(default)]
+    pub journal_path: Option<String>,
+}
+
+fn default_result_ttl_secs() -> u64 {
+    3600
+}
+
+fn default_ready_grace_secs() -> u64 {
+    10
+}
+
+/// Deep health check: dispatches a no-op handler to a worker and expects success
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeepHealthConfig {
+    /// Name of the no-op handler to dispatch (e.g., "health_noop")
+    pub handler: String,
+    /// Seconds to wait for the task result before reporting unhealthy
+    #[serde(default = "default_deep_health_timeout_secs")]
+    pub timeout_secs: u64,
+    /// Minimum seconds between probes; requests in between get the cached result
+    #[serde(default = "default_deep_health_min_interval_secs")]
+    pub min_interval_secs: u64,
+}
+
+fn default_deep_health_timeout_secs() -> u64 {
+    5
+}
+
+fn default_deep_health_min_interval_secs() -> u64 {
+    10
 }
 
 /// Configuration for a specific pool of workers
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WorkerPoolConfig {
-    /// Name of the worker pool (e.g., "gpu_workers", "cpu_workers")
+    /// Name of the worker pool (e.g., "gpu_workers", "cpu_workers"); no '-'
     pub name: String,
     /// Number of workers in this pool
     pub count: usize,
@@ -45,6 +413,87 @@ pub struct WorkerPoolConfig {
     /// GPU device indices to use (e.g., [0, 1] for GPUs 0 and 1)
     #[serde(default)]
     pub gpu_devices: Vec<usize>,
+    /// CPUs to pin each worker in this pool to (e.g., "0-7" or "0,2,4-6"), Linux only
+    #[serde(default)]
+    pub cpuset: Option<String>,
+    /// Number of workers to keep idle for bursts. Scheduling avoids dipping
+    /// below it, and autoscaling adds workers when idle count falls under it.
+    #[serde(default)]
+    pub min_idle: usize,
+    /// Upper bound for autoscaling this pool; autoscaling is disabled when unset
+    #[serde(default)]
+    pub max_count: Option<usize>,
+    /// Lower bound for autoscaling; idle workers are retired down to it while
+    /// the pool is underused (no scale-down when unset)
+    #[serde(default)]
+    pub min_count: Option<usize>,
+    /// Average worker utilization (share of the most-used resource allocated)
+    /// at or above which autoscaling adds a worker
+    #[serde(default = "default_scale_up_utilization")]
+    pub scale_up_utilization: f64,
+    /// Average worker utilization at or below which autoscaling retires one
+    #[serde(default = "default_scale_down_utilization")]
+    pub scale_down_utilization: f64,
+    /// Cap on the summed RSS of the pool's workers in MB. When exceeded, the
+    /// monitor recycles the pool's highest-memory idle worker even if no worker
+    /// crossed `max_memory_mb`.
+    #[serde(default)]
+    pub max_total_memory_mb: Option<u64>,
+    /// For GPU pools: the CPU pools whose tasks may overflow onto this pool's
+    /// workers when no CPU worker can take them ([] protects the pool from CPU
+    /// tasks; unset accepts any)
+    #[serde(default)]
+    pub allow_overflow_from: Option<Vec<String>>,
+    /// For CPU pools: the GPU pools this pool's tasks may overflow onto ([]
+    /// keeps them on CPU workers; unset allows any)
+    #[serde(default)]
+    pub allow_overflow_to: Option<Vec<String>>,
+    /// Handler run once on each new worker of the pool (e.g. to load a model
+    /// onto its GPU) before it is given tasks; a worker whose warm-up fails is
+    /// shut down. Warm-ups run without holding up dispatch to other workers.
+    #[serde(default)]
+    pub warmup_handler: Option<String>,
+    /// Seconds a warm-up may take before the worker is given up on
+    #[serde(default = "default_warmup_timeout_secs")]
+    pub warmup_timeout_secs: u64,
+}
+
+impl WorkerPoolConfig {
+    /// Check that the name can be read back from its workers' IDs
+    /// ("<pool>-<n>"), which routing, scaling, and recycling rely on
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.is_empty() || self.name.contains('-') {
+            return Err(format!(
+                "worker pool name '{}' must be non-empty and can't contain '-' (use '_')",
+                self.name
+            ));
+        }
+        Ok(())
+    }
+
+    /// Whether tasks of CPU pool `from` may overflow onto this pool, as both
+    /// pools' overflow lists allow
+    pub fn accepts_overflow_from(&self, from: &WorkerPoolConfig) -> bool {
+        self.allow_overflow_from
+            .as_ref()
+            .is_none_or(|pools| pools.contains(&from.name))
+            && from
+                .allow_overflow_to
+                .as_ref()
+                .is_none_or(|pools| pools.contains(&self.name))
+    }
+}
+
+fn default_warmup_timeout_secs() -> u64 {
+    300
+}
+
+fn default_scale_up_utilization() -> f64 {
+    0.8
+}
+
+fn default_scale_down_utilization() -> f64 {
+    0.2
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -56,11 +505,66 @@ pub struct WorkerConfig {
     /// Maximum worker lifetime in seconds before recycling
     #[serde(default = "default_max_lifetime_secs")]
     pub max_lifetime_secs: u64,
-    /// Interval in seconds for checking worker memory usage
+    /// Interval in seconds for checking worker memory usage, and for crashed workers to replace
     #[serde(default = "default_memory_check_interval_secs")]
     pub memory_check_interval_secs: u64,
     /// Worker startup timeout
     pub startup_timeout_secs: u64,
+    /// Permission bits for worker Unix sockets (e.g., "0600"), applied after bind
+    #[serde(
+        default,
+        serialize_with = "serialize_file_mode",
+        deserialize_with = "deserialize_file_mode"
+    )]
+    pub socket_mode: Option<u32>,
+    /// Directory for worker Unix sockets, created if missing (unset = /tmp)
+    #[serde(default)]
+    pub socket_dir: Option<String>,
+    /// Permission bits set on `socket_dir` (default "0700", so only the
+    /// orchestrator's user can reach the sockets; null leaves them as they are).
+    /// Not applied to an existing directory with another owner or other files.
+    #[serde(
+        default = "default_socket_dir_mode",
+        serialize_with = "serialize_file_mode",
+        deserialize_with = "deserialize_file_mode"
+    )]
+    pub socket_dir_mode: Option<u32>,
+    /// Seconds to wait for a worker to drain pending work before recycling (0 = don't drain)
+    #[serde(default = "default_drain_timeout_secs")]
+    pub drain_timeout_secs: u64,
+    /// Recycles run in the background while the monitor keeps sampling; at
+    /// most this many at once (each loads a replacement), others wait a check
+    #[serde(default = "default_max_concurrent_recycles")]
+    pub max_concurrent_recycles: usize,
+    /// Directory for per-worker stdout/stderr files (`<worker_id>.log`); output is
+    /// inherited by the orchestrator when unset
+    #[serde(default)]
+    pub worker_log_dir: Option<String>,
+    /// Worker socket payload encoding: "msgpack" (default) or "json" for readable
+    /// traffic while debugging; `NEUTRINO_WIRE_FORMAT` overrides it
+    #[serde(default)]
+    pub wire_format: WireFormat,
+    /// Heartbeats in a row an idle worker may miss before it is taken as hung
+    /// and replaced (0 = never). A worker still finishing a cancelled task
+    /// counts as busy and isn't sent heartbeats until it answers for it.
+    #[serde(default = "default_max_missed_heartbeats")]
+    pub max_missed_heartbeats: u32,
+}
+
+fn default_socket_dir_mode() -> Option<u32> {
+    Some(0o700)
+}
+
+fn default_max_missed_heartbeats() -> u32 {
+    3
+}
+
+fn default_drain_timeout_secs() -> u64 {
+    30
+}
+
+fn default_max_concurrent_recycles() -> usize {
+    1
 }
 
 fn default_max_lifetime_secs() -> u64 {
@@ -71,9 +575,218 @@ fn default_memory_check_interval_secs() -> u64 {
     30 // Check every 30 seconds
 }
 
+/// Serialize a file mode as an octal string ("0700"), as `deserialize_file_mode` reads it
+fn serialize_file_mode<S>(mode: &Option<u32>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    match mode {
+        Some(mode) => serializer.serialize_str(&format!("{:04o}", mode)),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Deserialize an octal file mode from either a string ("0600", "0o600") or an
+/// integer (YAML reads `0600` as decimal 600, so its digits are treated as octal)
+fn deserialize_file_mode<'de, D>(deserializer: D) -> Result<Option<u32>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum RawMode {
+        Int(u64),
+        Str(String),
+    }
+
+    let digits = match Option::<RawMode>::deserialize(deserializer)? {
+        None => return Ok(None),
+        Some(RawMode::Int(n)) => n.to_string(),
+        Some(RawMode::Str(s)) => s,
+    };
+    let trimmed = digits.trim().trim_start_matches("0o");
+
+    let mode = u32::from_str_radix(trimmed, 8)
+        .map_err(|_| serde::de::Error::custom(format!("invalid octal file mode '{}'", digits)))?;
+    if mode > 0o7777 {
+        return Err(serde::de::Error::custom(format!(
+            "file mode '{}' out of range",
+            digits
+        )));
+    }
+    Ok(Some(mode))
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TaskConfig {
+    /// Per-task timeout, including time spent queued (0 = no timeout, the default)
+    #[serde(default)]
     pub default_timeout_secs: u64,
+    /// What happens to the worker of a task that times out
+    #[serde(default)]
+    pub on_timeout: TimeoutAction,
+    /// Log a redacted, truncated preview of each task's args at debug level
+    #[serde(default)]
+    pub args_preview: Option<ArgsPreviewConfig>,
+    /// What to return when a successful result can't be converted to JSON
+    /// (ext types, invalid UTF-8, non-string map keys)
+    #[serde(default)]
+    pub unconvertible_results: UnconvertibleResultPolicy,
+    /// How NaN and ±Infinity in results are written as JSON
+    #[serde(default)]
+    pub non_finite_floats: NonFiniteFloats,
+    /// Seconds a queued task waits to gain one priority level, so low-priority
+    /// tasks aren't starved (0 = no aging)
+    #[serde(default = "default_priority_aging_secs")]
+    pub priority_aging_secs: u64,
+    /// What to do when no worker bound to the GPU requested with
+    /// X-Neutrino-Gpu-Affinity can take the task
+    #[serde(default)]
+    pub gpu_affinity_fallback: GpuAffinityFallback,
+    /// Record tasks that failed for good, listed at GET /admin/dead-letters
+    #[serde(default)]
+    pub dead_letters: Option<DeadLetterConfig>,
+    /// Let tasks no worker can take wait for capacity instead of failing with
+    /// 503 at once (disabled when unset)
+    #[serde(default)]
+    pub pending_queue: Option<PendingQueueConfig>,
+    /// Let a task no worker can take stop a running lower-priority task and
+    /// take its worker; the stopped task is queued again (disabled when unset).
+    /// Only workers that report they can cancel tasks are preempted.
+    #[serde(default)]
+    pub preemption: Option<PreemptionConfig>,
+    /// Turn tasks away with 429 and Retry-After while too many are queued or
+    /// in flight, instead of letting them pile up until they time out
+    /// (disabled when unset)
+    #[serde(default)]
+    pub backpressure: Option<BackpressureConfig>,
+}
+
+fn default_priority_aging_secs() -> u64 {
+    10
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum UnconvertibleResultPolicy {
+    /// Respond 502 with the conversion failure
+    #[default]
+    Error,
+    /// Return the result untouched to clients that accept msgpack
+    /// (`Accept: application/msgpack`); 502 for everyone else
+    Msgpack,
+    /// Return the msgpack-encoded result as base64: `{"$msgpack": "..."}`
+    Base64,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum TimeoutAction {
+    /// Send the worker `CancelTask` and keep using it once it has answered
+    /// for the task
+    Cancel,
+    /// Also take the worker out of service and replace it, for workers that
+    /// can't stop a task part-way (the bundled Python worker can't)
+    #[default]
+    Recycle,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum GpuAffinityFallback {
+    /// Respond 503
+    #[default]
+    Error,
+    /// Run the task on any worker that can take it
+    Any,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArgsPreviewConfig {
+    /// Longest preview logged, in characters
+    #[serde(default = "default_args_preview_max_chars")]
+    pub max_chars: usize,
+    /// Field names whose values are logged as "***" at any depth (case-insensitive)
+    #[serde(default = "crate::redact::default_redact_fields")]
+    pub redact_fields: Vec<String>,
+}
+
+fn default_args_preview_max_chars() -> usize {
+    512
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingQueueConfig {
+    /// Most tasks waiting for capacity; more fail with 503 at once
+    #[serde(default = "default_pending_max_depth")]
+    pub max_depth: usize,
+    /// Seconds a task waits for capacity before failing with 503
+    #[serde(default = "default_pending_max_wait_secs")]
+    pub max_wait_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackpressureConfig {
+    /// Most tasks waiting for a worker (unlimited when unset)
+    #[serde(default)]
+    pub max_queued: Option<usize>,
+    /// Most tasks accepted and not yet finished, queued or running (unlimited when unset)
+    #[serde(default)]
+    pub max_in_flight: Option<usize>,
+    /// Ceiling on the Retry-After given, used as is while nothing is finishing
+    #[serde(default = "default_backpressure_max_retry_after_secs")]
+    pub max_retry_after_secs: u64,
+}
+
+fn default_backpressure_max_retry_after_secs() -> u64 {
+    30
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreemptionConfig {
+    /// How far above the stopped task's priority the arriving task's must be
+    #[serde(default = "default_preemption_min_priority_gap")]
+    pub min_priority_gap: i32,
+    /// Only GPU tasks preempt; CPU tasks wait for capacity as usual
+    #[serde(default = "default_preemption_gpu_only")]
+    pub gpu_only: bool,
+}
+
+fn default_preemption_min_priority_gap() -> i32 {
+    1
+}
+
+fn default_preemption_gpu_only() -> bool {
+    true
+}
+
+fn default_pending_max_depth() -> usize {
+    100
+}
+
+fn default_pending_max_wait_secs() -> u64 {
+    10
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeadLetterConfig {
+    /// SQLite file to persist dead letters in (in memory if unset)
+    #[serde(default)]
+    pub store_path: Option<String>,
+    /// Most recent dead letters kept; older ones are dropped
+    #[serde(default = "default_dead_letter_max_entries")]
+    pub max_entries: usize,
+    /// Field names whose values are stored as "***" at any depth (case-insensitive)
+    #[serde(default = "crate::redact::default_redact_fields")]
+    pub redact_fields: Vec<String>,
+    /// Also keep each task's unredacted args, so it can be run again with
+    /// POST /admin/dead-letters/:task_id/redrive
+    #[serde(default)]
+    pub redrive: bool,
+}
+
+fn default_dead_letter_max_entries() -> usize {
+    1000
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -94,9 +807,69 @@ pub struct AsgiConfig {
     /// Request timeout in seconds for proxied requests
     #[serde(default = "default_asgi_timeout")]
     pub timeout_secs: u64,
+    /// Timeouts in seconds for paths under a prefix (e.g. "/reports"),
+    /// overriding `timeout_secs`; the longest matching prefix wins
+    #[serde(default)]
+    pub prefix_timeouts: HashMap<String, u64>,
     /// Uvicorn app command (e.g., "uvicorn_app:app" or "myapp:application")
     #[serde(default = "default_asgi_app_command")]
     pub app_command: String,
+    /// Maximum concurrent proxied requests; excess requests get 503 (None = unlimited)
+    #[serde(default)]
+    pub max_concurrent_proxies: Option<usize>,
+    /// Hosts ("host" for any port, or "host:port") the proxy may connect to,
+    /// including redirects it follows (empty = unrestricted)
+    #[serde(default)]
+    pub allowed_targets: Vec<String>,
+    /// Most response headers relayed from the ASGI app; the rest are dropped
+    #[serde(default = "default_max_response_headers")]
+    pub max_response_headers: usize,
+    /// Most bytes of response headers (names plus values) relayed from the ASGI app
+    #[serde(default = "default_max_response_header_bytes")]
+    pub max_response_header_bytes: usize,
+}
+
+impl AsgiConfig {
+    /// Bounds on the response headers relayed from the ASGI app
+    pub fn response_header_limits(&self) -> HeaderLimits {
+        HeaderLimits {
+            max_count: self.max_response_headers,
+            max_bytes: self.max_response_header_bytes,
+        }
+    }
+
+    /// Timeout for proxying a request to `path`
+    pub fn timeout_for(&self, path: &str) -> Duration {
+        let under = |prefix: &str| {
+            let prefix = prefix.trim_end_matches('/');
+            path.strip_prefix(prefix)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+        };
+        let secs = self
+            .prefix_timeouts
+            .iter()
+            .filter(|(prefix, _)| under(prefix))
+            .max_by_key(|(prefix, _)| prefix.trim_end_matches('/').len())
+            .map_or(self.timeout_secs, |(_, &secs)| secs);
+        Duration::from_secs(secs)
+    }
+
+    /// Whether the proxy may connect to `host:port` under `allowed_targets`
+    pub fn target_allowed(&self, host: &str, port: u16) -> bool {
+        if self.allowed_targets.is_empty() {
+            return true;
+        }
+
+        self.allowed_targets.iter().any(|entry| {
+            let entry = entry.trim();
+            // "[::1]" is a bare IPv6 host; otherwise the text after the last ':' is a port
+            let (allowed_host, allowed_port) = match entry.rsplit_once(':') {
+                Some((h, p)) if !entry.ends_with(']') => (h, p.parse::<u16>().ok()),
+                _ => (entry, None),
+            };
+            allowed_host.eq_ignore_ascii_case(host) && allowed_port.is_none_or(|p| p == port)
+        })
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -118,20 +891,83 @@ fn default_asgi_timeout() -> u64 {
     30
 }
 
+fn default_max_response_headers() -> usize {
+    DEFAULT_MAX_RESPONSE_HEADERS
+}
+
+fn default_max_response_header_bytes() -> usize {
+    DEFAULT_MAX_RESPONSE_HEADER_BYTES
+}
+
 fn default_asgi_app_command() -> String {
     "uvicorn_app:app".to_string()
 }
 
-impl Config {
-    /// Load configuration from YAML file
-    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn std::error::Error>> {
-        let content = std::fs::read_to_string(path)?;
-        let config: Config = serde_yaml::from_str(&content)?;
-        Ok(config)
+/// Failure injection configuration for pre-production resilience testing
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ChaosConfig {
+    /// Whether failures are currently injected (can be toggled via /admin/chaos)
+    #[serde(default)]
+    pub enabled: bool,
+    /// Probability (0.0 - 1.0) that a task request is affected
+    #[serde(default)]
+    pub probability: f64,
+    /// Kind of failure to inject
+    #[serde(default)]
+    pub mode: ChaosMode,
+    /// Delay applied in "delay" mode, in milliseconds
+    #[serde(default = "default_chaos_delay_ms")]
+    pub delay_ms: u64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ChaosMode {
+    /// Sleep for `delay_ms` before handling the request
+    Delay,
+    /// Respond with 503 Service Unavailable
+    #[default]
+    Error,
+    /// Abort the connection without a complete response
+    Drop,
+}
+
+fn default_chaos_delay_ms() -> u64 {
+    1000
+}
+
+impl AdmissionConfig {
+    /// Check that accepting pauses at some load and resumes below it
+    pub fn validate(&self) -> Result<(), String> {
+        if self.high_watermark == 0 {
+            return Err("admission high_watermark must be at least 1".to_string());
+        }
+        if self.low_watermark >= self.high_watermark {
+            return Err(format!(
+                "admission low_watermark ({}) must be below high_watermark ({})",
+                self.low_watermark, self.high_watermark
+            ));
+        }
+        Ok(())
     }
+}
 
+impl ChaosConfig {
+    /// Check that the probability is within 0.0..=1.0
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0.0..=1.0).contains(&self.probability) {
+            return Err(format!(
+                "chaos probability must be between 0.0 and 1.0, got {}",
+                self.probability
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Default for Config {
     /// Get default configuration
-    pub fn default() -> Self {
+    fn default() -> Self {
         Config {
             orchestrator: OrchestratorConfig {
                 worker_count: Some(4),
@@ -139,6 +975,35 @@ impl Config {
                     host: "0.0.0.0".to_string(),
                     port: 8080,
                     openapi_spec: Some("openapi.json".to_string()),
+                    spec_fetch: SpecFetchConfig::default(),
+                    extra_specs: Vec::new(),
+                    spec_conflicts: SpecConflictPolicy::default(),
+                    max_routes: None,
+                    resource_policy: None,
+                    validate_requests: false,
+                    reject_unknown_fields: false,
+                    enforce_response_schema: false,
+                    deep_health: None,
+                    ready_grace_secs: 10,
+                    async_results: None,
+                    reuse_port: false,
+                    ready_file: None,
+                    shutdown_timeout_secs: 30,
+                    max_body_bytes: default_max_body_bytes(),
+                    allow_route_patching: false,
+                    allow_pool_scaling: false,
+                    allow_worker_restart: false,
+                    allow_batch: false,
+                    max_batch_tasks: default_max_batch_tasks(),
+                    allow_dag: false,
+                    max_dag_nodes: default_max_dag_nodes(),
+                    max_cached_results: default_max_cached_results(),
+                    max_cached_result_bytes: default_max_cached_result_bytes(),
+                    max_connections_per_ip: None,
+                    fd_soft_limit_ratio: 0.0,
+                    admission: None,
+                    error_details: None,
+                    tenants: None,
                 },
                 worker: WorkerConfig {
                     max_tasks_per_worker: 1000,
@@ -146,16 +1011,59 @@ impl Config {
                     max_lifetime_secs: 3600,
                     memory_check_interval_secs: 30,
                     startup_timeout_secs: 10,
+                    socket_mode: None,
+                    socket_dir: None,
+                    socket_dir_mode: default_socket_dir_mode(),
+                    drain_timeout_secs: 30,
+                    max_concurrent_recycles: default_max_concurrent_recycles(),
+                    worker_log_dir: None,
+                    wire_format: WireFormat::default(),
+                    max_missed_heartbeats: default_max_missed_heartbeats(),
                 },
                 tasks: TaskConfig {
-                    default_timeout_secs: 30,
+                    default_timeout_secs: 0,
+                    on_timeout: TimeoutAction::default(),
+                    args_preview: None,
+                    unconvertible_results: UnconvertibleResultPolicy::default(),
+                    non_finite_floats: NonFiniteFloats::default(),
+                    priority_aging_secs: default_priority_aging_secs(),
+                    gpu_affinity_fallback: GpuAffinityFallback::default(),
+                    dead_letters: None,
+                    pending_queue: None,
+                    preemption: None,
+                    backpressure: None,
                 },
                 app_module: "app".to_string(),
                 asgi: None,
                 worker_pools: vec![],
+                scheduling: SchedulingStrategy::default(),
+                chaos: None,
+                overcommit: OvercommitConfig::default(),
+                validate_gpu_devices: default_validate_gpu_devices(),
+                snapshot_path: None,
+                otel: None,
+                schedules: Vec::new(),
             },
         }
     }
+}
+
+impl Config {
+    /// Load configuration from YAML file
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn std::error::Error>> {
+        let content = std::fs::read_to_string(path)?;
+        let config: Config = serde_yaml::from_str(&content)?;
+        if let Some(chaos) = &config.orchestrator.chaos {
+            chaos.validate()?;
+        }
+        if let Some(admission) = &config.orchestrator.http.admission {
+            admission.validate()?;
+        }
+        for pool in &config.orchestrator.worker_pools {
+            pool.validate()?;
+        }
+        Ok(config)
+    }
 
     /// Get the effective worker count (either from worker_pools or legacy worker_count)
     pub fn effective_worker_count(&self) -> usize {
@@ -177,6 +1085,17 @@ impl Config {
                 count: self.orchestrator.worker_count.unwrap_or(4),
                 resources: ResourceCapabilities::default(),
                 gpu_devices: vec![],
+                cpuset: None,
+                min_idle: 0,
+                max_count: None,
+                min_count: None,
+                scale_up_utilization: default_scale_up_utilization(),
+                scale_down_utilization: default_scale_down_utilization(),
+                max_total_memory_mb: None,
+                allow_overflow_from: None,
+                allow_overflow_to: None,
+                warmup_handler: None,
+                warmup_timeout_secs: default_warmup_timeout_secs(),
             }]
         }
     }
@@ -0,0 +1,432 @@
+//! Task graphs: a small DAG of handler calls whose args are wired to earlier
+//! calls' results. A string arg `"$<node>"` is replaced by that node's whole
+//! result and `"$<node>.<path>"` by part of it (dotted object fields and array
+//! indices, as in args templates); `"$$"` escapes a literal `$`. A node runs
+//! as soon as every node it references has succeeded, so independent stages
+//! run side by side. A node whose reference doesn't resolve (no such path in
+//! the result) fails without running. When a node fails, the nodes depending
+//! on it (directly or not) are skipped.
+
+use futures_util::stream::{FuturesUnordered, StreamExt};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+
+use crate::openapi::args_template::lookup;
+
+/// One handler call of a graph
+#[derive(Debug, Clone, Deserialize)]
+pub struct DagNode {
+    /// Name other nodes reference this one's result by
+    pub id: String,
+    pub handler: String,
+    #[serde(default)]
+    pub args: Value,
+}
+
+/// Why a graph can't run
+#[derive(Debug, Clone, PartialEq)]
+pub enum DagError {
+    Empty,
+    TooManyNodes { count: usize, max: usize },
+    InvalidId(String),
+    DuplicateId(String),
+    UnknownReference { node: String, reference: String },
+    Cycle(Vec<String>),
+    UnresolvedReference { node: String, reference: String },
+}
+
+impl fmt::Display for DagError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DagError::Empty => write!(f, "graph has no nodes"),
+            DagError::TooManyNodes { count, max } => {
+                write!(f, "graph has {} nodes, at most {} allowed", count, max)
+            }
+            DagError::InvalidId(id) => {
+                write!(
+                    f,
+                    "invalid node id '{}' (must be non-empty, without '.' or a leading '$')",
+                    id
+                )
+            }
+            DagError::DuplicateId(id) => write!(f, "node id '{}' is used more than once", id),
+            DagError::UnknownReference { node, reference } => {
+                write!(f, "node '{}' references unknown node '{}'", node, reference)
+            }
+            DagError::Cycle(nodes) => write!(f, "nodes {} depend on each other", nodes.join(", ")),
+            DagError::UnresolvedReference { node, reference } => write!(
+                f,
+                "node '{}' references '{}', which isn't in that result",
+                node, reference
+            ),
+        }
+    }
+}
+
+/// What became of a node
+#[derive(Debug)]
+pub enum NodeOutcome<E> {
+    Succeeded(Value),
+    Failed(E),
+    /// Not run because the named dependency failed or was skipped
+    Skipped(String),
+}
+
+/// A validated graph, ready to run
+#[derive(Debug)]
+pub struct Dag {
+    nodes: Vec<DagNode>,
+    /// Indices of the nodes each node references
+    dependencies: Vec<Vec<usize>>,
+    /// Indices of the nodes referencing each node
+    dependents: Vec<Vec<usize>>,
+}
+
+impl Dag {
+    /// Check `nodes` form an acyclic graph of at most `max_nodes`
+    pub fn new(nodes: Vec<DagNode>, max_nodes: usize) -> Result<Self, DagError> {
+        if nodes.is_empty() {
+            return Err(DagError::Empty);
+        }
+        if nodes.len() > max_nodes {
+            return Err(DagError::TooManyNodes {
+                count: nodes.len(),
+                max: max_nodes,
+            });
+        }
+
+        let mut index = HashMap::new();
+        for (i, node) in nodes.iter().enumerate() {
+            if node.id.is_empty() || node.id.contains('.') || node.id.starts_with('$') {
+                return Err(DagError::InvalidId(node.id.clone()));
+            }
+            if index.insert(node.id.as_str(), i).is_some() {
+                return Err(DagError::DuplicateId(node.id.clone()));
+            }
+        }
+
+        let mut dependencies = Vec::with_capacity(nodes.len());
+        let mut dependents = vec![Vec::new(); nodes.len()];
+        for (i, node) in nodes.iter().enumerate() {
+            let mut references = Vec::new();
+            collect_references(&node.args, &mut references);
+            let mut deps = Vec::new();
+            for reference in references {
+                let dep = *index
+                    .get(reference)
+                    .ok_or_else(|| DagError::UnknownReference {
+                        node: node.id.clone(),
+                        reference: reference.to_string(),
+                    })?;
+                if !deps.contains(&dep) {
+                    deps.push(dep);
+                    dependents[dep].push(i);
+                }
+            }
+            dependencies.push(deps);
+        }
+
+        // Kahn's algorithm: whatever can't be ordered is on a cycle
+        let mut pending: Vec<usize> = dependencies.iter().map(Vec::len).collect();
+        let mut ready: Vec<usize> = (0..nodes.len()).filter(|&i| pending[i] == 0).collect();
+        let mut ordered = 0;
+        while let Some(i) = ready.pop() {
+            ordered += 1;
+            for &dependent in &dependents[i] {
+                pending[dependent] -= 1;
+                if pending[dependent] == 0 {
+                    ready.push(dependent);
+                }
+            }
+        }
+        if ordered < nodes.len() {
+            let cycle = (0..nodes.len())
+                .filter(|&i| pending[i] > 0)
+                .map(|i| nodes[i].id.clone())
+                .collect();
+            return Err(DagError::Cycle(cycle));
+        }
+
+        Ok(Self {
+            nodes,
+            dependencies,
+            dependents,
+        })
+    }
+
+    pub fn nodes(&self) -> &[DagNode] {
+        &self.nodes
+    }
+
+    /// Run every node with `run(node, args)` as its dependencies complete,
+    /// returning each node's outcome in the order the nodes were given. A node
+    /// with an unresolved reference fails with `DagError::UnresolvedReference`.
+    pub async fn run<'a, E, F, Fut>(&'a self, run: F) -> Vec<NodeOutcome<E>>
+    where
+        E: From<DagError>,
+        F: Fn(&'a DagNode, Value) -> Fut,
+        Fut: Future<Output = Result<Value, E>>,
+    {
+        let mut outcomes: Vec<Option<NodeOutcome<E>>> = self.nodes.iter().map(|_| None).collect();
+        let mut pending: Vec<usize> = self.dependencies.iter().map(Vec::len).collect();
+        let mut running = FuturesUnordered::new();
+
+        let start = |i: usize, outcomes: &[Option<NodeOutcome<E>>]| {
+            let args = render(&self.nodes[i].args, &|id| {
+                let dep = self.nodes.iter().position(|node| node.id == id)?;
+                match &outcomes[dep] {
+                    Some(NodeOutcome::Succeeded(result)) => Some(result),
+                    _ => None,
+                }
+            })
+            .map_err(|reference| DagError::UnresolvedReference {
+                node: self.nodes[i].id.clone(),
+                reference,
+            })?;
+            let task = run(&self.nodes[i], args);
+            Ok(async move { (i, task.await) })
+        };
+
+        let mut ready: Vec<usize> = (0..self.nodes.len()).filter(|&i| pending[i] == 0).collect();
+        loop {
+            for i in ready.drain(..) {
+                match start(i, &outcomes) {
+                    Ok(task) => running.push(task),
+                    Err(e) => {
+                        outcomes[i] = Some(NodeOutcome::Failed(E::from(e)));
+                        self.skip_dependents(i, &mut outcomes);
+                    }
+                }
+            }
+            let Some((i, result)) = running.next().await else {
+                break;
+            };
+            match result {
+                Ok(value) => {
+                    outcomes[i] = Some(NodeOutcome::Succeeded(value));
+                    for &dependent in &self.dependents[i] {
+                        pending[dependent] -= 1;
+                        if pending[dependent] == 0 && outcomes[dependent].is_none() {
+                            ready.push(dependent);
+                        }
+                    }
+                }
+                Err(e) => {
+                    outcomes[i] = Some(NodeOutcome::Failed(e));
+                    self.skip_dependents(i, &mut outcomes);
+                }
+            }
+        }
+
+        outcomes
+            .into_iter()
+            .map(|outcome| outcome.expect("every node of an acyclic graph runs or is skipped"))
+            .collect()
+    }
+
+    /// Mark everything downstream of node `failed` as skipped
+    fn skip_dependents<E>(&self, failed: usize, outcomes: &mut [Option<NodeOutcome<E>>]) {
+        let mut stack = vec![failed];
+        while let Some(i) = stack.pop() {
+            for &dependent in &self.dependents[i] {
+                if outcomes[dependent].is_none() {
+                    outcomes[dependent] = Some(NodeOutcome::Skipped(self.nodes[i].id.clone()));
+                    stack.push(dependent);
+                }
+            }
+        }
+    }
+}
+
+/// The node id a string arg references, with the path into its result
+fn reference(s: &str) -> Option<(&str, Option<&str>)> {
+    let reference = s
+        .strip_prefix('$')
+        .filter(|r| !r.is_empty() && !r.starts_with('$'))?;
+    Some(match reference.split_once('.') {
+        Some((id, path)) => (id, Some(path)),
+        None => (reference, None),
+    })
+}
+
+fn collect_references<'a>(args: &'a Value, references: &mut Vec<&'a str>) {
+    match args {
+        Value::String(s) => references.extend(reference(s).map(|(id, _)| id)),
+        Value::Object(fields) => fields
+            .values()
+            .for_each(|field| collect_references(field, references)),
+        Value::Array(items) => items
+            .iter()
+            .for_each(|item| collect_references(item, references)),
+        _ => {}
+    }
+}
+
+/// `args` with references filled in from `results`, or the first reference
+/// that doesn't resolve
+fn render<'r>(args: &Value, results: &dyn Fn(&str) -> Option<&'r Value>) -> Result<Value, String> {
+    match args {
+        Value::String(s) => match (s.strip_prefix("$$"), reference(s)) {
+            (Some(escaped), _) => Ok(Value::String(format!("${}", escaped))),
+            (None, Some((id, path))) => {
+                let result = results(id);
+                let value = match path {
+                    Some(path) => result.and_then(|result| lookup(result, path)),
+                    None => result,
+                };
+                value.cloned().ok_or_else(|| s.clone())
+            }
+            (None, None) => Ok(args.clone()),
+        },
+        Value::Object(fields) => fields
+            .iter()
+            .map(|(name, field)| Ok((name.clone(), render(field, results)?)))
+            .collect::<Result<Map<_, _>, _>>()
+            .map(Value::Object),
+        Value::Array(items) => items
+            .iter()
+            .map(|item| render(item, results))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Value::Array),
+        _ => Ok(args.clone()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::time::{Duration, Instant};
+
+    fn nodes(value: Value) -> Vec<DagNode> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    /// A node's failure, as a message
+    #[derive(Debug, PartialEq)]
+    struct Failure(String);
+
+    impl From<DagError> for Failure {
+        fn from(e: DagError) -> Self {
+            Failure(e.to_string())
+        }
+    }
+
+    #[test]
+    fn test_invalid_graphs_are_rejected() {
+        let unknown = nodes(json!([{"id": "a", "handler": "h", "args": {"x": "$b.y"}}]));
+        assert_eq!(
+            Dag::new(unknown, 10).unwrap_err(),
+            DagError::UnknownReference {
+                node: "a".into(),
+                reference: "b".into()
+            }
+        );
+
+        let cycle = nodes(json!([
+            {"id": "a", "handler": "h", "args": {"x": "$c"}},
+            {"id": "b", "handler": "h", "args": {"x": "$a"}},
+            {"id": "c", "handler": "h", "args": ["$b"]},
+            {"id": "d", "handler": "h", "args": {"x": "$$a"}},
+        ]));
+        assert_eq!(
+            Dag::new(cycle, 10).unwrap_err(),
+            DagError::Cycle(vec!["a".into(), "b".into(), "c".into()])
+        );
+
+        let duplicate = nodes(json!([{"id": "a", "handler": "h"}, {"id": "a", "handler": "h"}]));
+        assert_eq!(
+            Dag::new(duplicate, 10).unwrap_err(),
+            DagError::DuplicateId("a".into())
+        );
+        let dotted = nodes(json!([{"id": "a.b", "handler": "h"}]));
+        assert_eq!(
+            Dag::new(dotted, 10).unwrap_err(),
+            DagError::InvalidId("a.b".into())
+        );
+        let large = nodes(json!([{"id": "a", "handler": "h"}, {"id": "b", "handler": "h"}]));
+        assert_eq!(
+            Dag::new(large, 1).unwrap_err(),
+            DagError::TooManyNodes { count: 2, max: 1 }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stages_run_as_dependencies_complete() {
+        // fetch_a and fetch_b run together; merge waits for both; fail's
+        // failure skips report and everything after it
+        let dag = Dag::new(
+            nodes(json!([
+                {"id": "merge", "handler": "merge", "args": {"a": "$fetch_a.text", "b": "$fetch_b", "cost": "$$5"}},
+                {"id": "fetch_a", "handler": "fetch", "args": {"text": "alpha"}},
+                {"id": "fetch_b", "handler": "fetch", "args": {"text": "beta"}},
+                {"id": "fail", "handler": "fail", "args": {"input": "$merge"}},
+                {"id": "report", "handler": "report", "args": {"input": "$fail"}},
+                {"id": "after_report", "handler": "report", "args": ["$report", "$merge.a"]},
+            ])),
+            10,
+        )
+        .unwrap();
+
+        let start = Instant::now();
+        let outcomes = dag
+            .run(|node, args| async move {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                match node.handler.as_str() {
+                    "fail" => Err(Failure(format!("{} failed", node.id))),
+                    _ => Ok(args),
+                }
+            })
+            .await;
+        // fetches, then merge, then fail: three stages
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(300) && elapsed < Duration::from_millis(400));
+
+        match &outcomes[0] {
+            NodeOutcome::Succeeded(result) => {
+                assert_eq!(
+                    result,
+                    &json!({"a": "alpha", "b": {"text": "beta"}, "cost": "$5"})
+                )
+            }
+            other => panic!("merge: {:?}", other),
+        }
+        assert!(matches!(&outcomes[3], NodeOutcome::Failed(Failure(e)) if e == "fail failed"));
+        assert!(matches!(&outcomes[4], NodeOutcome::Skipped(dep) if dep == "fail"));
+        assert!(matches!(&outcomes[5], NodeOutcome::Skipped(dep) if dep == "report"));
+    }
+
+    #[tokio::test]
+    async fn test_unresolved_reference_fails_node_and_skips_dependents() {
+        let dag = Dag::new(
+            nodes(json!([
+                {"id": "fetch", "handler": "fetch", "args": {"text": "alpha"}},
+                {"id": "use", "handler": "use", "args": {"text": "$fetch.missing"}},
+                {"id": "after", "handler": "use", "args": ["$use"]},
+            ])),
+            10,
+        )
+        .unwrap();
+
+        let ran = std::sync::Mutex::new(Vec::new());
+        let outcomes = dag
+            .run(|node, args| {
+                ran.lock().unwrap().push(node.id.clone());
+                async move { Ok::<_, Failure>(args) }
+            })
+            .await;
+
+        assert_eq!(*ran.lock().unwrap(), vec!["fetch"]);
+        assert!(matches!(&outcomes[0], NodeOutcome::Succeeded(_)));
+        match &outcomes[1] {
+            NodeOutcome::Failed(Failure(e)) => {
+                assert!(e.contains("'$fetch.missing'"), "{}", e)
+            }
+            other => panic!("use: {:?}", other),
+        }
+        assert!(matches!(&outcomes[2], NodeOutcome::Skipped(dep) if dep == "use"));
+    }
+}
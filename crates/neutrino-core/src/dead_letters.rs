@@ -0,0 +1,369 @@
+//! Dead-letter records of tasks that failed for good (handler errors, worker
+//! failures, timeouts), kept for diagnosing recurring failures after the
+//! client has moved on. Listed at `GET /admin/dead-letters`, fetched one at a
+//! time at `GET /admin/dead-letters/:task_id`, and run again with
+//! `POST /admin/dead-letters/:task_id/redrive` when their args were kept.
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tracing::{error, info};
+
+use crate::config::DeadLetterConfig;
+
+/// Why a task failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureKind {
+    /// The handler raised
+    HandlerError,
+    /// The worker crashed or answered with something unusable
+    WorkerError,
+    /// The task ran past its timeout
+    Timeout,
+}
+
+impl FailureKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            FailureKind::HandlerError => "handler_error",
+            FailureKind::WorkerError => "worker_error",
+            FailureKind::Timeout => "timeout",
+        }
+    }
+
+    fn parse(s: &str) -> Self {
+        match s {
+            "handler_error" => FailureKind::HandlerError,
+            "timeout" => FailureKind::Timeout,
+            _ => FailureKind::WorkerError,
+        }
+    }
+}
+
+/// A permanently failed task
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeadLetter {
+    pub task_id: String,
+    pub handler: String,
+    /// Unix time (seconds) the task failed
+    pub failed_at: u64,
+    pub kind: FailureKind,
+    pub error: String,
+    /// The task's args as JSON, secret fields redacted and cut to a bounded length
+    pub args: String,
+    /// Worker the task last ran on, if it reached one
+    pub worker_id: Option<String>,
+    /// Times the task was dispatched before it was given up on, counting redrives
+    pub attempts: u32,
+    /// The task's unredacted args as msgpack, kept only with `redrive` enabled
+    #[serde(skip)]
+    pub payload: Option<Vec<u8>>,
+}
+
+/// Pluggable storage for dead letters, keeping the most recent ones
+pub trait DeadLetterStore: Send + Sync {
+    /// Store a dead letter, replacing any earlier one for the same task
+    fn record(&self, letter: DeadLetter) -> Result<(), String>;
+    /// Most recent first, optionally only one handler's
+    fn recent(&self, handler: Option<&str>, limit: usize) -> Result<Vec<DeadLetter>, String>;
+    fn get(&self, task_id: &str) -> Result<Option<DeadLetter>, String>;
+    /// Drop a task's dead letter; whether there was one
+    fn remove(&self, task_id: &str) -> Result<bool, String>;
+}
+
+/// Run store calls from async code. Stores may do blocking disk I/O, so the
+/// calls run on the blocking thread pool.
+pub async fn blocking<T, F>(store: &Arc<dyn DeadLetterStore>, f: F) -> Result<T, String>
+where
+    T: Send + 'static,
+    F: FnOnce(&dyn DeadLetterStore) -> Result<T, String> + Send + 'static,
+{
+    let store = Arc::clone(store);
+    tokio::task::spawn_blocking(move || f(store.as_ref()))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Open the configured store, falling back to memory if SQLite can't be opened
+pub fn open_store(config: &DeadLetterConfig) -> Arc<dyn DeadLetterStore> {
+    match &config.store_path {
+        Some(path) => match SqliteDeadLetterStore::open(path, config.max_entries) {
+            Ok(store) => {
+                info!("Dead letters persisted to {}", path);
+                Arc::new(store)
+            }
+            Err(e) => {
+                error!(
+                    "Failed to open dead-letter store at {}: {}. Using in-memory store",
+                    path, e
+                );
+                Arc::new(MemoryDeadLetterStore::new(config.max_entries))
+            }
+        },
+        None => Arc::new(MemoryDeadLetterStore::new(config.max_entries)),
+    }
+}
+
+/// In-memory store; dead letters are lost on restart
+pub struct MemoryDeadLetterStore {
+    max_entries: usize,
+    entries: Mutex<VecDeque<DeadLetter>>,
+}
+
+impl MemoryDeadLetterStore {
+    pub fn new(max_entries: usize) -> Self {
+        Self {
+            max_entries,
+            entries: Mutex::new(VecDeque::new()),
+        }
+    }
+}
+
+impl DeadLetterStore for MemoryDeadLetterStore {
+    fn record(&self, letter: DeadLetter) -> Result<(), String> {
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|entry| entry.task_id != letter.task_id);
+        entries.push_front(letter);
+        entries.truncate(self.max_entries);
+        Ok(())
+    }
+
+    fn recent(&self, handler: Option<&str>, limit: usize) -> Result<Vec<DeadLetter>, String> {
+        let entries = self.entries.lock().unwrap();
+        Ok(entries
+            .iter()
+            .filter(|letter| handler.is_none_or(|handler| letter.handler == handler))
+            .take(limit)
+            .cloned()
+            .collect())
+    }
+
+    fn get(&self, task_id: &str) -> Result<Option<DeadLetter>, String> {
+        let entries = self.entries.lock().unwrap();
+        Ok(entries
+            .iter()
+            .find(|letter| letter.task_id == task_id)
+            .cloned())
+    }
+
+    fn remove(&self, task_id: &str) -> Result<bool, String> {
+        let mut entries = self.entries.lock().unwrap();
+        let before = entries.len();
+        entries.retain(|letter| letter.task_id != task_id);
+        Ok(entries.len() < before)
+    }
+}
+
+/// SQLite-backed store so dead letters survive orchestrator restarts
+pub struct SqliteDeadLetterStore {
+    max_entries: usize,
+    conn: Mutex<Connection>,
+}
+
+impl SqliteDeadLetterStore {
+    pub fn open<P: AsRef<Path>>(path: P, max_entries: usize) -> rusqlite::Result<Self> {
+        if let Some(parent) = path.as_ref().parent() {
+            std::fs::create_dir_all(parent).ok();
+        }
+
+        let conn = Connection::open(path.as_ref())?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS dead_letters (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                task_id TEXT NOT NULL,
+                handler TEXT NOT NULL,
+                failed_at INTEGER NOT NULL,
+                kind TEXT NOT NULL,
+                error TEXT NOT NULL,
+                args TEXT NOT NULL,
+                worker_id TEXT,
+                attempts INTEGER NOT NULL,
+                payload BLOB
+            )",
+            [],
+        )?;
+        // Stores created before redrive lack the payload column; the error
+        // when it already exists is expected
+        let _ = conn.execute("ALTER TABLE dead_letters ADD COLUMN payload BLOB", []);
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_dead_letters_task_id ON dead_letters(task_id)",
+            [],
+        )?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_dead_letters_handler ON dead_letters(handler)",
+            [],
+        )?;
+
+        Ok(Self {
+            max_entries,
+            conn: Mutex::new(conn),
+        })
+    }
+}
+
+impl DeadLetterStore for SqliteDeadLetterStore {
+    fn record(&self, letter: DeadLetter) -> Result<(), String> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "DELETE FROM dead_letters WHERE task_id = ?1",
+            params![letter.task_id],
+        )
+        .map_err(|e| e.to_string())?;
+        conn.execute(
+            "INSERT INTO dead_letters (task_id, handler, failed_at, kind, error, args, worker_id, attempts, payload)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                letter.task_id,
+                letter.handler,
+                letter.failed_at,
+                letter.kind.as_str(),
+                letter.error,
+                letter.args,
+                letter.worker_id,
+                letter.attempts,
+                letter.payload,
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+        conn.execute(
+            "DELETE FROM dead_letters WHERE id NOT IN (SELECT id FROM dead_letters ORDER BY id DESC LIMIT ?1)",
+            params![self.max_entries as i64],
+        )
+        .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    fn recent(&self, handler: Option<&str>, limit: usize) -> Result<Vec<DeadLetter>, String> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT {} FROM dead_letters WHERE ?1 IS NULL OR handler = ?1 ORDER BY id DESC LIMIT ?2",
+                COLUMNS
+            ))
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![handler, limit as i64], letter_from_row)
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+    }
+
+    fn get(&self, task_id: &str) -> Result<Option<DeadLetter>, String> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            &format!("SELECT {} FROM dead_letters WHERE task_id = ?1", COLUMNS),
+            params![task_id],
+            letter_from_row,
+        )
+        .optional()
+        .map_err(|e| e.to_string())
+    }
+
+    fn remove(&self, task_id: &str) -> Result<bool, String> {
+        let conn = self.conn.lock().unwrap();
+        let removed = conn
+            .execute(
+                "DELETE FROM dead_letters WHERE task_id = ?1",
+                params![task_id],
+            )
+            .map_err(|e| e.to_string())?;
+        Ok(removed > 0)
+    }
+}
+
+/// Columns read back into a `DeadLetter`, in `letter_from_row` order
+const COLUMNS: &str =
+    "task_id, handler, failed_at, kind, error, args, worker_id, attempts, payload";
+
+fn letter_from_row(row: &rusqlite::Row) -> rusqlite::Result<DeadLetter> {
+    Ok(DeadLetter {
+        task_id: row.get(0)?,
+        handler: row.get(1)?,
+        failed_at: row.get(2)?,
+        kind: FailureKind::parse(&row.get::<_, String>(3)?),
+        error: row.get(4)?,
+        args: row.get(5)?,
+        worker_id: row.get(6)?,
+        attempts: row.get(7)?,
+        payload: row.get(8)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn letter(task_id: &str, handler: &str) -> DeadLetter {
+        DeadLetter {
+            task_id: task_id.to_string(),
+            handler: handler.to_string(),
+            failed_at: 1_700_000_000,
+            kind: FailureKind::Timeout,
+            error: "Task timed out after 30s".to_string(),
+            args: r#"{"password":"***"}"#.to_string(),
+            worker_id: Some("default-0".to_string()),
+            attempts: 1,
+            payload: None,
+        }
+    }
+
+    #[test]
+    fn test_stores_keep_most_recent_entries() {
+        let path =
+            std::env::temp_dir().join(format!("neutrino-dead-letters-{}.db", uuid::Uuid::new_v4()));
+        let sqlite = SqliteDeadLetterStore::open(&path, 3).unwrap();
+        let memory = MemoryDeadLetterStore::new(3);
+        let stores: [&dyn DeadLetterStore; 2] = [&sqlite, &memory];
+
+        for store in stores {
+            for (task_id, handler) in [
+                ("t1", "embed"),
+                ("t2", "chat"),
+                ("t3", "embed"),
+                ("t4", "embed"),
+            ] {
+                store.record(letter(task_id, handler)).unwrap();
+            }
+            let task_ids = |letters: Vec<DeadLetter>| {
+                letters.into_iter().map(|l| l.task_id).collect::<Vec<_>>()
+            };
+            assert_eq!(
+                task_ids(store.recent(None, 10).unwrap()),
+                ["t4", "t3", "t2"]
+            );
+            assert_eq!(
+                task_ids(store.recent(Some("embed"), 10).unwrap()),
+                ["t4", "t3"]
+            );
+            assert_eq!(task_ids(store.recent(None, 1).unwrap()), ["t4"]);
+
+            // A task failing again replaces its earlier letter
+            let again = DeadLetter {
+                attempts: 2,
+                payload: Some(vec![0x90]),
+                ..letter("t3", "embed")
+            };
+            store.record(again.clone()).unwrap();
+            assert_eq!(
+                task_ids(store.recent(None, 10).unwrap()),
+                ["t3", "t4", "t2"]
+            );
+            assert_eq!(store.get("t3").unwrap(), Some(again));
+            assert!(store.remove("t3").unwrap());
+            assert!(!store.remove("t3").unwrap());
+            assert_eq!(store.get("t3").unwrap(), None);
+        }
+
+        // Persisted across a reopen
+        drop(sqlite);
+        let reopened = SqliteDeadLetterStore::open(&path, 3).unwrap();
+        assert_eq!(
+            reopened.recent(None, 1).unwrap(),
+            vec![letter("t4", "embed")]
+        );
+
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -0,0 +1,136 @@
+//! Open file descriptor usage. Every worker holds a socket and every client a
+//! connection, so a busy orchestrator can reach its RLIMIT_NOFILE, after which
+//! `accept` fails with EMFILE. Usage is reported in /status and /metrics, and
+//! past an opt-in soft limit new connections are refused before the hard one is hit.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// How long an fd count is reused before /proc is read again, so accepting a
+/// connection doesn't list every open descriptor
+const SAMPLE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// File descriptors open in this process (None where /proc is unavailable)
+pub fn open_fds() -> Option<usize> {
+    let entries = std::fs::read_dir("/proc/self/fd").ok()?;
+    // The listing includes the descriptor read_dir itself holds open
+    Some(entries.count().saturating_sub(1))
+}
+
+/// This process's RLIMIT_NOFILE soft limit (None when unlimited or unknown)
+pub fn fd_limit() -> Option<usize> {
+    #[cfg(unix)]
+    {
+        let mut limit = libc::rlimit {
+            rlim_cur: 0,
+            rlim_max: 0,
+        };
+        if unsafe { libc::getrlimit(libc::RLIMIT_NOFILE, &mut limit) } == 0
+            && limit.rlim_cur != libc::RLIM_INFINITY
+        {
+            return usize::try_from(limit.rlim_cur).ok();
+        }
+    }
+    None
+}
+
+/// Soft limit at `ratio` of the fd limit (None when disabled or the limit is unknown)
+pub fn soft_limit(ratio: f64) -> Option<usize> {
+    if ratio <= 0.0 {
+        return None;
+    }
+    fd_limit().map(|limit| (limit as f64 * ratio) as usize)
+}
+
+/// Checks fd usage against a soft limit, warning once each time it is crossed.
+/// Usage is sampled at most every `SAMPLE_INTERVAL`.
+pub struct FdMonitor {
+    soft_limit: usize,
+    warned: AtomicBool,
+    /// When usage was last sampled, and whether it was over the limit
+    sampled: Mutex<Option<(Instant, bool)>>,
+}
+
+impl FdMonitor {
+    pub fn new(soft_limit: usize) -> Self {
+        Self {
+            soft_limit,
+            warned: AtomicBool::new(false),
+            sampled: Mutex::new(None),
+        }
+    }
+
+    pub fn soft_limit(&self) -> usize {
+        self.soft_limit
+    }
+
+    /// Whether open fds were at or past the soft limit when last sampled
+    pub fn over_limit(&self) -> bool {
+        let mut sampled = self.sampled.lock().unwrap();
+        match *sampled {
+            Some((at, over)) if at.elapsed() < SAMPLE_INTERVAL => over,
+            _ => {
+                let over = self.sample();
+                *sampled = Some((Instant::now(), over));
+                over
+            }
+        }
+    }
+
+    /// Count open fds against the soft limit, warning on crossing it
+    fn sample(&self) -> bool {
+        let Some(open) = open_fds() else {
+            return false;
+        };
+        let over = open >= self.soft_limit;
+        if over != self.warned.swap(over, Ordering::Relaxed) {
+            if over {
+                warn!(
+                    open_fds = open,
+                    soft_limit = self.soft_limit,
+                    "Open file descriptors at {}/{}: refusing new connections",
+                    open,
+                    self.soft_limit
+                );
+            } else {
+                info!(
+                    open_fds = open,
+                    "Open file descriptors back under the soft limit; accepting connections"
+                );
+            }
+        }
+        over
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::CapturedEvents;
+
+    #[test]
+    fn test_fd_count_reported_and_warning_past_soft_limit() {
+        let open = open_fds().expect("fd count unavailable");
+        assert!(open >= 3, "stdio alone should be open, got {}", open);
+        let rendered = crate::metrics::Metrics::new().render();
+        assert!(rendered.contains("\nneutrino_open_fds "), "{}", rendered);
+
+        let events = CapturedEvents::default();
+        let _guard = events.install();
+
+        let roomy = FdMonitor::new(open + 1000);
+        assert!(!roomy.over_limit());
+        assert!(events.with_field("soft_limit").is_empty());
+
+        // Lowered below current usage: refuses, and warns only on crossing
+        let tight = FdMonitor::new(1);
+        assert!(tight.over_limit());
+        assert!(tight.over_limit());
+        let warnings = events.with_field("soft_limit");
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0]["soft_limit"], "1");
+        assert!(warnings[0]["open_fds"].parse::<usize>().unwrap() >= 1);
+    }
+}
@@ -0,0 +1,49 @@
+//! Bounds on the response headers relayed by the proxies (the ASGI fallback
+//! and the gateway), so a misbehaving backend can't make them buffer and
+//! forward thousands of headers or a megabyte-sized one.
+
+use axum::http::HeaderMap;
+
+/// Response headers relayed by default
+pub const DEFAULT_MAX_RESPONSE_HEADERS: usize = 100;
+
+/// Combined size of relayed response headers (names plus values) by default
+pub const DEFAULT_MAX_RESPONSE_HEADER_BYTES: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HeaderLimits {
+    /// Most headers relayed
+    pub max_count: usize,
+    /// Most bytes of header names and values relayed, combined
+    pub max_bytes: usize,
+}
+
+impl Default for HeaderLimits {
+    fn default() -> Self {
+        Self {
+            max_count: DEFAULT_MAX_RESPONSE_HEADERS,
+            max_bytes: DEFAULT_MAX_RESPONSE_HEADER_BYTES,
+        }
+    }
+}
+
+impl HeaderLimits {
+    /// The headers that fit within the limits, in order, and how many were
+    /// dropped. A header too large for the remaining byte budget is skipped,
+    /// so smaller ones after it (e.g. content-type) still get through.
+    pub fn apply(&self, headers: &HeaderMap) -> (HeaderMap, usize) {
+        let mut kept = HeaderMap::new();
+        let (mut count, mut bytes, mut dropped) = (0, 0, 0);
+        for (name, value) in headers {
+            let size = name.as_str().len() + value.len();
+            if count < self.max_count && bytes + size <= self.max_bytes {
+                kept.append(name, value.clone());
+                count += 1;
+                bytes += size;
+            } else {
+                dropped += 1;
+            }
+        }
+        (kept, dropped)
+    }
+}
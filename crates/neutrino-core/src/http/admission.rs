@@ -0,0 +1,265 @@
+//! Adaptive admission under overload. Once the requests in flight reach the
+//! high watermark, the listener stops accepting connections until they drop
+//! back to the low watermark, so new clients wait in the kernel's backlog (or
+//! are sent elsewhere by the load balancer) instead of being accepted only to
+//! get a 503. Responses sent while over the high watermark carry
+//! `Connection: close`, so kept-alive clients reconnect rather than keep
+//! queueing requests here.
+
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::sync::Notify;
+use tracing::{info, warn};
+
+/// Requests in flight and the watermarks accepting pauses and resumes at
+pub struct AdmissionControl {
+    high_watermark: usize,
+    low_watermark: usize,
+    in_flight: AtomicUsize,
+    /// Woken when the requests in flight drop to the low watermark
+    drained: Notify,
+    /// Woken when the requests in flight reach the high watermark
+    saturated: Notify,
+}
+
+impl AdmissionControl {
+    /// The low watermark should be below the high one (checked at config load)
+    pub fn new(high_watermark: usize, low_watermark: usize) -> Arc<Self> {
+        Arc::new(Self {
+            high_watermark,
+            low_watermark,
+            in_flight: AtomicUsize::new(0),
+            drained: Notify::new(),
+            saturated: Notify::new(),
+        })
+    }
+
+    /// Requests currently being handled
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::SeqCst)
+    }
+
+    fn saturated(&self) -> bool {
+        self.in_flight() >= self.high_watermark
+    }
+
+    /// Resolves once a connection may be accepted: at once below the high
+    /// watermark, otherwise when the requests in flight are down to the low one
+    pub async fn wait_for_capacity(&self) {
+        if !self.saturated() {
+            return;
+        }
+        warn!(
+            "{} requests in flight, pausing accepts until {} remain",
+            self.in_flight(),
+            self.low_watermark
+        );
+        loop {
+            let drained = self.drained.notified();
+            tokio::pin!(drained);
+            drained.as_mut().enable();
+            // Never resumes while still saturated, even with the watermarks equal
+            if self.in_flight() <= self.low_watermark && !self.saturated() {
+                break;
+            }
+            drained.await;
+        }
+        info!(
+            "{} requests in flight, accepting connections again",
+            self.in_flight()
+        );
+    }
+
+    /// Resolves once the requests in flight reach the high watermark, so a
+    /// pending accept can be abandoned
+    pub async fn until_saturated(&self) {
+        loop {
+            let saturated = self.saturated.notified();
+            tokio::pin!(saturated);
+            saturated.as_mut().enable();
+            if self.saturated() {
+                return;
+            }
+            saturated.await;
+        }
+    }
+
+    /// Count a request in flight until the returned guard is dropped
+    pub fn enter(self: &Arc<Self>) -> InFlight {
+        if self.in_flight.fetch_add(1, Ordering::SeqCst) + 1 >= self.high_watermark {
+            self.saturated.notify_waiters();
+        }
+        InFlight {
+            admission: Arc::clone(self),
+        }
+    }
+}
+
+/// A request being handled, counted until dropped
+pub struct InFlight {
+    admission: Arc<AdmissionControl>,
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        let remaining = self.admission.in_flight.fetch_sub(1, Ordering::SeqCst) - 1;
+        if remaining <= self.admission.low_watermark {
+            self.admission.drained.notify_waiters();
+        }
+    }
+}
+
+/// Middleware counting requests in flight, closing kept-alive connections
+/// whose response is sent while saturated. Handlers find the admission
+/// control among the request's extensions, to count work beyond the request.
+pub async fn track(
+    State(admission): State<Arc<AdmissionControl>>,
+    mut req: Request,
+    next: Next,
+) -> Response {
+    let in_flight = admission.enter();
+    req.extensions_mut().insert(Arc::clone(&admission));
+    let mut response = next.run(req).await;
+    if admission.saturated() {
+        response
+            .headers_mut()
+            .insert(header::CONNECTION, HeaderValue::from_static("close"));
+    }
+    drop(in_flight);
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::handoff;
+    use axum::{routing::get, Router};
+    use std::time::Duration;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpStream;
+    use tokio::sync::Semaphore;
+
+    /// Send a request and wait for its status line, if one arrives in `wait`
+    async fn request(stream: &mut TcpStream, path: &str, wait: Duration) -> Option<String> {
+        let request = format!("GET {} HTTP/1.1\r\nhost: test\r\n\r\n", path);
+        stream.write_all(request.as_bytes()).await.ok()?;
+        let mut buf = [0u8; 1024];
+        let n = tokio::time::timeout(wait, stream.read(&mut buf))
+            .await
+            .ok()?
+            .ok()?;
+        let response = String::from_utf8_lossy(&buf[..n]);
+        response.lines().next().map(str::to_string)
+    }
+
+    #[tokio::test]
+    async fn test_accepts_paused_while_saturated() {
+        let listener = handoff::bind_listener("127.0.0.1:0", false).await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let admission = AdmissionControl::new(2, 1);
+        // /slow requests hold until a permit is added
+        let gate = Arc::new(Semaphore::new(0));
+        let slow_gate = Arc::clone(&gate);
+        let app = Router::new()
+            .route("/ping", get(|| async { "pong" }))
+            .route(
+                "/slow",
+                get(move || {
+                    let gate = Arc::clone(&slow_gate);
+                    async move {
+                        gate.acquire().await.unwrap().forget();
+                        "done"
+                    }
+                }),
+            );
+        tokio::spawn(handoff::serve_with_connection_limit(
+            listener,
+            app,
+            std::future::pending(),
+            Duration::from_secs(1),
+            None,
+            Some(Arc::clone(&admission)),
+        ));
+
+        // Two slow requests saturate the server
+        let mut first = TcpStream::connect(addr).await.unwrap();
+        let mut second = TcpStream::connect(addr).await.unwrap();
+        for stream in [&mut first, &mut second] {
+            stream
+                .write_all(b"GET /slow HTTP/1.1\r\nhost: test\r\n\r\n")
+                .await
+                .unwrap();
+        }
+        tokio::time::timeout(Duration::from_secs(2), async {
+            while admission.in_flight() < 2 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+
+        // A new connection sits in the backlog, unserved
+        let mut waiting = TcpStream::connect(addr).await.unwrap();
+        assert_eq!(
+            request(&mut waiting, "/ping", Duration::from_millis(300)).await,
+            None
+        );
+
+        // Finishing one slow request reaches the low watermark and resumes accepting
+        gate.add_permits(1);
+        let (mut buf, mut other_buf) = ([0u8; 1024], [0u8; 1024]);
+        let finished = async {
+            tokio::select! {
+                n = first.read(&mut buf) => String::from_utf8_lossy(&buf[..n.unwrap()]).to_lowercase(),
+                n = second.read(&mut other_buf) => String::from_utf8_lossy(&other_buf[..n.unwrap()]).to_lowercase(),
+            }
+        };
+        let response = tokio::time::timeout(Duration::from_secs(2), finished)
+            .await
+            .unwrap();
+        assert!(response.starts_with("http/1.1 200 ok"));
+        assert!(
+            response.contains("connection: close"),
+            "sent while saturated: {}",
+            response
+        );
+
+        let mut buf = [0u8; 1024];
+        let n = tokio::time::timeout(Duration::from_secs(2), waiting.read(&mut buf))
+            .await
+            .unwrap()
+            .unwrap();
+        let response = String::from_utf8_lossy(&buf[..n]).to_lowercase();
+        assert!(response.starts_with("http/1.1 200 ok"), "{}", response);
+
+        gate.add_permits(1);
+    }
+
+    #[tokio::test]
+    async fn test_equal_watermarks_rejected_and_never_spin() {
+        let config = |high, low| crate::config::AdmissionConfig {
+            high_watermark: high,
+            low_watermark: low,
+        };
+        assert!(config(2, 1).validate().is_ok());
+        assert!(config(2, 2).validate().is_err());
+        assert!(config(0, 0).validate().is_err());
+
+        // Were they let through, a saturated server still waits rather than
+        // resuming at once
+        let admission = AdmissionControl::new(1, 1);
+        let in_flight = admission.enter();
+        let wait = tokio::time::timeout(Duration::from_millis(100), admission.wait_for_capacity());
+        assert!(wait.await.is_err(), "resumed while saturated");
+        drop(in_flight);
+        tokio::time::timeout(Duration::from_secs(1), admission.wait_for_capacity())
+            .await
+            .unwrap();
+    }
+}
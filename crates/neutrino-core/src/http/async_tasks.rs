@@ -0,0 +1,331 @@
+use axum::{
+    extract::{Path, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tracing::{error, info, info_span, warn, Instrument};
+
+use super::{backpressure, complete_task, AppError, AppState, ErrorDetail, RouteMetadata};
+use crate::config::AsyncResultsConfig;
+use crate::journal::{JournalEntry, TaskJournal};
+use crate::results::{
+    self, MemoryResultStore, ResultStatus, ResultStore, SqliteResultStore, StoredResult,
+};
+
+/// Restarts a journaled task may be interrupted by before it is given up on,
+/// so a task that brings the orchestrator down isn't run forever
+const MAX_RECOVERIES: u32 = 3;
+
+/// Open the configured result store, falling back to memory if SQLite can't be opened
+pub fn open_store(config: &AsyncResultsConfig) -> Arc<dyn ResultStore> {
+    let ttl = Duration::from_secs(config.ttl_secs);
+    match &config.store_path {
+        Some(path) => match SqliteResultStore::open(path, ttl) {
+            Ok(store) => {
+                info!("Async task results persisted to {}", path);
+                Arc::new(store)
+            }
+            Err(e) => {
+                error!(
+                    "Failed to open result store at {}: {}. Using in-memory store",
+                    path, e
+                );
+                Arc::new(MemoryResultStore::new(ttl))
+            }
+        },
+        None => Arc::new(MemoryResultStore::new(ttl)),
+    }
+}
+
+/// Whether the client asked for async handling (RFC 7240 `Prefer: respond-async`)
+pub fn wants_async(headers: &HeaderMap) -> bool {
+    headers
+        .get_all("prefer")
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .any(|pref| pref.trim().eq_ignore_ascii_case("respond-async"))
+}
+
+/// Run the task in the background and return where to poll for its result
+pub async fn submit(
+    state: &AppState,
+    store: Arc<dyn ResultStore>,
+    metadata: &RouteMetadata,
+    args: rmpv::Value,
+    task_id: String,
+    error_detail: ErrorDetail,
+    start: Instant,
+) -> Result<String, AppError> {
+    // Held until the result is stored: an accepted task is in flight until then
+    let admitted = backpressure::admit(state)?;
+    let result_url = format!("/tasks/{}/result", task_id);
+
+    let pending = StoredResult {
+        status: ResultStatus::Pending,
+        response: None,
+    };
+    results::put(&store, &task_id, pending)
+        .await
+        .map_err(AppError::ResultStoreError)?;
+    if let Some(journal) = &state.journal {
+        journal_task(journal, &metadata.handler_name, &args, &task_id, 0);
+    }
+
+    // The background run stays part of the task's span
+    let run = run_in_background(
+        state.clone(),
+        store,
+        metadata.clone(),
+        args,
+        task_id,
+        error_detail,
+        start,
+    );
+    tokio::spawn(
+        async move {
+            run.await;
+            drop(admitted);
+        }
+        .in_current_span(),
+    );
+    Ok(result_url)
+}
+
+/// 202 for an accepted async task, with where to poll for its result
+pub fn accepted_response(task_id: &str, result_url: &str) -> Response {
+    let mut response = (
+        StatusCode::ACCEPTED,
+        Json(serde_json::json!({
+            "task_id": task_id,
+            "status": "pending",
+            "result_url": result_url,
+        })),
+    )
+        .into_response();
+    let headers = response.headers_mut();
+    headers.insert(
+        "preference-applied",
+        HeaderValue::from_static("respond-async"),
+    );
+    if let Ok(location) = HeaderValue::from_str(result_url) {
+        headers.insert(header::LOCATION, location);
+    }
+    response
+}
+
+/// Run an accepted task and store its outcome for polling
+async fn run_in_background(
+    state: AppState,
+    store: Arc<dyn ResultStore>,
+    metadata: RouteMetadata,
+    args: rmpv::Value,
+    task_id: String,
+    error_detail: ErrorDetail,
+    start: Instant,
+) {
+    // Polled results are JSON, so a msgpack-only result is an error here
+    let completed = complete_task(&state, &metadata, args, task_id.clone(), start)
+        .await
+        .and_then(|task_response| task_response.check_json().map(|()| task_response));
+    let completed = completed.map(|mut task_response| {
+        error_detail.apply(&mut task_response);
+        task_response
+    });
+    let stored = match completed {
+        Ok(task_response) => StoredResult {
+            status: if task_response.success {
+                ResultStatus::Completed
+            } else {
+                ResultStatus::Failed
+            },
+            response: serde_json::to_value(&task_response).ok(),
+        },
+        Err(e) => {
+            // Store the same error body a synchronous client would have received
+            let response = e.into_response();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .ok()
+                .and_then(|bytes| serde_json::from_slice(&bytes).ok());
+            StoredResult {
+                status: ResultStatus::Failed,
+                response: body,
+            }
+        }
+    };
+
+    if let Err(e) = results::put(&store, &task_id, stored).await {
+        warn!("Failed to store result for async task {}: {}", task_id, e);
+    }
+    if let Some(journal) = &state.journal {
+        if let Err(e) = journal.finish(&task_id) {
+            warn!(
+                "Failed to remove async task {} from the journal: {}",
+                task_id, e
+            );
+        }
+    }
+}
+
+/// Write an accepted task to the journal. A task that can't be journaled still
+/// runs; it just won't be recovered after a restart.
+fn journal_task(
+    journal: &TaskJournal,
+    handler: &str,
+    args: &rmpv::Value,
+    task_id: &str,
+    recoveries: u32,
+) {
+    let mut encoded = Vec::new();
+    let recorded = rmpv::encode::write_value(&mut encoded, args)
+        .map_err(|e| e.to_string())
+        .and_then(|()| {
+            journal.record(&JournalEntry {
+                task_id: task_id.to_string(),
+                handler: handler.to_string(),
+                args: encoded,
+                accepted_at: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|elapsed| elapsed.as_secs())
+                    .unwrap_or_default(),
+                recoveries,
+            })
+        });
+    if let Err(e) = recorded {
+        warn!("Failed to journal async task {}: {}", task_id, e);
+    }
+}
+
+/// Dispatch again the async tasks a previous run accepted but didn't finish.
+/// They run with their handler's route settings (the first route, as for
+/// POST /batch); tasks recovered `MAX_RECOVERIES` times already, or whose
+/// handler is gone, are marked failed instead.
+pub fn recover_journaled(state: &AppState, journal: &TaskJournal, store: &Arc<dyn ResultStore>) {
+    let entries = match journal.unfinished() {
+        Ok(entries) => entries,
+        Err(e) => {
+            error!("Failed to read the task journal: {}", e);
+            return;
+        }
+    };
+    if !entries.is_empty() {
+        info!(
+            "Recovering {} unfinished async task(s) from the journal",
+            entries.len()
+        );
+    }
+
+    let error_config = state
+        .orchestrator
+        .config()
+        .orchestrator
+        .http
+        .error_details
+        .as_ref();
+    let error_detail = ErrorDetail::for_request(error_config, None, &HeaderMap::new());
+    for entry in entries {
+        let (metadata, args) = match recoverable(state, &entry) {
+            Ok(recoverable) => recoverable,
+            Err(reason) => {
+                warn!("Not recovering async task {}: {}", entry.task_id, reason);
+                let failed = StoredResult {
+                    status: ResultStatus::Failed,
+                    response: Some(serde_json::json!({"success": false, "error": reason})),
+                };
+                if let Err(e) = store.put(&entry.task_id, failed) {
+                    warn!(
+                        "Failed to store result for async task {}: {}",
+                        entry.task_id, e
+                    );
+                }
+                if let Err(e) = journal.finish(&entry.task_id) {
+                    warn!(
+                        "Failed to remove async task {} from the journal: {}",
+                        entry.task_id, e
+                    );
+                }
+                continue;
+            }
+        };
+
+        let pending = StoredResult {
+            status: ResultStatus::Pending,
+            response: None,
+        };
+        if let Err(e) = store.put(&entry.task_id, pending) {
+            warn!(
+                "Failed to store result for async task {}: {}",
+                entry.task_id, e
+            );
+        }
+        journal_task(
+            journal,
+            &entry.handler,
+            &args,
+            &entry.task_id,
+            entry.recoveries + 1,
+        );
+
+        let span = info_span!("task", task_id = %entry.task_id, handler = %entry.handler);
+        tokio::spawn(
+            run_in_background(
+                state.clone(),
+                Arc::clone(store),
+                metadata,
+                args,
+                entry.task_id,
+                error_detail,
+                Instant::now(),
+            )
+            .instrument(span),
+        );
+    }
+}
+
+/// The route settings and args to run a journaled task with, or why it can't be
+fn recoverable(
+    state: &AppState,
+    entry: &JournalEntry,
+) -> Result<(RouteMetadata, rmpv::Value), String> {
+    if entry.recoveries >= MAX_RECOVERIES {
+        return Err(format!(
+            "Task was interrupted by {} restarts; not retried again",
+            entry.recoveries + 1
+        ));
+    }
+    let metadata = state
+        .handler_routes
+        .get(&entry.handler)
+        .cloned()
+        .ok_or_else(|| format!("Handler {} no longer has a route", entry.handler))?;
+    let args = rmpv::decode::read_value(&mut entry.args.as_slice())
+        .map_err(|e| format!("Journaled args could not be read: {}", e))?;
+    Ok((metadata, args))
+}
+
+/// Poll an async task: 202 while pending, 200 with the task response once finished
+pub async fn get_task_result(
+    State(state): State<AppState>,
+    Path(task_id): Path<String>,
+) -> Result<Response, AppError> {
+    let store = state
+        .result_store
+        .as_ref()
+        .ok_or_else(|| AppError::TaskNotFound(task_id.clone()))?;
+
+    let stored = results::get(store, &task_id)
+        .await
+        .map_err(AppError::ResultStoreError)?
+        .ok_or_else(|| AppError::TaskNotFound(task_id.clone()))?;
+
+    let summary = serde_json::json!({"task_id": task_id, "status": stored.status});
+    let response = match stored.status {
+        ResultStatus::Pending => (StatusCode::ACCEPTED, Json(summary)).into_response(),
+        _ => Json(stored.response.unwrap_or(summary)).into_response(),
+    };
+    Ok(response)
+}
@@ -0,0 +1,169 @@
+//! Backpressure: while more tasks are waiting for a worker (or accepted and
+//! unfinished) than configured, new tasks are turned away with 429 and a
+//! `Retry-After` estimated from how fast tasks have been finishing, rather
+//! than queueing until they time out.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use super::{AppError, AppState};
+use crate::config::BackpressureConfig;
+
+/// Seconds of completions the drain rate is measured over
+const DRAIN_WINDOW_SECS: u64 = 30;
+
+pub struct Backpressure {
+    config: BackpressureConfig,
+    in_flight: AtomicUsize,
+    /// Tasks finished per second over the drain window, oldest first
+    finished: Mutex<VecDeque<(u64, u64)>>,
+    epoch: Instant,
+}
+
+/// A task counted as in flight until dropped, when it counts as finished
+pub struct Admitted {
+    backpressure: Arc<Backpressure>,
+}
+
+impl Drop for Admitted {
+    fn drop(&mut self) {
+        self.backpressure.in_flight.fetch_sub(1, Ordering::SeqCst);
+        self.backpressure.record_finished();
+    }
+}
+
+impl Backpressure {
+    pub fn new(config: BackpressureConfig) -> Self {
+        Self {
+            config,
+            in_flight: AtomicUsize::new(0),
+            finished: Mutex::new(VecDeque::new()),
+            epoch: Instant::now(),
+        }
+    }
+
+    /// Admit a task, or reject it with the seconds to wait before retrying
+    pub fn admit(self: &Arc<Self>, queued: usize) -> Result<Admitted, AppError> {
+        let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst);
+        let over_queued = self.config.max_queued.is_some_and(|max| queued >= max);
+        let over_in_flight = self
+            .config
+            .max_in_flight
+            .is_some_and(|max| in_flight >= max);
+        if over_queued || over_in_flight {
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            // Everything ahead has to drain before a retry would be admitted
+            let backlog = if over_queued { queued } else { in_flight };
+            return Err(AppError::Overloaded(self.retry_after_secs(backlog)));
+        }
+        Ok(Admitted {
+            backpressure: Arc::clone(self),
+        })
+    }
+
+    /// Seconds for `backlog` tasks to finish at the recent drain rate, at
+    /// least 1 and at most the configured ceiling
+    fn retry_after_secs(&self, backlog: usize) -> u64 {
+        let max = self.config.max_retry_after_secs.max(1);
+        let now = self.now_secs();
+        let mut finished = self.finished.lock().unwrap();
+        Self::expire(&mut finished, now);
+        let Some(&(oldest, _)) = finished.front() else {
+            return max;
+        };
+        let total: u64 = finished.iter().map(|(_, count)| count).sum();
+        let elapsed = (now - oldest).max(1) as f64;
+        let per_sec = total as f64 / elapsed;
+        ((backlog + 1) as f64 / per_sec)
+            .ceil()
+            .clamp(1.0, max as f64) as u64
+    }
+
+    fn record_finished(&self) {
+        let now = self.now_secs();
+        let mut finished = self.finished.lock().unwrap();
+        match finished.back_mut() {
+            Some((second, count)) if *second == now => *count += 1,
+            _ => finished.push_back((now, 1)),
+        }
+        Self::expire(&mut finished, now);
+    }
+
+    fn expire(finished: &mut VecDeque<(u64, u64)>, now: u64) {
+        while finished
+            .front()
+            .is_some_and(|&(second, _)| second + DRAIN_WINDOW_SECS <= now)
+        {
+            finished.pop_front();
+        }
+    }
+
+    fn now_secs(&self) -> u64 {
+        self.epoch.elapsed().as_secs()
+    }
+}
+
+/// Admit a task about to be accepted, when backpressure is configured; keep
+/// the returned guard until the task is finished
+pub fn admit(state: &AppState) -> Result<Option<Admitted>, AppError> {
+    let Some(backpressure) = &state.backpressure else {
+        return Ok(None);
+    };
+    // Tasks waiting for a worker, in the dispatch queue or the pending queue
+    let orchestrator = &state.orchestrator;
+    let queued = orchestrator.task_queue().waiting()
+        + orchestrator
+            .pending_tasks()
+            .map_or(0, |pending| pending.depth());
+    backpressure.admit(queued).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn backpressure(max_queued: Option<usize>, max_in_flight: Option<usize>) -> Arc<Backpressure> {
+        Arc::new(Backpressure::new(BackpressureConfig {
+            max_queued,
+            max_in_flight,
+            max_retry_after_secs: 30,
+        }))
+    }
+
+    #[test]
+    fn test_rejects_past_in_flight_limit_until_one_finishes() {
+        let backpressure = backpressure(None, Some(2));
+        let first = backpressure.admit(0).unwrap();
+        let _second = backpressure.admit(0).unwrap();
+
+        // Nothing has finished yet, so the ceiling is given
+        assert!(matches!(
+            backpressure.admit(0),
+            Err(AppError::Overloaded(30))
+        ));
+
+        drop(first);
+        assert!(backpressure.admit(0).is_ok());
+    }
+
+    #[test]
+    fn test_retry_after_follows_drain_rate() {
+        let backpressure = backpressure(Some(10), None);
+        for _ in 0..4 {
+            drop(backpressure.admit(0).unwrap());
+        }
+
+        // 4 finished within the last second: 20 queued (plus this one) take 6s
+        assert!(matches!(
+            backpressure.admit(20),
+            Err(AppError::Overloaded(6))
+        ));
+        assert!(matches!(
+            backpressure.admit(10),
+            Err(AppError::Overloaded(3))
+        ));
+        assert!(backpressure.admit(9).is_ok());
+    }
+}
@@ -0,0 +1,232 @@
+//! Batch submission (`POST /batch`, with `http.allow_batch`): an array of
+//! `{handler, args}` entries run in parallel, each served as a request to its
+//! handler's route would be (body limit, backpressure, result cache, async
+//! handling), and answered with one result per entry in the same order.
+//! Entries fail on their own: the response is 200 with each entry's `status`,
+//! unless the batch as a whole is invalid. Each entry counts as a request in
+//! flight for admission control.
+
+use axum::{
+    extract::{ConnectInfo, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::{info, info_span, Instrument};
+
+use super::admission::AdmissionControl;
+use super::error_details::ErrorDetail;
+use super::{
+    affinity_from_request, apply_priority_header, async_tasks, gpu_affinity_from_headers,
+    serve_task, tenant, validate_request, AppError, AppState, RouteMetadata, TaskOutcome,
+};
+use crate::serde_convert::json_to_msgpack_value;
+
+/// One task of a batch
+#[derive(Debug, Deserialize)]
+pub struct BatchEntry {
+    pub handler: String,
+    pub args: serde_json::Value,
+}
+
+/// What an entry's task takes from the request it came in with
+#[derive(Clone, Copy)]
+pub(super) struct EntryRequest<'a> {
+    pub headers: &'a HeaderMap,
+    pub peer: Option<IpAddr>,
+    pub error_detail: ErrorDetail,
+    /// Run the task in the background, answering with where to poll for it
+    pub respond_async: bool,
+    pub start: Instant,
+}
+
+/// Run a batch of tasks across the workers and return their results in order
+pub async fn submit_batch(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    client: Option<ConnectInfo<SocketAddr>>,
+    admission: Option<Extension<Arc<AdmissionControl>>>,
+    Json(entries): Json<Vec<BatchEntry>>,
+) -> Result<Response, AppError> {
+    let max_tasks = state
+        .orchestrator
+        .config()
+        .orchestrator
+        .http
+        .max_batch_tasks;
+    if entries.len() > max_tasks {
+        return Err(AppError::ValidationError(vec![format!(
+            "batch has {} tasks, at most {} allowed",
+            entries.len(),
+            max_tasks
+        )]));
+    }
+    info!("Received batch of {} tasks", entries.len());
+
+    // The request itself already counts once
+    let _in_flight: Vec<_> = admission
+        .map(|Extension(admission)| (1..entries.len()).map(|_| admission.enter()).collect())
+        .unwrap_or_default();
+
+    let error_config = state
+        .orchestrator
+        .config()
+        .orchestrator
+        .http
+        .error_details
+        .as_ref();
+    let peer = client.map(|ConnectInfo(addr)| addr.ip());
+    let request = EntryRequest {
+        headers: &headers,
+        peer,
+        error_detail: ErrorDetail::for_request(error_config, peer, &headers),
+        respond_async: async_tasks::wants_async(&headers),
+        start: Instant::now(),
+    };
+
+    let tasks = entries
+        .into_iter()
+        .map(|entry| run_entry(&state, request, entry));
+    let results = futures_util::future::join_all(tasks).await;
+
+    let succeeded = results
+        .iter()
+        .filter(|result| result["success"] == true)
+        .count();
+    let accepted = results
+        .iter()
+        .filter(|result| result["status"] == StatusCode::ACCEPTED.as_u16())
+        .count();
+    let body = serde_json::json!({
+        "succeeded": succeeded,
+        "accepted": accepted,
+        "failed": results.len() - succeeded - accepted,
+        "results": results,
+    });
+    Ok((StatusCode::OK, Json(body)).into_response())
+}
+
+/// An entry's outcome: its task response, where to poll for it, or the status
+/// and error it failed with
+async fn run_entry(
+    state: &AppState,
+    request: EntryRequest<'_>,
+    entry: BatchEntry,
+) -> serde_json::Value {
+    let task_id = uuid::Uuid::new_v4().to_string();
+    let span = info_span!("task", task_id = %task_id, handler = %entry.handler);
+
+    match run_task(state, request, entry, &task_id)
+        .instrument(span)
+        .await
+    {
+        Ok(TaskOutcome::Completed { task_response, .. }) => {
+            let mut result = serde_json::to_value(&task_response).unwrap_or_default();
+            result["task_id"] = task_id.into();
+            result["status"] = StatusCode::OK.as_u16().into();
+            result
+        }
+        Ok(TaskOutcome::Accepted { result_url }) => serde_json::json!({
+            "task_id": task_id,
+            "status": StatusCode::ACCEPTED.as_u16(),
+            "result_url": result_url,
+        }),
+        Err(e) => {
+            let (status, message) = e.status_and_message();
+            serde_json::json!({
+                "task_id": task_id,
+                "success": false,
+                "status": status.as_u16(),
+                "error": message,
+            })
+        }
+    }
+}
+
+/// The route settings an entry's task runs with, as its handler's route
+/// would apply them to a request with these headers from `peer`
+pub(super) fn entry_metadata(
+    state: &AppState,
+    headers: &HeaderMap,
+    peer: Option<IpAddr>,
+    entry: &BatchEntry,
+) -> Result<RouteMetadata, AppError> {
+    let mut metadata = state
+        .handler_routes
+        .get(&entry.handler)
+        .cloned()
+        .ok_or_else(|| AppError::RouteNotFound(format!("handler {}", entry.handler)))?;
+    apply_priority_header(&mut metadata, headers);
+    metadata.tenant = tenant::tenant_id(
+        state
+            .orchestrator
+            .config()
+            .orchestrator
+            .http
+            .tenants
+            .as_ref(),
+        peer,
+        headers,
+    );
+    metadata.gpu_affinity = gpu_affinity_from_headers(headers)?;
+
+    let no_params = HashMap::new();
+    metadata.affinity = affinity_from_request(
+        &metadata,
+        headers,
+        &no_params,
+        &no_params,
+        Some(&entry.args),
+    );
+    Ok(metadata)
+}
+
+/// Run an entry's task as a request to its handler's route would
+pub(super) async fn run_task(
+    state: &AppState,
+    request: EntryRequest<'_>,
+    entry: BatchEntry,
+    task_id: &str,
+) -> Result<TaskOutcome, AppError> {
+    let metadata = entry_metadata(state, request.headers, request.peer, &entry)?;
+    // Held to the limit a request to the route is, measured on the args alone
+    let size = serde_json::to_vec(&entry.args).map_or(0, |body| body.len());
+    if size > metadata.max_body_bytes {
+        return Err(AppError::PayloadTooLarge(metadata.max_body_bytes));
+    }
+    let no_params = HashMap::new();
+    validate_request(&metadata, &no_params, &no_params, Some(&entry.args))?;
+
+    if !state
+        .available_handlers
+        .is_available(&metadata.handler_name)
+    {
+        return Err(AppError::HandlerNotAvailable(metadata.handler_name.clone()));
+    }
+    let args = match &metadata.args_template {
+        Some(template) => json_to_msgpack_value(&template.apply(&entry.args)),
+        None => json_to_msgpack_value(&entry.args),
+    }
+    .map_err(AppError::SerializationError)?;
+
+    let outcome = serve_task(
+        state,
+        &metadata,
+        args,
+        task_id,
+        request.error_detail,
+        request.respond_async,
+        request.start,
+    )
+    .await?;
+    // Entry results are JSON only
+    if let TaskOutcome::Completed { task_response, .. } = &outcome {
+        task_response.check_json()?;
+    }
+    Ok(outcome)
+}
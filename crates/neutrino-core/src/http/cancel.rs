@@ -0,0 +1,258 @@
+//! The registry of tasks in flight, which can be looked up (`GET /tasks/{task_id}`,
+//! with the time spent queued for a worker) and cancelled
+//! (`DELETE /tasks/{task_id}`). A cancelled task
+//! stops waiting for its result and its worker is sent `CancelTask`. The
+//! worker keeps the task's resources until it answers for the task (at once
+//! if it can stop part-way, otherwise when the task finishes); that result is
+//! discarded.
+//! Tasks abandoned because their client went away or they timed out are
+//! cancelled on the worker the same way, as are tasks preempted by a
+//! higher-priority one, which are then run again.
+
+use axum::{
+    extract::{Path, State},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+use tracing::info;
+
+use super::{AppError, AppState, TaskResponse};
+use crate::protocol::ResourceRequirements;
+
+/// Tasks being run, by task ID, so they can be cancelled or preempted
+#[derive(Default)]
+pub struct RunningTasks {
+    tasks: Mutex<HashMap<String, Arc<Control>>>,
+    /// Client-supplied task IDs of requests still being handled
+    claimed: Mutex<HashSet<String>>,
+}
+
+/// A client-supplied task ID reserved for one request, released when dropped
+pub struct TaskIdClaim {
+    tasks: Arc<RunningTasks>,
+    task_id: String,
+}
+
+/// How a running task is stopped, and what preemption needs to know about it
+struct Control {
+    stop: Notify,
+    /// Stopped to make room for a higher-priority task, rather than cancelled
+    preempted: AtomicBool,
+    priority: i32,
+    /// When the request for the task arrived
+    start: Instant,
+    /// Worker the task runs on, the resources it holds there, and how long
+    /// it waited for the worker, once leased
+    leased: Mutex<Option<(String, ResourceRequirements, Duration)>>,
+}
+
+/// A task in the registry, as served by `GET /tasks/{task_id}`
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskStatus {
+    pub task_id: String,
+    /// "queued" until a worker is leased, then "running"
+    pub status: &'static str,
+    pub priority: i32,
+    pub worker_id: Option<String>,
+    /// Time spent waiting for a worker: so far while queued, until the
+    /// lease once running
+    pub queue_wait_ms: u64,
+}
+
+/// A running task that could be preempted
+#[derive(Debug, Clone, PartialEq)]
+pub struct Preemptible {
+    pub task_id: String,
+    pub priority: i32,
+    pub worker_id: String,
+    pub resources: ResourceRequirements,
+}
+
+impl RunningTasks {
+    /// Run `task`, for a request that arrived at `start`, until it completes,
+    /// is cancelled, or is preempted (`AppError::TaskPreempted`)
+    pub async fn run(
+        &self,
+        task_id: &str,
+        priority: i32,
+        start: Instant,
+        task: impl Future<Output = Result<TaskResponse, AppError>>,
+    ) -> Result<TaskResponse, AppError> {
+        let control = Arc::new(Control {
+            stop: Notify::new(),
+            preempted: AtomicBool::new(false),
+            priority,
+            start,
+            leased: Mutex::new(None),
+        });
+        self.tasks
+            .lock()
+            .unwrap()
+            .insert(task_id.to_string(), Arc::clone(&control));
+        let _running = Running {
+            tasks: self,
+            task_id,
+            control: &control,
+        };
+
+        tokio::select! {
+            result = task => result,
+            // Dropping the task releases its worker, telling it to stop
+            _ = control.stop.notified() => Err(if control.preempted.load(Ordering::SeqCst) {
+                AppError::TaskPreempted(task_id.to_string())
+            } else {
+                AppError::TaskCancelled(task_id.to_string())
+            }),
+        }
+    }
+
+    /// Reserve a client-supplied task ID for a request; None if a task with
+    /// this ID is running or another request has claimed it
+    pub fn claim(self: &Arc<Self>, task_id: &str) -> Option<TaskIdClaim> {
+        let tasks = self.tasks.lock().unwrap();
+        let mut claimed = self.claimed.lock().unwrap();
+        if tasks.contains_key(task_id) || !claimed.insert(task_id.to_string()) {
+            return None;
+        }
+        Some(TaskIdClaim {
+            tasks: Arc::clone(self),
+            task_id: task_id.to_string(),
+        })
+    }
+
+    /// Cancel a running task; false if no task with this ID is running
+    pub fn cancel(&self, task_id: &str) -> bool {
+        match self.tasks.lock().unwrap().get(task_id) {
+            Some(control) => {
+                control.stop.notify_one();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Record the worker a running task was leased, what it holds there, and
+    /// how long it waited for it
+    pub fn leased(
+        &self,
+        task_id: &str,
+        worker_id: &str,
+        resources: &ResourceRequirements,
+        queue_wait: Duration,
+    ) {
+        if let Some(control) = self.tasks.lock().unwrap().get(task_id) {
+            *control.leased.lock().unwrap() =
+                Some((worker_id.to_string(), resources.clone(), queue_wait));
+        }
+    }
+
+    /// A task in flight, queued or running; None if no task with this ID is
+    pub fn status(&self, task_id: &str) -> Option<TaskStatus> {
+        let tasks = self.tasks.lock().unwrap();
+        let control = tasks.get(task_id)?;
+        let leased = control.leased.lock().unwrap();
+        let (status, worker_id, queue_wait) = match &*leased {
+            Some((worker_id, _, queue_wait)) => ("running", Some(worker_id.clone()), *queue_wait),
+            None => ("queued", None, control.start.elapsed()),
+        };
+        Some(TaskStatus {
+            task_id: task_id.to_string(),
+            status,
+            priority: control.priority,
+            worker_id,
+            queue_wait_ms: queue_wait.as_millis() as u64,
+        })
+    }
+
+    /// Leased tasks with a priority of at most `max_priority`, lowest first
+    pub fn preemptible(&self, max_priority: i32) -> Vec<Preemptible> {
+        let mut candidates: Vec<Preemptible> = self
+            .tasks
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, control)| {
+                control.priority <= max_priority && !control.preempted.load(Ordering::SeqCst)
+            })
+            .filter_map(|(task_id, control)| {
+                let (worker_id, resources, _) = control.leased.lock().unwrap().clone()?;
+                Some(Preemptible {
+                    task_id: task_id.clone(),
+                    priority: control.priority,
+                    worker_id,
+                    resources,
+                })
+            })
+            .collect();
+        candidates.sort_by_key(|candidate| candidate.priority);
+        candidates
+    }
+
+    /// Stop a running task to be run again; false if it's no longer running
+    pub fn preempt(&self, task_id: &str) -> bool {
+        match self.tasks.lock().unwrap().get(task_id) {
+            Some(control) if !control.preempted.swap(true, Ordering::SeqCst) => {
+                control.stop.notify_one();
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// A task's entry in the registry, removed when the task ends
+struct Running<'a> {
+    tasks: &'a RunningTasks,
+    task_id: &'a str,
+    control: &'a Arc<Control>,
+}
+
+impl Drop for Running<'_> {
+    fn drop(&mut self) {
+        let mut tasks = self.tasks.tasks.lock().unwrap();
+        // A later task reusing the ID keeps its own entry
+        if tasks
+            .get(self.task_id)
+            .is_some_and(|entry| Arc::ptr_eq(entry, self.control))
+        {
+            tasks.remove(self.task_id);
+        }
+    }
+}
+
+impl Drop for TaskIdClaim {
+    fn drop(&mut self) {
+        self.tasks.claimed.lock().unwrap().remove(&self.task_id);
+    }
+}
+
+/// Look up a task in flight
+pub async fn get_task(
+    State(state): State<AppState>,
+    Path(task_id): Path<String>,
+) -> Result<Response, AppError> {
+    let status = state
+        .running_tasks
+        .status(&task_id)
+        .ok_or(AppError::TaskNotFound(task_id))?;
+    Ok(Json(status).into_response())
+}
+
+/// Cancel a task in flight
+pub async fn cancel_task(
+    State(state): State<AppState>,
+    Path(task_id): Path<String>,
+) -> Result<Response, AppError> {
+    if !state.running_tasks.cancel(&task_id) {
+        return Err(AppError::TaskNotFound(task_id));
+    }
+    info!(task_id = %task_id, "Task cancelled");
+    Ok(Json(serde_json::json!({"task_id": task_id, "status": "cancelled"})).into_response())
+}
@@ -0,0 +1,113 @@
+//! Single-flight coalescing of identical requests (`x-neutrino-coalesce`).
+//! While a task is in flight for a route, identical requests (same handler,
+//! args, and tenant) wait for its result instead of dispatching their own, so
+//! a thundering herd on a cache miss costs one worker task. Only meant for
+//! idempotent handlers: every waiter gets the same response.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+use tokio::sync::oneshot;
+
+use super::{AppError, TaskResponse};
+
+type Outcome = Result<TaskResponse, AppError>;
+
+/// What makes two requests identical: handler, tenant, and msgpack-encoded args
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FlightKey {
+    handler: String,
+    tenant: Option<String>,
+    args: Vec<u8>,
+}
+
+impl FlightKey {
+    /// None if the args can't be encoded (the request then runs on its own)
+    pub fn new(handler: &str, tenant: Option<&str>, args: &rmpv::Value) -> Option<Self> {
+        let mut encoded = Vec::new();
+        rmpv::encode::write_value(&mut encoded, args).ok()?;
+        Some(Self {
+            handler: handler.to_string(),
+            tenant: tenant.map(str::to_string),
+            args: encoded,
+        })
+    }
+}
+
+/// In-flight coalesced tasks and the requests waiting on each
+#[derive(Default)]
+pub struct Coalescer {
+    flights: Mutex<HashMap<FlightKey, Vec<oneshot::Sender<Outcome>>>>,
+}
+
+impl Coalescer {
+    /// Run `task` unless an identical one is in flight, in which case wait for
+    /// its outcome. If that task is abandoned (its client went away), one of
+    /// the waiters runs its own.
+    pub async fn run(&self, key: FlightKey, task: impl Future<Output = Outcome>) -> Outcome {
+        loop {
+            let waiting = {
+                let mut flights = self.flights.lock().unwrap();
+                match flights.get_mut(&key) {
+                    Some(waiters) => {
+                        let (sender, receiver) = oneshot::channel();
+                        waiters.push(sender);
+                        Some(receiver)
+                    }
+                    None => {
+                        flights.insert(key.clone(), Vec::new());
+                        None
+                    }
+                }
+            };
+
+            match waiting {
+                Some(receiver) => {
+                    if let Ok(outcome) = receiver.await {
+                        return outcome;
+                    }
+                }
+                None => {
+                    let mut flight = Flight {
+                        coalescer: self,
+                        key: Some(key),
+                    };
+                    let outcome = task.await;
+                    for waiter in flight.land() {
+                        let _ = waiter.send(outcome.clone());
+                    }
+                    return outcome;
+                }
+            }
+        }
+    }
+}
+
+/// The leading request's claim on a key, released when it lands or is dropped
+struct Flight<'a> {
+    coalescer: &'a Coalescer,
+    /// None once landed
+    key: Option<FlightKey>,
+}
+
+impl Flight<'_> {
+    /// Release the key, returning the requests waiting on it
+    fn land(&mut self) -> Vec<oneshot::Sender<Outcome>> {
+        let key = self.key.take().expect("flight already landed");
+        self.coalescer
+            .flights
+            .lock()
+            .unwrap()
+            .remove(&key)
+            .unwrap_or_default()
+    }
+}
+
+impl Drop for Flight<'_> {
+    fn drop(&mut self) {
+        // Abandoned mid-flight: dropping the waiters' senders wakes them to retry
+        if let Some(key) = self.key.take() {
+            self.coalescer.flights.lock().unwrap().remove(&key);
+        }
+    }
+}
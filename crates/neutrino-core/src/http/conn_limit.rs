@@ -0,0 +1,159 @@
+//! Per-client-IP cap on concurrent connections, enforced when a connection is
+//! accepted, so one client can't exhaust file descriptors by holding thousands
+//! of (possibly idle, slowloris-style) connections open. Connections are also
+//! refused while the process's open file descriptors are past their soft limit.
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+
+use crate::fds::FdMonitor;
+
+/// Active connection counts per peer IP
+pub struct ConnectionLimiter {
+    max_per_ip: Option<usize>,
+    fds: Option<FdMonitor>,
+    active: Mutex<HashMap<IpAddr, usize>>,
+}
+
+impl ConnectionLimiter {
+    pub fn new(max_per_ip: Option<usize>, fds: Option<FdMonitor>) -> Arc<Self> {
+        Arc::new(Self {
+            max_per_ip,
+            fds,
+            active: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Count a new connection from `ip`, or None if it is already at its cap or
+    /// file descriptors are running out. The connection is counted until the
+    /// guard is dropped.
+    pub fn try_acquire(self: &Arc<Self>, ip: IpAddr) -> Option<ConnectionGuard> {
+        if self.fds.as_ref().is_some_and(FdMonitor::over_limit) {
+            return None;
+        }
+        let mut active = self.active.lock().unwrap();
+        let count = active.entry(ip).or_insert(0);
+        if self.max_per_ip.is_some_and(|max| *count >= max) {
+            return None;
+        }
+        *count += 1;
+        Some(ConnectionGuard {
+            limiter: Arc::clone(self),
+            ip,
+        })
+    }
+
+    /// Connections currently open from `ip`
+    pub fn active(&self, ip: IpAddr) -> usize {
+        self.active.lock().unwrap().get(&ip).copied().unwrap_or(0)
+    }
+}
+
+/// An accepted connection, released from its IP's count on drop
+pub struct ConnectionGuard {
+    limiter: Arc<ConnectionLimiter>,
+    ip: IpAddr,
+}
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        let mut active = self.limiter.active.lock().unwrap();
+        if let Some(count) = active.get_mut(&self.ip) {
+            *count -= 1;
+            if *count == 0 {
+                active.remove(&self.ip);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::handoff;
+    use axum::{routing::get, Router};
+    use std::net::SocketAddr;
+    use std::time::Duration;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpSocket, TcpStream};
+
+    async fn connect_from(source: [u8; 4], addr: SocketAddr) -> TcpStream {
+        let socket = TcpSocket::new_v4().unwrap();
+        socket.bind(SocketAddr::from((source, 0))).unwrap();
+        socket.connect(addr).await.unwrap()
+    }
+
+    /// Send a request and return the status line, or None if the server closed the connection
+    async fn status_line(stream: &mut TcpStream) -> Option<String> {
+        stream
+            .write_all(b"GET /ping HTTP/1.1\r\nhost: test\r\n\r\n")
+            .await
+            .ok()?;
+        let mut buf = [0u8; 1024];
+        let n = tokio::time::timeout(Duration::from_secs(2), stream.read(&mut buf))
+            .await
+            .unwrap()
+            .ok()?;
+        let response = String::from_utf8_lossy(&buf[..n]);
+        response.lines().next().map(str::to_string)
+    }
+
+    #[tokio::test]
+    async fn test_connections_over_per_ip_limit_refused() {
+        let listener = handoff::bind_listener("127.0.0.1:0", false).await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let limiter = ConnectionLimiter::new(Some(2), None);
+        let app = Router::new().route("/ping", get(|| async { "pong" }));
+        tokio::spawn(handoff::serve_with_connection_limit(
+            listener,
+            app,
+            std::future::pending(),
+            Duration::from_secs(1),
+            Some(Arc::clone(&limiter)),
+            None,
+        ));
+
+        // Two kept-alive connections from one IP use up its limit
+        let local: IpAddr = [127, 0, 0, 1].into();
+        let mut held = Vec::new();
+        for _ in 0..2 {
+            let mut stream = connect_from([127, 0, 0, 1], addr).await;
+            assert_eq!(
+                status_line(&mut stream).await.as_deref(),
+                Some("HTTP/1.1 200 OK")
+            );
+            held.push(stream);
+        }
+        assert_eq!(limiter.active(local), 2);
+
+        // Further connections from it are closed without being served
+        for _ in 0..3 {
+            let mut refused = connect_from([127, 0, 0, 1], addr).await;
+            assert_eq!(status_line(&mut refused).await, None);
+        }
+        assert_eq!(limiter.active(local), 2);
+
+        // Another client is unaffected
+        let mut other = connect_from([127, 0, 0, 2], addr).await;
+        assert_eq!(
+            status_line(&mut other).await.as_deref(),
+            Some("HTTP/1.1 200 OK")
+        );
+
+        // Closing a connection frees a slot
+        drop(held.pop());
+        tokio::time::timeout(Duration::from_secs(2), async {
+            while limiter.active(local) > 1 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+        let mut stream = connect_from([127, 0, 0, 1], addr).await;
+        assert_eq!(
+            status_line(&mut stream).await.as_deref(),
+            Some("HTTP/1.1 200 OK")
+        );
+    }
+}
@@ -0,0 +1,156 @@
+//! Task graph submission (`POST /dag`, with `http.allow_dag`):
+//! `{"nodes": [{id, handler, args}]}` run by [`crate::dag`], each node as a
+//! request to its handler's route would be (body limit, backpressure, result
+//! cache), and counted as a request in flight for admission control. Answered
+//! with every node's outcome in the order given; a failed node skips what
+//! depends on it without failing the request. A node whose reference doesn't
+//! resolve fails as a validation error, with no task id.
+
+use axum::{
+    extract::{ConnectInfo, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use serde::Deserialize;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::{info, info_span, Instrument};
+
+use super::admission::AdmissionControl;
+use super::batch::{run_task, BatchEntry, EntryRequest};
+use super::error_details::ErrorDetail;
+use super::{AppError, AppState, TaskOutcome};
+use crate::dag::{Dag, DagError, DagNode, NodeOutcome};
+
+#[derive(Debug, Deserialize)]
+pub struct DagRequest {
+    pub nodes: Vec<DagNode>,
+}
+
+/// Why a node failed (no task id when it never ran)
+struct NodeFailure {
+    task_id: Option<String>,
+    status: StatusCode,
+    error: String,
+}
+
+impl From<DagError> for NodeFailure {
+    fn from(e: DagError) -> Self {
+        let (status, error) = AppError::ValidationError(vec![e.to_string()]).status_and_message();
+        NodeFailure {
+            task_id: None,
+            status,
+            error,
+        }
+    }
+}
+
+/// Run a graph of tasks across the workers and return each node's outcome
+pub async fn submit_dag(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    client: Option<ConnectInfo<SocketAddr>>,
+    admission: Option<Extension<Arc<AdmissionControl>>>,
+    Json(request): Json<DagRequest>,
+) -> Result<Response, AppError> {
+    let max_nodes = state.orchestrator.config().orchestrator.http.max_dag_nodes;
+    let dag = Dag::new(request.nodes, max_nodes)
+        .map_err(|e| AppError::ValidationError(vec![e.to_string()]))?;
+    info!("Received task graph of {} nodes", dag.nodes().len());
+
+    // The request itself already counts once
+    let _in_flight: Vec<_> = admission
+        .map(|Extension(admission)| (1..dag.nodes().len()).map(|_| admission.enter()).collect())
+        .unwrap_or_default();
+
+    let error_config = state
+        .orchestrator
+        .config()
+        .orchestrator
+        .http
+        .error_details
+        .as_ref();
+    let peer = client.map(|ConnectInfo(addr)| addr.ip());
+    // Nodes feed each other's args, so they never run in the background
+    let request = EntryRequest {
+        headers: &headers,
+        peer,
+        error_detail: ErrorDetail::for_request(error_config, peer, &headers),
+        respond_async: false,
+        start: Instant::now(),
+    };
+
+    let outcomes = dag
+        .run(|node, args| {
+            let state = &state;
+            async move {
+                let task_id = uuid::Uuid::new_v4().to_string();
+                let span = info_span!("task", task_id = %task_id, handler = %node.handler, node = %node.id);
+                let entry = BatchEntry { handler: node.handler.clone(), args };
+                match run_task(state, request, entry, &task_id).instrument(span).await {
+                    Ok(TaskOutcome::Completed { task_response, .. }) => {
+                        if task_response.success {
+                            Ok(task_response.result.unwrap_or_default())
+                        } else {
+                            let error = task_response.error.unwrap_or_default();
+                            Err(NodeFailure { task_id: Some(task_id), status: StatusCode::OK, error })
+                        }
+                    }
+                    Ok(TaskOutcome::Accepted { .. }) => unreachable!("nodes never run async"),
+                    Err(e) => {
+                        let (status, error) = e.status_and_message();
+                        Err(NodeFailure { task_id: Some(task_id), status, error })
+                    }
+                }
+            }
+        })
+        .await;
+
+    let succeeded = outcomes
+        .iter()
+        .filter(|outcome| matches!(outcome, NodeOutcome::Succeeded(_)))
+        .count();
+    let skipped = outcomes
+        .iter()
+        .filter(|outcome| matches!(outcome, NodeOutcome::Skipped(_)))
+        .count();
+    let nodes: Vec<_> = dag
+        .nodes()
+        .iter()
+        .zip(&outcomes)
+        .map(|(node, outcome)| match outcome {
+            NodeOutcome::Succeeded(result) => serde_json::json!({
+                "id": node.id,
+                "success": true,
+                "result": result,
+            }),
+            NodeOutcome::Failed(NodeFailure {
+                task_id,
+                status,
+                error,
+            }) => serde_json::json!({
+                "id": node.id,
+                "task_id": task_id,
+                "success": false,
+                "status": status.as_u16(),
+                "error": error,
+            }),
+            NodeOutcome::Skipped(dependency) => serde_json::json!({
+                "id": node.id,
+                "success": false,
+                "skipped": true,
+                "error": format!("dependency '{}' did not succeed", dependency),
+            }),
+        })
+        .collect();
+
+    let body = serde_json::json!({
+        "succeeded": succeeded,
+        "failed": outcomes.len() - succeeded - skipped,
+        "skipped": skipped,
+        "nodes": nodes,
+    });
+    Ok((StatusCode::OK, Json(body)).into_response())
+}
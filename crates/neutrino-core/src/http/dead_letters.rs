@@ -0,0 +1,194 @@
+use axum::{
+    extract::{Path, Query, State},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tracing::{info, info_span, warn, Instrument};
+
+use super::{complete_task, AppError, AppState, RouteMetadata, TaskResponse};
+use crate::dead_letters::{self, DeadLetter, FailureKind};
+use crate::redact;
+use crate::serde_convert::msgpack_value_to_json;
+
+/// Longest args stored with a dead letter, in characters
+const MAX_ARGS_CHARS: usize = 4096;
+
+/// Dead letters listed by default
+const DEFAULT_LIMIT: usize = 100;
+
+/// Record a task whose outcome is a failure after `attempts` dispatches.
+/// Errors that stop a task from reaching a worker (no capacity, missing
+/// handler) aren't recorded.
+pub async fn record_failure(
+    state: &AppState,
+    metadata: &RouteMetadata,
+    task_id: &str,
+    args: &rmpv::Value,
+    attempts: u32,
+    outcome: &Result<TaskResponse, AppError>,
+) {
+    let Some(store) = &state.dead_letters else {
+        return;
+    };
+
+    let (kind, error, worker_id) = match outcome {
+        Ok(response) if response.success => return,
+        Ok(response) => (
+            FailureKind::HandlerError,
+            response.error.clone().unwrap_or_default(),
+            response.worker_id.clone(),
+        ),
+        Err(AppError::TaskTimeout(secs)) => (
+            FailureKind::Timeout,
+            format!("Task timed out after {}s", secs),
+            None,
+        ),
+        Err(AppError::WorkerCommunicationError(e)) => (
+            FailureKind::WorkerError,
+            format!("Worker communication error: {}", e),
+            None,
+        ),
+        Err(AppError::DeserializationError(e)) => (
+            FailureKind::WorkerError,
+            format!("Deserialization error: {}", e),
+            None,
+        ),
+        Err(AppError::UnexpectedResponse) => (
+            FailureKind::WorkerError,
+            "Unexpected response from worker".to_string(),
+            None,
+        ),
+        Err(_) => return,
+    };
+
+    let tasks = &state.orchestrator.config().orchestrator.tasks;
+    let Some(config) = tasks.dead_letters.as_ref() else {
+        return;
+    };
+    let redact_fields = config.redact_fields.as_slice();
+    let payload = config.redrive.then(|| {
+        let mut payload = Vec::new();
+        rmpv::encode::write_value(&mut payload, args)
+            .map(|()| payload)
+            .ok()
+    });
+    let args = match msgpack_value_to_json(args, tasks.non_finite_floats) {
+        Ok(args) => redact::preview(&args, redact_fields, MAX_ARGS_CHARS),
+        Err(e) => format!("<unrepresentable args: {}>", e),
+    };
+    let mut letter = DeadLetter {
+        task_id: task_id.to_string(),
+        handler: metadata.handler_name.clone(),
+        failed_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default(),
+        kind,
+        error,
+        args,
+        worker_id,
+        attempts,
+        payload: payload.flatten(),
+    };
+    let recorded = dead_letters::blocking(store, move |store| {
+        // A redriven task that fails again adds to its earlier attempts
+        if let Some(previous) = store.get(&letter.task_id).ok().flatten() {
+            letter.attempts += previous.attempts;
+        }
+        store.record(letter)
+    })
+    .await;
+    if let Err(e) = recorded {
+        warn!(task_id = %task_id, "Failed to record dead letter: {}", e);
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeadLetterQuery {
+    /// Only this handler's dead letters
+    handler: Option<String>,
+    limit: Option<usize>,
+}
+
+/// Most recent dead letters first
+pub async fn get_dead_letters(
+    State(state): State<AppState>,
+    Query(query): Query<DeadLetterQuery>,
+) -> Result<Response, AppError> {
+    let Some(store) = &state.dead_letters else {
+        return Err(AppError::RouteNotFound("/admin/dead-letters".to_string()));
+    };
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT);
+    let letters = dead_letters::blocking(store, move |store| {
+        store.recent(query.handler.as_deref(), limit)
+    })
+    .await
+    .map_err(AppError::ResultStoreError)?;
+    Ok(Json(serde_json::json!({"dead_letters": letters})).into_response())
+}
+
+/// One dead letter
+pub async fn get_dead_letter(
+    State(state): State<AppState>,
+    Path(task_id): Path<String>,
+) -> Result<Response, AppError> {
+    let letter = find(&state, &task_id).await?;
+    Ok(Json(letter).into_response())
+}
+
+/// Run a dead-lettered task again with its original args, under the same task
+/// id. Its letter is dropped if it succeeds, and replaced with the attempts
+/// added up if it fails again.
+pub async fn redrive_dead_letter(
+    State(state): State<AppState>,
+    Path(task_id): Path<String>,
+) -> Result<Response, AppError> {
+    let letter = find(&state, &task_id).await?;
+    let payload = letter
+        .payload
+        .as_deref()
+        .ok_or_else(|| AppError::NotRedrivable(task_id.clone()))?;
+    let args = rmpv::decode::read_value(&mut &payload[..])
+        .map_err(|e| AppError::DeserializationError(e.to_string()))?;
+
+    let metadata = state
+        .handler_routes
+        .get(&letter.handler)
+        .cloned()
+        .ok_or_else(|| AppError::RouteNotFound(format!("handler {}", letter.handler)))?;
+    if !state
+        .available_handlers
+        .is_available(&metadata.handler_name)
+    {
+        return Err(AppError::HandlerNotAvailable(metadata.handler_name.clone()));
+    }
+
+    let span = info_span!("task", task_id = %task_id, handler = %letter.handler);
+    info!(parent: &span, attempts = letter.attempts, "Redriving dead letter");
+    let task_response = complete_task(&state, &metadata, args, task_id.clone(), Instant::now())
+        .instrument(span)
+        .await?;
+    if task_response.success {
+        if let Some(store) = &state.dead_letters {
+            let id = task_id.clone();
+            if let Err(e) = dead_letters::blocking(store, move |store| store.remove(&id)).await {
+                warn!(task_id = %task_id, "Failed to remove redriven dead letter: {}", e);
+            }
+        }
+    }
+    task_response.check_json()?;
+    Ok(Json(task_response).into_response())
+}
+
+async fn find(state: &AppState, task_id: &str) -> Result<DeadLetter, AppError> {
+    let Some(store) = &state.dead_letters else {
+        return Err(AppError::DeadLetterNotFound(task_id.to_string()));
+    };
+    let id = task_id.to_string();
+    dead_letters::blocking(store, move |store| store.get(&id))
+        .await
+        .map_err(AppError::ResultStoreError)?
+        .ok_or_else(|| AppError::DeadLetterNotFound(task_id.to_string()))
+}
@@ -0,0 +1,158 @@
+use axum::{
+    body::Body,
+    http::{header, HeaderMap, HeaderValue},
+    response::{IntoResponse, Response},
+    Json,
+};
+
+use serde::Serialize;
+
+use super::{AppError, TaskResponse};
+
+pub const MSGPACK_CONTENT_TYPE: &str = "application/msgpack";
+
+/// zstd level used for compressed responses (favors speed over ratio)
+const ZSTD_LEVEL: i32 = 3;
+
+/// Whether a comma-separated header (Accept / Accept-Encoding) lists one of
+/// `values` without `q=0`
+fn header_accepts(headers: &HeaderMap, name: header::HeaderName, values: &[&str]) -> bool {
+    headers
+        .get_all(name)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .any(|item| {
+            let mut parts = item.split(';').map(str::trim);
+            let token = parts.next().unwrap_or_default();
+            let rejected = parts.any(|p| {
+                p.strip_prefix("q=")
+                    .and_then(|q| q.parse::<f32>().ok())
+                    .is_some_and(|q| q == 0.0)
+            });
+            !rejected && values.iter().any(|v| token.eq_ignore_ascii_case(v))
+        })
+}
+
+/// Encode a task response as JSON, or as msgpack (zstd-compressed if accepted)
+/// when the client asks for it via `Accept: application/msgpack`, in which case
+/// the worker's result is sent as it came, without a JSON round trip. With a route
+/// `default_content_type`, a successful scalar result is returned raw instead.
+pub fn encode_task_response(
+    task_response: &TaskResponse,
+    request_headers: &HeaderMap,
+    default_content_type: Option<&HeaderValue>,
+) -> Result<Response, AppError> {
+    if !header_accepts(
+        request_headers,
+        header::ACCEPT,
+        &[MSGPACK_CONTENT_TYPE, "application/x-msgpack"],
+    ) {
+        let raw = default_content_type
+            .filter(|_| task_response.success)
+            .and_then(|content_type| {
+                Some((content_type, raw_scalar(task_response.result.as_ref()?)?))
+            });
+        if let Some((content_type, body)) = raw {
+            return Ok(([(header::CONTENT_TYPE, content_type.clone())], body).into_response());
+        }
+        task_response.check_json()?;
+        return Ok(Json(task_response).into_response());
+    }
+
+    // Named (map) encoding so fields are self-describing for clients
+    let encoded = match &task_response.raw_result {
+        Some(raw_result) => {
+            rmp_serde::to_vec_named(&RawResultResponse::new(task_response, raw_result))
+        }
+        None => rmp_serde::to_vec_named(task_response),
+    };
+    let mut bytes = encoded.map_err(|e| AppError::SerializationError(e.to_string()))?;
+
+    let compress = header_accepts(request_headers, header::ACCEPT_ENCODING, &["zstd"]);
+    if compress {
+        bytes = zstd::encode_all(bytes.as_slice(), ZSTD_LEVEL)
+            .map_err(|e| AppError::SerializationError(format!("zstd compression failed: {}", e)))?;
+    }
+
+    let mut response = Response::new(Body::from(bytes));
+    let headers = response.headers_mut();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static(MSGPACK_CONTENT_TYPE),
+    );
+    headers.insert(
+        header::VARY,
+        HeaderValue::from_static("accept, accept-encoding"),
+    );
+    if compress {
+        headers.insert(header::CONTENT_ENCODING, HeaderValue::from_static("zstd"));
+    }
+    Ok(response)
+}
+
+/// A task response whose result is served as the worker's msgpack, unconverted
+#[derive(Serialize)]
+struct RawResultResponse<'a> {
+    success: bool,
+    result: &'a rmpv::Value,
+    error: &'a Option<String>,
+    worker_id: &'a Option<String>,
+    execution_time_ms: Option<u64>,
+    queue_wait_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    traceback: Option<&'a str>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    cached: bool,
+}
+
+impl<'a> RawResultResponse<'a> {
+    fn new(task_response: &'a TaskResponse, result: &'a rmpv::Value) -> Self {
+        Self {
+            success: task_response.success,
+            result,
+            error: &task_response.error,
+            worker_id: &task_response.worker_id,
+            execution_time_ms: task_response.execution_time_ms,
+            queue_wait_ms: task_response.queue_wait_ms,
+            traceback: task_response.traceback.as_deref(),
+            cached: task_response.cached,
+        }
+    }
+}
+
+/// Body for a scalar result served raw: strings as-is, numbers and booleans as
+/// their JSON text. None for objects, arrays, and null.
+fn raw_scalar(result: &serde_json::Value) -> Option<String> {
+    match result {
+        serde_json::Value::String(s) => Some(s.clone()),
+        serde_json::Value::Number(n) => Some(n.to_string()),
+        serde_json::Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(pairs: &[(header::HeaderName, &str)]) -> HeaderMap {
+        let mut map = HeaderMap::new();
+        for (name, value) in pairs {
+            map.append(name.clone(), HeaderValue::from_str(value).unwrap());
+        }
+        map
+    }
+
+    #[test]
+    fn test_header_accepts() {
+        let h = headers(&[(header::ACCEPT_ENCODING, "gzip, zstd;q=0.5")]);
+        assert!(header_accepts(&h, header::ACCEPT_ENCODING, &["zstd"]));
+
+        let h = headers(&[(header::ACCEPT_ENCODING, "gzip, zstd;q=0")]);
+        assert!(!header_accepts(&h, header::ACCEPT_ENCODING, &["zstd"]));
+
+        let h = headers(&[(header::ACCEPT, "application/json")]);
+        assert!(!header_accepts(&h, header::ACCEPT, &[MSGPACK_CONTENT_TYPE]));
+    }
+}
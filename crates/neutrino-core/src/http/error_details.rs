@@ -0,0 +1,79 @@
+//! How much of a failed task's error a client sees. Worker tracebacks help
+//! debugging but expose code paths and sometimes data, so with
+//! `http.error_details` configured they go only to trusted sources (listed
+//! networks, or requests carrying the debug token); everyone else gets a
+//! generic message. Tracebacks are always logged server-side.
+
+use axum::http::HeaderMap;
+use std::net::IpAddr;
+
+use super::TaskResponse;
+use crate::config::ErrorDetailsConfig;
+
+/// Request header carrying `error_details.debug_token`
+pub const DEBUG_HEADER: &str = "x-neutrino-debug";
+
+/// Returned to untrusted clients in place of a task's error
+pub const GENERIC_ERROR: &str = "Task failed; details were logged on the server";
+
+/// Error detail included in a failed task's response
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ErrorDetail {
+    /// The worker's error message, without the traceback (not configured)
+    Message,
+    /// Message and traceback, for trusted clients
+    Full,
+    /// A generic message only
+    Generic,
+}
+
+impl ErrorDetail {
+    /// Detail for a request from `peer` with `headers`
+    pub fn for_request(
+        config: Option<&ErrorDetailsConfig>,
+        peer: Option<IpAddr>,
+        headers: &HeaderMap,
+    ) -> Self {
+        let Some(config) = config else {
+            return ErrorDetail::Message;
+        };
+
+        let trusted_peer = peer
+            .map(|ip| ip.to_canonical())
+            .is_some_and(|ip| config.trusted_cidrs.iter().any(|net| net.contains(&ip)));
+        let debug_token = config
+            .debug_token
+            .as_deref()
+            .filter(|token| !token.is_empty());
+        let trusted_token = debug_token.is_some_and(|token| {
+            headers
+                .get(DEBUG_HEADER)
+                .is_some_and(|value| constant_time_eq(value.as_bytes(), token.as_bytes()))
+        });
+
+        if trusted_peer || trusted_token {
+            ErrorDetail::Full
+        } else {
+            ErrorDetail::Generic
+        }
+    }
+
+    /// Strip what this client may not see from a task response
+    pub fn apply(self, task_response: &mut TaskResponse) {
+        match self {
+            ErrorDetail::Full => {}
+            ErrorDetail::Message => task_response.traceback = None,
+            ErrorDetail::Generic => {
+                task_response.traceback = None;
+                if !task_response.success {
+                    task_response.error = Some(GENERIC_ERROR.to_string());
+                }
+            }
+        }
+    }
+}
+
+/// Compare without short-circuiting, so response timing doesn't reveal the token
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
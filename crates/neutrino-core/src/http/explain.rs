@@ -0,0 +1,82 @@
+//! Scheduler explanations (`POST /admin/scheduler/explain`): for a task given
+//! as a batch entry (`{handler, args}`, with the request's headers applied as
+//! they would be to the task), why each worker would or wouldn't take it. For
+//! diagnosing "no workers available" without sending the task.
+
+use axum::{
+    extract::{ConnectInfo, State},
+    http::HeaderMap,
+    response::IntoResponse,
+    Json,
+};
+use std::net::SocketAddr;
+
+use super::batch::{entry_metadata, BatchEntry};
+use super::{task_placement, AppError, AppState};
+use crate::config::GpuAffinityFallback;
+use crate::orchestrator::{Placement, SelectionPass};
+
+/// Explain where the entry's task could run
+pub async fn explain_schedule(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    client: Option<ConnectInfo<SocketAddr>>,
+    Json(entry): Json<BatchEntry>,
+) -> Result<impl IntoResponse, AppError> {
+    let peer = client.map(|ConnectInfo(addr)| addr.ip());
+    let metadata = entry_metadata(&state, &headers, peer, &entry)?;
+    let placement = task_placement(&state, &metadata);
+    let overflow_pool = metadata.overflow_pool.as_deref();
+    let orchestrator = &state.orchestrator;
+
+    let mut verdicts = orchestrator
+        .explain_selection(&metadata.resources, overflow_pool, &placement)
+        .await;
+    // As in dispatch, a GPU no worker is bound to may give way to any GPU
+    let mut gpu_affinity_fallback = false;
+    if verdicts.iter().all(|verdict| verdict.pass.is_none())
+        && placement.gpu_device.is_some()
+        && orchestrator
+            .config()
+            .orchestrator
+            .tasks
+            .gpu_affinity_fallback
+            == GpuAffinityFallback::Any
+    {
+        let any_device = Placement {
+            gpu_device: None,
+            ..placement
+        };
+        verdicts = orchestrator
+            .explain_selection(&metadata.resources, overflow_pool, &any_device)
+            .await;
+        gpu_affinity_fallback = true;
+    }
+
+    // The earliest pass any worker qualifies for is the one dispatch picks from
+    let pass = [
+        SelectionPass::Idle,
+        SelectionPass::Busy,
+        SelectionPass::Fallback,
+        SelectionPass::Overflow,
+    ]
+    .into_iter()
+    .map(|pass| pass.as_str())
+    .find(|pass| verdicts.iter().any(|verdict| verdict.pass == Some(*pass)));
+
+    Ok(Json(serde_json::json!({
+        "handler": metadata.handler_name,
+        "handler_available": state.available_handlers.is_available(&metadata.handler_name),
+        "resources": metadata.resources,
+        "placement": {
+            "pool": placement.pool,
+            "gpu_device": placement.gpu_device,
+            "affinity": placement.affinity,
+        },
+        "overflow_pool": overflow_pool,
+        "gpu_affinity_fallback": gpu_affinity_fallback,
+        "schedulable": pass.is_some(),
+        "pass": pass,
+        "workers": verdicts,
+    })))
+}
@@ -0,0 +1,33 @@
+//! Per-handler concurrency caps (`x-neutrino-max-concurrency`). Tasks of a
+//! capped handler beyond its limit wait for a running one to finish before
+//! queueing for a worker, so they don't hold up other handlers' tasks.
+//! Routes sharing a handler share its cap; the first limit seen for a handler
+//! is the one enforced.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Running tasks of each capped handler
+#[derive(Default)]
+pub struct HandlerLimits {
+    handlers: Mutex<HashMap<String, Arc<Semaphore>>>,
+}
+
+impl HandlerLimits {
+    /// Wait until fewer than `limit` tasks of the handler are running. The
+    /// task counts as running until the permit is dropped.
+    pub async fn acquire(&self, handler: &str, limit: usize) -> OwnedSemaphorePermit {
+        let semaphore = Arc::clone(
+            self.handlers
+                .lock()
+                .unwrap()
+                .entry(handler.to_string())
+                .or_insert_with(|| Arc::new(Semaphore::new(limit))),
+        );
+        semaphore
+            .acquire_owned()
+            .await
+            .expect("handler semaphores are never closed")
+    }
+}
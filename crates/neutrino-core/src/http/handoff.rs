@@ -0,0 +1,251 @@
+//! Zero-downtime upgrades by handing the listening port to a new process.
+//!
+//! With `reuse_port: true`, an external supervisor upgrades the orchestrator like so:
+//!
+//! 1. Start the new binary with the same config. It binds the same port with
+//!    `SO_REUSEPORT`, so the kernel spreads new connections over both processes.
+//! 2. Once its workers are up and it is accepting, the new process writes its PID
+//!    to `ready_file`. The supervisor waits until the file holds the new PID.
+//! 3. Send SIGTERM to the old process. It stops accepting, finishes in-flight
+//!    requests (up to `shutdown_timeout_secs`), then shuts down its workers.
+//!
+//! Connections the kernel queued on the old socket but the old process had not yet
+//! accepted when it closed are reset; clients should retry idempotent requests.
+
+use axum::extract::ConnectInfo;
+use axum::middleware;
+use axum::Router;
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use hyper_util::server::conn::auto;
+use hyper_util::service::TowerToHyperService;
+use std::future::Future;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpSocket};
+use tokio::sync::watch;
+use tower::ServiceExt;
+use tracing::{debug, error, info, warn};
+
+use super::admission::{self, AdmissionControl};
+use super::conn_limit::ConnectionLimiter;
+
+/// Bind a listener, sharing the port with other processes when `reuse_port` is set
+pub async fn bind_listener(addr: &str, reuse_port: bool) -> io::Result<TcpListener> {
+    if !reuse_port {
+        return TcpListener::bind(addr).await;
+    }
+
+    let addr = tokio::net::lookup_host(addr).await?.next().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("no address for {}", addr),
+        )
+    })?;
+
+    let socket = if addr.is_ipv4() {
+        TcpSocket::new_v4()?
+    } else {
+        TcpSocket::new_v6()?
+    };
+    socket.set_reuseaddr(true)?;
+    socket.set_reuseport(true)?;
+    socket.bind(addr)?;
+    socket.listen(1024)
+}
+
+/// Serve `app` until `shutdown` resolves, then stop accepting and give in-flight
+/// requests up to `drain_timeout` to finish
+pub async fn serve(
+    listener: TcpListener,
+    app: Router,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+    drain_timeout: Duration,
+) -> io::Result<()> {
+    serve_with_connection_limit(listener, app, shutdown, drain_timeout, None, None).await
+}
+
+/// `serve`, closing connections the limiter refuses (client at its per-IP cap,
+/// or file descriptors past their soft limit) as soon as they are accepted, and
+/// pausing accepts while `admission` reports the server saturated
+pub async fn serve_with_connection_limit(
+    listener: TcpListener,
+    app: Router,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+    drain_timeout: Duration,
+    limiter: Option<Arc<ConnectionLimiter>>,
+    admission: Option<Arc<AdmissionControl>>,
+) -> io::Result<()> {
+    let app = match &admission {
+        Some(admission) => app.layer(middleware::from_fn_with_state(
+            Arc::clone(admission),
+            admission::track,
+        )),
+        None => app,
+    };
+    // Tells connections to finish their current request and close
+    let (draining_tx, draining_rx) = watch::channel(());
+    // Closed once every connection task has dropped its receiver
+    let (closed_tx, closed_rx) = watch::channel(());
+    tokio::pin!(shutdown);
+
+    loop {
+        if let Some(admission) = &admission {
+            tokio::select! {
+                _ = admission.wait_for_capacity() => {}
+                _ = &mut shutdown => break,
+            }
+        }
+        let saturated = async {
+            match &admission {
+                Some(admission) => admission.until_saturated().await,
+                None => std::future::pending().await,
+            }
+        };
+
+        let (stream, peer) = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    // Per-connection errors (e.g. reset before accept) are not worth a pause
+                    if !is_connection_error(&e) {
+                        error!("Accept error: {}", e);
+                        tokio::time::sleep(Duration::from_secs(1)).await;
+                    }
+                    continue;
+                }
+            },
+            // Stop waiting on accept once saturated (accept is cancel-safe)
+            _ = saturated => continue,
+            _ = &mut shutdown => break,
+        };
+
+        let guard = match &limiter {
+            Some(limiter) => match limiter.try_acquire(peer.ip()) {
+                Some(guard) => Some(guard),
+                None => {
+                    debug!(
+                        "Refusing connection from {}: connection limit reached",
+                        peer.ip()
+                    );
+                    continue;
+                }
+            },
+            None => None,
+        };
+
+        // Handlers see the client's address as axum's ConnectInfo
+        let app = app
+            .clone()
+            .map_request(move |mut req: hyper::Request<hyper::body::Incoming>| {
+                req.extensions_mut().insert(ConnectInfo(peer));
+                req
+            });
+        let service = TowerToHyperService::new(app);
+        let mut draining = draining_rx.clone();
+        let closed = closed_rx.clone();
+        tokio::spawn(async move {
+            let builder = auto::Builder::new(TokioExecutor::new());
+            let conn = builder.serve_connection_with_upgrades(TokioIo::new(stream), service);
+            tokio::pin!(conn);
+
+            let result = tokio::select! {
+                result = conn.as_mut() => result,
+                _ = draining.changed() => {
+                    conn.as_mut().graceful_shutdown();
+                    conn.await
+                }
+            };
+            if let Err(e) = result {
+                debug!("Connection from {} ended with error: {}", peer, e);
+            }
+            drop((guard, closed));
+        });
+    }
+
+    info!("Shutdown requested: no longer accepting connections, draining in-flight requests");
+    drop(listener);
+    draining_tx.send_replace(());
+    drop(closed_rx);
+
+    if tokio::time::timeout(drain_timeout, closed_tx.closed())
+        .await
+        .is_err()
+    {
+        warn!(
+            "In-flight requests still running after {}s, stopping anyway",
+            drain_timeout.as_secs()
+        );
+    }
+    Ok(())
+}
+
+fn is_connection_error(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        io::ErrorKind::ConnectionRefused
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::ConnectionReset
+    )
+}
+
+/// Resolves on Ctrl+C or, on Unix, SIGTERM (what supervisors send to the old process)
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            warn!("Failed to listen for Ctrl+C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                warn!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
+
+/// Readiness marker for the supervisor: holds this process's PID while it serves.
+/// Removed on drop unless a newer process has taken it over.
+pub struct ReadyFile {
+    path: PathBuf,
+    pid: String,
+}
+
+impl ReadyFile {
+    pub fn write<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let pid = std::process::id().to_string();
+
+        // Write then rename so the supervisor never reads a partial PID
+        let tmp = path.with_extension(format!("tmp.{}", pid));
+        std::fs::write(&tmp, &pid)?;
+        std::fs::rename(&tmp, &path)?;
+
+        info!("Ready for traffic; wrote PID {} to {:?}", pid, path);
+        Ok(Self { path, pid })
+    }
+}
+
+impl Drop for ReadyFile {
+    fn drop(&mut self) {
+        if std::fs::read_to_string(&self.path).is_ok_and(|owner| owner == self.pid) {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
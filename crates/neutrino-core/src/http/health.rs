@@ -0,0 +1,353 @@
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use tracing::warn;
+
+use super::{dispatch_task, AppState, RouteMetadata};
+use crate::config::DeepHealthConfig;
+use crate::protocol::ResourceRequirements;
+use crate::worker::WorkerState;
+
+/// Debounced readiness: the orchestrator is ready while a worker is idle, and
+/// stays ready for the grace period after the last idle worker was seen so
+/// routine recycling doesn't flap. It is never ready before a worker has been idle.
+pub struct Readiness {
+    grace: Duration,
+    last_ready: std::sync::Mutex<Option<Instant>>,
+}
+
+impl Readiness {
+    pub fn new(grace: Duration) -> Self {
+        Self {
+            grace,
+            last_ready: std::sync::Mutex::new(None),
+        }
+    }
+
+    /// Record the current number of idle workers and decide whether we're ready
+    fn observe(&self, idle_workers: usize) -> bool {
+        let mut last_ready = self.last_ready.lock().unwrap();
+        if idle_workers > 0 {
+            *last_ready = Some(Instant::now());
+            return true;
+        }
+        last_ready.is_some_and(|seen| seen.elapsed() < self.grace)
+    }
+}
+
+/// Readiness endpoint: 503 once no worker has been idle for the grace period
+pub async fn readiness_check(State(state): State<AppState>) -> impl IntoResponse {
+    let workers = state.orchestrator.workers();
+    let workers_guard = workers.read().await;
+    let total = workers_guard.len();
+    let ready_workers = workers_guard
+        .iter()
+        .filter(|w| w.worker.state == WorkerState::Idle)
+        .count();
+    drop(workers_guard);
+
+    let ready = state.readiness.observe(ready_workers);
+    let status = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    (
+        status,
+        Json(serde_json::json!({
+            "status": if ready { "ready" } else { "unready" },
+            "ready_workers": ready_workers,
+            "total_workers": total,
+        })),
+    )
+}
+
+/// Outcome of a deep health probe
+#[derive(Debug, Clone)]
+struct ProbeResult {
+    healthy: bool,
+    worker_id: Option<String>,
+    error: Option<String>,
+    execution_time_ms: u64,
+}
+
+/// End-to-end health probe that runs a no-op task on a worker.
+/// Probes are rate-limited: within `min_interval_secs` the last result is reused.
+pub struct DeepHealthCheck {
+    config: DeepHealthConfig,
+    last: Mutex<Option<(Instant, ProbeResult)>>,
+}
+
+impl DeepHealthCheck {
+    pub fn new(config: DeepHealthConfig) -> Self {
+        Self {
+            config,
+            last: Mutex::new(None),
+        }
+    }
+
+    async fn check(&self, state: &AppState) -> (ProbeResult, bool) {
+        // Holding the lock while probing coalesces concurrent health requests
+        let mut last = self.last.lock().await;
+        if let Some((checked_at, result)) = last.as_ref() {
+            if checked_at.elapsed() < Duration::from_secs(self.config.min_interval_secs) {
+                return (result.clone(), true);
+            }
+        }
+
+        let result = self.probe(state).await;
+        if !result.healthy {
+            warn!(
+                "Deep health check failed: {}",
+                result.error.as_deref().unwrap_or("unknown error")
+            );
+        }
+        *last = Some((Instant::now(), result.clone()));
+        (result, false)
+    }
+
+    async fn probe(&self, state: &AppState) -> ProbeResult {
+        let metadata = RouteMetadata {
+            handler_name: self.config.handler.clone(),
+            // A no-op should fit on any worker
+            resources: ResourceRequirements {
+                num_cpus: 0.0,
+                num_gpus: 0.0,
+                memory_gb: 0.0,
+                gpu_memory_gb: 0.0,
+            },
+            request_schema: None,
+            response_schema: None,
+            default_result_content_type: None,
+            max_body_bytes: 0,
+            overflow_pool: None,
+            pool: None,
+            max_concurrency: None,
+            hedge_after: None,
+            cache_ttl: None,
+            priority: 0,
+            args_template: None,
+            tenant_pools: Default::default(),
+            tenant: None,
+            coalesce: false,
+            gpu_affinity: None,
+            affinity_key: None,
+            affinity: None,
+        };
+
+        // Run the dispatch in its own task so a timeout doesn't abandon a worker
+        // mid-exchange and leave an unread result on its socket
+        let start = Instant::now();
+        let dispatch_state = state.clone();
+        let dispatch = tokio::spawn(async move {
+            let args = rmpv::Value::Map(vec![]);
+            let task_id = uuid::Uuid::new_v4().to_string();
+            dispatch_task(&dispatch_state, &metadata, args, task_id, start).await
+        });
+
+        let timeout = Duration::from_secs(self.config.timeout_secs);
+        let (healthy, worker_id, error) = match tokio::time::timeout(timeout, dispatch).await {
+            Ok(Ok(Ok(response))) => (response.success, response.worker_id, response.error),
+            Ok(Ok(Err(e))) => (false, None, Some(format!("{:?}", e))),
+            Ok(Err(e)) => (false, None, Some(format!("Probe task failed: {}", e))),
+            Err(_) => (
+                false,
+                None,
+                Some(format!("No result within {}s", self.config.timeout_secs)),
+            ),
+        };
+
+        ProbeResult {
+            healthy,
+            worker_id,
+            error,
+            execution_time_ms: start.elapsed().as_millis() as u64,
+        }
+    }
+}
+
+/// Deep health check endpoint: 200 if a no-op task round-trips successfully, 503 otherwise
+pub async fn deep_health_check(State(state): State<AppState>) -> impl IntoResponse {
+    let Some(checker) = state.deep_health.clone() else {
+        let body = Json(serde_json::json!({"error": "Deep health check not configured"}));
+        return (StatusCode::NOT_FOUND, body);
+    };
+
+    let (result, cached) = checker.check(&state).await;
+    let status = if result.healthy {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    (
+        status,
+        Json(serde_json::json!({
+            "status": if result.healthy { "healthy" } else { "unhealthy" },
+            "handler": checker.config.handler,
+            "worker_id": result.worker_id,
+            "error": result.error,
+            "execution_time_ms": result.execution_time_ms,
+            "cached": cached,
+        })),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::http::create_router_with_openapi;
+    use crate::orchestrator::Orchestrator;
+    use crate::protocol::{self, Message, ResourceCapabilities};
+    use crate::testing::{mock_worker_handle, spawn_echo_worker};
+    use axum::{body::Body, extract::Request, Router};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    async fn router_with_deep_health() -> (Router, tokio::net::UnixStream) {
+        let mut config = Config::default();
+        config.orchestrator.http.deep_health = Some(DeepHealthConfig {
+            handler: "health_noop".to_string(),
+            timeout_secs: 1,
+            min_interval_secs: 60,
+        });
+        let orchestrator = Arc::new(Orchestrator::new(config));
+        let (handle, worker_side) =
+            mock_worker_handle("default-0", ResourceCapabilities::default());
+        orchestrator.workers().write().await.push(handle);
+
+        (
+            create_router_with_openapi(orchestrator, None, None, None),
+            worker_side,
+        )
+    }
+
+    async fn get_deep_health(router: Router) -> (StatusCode, serde_json::Value) {
+        let req = Request::builder()
+            .uri("/health/deep")
+            .body(Body::empty())
+            .unwrap();
+        let response = router.oneshot(req).await.unwrap();
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_deep_health_reports_failed_noop() {
+        let (router, mut worker_side) = router_with_deep_health().await;
+
+        // Worker looks idle, but the handler can't be loaded
+        tokio::spawn(async move {
+            while let Ok(Message::TaskAssignment { task_id, .. }) =
+                protocol::read_message(&mut worker_side).await
+            {
+                let reply = Message::TaskResult {
+                    task_id,
+                    success: false,
+                    result: rmpv::Value::from("Route handler 'health_noop' not found"),
+                };
+                protocol::write_message(&mut worker_side, &reply)
+                    .await
+                    .unwrap();
+            }
+        });
+
+        let (status, body) = get_deep_health(router).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["status"], "unhealthy");
+        assert!(body["error"].as_str().unwrap().contains("not found"));
+    }
+
+    #[tokio::test]
+    async fn test_deep_health_healthy_and_rate_limited() {
+        let (router, worker_side) = router_with_deep_health().await;
+        spawn_echo_worker(worker_side, Duration::from_millis(1));
+
+        let (status, body) = get_deep_health(router.clone()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["status"], "healthy");
+        assert_eq!(body["worker_id"], "default-0");
+        assert_eq!(body["cached"], false);
+
+        // Within min_interval_secs the previous result is reused
+        let (status, body) = get_deep_health(router).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["cached"], true);
+    }
+
+    #[tokio::test]
+    async fn test_deep_health_times_out() {
+        // Worker never answers
+        let (router, _worker_side) = router_with_deep_health().await;
+
+        let (status, body) = get_deep_health(router).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["error"], "No result within 1s");
+    }
+
+    async fn get_ready(router: Router) -> StatusCode {
+        let req = Request::builder()
+            .uri("/ready")
+            .body(Body::empty())
+            .unwrap();
+        router.oneshot(req).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn test_ready_survives_brief_worker_loss() {
+        let mut config = Config::default();
+        config.orchestrator.http.ready_grace_secs = 1;
+        let orchestrator = Arc::new(Orchestrator::new(config));
+        let (handle, _worker_side) =
+            mock_worker_handle("default-0", ResourceCapabilities::default());
+        orchestrator.workers().write().await.push(handle);
+        let router = create_router_with_openapi(Arc::clone(&orchestrator), None, None, None);
+
+        assert_eq!(get_ready(router.clone()).await, StatusCode::OK);
+
+        // All workers recycled at once; replacements arrive within the grace period
+        let removed: Vec<_> = orchestrator.workers().write().await.drain(..).collect();
+        for _ in 0..5 {
+            assert_eq!(get_ready(router.clone()).await, StatusCode::OK);
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        orchestrator.workers().write().await.extend(removed);
+        assert_eq!(get_ready(router.clone()).await, StatusCode::OK);
+
+        // The grace period restarts after recovery, and sustained loss goes unready
+        let _removed: Vec<_> = orchestrator.workers().write().await.drain(..).collect();
+        assert_eq!(get_ready(router.clone()).await, StatusCode::OK);
+        tokio::time::sleep(Duration::from_millis(1100)).await;
+        assert_eq!(get_ready(router).await, StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn test_not_ready_without_an_idle_worker() {
+        // The grace period only covers losing workers, not startup
+        let mut config = Config::default();
+        config.orchestrator.http.ready_grace_secs = 60;
+        let orchestrator = Arc::new(Orchestrator::new(config));
+        let router = create_router_with_openapi(Arc::clone(&orchestrator), None, None, None);
+        assert_eq!(
+            get_ready(router.clone()).await,
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+
+        let (mut handle, _worker_side) =
+            mock_worker_handle("default-0", ResourceCapabilities::default());
+        handle.worker.state = WorkerState::Starting;
+        orchestrator.workers().write().await.push(handle);
+        assert_eq!(
+            get_ready(router.clone()).await,
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+
+        orchestrator.workers().write().await[0].worker.state = WorkerState::Idle;
+        assert_eq!(get_ready(router).await, StatusCode::OK);
+    }
+}
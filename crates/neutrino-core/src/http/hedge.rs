@@ -0,0 +1,61 @@
+//! Hedged dispatch for GET routes with `x-neutrino-hedge-after-ms`: when a
+//! task hasn't finished after the delay and another worker is idle, a
+//! duplicate runs there too. The first result wins; the other task is dropped,
+//! which sends its worker `CancelTask`. That worker isn't handed more work
+//! until it answers for the dropped task, which for workers that can't stop a
+//! task part-way means when the task finishes. This trims tail latency from
+//! stragglers (e.g. a worker paused for garbage collection) at the cost of
+//! the occasional duplicate run.
+
+use std::time::{Duration, Instant};
+use tracing::info;
+
+use super::{
+    dispatch_task, select_worker, task_placement, AppError, AppState, RouteMetadata, TaskResponse,
+};
+
+/// Dispatch a task, hedging it on a second idle worker after `delay`
+pub async fn dispatch_hedged(
+    state: &AppState,
+    metadata: &RouteMetadata,
+    args: rmpv::Value,
+    task_id: &str,
+    start: Instant,
+    delay: Duration,
+) -> Result<TaskResponse, AppError> {
+    let primary = dispatch_task(state, metadata, args.clone(), task_id.to_string(), start);
+    tokio::pin!(primary);
+    tokio::select! {
+        result = &mut primary => return result,
+        _ = tokio::time::sleep(delay) => {}
+    }
+
+    // Only hedge on a worker that can start at once; a hedge waiting in the
+    // queue would only hold up other tasks
+    if select_worker(state, metadata, &task_placement(state, metadata))
+        .await
+        .is_none()
+    {
+        return primary.await;
+    }
+    let hedge_id = format!("{}-hedge", task_id);
+    info!(task_id = %task_id, hedge_task_id = %hedge_id, "Task still running after {}ms, hedging it", delay.as_millis());
+    let hedge = dispatch_task(state, metadata, args, hedge_id, start);
+    tokio::pin!(hedge);
+
+    // The first to succeed in reaching a result wins; if one fails outright
+    // (e.g. its worker died), the other still gets to finish
+    tokio::select! {
+        result = &mut primary => match result {
+            Ok(response) => Ok(response),
+            Err(_) => hedge.await,
+        },
+        result = &mut hedge => match result {
+            Ok(response) => {
+                info!(task_id = %task_id, "Hedged task finished first");
+                Ok(response)
+            }
+            Err(_) => primary.await,
+        },
+    }
+}
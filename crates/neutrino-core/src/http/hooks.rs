@@ -0,0 +1,38 @@
+use std::sync::Arc;
+
+use super::RouteMetadata;
+
+/// A function applied to every successful task result before it's returned
+pub type ResultHook = Arc<dyn Fn(&RouteMetadata, &mut serde_json::Value) + Send + Sync>;
+
+/// Ordered set of result post-processing hooks (e.g. redaction, added metadata)
+#[derive(Clone, Default)]
+pub struct ResultHooks {
+    hooks: Vec<ResultHook>,
+}
+
+impl ResultHooks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a hook; hooks run in registration order
+    pub fn with_hook<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&RouteMetadata, &mut serde_json::Value) + Send + Sync + 'static,
+    {
+        self.hooks.push(Arc::new(hook));
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hooks.is_empty()
+    }
+
+    /// Run every hook on a result
+    pub fn apply(&self, metadata: &RouteMetadata, result: &mut serde_json::Value) {
+        for hook in &self.hooks {
+            hook(metadata, result);
+        }
+    }
+}
@@ -1,24 +1,78 @@
 use axum::{
     body::Body,
-    extract::{State, Request},
-    http::StatusCode,
+    extract::{ConnectInfo, DefaultBodyLimit, Path, Query, Request, State},
+    http::{HeaderMap, HeaderValue, StatusCode},
     middleware::{self, Next},
     response::{IntoResponse, Response},
-    routing::{get, post, delete, patch, put},
+    routing::{delete, get, patch, post, put, MethodRouter},
     Extension, Json, Router,
 };
+use base64::Engine;
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
-use std::time::Duration;
-use tracing::{info, warn};
+use std::time::{Duration, Instant};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tower::ServiceExt;
+use tracing::{debug, error, info, info_span, warn, Instrument};
 
-use crate::config::AsgiConfig;
-use crate::openapi::OpenApiSpec;
-use crate::orchestrator::Orchestrator;
+use crate::chaos::{self, ChaosInjector};
+use crate::config::{
+    AsgiConfig, GpuAffinityFallback, HttpConfig, TimeoutAction, UnconvertibleResultPolicy,
+};
+use crate::dead_letters::DeadLetterStore;
+use crate::journal::TaskJournal;
+use crate::openapi::compose::{self, SpecRoutes};
+use crate::openapi::remote;
+use crate::openapi::{
+    ArgsTemplate, OpenApiSpec, RequestSchema, ResourcePolicy, ResponseSchema, RouteInfo,
+};
+use crate::orchestrator::{
+    handlers::HandlerRegistry, pool_name, Orchestrator, Placement, SelectionPass, WorkerSelection,
+};
 use crate::protocol::Message;
 
 use crate::protocol::ResourceRequirements;
+use crate::redact;
+use crate::results::ResultStore;
+use crate::serde_convert::{json_to_msgpack_value, msgpack_value_to_json, NonFiniteFloats};
+
+pub mod admission;
+mod async_tasks;
+mod backpressure;
+mod batch;
+mod cancel;
+mod coalesce;
+pub mod conn_limit;
+mod dag;
+mod dead_letters;
+mod encoding;
+mod error_details;
+mod explain;
+mod handler_limits;
+pub mod handoff;
+mod health;
+mod hedge;
+mod hooks;
+mod pool_scale;
+mod preempt;
+mod result_cache;
+mod route_patch;
+mod schedules;
+mod tenant;
+
+use backpressure::Backpressure;
+use cancel::{RunningTasks, TaskIdClaim};
+use coalesce::{Coalescer, FlightKey};
+use error_details::ErrorDetail;
+use handler_limits::HandlerLimits;
+use result_cache::ResultCache;
+use route_patch::PatchedRoutes;
+
+pub use error_details::DEBUG_HEADER;
+pub use health::{DeepHealthCheck, Readiness};
+pub use hooks::{ResultHook, ResultHooks};
 
 /// Shared application state
 #[derive(Clone)]
@@ -26,8 +80,41 @@ pub struct AppState {
     pub orchestrator: Arc<Orchestrator>,
     pub asgi_config: Option<AsgiConfig>,
     pub asgi_client: Option<reqwest::Client>,
+    /// Limits concurrent ASGI proxy requests (None = unlimited)
+    pub asgi_permits: Option<Arc<Semaphore>>,
+    /// End-to-end health probe, present when configured
+    pub deep_health: Option<Arc<DeepHealthCheck>>,
+    /// Debounced worker readiness for /ready
+    pub readiness: Arc<Readiness>,
+    /// Async task results, present when async mode is configured
+    pub result_store: Option<Arc<dyn ResultStore>>,
+    /// Accepted async tasks not yet finished, present when configured
+    pub journal: Option<Arc<TaskJournal>>,
+    /// Post-processing applied to every successful task result
+    pub result_hooks: ResultHooks,
+    /// Handlers the workers provide; routes whose handler is missing fail fast
+    pub available_handlers: Arc<HandlerRegistry>,
     /// Set of registered Neutrino route paths for lookup-based routing
     pub neutrino_routes: Arc<HashSet<String>>,
+    /// Task routes added via PATCH /admin/routes, present when enabled
+    pub patched_routes: Option<Arc<PatchedRoutes>>,
+    /// In-flight tasks of coalescing routes, shared by identical requests
+    pub coalescer: Arc<Coalescer>,
+    /// Results of routes with x-neutrino-cache-ttl, returned for identical requests
+    pub result_cache: Arc<ResultCache>,
+    /// Tasks that failed for good, present when configured
+    pub dead_letters: Option<Arc<dyn DeadLetterStore>>,
+    /// Tasks being run, for cancellation
+    pub running_tasks: Arc<RunningTasks>,
+    /// Running tasks of handlers with x-neutrino-max-concurrency
+    pub handler_limits: Arc<HandlerLimits>,
+    /// Route settings per handler, for tasks submitted by handler name (POST /batch)
+    pub handler_routes: Arc<HashMap<String, RouteMetadata>>,
+    /// Pools dedicated to a tenant on a spec route (x-neutrino-tenant-pools),
+    /// which take no other tasks
+    pub dedicated_pools: Arc<HashSet<String>>,
+    /// Turns tasks away with 429 while too many are queued, present when configured
+    pub backpressure: Option<Arc<Backpressure>>,
 }
 
 /// Route metadata passed through request extensions
@@ -35,6 +122,57 @@ pub struct AppState {
 pub struct RouteMetadata {
     pub handler_name: String,
     pub resources: ResourceRequirements,
+    /// Request schema, present when request validation is enabled
+    pub request_schema: Option<Arc<RequestSchema>>,
+    /// Response schema, present when response schema enforcement is enabled
+    pub response_schema: Option<Arc<ResponseSchema>>,
+    /// Content type scalar results are returned raw with, instead of the JSON envelope
+    pub default_result_content_type: Option<HeaderValue>,
+    /// Largest request body the route accepts, in bytes
+    pub max_body_bytes: usize,
+    /// Pool tried, without the GPU requirement, when no worker can take the task
+    pub overflow_pool: Option<String>,
+    /// Pool the route's tasks are pinned to (x-neutrino-pool)
+    pub pool: Option<String>,
+    /// Most tasks of the handler running at once (x-neutrino-max-concurrency)
+    pub max_concurrency: Option<usize>,
+    /// Delay before a still-running task is hedged on a second worker (GET
+    /// routes with x-neutrino-hedge-after-ms)
+    pub hedge_after: Option<Duration>,
+    /// How long a successful result is returned for identical requests (x-neutrino-cache-ttl)
+    pub cache_ttl: Option<Duration>,
+    /// Dispatch priority while waiting for a worker (route default, or the request's header)
+    pub priority: i32,
+    /// Reshapes the request's args into what the handler expects
+    pub args_template: Option<Arc<ArgsTemplate>>,
+    /// Dedicated pool per tenant ID
+    pub tenant_pools: Arc<HashMap<String, String>>,
+    /// Tenant the request was made for, when tenant tagging is configured
+    pub tenant: Option<String>,
+    /// Identical concurrent requests share one task
+    pub coalesce: bool,
+    /// Physical GPU the request asked to run on (X-Neutrino-Gpu-Affinity)
+    pub gpu_affinity: Option<usize>,
+    /// Arg naming the request's session (x-neutrino-affinity-key)
+    pub affinity_key: Option<String>,
+    /// The request's session, from X-Neutrino-Affinity-Key or the affinity arg
+    pub affinity: Option<String>,
+}
+
+/// Validate path, query, and body together, reporting every violation at once
+fn validate_request(
+    metadata: &RouteMetadata,
+    path_params: &HashMap<String, String>,
+    query_params: &HashMap<String, String>,
+    body: Option<&serde_json::Value>,
+) -> Result<(), AppError> {
+    if let Some(schema) = &metadata.request_schema {
+        let violations = schema.validate(path_params, query_params, body);
+        if !violations.is_empty() {
+            return Err(AppError::ValidationError(violations));
+        }
+    }
+    Ok(())
 }
 
 /// Request body for task execution
@@ -44,91 +182,162 @@ pub struct TaskRequest {
 }
 
 /// Response for task execution
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct TaskResponse {
     pub success: bool,
     pub result: Option<serde_json::Value>,
     pub error: Option<String>,
     pub worker_id: Option<String>,
     pub execution_time_ms: Option<u64>,
+    /// Time spent waiting for a worker before dispatch
+    pub queue_wait_ms: Option<u64>,
+    /// Worker traceback of a failed task, for trusted clients (`http.error_details`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub traceback: Option<String>,
+    /// Answered from the result cache (`x-neutrino-cache-ttl`)
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub cached: bool,
+    /// The worker's result as sent, served unconverted to clients accepting
+    /// msgpack; its only form when it has no JSON one (`unconvertible_results: msgpack`)
+    #[serde(skip)]
+    pub raw_result: Option<rmpv::Value>,
 }
 
-/// Convert serde_json::Value to rmpv::Value
-fn json_to_msgpack_value(json: &serde_json::Value) -> Result<rmpv::Value, String> {
-    match json {
-        serde_json::Value::Null => Ok(rmpv::Value::Nil),
-        serde_json::Value::Bool(b) => Ok(rmpv::Value::Boolean(*b)),
-        serde_json::Value::Number(n) => {
-            if let Some(i) = n.as_i64() {
-                Ok(rmpv::Value::Integer(i.into()))
-            } else if let Some(f) = n.as_f64() {
-                Ok(rmpv::Value::F64(f))
-            } else {
-                Err("Invalid number".to_string())
-            }
-        }
-        serde_json::Value::String(s) => Ok(rmpv::Value::String(s.clone().into())),
-        serde_json::Value::Array(arr) => {
-            let values: Result<Vec<_>, _> = arr.iter().map(json_to_msgpack_value).collect();
-            Ok(rmpv::Value::Array(values?))
+impl TaskResponse {
+    /// Fail if the result can only be served as msgpack
+    fn check_json(&self) -> Result<(), AppError> {
+        if self.result.is_some() {
+            return Ok(());
         }
-        serde_json::Value::Object(obj) => {
-            let pairs: Result<Vec<(rmpv::Value, rmpv::Value)>, String> = obj
-                .iter()
-                .map(|(k, v)| {
-                    Ok((
-                        rmpv::Value::String(k.clone().into()),
-                        json_to_msgpack_value(v)?,
-                    ))
-                })
-                .collect();
-            Ok(rmpv::Value::Map(pairs?))
+        // The strictest conversion reproduces the failure under any
+        // non_finite_floats setting
+        let strict = |raw: &rmpv::Value| msgpack_value_to_json(raw, NonFiniteFloats::Error);
+        match self.raw_result.as_ref().map(strict) {
+            Some(Err(e)) => Err(AppError::UnconvertibleResult(format!(
+                "{}; request it with Accept: {}",
+                e,
+                encoding::MSGPACK_CONTENT_TYPE
+            ))),
+            _ => Ok(()),
         }
     }
 }
 
-/// Convert rmpv::Value to serde_json::Value
-fn msgpack_value_to_json(msgpack: &rmpv::Value) -> Result<serde_json::Value, String> {
-    match msgpack {
-        rmpv::Value::Nil => Ok(serde_json::Value::Null),
-        rmpv::Value::Boolean(b) => Ok(serde_json::Value::Bool(*b)),
-        rmpv::Value::Integer(i) => {
-            if let Some(val) = i.as_i64() {
-                Ok(serde_json::json!(val))
-            } else if let Some(val) = i.as_u64() {
-                Ok(serde_json::json!(val))
-            } else {
-                Err("Integer out of range".to_string())
-            }
-        }
-        rmpv::Value::F32(f) => Ok(serde_json::json!(*f)),
-        rmpv::Value::F64(f) => Ok(serde_json::json!(*f)),
-        rmpv::Value::String(s) => Ok(serde_json::Value::String(
-            s.as_str().ok_or("Invalid UTF-8")?.to_string(),
-        )),
-        rmpv::Value::Binary(b) => {
-            // Convert binary to array of numbers for JSON compatibility
-            Ok(serde_json::Value::Array(
-                b.iter().map(|&byte| serde_json::json!(byte)).collect(),
-            ))
-        }
-        rmpv::Value::Array(arr) => {
-            let values: Result<Vec<_>, _> = arr.iter().map(msgpack_value_to_json).collect();
-            Ok(serde_json::Value::Array(values?))
+/// Response header carrying the task's queue wait time in milliseconds
+pub const QUEUE_WAIT_HEADER: &str = "x-neutrino-queue-wait-ms";
+
+/// Response header saying whether a cached route's result came from cache
+/// ("hit") or a task ("miss")
+pub const CACHE_HEADER: &str = "x-neutrino-cache";
+
+/// Request header with a caller-assigned task ID (set by the gateway), adopted
+/// so gateway and orchestrator logs for a request share one ID. Echoed on responses.
+/// An ID already in use by another task is refused with 409.
+pub const TASK_ID_HEADER: &str = "x-neutrino-task-id";
+
+/// Request header overriding the route's dispatch priority (x-neutrino-priority)
+pub const PRIORITY_HEADER: &str = "x-neutrino-priority";
+
+/// Apply a valid priority header over the route's default
+fn apply_priority_header(metadata: &mut RouteMetadata, headers: &HeaderMap) {
+    let priority = headers
+        .get(PRIORITY_HEADER)
+        .and_then(|value| value.to_str().ok()?.trim().parse().ok());
+    if let Some(priority) = priority {
+        metadata.priority = priority;
+    }
+}
+
+/// Header pinning a task to the worker(s) bound to one physical GPU
+pub const GPU_AFFINITY_HEADER: &str = "x-neutrino-gpu-affinity";
+
+/// The GPU device requested with X-Neutrino-Gpu-Affinity, if any
+fn gpu_affinity_from_headers(headers: &HeaderMap) -> Result<Option<usize>, AppError> {
+    let Some(value) = headers.get(GPU_AFFINITY_HEADER) else {
+        return Ok(None);
+    };
+    value
+        .to_str()
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .map(Some)
+        .ok_or_else(|| {
+            AppError::ValidationError(vec![format!(
+                "{} must be a GPU device index, got {:?}",
+                GPU_AFFINITY_HEADER, value
+            )])
+        })
+}
+
+/// Header naming the request's session, so its tasks prefer one worker
+pub const AFFINITY_HEADER: &str = "x-neutrino-affinity-key";
+
+/// The request's session: the affinity header, else the route's affinity arg
+/// from the path, query, or top level of the body
+fn affinity_from_request(
+    metadata: &RouteMetadata,
+    headers: &HeaderMap,
+    path_params: &HashMap<String, String>,
+    query_params: &HashMap<String, String>,
+    body: Option<&serde_json::Value>,
+) -> Option<String> {
+    let from_header = headers
+        .get(AFFINITY_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty());
+    from_header.or_else(|| {
+        let arg = metadata.affinity_key.as_deref()?;
+        path_params
+            .get(arg)
+            .or_else(|| query_params.get(arg))
+            .cloned()
+            .or_else(|| match body?.get(arg)? {
+                serde_json::Value::String(value) => Some(value.clone()),
+                serde_json::Value::Number(value) => Some(value.to_string()),
+                _ => None,
+            })
+    })
+}
+
+/// The forwarded task ID, if present and well-formed
+fn task_id_from_headers(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(TASK_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| {
+            (1..=128).contains(&id.len())
+                && id
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'))
+        })
+        .map(str::to_string)
+}
+
+/// Reserve a forwarded task ID for this request. An ID that's already running,
+/// claimed by another request, or has a stored result or dead letter is
+/// refused, so one client can't take over another's task.
+async fn claim_task_id(state: &AppState, task_id: &str) -> Result<TaskIdClaim, AppError> {
+    let in_use = || AppError::TaskIdInUse(task_id.to_string());
+    let claim = state.running_tasks.claim(task_id).ok_or_else(in_use)?;
+    if let Some(store) = &state.result_store {
+        let stored = crate::results::get(store, task_id)
+            .await
+            .map_err(AppError::ResultStoreError)?;
+        if stored.is_some() {
+            return Err(in_use());
         }
-        rmpv::Value::Map(map) => {
-            let mut obj = serde_json::Map::new();
-            for (k, v) in map {
-                let key = match k {
-                    rmpv::Value::String(s) => s.as_str().ok_or("Invalid UTF-8")?.to_string(),
-                    _ => return Err("Map keys must be strings".to_string()),
-                };
-                obj.insert(key, msgpack_value_to_json(v)?);
-            }
-            Ok(serde_json::Value::Object(obj))
+    }
+    if let Some(store) = &state.dead_letters {
+        let id = task_id.to_string();
+        let letter = crate::dead_letters::blocking(store, move |store| store.get(&id))
+            .await
+            .map_err(AppError::ResultStoreError)?;
+        if letter.is_some() {
+            return Err(in_use());
         }
-        rmpv::Value::Ext(_, _) => Err("Extension types not supported".to_string()),
     }
+    Ok(claim)
 }
 
 /// Health check endpoint
@@ -142,12 +351,85 @@ async fn health_check() -> impl IntoResponse {
 /// Get orchestrator status
 async fn get_status(State(state): State<AppState>) -> impl IntoResponse {
     let worker_count = state.orchestrator.worker_count().await;
+    let pools = state.orchestrator.pool_status().await;
 
     Json(serde_json::json!({
         "status": "running",
         "workers": {
             "active": worker_count,
+        },
+        "pools": pools,
+        "fds": {
+            "open": crate::fds::open_fds(),
+            "limit": crate::fds::fd_limit(),
+            "soft_limit": crate::fds::soft_limit(state.orchestrator.config().orchestrator.http.fd_soft_limit_ratio),
+        },
+    }))
+}
+
+/// Per-worker state, including internal queue depth reported by workers
+async fn get_workers(State(state): State<AppState>) -> impl IntoResponse {
+    Json(serde_json::json!({
+        "workers": state.orchestrator.worker_status().await,
+    }))
+}
+
+/// A worker's recent allocate/deallocate events, oldest first
+async fn get_worker_allocations(
+    State(state): State<AppState>,
+    Path(worker_id): Path<String>,
+) -> Response {
+    match state.orchestrator.allocation_history(&worker_id).await {
+        Some(events) => Json(serde_json::json!({
+            "worker_id": worker_id,
+            "allocations": events,
+        }))
+        .into_response(),
+        None => {
+            let body =
+                Json(serde_json::json!({"error": format!("Worker not found: {}", worker_id)}));
+            (StatusCode::NOT_FOUND, body).into_response()
         }
+    }
+}
+
+/// Replace every worker in turn, each once idle (e.g. to load new code)
+async fn restart_workers(State(state): State<AppState>) -> Result<Response, AppError> {
+    let workers = state
+        .orchestrator
+        .rolling_restart()
+        .await
+        .ok_or(AppError::RestartInProgress)?;
+    let body = Json(serde_json::json!({"status": "restarting", "workers": workers}));
+    Ok((StatusCode::ACCEPTED, body).into_response())
+}
+
+/// Metrics and worker state saved by the previous run's shutdown
+async fn get_snapshot(State(state): State<AppState>) -> Response {
+    match state.orchestrator.last_snapshot() {
+        Some(snapshot) => Json(snapshot).into_response(),
+        None => {
+            let body = Json(serde_json::json!({"error": "No snapshot from a previous run"}));
+            (StatusCode::NOT_FOUND, body).into_response()
+        }
+    }
+}
+
+/// Prometheus metrics endpoint
+async fn get_metrics(State(state): State<AppState>) -> impl IntoResponse {
+    (
+        [(
+            axum::http::header::CONTENT_TYPE,
+            "text/plain; version=0.0.4",
+        )],
+        state.orchestrator.metrics().render(),
+    )
+}
+
+/// Per-handler call volume, success rate, and latency summary
+async fn get_stats(State(state): State<AppState>) -> impl IntoResponse {
+    Json(serde_json::json!({
+        "handlers": state.orchestrator.metrics().handler_stats(),
     }))
 }
 
@@ -163,6 +445,8 @@ async fn get_capacity(State(state): State<AppState>) -> impl IntoResponse {
     let mut available_cpus = 0.0;
     let mut available_gpus = 0.0;
     let mut available_memory_gb = 0.0;
+    let mut total_gpu_memory_gb = 0.0;
+    let mut available_gpu_memory_gb = 0.0;
 
     for worker_handle in workers_guard.iter() {
         let worker = &worker_handle.worker;
@@ -175,19 +459,30 @@ async fn get_capacity(State(state): State<AppState>) -> impl IntoResponse {
                 "cpus": worker.capabilities.num_cpus,
                 "gpus": worker.capabilities.num_gpus,
                 "memory_gb": worker.capabilities.memory_gb,
+                "gpu_memory_gb": worker.capabilities.gpu_memory_gb,
             },
             "allocated": {
                 "cpus": worker.allocation.allocated_cpus,
                 "gpus": worker.allocation.allocated_gpus,
                 "memory_gb": worker.allocation.allocated_memory_gb,
+                "gpu_memory_gb": worker.allocation.allocated_gpu_memory_gb,
             },
             "available": {
                 "cpus": avail_cpu,
                 "gpus": avail_gpu,
                 "memory_gb": avail_mem,
+                "gpu_memory_gb": worker.available_gpu_memory_gb(),
             },
         }));
 
+        if let (Some(total), Some(avail)) = (
+            worker.capabilities.gpu_memory_gb,
+            worker.available_gpu_memory_gb(),
+        ) {
+            total_gpu_memory_gb += total;
+            available_gpu_memory_gb += avail;
+        }
+
         total_cpus += worker.capabilities.num_cpus;
         total_gpus += worker.capabilities.num_gpus;
         total_memory_gb += worker.capabilities.memory_gb;
@@ -201,16 +496,19 @@ async fn get_capacity(State(state): State<AppState>) -> impl IntoResponse {
             "cpus": total_cpus,
             "gpus": total_gpus,
             "memory_gb": total_memory_gb,
+            "gpu_memory_gb": total_gpu_memory_gb,
         },
         "available": {
             "cpus": available_cpus,
             "gpus": available_gpus,
             "memory_gb": available_memory_gb,
+            "gpu_memory_gb": available_gpu_memory_gb,
         },
         "allocated": {
             "cpus": total_cpus - available_cpus,
             "gpus": total_gpus - available_gpus,
             "memory_gb": total_memory_gb - available_memory_gb,
+            "gpu_memory_gb": total_gpu_memory_gb - available_gpu_memory_gb,
         },
         "workers": worker_capacities,
     }))
@@ -219,247 +517,836 @@ async fn get_capacity(State(state): State<AppState>) -> impl IntoResponse {
 /// Execute a task with no request body (for GET/DELETE requests)
 async fn execute_task_no_body(
     State(state): State<AppState>,
-    Extension(metadata): Extension<RouteMetadata>,
-) -> Result<Json<TaskResponse>, AppError> {
+    Extension(mut metadata): Extension<RouteMetadata>,
+    path_params: Option<Path<HashMap<String, String>>>,
+    Query(query_params): Query<HashMap<String, String>>,
+    headers: HeaderMap,
+    client: Option<ConnectInfo<SocketAddr>>,
+) -> Result<Response, AppError> {
     info!("Received request for handler: {}", metadata.handler_name);
+    apply_priority_header(&mut metadata, &headers);
+    let peer = client.map(|ConnectInfo(addr)| addr.ip());
+    metadata.tenant = tenant::tenant_id(
+        state
+            .orchestrator
+            .config()
+            .orchestrator
+            .http
+            .tenants
+            .as_ref(),
+        peer,
+        &headers,
+    );
+    metadata.gpu_affinity = gpu_affinity_from_headers(&headers)?;
 
-    let start = std::time::Instant::now();
+    let start = Instant::now();
 
-    // Find worker with sufficient resources
-    let worker_idx = state
-        .orchestrator
-        .find_worker_with_resources(&metadata.resources)
-        .await
-        .ok_or_else(|| AppError::InsufficientResources(format!(
-            "No workers available with required resources: cpus={}, gpus={}, memory={}GB",
-            metadata.resources.num_cpus,
-            metadata.resources.num_gpus,
-            metadata.resources.memory_gb
-        )))?;
+    let path_params = path_params.map(|Path(p)| p).unwrap_or_default();
+    validate_request(&metadata, &path_params, &query_params, None)?;
+    metadata.affinity =
+        affinity_from_request(&metadata, &headers, &path_params, &query_params, None);
 
-    let workers = state.orchestrator.workers();
-    let mut workers_guard = workers.write().await;
-    let worker = &mut workers_guard[worker_idx];
+    // For GET/DELETE, send empty map as args
+    let args = rmpv::Value::Map(vec![]);
 
-    info!(
-        "Routing handler {} to worker {} (index {}) with resources: cpus={}, gpus={}, mem={}GB",
-        metadata.handler_name,
-        worker.worker.id,
-        worker_idx,
-        metadata.resources.num_cpus,
-        metadata.resources.num_gpus,
-        metadata.resources.memory_gb
+    run_task(&state, &metadata, args, &headers, peer, start).await
+}
+
+/// Execute a task with JSON request body (for POST/PUT/PATCH requests)
+async fn execute_task_with_body(
+    State(state): State<AppState>,
+    Extension(mut metadata): Extension<RouteMetadata>,
+    path_params: Option<Path<HashMap<String, String>>>,
+    Query(query_params): Query<HashMap<String, String>>,
+    headers: HeaderMap,
+    client: Option<ConnectInfo<SocketAddr>>,
+    Json(request): Json<TaskRequest>,
+) -> Result<Response, AppError> {
+    info!("Received request for handler: {}", metadata.handler_name);
+    apply_priority_header(&mut metadata, &headers);
+    let peer = client.map(|ConnectInfo(addr)| addr.ip());
+    metadata.tenant = tenant::tenant_id(
+        state
+            .orchestrator
+            .config()
+            .orchestrator
+            .http
+            .tenants
+            .as_ref(),
+        peer,
+        &headers,
     );
+    metadata.gpu_affinity = gpu_affinity_from_headers(&headers)?;
 
-    // Allocate resources
-    worker.worker.allocation.allocate(&metadata.resources);
+    let start = Instant::now();
 
-    // For GET/DELETE, send empty map as args
-    let args = rmpv::Value::Map(vec![]);
+    let path_params = path_params.map(|Path(p)| p).unwrap_or_default();
+    validate_request(&metadata, &path_params, &query_params, Some(&request.args))?;
+    metadata.affinity = affinity_from_request(
+        &metadata,
+        &headers,
+        &path_params,
+        &query_params,
+        Some(&request.args),
+    );
 
-    // Create task assignment message
-    let task_id = uuid::Uuid::new_v4().to_string();
-    let msg = Message::TaskAssignment {
-        task_id: task_id.clone(),
-        function_name: metadata.handler_name.clone(),
-        args,
-        resources: metadata.resources.clone(),
+    // Validated against the API contract above, then reshaped for the handler
+    let args = match &metadata.args_template {
+        Some(template) => json_to_msgpack_value(&template.apply(&request.args)),
+        None => json_to_msgpack_value(&request.args),
+    }
+    .map_err(AppError::SerializationError)?;
+
+    run_task(&state, &metadata, args, &headers, peer, start).await
+}
+
+/// Dispatch a task, record its outcome, and build the HTTP response
+async fn run_task(
+    state: &AppState,
+    metadata: &RouteMetadata,
+    args: rmpv::Value,
+    request_headers: &HeaderMap,
+    peer: Option<IpAddr>,
+    start: Instant,
+) -> Result<Response, AppError> {
+    // Don't spend a worker round-trip on a handler no worker has
+    if !state
+        .available_handlers
+        .is_available(&metadata.handler_name)
+    {
+        return Err(AppError::HandlerNotAvailable(metadata.handler_name.clone()));
+    }
+
+    // Held until the response is built; an async task's stored result keeps
+    // its ID taken from then on
+    let (task_id, _claim) = match task_id_from_headers(request_headers) {
+        Some(task_id) => {
+            let claim = claim_task_id(state, &task_id).await?;
+            (task_id, Some(claim))
+        }
+        None => (uuid::Uuid::new_v4().to_string(), None),
     };
+    let span = info_span!(
+        "task",
+        task_id = %task_id,
+        handler = %metadata.handler_name,
+        tenant = tracing::field::Empty
+    );
+    if let Some(tenant) = &metadata.tenant {
+        span.record("tenant", tenant.as_str());
+    }
 
-    // Send task to worker
-    worker
-        .send(&msg)
-        .await
-        .map_err(|e| {
-            // Deallocate on error
-            worker.worker.allocation.deallocate(&metadata.resources);
-            AppError::WorkerCommunicationError(e.to_string())
-        })?;
-
-    // Mark worker as busy
-    worker.worker.state = crate::worker::WorkerState::Busy;
-
-    // Wait for result
-    let result_msg = worker
-        .recv()
-        .await
-        .map_err(|e| {
-            // Deallocate on error
-            worker.worker.allocation.deallocate(&metadata.resources);
-            worker.worker.state = crate::worker::WorkerState::Idle;
-            AppError::WorkerCommunicationError(e.to_string())
-        })?;
+    let error_config = state
+        .orchestrator
+        .config()
+        .orchestrator
+        .http
+        .error_details
+        .as_ref();
+    let error_detail = ErrorDetail::for_request(error_config, peer, request_headers);
 
-    // Deallocate resources after task completion
-    worker.worker.allocation.deallocate(&metadata.resources);
+    async move {
+        let respond_async = async_tasks::wants_async(request_headers);
+        let outcome = serve_task(
+            state,
+            metadata,
+            args,
+            &task_id,
+            error_detail,
+            respond_async,
+            start,
+        )
+        .await?;
+        let (task_response, cache_hit) = match outcome {
+            TaskOutcome::Accepted { result_url } => {
+                return Ok(async_tasks::accepted_response(&task_id, &result_url));
+            }
+            TaskOutcome::Completed {
+                task_response,
+                cache_hit,
+            } => (task_response, cache_hit),
+        };
+        let queue_wait_ms = task_response.queue_wait_ms.unwrap_or_default();
 
-    // Increment task counter
-    worker.worker.increment_task_count();
+        let mut response = encoding::encode_task_response(
+            &task_response,
+            request_headers,
+            metadata.default_result_content_type.as_ref(),
+        )?;
+        let headers = response.headers_mut();
+        headers.insert(QUEUE_WAIT_HEADER, HeaderValue::from(queue_wait_ms));
+        if let Some(cache_hit) = cache_hit {
+            let outcome = if cache_hit { "hit" } else { "miss" };
+            headers.insert(CACHE_HEADER, HeaderValue::from_static(outcome));
+        }
+        if let Ok(task_id) = HeaderValue::from_str(&task_id) {
+            headers.insert(TASK_ID_HEADER, task_id);
+        }
+        Ok(response)
+    }
+    .instrument(span)
+    .await
+}
 
-    // Mark worker as idle again
-    worker.worker.state = crate::worker::WorkerState::Idle;
+/// How a task was served
+enum TaskOutcome {
+    /// Accepted to run in the background, its result polled at `result_url`
+    Accepted { result_url: String },
+    /// Finished; on routes with a result cache, whether it came from there
+    Completed {
+        task_response: TaskResponse,
+        cache_hit: Option<bool>,
+    },
+}
 
-    let execution_time = start.elapsed().as_millis() as u64;
+/// Serve a task as its route does: in the background when async handling is
+/// asked for (and configured), from the result cache when it holds the
+/// result, otherwise dispatched once backpressure admits it, shared with
+/// identical tasks on coalescing routes
+async fn serve_task(
+    state: &AppState,
+    metadata: &RouteMetadata,
+    args: rmpv::Value,
+    task_id: &str,
+    error_detail: ErrorDetail,
+    respond_async: bool,
+    start: Instant,
+) -> Result<TaskOutcome, AppError> {
+    log_args_preview(state, metadata, &args, task_id);
 
-    // Process result
-    match result_msg {
-        Message::TaskResult {
-            success,
-            result: result_value,
-            ..
-        } => {
-            if success {
-                let result = msgpack_value_to_json(&result_value)
-                    .map_err(|e| AppError::DeserializationError(e))?;
+    if let Some(store) = state.result_store.as_ref().filter(|_| respond_async) {
+        let result_url = async_tasks::submit(
+            state,
+            Arc::clone(store),
+            metadata,
+            args,
+            task_id.to_string(),
+            error_detail,
+            start,
+        )
+        .await?;
+        return Ok(TaskOutcome::Accepted { result_url });
+    }
 
-                Ok(Json(TaskResponse {
-                    success: true,
-                    result: Some(result),
-                    error: None,
-                    worker_id: Some(worker.worker.id.clone()),
-                    execution_time_ms: Some(execution_time),
-                }))
-            } else {
-                let error = msgpack_value_to_json(&result_value)
-                    .map_err(|e| AppError::DeserializationError(e))?;
+    let cache_key = metadata.cache_ttl.and_then(|_| {
+        result_cache::cache_key(&metadata.handler_name, metadata.tenant.as_deref(), &args)
+    });
+    let cached = cache_key
+        .as_ref()
+        .and_then(|key| state.result_cache.get(key));
+    let cached_hit = cached.is_some();
+    if cache_key.is_some() {
+        state
+            .orchestrator
+            .metrics()
+            .observe_cache_lookup(&metadata.handler_name, cached_hit);
+    }
 
-                Ok(Json(TaskResponse {
-                    success: false,
-                    result: None,
-                    error: Some(error.to_string()),
-                    worker_id: Some(worker.worker.id.clone()),
-                    execution_time_ms: Some(execution_time),
-                }))
+    let mut task_response = match cached {
+        // Served without waiting on a worker, and without claiming the
+        // worker and run time of the task that produced it
+        Some(cached) => TaskResponse {
+            worker_id: None,
+            execution_time_ms: None,
+            queue_wait_ms: Some(0),
+            cached: true,
+            ..cached
+        },
+        None => {
+            let _admitted = backpressure::admit(state)?;
+            let coalesce_key = metadata
+                .coalesce
+                .then(|| FlightKey::new(&metadata.handler_name, metadata.tenant.as_deref(), &args))
+                .flatten();
+            let task_id = task_id.to_string();
+            let task_response = match coalesce_key {
+                Some(key) => {
+                    let task = complete_task(state, metadata, args, task_id, start);
+                    state.coalescer.run(key, task).await?
+                }
+                None => complete_task(state, metadata, args, task_id, start).await?,
+            };
+            if let (Some(key), Some(ttl)) = (cache_key, metadata.cache_ttl) {
+                if task_response.success {
+                    state.result_cache.put(key, task_response.clone(), ttl);
+                }
             }
+            task_response
         }
-        _ => Err(AppError::UnexpectedResponse),
+    };
+    error_detail.apply(&mut task_response);
+    Ok(TaskOutcome::Completed {
+        task_response,
+        cache_hit: cache_key.is_some().then_some(cached_hit),
+    })
+}
+
+/// Log the task's args at debug level with secret fields redacted, if configured
+fn log_args_preview(state: &AppState, metadata: &RouteMetadata, args: &rmpv::Value, task_id: &str) {
+    let Some(config) = &state.orchestrator.config().orchestrator.tasks.args_preview else {
+        return;
+    };
+    if !tracing::enabled!(tracing::Level::DEBUG) {
+        return;
     }
+
+    let non_finite = state
+        .orchestrator
+        .config()
+        .orchestrator
+        .tasks
+        .non_finite_floats;
+    let preview = match msgpack_value_to_json(args, non_finite) {
+        Ok(args) => redact::preview(&args, &config.redact_fields, config.max_chars),
+        Err(e) => format!("<unrenderable args: {}>", e),
+    };
+    debug!(task_id = %task_id, handler = %metadata.handler_name, args = %preview, "Task args");
 }
 
-/// Execute a task with JSON request body (for POST/PUT/PATCH requests)
-async fn execute_task_with_body(
-    State(state): State<AppState>,
-    Extension(metadata): Extension<RouteMetadata>,
-    Json(request): Json<TaskRequest>,
-) -> Result<Json<TaskResponse>, AppError> {
-    info!("Received request for handler: {}", metadata.handler_name);
+/// Dispatch a task, record its outcome, and apply result hooks
+async fn complete_task(
+    state: &AppState,
+    metadata: &RouteMetadata,
+    args: rmpv::Value,
+    task_id: String,
+    start: Instant,
+) -> Result<TaskResponse, AppError> {
+    // Kept for the dead-letter record, and to run the task again if it's
+    // preempted; dispatch consumes the args
+    let dead_letter_args = state.dead_letters.is_some().then(|| args.clone());
+    let preemption = state
+        .orchestrator
+        .config()
+        .orchestrator
+        .tasks
+        .preemption
+        .is_some();
+
+    let mut args = Some(args);
+    let mut attempts = 0;
+    let result = loop {
+        attempts += 1;
+        let task_args = args.take().expect("args kept for every dispatch");
+        if preemption {
+            args = Some(task_args.clone());
+        }
+        let dispatch = async {
+            // Wait for the handler's running tasks to drop below its cap; the
+            // wait counts against the task's timeout like any other queueing
+            let _running = match metadata.max_concurrency {
+                Some(limit) => Some(wait_for_handler_slot(state, metadata, limit, start).await?),
+                None => None,
+            };
+            match metadata.hedge_after {
+                Some(delay) => {
+                    hedge::dispatch_hedged(state, metadata, task_args, &task_id, start, delay).await
+                }
+                None => dispatch_task(state, metadata, task_args, task_id.clone(), start).await,
+            }
+        };
+        match state
+            .running_tasks
+            .run(&task_id, metadata.priority, start, dispatch)
+            .await
+        {
+            Err(AppError::TaskPreempted(_)) => {
+                info!(task_id = %task_id, "Task preempted, queued again")
+            }
+            result => break result,
+        }
+    };
+    if let Some(args) = &dead_letter_args {
+        dead_letters::record_failure(state, metadata, &task_id, args, attempts, &result).await;
+    }
+
+    let success = matches!(&result, Ok(task_response) if task_response.success);
+    state
+        .orchestrator
+        .metrics()
+        .observe_task(&metadata.handler_name, success, start.elapsed());
+    if let Some(tenant) = &metadata.tenant {
+        state
+            .orchestrator
+            .metrics()
+            .observe_tenant_task(tenant, success, start.elapsed());
+    }
 
-    let start = std::time::Instant::now();
+    let mut task_response = result?;
+    if let Some(result) = task_response.result.as_mut() {
+        state.result_hooks.apply(metadata, result);
+
+        // Enforce the API contract last, so nothing undeclared reaches the client
+        if let Some(schema) = &metadata.response_schema {
+            let removed = schema.filter(result);
+            if !removed.is_empty() {
+                warn!(
+                    "Handler {} returned fields not in its response schema, removed: {}",
+                    metadata.handler_name,
+                    removed.join(", ")
+                );
+            }
+        }
+    }
+    Ok(task_response)
+}
 
-    // Find worker with sufficient resources
-    let worker_idx = state
+/// A turn to run one of a capped handler's tasks, within the task's timeout
+async fn wait_for_handler_slot(
+    state: &AppState,
+    metadata: &RouteMetadata,
+    limit: usize,
+    start: Instant,
+) -> Result<OwnedSemaphorePermit, AppError> {
+    let permit = state.handler_limits.acquire(&metadata.handler_name, limit);
+    let timeout_secs = state
         .orchestrator
-        .find_worker_with_resources(&metadata.resources)
-        .await
-        .ok_or_else(|| AppError::InsufficientResources(format!(
-            "No workers available with required resources: cpus={}, gpus={}, memory={}GB",
+        .config()
+        .orchestrator
+        .tasks
+        .default_timeout_secs;
+    let deadline = (timeout_secs > 0).then(|| start + Duration::from_secs(timeout_secs));
+    until_deadline(deadline, timeout_secs, permit).await
+}
+
+/// Wait for `wait`, but no later than the task's deadline (None = no timeout)
+async fn until_deadline<F: std::future::Future>(
+    deadline: Option<Instant>,
+    timeout_secs: u64,
+    wait: F,
+) -> Result<F::Output, AppError> {
+    match deadline {
+        Some(deadline) => tokio::time::timeout_at(deadline.into(), wait)
+            .await
+            .map_err(|_| AppError::TaskTimeout(timeout_secs)),
+        None => Ok(wait.await),
+    }
+}
+
+/// A worker in the task's placement with capacity for it, falling back to
+/// any GPU when configured for tasks bound to one
+async fn select_worker(
+    state: &AppState,
+    metadata: &RouteMetadata,
+    placement: &Placement<'_>,
+) -> Option<WorkerSelection> {
+    let overflow_pool = metadata.overflow_pool.as_deref();
+    let selection = state
+        .orchestrator
+        .find_worker_with_resources(&metadata.resources, overflow_pool, placement)
+        .await;
+    if selection.is_none()
+        && placement.gpu_device.is_some()
+        && state
+            .orchestrator
+            .config()
+            .orchestrator
+            .tasks
+            .gpu_affinity_fallback
+            == GpuAffinityFallback::Any
+    {
+        let any_device = Placement {
+            gpu_device: None,
+            ..*placement
+        };
+        return state
+            .orchestrator
+            .find_worker_with_resources(&metadata.resources, overflow_pool, &any_device)
+            .await;
+    }
+    selection
+}
+
+/// The pool dedicated to the task's tenant on this route, if any
+fn tenant_pool(metadata: &RouteMetadata) -> Option<&str> {
+    metadata
+        .tenant
+        .as_ref()
+        .and_then(|tenant| metadata.tenant_pools.get(tenant))
+        .map(String::as_str)
+}
+
+/// Where a task may run: tenants with a dedicated pool on this route run only
+/// there, other tasks only in the route's pinned pool, if any, and otherwise
+/// anywhere but tenants' dedicated pools
+fn task_placement<'a>(state: &'a AppState, metadata: &'a RouteMetadata) -> Placement<'a> {
+    let pool = tenant_pool(metadata).or(metadata.pool.as_deref());
+    Placement {
+        pool,
+        excluded_pools: pool.is_none().then_some(&*state.dedicated_pools),
+        gpu_device: metadata.gpu_affinity,
+        affinity: metadata.affinity.as_deref(),
+    }
+}
+
+/// Route a task to a worker with sufficient resources and wait for its result
+async fn dispatch_task(
+    state: &AppState,
+    metadata: &RouteMetadata,
+    args: rmpv::Value,
+    task_id: String,
+    start: Instant,
+) -> Result<TaskResponse, AppError> {
+    let tenant_pool = tenant_pool(metadata);
+    let placement = task_placement(state, metadata);
+    let no_capacity = |reason: String| {
+        let pool = match (tenant_pool, placement.pool) {
+            (Some(pool), _) => format!(" in tenant pool {}", pool),
+            (None, Some(pool)) => format!(" in pool {}", pool),
+            (None, None) => String::new(),
+        };
+        AppError::InsufficientResources(format!(
+            "No workers available{}{} with required resources: cpus={}, gpus={}, memory={}GB{}",
+            pool,
+            placement
+                .gpu_device
+                .map(|device| format!(" on GPU {}", device))
+                .unwrap_or_default(),
             metadata.resources.num_cpus,
             metadata.resources.num_gpus,
-            metadata.resources.memory_gb
-        )))?;
+            metadata.resources.memory_gb,
+            reason
+        ))
+    };
 
-    let workers = state.orchestrator.workers();
-    let mut workers_guard = workers.write().await;
-    let worker = &mut workers_guard[worker_idx];
+    // Allocate resources (an overflow worker runs the task without GPUs)
+    let resources_for = |pass: SelectionPass| {
+        if pass == SelectionPass::Overflow {
+            metadata.resources.without_gpus()
+        } else {
+            metadata.resources.clone()
+        }
+    };
+
+    // Wait behind higher-priority tasks for the right to pick a worker. A task
+    // no worker can take parks while running tasks hold the capacity it needs,
+    // so tasks that fit elsewhere keep flowing, and rejoins in its place when
+    // one finishes; it waits no longer than its timeout. One that preempted a
+    // task keeps the slot until the freed worker is its. With nothing running
+    // to wait for, it waits for capacity in the pending queue (when configured).
+    let timeout_secs = state
+        .orchestrator
+        .config()
+        .orchestrator
+        .tasks
+        .default_timeout_secs;
+    let deadline = (timeout_secs > 0).then(|| start + Duration::from_secs(timeout_secs));
+    let mut pending = None;
+    // Counts toward the demand on the pools that could run it while it
+    // waits, for the autoscaler
+    let mut demand = None;
+    let wait_for_capacity = || {
+        state
+            .orchestrator
+            .wait_for_capacity(&metadata.resources, &placement)
+    };
+    let (selection, mut lease) = loop {
+        let mut queue_slot = until_deadline(
+            deadline,
+            timeout_secs,
+            state.orchestrator.task_queue().acquire(metadata.priority),
+        )
+        .await?;
+        let leased = loop {
+            let released = state.orchestrator.leases().released();
+            tokio::pin!(released);
+            released.as_mut().enable();
+            let selection = select_worker(state, metadata, &placement).await;
+            let no_worker = selection.is_none();
+            if let Some(selection) = selection {
+                let resources = resources_for(selection.pass);
+                if let Some(lease) = state
+                    .orchestrator
+                    .lease_worker(selection.index, &task_id, resources)
+                    .await
+                {
+                    break Some((selection, lease));
+                }
+            }
+            if state.orchestrator.leases().active() == 0 {
+                break None;
+            }
+            demand.get_or_insert_with(wait_for_capacity);
+            // No worker can take it: it may stop a lower-priority task for its
+            // worker, and keeps the slot so the worker comes to it once freed
+            let preempted = no_worker
+                && preempt::make_room(state, metadata, &metadata.resources, &placement)
+                    .await
+                    .is_some();
+            queue_slot = if preempted {
+                until_deadline(deadline, timeout_secs, released).await?;
+                queue_slot.yield_to_waiters().await
+            } else {
+                until_deadline(deadline, timeout_secs, queue_slot.park_until(released)).await?
+            };
+        };
+        // With a worker taken, the next task may pick one
+        drop(queue_slot);
+        if let Some(leased) = leased {
+            break leased;
+        }
+
+        let Some(queue) = state.orchestrator.pending_tasks() else {
+            return Err(no_capacity(String::new()));
+        };
+        let ticket = match pending.take() {
+            Some(ticket) => ticket,
+            None => queue
+                .join()
+                .ok_or_else(|| no_capacity(" (pending queue full)".to_string()))?,
+        };
+        demand.get_or_insert_with(wait_for_capacity);
+        if !ticket.next_attempt().await {
+            return Err(no_capacity(format!(
+                " after waiting {}s",
+                queue.max_wait().as_secs()
+            )));
+        }
+        pending = Some(ticket);
+    };
+    // Leaving the pending queue lets the next waiter try
+    drop(pending);
+    drop(demand);
+    // Everything from the request's arrival up to acquiring the worker
+    // counts as queue wait
+    let queue_wait = start.elapsed();
+    let resources = resources_for(selection.pass);
+    state
+        .running_tasks
+        .leased(&task_id, lease.worker_id(), &resources, queue_wait);
+    state
+        .orchestrator
+        .metrics()
+        .observe_queue_wait(&metadata.handler_name, queue_wait);
 
+    let (headroom_cpus, headroom_gpus, headroom_memory_gb) = selection.headroom;
     info!(
-        "Routing handler {} to worker {} (index {}) with resources: cpus={}, gpus={}, mem={}GB",
+        task_id = %task_id,
+        handler = %metadata.handler_name,
+        tenant = metadata.tenant.as_deref(),
+        gpu_affinity = metadata.gpu_affinity,
+        affinity = metadata.affinity.as_deref(),
+        worker_id = %lease.worker_id(),
+        pool = pool_name(lease.worker_id()),
+        selection_pass = selection.pass.as_str(),
+        headroom_cpus,
+        headroom_gpus,
+        headroom_memory_gb,
+        queue_wait_ms = queue_wait.as_millis() as u64,
+        "Routing handler {} to worker {} (index {}, {} pass, queued {}ms) with resources: cpus={}, gpus={}, mem={}GB",
         metadata.handler_name,
-        worker.worker.id,
-        worker_idx,
+        lease.worker_id(),
+        selection.index,
+        selection.pass.as_str(),
+        queue_wait.as_millis(),
         metadata.resources.num_cpus,
         metadata.resources.num_gpus,
         metadata.resources.memory_gb
     );
 
-    // Allocate resources
-    worker.worker.allocation.allocate(&metadata.resources);
-
-    // Convert JSON to msgpack Value
-    let args = json_to_msgpack_value(&request.args)
-        .map_err(|e| {
-            // Deallocate on error
-            worker.worker.allocation.deallocate(&metadata.resources);
-            AppError::SerializationError(e.to_string())
-        })?;
+    // Remaining time budget; queue wait counts against the task's timeout
+    let remaining =
+        (timeout_secs > 0).then(|| Duration::from_secs(timeout_secs).saturating_sub(queue_wait));
+    if remaining == Some(Duration::ZERO) {
+        return Err(AppError::TaskTimeout(timeout_secs));
+    }
 
     // Create task assignment message
-    let task_id = uuid::Uuid::new_v4().to_string();
     let msg = Message::TaskAssignment {
         task_id: task_id.clone(),
         function_name: metadata.handler_name.clone(),
         args,
-        resources: metadata.resources.clone(),
+        resources: resources.clone(),
+        deadline_ms_remaining: remaining.map(|r| r.as_millis() as u64),
+        priority: metadata.priority,
     };
 
-    // Send task to worker
-    worker
-        .send(&msg)
-        .await
-        .map_err(|e| {
-            // Deallocate on error
-            worker.worker.allocation.deallocate(&metadata.resources);
-            AppError::WorkerCommunicationError(e.to_string())
-        })?;
-
-    // Mark worker as busy
-    worker.worker.state = crate::worker::WorkerState::Busy;
-
-    // Wait for result
-    let result_msg = worker
-        .recv()
-        .await
-        .map_err(|e| {
-            // Deallocate on error
-            worker.worker.allocation.deallocate(&metadata.resources);
-            worker.worker.state = crate::worker::WorkerState::Idle;
-            AppError::WorkerCommunicationError(e.to_string())
-        })?;
-
-    // Deallocate resources after task completion
-    worker.worker.allocation.deallocate(&metadata.resources);
+    // Exchange with the worker over its own connection, without the workers
+    // lock, so tasks on other workers run alongside. Results for earlier
+    // tasks that timed out arrive late on the same socket and are discarded.
+    // On any early return the lease is released.
+    let exchange = async {
+        lease.send(&msg).await?;
+        loop {
+            match lease.recv().await? {
+                Message::TaskResult { task_id: id, .. } if id != task_id => {
+                    debug!("Discarding late result for timed-out task {}", id);
+                }
+                other => return Ok(other),
+            }
+        }
+    };
+    let received = match remaining {
+        Some(remaining) => tokio::time::timeout(remaining, exchange)
+            .await
+            .map_err(|_| AppError::TaskTimeout(timeout_secs)),
+        None => Ok(exchange.await),
+    }
+    .and_then(|result: Result<Message, Box<dyn std::error::Error>>| {
+        result.map_err(|e| AppError::WorkerCommunicationError(e.to_string()))
+    });
 
-    // Increment task counter
-    worker.worker.increment_task_count();
+    // Dropping the lease tells the worker to cancel the task; one that can't
+    // stop part-way is taken out of service so it isn't handed more work
+    match &received {
+        Err(AppError::TaskTimeout(_)) => {
+            let tasks = &state.orchestrator.config().orchestrator.tasks;
+            warn!(task_id = %task_id, worker_id = %lease.worker_id(), "Task timed out after {}s", timeout_secs);
+            if tasks.on_timeout == TimeoutAction::Recycle {
+                lease.mark_stuck();
+            }
+        }
+        // The worker crashed or its connection broke: it is replaced rather
+        // than handed the next task
+        Err(AppError::WorkerCommunicationError(e)) => {
+            warn!(task_id = %task_id, worker_id = %lease.worker_id(), "Lost worker mid-task: {}", e);
+            lease.mark_stuck();
+        }
+        _ => {}
+    }
+    let result_msg = received?;
 
-    // Mark worker as idle again
-    worker.worker.state = crate::worker::WorkerState::Idle;
+    // Deallocate resources, count the task, and mark the worker idle again
+    let worker_id = lease.worker_id().to_string();
+    lease.finish().await;
+    if let Some(pending) = state.orchestrator.pending_tasks() {
+        pending.released();
+    }
 
     let execution_time = start.elapsed().as_millis() as u64;
+    let queue_wait_ms = queue_wait.as_millis() as u64;
 
     // Process result
-    match result_msg {
+    let task_response = match result_msg {
         Message::TaskResult {
             success,
             result: result_value,
             ..
         } => {
             if success {
-                let result = msgpack_value_to_json(&result_value)
-                    .map_err(|e| AppError::DeserializationError(e))?;
+                let non_finite = state
+                    .orchestrator
+                    .config()
+                    .orchestrator
+                    .tasks
+                    .non_finite_floats;
+                let result = match msgpack_value_to_json(&result_value, non_finite) {
+                    Ok(result) => Some(result),
+                    Err(e) => {
+                        let policy = state
+                            .orchestrator
+                            .config()
+                            .orchestrator
+                            .tasks
+                            .unconvertible_results;
+                        unconvertible_result(policy, &metadata.handler_name, &result_value, e)?
+                    }
+                };
 
-                Ok(Json(TaskResponse {
+                TaskResponse {
                     success: true,
-                    result: Some(result),
+                    result,
                     error: None,
-                    worker_id: Some(worker.worker.id.clone()),
+                    worker_id: Some(worker_id.clone()),
                     execution_time_ms: Some(execution_time),
-                }))
+                    queue_wait_ms: Some(queue_wait_ms),
+                    traceback: None,
+                    cached: false,
+                    raw_result: Some(result_value),
+                }
             } else {
-                let error = msgpack_value_to_json(&result_value)
-                    .map_err(|e| AppError::DeserializationError(e))?;
+                let non_finite = state
+                    .orchestrator
+                    .config()
+                    .orchestrator
+                    .tasks
+                    .non_finite_floats;
+                let mut error = msgpack_value_to_json(&result_value, non_finite)
+                    .map_err(AppError::DeserializationError)?;
+                // The traceback is split out so it can be withheld from untrusted clients
+                let traceback = error
+                    .as_object_mut()
+                    .and_then(|error| error.remove("traceback"))
+                    .and_then(|traceback| traceback.as_str().map(str::to_string));
+                warn!(
+                    task_id = %task_id,
+                    handler = %metadata.handler_name,
+                    traceback = traceback.as_deref().unwrap_or_default(),
+                    "Task failed: {}",
+                    error
+                );
 
-                Ok(Json(TaskResponse {
+                TaskResponse {
                     success: false,
                     result: None,
                     error: Some(error.to_string()),
-                    worker_id: Some(worker.worker.id.clone()),
+                    worker_id: Some(worker_id.clone()),
                     execution_time_ms: Some(execution_time),
-                }))
+                    queue_wait_ms: Some(queue_wait_ms),
+                    traceback,
+                    cached: false,
+                    raw_result: None,
+                }
             }
         }
-        _ => Err(AppError::UnexpectedResponse),
+        _ => return Err(AppError::UnexpectedResponse),
+    };
+
+    Ok(task_response)
+}
+
+/// Apply `policy` to a successful result that failed JSON conversion with
+/// `error`, returning its JSON form, or None when it's served only as msgpack
+fn unconvertible_result(
+    policy: UnconvertibleResultPolicy,
+    handler_name: &str,
+    value: &rmpv::Value,
+    error: String,
+) -> Result<Option<serde_json::Value>, AppError> {
+    warn!(
+        "Result of handler {} can't be converted to JSON ({}); unconvertible_results: {:?}",
+        handler_name, error, policy
+    );
+    match policy {
+        UnconvertibleResultPolicy::Error => Err(AppError::UnconvertibleResult(format!(
+            "handler {} returned a result that can't be converted to JSON: {}",
+            handler_name, error
+        ))),
+        UnconvertibleResultPolicy::Msgpack => Ok(None),
+        UnconvertibleResultPolicy::Base64 => {
+            let mut bytes = Vec::new();
+            rmpv::encode::write_value(&mut bytes, value)
+                .map_err(|e| AppError::SerializationError(e.to_string()))?;
+            let encoded = base64::engine::general_purpose::STANDARD.encode(bytes);
+            Ok(Some(serde_json::json!({ "$msgpack": encoded })))
+        }
+    }
+}
+
+/// Fallback handler: serves routes patched in at runtime, then the ASGI app if enabled
+async fn fallback_handler(
+    State(state): State<AppState>,
+    req: Request,
+) -> Result<Response, AppError> {
+    let patched = state
+        .patched_routes
+        .as_ref()
+        .and_then(|patched| patched.router_for(req.uri().path()));
+    if let Some(patched) = patched {
+        return Ok(patched
+            .with_state(state)
+            .oneshot(req)
+            .await
+            .unwrap_or_else(|e| match e {}));
+    }
+
+    if state
+        .asgi_config
+        .as_ref()
+        .is_some_and(|config| config.enabled)
+    {
+        return asgi_fallback_handler(State(state), req).await;
     }
+    Err(AppError::RouteNotFound(req.uri().path().to_string()))
 }
 
 /// Fallback handler that checks route lookup and proxies to ASGI if not found
@@ -480,12 +1367,23 @@ async fn asgi_fallback_handler(
     let asgi_config = state
         .asgi_config
         .as_ref()
-        .ok_or_else(|| AppError::AsgiNotConfigured)?;
+        .ok_or(AppError::AsgiNotConfigured)?;
+
+    // Reject rather than queue when saturated, so bodies aren't buffered for waiting requests.
+    // The permit is held until the proxied response has been fully read.
+    let _permit = match &state.asgi_permits {
+        Some(permits) => Some(
+            Arc::clone(permits)
+                .try_acquire_owned()
+                .map_err(|_| AppError::AsgiSaturated)?,
+        ),
+        None => None,
+    };
 
     let client = state
         .asgi_client
         .as_ref()
-        .ok_or_else(|| AppError::AsgiNotConfigured)?;
+        .ok_or(AppError::AsgiNotConfigured)?;
 
     // Determine target URL based on mode
     let target_base = match asgi_config.mode {
@@ -508,9 +1406,29 @@ async fn asgi_fallback_handler(
     // Build target URL
     let target_url = format!("{}{}{}", target_base, path, query);
 
+    // Only connect to allowlisted hosts, whatever the target was derived from
+    let parsed = reqwest::Url::parse(&target_url).map_err(|e| {
+        AppError::AsgiConfigError(format!("invalid ASGI target {}: {}", target_url, e))
+    })?;
+    let (host, port) = (
+        parsed.host_str().unwrap_or_default(),
+        parsed.port_or_known_default().unwrap_or_default(),
+    );
+    if !asgi_config.target_allowed(host, port) {
+        warn!(
+            "Refusing to proxy {} to {}:{} (not in allowed_targets)",
+            path, host, port
+        );
+        return Err(AppError::ProxyTargetNotAllowed(format!(
+            "{}:{}",
+            host, port
+        )));
+    }
+
     info!("Proxying to ASGI: {} -> {}", path, target_url);
 
     // Convert axum request to reqwest request
+    let timeout = asgi_config.timeout_for(path);
     let method = req.method().clone();
     let headers = req.headers().clone();
     let body_bytes = axum::body::to_bytes(req.into_body(), usize::MAX)
@@ -520,7 +1438,7 @@ async fn asgi_fallback_handler(
     // Build reqwest request
     let mut proxy_req = client
         .request(method, &target_url)
-        .timeout(Duration::from_secs(asgi_config.timeout_secs))
+        .timeout(timeout)
         .body(body_bytes.to_vec());
 
     // Forward headers (excluding host)
@@ -538,7 +1456,19 @@ async fn asgi_fallback_handler(
 
     // Convert reqwest response to axum response
     let status = proxy_resp.status();
-    let headers = proxy_resp.headers().clone();
+    let (headers, dropped) = asgi_config
+        .response_header_limits()
+        .apply(proxy_resp.headers());
+    if dropped > 0 {
+        warn!(
+            "Dropped {} of {} response headers from {} (limits: {} headers, {} bytes)",
+            dropped,
+            proxy_resp.headers().len(),
+            target_url,
+            asgi_config.max_response_headers,
+            asgi_config.max_response_header_bytes
+        );
+    }
     let body_bytes = proxy_resp
         .bytes()
         .await
@@ -559,39 +1489,124 @@ async fn asgi_fallback_handler(
 }
 
 /// Custom error type
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum AppError {
     NoWorkersAvailable,
     InsufficientResources(String),
     RouteNotFound(String),
+    ValidationError(Vec<String>),
+    /// Body over the route's limit, in bytes
+    PayloadTooLarge(usize),
     SerializationError(String),
     DeserializationError(String),
+    UnconvertibleResult(String),
     WorkerCommunicationError(String),
     UnexpectedResponse,
     AsgiNotConfigured,
     AsgiConfigError(String),
+    AsgiSaturated,
+    ProxyTargetNotAllowed(String),
     ProxyError(String),
+    TaskNotFound(String),
+    TaskIdInUse(String),
+    TaskCancelled(String),
+    TaskPreempted(String),
+    ResultStoreError(String),
+    TaskTimeout(u64),
+    HandlerNotAvailable(String),
+    RouteConflict(String),
+    PoolNotFound(String),
+    PoolScaleFailed(String),
+    RestartInProgress,
+    DeadLetterNotFound(String),
+    NotRedrivable(String),
+    /// Too many tasks queued; retry after this many seconds
+    Overloaded(u64),
 }
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
-        let (status, message) = match self {
-            AppError::NoWorkersAvailable => {
-                (StatusCode::SERVICE_UNAVAILABLE, "No workers available".to_string())
-            }
-            AppError::InsufficientResources(details) => {
-                (StatusCode::SERVICE_UNAVAILABLE, format!("Insufficient resources: {}", details))
-            }
-            AppError::RouteNotFound(route) => {
-                (StatusCode::NOT_FOUND, format!("Route not found: {}", route))
+        // Validation errors list every violation so clients can fix them in one pass
+        if let AppError::ValidationError(violations) = self {
+            let body = Json(serde_json::json!({
+                "error": "Request validation failed",
+                "violations": violations,
+            }));
+            return (StatusCode::BAD_REQUEST, body).into_response();
+        }
+
+        if let AppError::AsgiSaturated = self {
+            let body = Json(serde_json::json!({
+                "error": "ASGI app at maximum concurrent requests",
+            }));
+            return (
+                StatusCode::SERVICE_UNAVAILABLE,
+                [(axum::http::header::RETRY_AFTER, "1")],
+                body,
+            )
+                .into_response();
+        }
+
+        if let AppError::Overloaded(retry_after_secs) = self {
+            let body = Json(serde_json::json!({
+                "error": "Too many tasks queued",
+                "retry_after_secs": retry_after_secs,
+            }));
+            return (
+                StatusCode::TOO_MANY_REQUESTS,
+                [(
+                    axum::http::header::RETRY_AFTER,
+                    retry_after_secs.to_string(),
+                )],
+                body,
+            )
+                .into_response();
+        }
+
+        let (status, message) = self.status_and_message();
+
+        let body = Json(serde_json::json!({
+            "error": message,
+        }));
+
+        (status, body).into_response()
+    }
+}
+
+impl AppError {
+    /// HTTP status and message the error is reported with
+    fn status_and_message(self) -> (StatusCode, String) {
+        match self {
+            AppError::NoWorkersAvailable => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                "No workers available".to_string(),
+            ),
+            AppError::InsufficientResources(details) => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                format!("Insufficient resources: {}", details),
+            ),
+            AppError::RouteNotFound(route) => {
+                (StatusCode::NOT_FOUND, format!("Route not found: {}", route))
             }
-            AppError::SerializationError(e) => {
-                (StatusCode::BAD_REQUEST, format!("Serialization error: {}", e))
+            AppError::ValidationError(violations) => {
+                (StatusCode::BAD_REQUEST, violations.join("; "))
             }
+            AppError::PayloadTooLarge(limit) => (
+                StatusCode::PAYLOAD_TOO_LARGE,
+                format!("Request body over {} bytes", limit),
+            ),
+            AppError::SerializationError(e) => (
+                StatusCode::BAD_REQUEST,
+                format!("Serialization error: {}", e),
+            ),
             AppError::DeserializationError(e) => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 format!("Deserialization error: {}", e),
             ),
+            AppError::UnconvertibleResult(e) => (
+                StatusCode::BAD_GATEWAY,
+                format!("Unconvertible result: {}", e),
+            ),
             AppError::WorkerCommunicationError(e) => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 format!("Worker communication error: {}", e),
@@ -608,53 +1623,312 @@ impl IntoResponse for AppError {
                 StatusCode::INTERNAL_SERVER_ERROR,
                 format!("ASGI configuration error: {}", e),
             ),
-            AppError::ProxyError(e) => (
-                StatusCode::BAD_GATEWAY,
-                format!("Proxy error: {}", e),
+            AppError::AsgiSaturated => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                "ASGI app at maximum concurrent requests".to_string(),
             ),
-        };
-
-        let body = Json(serde_json::json!({
-            "error": message,
-        }));
-
-        (status, body).into_response()
+            AppError::Overloaded(retry_after_secs) => (
+                StatusCode::TOO_MANY_REQUESTS,
+                format!("Too many tasks queued, retry after {}s", retry_after_secs),
+            ),
+            AppError::ProxyTargetNotAllowed(target) => (
+                StatusCode::FORBIDDEN,
+                format!("Proxy target not allowed: {}", target),
+            ),
+            AppError::ProxyError(e) => (StatusCode::BAD_GATEWAY, format!("Proxy error: {}", e)),
+            AppError::TaskNotFound(task_id) => (
+                StatusCode::NOT_FOUND,
+                format!("Task not found: {}", task_id),
+            ),
+            AppError::TaskIdInUse(task_id) => (
+                StatusCode::CONFLICT,
+                format!("Task ID already in use: {}", task_id),
+            ),
+            AppError::TaskCancelled(task_id) => {
+                (StatusCode::CONFLICT, format!("Task cancelled: {}", task_id))
+            }
+            AppError::TaskPreempted(task_id) => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                format!("Task preempted: {}", task_id),
+            ),
+            AppError::ResultStoreError(e) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Result store error: {}", e),
+            ),
+            AppError::TaskTimeout(secs) => (
+                StatusCode::GATEWAY_TIMEOUT,
+                format!("Task timed out after {}s", secs),
+            ),
+            AppError::HandlerNotAvailable(handler) => (
+                StatusCode::NOT_IMPLEMENTED,
+                format!("No worker provides handler: {}", handler),
+            ),
+            AppError::RouteConflict(e) => (StatusCode::CONFLICT, format!("Route conflict: {}", e)),
+            AppError::PoolNotFound(pool) => {
+                (StatusCode::NOT_FOUND, format!("Pool not found: {}", pool))
+            }
+            AppError::PoolScaleFailed(e) => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                format!("Pool scaling failed: {}", e),
+            ),
+            AppError::RestartInProgress => (
+                StatusCode::CONFLICT,
+                "A rolling restart is already in progress".to_string(),
+            ),
+            AppError::DeadLetterNotFound(task_id) => (
+                StatusCode::NOT_FOUND,
+                format!("Dead letter not found: {}", task_id),
+            ),
+            AppError::NotRedrivable(task_id) => (
+                StatusCode::CONFLICT,
+                format!(
+                    "Dead letter {} has no stored args to redrive (dead_letters.redrive is off)",
+                    task_id
+                ),
+            ),
+        }
     }
 }
 
 /// Create the HTTP server router with optional OpenAPI spec for dynamic routing
 pub fn create_router(orchestrator: Arc<Orchestrator>) -> Router {
-    create_router_with_openapi(orchestrator, None, None)
+    create_router_with_openapi(orchestrator, None, None, None)
+}
+
+/// What dispatching a route's tasks needs to know about the route
+fn route_metadata(route_info: &RouteInfo, http_config: &HttpConfig) -> RouteMetadata {
+    RouteMetadata {
+        handler_name: route_info.handler_name.clone(),
+        resources: route_info.resources.clone(),
+        request_schema: http_config.validate_requests.then(|| {
+            let mut schema = route_info.request_schema.clone();
+            schema
+                .reject_unknown_fields
+                .get_or_insert(http_config.reject_unknown_fields);
+            Arc::new(schema)
+        }),
+        response_schema: route_info
+            .response_schema
+            .clone()
+            .filter(|_| http_config.enforce_response_schema)
+            .map(Arc::new),
+        default_result_content_type: route_info.default_result_content_type.as_deref().and_then(
+            |content_type| match HeaderValue::from_str(content_type) {
+                Ok(value) => Some(value),
+                Err(_) => {
+                    warn!(
+                        "Ignoring invalid x-neutrino-default-result-content-type {:?} on {} {}",
+                        content_type, route_info.method, route_info.path
+                    );
+                    None
+                }
+            },
+        ),
+        max_body_bytes: route_info
+            .max_body_bytes
+            .unwrap_or(http_config.max_body_bytes),
+        overflow_pool: route_info.overflow_pool.clone(),
+        pool: route_info.pool.clone(),
+        max_concurrency: match route_info.max_concurrency {
+            Some(0) => {
+                warn!(
+                    "Ignoring x-neutrino-max-concurrency of 0 on {} {}",
+                    route_info.method, route_info.path
+                );
+                None
+            }
+            limit => limit,
+        },
+        hedge_after: match route_info.hedge_after_ms {
+            // A duplicate run must be harmless, so only idempotent routes hedge
+            Some(_) if route_info.method != "GET" => {
+                warn!(
+                    "Ignoring x-neutrino-hedge-after-ms on {} {}: only GET routes are hedged",
+                    route_info.method, route_info.path
+                );
+                None
+            }
+            delay => delay.map(Duration::from_millis),
+        },
+        cache_ttl: route_info
+            .cache_ttl
+            .filter(|&ttl| ttl > 0)
+            .map(Duration::from_secs),
+        priority: route_info.priority,
+        args_template: route_info.args_template.clone().map(Arc::new),
+        tenant_pools: Arc::new(route_info.tenant_pools.clone()),
+        tenant: None,
+        coalesce: route_info.coalesce,
+        gpu_affinity: None,
+        affinity_key: route_info.affinity_key.clone(),
+        affinity: None,
+    }
+}
+
+/// Method router dispatching a task route to its handler, with the route's
+/// metadata and body limit applied (None for unsupported methods)
+fn task_method_router(
+    route_info: &RouteInfo,
+    http_config: &HttpConfig,
+) -> Option<MethodRouter<AppState>> {
+    let metadata = route_metadata(route_info, http_config);
+    let body_limit = metadata.max_body_bytes;
+
+    // Create a middleware that injects the metadata as an extension
+    let handler_middleware = middleware::from_fn(move |mut req: Request, next: Next| {
+        let metadata = metadata.clone();
+        async move {
+            req.extensions_mut().insert(metadata);
+            next.run(req).await
+        }
+    });
+
+    // Create the method router based on the HTTP method with the middleware
+    // Use execute_task_no_body for GET/DELETE, execute_task_with_body for POST/PUT/PATCH
+    let method_router: MethodRouter<AppState> = match route_info.method.as_str() {
+        "GET" => get(execute_task_no_body).layer(handler_middleware),
+        "DELETE" => delete(execute_task_no_body).layer(handler_middleware),
+        "POST" => post(execute_task_with_body).layer(handler_middleware),
+        "PUT" => put(execute_task_with_body).layer(handler_middleware),
+        "PATCH" => patch(execute_task_with_body).layer(handler_middleware),
+        _ => {
+            warn!("Unsupported HTTP method: {}", route_info.method);
+            return None;
+        }
+    };
+
+    // Oversized bodies are rejected with 413 before they reach a worker
+    Some(method_router.layer(DefaultBodyLimit::max(body_limit)))
 }
 
-/// Create the HTTP server router with OpenAPI spec and optional ASGI config
+/// Create the HTTP server router with OpenAPI spec, optional ASGI config, and
+/// optional hooks run on every task result
 pub fn create_router_with_openapi(
     orchestrator: Arc<Orchestrator>,
     openapi_spec: Option<OpenApiSpec>,
     asgi_config: Option<AsgiConfig>,
+    result_hooks: Option<ResultHooks>,
 ) -> Router {
-    // Create HTTP client for ASGI proxy if configured
-    let asgi_client = if asgi_config.is_some() {
-        Some(reqwest::Client::new())
-    } else {
-        None
-    };
+    create_router_with_routes(
+        orchestrator,
+        openapi_spec.map(|spec| spec.extract_routes()),
+        asgi_config,
+        result_hooks,
+    )
+}
+
+/// Create the HTTP server router serving `routes` (e.g. composed from several
+/// specs), with optional ASGI config and result hooks. Background work
+/// (schedules, recovering journaled async tasks) is only started by the
+/// `start_server*` functions.
+pub fn create_router_with_routes(
+    orchestrator: Arc<Orchestrator>,
+    routes: Option<Vec<RouteInfo>>,
+    asgi_config: Option<AsgiConfig>,
+    result_hooks: Option<ResultHooks>,
+) -> Router {
+    router_and_state(orchestrator, routes, asgi_config, result_hooks).0
+}
+
+/// The router along with the state it serves, for starting background work
+fn router_and_state(
+    orchestrator: Arc<Orchestrator>,
+    routes: Option<Vec<RouteInfo>>,
+    asgi_config: Option<AsgiConfig>,
+    result_hooks: Option<ResultHooks>,
+) -> (Router, AppState) {
+    // Create HTTP client for ASGI proxy if configured. Redirects are only
+    // followed to allowlisted targets.
+    let asgi_client = asgi_config.as_ref().map(|config| {
+        let config = config.clone();
+        let redirects = reqwest::redirect::Policy::custom(move |attempt| {
+            let url = attempt.url();
+            let allowed = config.target_allowed(
+                url.host_str().unwrap_or_default(),
+                url.port_or_known_default().unwrap_or_default(),
+            );
+            if !allowed {
+                let message = format!("redirect to {} is not in allowed_targets", url);
+                attempt.error(message)
+            } else if attempt.previous().len() >= 10 {
+                attempt.error("too many redirects")
+            } else {
+                attempt.follow()
+            }
+        });
+        reqwest::Client::builder()
+            .redirect(redirects)
+            .build()
+            .expect("Failed to create ASGI proxy client")
+    });
 
     // Build set of registered Neutrino routes for lookup
     let mut neutrino_routes = HashSet::new();
     neutrino_routes.insert("/health".to_string());
+    neutrino_routes.insert("/ready".to_string());
     neutrino_routes.insert("/status".to_string());
     neutrino_routes.insert("/capacity".to_string());
+    neutrino_routes.insert("/metrics".to_string());
+    neutrino_routes.insert("/admin/stats".to_string());
+    neutrino_routes.insert("/admin/workers".to_string());
+    neutrino_routes.insert("/admin/snapshot".to_string());
+    neutrino_routes.insert("/admin/workers/:worker_id/allocations".to_string());
+    neutrino_routes.insert("/admin/scheduler/explain".to_string());
+    neutrino_routes.insert("/tasks/:task_id".to_string());
 
     let mut router = Router::new()
         .route("/health", get(health_check))
+        .route("/ready", get(health::readiness_check))
         .route("/status", get(get_status))
-        .route("/capacity", get(get_capacity));
+        .route("/capacity", get(get_capacity))
+        .route("/metrics", get(get_metrics))
+        .route("/admin/stats", get(get_stats))
+        .route("/admin/workers", get(get_workers))
+        .route("/admin/snapshot", get(get_snapshot))
+        .route(
+            "/admin/workers/:worker_id/allocations",
+            get(get_worker_allocations),
+        )
+        .route("/admin/scheduler/explain", post(explain::explain_schedule))
+        .route(
+            "/tasks/:task_id",
+            get(cancel::get_task).delete(cancel::cancel_task),
+        );
+    if orchestrator.config().orchestrator.http.allow_pool_scaling {
+        info!("Pool scaling enabled at POST /admin/pools/{{name}}/scale");
+        neutrino_routes.insert("/admin/pools/:name/scale".to_string());
+        router = router.route("/admin/pools/:name/scale", post(pool_scale::scale_pool));
+    }
+    if orchestrator.config().orchestrator.http.allow_worker_restart {
+        info!("Rolling worker restarts enabled at POST /admin/workers/restart");
+        neutrino_routes.insert("/admin/workers/restart".to_string());
+        router = router.route("/admin/workers/restart", post(restart_workers));
+    }
+
+    // Task routes are collected separately so task-only layers (e.g. chaos) can be applied
+    let mut task_router = Router::new();
+    let mut task_route_count = 0;
+
+    let http_config = &orchestrator.config().orchestrator.http;
+
+    // The first route of each handler, for tasks submitted by handler name
+    let mut handler_routes = HashMap::new();
+
+    let dedicated_pools: HashSet<String> = routes
+        .iter()
+        .flatten()
+        .flat_map(|route_info| route_info.tenant_pools.values().cloned())
+        .collect();
 
-    // If OpenAPI spec is provided, create dynamic routes
-    if let Some(spec) = openapi_spec {
+    // If OpenAPI routes are provided, create dynamic routes
+    if let Some(routes) = routes {
         info!("Loading routes from OpenAPI specification");
-        let routes = spec.extract_routes();
+        let pool_names: HashSet<String> = orchestrator
+            .config()
+            .effective_worker_pools()
+            .into_iter()
+            .map(|pool| pool.name)
+            .collect();
 
         for route_info in routes {
             info!(
@@ -670,36 +1944,39 @@ pub fn create_router_with_openapi(
             // Add to Neutrino routes set
             neutrino_routes.insert(route_info.path.clone());
 
-            // Create metadata with handler name and resource requirements
-            let metadata = RouteMetadata {
-                handler_name: route_info.handler_name.clone(),
-                resources: route_info.resources.clone(),
-            };
-
-            // Create a middleware that injects the metadata as an extension
-            let handler_middleware = middleware::from_fn(move |mut req: Request, next: Next| {
-                let metadata = metadata.clone();
-                async move {
-                    req.extensions_mut().insert(metadata);
-                    next.run(req).await
+            if let Some(overflow_pool) = &route_info.overflow_pool {
+                if !pool_names.contains(overflow_pool) {
+                    warn!(
+                        "{} {} names overflow pool '{}', which is not a configured pool",
+                        route_info.method, route_info.path, overflow_pool
+                    );
                 }
-            });
-
-            // Create the method router based on the HTTP method with the middleware
-            // Use execute_task_no_body for GET/DELETE, execute_task_with_body for POST/PUT/PATCH
-            let method_router = match route_info.method.as_str() {
-                "GET" => get(execute_task_no_body).layer(handler_middleware),
-                "DELETE" => delete(execute_task_no_body).layer(handler_middleware),
-                "POST" => post(execute_task_with_body).layer(handler_middleware),
-                "PUT" => put(execute_task_with_body).layer(handler_middleware),
-                "PATCH" => patch(execute_task_with_body).layer(handler_middleware),
-                _ => {
-                    warn!("Unsupported HTTP method: {}", route_info.method);
-                    continue;
+            }
+            if let Some(pool) = &route_info.pool {
+                if !pool_names.contains(pool) {
+                    warn!(
+                        "{} {} is pinned to pool '{}', which is not a configured pool",
+                        route_info.method, route_info.path, pool
+                    );
                 }
-            };
+            }
+            for (tenant, pool) in &route_info.tenant_pools {
+                if !pool_names.contains(pool) {
+                    warn!(
+                        "{} {} names pool '{}' for tenant '{}', which is not a configured pool",
+                        route_info.method, route_info.path, pool, tenant
+                    );
+                }
+            }
 
-            router = router.route(&route_info.path, method_router);
+            handler_routes
+                .entry(route_info.handler_name.clone())
+                .or_insert_with(|| route_metadata(&route_info, http_config));
+            let Some(method_router) = task_method_router(&route_info, http_config) else {
+                continue;
+            };
+            task_router = task_router.route(&route_info.path, method_router);
+            task_route_count += 1;
         }
     } else {
         // Fallback to generic task route if no OpenAPI spec
@@ -707,24 +1984,190 @@ pub fn create_router_with_openapi(
         // Note: For production use, always provide an OpenAPI spec
     }
 
+    // Failure injection is only wired up when explicitly configured
+    if let Some(chaos_config) = orchestrator.config().orchestrator.chaos.clone() {
+        let injector = Arc::new(ChaosInjector::new(chaos_config));
+        info!("Chaos mode configured - admin toggle available at /admin/chaos");
+
+        if task_route_count > 0 {
+            task_router = task_router.route_layer(middleware::from_fn_with_state(
+                Arc::clone(&injector),
+                chaos::chaos_middleware,
+            ));
+        }
+        neutrino_routes.insert("/admin/chaos".to_string());
+        router = router.merge(
+            Router::new()
+                .route("/admin/chaos", get(chaos::get_chaos).post(chaos::set_chaos))
+                .with_state(injector),
+        );
+    }
+
+    router = router.merge(task_router);
+
+    // When enabled, unless the app has a route of its own there
+    if !orchestrator.config().orchestrator.http.allow_batch {
+        debug!("POST /batch task batches are disabled");
+    } else if neutrino_routes.insert("/batch".to_string()) {
+        router = router.route("/batch", post(batch::submit_batch));
+    } else {
+        warn!("The app has its own /batch route, so POST /batch task batches are unavailable");
+    }
+    if !orchestrator.config().orchestrator.http.allow_dag {
+        debug!("POST /dag task graphs are disabled");
+    } else if neutrino_routes.insert("/dag".to_string()) {
+        router = router.route("/dag", post(dag::submit_dag));
+    } else {
+        warn!("The app has its own /dag route, so POST /dag task graphs are unavailable");
+    }
+
+    let ready_grace_secs = orchestrator.config().orchestrator.http.ready_grace_secs;
+
+    let result_store = orchestrator
+        .config()
+        .orchestrator
+        .http
+        .async_results
+        .as_ref()
+        .map(async_tasks::open_store);
+    if result_store.is_some() {
+        neutrino_routes.insert("/tasks/:task_id/result".to_string());
+        router = router.route("/tasks/:task_id/result", get(async_tasks::get_task_result));
+    }
+    let journal = orchestrator
+        .config()
+        .orchestrator
+        .http
+        .async_results
+        .as_ref()
+        .and_then(|config| config.journal_path.as_deref())
+        .and_then(|path| match TaskJournal::open(path) {
+            Ok(journal) => {
+                info!("Async tasks journaled to {}", path);
+                Some(Arc::new(journal))
+            }
+            Err(e) => {
+                error!(
+                    "Failed to open task journal at {}: {}. Async tasks won't survive a restart",
+                    path, e
+                );
+                None
+            }
+        });
+
+    let deep_health = orchestrator
+        .config()
+        .orchestrator
+        .http
+        .deep_health
+        .clone()
+        .map(|config| {
+            info!(
+                "Deep health check enabled at /health/deep (handler: {})",
+                config.handler
+            );
+            Arc::new(DeepHealthCheck::new(config))
+        });
+    if deep_health.is_some() {
+        neutrino_routes.insert("/health/deep".to_string());
+        router = router.route("/health/deep", get(health::deep_health_check));
+    }
+
+    let dead_letters = orchestrator
+        .config()
+        .orchestrator
+        .tasks
+        .dead_letters
+        .as_ref()
+        .map(crate::dead_letters::open_store);
+    if dead_letters.is_some() {
+        info!("Dead-letter records enabled at /admin/dead-letters");
+        neutrino_routes.insert("/admin/dead-letters".to_string());
+        neutrino_routes.insert("/admin/dead-letters/:task_id".to_string());
+        neutrino_routes.insert("/admin/dead-letters/:task_id/redrive".to_string());
+        router = router
+            .route("/admin/dead-letters", get(dead_letters::get_dead_letters))
+            .route(
+                "/admin/dead-letters/:task_id",
+                get(dead_letters::get_dead_letter),
+            )
+            .route(
+                "/admin/dead-letters/:task_id/redrive",
+                post(dead_letters::redrive_dead_letter),
+            );
+    }
+
+    let mut patched_routes = None;
+    if http_config.allow_route_patching {
+        info!("Route patching enabled at PATCH /admin/routes");
+        neutrino_routes.insert("/admin/routes".to_string());
+        router = router.route("/admin/routes", patch(route_patch::patch_route));
+
+        let fixed_paths: Vec<String> = neutrino_routes.iter().cloned().collect();
+        patched_routes = Some(Arc::new(PatchedRoutes::new(fixed_paths)));
+    }
+
+    let asgi_permits = asgi_config
+        .as_ref()
+        .and_then(|config| config.max_concurrent_proxies)
+        .map(|limit| Arc::new(Semaphore::new(limit)));
+
+    let backpressure = orchestrator
+        .config()
+        .orchestrator
+        .tasks
+        .backpressure
+        .clone()
+        .map(|config| Arc::new(Backpressure::new(config)));
+
+    let result_cache = Arc::new(ResultCache::new(
+        http_config.max_cached_results,
+        http_config.max_cached_result_bytes,
+    ));
+
+    let available_handlers = orchestrator.handler_registry();
     let state = AppState {
         orchestrator,
         asgi_config: asgi_config.clone(),
         asgi_client,
+        asgi_permits,
+        deep_health,
+        result_hooks: result_hooks.unwrap_or_default(),
+        readiness: Arc::new(Readiness::new(Duration::from_secs(ready_grace_secs))),
+        result_store,
+        journal,
+        available_handlers,
         neutrino_routes: Arc::new(neutrino_routes),
+        patched_routes,
+        coalescer: Arc::new(Coalescer::default()),
+        result_cache,
+        dead_letters,
+        running_tasks: Arc::new(RunningTasks::default()),
+        handler_limits: Arc::new(HandlerLimits::default()),
+        handler_routes: Arc::new(handler_routes),
+        dedicated_pools: Arc::new(dedicated_pools),
+        backpressure,
     };
-
     // Add ASGI fallback handler if configured
-    if let Some(ref config) = asgi_config {
-        if config.enabled {
-            info!("ASGI integration enabled - unmatched routes will fallback to ASGI app");
+    let asgi_enabled = asgi_config.as_ref().is_some_and(|config| config.enabled);
+    if asgi_enabled {
+        info!("ASGI integration enabled - unmatched routes will fallback to ASGI app");
+    }
 
-            // Add catch-all fallback route (lowest priority)
-            router = router.fallback(asgi_fallback_handler);
-        }
+    // Add catch-all fallback route (lowest priority)
+    if asgi_enabled || state.patched_routes.is_some() {
+        router = router.fallback(fallback_handler);
     }
 
-    router.with_state(state)
+    (router.with_state(state.clone()), state)
+}
+
+/// Start the work that runs alongside the server rather than per request
+fn start_background_tasks(state: &AppState) {
+    schedules::spawn_schedules(state);
+    if let (Some(journal), Some(store)) = (&state.journal, &state.result_store) {
+        async_tasks::recover_journaled(state, journal, store);
+    }
 }
 
 /// Start the HTTP server
@@ -744,30 +2187,3383 @@ pub async fn start_server_with_openapi(
     openapi_path: Option<&str>,
     asgi_config: Option<AsgiConfig>,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    // Load OpenAPI spec if path is provided
-    let openapi_spec = if let Some(path) = openapi_path {
-        info!("Loading OpenAPI spec from: {}", path);
-        match OpenApiSpec::from_file(path) {
-            Ok(spec) => {
-                info!("Successfully loaded OpenAPI spec: {} v{}", spec.info.title, spec.info.version);
-                Some(spec)
-            }
-            Err(e) => {
-                warn!("Failed to load OpenAPI spec: {}. Using fallback routing.", e);
-                None
+    start_server_with_shutdown(
+        orchestrator,
+        host,
+        port,
+        openapi_path,
+        asgi_config,
+        std::future::pending(),
+    )
+    .await
+}
+
+/// Load an OpenAPI spec from a file or URL and apply the resource policy, if
+/// any. A spec that can't be loaded is skipped with a warning.
+async fn load_spec(
+    location: &str,
+    http_config: &HttpConfig,
+) -> Result<Option<OpenApiSpec>, Box<dyn std::error::Error>> {
+    info!("Loading OpenAPI spec from: {}", location);
+    let loaded = if remote::is_url(location) {
+        remote::fetch_spec(location, &http_config.spec_fetch)
+            .await
+            .map_err(Into::into)
+    } else {
+        OpenApiSpec::from_file(location)
+    };
+    match loaded {
+        Ok(mut spec) => {
+            info!(
+                "Successfully loaded OpenAPI spec: {} v{}",
+                spec.info.title, spec.info.version
+            );
+            if let Some(policy_path) = &http_config.resource_policy {
+                info!("Applying resource policy from: {}", policy_path);
+                ResourcePolicy::from_file(policy_path)?.apply(&mut spec);
             }
+            Ok(Some(spec))
         }
-    } else {
+        Err(e) => {
+            warn!(
+                "Failed to load OpenAPI spec {}: {}. Using fallback routing.",
+                location, e
+            );
+            Ok(None)
+        }
+    }
+}
+
+/// Start the HTTP server and serve until `shutdown` resolves, then drain in-flight
+/// requests. See [`handoff`] for upgrading without dropping connections.
+pub async fn start_server_with_shutdown(
+    orchestrator: Arc<Orchestrator>,
+    host: String,
+    port: u16,
+    openapi_path: Option<&str>,
+    asgi_config: Option<AsgiConfig>,
+    shutdown: impl std::future::Future<Output = ()> + Send + 'static,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let http_config = &orchestrator.config().orchestrator.http;
+    let mut specs = Vec::new();
+    if let Some(path) = openapi_path {
+        if let Some(spec) = load_spec(path, http_config).await? {
+            specs.push(SpecRoutes {
+                source: path.to_string(),
+                prefix: None,
+                routes: spec.extract_routes(),
+            });
+        }
+    }
+    for extra in &http_config.extra_specs {
+        if let Some(spec) = load_spec(&extra.path, http_config).await? {
+            specs.push(SpecRoutes {
+                source: extra.path.clone(),
+                prefix: extra.prefix.clone(),
+                routes: spec.extract_routes(),
+            });
+        }
+    }
+    let routes = if specs.is_empty() {
         None
+    } else {
+        let routes = compose::compose(specs, http_config.spec_conflicts)?;
+        compose::check_route_count(&routes, http_config.max_routes)?;
+        Some(routes)
     };
 
-    let app = create_router_with_openapi(orchestrator, openapi_spec, asgi_config);
+    let http_config = http_config.clone();
+    let (app, state) = router_and_state(orchestrator, routes, asgi_config, None);
+    start_background_tasks(&state);
     let addr = format!("{}:{}", host, port);
 
-    info!("Starting HTTP server on {}", addr);
+    info!(
+        "Starting HTTP server on {}{}",
+        addr,
+        if http_config.reuse_port {
+            " (SO_REUSEPORT)"
+        } else {
+            ""
+        }
+    );
+
+    let listener = handoff::bind_listener(&addr, http_config.reuse_port).await?;
+    let _ready_file = http_config
+        .ready_file
+        .as_ref()
+        .map(handoff::ReadyFile::write)
+        .transpose()?;
+
+    if let Some(limit) = http_config.max_connections_per_ip {
+        info!(
+            "Limiting each client IP to {} concurrent connections",
+            limit
+        );
+    }
+    let fd_monitor = crate::fds::soft_limit(http_config.fd_soft_limit_ratio).map(|soft_limit| {
+        info!(
+            "Refusing new connections past {} open file descriptors",
+            soft_limit
+        );
+        crate::fds::FdMonitor::new(soft_limit)
+    });
+    let connection_limiter = (http_config.max_connections_per_ip.is_some() || fd_monitor.is_some())
+        .then(|| {
+            conn_limit::ConnectionLimiter::new(http_config.max_connections_per_ip, fd_monitor)
+        });
+    let admission = http_config.admission.as_ref().map(|config| {
+        info!(
+            "Pausing accepts at {} requests in flight, resuming at {}",
+            config.high_watermark, config.low_watermark
+        );
+        admission::AdmissionControl::new(config.high_watermark, config.low_watermark)
+    });
 
-    let listener = tokio::net::TcpListener::bind(&addr).await?;
-    axum::serve(listener, app).await?;
+    handoff::serve_with_connection_limit(
+        listener,
+        app,
+        shutdown,
+        Duration::from_secs(http_config.shutdown_timeout_secs),
+        connection_limiter,
+        admission,
+    )
+    .await?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::protocol::ResourceCapabilities;
+    use crate::serde_convert;
+    use crate::testing::{
+        mock_worker_handle, spawn_echo_worker, spawn_queued_worker, spec_with_routes,
+        wait_for_worker_state, CapturedEvents,
+    };
+    use tower::ServiceExt;
+
+    async fn post_json(router: Router, uri: &str, body: serde_json::Value) -> Response {
+        let req = Request::builder()
+            .method("POST")
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        router.oneshot(req).await.unwrap()
+    }
+
+    async fn json_body(response: Response) -> serde_json::Value {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_queue_wait_reported_when_task_queues() {
+        let orchestrator = Arc::new(Orchestrator::new(Config::default()));
+        let (handle, worker_side) =
+            mock_worker_handle("default-0", ResourceCapabilities::default());
+        orchestrator.workers().write().await.push(handle);
+        spawn_echo_worker(worker_side, Duration::from_millis(200));
+
+        let spec = spec_with_routes(&[("POST", "/work", "post_work")]);
+        let router = create_router_with_openapi(Arc::clone(&orchestrator), Some(spec), None, None);
+
+        // With a single worker, the second request has to wait for the first to finish
+        let first = tokio::spawn(post_json(
+            router.clone(),
+            "/work",
+            serde_json::json!({"args": {"n": 1}}),
+        ));
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let request = Request::builder()
+            .method("POST")
+            .uri("/work")
+            .header("content-type", "application/json")
+            .header(TASK_ID_HEADER, "second")
+            .body(Body::from(
+                serde_json::json!({"args": {"n": 2}}).to_string(),
+            ))
+            .unwrap();
+        let second = tokio::spawn(router.clone().oneshot(request));
+
+        // The task registry shows it queued, then running after its wait
+        let status = |router: Router| async move {
+            let req = Request::builder()
+                .uri("/tasks/second")
+                .body(Body::empty())
+                .unwrap();
+            json_body(router.oneshot(req).await.unwrap()).await
+        };
+        tokio::time::sleep(Duration::from_millis(80)).await;
+        let queued = status(router.clone()).await;
+        assert_eq!(queued["status"], "queued");
+        assert!(
+            queued["queue_wait_ms"].as_u64().unwrap() >= 50,
+            "{}",
+            queued
+        );
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let running = status(router.clone()).await;
+        assert_eq!(running["status"], "running");
+        assert_eq!(running["worker_id"], "default-0");
+
+        let second = second.await.unwrap().unwrap();
+        let first = first.await.unwrap();
+
+        assert_eq!(first.status(), StatusCode::OK);
+        assert_eq!(second.status(), StatusCode::OK);
+
+        let header_ms: u64 = second.headers()[QUEUE_WAIT_HEADER]
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        let body = json_body(second).await;
+        let queue_wait_ms = body["queue_wait_ms"].as_u64().unwrap();
+
+        assert_eq!(header_ms, queue_wait_ms);
+        assert_eq!(running["queue_wait_ms"].as_u64().unwrap(), queue_wait_ms);
+        assert_eq!(body["result"], serde_json::json!({"n": 2}));
+        // Second request arrived ~20ms after the first, which held the worker for 200ms
+        assert!(
+            (120..=400).contains(&queue_wait_ms),
+            "queue wait {}ms should roughly match the 180ms delay",
+            queue_wait_ms
+        );
+
+        let histogram = orchestrator.metrics().queue_wait("work").unwrap();
+        assert_eq!(histogram.count(), 2);
+        assert!(histogram.sum() >= 0.12);
+
+        let metrics = orchestrator.metrics().render();
+        assert!(metrics.contains("neutrino_task_queue_wait_seconds_count{handler=\"work\"} 2"));
+    }
+
+    #[tokio::test]
+    async fn test_admin_stats_per_handler() {
+        let orchestrator = Arc::new(Orchestrator::new(Config::default()));
+        let (handle, worker_side) =
+            mock_worker_handle("default-0", ResourceCapabilities::default());
+        orchestrator.workers().write().await.push(handle);
+        spawn_echo_worker(worker_side, Duration::from_millis(1));
+
+        let spec = spec_with_routes(&[
+            ("POST", "/busy", "post_busy"),
+            ("POST", "/quiet", "post_quiet"),
+        ]);
+        let router = create_router_with_openapi(Arc::clone(&orchestrator), Some(spec), None, None);
+
+        for i in 0..4 {
+            let args = serde_json::json!({"args": {"fail": i == 3}});
+            post_json(router.clone(), "/busy", args).await;
+        }
+        post_json(router.clone(), "/quiet", serde_json::json!({"args": {}})).await;
+
+        let req = Request::builder()
+            .uri("/admin/stats")
+            .body(Body::empty())
+            .unwrap();
+        let response = router.oneshot(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = json_body(response).await;
+        let handlers = body["handlers"].as_array().unwrap();
+        assert_eq!(handlers.len(), 2);
+
+        assert_eq!(handlers[0]["handler"], "busy");
+        assert_eq!(handlers[0]["calls"], 4);
+        assert_eq!(handlers[0]["failures"], 1);
+        assert_eq!(handlers[0]["success_rate"], 0.75);
+        assert!(handlers[0]["avg_latency_ms"].as_f64().unwrap() > 0.0);
+        assert!(handlers[0]["p95_latency_ms"].as_f64().unwrap() > 0.0);
+
+        assert_eq!(handlers[1]["handler"], "quiet");
+        assert_eq!(handlers[1]["calls"], 1);
+        assert_eq!(handlers[1]["success_rate"], 1.0);
+    }
+
+    #[tokio::test]
+    async fn test_asgi_proxy_concurrency_limit() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use tokio::sync::Notify;
+
+        // Backend that holds every request until released
+        let arrived = Arc::new(AtomicUsize::new(0));
+        let release = Arc::new(Notify::new());
+        let backend = {
+            let arrived = Arc::clone(&arrived);
+            let release = Arc::clone(&release);
+            Router::new().fallback(move || {
+                let arrived = Arc::clone(&arrived);
+                let release = Arc::clone(&release);
+                async move {
+                    let notified = release.notified();
+                    arrived.fetch_add(1, Ordering::SeqCst);
+                    notified.await;
+                    "ok"
+                }
+            })
+        };
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, backend).await.unwrap() });
+
+        let asgi_config: AsgiConfig = serde_yaml::from_str(&format!(
+            "enabled: true\nmode: proxy\nservice_url: http://{}\nmax_concurrent_proxies: 2",
+            addr
+        ))
+        .unwrap();
+        let orchestrator = Arc::new(Orchestrator::new(Config::default()));
+        let router = create_router_with_openapi(orchestrator, None, Some(asgi_config), None);
+
+        let get = |router: Router| async move {
+            let req = Request::builder()
+                .uri("/legacy")
+                .body(Body::empty())
+                .unwrap();
+            router.oneshot(req).await.unwrap()
+        };
+
+        let first = tokio::spawn(get(router.clone()));
+        let second = tokio::spawn(get(router.clone()));
+        while arrived.load(Ordering::SeqCst) < 2 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+
+        let rejected = get(router.clone()).await;
+        assert_eq!(rejected.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(rejected.headers()["retry-after"], "1");
+        assert_eq!(arrived.load(Ordering::SeqCst), 2);
+
+        release.notify_waiters();
+        assert_eq!(first.await.unwrap().status(), StatusCode::OK);
+        assert_eq!(second.await.unwrap().status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_asgi_proxy_target_allowlist() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let hits = Arc::new(AtomicUsize::new(0));
+        let backend = {
+            let hits = Arc::clone(&hits);
+            Router::new().fallback(move || {
+                hits.fetch_add(1, Ordering::SeqCst);
+                async { "ok" }
+            })
+        };
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, backend).await.unwrap() });
+
+        let proxy = |allowed: String| {
+            let asgi_config: AsgiConfig = serde_yaml::from_str(&format!(
+                "enabled: true\nmode: proxy\nservice_url: http://{}\nallowed_targets: [{}]",
+                addr, allowed
+            ))
+            .unwrap();
+            let orchestrator = Arc::new(Orchestrator::new(Config::default()));
+            let router = create_router_with_openapi(orchestrator, None, Some(asgi_config), None);
+            async move {
+                let req = Request::builder()
+                    .uri("/legacy")
+                    .body(Body::empty())
+                    .unwrap();
+                router.oneshot(req).await.unwrap()
+            }
+        };
+
+        // Same host on another port is outside the allowlist
+        let refused = proxy(format!("\"127.0.0.1:{}\"", addr.port().wrapping_add(1))).await;
+        assert_eq!(refused.status(), StatusCode::FORBIDDEN);
+        assert_eq!(hits.load(Ordering::SeqCst), 0);
+
+        let allowed = proxy(format!("\"{}\"", addr)).await;
+        assert_eq!(allowed.status(), StatusCode::OK);
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+
+        // A bare host allows any port
+        let any_port = proxy("\"127.0.0.1\"".to_string()).await;
+        assert_eq!(any_port.status(), StatusCode::OK);
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_asgi_proxy_prefix_timeout_override() {
+        // Backend that takes 1.5s to answer anything
+        let backend = Router::new().fallback(|| async {
+            tokio::time::sleep(Duration::from_millis(1500)).await;
+            "ok"
+        });
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, backend).await.unwrap() });
+
+        let asgi_config: AsgiConfig = serde_yaml::from_str(&format!(
+            "enabled: true\nmode: proxy\nservice_url: http://{}\ntimeout_secs: 1\n\
+             prefix_timeouts: {{\"/reports\": 5, \"/reports/fast\": 1}}",
+            addr
+        ))
+        .unwrap();
+        assert_eq!(asgi_config.timeout_for("/reports"), Duration::from_secs(5));
+        assert_eq!(
+            asgi_config.timeout_for("/reports/fast/daily"),
+            Duration::from_secs(1)
+        );
+        assert_eq!(
+            asgi_config.timeout_for("/reportsarchive"),
+            Duration::from_secs(1)
+        );
+
+        let orchestrator = Arc::new(Orchestrator::new(Config::default()));
+        let router = create_router_with_openapi(orchestrator, None, Some(asgi_config), None);
+        let get = |uri: &'static str| {
+            let router = router.clone();
+            async move {
+                let req = Request::builder().uri(uri).body(Body::empty()).unwrap();
+                router.oneshot(req).await.unwrap().status()
+            }
+        };
+
+        // Under the prefix the slow endpoint gets 5s; elsewhere the global 1s applies
+        let (slow_report, legacy) = tokio::join!(get("/reports/monthly"), get("/legacy"));
+        assert_eq!(slow_report, StatusCode::OK);
+        assert_eq!(legacy, StatusCode::BAD_GATEWAY);
+    }
+
+    #[tokio::test]
+    async fn test_asgi_proxy_bounds_response_headers() {
+        // Backend answering with one oversized header followed by many small ones
+        let backend = Router::new().fallback(|| async {
+            let mut headers = HeaderMap::new();
+            headers.insert("x-huge", HeaderValue::from_str(&"a".repeat(8192)).unwrap());
+            for i in 0..50 {
+                let name = axum::http::HeaderName::from_bytes(format!("x-filler-{}", i).as_bytes())
+                    .unwrap();
+                headers.insert(name, HeaderValue::from_static("1"));
+            }
+            (headers, "ok")
+        });
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, backend).await.unwrap() });
+
+        let asgi_config: AsgiConfig = serde_yaml::from_str(&format!(
+            "enabled: true\nmode: proxy\nservice_url: http://{}\n\
+             max_response_headers: 10\nmax_response_header_bytes: 4096",
+            addr
+        ))
+        .unwrap();
+        let orchestrator = Arc::new(Orchestrator::new(Config::default()));
+        let router = create_router_with_openapi(orchestrator, None, Some(asgi_config), None);
+
+        let req = Request::builder()
+            .uri("/anything")
+            .body(Body::empty())
+            .unwrap();
+        let response = router.oneshot(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        // The oversized header is skipped; content-type and small ones fill the count limit
+        assert!(response.headers().get("x-huge").is_none());
+        assert!(response.headers().contains_key("content-type"));
+        let fillers = response
+            .headers()
+            .keys()
+            .filter(|name| name.as_str().starts_with("x-filler-"));
+        assert_eq!(fillers.count(), 9);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"ok");
+    }
+
+    #[tokio::test]
+    async fn test_result_hook_applied_to_response() {
+        let orchestrator = Arc::new(Orchestrator::new(Config::default()));
+        let (handle, worker_side) =
+            mock_worker_handle("default-0", ResourceCapabilities::default());
+        orchestrator.workers().write().await.push(handle);
+        spawn_echo_worker(worker_side, Duration::from_millis(1));
+
+        let hooks = ResultHooks::new().with_hook(|metadata, result| {
+            if let Some(obj) = result.as_object_mut() {
+                obj.insert(
+                    "handled_by".to_string(),
+                    metadata.handler_name.clone().into(),
+                );
+                obj.remove("secret");
+            }
+        });
+        let spec = spec_with_routes(&[("POST", "/work", "post_work")]);
+        let router = create_router_with_openapi(orchestrator, Some(spec), None, Some(hooks));
+
+        let args = serde_json::json!({"args": {"n": 1, "secret": "hunter2"}});
+        let response = post_json(router, "/work", args).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = json_body(response).await;
+        assert_eq!(
+            body["result"],
+            serde_json::json!({"n": 1, "handled_by": "work"})
+        );
+    }
+
+    #[tokio::test]
+    async fn test_zstd_msgpack_response() {
+        let orchestrator = Arc::new(Orchestrator::new(Config::default()));
+        let (handle, worker_side) =
+            mock_worker_handle("default-0", ResourceCapabilities::default());
+        orchestrator.workers().write().await.push(handle);
+        spawn_echo_worker(worker_side, Duration::from_millis(1));
+
+        let spec = spec_with_routes(&[("POST", "/work", "post_work")]);
+        let router = create_router_with_openapi(orchestrator, Some(spec), None, None);
+
+        let values: Vec<f64> = (0..1000).map(|i| i as f64 * 0.5).collect();
+        let req = Request::builder()
+            .method("POST")
+            .uri("/work")
+            .header("content-type", "application/json")
+            .header("accept", "application/msgpack")
+            .header("accept-encoding", "gzip, zstd")
+            .body(Body::from(
+                serde_json::json!({"args": {"values": values}}).to_string(),
+            ))
+            .unwrap();
+        let response = router.oneshot(req).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["content-type"], "application/msgpack");
+        assert_eq!(response.headers()["content-encoding"], "zstd");
+
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let decompressed = zstd::decode_all(&bytes[..]).unwrap();
+        let body: serde_json::Value = rmp_serde::from_slice(&decompressed).unwrap();
+
+        assert_eq!(body["success"], true);
+        assert_eq!(body["result"], serde_json::json!({"values": values}));
+    }
+
+    #[tokio::test]
+    async fn test_json_response_by_default() {
+        let orchestrator = Arc::new(Orchestrator::new(Config::default()));
+        let (handle, worker_side) =
+            mock_worker_handle("default-0", ResourceCapabilities::default());
+        orchestrator.workers().write().await.push(handle);
+        spawn_echo_worker(worker_side, Duration::from_millis(1));
+
+        let spec = spec_with_routes(&[("POST", "/work", "post_work")]);
+        let router = create_router_with_openapi(orchestrator, Some(spec), None, None);
+
+        // zstd alone doesn't switch JSON clients (e.g. browsers) to compressed responses
+        let req = Request::builder()
+            .method("POST")
+            .uri("/work")
+            .header("content-type", "application/json")
+            .header("accept-encoding", "zstd")
+            .body(Body::from(
+                serde_json::json!({"args": {"n": 1}}).to_string(),
+            ))
+            .unwrap();
+        let response = router.oneshot(req).await.unwrap();
+
+        assert_eq!(response.headers()["content-type"], "application/json");
+        assert!(response.headers().get("content-encoding").is_none());
+        assert_eq!(
+            json_body(response).await["result"],
+            serde_json::json!({"n": 1})
+        );
+    }
+
+    #[tokio::test]
+    async fn test_async_task_result_polling() {
+        let db_path =
+            std::env::temp_dir().join(format!("neutrino-async-{}.db", uuid::Uuid::new_v4()));
+        let mut config = Config::default();
+        config.orchestrator.http.async_results = Some(crate::config::AsyncResultsConfig {
+            store_path: Some(db_path.to_string_lossy().to_string()),
+            ttl_secs: 60,
+            journal_path: None,
+        });
+        let orchestrator = Arc::new(Orchestrator::new(config));
+        let (handle, worker_side) =
+            mock_worker_handle("default-0", ResourceCapabilities::default());
+        orchestrator.workers().write().await.push(handle);
+        spawn_echo_worker(worker_side, Duration::from_millis(50));
+
+        let spec = spec_with_routes(&[("POST", "/work", "post_work")]);
+        let router = create_router_with_openapi(orchestrator, Some(spec), None, None);
+
+        let req = Request::builder()
+            .method("POST")
+            .uri("/work")
+            .header("content-type", "application/json")
+            .header("prefer", "respond-async")
+            .body(Body::from(
+                serde_json::json!({"args": {"n": 7}}).to_string(),
+            ))
+            .unwrap();
+        let response = router.clone().oneshot(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        let result_url = json_body(response).await["result_url"]
+            .as_str()
+            .unwrap()
+            .to_string();
+
+        let get = |router: Router, uri: String| async move {
+            let req = Request::builder().uri(uri).body(Body::empty()).unwrap();
+            router.oneshot(req).await.unwrap()
+        };
+
+        let pending = get(router.clone(), result_url.clone()).await;
+        assert_eq!(pending.status(), StatusCode::ACCEPTED);
+
+        let mut done = None;
+        for _ in 0..50 {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            let response = get(router.clone(), result_url.clone()).await;
+            if response.status() == StatusCode::OK {
+                done = Some(json_body(response).await);
+                break;
+            }
+        }
+        let body = done.expect("async task never completed");
+        assert_eq!(body["success"], true);
+        assert_eq!(body["result"], serde_json::json!({"n": 7}));
+
+        let missing = get(router, "/tasks/unknown/result".to_string()).await;
+        assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+
+        let _ = std::fs::remove_file(db_path);
+    }
+
+    #[tokio::test]
+    async fn test_journaled_async_tasks_recovered_at_startup() {
+        let journal_path =
+            std::env::temp_dir().join(format!("neutrino-journal-{}.db", uuid::Uuid::new_v4()));
+        let journal = TaskJournal::open(&journal_path).unwrap();
+        let mut args = Vec::new();
+        rmpv::encode::write_value(&mut args, &rmpv::Value::Map(vec![("n".into(), 3.into())]))
+            .unwrap();
+        let entry = |task_id: &str, recoveries| crate::journal::JournalEntry {
+            task_id: task_id.to_string(),
+            handler: "work".to_string(),
+            args: args.clone(),
+            accepted_at: 1_700_000_000,
+            recoveries,
+        };
+        journal.record(&entry("interrupted", 0)).unwrap();
+        journal.record(&entry("crashes-every-time", 3)).unwrap();
+        drop(journal);
+
+        let mut config = Config::default();
+        config.orchestrator.http.async_results = Some(crate::config::AsyncResultsConfig {
+            store_path: None,
+            ttl_secs: 60,
+            journal_path: Some(journal_path.to_string_lossy().to_string()),
+        });
+        let orchestrator = Arc::new(Orchestrator::new(config));
+        let (handle, worker_side) =
+            mock_worker_handle("default-0", ResourceCapabilities::default());
+        orchestrator.workers().write().await.push(handle);
+        spawn_echo_worker(worker_side, Duration::ZERO);
+        let spec = spec_with_routes(&[("POST", "/work", "post_work")]);
+        let (router, state) =
+            router_and_state(orchestrator, Some(spec.extract_routes()), None, None);
+        start_background_tasks(&state);
+
+        let get = |uri: &str| {
+            let req = Request::builder().uri(uri).body(Body::empty()).unwrap();
+            router.clone().oneshot(req)
+        };
+        let mut recovered = None;
+        for _ in 0..50 {
+            let response = get("/tasks/interrupted/result").await.unwrap();
+            if response.status() == StatusCode::OK {
+                recovered = Some(json_body(response).await);
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        let recovered = recovered.expect("journaled task never completed");
+        assert_eq!(
+            recovered["result"],
+            serde_json::json!({"n": 3}),
+            "{}",
+            recovered
+        );
+
+        let given_up = json_body(get("/tasks/crashes-every-time/result").await.unwrap()).await;
+        assert_eq!(given_up["success"], false);
+        // Removed from the journal just after the result is stored
+        let journal = TaskJournal::open(&journal_path).unwrap();
+        for _ in 0..50 {
+            if journal.unfinished().unwrap().is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert!(journal.unfinished().unwrap().is_empty());
+
+        let _ = std::fs::remove_file(journal_path);
+    }
+
+    #[tokio::test]
+    async fn test_task_assignment_carries_deadline_and_priority() {
+        let mut config = Config::default();
+        config.orchestrator.tasks.default_timeout_secs = 5;
+        let orchestrator = Arc::new(Orchestrator::new(config));
+        let (handle, mut worker_side) =
+            mock_worker_handle("default-0", ResourceCapabilities::default());
+        orchestrator.workers().write().await.push(handle);
+
+        let worker = tokio::spawn(async move {
+            let Ok(Message::TaskAssignment {
+                task_id,
+                deadline_ms_remaining,
+                priority,
+                ..
+            }) = crate::protocol::read_message(&mut worker_side).await
+            else {
+                panic!("expected TaskAssignment");
+            };
+            let reply = Message::TaskResult {
+                task_id,
+                success: true,
+                result: rmpv::Value::Nil,
+            };
+            crate::protocol::write_message(&mut worker_side, &reply)
+                .await
+                .unwrap();
+            (deadline_ms_remaining, priority)
+        });
+
+        let spec = spec_with_routes(&[("POST", "/work", "post_work")]);
+        let router = create_router_with_openapi(orchestrator, Some(spec), None, None);
+        let req = Request::builder()
+            .method("POST")
+            .uri("/work")
+            .header("content-type", "application/json")
+            .header(PRIORITY_HEADER, "7")
+            .body(Body::from(serde_json::json!({"args": {}}).to_string()))
+            .unwrap();
+        let response = router.oneshot(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let (deadline, priority) = worker.await.unwrap();
+        assert_eq!(priority, 7);
+        let deadline = deadline.expect("assignment should carry a deadline");
+        assert!(
+            deadline <= 5000 && deadline > 4000,
+            "deadline {}ms",
+            deadline
+        );
+    }
+
+    #[tokio::test]
+    async fn test_forwarded_task_id_adopted() {
+        let orchestrator = Arc::new(Orchestrator::new(Config::default()));
+        let (handle, mut worker_side) =
+            mock_worker_handle("default-0", ResourceCapabilities::default());
+        orchestrator.workers().write().await.push(handle);
+
+        let (assigned_tx, mut assigned_rx) = tokio::sync::mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            while let Ok(Message::TaskAssignment { task_id, .. }) =
+                crate::protocol::read_message(&mut worker_side).await
+            {
+                assigned_tx.send(task_id.clone()).unwrap();
+                let reply = Message::TaskResult {
+                    task_id,
+                    success: true,
+                    result: rmpv::Value::Nil,
+                };
+                crate::protocol::write_message(&mut worker_side, &reply)
+                    .await
+                    .unwrap();
+            }
+        });
+
+        let spec = spec_with_routes(&[("POST", "/work", "post_work")]);
+        let router = create_router_with_openapi(orchestrator, Some(spec), None, None);
+        let post = |task_id: Option<&str>| {
+            let mut req = Request::builder()
+                .method("POST")
+                .uri("/work")
+                .header("content-type", "application/json");
+            if let Some(task_id) = task_id {
+                req = req.header(TASK_ID_HEADER, task_id);
+            }
+            router
+                .clone()
+                .oneshot(req.body(Body::from(r#"{"args": {}}"#)).unwrap())
+        };
+
+        let gateway_id = "3f1c9a2e-5b7d-4e8f-9a0b-1c2d3e4f5a6b";
+        let response = post(Some(gateway_id)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[TASK_ID_HEADER], gateway_id);
+        assert_eq!(assigned_rx.recv().await.unwrap(), gateway_id);
+
+        // Without a usable forwarded ID the orchestrator generates its own
+        for forwarded in [None, Some("not a task id!")] {
+            let response = post(forwarded).await.unwrap();
+            let assigned = assigned_rx.recv().await.unwrap();
+            assert!(uuid::Uuid::parse_str(&assigned).is_ok(), "{}", assigned);
+            assert_ne!(assigned, gateway_id);
+            assert_eq!(response.headers()[TASK_ID_HEADER], assigned.as_str());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_forwarded_task_id_in_use_refused() {
+        let orchestrator = Arc::new(Orchestrator::new(Config::default()));
+        let (handle, worker_side) =
+            mock_worker_handle("default-0", ResourceCapabilities::default());
+        orchestrator.workers().write().await.push(handle);
+        spawn_echo_worker(worker_side, Duration::from_millis(300));
+
+        let spec = spec_with_routes(&[("POST", "/work", "post_work")]);
+        let router = create_router_with_openapi(orchestrator, Some(spec), None, None);
+        let post = || {
+            let req = Request::builder()
+                .method("POST")
+                .uri("/work")
+                .header("content-type", "application/json")
+                .header(TASK_ID_HEADER, "task-1")
+                .body(Body::from(r#"{"args": {}}"#))
+                .unwrap();
+            router.clone().oneshot(req)
+        };
+
+        let first = tokio::spawn(post());
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let response = post().await.unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        assert_eq!(first.await.unwrap().unwrap().status(), StatusCode::OK);
+
+        // Free again once the first task is done with it
+        assert_eq!(post().await.unwrap().status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_args_preview_logged_with_secrets_redacted() {
+        let mut config = Config::default();
+        config.orchestrator.tasks.args_preview =
+            Some(serde_yaml::from_str("max_chars: 200").unwrap());
+        let orchestrator = Arc::new(Orchestrator::new(config));
+        let (handle, worker_side) =
+            mock_worker_handle("default-0", ResourceCapabilities::default());
+        orchestrator.workers().write().await.push(handle);
+        spawn_echo_worker(worker_side, Duration::ZERO);
+
+        let events = CapturedEvents::default();
+        let _guard = events.install();
+
+        let spec = spec_with_routes(&[("POST", "/work", "post_work")]);
+        let router = create_router_with_openapi(orchestrator, Some(spec), None, None);
+        let args = serde_json::json!({"prompt": "hi", "auth": {"api_key": "sk-live-123"}});
+        let response = post_json(router, "/work", serde_json::json!({"args": args})).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let previews = events.with_field("args");
+        assert_eq!(previews.len(), 1);
+        let preview: serde_json::Value = serde_json::from_str(&previews[0]["args"]).unwrap();
+        assert_eq!(
+            preview,
+            serde_json::json!({"prompt": "hi", "auth": {"api_key": "***"}})
+        );
+    }
+
+    #[tokio::test]
+    async fn test_task_times_out() {
+        for action in [TimeoutAction::Recycle, TimeoutAction::Cancel] {
+            let mut config = Config::default();
+            config.orchestrator.tasks.default_timeout_secs = 1;
+            config.orchestrator.tasks.on_timeout = action;
+            let orchestrator = Arc::new(Orchestrator::new(config));
+            let (handle, mut worker_side) =
+                mock_worker_handle("default-0", ResourceCapabilities::default());
+            orchestrator.workers().write().await.push(handle);
+
+            // The mock worker never answers
+            let spec = spec_with_routes(&[("POST", "/work", "post_work")]);
+            let router = create_router_with_openapi(orchestrator.clone(), Some(spec), None, None);
+            let response =
+                post_json(router.clone(), "/work", serde_json::json!({"args": {}})).await;
+            assert_eq!(response.status(), StatusCode::GATEWAY_TIMEOUT);
+
+            // The worker is told to stop the task
+            let assignment = crate::protocol::read_message(&mut worker_side)
+                .await
+                .unwrap();
+            assert!(matches!(assignment, Message::TaskAssignment { .. }));
+            let cancel = crate::protocol::read_message(&mut worker_side)
+                .await
+                .unwrap();
+            assert!(matches!(cancel, Message::CancelTask { .. }));
+
+            // A worker kept in service is only handed more work once it has
+            // answered for the timed-out task
+            if action == TimeoutAction::Cancel {
+                let Message::TaskAssignment { task_id, .. } = assignment else {
+                    unreachable!()
+                };
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                assert_eq!(
+                    orchestrator.workers().read().await[0].worker.state,
+                    crate::worker::WorkerState::Busy
+                );
+                let late = Message::TaskResult {
+                    task_id,
+                    success: true,
+                    result: rmpv::Value::Nil,
+                };
+                crate::protocol::write_message(&mut worker_side, &late)
+                    .await
+                    .unwrap();
+            }
+            let expected = match action {
+                TimeoutAction::Recycle => crate::worker::WorkerState::Stuck,
+                TimeoutAction::Cancel => crate::worker::WorkerState::Idle,
+            };
+            wait_for_worker_state(&orchestrator.workers(), 0, expected).await;
+            assert_eq!(
+                orchestrator.workers().read().await[0]
+                    .worker
+                    .allocation
+                    .allocated_cpus,
+                0.0
+            );
+
+            // A stuck worker gets no more tasks until it is replaced
+            let response = post_json(router, "/work", serde_json::json!({"args": {}})).await;
+            let expected = match action {
+                TimeoutAction::Recycle => StatusCode::SERVICE_UNAVAILABLE,
+                TimeoutAction::Cancel => StatusCode::GATEWAY_TIMEOUT,
+            };
+            assert_eq!(response.status(), expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unadvertised_handler_rejected_before_dispatch() {
+        let orchestrator = Arc::new(Orchestrator::new(Config::default()));
+        let (mut handle, mut worker_side) =
+            mock_worker_handle("default-0", ResourceCapabilities::default());
+        handle.worker.handlers = Some(HashSet::from(["other".to_string()]));
+        orchestrator.workers().write().await.push(handle);
+        orchestrator.refresh_handlers().await;
+
+        let spec = spec_with_routes(&[("POST", "/work", "post_work")]);
+        let router = create_router_with_openapi(orchestrator.clone(), Some(spec), None, None);
+
+        let start = Instant::now();
+        let response = post_json(router, "/work", serde_json::json!({"args": {}})).await;
+        assert_eq!(response.status(), StatusCode::NOT_IMPLEMENTED);
+        assert!(start.elapsed() < Duration::from_millis(500));
+
+        // The worker never heard about the request
+        let contact = tokio::time::timeout(
+            Duration::from_millis(100),
+            crate::protocol::read_message(&mut worker_side),
+        )
+        .await;
+        assert!(contact.is_err(), "worker was contacted: {:?}", contact);
+    }
+
+    #[tokio::test]
+    async fn test_response_schema_enforced() {
+        let spec: OpenApiSpec = serde_json::from_value(serde_json::json!({
+            "openapi": "3.0.0",
+            "info": {"title": "test", "version": "1.0.0"},
+            "paths": {"/work": {"post": {
+                "operationId": "post_work",
+                "responses": {"200": {
+                    "description": "ok",
+                    "content": {"application/json": {"schema": {
+                        "type": "object",
+                        "properties": {"n": {"type": "integer"}}
+                    }}}
+                }}
+            }}}
+        }))
+        .unwrap();
+
+        for enforce in [true, false] {
+            let mut config = Config::default();
+            config.orchestrator.http.enforce_response_schema = enforce;
+            let orchestrator = Arc::new(Orchestrator::new(config));
+            let (handle, worker_side) =
+                mock_worker_handle("default-0", ResourceCapabilities::default());
+            orchestrator.workers().write().await.push(handle);
+            spawn_echo_worker(worker_side, Duration::ZERO);
+
+            // The echo worker returns the args, including the undeclared field
+            let router = create_router_with_openapi(orchestrator, Some(spec.clone()), None, None);
+            let args = serde_json::json!({"n": 1, "secret": "internal"});
+            let response = post_json(router, "/work", serde_json::json!({"args": args})).await;
+            let body = json_body(response).await;
+
+            let expected = if enforce {
+                serde_json::json!({"n": 1})
+            } else {
+                args
+            };
+            assert_eq!(body["result"], expected, "enforce={}", enforce);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_patched_route_served_immediately() {
+        let mut config = Config::default();
+        config.orchestrator.http.allow_route_patching = true;
+        let orchestrator = Arc::new(Orchestrator::new(config));
+        let (handle, worker_side) =
+            mock_worker_handle("default-0", ResourceCapabilities::default());
+        orchestrator.workers().write().await.push(handle);
+        spawn_echo_worker(worker_side, Duration::ZERO);
+
+        let spec = spec_with_routes(&[("POST", "/work", "post_work")]);
+        let router = create_router_with_openapi(orchestrator, Some(spec), None, None);
+        let patch_route = |route: serde_json::Value| {
+            let req = Request::builder()
+                .method("PATCH")
+                .uri("/admin/routes")
+                .header("content-type", "application/json")
+                .body(Body::from(route.to_string()))
+                .unwrap();
+            router.clone().oneshot(req)
+        };
+
+        let response = post_json(router.clone(), "/items/7", serde_json::json!({"args": {}})).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let route = serde_json::json!({"path": "/items/{id}", "method": "POST", "handler_name": "post_item"});
+        let response = patch_route(route.clone()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert_eq!(json_body(response).await["path"], "/items/:id");
+
+        let response = post_json(
+            router.clone(),
+            "/items/7",
+            serde_json::json!({"args": {"n": 1}}),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(json_body(response).await["result"]["n"], 1);
+
+        // Patching the same route again updates it in place
+        let mut update = route;
+        update["max_body_bytes"] = serde_json::json!(8);
+        assert_eq!(patch_route(update).await.unwrap().status(), StatusCode::OK);
+        let response = post_json(
+            router.clone(),
+            "/items/7",
+            serde_json::json!({"args": {"n": 1}}),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        // Spec and built-in routes can't be replaced or shadowed, nor can patched
+        // routes overlap each other
+        for path in ["/work", "/{anything}", "/admin/{page}", "/items/{item_id}"] {
+            let conflict =
+                serde_json::json!({"path": path, "method": "GET", "handler_name": "get_x"});
+            assert_eq!(
+                patch_route(conflict).await.unwrap().status(),
+                StatusCode::CONFLICT,
+                "{}",
+                path
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_saturated_gpu_route_overflows_to_cpu_pool() {
+        let spec_with_overflow = |overflow_pool: Option<&str>| -> OpenApiSpec {
+            serde_json::from_value(serde_json::json!({
+                "openapi": "3.0.0",
+                "info": {"title": "test", "version": "1.0.0"},
+                "paths": {
+                    "/embed": {"post": {
+                        "operationId": "post_embed",
+                        "x-neutrino-resources": {"num_cpus": 1, "num_gpus": 1, "memory_gb": 1},
+                        "x-neutrino-overflow-pool": overflow_pool,
+                    }}
+                }
+            }))
+            .unwrap()
+        };
+        let gpu = ResourceCapabilities {
+            num_gpus: 1.0,
+            ..ResourceCapabilities::default()
+        };
+
+        for overflow_pool in [None, Some("cpu")] {
+            let orchestrator = Arc::new(Orchestrator::new(Config::default()));
+            let (mut gpu_handle, _gpu_side) = mock_worker_handle("gpu-0", gpu.clone());
+            let (cpu_handle, cpu_side) =
+                mock_worker_handle("cpu-0", ResourceCapabilities::default());
+            spawn_echo_worker(cpu_side, Duration::ZERO);
+
+            // The only GPU is taken by a running task
+            gpu_handle
+                .worker
+                .allocation
+                .allocate(&ResourceRequirements {
+                    num_cpus: 1.0,
+                    num_gpus: 1.0,
+                    memory_gb: 1.0,
+                    gpu_memory_gb: 0.0,
+                });
+            gpu_handle.worker.state = crate::worker::WorkerState::Busy;
+            orchestrator
+                .workers()
+                .write()
+                .await
+                .extend([gpu_handle, cpu_handle]);
+
+            let router = create_router_with_openapi(
+                Arc::clone(&orchestrator),
+                Some(spec_with_overflow(overflow_pool)),
+                None,
+                None,
+            );
+            let response = post_json(router, "/embed", serde_json::json!({"args": {}})).await;
+
+            let workers = orchestrator.workers();
+            let workers = workers.read().await;
+            if overflow_pool.is_none() {
+                assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+                assert_eq!(workers[1].worker.tasks_completed, 0);
+            } else {
+                assert_eq!(response.status(), StatusCode::OK);
+                assert_eq!(workers[1].worker.tasks_completed, 1);
+                // The overflow worker never had GPUs allocated to it
+                assert_eq!(workers[1].worker.allocation.allocated_gpus, 0.0);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_per_route_body_limit() {
+        let spec: OpenApiSpec = serde_json::from_value(serde_json::json!({
+            "openapi": "3.0.0",
+            "info": {"title": "test", "version": "1.0.0"},
+            "paths": {
+                "/classify": {"post": {"operationId": "post_classify", "x-neutrino-max-body-bytes": 1024}},
+                "/upload": {"post": {"operationId": "post_upload", "x-neutrino-max-body-bytes": 64 * 1024}}
+            }
+        }))
+        .unwrap();
+
+        let mut config = Config::default();
+        config.orchestrator.http.max_body_bytes = 4 * 1024;
+        let orchestrator = Arc::new(Orchestrator::new(config));
+        let (handle, worker_side) =
+            mock_worker_handle("default-0", ResourceCapabilities::default());
+        orchestrator.workers().write().await.push(handle);
+        spawn_echo_worker(worker_side, Duration::ZERO);
+        let router = create_router_with_openapi(orchestrator, Some(spec), None, None);
+
+        let body_of = |size: usize| serde_json::json!({"args": {"data": "x".repeat(size)}});
+
+        // 8KB is over /classify's 1KB but within /upload's 64KB (and over the 4KB global)
+        let response = post_json(router.clone(), "/classify", body_of(8 * 1024)).await;
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let response = post_json(router.clone(), "/upload", body_of(8 * 1024)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = post_json(router.clone(), "/classify", body_of(100)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = post_json(router, "/upload", body_of(128 * 1024)).await;
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[tokio::test]
+    async fn test_selection_logged_for_fallback_pass() {
+        // The pool's only worker is its min_idle reserve, so it is only picked
+        // by the fallback pass
+        let mut config = Config::default();
+        config.orchestrator.worker_pools = vec![crate::config::WorkerPoolConfig {
+            name: "cpu".to_string(),
+            count: 1,
+            resources: ResourceCapabilities::default(),
+            gpu_devices: vec![],
+            cpuset: None,
+            min_idle: 1,
+            max_count: None,
+            min_count: None,
+            scale_up_utilization: 0.8,
+            scale_down_utilization: 0.2,
+            max_total_memory_mb: None,
+            allow_overflow_from: None,
+            allow_overflow_to: None,
+            warmup_handler: None,
+            warmup_timeout_secs: 300,
+        }];
+        let orchestrator = Arc::new(Orchestrator::new(config));
+        let (handle, worker_side) = mock_worker_handle("cpu-0", ResourceCapabilities::default());
+        orchestrator.workers().write().await.push(handle);
+        spawn_echo_worker(worker_side, Duration::ZERO);
+
+        let events = CapturedEvents::default();
+        let _guard = events.install();
+
+        let spec = spec_with_routes(&[("POST", "/work", "post_work")]);
+        let router = create_router_with_openapi(orchestrator, Some(spec), None, None);
+        let response = post_json(router, "/work", serde_json::json!({"args": {}})).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let selections = events.with_field("selection_pass");
+        assert_eq!(selections.len(), 1);
+        let fields = &selections[0];
+        assert_eq!(fields["selection_pass"], "fallback");
+        assert_eq!(fields["pool"], "cpu");
+        assert_eq!(fields["worker_id"], "cpu-0");
+        assert_eq!(fields["handler"], "work");
+        for headroom in ["headroom_cpus", "headroom_gpus", "headroom_memory_gb"] {
+            assert!(fields.contains_key(headroom), "missing {}", headroom);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_scalar_result_served_with_default_content_type() {
+        let spec: OpenApiSpec = serde_json::from_value(serde_json::json!({
+            "openapi": "3.0.0",
+            "info": {"title": "test", "version": "1.0.0"},
+            "paths": {"/greet": {"post": {
+                "operationId": "post_greet",
+                "x-neutrino-default-result-content-type": "text/plain"
+            }}}
+        }))
+        .unwrap();
+
+        let orchestrator = Arc::new(Orchestrator::new(Config::default()));
+        let (handle, worker_side) =
+            mock_worker_handle("default-0", ResourceCapabilities::default());
+        orchestrator.workers().write().await.push(handle);
+        spawn_echo_worker(worker_side, Duration::ZERO);
+        let router = create_router_with_openapi(orchestrator, Some(spec), None, None);
+
+        // The echo worker returns the args, here a plain string
+        let response = post_json(
+            router.clone(),
+            "/greet",
+            serde_json::json!({"args": "hello"}),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[axum::http::header::CONTENT_TYPE],
+            "text/plain"
+        );
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&bytes[..], b"hello");
+
+        // Structured results keep the JSON envelope
+        let response = post_json(
+            router,
+            "/greet",
+            serde_json::json!({"args": {"greeting": "hello"}}),
+        )
+        .await;
+        let body = json_body(response).await;
+        assert_eq!(body["result"], serde_json::json!({"greeting": "hello"}));
+    }
+
+    #[tokio::test]
+    async fn test_admin_workers_reports_queue_depth() {
+        let orchestrator = Arc::new(Orchestrator::new(Config::default()));
+        let (mut handle, worker_side) =
+            mock_worker_handle("default-0", ResourceCapabilities::default());
+        spawn_queued_worker(worker_side, 7);
+        handle.heartbeat(Duration::from_secs(1)).await.unwrap();
+        orchestrator.workers().write().await.push(handle);
+
+        let router = create_router(orchestrator);
+        let req = Request::builder()
+            .uri("/admin/workers")
+            .body(Body::empty())
+            .unwrap();
+        let body = json_body(router.oneshot(req).await.unwrap()).await;
+
+        assert_eq!(body["workers"][0]["id"], "default-0");
+        assert_eq!(body["workers"][0]["pool"], "default");
+        assert_eq!(body["workers"][0]["queue_depth"], 7);
+    }
+
+    #[tokio::test]
+    async fn test_scale_pool_down_retires_idle_workers_first() {
+        let response = post_json(
+            create_router(Arc::new(Orchestrator::new(Config::default()))),
+            "/admin/pools/default/scale",
+            serde_json::json!({"count": 1}),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let mut config = Config::default();
+        config.orchestrator.http.allow_pool_scaling = true;
+        let orchestrator = Arc::new(Orchestrator::new(config));
+        // Worker sides stay open while the retired workers drain
+        let mut worker_sides = Vec::new();
+        for id in ["default-0", "default-1", "default-2"] {
+            let (mut handle, worker_side) = mock_worker_handle(id, ResourceCapabilities::default());
+            if id == "default-2" {
+                handle.worker.state = crate::worker::WorkerState::Busy;
+            }
+            orchestrator.workers().write().await.push(handle);
+            worker_sides.push(worker_side);
+        }
+        let router = create_router(Arc::clone(&orchestrator));
+
+        let response = post_json(
+            router.clone(),
+            "/admin/pools/default/scale",
+            serde_json::json!({"count": 1}),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = json_body(response).await;
+        assert_eq!(body["previous"], 3);
+        assert_eq!(body["count"], 1);
+        assert_eq!(body["added"], serde_json::json!([]));
+        assert_eq!(
+            body["retiring"],
+            serde_json::json!(["default-0", "default-1"])
+        );
+
+        // The busy worker stays in service
+        let remaining: Vec<String> = orchestrator
+            .worker_status()
+            .await
+            .into_iter()
+            .map(|w| w.id)
+            .collect();
+        assert_eq!(remaining, vec!["default-2"]);
+
+        // Already at the requested count
+        let body = json_body(
+            post_json(
+                router.clone(),
+                "/admin/pools/default/scale",
+                serde_json::json!({"count": 1}),
+            )
+            .await,
+        )
+        .await;
+        assert_eq!(body["retiring"], serde_json::json!([]));
+
+        let response = post_json(
+            router,
+            "/admin/pools/gpu/scale",
+            serde_json::json!({"count": 1}),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_scale_pool_rejects_count_outside_bounds() {
+        let mut config = Config::default();
+        config.orchestrator.worker_pools = vec![crate::config::WorkerPoolConfig {
+            min_count: Some(1),
+            max_count: Some(4),
+            ..config.effective_worker_pools().remove(0)
+        }];
+        config.orchestrator.http.allow_pool_scaling = true;
+        let router = create_router(Arc::new(Orchestrator::new(config)));
+
+        for count in [0, 5] {
+            let response = post_json(
+                router.clone(),
+                "/admin/pools/default/scale",
+                serde_json::json!({"count": count}),
+            )
+            .await;
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+            let body = json_body(response).await;
+            assert_eq!(
+                body["violations"],
+                serde_json::json!(["pool 'default' is bounded to 1..=4 workers"])
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rolling_restart_waits_for_busy_worker() {
+        let response = post_json(
+            create_router(Arc::new(Orchestrator::new(Config::default()))),
+            "/admin/workers/restart",
+            serde_json::json!({}),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let mut config = Config::default();
+        config.orchestrator.http.allow_worker_restart = true;
+        let orchestrator = Arc::new(Orchestrator::new(config));
+        let (mut handle, _worker_side) =
+            mock_worker_handle("default-0", ResourceCapabilities::default());
+        handle.worker.state = crate::worker::WorkerState::Busy;
+        orchestrator.workers().write().await.push(handle);
+        let router = create_router(Arc::clone(&orchestrator));
+
+        let response = post_json(
+            router.clone(),
+            "/admin/workers/restart",
+            serde_json::json!({}),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        assert_eq!(
+            json_body(response).await["workers"],
+            serde_json::json!(["default-0"])
+        );
+
+        // The busy worker keeps its task, and only one rollout runs at a time
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(orchestrator.worker_count().await, 1);
+        let response = post_json(router, "/admin/workers/restart", serde_json::json!({})).await;
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+    }
+
+    #[tokio::test]
+    async fn test_session_affinity_from_arg_or_header() {
+        let orchestrator = Arc::new(Orchestrator::new(Config::default()));
+        for id in ["default-0", "default-1", "default-2"] {
+            let (handle, worker_side) = mock_worker_handle(id, ResourceCapabilities::default());
+            orchestrator.workers().write().await.push(handle);
+            spawn_echo_worker(worker_side, Duration::ZERO);
+        }
+        let mut spec = spec_with_routes(&[("POST", "/chat", "chat")]);
+        spec.paths
+            .get_mut("/chat")
+            .unwrap()
+            .post
+            .as_mut()
+            .unwrap()
+            .affinity_key = Some("session_id".to_string());
+        let router = create_router_with_openapi(Arc::clone(&orchestrator), Some(spec), None, None);
+
+        // The session named in the body, then the same one in the header
+        for _ in 0..3 {
+            let args = serde_json::json!({"args": {"session_id": "abc", "prompt": "hi"}});
+            assert_eq!(
+                post_json(router.clone(), "/chat", args).await.status(),
+                StatusCode::OK
+            );
+        }
+        let req = Request::builder()
+            .method("POST")
+            .uri("/chat")
+            .header("content-type", "application/json")
+            .header(AFFINITY_HEADER, "abc")
+            .body(Body::from(
+                serde_json::json!({"args": {"prompt": "hi"}}).to_string(),
+            ))
+            .unwrap();
+        assert_eq!(router.oneshot(req).await.unwrap().status(), StatusCode::OK);
+
+        let mut completed: Vec<u32> = orchestrator
+            .worker_status()
+            .await
+            .iter()
+            .map(|w| w.tasks_completed)
+            .collect();
+        completed.sort_unstable();
+        assert_eq!(completed, vec![0, 0, 4]);
+    }
+
+    #[tokio::test]
+    async fn test_worker_allocation_history_in_order() {
+        let orchestrator = Arc::new(Orchestrator::new(Config::default()));
+        let (handle, worker_side) =
+            mock_worker_handle("default-0", ResourceCapabilities::default());
+        orchestrator.workers().write().await.push(handle);
+        spawn_echo_worker(worker_side, Duration::ZERO);
+        let spec = spec_with_routes(&[("POST", "/work", "post_work")]);
+        let router = create_router_with_openapi(orchestrator, Some(spec), None, None);
+
+        for task_id in ["task-1", "task-2", "task-3"] {
+            let req = Request::builder()
+                .method("POST")
+                .uri("/work")
+                .header("content-type", "application/json")
+                .header(TASK_ID_HEADER, task_id)
+                .body(Body::from(serde_json::json!({"args": {}}).to_string()))
+                .unwrap();
+            assert_eq!(
+                router.clone().oneshot(req).await.unwrap().status(),
+                StatusCode::OK
+            );
+        }
+
+        let req = Request::builder()
+            .uri("/admin/workers/default-0/allocations")
+            .body(Body::empty())
+            .unwrap();
+        let body = json_body(router.clone().oneshot(req).await.unwrap()).await;
+        let events = body["allocations"].as_array().unwrap();
+        let summary: Vec<(&str, &str, f64)> = events
+            .iter()
+            .map(|event| {
+                (
+                    event["task_id"].as_str().unwrap(),
+                    event["change"].as_str().unwrap(),
+                    event["allocation"]["allocated_cpus"].as_f64().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("task-1", "allocate", 1.0),
+                ("task-1", "deallocate", 0.0),
+                ("task-2", "allocate", 1.0),
+                ("task-2", "deallocate", 0.0),
+                ("task-3", "allocate", 1.0),
+                ("task-3", "deallocate", 0.0),
+            ]
+        );
+        assert!(events
+            .windows(2)
+            .all(|pair| pair[0]["timestamp_ms"].as_u64() <= pair[1]["timestamp_ms"].as_u64()));
+
+        let req = Request::builder()
+            .uri("/admin/workers/missing-0/allocations")
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(
+            router.oneshot(req).await.unwrap().status(),
+            StatusCode::NOT_FOUND
+        );
+    }
+
+    #[tokio::test]
+    async fn test_identical_requests_coalesced_into_one_dispatch() {
+        let spec: OpenApiSpec = serde_json::from_value(serde_json::json!({
+            "openapi": "3.0.0",
+            "info": {"title": "test", "version": "1.0.0"},
+            "paths": {
+                "/prediction": {"get": {"operationId": "get_prediction", "x-neutrino-coalesce": true}},
+                "/fresh": {"get": {"operationId": "get_fresh"}}
+            }
+        }))
+        .unwrap();
+
+        let orchestrator = Arc::new(Orchestrator::new(Config::default()));
+        let (handle, worker_side) =
+            mock_worker_handle("default-0", ResourceCapabilities::default());
+        orchestrator.workers().write().await.push(handle);
+        spawn_echo_worker(worker_side, Duration::from_millis(200));
+        let router = create_router_with_openapi(Arc::clone(&orchestrator), Some(spec), None, None);
+
+        let fire = |uri: &'static str, n: usize| {
+            let requests = (0..n).map(|_| {
+                let router = router.clone();
+                tokio::spawn(async move {
+                    let req = Request::builder().uri(uri).body(Body::empty()).unwrap();
+                    router.oneshot(req).await.unwrap().status()
+                })
+            });
+            futures_util::future::join_all(requests)
+        };
+        let tasks_completed = || async {
+            orchestrator.workers().read().await[0]
+                .worker
+                .tasks_completed
+        };
+
+        // Eight identical requests in flight together share one worker task
+        for status in fire("/prediction", 8).await {
+            assert_eq!(status.unwrap(), StatusCode::OK);
+        }
+        assert_eq!(tasks_completed().await, 1);
+
+        // Routes without the extension dispatch every request
+        for status in fire("/fresh", 3).await {
+            assert_eq!(status.unwrap(), StatusCode::OK);
+        }
+        assert_eq!(tasks_completed().await, 4);
+    }
+
+    #[tokio::test]
+    async fn test_tasks_on_different_workers_run_in_parallel() {
+        let orchestrator = Arc::new(Orchestrator::new(Config::default()));
+        for id in ["default-0", "default-1"] {
+            let (handle, worker_side) = mock_worker_handle(id, ResourceCapabilities::default());
+            orchestrator.workers().write().await.push(handle);
+            spawn_echo_worker(worker_side, Duration::from_millis(300));
+        }
+        let spec = spec_with_routes(&[("POST", "/work", "post_work")]);
+        let router = create_router_with_openapi(Arc::clone(&orchestrator), Some(spec), None, None);
+
+        let start = Instant::now();
+        let (first, second) = tokio::join!(
+            post_json(
+                router.clone(),
+                "/work",
+                serde_json::json!({"args": {"n": 1}})
+            ),
+            post_json(
+                router.clone(),
+                "/work",
+                serde_json::json!({"args": {"n": 2}})
+            ),
+        );
+        let elapsed = start.elapsed();
+
+        let (first, second) = (json_body(first).await, json_body(second).await);
+        assert_ne!(first["worker_id"], second["worker_id"]);
+        assert!(
+            elapsed < Duration::from_millis(550),
+            "tasks ran one after the other: {:?}",
+            elapsed
+        );
+
+        // The workers lock stays free while the tasks run
+        let slow = tokio::spawn(post_json(router, "/work", serde_json::json!({"args": {}})));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let workers = tokio::time::timeout(
+            Duration::from_millis(50),
+            orchestrator.workers().write_owned(),
+        )
+        .await
+        .expect("workers lock held during the task");
+        drop(workers);
+        assert_eq!(slow.await.unwrap().status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_task_frees_its_worker() {
+        let orchestrator = Arc::new(Orchestrator::new(Config::default()));
+        let (handle, mut worker_side) =
+            mock_worker_handle("default-0", ResourceCapabilities::default());
+        orchestrator.workers().write().await.push(handle);
+
+        // A worker that can't stop a task part-way, reporting what it is sent
+        // and finishing a cancelled task once told to
+        let (messages_tx, mut messages) = tokio::sync::mpsc::unbounded_channel();
+        let (finish_tx, finish_rx) = tokio::sync::oneshot::channel::<()>();
+        tokio::spawn(async move {
+            let mut finish = Some(finish_rx);
+            while let Ok(msg) = crate::protocol::read_message(&mut worker_side).await {
+                let cancelled = match &msg {
+                    Message::CancelTask { task_id } => Some(task_id.clone()),
+                    _ => None,
+                };
+                let _ = messages_tx.send(msg);
+                if let Some(task_id) = cancelled {
+                    if let Some(finish) = finish.take() {
+                        let _ = finish.await;
+                    }
+                    let reply = Message::TaskResult {
+                        task_id,
+                        success: true,
+                        result: rmpv::Value::Nil,
+                    };
+                    crate::protocol::write_message(&mut worker_side, &reply)
+                        .await
+                        .unwrap();
+                }
+            }
+        });
+
+        let spec = spec_with_routes(&[("POST", "/work", "post_work")]);
+        let router = create_router_with_openapi(Arc::clone(&orchestrator), Some(spec), None, None);
+        let req = Request::builder()
+            .method("POST")
+            .uri("/work")
+            .header("content-type", "application/json")
+            .header(TASK_ID_HEADER, "task-1")
+            .body(Body::from(serde_json::json!({"args": {}}).to_string()))
+            .unwrap();
+        let running = tokio::spawn(router.clone().oneshot(req));
+        assert!(matches!(
+            messages.recv().await,
+            Some(Message::TaskAssignment { .. })
+        ));
+
+        let cancel = |task_id: &str| {
+            let req = Request::builder()
+                .method("DELETE")
+                .uri(format!("/tasks/{}", task_id))
+                .body(Body::empty())
+                .unwrap();
+            router.clone().oneshot(req)
+        };
+        let response = cancel("task-1").await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(json_body(response).await["status"], "cancelled");
+
+        let response = tokio::time::timeout(Duration::from_secs(2), running)
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        match tokio::time::timeout(Duration::from_secs(2), messages.recv())
+            .await
+            .unwrap()
+        {
+            Some(Message::CancelTask { task_id }) => assert_eq!(task_id, "task-1"),
+            other => panic!("expected CancelTask, got {:?}", other),
+        }
+        // The worker is still running the task, so it stays busy until it answers
+        assert_eq!(
+            orchestrator.workers().read().await[0].worker.state,
+            crate::worker::WorkerState::Busy
+        );
+        finish_tx.send(()).unwrap();
+        wait_for_worker_state(&orchestrator.workers(), 0, crate::worker::WorkerState::Idle).await;
+        assert_eq!(
+            orchestrator.workers().read().await[0]
+                .worker
+                .allocation
+                .allocated_cpus,
+            0.0
+        );
+
+        // Only tasks in flight can be cancelled
+        assert_eq!(
+            cancel("task-1").await.unwrap().status(),
+            StatusCode::NOT_FOUND
+        );
+    }
+
+    #[tokio::test]
+    async fn test_allocation_released_exactly_once_on_every_path() {
+        let capabilities = ResourceCapabilities {
+            num_cpus: 4.0,
+            memory_gb: 8.0,
+            ..Default::default()
+        };
+        let spec = spec_with_routes(&[("POST", "/work", "post_work")]);
+
+        for path in ["success", "send failure", "recv failure", "cancelled"] {
+            let orchestrator = Arc::new(Orchestrator::new(Config::default()));
+            let (mut handle, mut worker_side) =
+                mock_worker_handle("default-0", capabilities.clone());
+            // Resources held by another task, which must survive this one
+            let other = ResourceRequirements {
+                num_cpus: 1.0,
+                memory_gb: 2.0,
+                ..Default::default()
+            };
+            handle.worker.allocation.allocate(&other);
+            orchestrator.workers().write().await.push(handle);
+
+            let (assigned_tx, assigned_rx) = tokio::sync::oneshot::channel();
+            match path {
+                "success" => drop(spawn_echo_worker(worker_side, Duration::ZERO)),
+                "send failure" => drop(worker_side),
+                _ => drop(tokio::spawn(async move {
+                    let assignment = crate::protocol::read_message(&mut worker_side)
+                        .await
+                        .unwrap();
+                    let _ = assigned_tx.send(());
+                    // Hang up for a receive failure, otherwise answer once told to cancel
+                    if path == "cancelled" {
+                        let Message::TaskAssignment { task_id, .. } = assignment else {
+                            unreachable!()
+                        };
+                        let cancel = crate::protocol::read_message(&mut worker_side).await;
+                        assert!(matches!(cancel, Ok(Message::CancelTask { .. })));
+                        let reply = Message::TaskResult {
+                            task_id,
+                            success: true,
+                            result: rmpv::Value::Nil,
+                        };
+                        crate::protocol::write_message(&mut worker_side, &reply)
+                            .await
+                            .unwrap();
+                        std::future::pending::<()>().await;
+                    }
+                })),
+            }
+
+            let router =
+                create_router_with_openapi(orchestrator.clone(), Some(spec.clone()), None, None);
+            let request = tokio::spawn(post_json(router, "/work", serde_json::json!({"args": {}})));
+            if path == "cancelled" {
+                assigned_rx.await.unwrap();
+                request.abort();
+                assert!(request.await.unwrap_err().is_cancelled());
+            } else {
+                let status = request.await.unwrap().status();
+                assert_eq!(
+                    status.is_success(),
+                    path == "success",
+                    "{}: {}",
+                    path,
+                    status
+                );
+            }
+
+            // A worker kept in service is released once it answers for the task
+            let state = match path {
+                "send failure" | "recv failure" => crate::worker::WorkerState::Stuck,
+                _ => crate::worker::WorkerState::Idle,
+            };
+            wait_for_worker_state(&orchestrator.workers(), 0, state).await;
+            let workers = orchestrator.workers();
+            let workers = workers.read().await;
+            let allocation = &workers[0].worker.allocation;
+            assert_eq!(
+                (
+                    allocation.allocated_cpus,
+                    allocation.allocated_memory_gb,
+                    allocation.allocated_gpus
+                ),
+                (1.0, 2.0, 0.0),
+                "{}",
+                path
+            );
+            // A worker whose connection failed is taken out of service for replacement
+            assert_eq!(workers[0].worker.state, state, "{}", path);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reduced_capabilities_stop_over_allocation() {
+        let orchestrator = Arc::new(Orchestrator::new(Config::default()));
+        let capabilities = ResourceCapabilities {
+            num_cpus: 2.0,
+            memory_gb: 8.0,
+            ..Default::default()
+        };
+        let (handle, mut worker_side) = mock_worker_handle("default-0", capabilities.clone());
+        orchestrator.workers().write().await.push(handle);
+
+        // The worker loses a CPU mid-task and says so before answering
+        tokio::spawn(async move {
+            let Ok(Message::TaskAssignment { task_id, .. }) =
+                crate::protocol::read_message(&mut worker_side).await
+            else {
+                panic!("expected TaskAssignment");
+            };
+            let reduced = ResourceCapabilities {
+                num_cpus: 1.0,
+                ..capabilities
+            };
+            let update = Message::UpdateCapabilities {
+                capabilities: reduced,
+            };
+            crate::protocol::write_message(&mut worker_side, &update)
+                .await
+                .unwrap();
+            let reply = Message::TaskResult {
+                task_id,
+                success: true,
+                result: rmpv::Value::Nil,
+            };
+            crate::protocol::write_message(&mut worker_side, &reply)
+                .await
+                .unwrap();
+            spawn_echo_worker(worker_side, Duration::ZERO);
+        });
+
+        let spec = spec_with_routes(&[("POST", "/work", "post_work")]);
+        let router = create_router_with_openapi(orchestrator.clone(), Some(spec), None, None);
+        let response = post_json(router, "/work", serde_json::json!({"args": {}})).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            orchestrator.workers().read().await[0]
+                .worker
+                .capabilities
+                .num_cpus,
+            1.0
+        );
+
+        // With one CPU taken, a second one-CPU task no longer fits on the worker
+        let one_cpu = ResourceRequirements::default();
+        orchestrator.workers().write().await[0]
+            .worker
+            .allocation
+            .allocate(&one_cpu);
+        assert!(orchestrator
+            .find_worker_with_resources(&one_cpu, None, &Placement::default())
+            .await
+            .is_none());
+    }
+
+    /// Worker answering every task with `result`
+    fn spawn_result_worker(mut stream: tokio::net::UnixStream, result: rmpv::Value) {
+        tokio::spawn(async move {
+            while let Ok(Message::TaskAssignment { task_id, .. }) =
+                crate::protocol::read_message(&mut stream).await
+            {
+                let result = result.clone();
+                let reply = Message::TaskResult {
+                    task_id,
+                    success: true,
+                    result,
+                };
+                if crate::protocol::write_message(&mut stream, &reply)
+                    .await
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        });
+    }
+
+    /// A result containing a msgpack ext type, which has no JSON form
+    fn ext_result() -> rmpv::Value {
+        rmpv::Value::Map(vec![
+            ("tokens".into(), rmpv::Value::from(42)),
+            ("tensor".into(), rmpv::Value::Ext(5, vec![1, 2, 3])),
+        ])
+    }
+
+    async fn unconvertible_result_router(policy: &str) -> Router {
+        let mut config = Config::default();
+        config.orchestrator.tasks.unconvertible_results = serde_yaml::from_str(policy).unwrap();
+        let orchestrator = Arc::new(Orchestrator::new(config));
+        let (handle, worker_side) =
+            mock_worker_handle("default-0", ResourceCapabilities::default());
+        orchestrator.workers().write().await.push(handle);
+        spawn_result_worker(worker_side, ext_result());
+
+        let spec = spec_with_routes(&[("POST", "/work", "post_work")]);
+        create_router_with_openapi(orchestrator, Some(spec), None, None)
+    }
+
+    fn msgpack_request() -> Request {
+        Request::builder()
+            .method("POST")
+            .uri("/work")
+            .header("content-type", "application/json")
+            .header("accept", "application/msgpack")
+            .body(Body::from(serde_json::json!({"args": {}}).to_string()))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_unconvertible_result_under_error_policy() {
+        let router = unconvertible_result_router("error").await;
+        let response = post_json(router, "/work", serde_json::json!({"args": {}})).await;
+        assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+        let error = json_body(response).await["error"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        assert!(
+            error.contains("handler work") && error.contains("Extension types"),
+            "{}",
+            error
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unconvertible_result_under_msgpack_policy() {
+        let router = unconvertible_result_router("msgpack").await;
+
+        // Clients accepting msgpack get the result exactly as the worker sent it
+        let response = router.clone().oneshot(msgpack_request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["content-type"], "application/msgpack");
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body = rmpv::decode::read_value(&mut &bytes[..]).unwrap();
+        let field = |name: &str| {
+            let (_, value) = body
+                .as_map()
+                .unwrap()
+                .iter()
+                .find(|(k, _)| k.as_str() == Some(name))
+                .unwrap();
+            value.clone()
+        };
+        assert_eq!(field("success"), rmpv::Value::Boolean(true));
+        assert_eq!(field("result"), ext_result());
+
+        // JSON clients can't represent it
+        let response = post_json(router, "/work", serde_json::json!({"args": {}})).await;
+        assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+        let error = json_body(response).await["error"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        assert!(error.contains("Accept: application/msgpack"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn test_msgpack_clients_get_result_without_json_round_trip() {
+        // Binary and whole floats have a JSON form, but not one that survives the trip back
+        let result = rmpv::Value::Map(vec![
+            ("blob".into(), rmpv::Value::Binary(vec![0, 255])),
+            ("ratio".into(), rmpv::Value::F64(1.0)),
+        ]);
+        let orchestrator = Arc::new(Orchestrator::new(Config::default()));
+        let (handle, worker_side) =
+            mock_worker_handle("default-0", ResourceCapabilities::default());
+        orchestrator.workers().write().await.push(handle);
+        spawn_result_worker(worker_side, result.clone());
+        let spec = spec_with_routes(&[("POST", "/work", "post_work")]);
+        let router = create_router_with_openapi(orchestrator, Some(spec), None, None);
+
+        let response = router.oneshot(msgpack_request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body = rmpv::decode::read_value(&mut &bytes[..]).unwrap();
+        let (_, served) = body
+            .as_map()
+            .unwrap()
+            .iter()
+            .find(|(k, _)| k.as_str() == Some("result"))
+            .unwrap();
+        assert_eq!(served, &result);
+    }
+
+    #[tokio::test]
+    async fn test_unconvertible_result_under_base64_policy() {
+        let router = unconvertible_result_router("base64").await;
+        let response = post_json(router, "/work", serde_json::json!({"args": {}})).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = json_body(response).await;
+        assert_eq!(body["success"], true);
+        let encoded = body["result"]["$msgpack"].as_str().unwrap();
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(encoded)
+            .unwrap();
+        assert_eq!(
+            rmpv::decode::read_value(&mut &bytes[..]).unwrap(),
+            ext_result()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unknown_body_fields_rejected_in_strict_mode() {
+        let spec: OpenApiSpec = serde_json::from_value(serde_json::json!({
+            "openapi": "3.0.0",
+            "info": {"title": "test", "version": "1.0.0"},
+            "paths": {
+                "/items": {"post": {
+                    "operationId": "post_items",
+                    "requestBody": {"content": {"application/json": {"schema": {
+                        "type": "object",
+                        "properties": {"name": {"type": "string"}}
+                    }}}}
+                }},
+                "/legacy": {"post": {
+                    "operationId": "post_legacy",
+                    "x-neutrino-reject-unknown-fields": false,
+                    "requestBody": {"content": {"application/json": {"schema": {
+                        "type": "object",
+                        "properties": {"name": {"type": "string"}}
+                    }}}}
+                }}
+            }
+        }))
+        .unwrap();
+        let args = serde_json::json!({"args": {"name": "widget", "is_admin": true}});
+
+        for strict in [false, true] {
+            let mut config = Config::default();
+            config.orchestrator.http.validate_requests = true;
+            config.orchestrator.http.reject_unknown_fields = strict;
+            let orchestrator = Arc::new(Orchestrator::new(config));
+            let (handle, worker_side) =
+                mock_worker_handle("default-0", ResourceCapabilities::default());
+            orchestrator.workers().write().await.push(handle);
+            spawn_echo_worker(worker_side, Duration::ZERO);
+            let router = create_router_with_openapi(orchestrator, Some(spec.clone()), None, None);
+
+            let response = post_json(router.clone(), "/items", args.clone()).await;
+            if strict {
+                assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+                let body = json_body(response).await;
+                assert_eq!(
+                    body["violations"],
+                    serde_json::json!(["body.is_admin: unknown field"])
+                );
+            } else {
+                // Passed through to the handler untouched
+                assert_eq!(response.status(), StatusCode::OK);
+                assert_eq!(json_body(response).await["result"]["is_admin"], true);
+            }
+
+            // The route's extension overrides the global setting
+            let response = post_json(router, "/legacy", args.clone()).await;
+            assert_eq!(response.status(), StatusCode::OK, "strict={}", strict);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_non_finite_result_floats_match_serde_convert() {
+        let result = rmpv::Value::Map(vec![
+            ("nan".into(), rmpv::Value::F64(f64::NAN)),
+            ("inf".into(), rmpv::Value::F32(f32::INFINITY)),
+            (
+                "scores".into(),
+                rmpv::Value::Array(vec![
+                    rmpv::Value::F64(0.25),
+                    rmpv::Value::F64(f64::NEG_INFINITY),
+                ]),
+            ),
+        ]);
+
+        for policy in [
+            NonFiniteFloats::Null,
+            NonFiniteFloats::String,
+            NonFiniteFloats::Error,
+        ] {
+            let mut config = Config::default();
+            config.orchestrator.tasks.non_finite_floats = policy;
+            let orchestrator = Arc::new(Orchestrator::new(config));
+            let (handle, worker_side) =
+                mock_worker_handle("default-0", ResourceCapabilities::default());
+            orchestrator.workers().write().await.push(handle);
+            spawn_result_worker(worker_side, result.clone());
+            let spec = spec_with_routes(&[("POST", "/work", "post_work")]);
+            let router = create_router_with_openapi(orchestrator, Some(spec), None, None);
+
+            let response = post_json(router, "/work", serde_json::json!({"args": {}})).await;
+            match serde_convert::msgpack_value_to_json(&result, policy) {
+                Ok(expected) => {
+                    assert_eq!(response.status(), StatusCode::OK, "{:?}", policy);
+                    assert_eq!(
+                        json_body(response).await["result"],
+                        expected,
+                        "{:?}",
+                        policy
+                    );
+                }
+                // Left to unconvertible_results, which defaults to 502
+                Err(_) => assert_eq!(response.status(), StatusCode::BAD_GATEWAY, "{:?}", policy),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_waiting_tasks_dispatched_by_priority() {
+        let spec: OpenApiSpec = serde_json::from_value(serde_json::json!({
+            "openapi": "3.0.0",
+            "info": {"title": "test", "version": "1.0.0"},
+            "paths": {
+                "/batch": {"post": {"operationId": "post_batch"}},
+                "/chat": {"post": {"operationId": "post_chat", "x-neutrino-priority": 5}}
+            }
+        }))
+        .unwrap();
+
+        let orchestrator = Arc::new(Orchestrator::new(Config::default()));
+        let (handle, worker_side) =
+            mock_worker_handle("default-0", ResourceCapabilities::default());
+        orchestrator.workers().write().await.push(handle);
+        spawn_echo_worker(worker_side, Duration::from_millis(150));
+        let router = create_router_with_openapi(orchestrator, Some(spec), None, None);
+
+        // The first task holds the only worker while the rest queue up in turn
+        let completed = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let requests = [
+            ("running", "/batch", None),
+            ("batch", "/batch", None),
+            ("chat", "/chat", None),
+            ("urgent-batch", "/batch", Some("10")),
+        ];
+        let mut tasks = Vec::new();
+        for (name, uri, priority) in requests {
+            let mut req = Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header("content-type", "application/json");
+            if let Some(priority) = priority {
+                req = req.header(PRIORITY_HEADER, priority);
+            }
+            let req = req
+                .body(Body::from(serde_json::json!({"args": name}).to_string()))
+                .unwrap();
+            let (router, completed) = (router.clone(), completed.clone());
+            tasks.push(tokio::spawn(async move {
+                let response = router.oneshot(req).await.unwrap();
+                assert_eq!(response.status(), StatusCode::OK);
+                let result = json_body(response).await["result"].clone();
+                completed.lock().unwrap().push(result);
+            }));
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        assert_eq!(
+            *completed.lock().unwrap(),
+            vec!["running", "urgent-batch", "chat", "batch"],
+            "the header outranks the route's priority, which outranks the default"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_traceback_returned_only_to_trusted_clients() {
+        let mut config = Config::default();
+        config.orchestrator.http.error_details = Some(
+            serde_yaml::from_str("{trusted_cidrs: [127.0.0.0/8], debug_token: s3cret}").unwrap(),
+        );
+        let orchestrator = Arc::new(Orchestrator::new(config));
+        let (handle, mut worker_side) =
+            mock_worker_handle("default-0", ResourceCapabilities::default());
+        orchestrator.workers().write().await.push(handle);
+        tokio::spawn(async move {
+            while let Ok(Message::TaskAssignment { task_id, .. }) =
+                crate::protocol::read_message(&mut worker_side).await
+            {
+                let error = rmpv::Value::Map(vec![
+                    ("error".into(), "boom".into()),
+                    ("type".into(), "ValueError".into()),
+                    (
+                        "traceback".into(),
+                        "Traceback (most recent call last):\n  File \"app.py\"".into(),
+                    ),
+                ]);
+                let reply = Message::TaskResult {
+                    task_id,
+                    success: false,
+                    result: error,
+                };
+                crate::protocol::write_message(&mut worker_side, &reply)
+                    .await
+                    .unwrap();
+            }
+        });
+
+        let events = CapturedEvents::default();
+        let _guard = events.install();
+
+        let spec = spec_with_routes(&[("POST", "/work", "post_work")]);
+        let router = create_router_with_openapi(orchestrator, Some(spec), None, None);
+
+        // Served over TCP, a client on a trusted network sees the traceback
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(handoff::serve(
+            listener,
+            router.clone(),
+            std::future::pending(),
+            Duration::ZERO,
+        ));
+        let body: serde_json::Value = reqwest::Client::new()
+            .post(format!("http://{}/work", addr))
+            .json(&serde_json::json!({"args": {}}))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(body["success"], false);
+        assert!(body["error"].as_str().unwrap().contains("boom"));
+        assert!(body["traceback"].as_str().unwrap().contains("app.py"));
+
+        // From anywhere else the client gets a generic message
+        let untrusted = |debug_token: Option<&str>| {
+            let mut req = Request::builder()
+                .method("POST")
+                .uri("/work")
+                .header("content-type", "application/json");
+            if let Some(token) = debug_token {
+                req = req.header(DEBUG_HEADER, token);
+            }
+            let mut req = req
+                .body(Body::from(serde_json::json!({"args": {}}).to_string()))
+                .unwrap();
+            req.extensions_mut()
+                .insert(ConnectInfo(SocketAddr::from(([203, 0, 113, 5], 40000))));
+            req
+        };
+        let body = json_body(router.clone().oneshot(untrusted(None)).await.unwrap()).await;
+        assert_eq!(body["error"], error_details::GENERIC_ERROR);
+        assert!(body.get("traceback").is_none());
+        let body = json_body(
+            router
+                .clone()
+                .oneshot(untrusted(Some("wrong")))
+                .await
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(body["error"], error_details::GENERIC_ERROR);
+
+        // ...unless it presents the debug token
+        let body = json_body(router.oneshot(untrusted(Some("s3cret"))).await.unwrap()).await;
+        assert!(body["traceback"].as_str().unwrap().contains("app.py"));
+
+        // Every failure's traceback is logged server-side
+        let logged = events.with_field("traceback");
+        assert_eq!(logged.len(), 4);
+        assert!(logged
+            .iter()
+            .all(|event| event["traceback"].contains("app.py")));
+    }
+
+    #[tokio::test]
+    async fn test_args_reshaped_by_template_before_dispatch() {
+        let spec: OpenApiSpec = serde_json::from_value(serde_json::json!({
+            "openapi": "3.0.0",
+            "info": {"title": "test", "version": "1.0.0"},
+            "paths": {"/generate": {"post": {
+                "operationId": "post_generate",
+                "x-neutrino-args-template": {
+                    "payload": {"text": "$.prompt", "options": {"max_tokens": "$.length"}},
+                    "model": "$.model",
+                    "source": "api"
+                }
+            }}}
+        }))
+        .unwrap();
+
+        let orchestrator = Arc::new(Orchestrator::new(Config::default()));
+        let (handle, worker_side) =
+            mock_worker_handle("default-0", ResourceCapabilities::default());
+        orchestrator.workers().write().await.push(handle);
+        spawn_echo_worker(worker_side, Duration::ZERO);
+        let router = create_router_with_openapi(orchestrator, Some(spec), None, None);
+
+        // The echo worker returns the args it received
+        let args = serde_json::json!({"prompt": "hello", "length": 16});
+        let response = post_json(router, "/generate", serde_json::json!({"args": args})).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            json_body(response).await["result"],
+            serde_json::json!({
+                "payload": {"text": "hello", "options": {"max_tokens": 16}},
+                "source": "api"
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tenant_routed_to_tenant_pool_and_counted() {
+        let spec: OpenApiSpec = serde_json::from_value(serde_json::json!({
+            "openapi": "3.0.0",
+            "info": {"title": "test", "version": "1.0.0"},
+            "paths": {"/work": {"post": {
+                "operationId": "post_work",
+                "x-neutrino-tenant-pools": {"acme": "acme"}
+            }}}
+        }))
+        .unwrap();
+
+        let mut config = Config::default();
+        config.orchestrator.http.tenants = Some(crate::config::TenantConfig {
+            header: "x-tenant-id".to_string(),
+            trusted_cidrs: vec!["10.0.0.0/8".parse().unwrap()],
+            jwt_claim: None,
+            jwt_secret: None,
+        });
+        let orchestrator = Arc::new(Orchestrator::new(config));
+        for id in ["default-0", "acme-0"] {
+            let (handle, worker_side) = mock_worker_handle(id, ResourceCapabilities::default());
+            orchestrator.workers().write().await.push(handle);
+            spawn_echo_worker(worker_side, Duration::ZERO);
+        }
+        let router = create_router_with_openapi(Arc::clone(&orchestrator), Some(spec), None, None);
+
+        let request = |tenant: &str, peer: [u8; 4]| {
+            let mut request = Request::builder()
+                .method("POST")
+                .uri("/work")
+                .header("content-type", "application/json")
+                .header("x-tenant-id", tenant)
+                .body(Body::from(serde_json::json!({"args": {}}).to_string()))
+                .unwrap();
+            request
+                .extensions_mut()
+                .insert(ConnectInfo(SocketAddr::from((peer, 40000))));
+            request
+        };
+        let gateway = [10, 0, 0, 7];
+        for _ in 0..3 {
+            let response = router
+                .clone()
+                .oneshot(request("acme", gateway))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+        // Other tenants, and a header not set by the gateway, stay off the
+        // tenant's pool
+        for (tenant, peer) in [("globex", gateway), ("acme", [203, 0, 113, 5])] {
+            let response = router.clone().oneshot(request(tenant, peer)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+
+        {
+            let workers = orchestrator.workers();
+            let workers = workers.read().await;
+            assert_eq!(workers[0].worker.tasks_completed, 2);
+            assert_eq!(workers[1].worker.tasks_completed, 3);
+        }
+
+        let rendered = orchestrator.metrics().render();
+        assert!(
+            rendered.contains("neutrino_tenant_tasks_total{tenant=\"acme\",outcome=\"success\"} 3")
+        );
+        assert!(rendered
+            .contains("neutrino_tenant_tasks_total{tenant=\"globex\",outcome=\"success\"} 1"));
+    }
+
+    #[tokio::test]
+    async fn test_route_pinned_to_pool() {
+        let spec: OpenApiSpec = serde_json::from_value(serde_json::json!({
+            "openapi": "3.0.0",
+            "info": {"title": "test", "version": "1.0.0"},
+            "paths": {"/work": {"post": {
+                "operationId": "post_work",
+                "x-neutrino-pool": "gpu_workers"
+            }}}
+        }))
+        .unwrap();
+
+        // The default worker fits the task just as well, and comes first
+        let orchestrator = Arc::new(Orchestrator::new(Config::default()));
+        for id in ["default-0", "gpu_workers-0"] {
+            let (handle, worker_side) = mock_worker_handle(id, ResourceCapabilities::default());
+            orchestrator.workers().write().await.push(handle);
+            spawn_echo_worker(worker_side, Duration::ZERO);
+        }
+        let router = create_router_with_openapi(Arc::clone(&orchestrator), Some(spec), None, None);
+
+        for _ in 0..3 {
+            let response =
+                post_json(router.clone(), "/work", serde_json::json!({"args": {}})).await;
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+        let completed: Vec<(String, u32)> = orchestrator
+            .worker_status()
+            .await
+            .into_iter()
+            .map(|w| (w.id, w.tasks_completed))
+            .collect();
+        assert_eq!(
+            completed,
+            vec![
+                ("default-0".to_string(), 0),
+                ("gpu_workers-0".to_string(), 3)
+            ]
+        );
+
+        // Without a worker in the pool, the task isn't run elsewhere
+        orchestrator.workers().write().await.pop();
+        let response = post_json(router, "/work", serde_json::json!({"args": {}})).await;
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert!(json_body(response).await["error"]
+            .as_str()
+            .unwrap()
+            .contains("in pool gpu_workers"));
+    }
+
+    #[tokio::test]
+    async fn test_high_priority_gpu_task_preempts_low_priority_one() {
+        let gpu_route = |operation_id: &str, priority: i32| {
+            serde_json::json!({"post": {
+                "operationId": operation_id,
+                "x-neutrino-resources": {"num_cpus": 1.0, "num_gpus": 1.0, "memory_gb": 1.0},
+                "x-neutrino-priority": priority
+            }})
+        };
+        let spec: OpenApiSpec = serde_json::from_value(serde_json::json!({
+            "openapi": "3.0.0",
+            "info": {"title": "test", "version": "1.0.0"},
+            "paths": {"/batch": gpu_route("batch", 0), "/interactive": gpu_route("interactive", 10)}
+        }))
+        .unwrap();
+
+        let mut config = Config::default();
+        config.orchestrator.tasks.preemption = Some(crate::config::PreemptionConfig {
+            min_priority_gap: 1,
+            gpu_only: true,
+        });
+        let orchestrator = Arc::new(Orchestrator::new(config));
+        let gpu = ResourceCapabilities {
+            num_gpus: 1.0,
+            ..ResourceCapabilities::default()
+        };
+        let (mut handle, worker_side) = mock_worker_handle("gpu-0", gpu);
+        handle.worker.cancellable = true;
+        orchestrator.workers().write().await.push(handle);
+        spawn_echo_worker(worker_side, Duration::from_millis(200));
+        let router = create_router_with_openapi(orchestrator, Some(spec), None, None);
+
+        let batch = tokio::spawn(post_json(
+            router.clone(),
+            "/batch",
+            serde_json::json!({"args": {"job": "batch"}}),
+        ));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        // The interactive task takes the GPU; the batch task runs again after it
+        let response = post_json(
+            router,
+            "/interactive",
+            serde_json::json!({"args": {"job": "interactive"}}),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(json_body(response).await["result"]["job"], "interactive");
+        assert!(!batch.is_finished());
+
+        let response = batch.await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(json_body(response).await["result"]["job"], "batch");
+    }
+
+    #[tokio::test]
+    async fn test_task_on_non_cancelling_worker_is_not_preempted() {
+        let gpu_route = |operation_id: &str, priority: i32| {
+            serde_json::json!({"post": {
+                "operationId": operation_id,
+                "x-neutrino-resources": {"num_cpus": 1.0, "num_gpus": 1.0, "memory_gb": 1.0},
+                "x-neutrino-priority": priority
+            }})
+        };
+        let spec: OpenApiSpec = serde_json::from_value(serde_json::json!({
+            "openapi": "3.0.0",
+            "info": {"title": "test", "version": "1.0.0"},
+            "paths": {"/batch": gpu_route("batch", 0), "/interactive": gpu_route("interactive", 10)}
+        }))
+        .unwrap();
+
+        let mut config = Config::default();
+        config.orchestrator.tasks.preemption = Some(crate::config::PreemptionConfig {
+            min_priority_gap: 1,
+            gpu_only: true,
+        });
+        let orchestrator = Arc::new(Orchestrator::new(config));
+        let gpu = ResourceCapabilities {
+            num_gpus: 1.0,
+            ..ResourceCapabilities::default()
+        };
+        // Runs every task to the end, like the bundled Python worker
+        let (handle, worker_side) = mock_worker_handle("gpu-0", gpu);
+        orchestrator.workers().write().await.push(handle);
+        spawn_echo_worker(worker_side, Duration::from_millis(200));
+        let router = create_router_with_openapi(orchestrator, Some(spec), None, None);
+
+        let batch = tokio::spawn(post_json(
+            router.clone(),
+            "/batch",
+            serde_json::json!({"args": {"job": "batch"}}),
+        ));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        // The interactive task waits for the batch task instead of stopping it
+        let interactive = tokio::spawn(post_json(
+            router,
+            "/interactive",
+            serde_json::json!({"args": {"job": "interactive"}}),
+        ));
+        let response = batch.await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(json_body(response).await["result"]["job"], "batch");
+        assert!(!interactive.is_finished());
+
+        let response = interactive.await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(json_body(response).await["result"]["job"], "interactive");
+    }
+
+    #[tokio::test]
+    async fn test_hedged_get_returns_first_result_and_cancels_straggler() {
+        let mut spec = spec_with_routes(&[
+            ("GET", "/lookup", "get_lookup"),
+            ("POST", "/store", "post_store"),
+        ]);
+        spec.paths
+            .get_mut("/lookup")
+            .unwrap()
+            .get
+            .as_mut()
+            .unwrap()
+            .hedge_after_ms = Some(50);
+        spec.paths
+            .get_mut("/store")
+            .unwrap()
+            .post
+            .as_mut()
+            .unwrap()
+            .hedge_after_ms = Some(50);
+        let orchestrator = Arc::new(Orchestrator::new(Config::default()));
+
+        // The first task either worker gets stalls; everything else is quick
+        let assigned = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let (cancelled_tx, mut cancelled) = tokio::sync::mpsc::unbounded_channel();
+        for id in ["default-0", "default-1"] {
+            let (handle, mut worker_side) = mock_worker_handle(id, ResourceCapabilities::default());
+            orchestrator.workers().write().await.push(handle);
+            let (assigned, cancelled_tx) = (Arc::clone(&assigned), cancelled_tx.clone());
+            tokio::spawn(async move {
+                while let Ok(msg) = crate::protocol::read_message(&mut worker_side).await {
+                    match msg {
+                        Message::TaskAssignment { task_id, args, .. } => {
+                            if assigned.fetch_add(1, std::sync::atomic::Ordering::SeqCst) == 0 {
+                                tokio::time::sleep(Duration::from_millis(500)).await;
+                            }
+                            let reply = Message::TaskResult {
+                                task_id,
+                                success: true,
+                                result: args,
+                            };
+                            crate::protocol::write_message(&mut worker_side, &reply)
+                                .await
+                                .unwrap();
+                        }
+                        Message::CancelTask { task_id } => cancelled_tx.send(task_id).unwrap(),
+                        _ => {}
+                    }
+                }
+            });
+        }
+        let router = create_router_with_openapi(orchestrator, Some(spec), None, None);
+
+        let start = Instant::now();
+        let req = Request::builder()
+            .uri("/lookup")
+            .body(Body::empty())
+            .unwrap();
+        let response = router.clone().oneshot(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(
+            start.elapsed() < Duration::from_millis(400),
+            "the hedge answered first"
+        );
+        assert_eq!(json_body(response).await["success"], true);
+        let cancelled = tokio::time::timeout(Duration::from_secs(1), cancelled.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(
+            !cancelled.ends_with("-hedge"),
+            "the straggling original was cancelled: {}",
+            cancelled
+        );
+
+        // Only GET routes hedge: a stalled POST waits for its own result
+        assigned.store(0, std::sync::atomic::Ordering::SeqCst);
+        let start = Instant::now();
+        let response = post_json(router, "/store", serde_json::json!({"args": {"key": "b"}})).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(start.elapsed() >= Duration::from_millis(500));
+    }
+
+    #[tokio::test]
+    async fn test_hedge_loser_stays_leased_until_it_answers() {
+        let mut spec = spec_with_routes(&[
+            ("GET", "/lookup", "get_lookup"),
+            ("POST", "/store", "post_store"),
+        ]);
+        spec.paths
+            .get_mut("/lookup")
+            .unwrap()
+            .get
+            .as_mut()
+            .unwrap()
+            .hedge_after_ms = Some(50);
+        let orchestrator = Arc::new(Orchestrator::new(Config::default()));
+
+        // Workers that can't stop a task part-way: a cancelled task still runs
+        // to the end. The first task either worker gets stalls.
+        let assigned = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let straggler = Arc::new(std::sync::Mutex::new(None));
+        for id in ["default-0", "default-1"] {
+            let (handle, mut worker_side) = mock_worker_handle(id, ResourceCapabilities::default());
+            orchestrator.workers().write().await.push(handle);
+            let (assigned, straggler) = (Arc::clone(&assigned), Arc::clone(&straggler));
+            tokio::spawn(async move {
+                while let Ok(msg) = crate::protocol::read_message(&mut worker_side).await {
+                    if let Message::TaskAssignment { task_id, args, .. } = msg {
+                        if assigned.fetch_add(1, std::sync::atomic::Ordering::SeqCst) == 0 {
+                            *straggler.lock().unwrap() = Some(id);
+                            tokio::time::sleep(Duration::from_millis(500)).await;
+                        }
+                        let reply = Message::TaskResult {
+                            task_id,
+                            success: true,
+                            result: args,
+                        };
+                        crate::protocol::write_message(&mut worker_side, &reply)
+                            .await
+                            .unwrap();
+                    }
+                }
+            });
+        }
+        let router = create_router_with_openapi(Arc::clone(&orchestrator), Some(spec), None, None);
+
+        let req = Request::builder()
+            .uri("/lookup")
+            .body(Body::empty())
+            .unwrap();
+        let response = router.clone().oneshot(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let straggler = straggler.lock().unwrap().expect("a task stalled");
+
+        // The loser's worker is still running the cancelled task, so it isn't
+        // handed the next one
+        let index = usize::from(straggler == "default-1");
+        assert_eq!(
+            orchestrator.workers().read().await[index].worker.state,
+            crate::worker::WorkerState::Busy
+        );
+        let response = post_json(router, "/store", serde_json::json!({"args": {}})).await;
+        let worker_id = json_body(response).await["worker_id"].clone();
+        assert_ne!(worker_id, straggler);
+
+        // Freed once its result for the cancelled task arrives
+        wait_for_worker_state(
+            &orchestrator.workers(),
+            index,
+            crate::worker::WorkerState::Idle,
+        )
+        .await;
+        assert_eq!(
+            orchestrator.workers().read().await[index]
+                .worker
+                .allocation
+                .allocated_cpus,
+            0.0
+        );
+    }
+
+    #[tokio::test]
+    async fn test_handler_concurrency_cap_queues_extra_tasks() {
+        let mut spec =
+            spec_with_routes(&[("POST", "/load", "load_model"), ("POST", "/other", "other")]);
+        spec.paths
+            .get_mut("/load")
+            .unwrap()
+            .post
+            .as_mut()
+            .unwrap()
+            .max_concurrency = Some(1);
+        let orchestrator = Arc::new(Orchestrator::new(Config::default()));
+        for id in ["default-0", "default-1", "default-2"] {
+            let (handle, worker_side) = mock_worker_handle(id, ResourceCapabilities::default());
+            orchestrator.workers().write().await.push(handle);
+            spawn_echo_worker(worker_side, Duration::from_millis(200));
+        }
+        let router = create_router_with_openapi(orchestrator, Some(spec), None, None);
+
+        let start = Instant::now();
+        let capped: Vec<_> = (0..2)
+            .map(|_| {
+                tokio::spawn(post_json(
+                    router.clone(),
+                    "/load",
+                    serde_json::json!({"args": {}}),
+                ))
+            })
+            .collect();
+
+        // Other handlers aren't held up by the capped one
+        let response = post_json(router, "/other", serde_json::json!({"args": {}})).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(start.elapsed() < Duration::from_millis(400));
+
+        // With workers to spare, the capped handler still ran one task at a time
+        for task in capped {
+            assert_eq!(task.await.unwrap().status(), StatusCode::OK);
+        }
+        assert!(start.elapsed() >= Duration::from_millis(400));
+    }
+
+    #[tokio::test]
+    async fn test_batch_runs_tasks_in_parallel_with_results_in_order() {
+        let mut config = Config::default();
+        config.orchestrator.http.allow_batch = true;
+        config.orchestrator.http.max_batch_tasks = 5;
+        config.orchestrator.http.max_body_bytes = 64;
+        let orchestrator = Arc::new(Orchestrator::new(config));
+        for id in ["default-0", "default-1", "default-2"] {
+            let (handle, worker_side) = mock_worker_handle(id, ResourceCapabilities::default());
+            orchestrator.workers().write().await.push(handle);
+            spawn_echo_worker(worker_side, Duration::from_millis(200));
+        }
+        let spec = spec_with_routes(&[("POST", "/echo", "echo"), ("POST", "/other", "other")]);
+        let router = create_router_with_openapi(orchestrator, Some(spec), None, None);
+
+        let start = Instant::now();
+        let batch = serde_json::json!([
+            {"handler": "echo", "args": {"n": 1}},
+            {"handler": "missing", "args": {}},
+            {"handler": "other", "args": {"n": 3, "fail": true}},
+            {"handler": "echo", "args": {"n": 4}},
+            {"handler": "echo", "args": {"blob": "x".repeat(64)}},
+        ]);
+        let response = post_json(router.clone(), "/batch", batch).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        // Three tasks on three workers run side by side
+        assert!(start.elapsed() < Duration::from_millis(400));
+
+        let body = json_body(response).await;
+        assert_eq!(
+            (body["succeeded"].as_u64(), body["failed"].as_u64()),
+            (Some(2), Some(3))
+        );
+        let results = body["results"].as_array().unwrap();
+        assert_eq!(results[0]["result"], serde_json::json!({"n": 1}));
+        assert_eq!(
+            (
+                results[0]["status"].as_u64(),
+                results[0]["success"].as_bool()
+            ),
+            (Some(200), Some(true))
+        );
+        assert_eq!(results[1]["status"], 404);
+        assert_eq!(results[1]["error"], "Route not found: handler missing");
+        assert_eq!(
+            (
+                results[2]["status"].as_u64(),
+                results[2]["success"].as_bool()
+            ),
+            (Some(200), Some(false))
+        );
+        assert_eq!(results[3]["result"], serde_json::json!({"n": 4}));
+        // Held to the route's body limit, like a request to it
+        assert_eq!(results[4]["status"], 413);
+        assert!(results.iter().all(|result| result["task_id"].is_string()));
+
+        let too_many =
+            serde_json::Value::Array(vec![serde_json::json!({"handler": "echo", "args": {}}); 6]);
+        assert_eq!(
+            post_json(router, "/batch", too_many).await.status(),
+            StatusCode::BAD_REQUEST
+        );
+
+        // Not served unless enabled
+        let orchestrator = Arc::new(Orchestrator::new(Config::default()));
+        let spec = spec_with_routes(&[("POST", "/echo", "echo")]);
+        let router = create_router_with_openapi(orchestrator, Some(spec), None, None);
+        let batch = serde_json::json!([{"handler": "echo", "args": {}}]);
+        assert_eq!(
+            post_json(router, "/batch", batch).await.status(),
+            StatusCode::NOT_FOUND
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dag_wires_results_into_dependent_tasks() {
+        let mut config = Config::default();
+        config.orchestrator.http.allow_dag = true;
+        config.orchestrator.http.max_body_bytes = 256;
+        let orchestrator = Arc::new(Orchestrator::new(config));
+        for id in ["default-0", "default-1"] {
+            let (handle, worker_side) = mock_worker_handle(id, ResourceCapabilities::default());
+            orchestrator.workers().write().await.push(handle);
+            spawn_echo_worker(worker_side, Duration::from_millis(100));
+        }
+        let spec = spec_with_routes(&[("POST", "/echo", "echo")]);
+        let router = create_router_with_openapi(orchestrator, Some(spec), None, None);
+
+        let graph = serde_json::json!({"nodes": [
+            {"id": "left", "handler": "echo", "args": {"text": "a"}},
+            {"id": "right", "handler": "echo", "args": {"text": "b"}},
+            {"id": "join", "handler": "echo", "args": {"parts": ["$left.text", "$right.text"]}},
+            {"id": "broken", "handler": "echo", "args": {"fail": true, "input": "$join"}},
+            {"id": "after", "handler": "echo", "args": {"input": "$broken"}},
+            {"id": "big", "handler": "echo", "args": {"blob": "x".repeat(256)}},
+        ]});
+        let start = Instant::now();
+        let response = post_json(router.clone(), "/dag", graph).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        // left and right side by side, then join, then broken
+        assert!(start.elapsed() < Duration::from_millis(400));
+
+        let body = json_body(response).await;
+        assert_eq!(
+            (
+                body["succeeded"].as_u64(),
+                body["failed"].as_u64(),
+                body["skipped"].as_u64()
+            ),
+            (Some(3), Some(2), Some(1))
+        );
+        let nodes = body["nodes"].as_array().unwrap();
+        assert_eq!(nodes[2]["result"], serde_json::json!({"parts": ["a", "b"]}));
+        assert_eq!(
+            (nodes[3]["success"].as_bool(), nodes[3]["status"].as_u64()),
+            (Some(false), Some(200))
+        );
+        assert_eq!(nodes[4]["skipped"], true);
+        // Held to the route's body limit, like a request to it
+        assert_eq!(nodes[5]["status"], 413);
+
+        let cyclic = serde_json::json!({"nodes": [
+            {"id": "a", "handler": "echo", "args": {"x": "$b"}},
+            {"id": "b", "handler": "echo", "args": {"x": "$a"}},
+        ]});
+        assert_eq!(
+            post_json(router, "/dag", cyclic).await.status(),
+            StatusCode::BAD_REQUEST
+        );
+
+        // Not served unless enabled
+        let orchestrator = Arc::new(Orchestrator::new(Config::default()));
+        let spec = spec_with_routes(&[("POST", "/echo", "echo")]);
+        let router = create_router_with_openapi(orchestrator, Some(spec), None, None);
+        let graph = serde_json::json!({"nodes": [{"id": "a", "handler": "echo", "args": {}}]});
+        assert_eq!(
+            post_json(router, "/dag", graph).await.status(),
+            StatusCode::NOT_FOUND
+        );
+    }
+
+    #[tokio::test]
+    async fn test_gpu_affinity_runs_only_on_bound_worker() {
+        let spec = spec_with_routes(&[("post", "/infer", "infer")]);
+        let request = |device: &str| {
+            Request::builder()
+                .method("POST")
+                .uri("/infer")
+                .header("content-type", "application/json")
+                .header(GPU_AFFINITY_HEADER, device)
+                .body(Body::from(serde_json::json!({"args": {}}).to_string()))
+                .unwrap()
+        };
+
+        for fallback in [GpuAffinityFallback::Error, GpuAffinityFallback::Any] {
+            let mut config = Config::default();
+            config.orchestrator.tasks.gpu_affinity_fallback = fallback;
+            let orchestrator = Arc::new(Orchestrator::new(config));
+            for (id, device) in [("gpu-0", 0), ("gpu-1", 2)] {
+                let (mut handle, worker_side) =
+                    mock_worker_handle(id, ResourceCapabilities::default());
+                handle.worker.gpu_devices = vec![device];
+                orchestrator.workers().write().await.push(handle);
+                spawn_echo_worker(worker_side, Duration::ZERO);
+            }
+            let router = create_router_with_openapi(
+                Arc::clone(&orchestrator),
+                Some(spec.clone()),
+                None,
+                None,
+            );
+
+            for _ in 0..4 {
+                let response = router.clone().oneshot(request("2")).await.unwrap();
+                assert_eq!(response.status(), StatusCode::OK);
+            }
+            {
+                let workers = orchestrator.workers();
+                let workers = workers.read().await;
+                assert_eq!(workers[0].worker.tasks_completed, 0);
+                assert_eq!(workers[1].worker.tasks_completed, 4);
+            }
+
+            // No worker is bound to GPU 5
+            let response = router.clone().oneshot(request("5")).await.unwrap();
+            let expected = match fallback {
+                GpuAffinityFallback::Error => StatusCode::SERVICE_UNAVAILABLE,
+                GpuAffinityFallback::Any => StatusCode::OK,
+            };
+            assert_eq!(response.status(), expected);
+
+            let response = router.oneshot(request("first")).await.unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_task_waiting_for_busy_gpu_does_not_stall_cpu_task() {
+        let spec = spec_with_routes(&[("post", "/infer", "infer"), ("post", "/work", "work")]);
+        let orchestrator = Arc::new(Orchestrator::new(Config::default()));
+        let (mut gpu, gpu_side) = mock_worker_handle("gpu-0", ResourceCapabilities::default());
+        gpu.worker.gpu_devices = vec![0];
+        let (cpu, cpu_side) = mock_worker_handle("default-0", ResourceCapabilities::default());
+        orchestrator.workers().write().await.extend([gpu, cpu]);
+        spawn_echo_worker(gpu_side, Duration::from_millis(500));
+        spawn_echo_worker(cpu_side, Duration::ZERO);
+        let router = create_router_with_openapi(orchestrator, Some(spec), None, None);
+        let on_gpu_0 = || {
+            Request::builder()
+                .method("POST")
+                .uri("/infer")
+                .header("content-type", "application/json")
+                .header(GPU_AFFINITY_HEADER, "0")
+                .body(Body::from(serde_json::json!({"args": {}}).to_string()))
+                .unwrap()
+        };
+
+        // One task runs on GPU 0 and a second waits for it
+        let running = tokio::spawn(router.clone().oneshot(on_gpu_0()));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let waiting = tokio::spawn(router.clone().oneshot(on_gpu_0()));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        // A task of the same priority that fits elsewhere goes straight through
+        let started = Instant::now();
+        let response = post_json(router, "/work", serde_json::json!({"args": {}})).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(started.elapsed() < Duration::from_millis(200));
+        assert!(!waiting.is_finished());
+
+        assert_eq!(running.await.unwrap().unwrap().status(), StatusCode::OK);
+        assert_eq!(waiting.await.unwrap().unwrap().status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_task_waiting_for_capacity_times_out() {
+        let mut config = Config::default();
+        config.orchestrator.tasks.default_timeout_secs = 1;
+        // The worker stays busy until it answers for the timed-out task
+        config.orchestrator.tasks.on_timeout = TimeoutAction::Cancel;
+        let orchestrator = Arc::new(Orchestrator::new(config));
+        let (handle, worker_side) =
+            mock_worker_handle("default-0", ResourceCapabilities::default());
+        orchestrator.workers().write().await.push(handle);
+        spawn_echo_worker(worker_side, Duration::from_millis(1500));
+        let spec = spec_with_routes(&[("post", "/work", "work")]);
+        let router = create_router_with_openapi(orchestrator, Some(spec), None, None);
+
+        let running = tokio::spawn(post_json(
+            router.clone(),
+            "/work",
+            serde_json::json!({"args": {}}),
+        ));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        // Gives up waiting for the busy worker once its timeout is spent
+        let started = Instant::now();
+        let response = post_json(router, "/work", serde_json::json!({"args": {}})).await;
+        assert_eq!(response.status(), StatusCode::GATEWAY_TIMEOUT);
+        assert!(started.elapsed() < Duration::from_millis(1200));
+        running.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_failed_task_recorded_as_dead_letter() {
+        let mut config = Config::default();
+        config.orchestrator.tasks.dead_letters = Some(crate::config::DeadLetterConfig {
+            store_path: None,
+            max_entries: 10,
+            redact_fields: crate::redact::default_redact_fields(),
+            redrive: false,
+        });
+        let orchestrator = Arc::new(Orchestrator::new(config));
+        let (handle, worker_side) =
+            mock_worker_handle("default-0", ResourceCapabilities::default());
+        orchestrator.workers().write().await.push(handle);
+        spawn_echo_worker(worker_side, Duration::ZERO);
+        let spec = spec_with_routes(&[("post", "/work", "work")]);
+        let router = create_router_with_openapi(orchestrator, Some(spec), None, None);
+
+        let response = post_json(
+            router.clone(),
+            "/work",
+            serde_json::json!({"args": {"fail": false}}),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = serde_json::json!({"args": {"fail": true, "password": "hunter2"}});
+        let response = post_json(router.clone(), "/work", body).await;
+        assert_eq!(json_body(response).await["success"], false);
+
+        let req = Request::builder()
+            .uri("/admin/dead-letters")
+            .body(Body::empty())
+            .unwrap();
+        let response = router.clone().oneshot(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let letters = json_body(response).await["dead_letters"].clone();
+        let letters = letters.as_array().unwrap();
+        assert_eq!(
+            letters.len(),
+            1,
+            "only the failed task is recorded: {:?}",
+            letters
+        );
+        let letter = &letters[0];
+        assert_eq!(letter["handler"], "work");
+        assert_eq!(letter["kind"], "handler_error");
+        assert_eq!(letter["worker_id"], "default-0");
+        assert_eq!(letter["attempts"], 1);
+        assert!(!letter["error"].as_str().unwrap().is_empty());
+        let args = letter["args"].as_str().unwrap();
+        assert!(
+            args.contains("\"password\":\"***\"") && !args.contains("hunter2"),
+            "{}",
+            args
+        );
+
+        let req = Request::builder()
+            .uri("/admin/dead-letters?handler=other")
+            .body(Body::empty())
+            .unwrap();
+        let response = router.oneshot(req).await.unwrap();
+        assert_eq!(
+            json_body(response).await["dead_letters"],
+            serde_json::json!([])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dead_letter_redriven_with_original_args() {
+        let mut config = Config::default();
+        config.orchestrator.tasks.dead_letters = Some(crate::config::DeadLetterConfig {
+            store_path: None,
+            max_entries: 10,
+            redact_fields: crate::redact::default_redact_fields(),
+            redrive: true,
+        });
+        let orchestrator = Arc::new(Orchestrator::new(config));
+        let (handle, mut worker_side) =
+            mock_worker_handle("default-0", ResourceCapabilities::default());
+        orchestrator.workers().write().await.push(handle);
+        // Fails the first two attempts, then echoes its args
+        tokio::spawn(async move {
+            let mut attempts = 0;
+            while let Ok(msg) = crate::protocol::read_message(&mut worker_side).await {
+                let Message::TaskAssignment { task_id, args, .. } = msg else {
+                    continue;
+                };
+                attempts += 1;
+                let reply = Message::TaskResult {
+                    task_id,
+                    success: attempts > 2,
+                    result: args,
+                };
+                crate::protocol::write_message(&mut worker_side, &reply)
+                    .await
+                    .unwrap();
+            }
+        });
+        let spec = spec_with_routes(&[("post", "/work", "work")]);
+        let router = create_router_with_openapi(orchestrator, Some(spec), None, None);
+
+        let body = serde_json::json!({"args": {"password": "hunter2"}});
+        assert_eq!(
+            json_body(post_json(router.clone(), "/work", body).await).await["success"],
+            false
+        );
+        let req = Request::builder()
+            .uri("/admin/dead-letters")
+            .body(Body::empty())
+            .unwrap();
+        let letters = json_body(router.clone().oneshot(req).await.unwrap()).await;
+        let task_id = letters["dead_letters"][0]["task_id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+
+        let redrive_uri = format!("/admin/dead-letters/{}/redrive", task_id);
+        let response = post_json(router.clone(), &redrive_uri, serde_json::json!({})).await;
+        assert_eq!(json_body(response).await["success"], false);
+        let req = Request::builder()
+            .uri(format!("/admin/dead-letters/{}", task_id))
+            .body(Body::empty())
+            .unwrap();
+        let letter = json_body(router.clone().oneshot(req).await.unwrap()).await;
+        assert_eq!(letter["attempts"], 2);
+        assert!(letter.get("payload").is_none());
+
+        // Redriven with the unredacted args; success clears the letter
+        let response = post_json(router.clone(), &redrive_uri, serde_json::json!({})).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            json_body(response).await["result"],
+            serde_json::json!({"password": "hunter2"})
+        );
+        let req = Request::builder()
+            .uri(format!("/admin/dead-letters/{}", task_id))
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(
+            router.clone().oneshot(req).await.unwrap().status(),
+            StatusCode::NOT_FOUND
+        );
+        let response = post_json(router, &redrive_uri, serde_json::json!({})).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_task_waits_in_pending_queue_for_capacity() {
+        let mut config = Config::default();
+        config.orchestrator.tasks.pending_queue = Some(crate::config::PendingQueueConfig {
+            max_depth: 1,
+            max_wait_secs: 5,
+        });
+        let orchestrator = Arc::new(Orchestrator::new(config));
+        let (mut handle, worker_side) =
+            mock_worker_handle("default-0", ResourceCapabilities::default());
+        // Every CPU taken, as if by another task
+        handle.worker.allocation.allocated_cpus = handle.worker.capabilities.num_cpus;
+        orchestrator.workers().write().await.push(handle);
+        spawn_echo_worker(worker_side, Duration::ZERO);
+        let spec = spec_with_routes(&[("post", "/work", "work")]);
+        let router = create_router_with_openapi(Arc::clone(&orchestrator), Some(spec), None, None);
+
+        let waiting = tokio::spawn(post_json(
+            router.clone(),
+            "/work",
+            serde_json::json!({"args": {}}),
+        ));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(orchestrator.pending_tasks().unwrap().depth(), 1);
+
+        // The queue is full, so the next task fails at once
+        let response = post_json(router, "/work", serde_json::json!({"args": {}})).await;
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let error = json_body(response).await["error"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        assert!(error.contains("pending queue full"), "{}", error);
+
+        // Freeing the CPUs lets the waiting task run
+        orchestrator.workers().write().await[0]
+            .worker
+            .allocation
+            .allocated_cpus = 0.0;
+        orchestrator.pending_tasks().unwrap().released();
+        let response = tokio::time::timeout(Duration::from_secs(2), waiting)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(orchestrator.pending_tasks().unwrap().depth(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_backpressure_rejects_with_retry_after_from_drain_rate() {
+        let mut config = Config::default();
+        config.orchestrator.tasks.backpressure = Some(crate::config::BackpressureConfig {
+            max_queued: None,
+            max_in_flight: Some(1),
+            max_retry_after_secs: 30,
+        });
+        let orchestrator = Arc::new(Orchestrator::new(config));
+        let (handle, worker_side) =
+            mock_worker_handle("default-0", ResourceCapabilities::default());
+        orchestrator.workers().write().await.push(handle);
+        spawn_echo_worker(worker_side, Duration::from_millis(200));
+        let spec = spec_with_routes(&[("POST", "/work", "post_work")]);
+        let router = create_router_with_openapi(orchestrator, Some(spec), None, None);
+        let work = || post_json(router.clone(), "/work", serde_json::json!({"args": {}}));
+
+        // One task finished so far, so the drain rate is one a second
+        assert_eq!(work().await.status(), StatusCode::OK);
+
+        let running = tokio::spawn(work());
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let rejected = work().await;
+        assert_eq!(rejected.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(rejected.headers()["retry-after"], "2");
+        assert_eq!(json_body(rejected).await["retry_after_secs"], 2);
+
+        assert_eq!(running.await.unwrap().status(), StatusCode::OK);
+        assert_eq!(work().await.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_scheduler_explain_reports_skipped_workers() {
+        let orchestrator = Arc::new(Orchestrator::new(Config::default()));
+        let (handle, _worker_side) =
+            mock_worker_handle("default-0", ResourceCapabilities::default());
+        orchestrator.workers().write().await.push(handle);
+        let spec = spec_with_routes(&[("POST", "/work", "post_work")]);
+        let router = create_router_with_openapi(orchestrator, Some(spec), None, None);
+        let entry = serde_json::json!({"handler": "work", "args": {}});
+
+        let body =
+            json_body(post_json(router.clone(), "/admin/scheduler/explain", entry.clone()).await)
+                .await;
+        assert_eq!(body["schedulable"], true);
+        assert_eq!(body["pass"], "idle");
+        assert_eq!(body["workers"][0]["worker_id"], "default-0");
+        assert_eq!(body["workers"][0]["pass"], "idle");
+
+        let req = Request::builder()
+            .method("POST")
+            .uri("/admin/scheduler/explain")
+            .header("content-type", "application/json")
+            .header(GPU_AFFINITY_HEADER, "3")
+            .body(Body::from(entry.to_string()))
+            .unwrap();
+        let body = json_body(router.clone().oneshot(req).await.unwrap()).await;
+        assert_eq!(body["schedulable"], false);
+        assert_eq!(body["placement"]["gpu_device"], 3);
+        assert_eq!(
+            body["workers"][0]["reasons"],
+            serde_json::json!(["not bound to GPU 3"])
+        );
+
+        let unknown = serde_json::json!({"handler": "missing", "args": {}});
+        let response = post_json(router, "/admin/scheduler/explain", unknown).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_cached_route_answers_identical_requests_from_cache() {
+        let orchestrator = Arc::new(Orchestrator::new(Config::default()));
+        let (handle, worker_side) =
+            mock_worker_handle("default-0", ResourceCapabilities::default());
+        orchestrator.workers().write().await.push(handle);
+        spawn_echo_worker(worker_side, Duration::from_millis(1));
+        let mut spec = spec_with_routes(&[("POST", "/work", "post_work")]);
+        spec.paths
+            .get_mut("/work")
+            .unwrap()
+            .post
+            .as_mut()
+            .unwrap()
+            .cache_ttl = Some(60);
+        let router = create_router_with_openapi(Arc::clone(&orchestrator), Some(spec), None, None);
+
+        let first = post_json(
+            router.clone(),
+            "/work",
+            serde_json::json!({"args": {"a": 1, "b": 2}}),
+        )
+        .await;
+        assert_eq!(first.headers()[CACHE_HEADER], "miss");
+        // Same args in another key order
+        let second = post_json(
+            router.clone(),
+            "/work",
+            serde_json::json!({"args": {"b": 2, "a": 1}}),
+        )
+        .await;
+        assert_eq!(second.headers()[CACHE_HEADER], "hit");
+        let second = json_body(second).await;
+        assert_eq!(second["result"], serde_json::json!({"a": 1, "b": 2}));
+        assert_eq!(second["cached"], true);
+        assert!(second["worker_id"].is_null() && second["execution_time_ms"].is_null());
+        assert!(json_body(first).await.get("cached").is_none());
+
+        // Failures aren't cached
+        for _ in 0..2 {
+            let failed = post_json(
+                router.clone(),
+                "/work",
+                serde_json::json!({"args": {"fail": true}}),
+            )
+            .await;
+            assert_eq!(failed.headers()[CACHE_HEADER], "miss");
+        }
+
+        let lookups = orchestrator.metrics().cache_lookups("work").unwrap();
+        assert_eq!((lookups.hits, lookups.misses), (1, 3));
+        assert_eq!(orchestrator.metrics().handler_stats()[0].calls, 3);
+        assert!(orchestrator
+            .metrics()
+            .render()
+            .contains("neutrino_result_cache_hit_ratio{handler=\"work\"} 0.25"));
+    }
+}
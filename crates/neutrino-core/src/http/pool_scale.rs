@@ -0,0 +1,32 @@
+//! Runtime pool scaling: `POST /admin/pools/{name}/scale` spawns or drains
+//! workers to reach a count without restarting the orchestrator.
+
+use axum::{
+    extract::{Path, State},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+
+use super::{AppError, AppState};
+use crate::orchestrator::ScaleError;
+
+/// Body of `POST /admin/pools/{name}/scale`
+#[derive(Debug, Deserialize)]
+pub struct ScaleRequest {
+    pub count: usize,
+}
+
+/// Resize a pool to the requested number of workers
+pub async fn scale_pool(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Json(request): Json<ScaleRequest>,
+) -> Result<Response, AppError> {
+    match state.orchestrator.scale_pool(&name, request.count).await {
+        Ok(scale) => Ok(Json(scale).into_response()),
+        Err(ScaleError::UnknownPool(name)) => Err(AppError::PoolNotFound(name)),
+        Err(ScaleError::OutOfBounds(e)) => Err(AppError::ValidationError(vec![e])),
+        Err(ScaleError::SpawnFailed(e)) => Err(AppError::PoolScaleFailed(e)),
+    }
+}
@@ -0,0 +1,60 @@
+//! Preemption (`tasks.preemption`): a task no worker can take stops a running
+//! task of lower priority whose worker it would fit on once freed. The stopped
+//! task's worker is sent `CancelTask`, and the task is queued again behind the
+//! one that took its place. Only tasks on workers that report `cancellable` in
+//! `WorkerReady` are stopped: elsewhere the task would run to the end anyway,
+//! holding its worker while being run a second time. The bundled Python
+//! worker doesn't report it, so preemption has no effect with it.
+
+use tracing::info;
+
+use super::{AppState, RouteMetadata};
+use crate::orchestrator::Placement;
+use crate::protocol::ResourceRequirements;
+
+/// Preempt one running task to make room for this one, if the policy allows
+/// and a task to stop exists. Returns the preempted task's ID.
+pub async fn make_room(
+    state: &AppState,
+    metadata: &RouteMetadata,
+    resources: &ResourceRequirements,
+    placement: &Placement<'_>,
+) -> Option<String> {
+    let policy = state
+        .orchestrator
+        .config()
+        .orchestrator
+        .tasks
+        .preemption
+        .as_ref()?;
+    if policy.gpu_only && resources.num_gpus <= 0.0 {
+        return None;
+    }
+
+    let max_priority = metadata.priority.saturating_sub(policy.min_priority_gap);
+    for candidate in state.running_tasks.preemptible(max_priority) {
+        let fits = state
+            .orchestrator
+            .fits_once_released(
+                &candidate.worker_id,
+                &candidate.resources,
+                resources,
+                placement,
+            )
+            .await;
+        if fits && state.running_tasks.preempt(&candidate.task_id) {
+            info!(
+                task_id = %candidate.task_id,
+                worker_id = %candidate.worker_id,
+                priority = candidate.priority,
+                "Preempting task {} on worker {} for a {} task of priority {}",
+                candidate.task_id,
+                candidate.worker_id,
+                metadata.handler_name,
+                metadata.priority
+            );
+            return Some(candidate.task_id);
+        }
+    }
+    None
+}
@@ -0,0 +1,248 @@
+//! Result caching for routes with `x-neutrino-cache-ttl`: a successful result
+//! is kept for the TTL and returned for identical requests (same handler,
+//! tenant, and args, whatever the order of their map keys) without
+//! dispatching a task. Only meant for deterministic handlers. Hits are marked
+//! `cached` and carry no worker or execution time.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use ring::digest::{Context, SHA256};
+
+use super::TaskResponse;
+
+/// SHA-256 of a task's handler, tenant, and canonical args, so entries don't
+/// hold on to the args themselves
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CacheKey([u8; 32]);
+
+struct Cached {
+    expires: Instant,
+    /// Tiebreak for entries expiring at the same instant
+    seq: u64,
+    bytes: usize,
+    response: TaskResponse,
+}
+
+#[derive(Default)]
+struct Entries {
+    by_key: HashMap<CacheKey, Cached>,
+    /// Keys in the order they expire, for purging and eviction
+    by_expiry: BTreeMap<(Instant, u64), CacheKey>,
+    bytes: usize,
+    next_seq: u64,
+}
+
+impl Entries {
+    fn remove(&mut self, key: &CacheKey) -> Option<Cached> {
+        let cached = self.by_key.remove(key)?;
+        self.by_expiry.remove(&(cached.expires, cached.seq));
+        self.bytes -= cached.bytes;
+        Some(cached)
+    }
+
+    /// Drop the entry closest to expiring; false if there are none
+    fn evict_soonest(&mut self) -> bool {
+        match self.by_expiry.first_key_value() {
+            Some((_, key)) => {
+                let key = *key;
+                self.remove(&key);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Cached results, bounded by entry count and by their total size
+pub struct ResultCache {
+    max_entries: usize,
+    max_bytes: usize,
+    entries: Mutex<Entries>,
+}
+
+impl ResultCache {
+    pub fn new(max_entries: usize, max_bytes: usize) -> Self {
+        Self {
+            max_entries,
+            max_bytes,
+            entries: Mutex::new(Entries::default()),
+        }
+    }
+
+    /// The cached response for `key`, if it hasn't expired
+    pub fn get(&self, key: &CacheKey) -> Option<TaskResponse> {
+        let mut entries = self.entries.lock().unwrap();
+        match entries.by_key.get(key) {
+            Some(cached) if cached.expires > Instant::now() => Some(cached.response.clone()),
+            Some(_) => {
+                entries.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Cache a response for `ttl`, unless it alone is over the byte budget.
+    /// Expired entries go first, then those closest to expiring, until it fits.
+    pub fn put(&self, key: CacheKey, response: TaskResponse, ttl: Duration) {
+        let bytes = response_size(&response);
+        if self.max_entries == 0 || bytes > self.max_bytes {
+            return;
+        }
+        let now = Instant::now();
+        let mut entries = self.entries.lock().unwrap();
+        entries.remove(&key);
+        while entries
+            .by_expiry
+            .first_key_value()
+            .is_some_and(|((expires, _), _)| *expires <= now)
+        {
+            entries.evict_soonest();
+        }
+        while entries.by_key.len() >= self.max_entries || entries.bytes + bytes > self.max_bytes {
+            if !entries.evict_soonest() {
+                break;
+            }
+        }
+
+        let expires = now + ttl;
+        let seq = entries.next_seq;
+        entries.next_seq += 1;
+        entries.by_expiry.insert((expires, seq), key);
+        entries.bytes += bytes;
+        entries.by_key.insert(
+            key,
+            Cached {
+                expires,
+                seq,
+                bytes,
+                response,
+            },
+        );
+    }
+}
+
+/// Approximate memory held by a cached response: its JSON form plus the
+/// worker's msgpack result
+fn response_size(response: &TaskResponse) -> usize {
+    let json = serde_json::to_vec(response).map_or(0, |json| json.len());
+    let mut raw = Vec::new();
+    if let Some(raw_result) = &response.raw_result {
+        let _ = rmpv::encode::write_value(&mut raw, raw_result);
+    }
+    json + raw.len()
+}
+
+/// Cache key of a task: map keys are sorted at every depth, so requests
+/// differing only in key order share a result
+pub fn cache_key(handler: &str, tenant: Option<&str>, args: &rmpv::Value) -> Option<CacheKey> {
+    let mut encoded = Vec::new();
+    rmpv::encode::write_value(&mut encoded, &canonical(args)).ok()?;
+    let mut context = Context::new(&SHA256);
+    // Length-prefixed, so no handler/tenant pair hashes like another
+    for part in [Some(handler), tenant] {
+        match part {
+            Some(part) => {
+                context.update(&(part.len() as u64 + 1).to_be_bytes());
+                context.update(part.as_bytes());
+            }
+            None => context.update(&0u64.to_be_bytes()),
+        }
+    }
+    context.update(&encoded);
+    let mut key = [0; 32];
+    key.copy_from_slice(context.finish().as_ref());
+    Some(CacheKey(key))
+}
+
+fn canonical(value: &rmpv::Value) -> rmpv::Value {
+    match value {
+        rmpv::Value::Map(entries) => {
+            let mut entries: Vec<(rmpv::Value, rmpv::Value)> = entries
+                .iter()
+                .map(|(key, value)| (canonical(key), canonical(value)))
+                .collect();
+            entries.sort_by_cached_key(|(key, _)| {
+                let mut encoded = Vec::new();
+                let _ = rmpv::encode::write_value(&mut encoded, key);
+                encoded
+            });
+            rmpv::Value::Map(entries)
+        }
+        rmpv::Value::Array(items) => rmpv::Value::Array(items.iter().map(canonical).collect()),
+        other => other.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(result: i64) -> TaskResponse {
+        TaskResponse {
+            success: true,
+            result: Some(serde_json::json!(result)),
+            error: None,
+            worker_id: Some("default-0".to_string()),
+            execution_time_ms: Some(5),
+            queue_wait_ms: Some(0),
+            traceback: None,
+            cached: false,
+            raw_result: Some(result.into()),
+        }
+    }
+
+    fn key(args: rmpv::Value) -> CacheKey {
+        cache_key("embed", None, &args).unwrap()
+    }
+
+    #[test]
+    fn test_key_ignores_map_order() {
+        let ab = rmpv::Value::Map(vec![("a".into(), 1.into()), ("b".into(), 2.into())]);
+        let ba = rmpv::Value::Map(vec![("b".into(), 2.into()), ("a".into(), 1.into())]);
+        assert_eq!(key(ab), key(ba));
+        assert_ne!(key(rmpv::Value::from(1)), key(rmpv::Value::from(2)));
+        assert_ne!(
+            cache_key("embed", Some("acme"), &1.into()),
+            cache_key("embed", None, &1.into())
+        );
+    }
+
+    #[test]
+    fn test_entries_expire_and_evict_soonest_first() {
+        let cache = ResultCache::new(2, usize::MAX);
+        cache.put(key(1.into()), response(1), Duration::ZERO);
+        assert!(cache.get(&key(1.into())).is_none());
+
+        cache.put(key(1.into()), response(1), Duration::from_secs(10));
+        cache.put(key(2.into()), response(2), Duration::from_secs(60));
+        cache.put(key(3.into()), response(3), Duration::from_secs(60));
+        assert!(cache.get(&key(1.into())).is_none());
+        assert_eq!(
+            cache.get(&key(2.into())).unwrap().result,
+            Some(serde_json::json!(2))
+        );
+        assert_eq!(
+            cache.get(&key(3.into())).unwrap().result,
+            Some(serde_json::json!(3))
+        );
+    }
+
+    #[test]
+    fn test_byte_budget_evicts_soonest_and_skips_oversized() {
+        let bytes = response_size(&response(1));
+        let cache = ResultCache::new(10, 2 * bytes);
+        cache.put(key(1.into()), response(1), Duration::from_secs(10));
+        cache.put(key(2.into()), response(2), Duration::from_secs(60));
+        cache.put(key(3.into()), response(3), Duration::from_secs(60));
+        assert!(cache.get(&key(1.into())).is_none());
+        assert!(cache.get(&key(2.into())).is_some());
+        assert!(cache.get(&key(3.into())).is_some());
+
+        let small = ResultCache::new(10, bytes - 1);
+        small.put(key(1.into()), response(1), Duration::from_secs(60));
+        assert!(small.get(&key(1.into())).is_none());
+    }
+}
@@ -0,0 +1,254 @@
+//! Runtime route patching: `PATCH /admin/routes` adds or updates one task route
+//! without reloading the spec (enabled with `http.allow_route_patching`).
+//!
+//! Patched routes are served from a router of their own, consulted by the
+//! fallback before the ASGI app. Each path gets its own router, so a patch only
+//! rebuilds the path it touches. Routes from the spec and built-in endpoints
+//! can't be replaced or shadowed.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::RwLock;
+
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::MethodRouter,
+    Json, Router,
+};
+use serde::Deserialize;
+use tracing::info;
+
+use super::{task_method_router, AppError, AppState};
+use crate::config::HttpConfig;
+use crate::openapi::{convert_openapi_path_to_axum, ArgsTemplate, RouteInfo};
+use crate::protocol::ResourceRequirements;
+
+const METHODS: [&str; 5] = ["GET", "POST", "PUT", "PATCH", "DELETE"];
+
+/// Body of `PATCH /admin/routes`: one task route, like an entry of the spec
+#[derive(Debug, Clone, Deserialize)]
+pub struct RoutePatch {
+    /// Route path, OpenAPI (`/items/{id}`) or axum (`/items/:id`) style
+    pub path: String,
+    pub method: String,
+    pub handler_name: String,
+    #[serde(default)]
+    pub resources: ResourceRequirements,
+    /// Request body limit (None = global limit)
+    #[serde(default)]
+    pub max_body_bytes: Option<usize>,
+    /// Content type for raw scalar results
+    #[serde(default)]
+    pub default_result_content_type: Option<String>,
+    /// Pool to run on, without the GPU requirement, when no worker can take the task
+    #[serde(default)]
+    pub overflow_pool: Option<String>,
+    /// Pool the route's tasks are pinned to (see x-neutrino-pool)
+    #[serde(default)]
+    pub pool: Option<String>,
+    /// Most tasks of the handler running at once (see x-neutrino-max-concurrency)
+    #[serde(default)]
+    pub max_concurrency: Option<usize>,
+    /// Hedging delay for GET routes (see x-neutrino-hedge-after-ms)
+    #[serde(default)]
+    pub hedge_after_ms: Option<u64>,
+    /// Result cache lifetime in seconds (see x-neutrino-cache-ttl)
+    #[serde(default)]
+    pub cache_ttl: Option<u64>,
+    /// Dispatch priority while waiting for a worker; higher goes first
+    #[serde(default)]
+    pub priority: i32,
+    /// Shape the handler's args are rebuilt into (see x-neutrino-args-template)
+    #[serde(default)]
+    pub args_template: Option<ArgsTemplate>,
+    /// Dedicated pool per tenant ID (see x-neutrino-tenant-pools)
+    #[serde(default)]
+    pub tenant_pools: HashMap<String, String>,
+    /// Identical concurrent requests share one task (see x-neutrino-coalesce)
+    #[serde(default)]
+    pub coalesce: bool,
+    /// Arg naming the request's session (see x-neutrino-affinity-key)
+    #[serde(default)]
+    pub affinity_key: Option<String>,
+}
+
+impl RoutePatch {
+    fn into_route_info(self) -> Result<RouteInfo, AppError> {
+        let method = self.method.to_ascii_uppercase();
+        let mut violations = Vec::new();
+        if !METHODS.contains(&method.as_str()) {
+            violations.push(format!("unsupported method '{}'", self.method));
+        }
+        if !self.path.starts_with('/') {
+            violations.push(format!("path '{}' must start with '/'", self.path));
+        }
+        if self.handler_name.is_empty() {
+            violations.push("handler_name must not be empty".to_string());
+        }
+        if !violations.is_empty() {
+            return Err(AppError::ValidationError(violations));
+        }
+
+        Ok(RouteInfo {
+            path: convert_openapi_path_to_axum(&self.path),
+            operation_id: self.handler_name.clone(),
+            method,
+            handler_name: self.handler_name,
+            resources: self.resources,
+            request_schema: Default::default(),
+            response_schema: None,
+            max_body_bytes: self.max_body_bytes,
+            default_result_content_type: self.default_result_content_type,
+            overflow_pool: self.overflow_pool,
+            pool: self.pool,
+            max_concurrency: self.max_concurrency,
+            hedge_after_ms: self.hedge_after_ms,
+            cache_ttl: self.cache_ttl,
+            priority: self.priority,
+            args_template: self.args_template,
+            tenant_pools: self.tenant_pools,
+            coalesce: self.coalesce,
+            affinity_key: self.affinity_key,
+        })
+    }
+}
+
+/// Routes added at runtime, checked against the routes fixed at startup
+pub struct PatchedRoutes {
+    /// Paths served by the main router (spec and built-in routes)
+    fixed: matchit::Router<String>,
+    fixed_paths: Vec<String>,
+    inner: RwLock<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    /// Patched path pattern matched against request paths
+    matcher: matchit::Router<String>,
+    paths: HashMap<String, PatchedPath>,
+}
+
+struct PatchedPath {
+    methods: BTreeMap<String, MethodRouter<AppState>>,
+    router: Router<AppState>,
+}
+
+impl PatchedRoutes {
+    pub fn new(fixed_paths: impl IntoIterator<Item = String>) -> Self {
+        let mut fixed = matchit::Router::new();
+        let fixed_paths: Vec<String> = fixed_paths.into_iter().collect();
+        for path in &fixed_paths {
+            // The main router already rejected conflicting paths
+            let _ = fixed.insert(path.clone(), path.clone());
+        }
+        Self {
+            fixed,
+            fixed_paths,
+            inner: RwLock::new(Inner::default()),
+        }
+    }
+
+    /// Router serving the patched route matching `path`, if any
+    pub fn router_for(&self, path: &str) -> Option<Router<AppState>> {
+        let inner = self.inner.read().unwrap();
+        let matched = inner.matcher.at(path).ok()?;
+        inner
+            .paths
+            .get(matched.value)
+            .map(|patched| patched.router.clone())
+    }
+
+    /// Add or replace the route for its method and path, returning whether it is new
+    fn upsert(&self, route: &RouteInfo, http_config: &HttpConfig) -> Result<bool, AppError> {
+        if let Some(existing) = self.fixed_overlap(&route.path) {
+            return Err(AppError::RouteConflict(format!(
+                "{} overlaps existing route {}",
+                route.path, existing
+            )));
+        }
+        let Some(method_router) = task_method_router(route, http_config) else {
+            return Err(AppError::ValidationError(vec![format!(
+                "unsupported method '{}'",
+                route.method
+            )]));
+        };
+
+        let mut inner = self.inner.write().unwrap();
+        if !inner.paths.contains_key(&route.path) {
+            inner
+                .matcher
+                .insert(route.path.clone(), route.path.clone())
+                .map_err(|e| AppError::RouteConflict(format!("{}: {}", route.path, e)))?;
+        }
+
+        // Only this path's router is rebuilt
+        let patched = inner
+            .paths
+            .entry(route.path.clone())
+            .or_insert_with(|| PatchedPath {
+                methods: BTreeMap::new(),
+                router: Router::new(),
+            });
+        let created = patched
+            .methods
+            .insert(route.method.clone(), method_router)
+            .is_none();
+        let combined = patched
+            .methods
+            .values()
+            .cloned()
+            .reduce(MethodRouter::merge)
+            .expect("patched path has at least one method");
+        patched.router = Router::new().route(&route.path, combined);
+
+        Ok(created)
+    }
+
+    /// A fixed route that `path` would match or be matched by
+    fn fixed_overlap(&self, path: &str) -> Option<String> {
+        if let Ok(matched) = self.fixed.at(path) {
+            return Some(matched.value.clone());
+        }
+        let mut probe = matchit::Router::new();
+        probe.insert(path, ()).ok()?;
+        self.fixed_paths
+            .iter()
+            .find(|fixed| probe.at(fixed).is_ok())
+            .cloned()
+    }
+}
+
+/// PATCH /admin/routes - add (201) or update (200) one task route
+pub async fn patch_route(
+    State(state): State<AppState>,
+    Json(patch): Json<RoutePatch>,
+) -> Result<Response, AppError> {
+    let patched_routes = state
+        .patched_routes
+        .as_ref()
+        .ok_or_else(|| AppError::RouteNotFound("/admin/routes".to_string()))?;
+    let route = patch.into_route_info()?;
+
+    let created = patched_routes.upsert(&route, &state.orchestrator.config().orchestrator.http)?;
+    info!(
+        "{} route at runtime: {} {} -> {}",
+        if created { "Added" } else { "Updated" },
+        route.method,
+        route.path,
+        route.handler_name
+    );
+
+    let status = if created {
+        StatusCode::CREATED
+    } else {
+        StatusCode::OK
+    };
+    let body = Json(serde_json::json!({
+        "method": route.method,
+        "path": route.path,
+        "handler_name": route.handler_name,
+        "created": created,
+    }));
+    Ok((status, body).into_response())
+}
@@ -0,0 +1,113 @@
+//! Background runs of `orchestrator.schedules`: each schedule waits for its
+//! next cron minute, then calls its handler as a `POST /batch` entry would.
+//! A run still going when the next minute comes due delays it rather than
+//! overlapping. With `http.async_results` configured each run's response is
+//! stored under the task id `<schedule>-<unix time>`, for
+//! `GET /tasks/:task_id/result`; otherwise runs are only logged.
+
+use axum::http::HeaderMap;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tracing::{error, info, info_span, warn, Instrument};
+
+use super::batch::{run_task, BatchEntry, EntryRequest};
+use super::error_details::ErrorDetail;
+use super::{AppError, AppState, TaskOutcome};
+use crate::config::ScheduleConfig;
+use crate::results::{self, ResultStatus, StoredResult};
+use crate::schedule::Cron;
+
+/// Start a background loop for every schedule with a valid cron expression
+pub fn spawn_schedules(state: &AppState) {
+    let schedules = &state.orchestrator.config().orchestrator.schedules;
+    if !schedules.is_empty() && state.result_store.is_none() {
+        warn!("Schedules configured without http.async_results; scheduled runs are only logged");
+    }
+    for schedule in schedules {
+        match Cron::parse(&schedule.cron) {
+            Ok(cron) => {
+                info!(
+                    "Scheduled handler {} ({}) on '{}'",
+                    schedule.handler, schedule.name, schedule.cron
+                );
+                tokio::spawn(run_schedule(state.clone(), schedule.clone(), cron));
+            }
+            Err(e) => error!("Schedule {} not started: {}", schedule.name, e),
+        }
+    }
+}
+
+async fn run_schedule(state: AppState, schedule: ScheduleConfig, cron: Cron) {
+    loop {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let Some(due) = cron.next_after(now.as_secs()) else {
+            warn!(
+                "Schedule {} has no upcoming run; stopping it",
+                schedule.name
+            );
+            return;
+        };
+        tokio::time::sleep(Duration::from_secs(due).saturating_sub(now)).await;
+
+        let task_id = format!("{}-{}", schedule.name, due);
+        let span = info_span!("task", task_id = %task_id, handler = %schedule.handler, schedule = %schedule.name);
+        run_once(&state, &schedule, task_id).instrument(span).await;
+    }
+}
+
+/// Run the schedule's handler once and record the outcome
+async fn run_once(state: &AppState, schedule: &ScheduleConfig, task_id: String) {
+    info!("Running scheduled task");
+    let entry = BatchEntry {
+        handler: schedule.handler.clone(),
+        args: schedule.args.clone(),
+    };
+    let request = EntryRequest {
+        headers: &HeaderMap::new(),
+        peer: None,
+        error_detail: ErrorDetail::Full,
+        respond_async: false,
+        start: Instant::now(),
+    };
+    let stored = match run_task(state, request, entry, &task_id).await {
+        Ok(TaskOutcome::Completed { task_response, .. }) => {
+            if task_response.success {
+                info!("Scheduled task completed");
+            } else {
+                warn!(
+                    "Scheduled task failed: {}",
+                    task_response.error.as_deref().unwrap_or_default()
+                );
+            }
+            StoredResult {
+                status: if task_response.success {
+                    ResultStatus::Completed
+                } else {
+                    ResultStatus::Failed
+                },
+                response: serde_json::to_value(&task_response).ok(),
+            }
+        }
+        Ok(TaskOutcome::Accepted { .. }) => unreachable!("scheduled runs never run async"),
+        Err(e) => failed(e),
+    };
+
+    if let Some(store) = &state.result_store {
+        if let Err(e) = results::put(store, &task_id, stored).await {
+            warn!(
+                "Failed to store result for scheduled task {}: {}",
+                task_id, e
+            );
+        }
+    }
+}
+
+fn failed(e: AppError) -> StoredResult {
+    let (status, message) = e.status_and_message();
+    warn!("Scheduled task failed with {}: {}", status, message);
+    StoredResult {
+        status: ResultStatus::Failed,
+        response: Some(serde_json::json!({"error": message, "status": status.as_u16()})),
+    }
+}
@@ -0,0 +1,176 @@
+//! The tenant a request is made for, with `http.tenants` configured. It is
+//! taken only from verified sources: a claim of a bearer token whose signature
+//! checks out, or a header set by a trusted proxy that authenticated the
+//! client. It is tagged on the task's logs, counted in per-tenant metrics, and
+//! matched against a route's `x-neutrino-tenant-pools` to pick the pool the
+//! task runs on.
+
+use axum::http::{header::AUTHORIZATION, HeaderMap};
+use base64::Engine;
+use std::net::IpAddr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config::TenantConfig;
+
+/// The request's tenant ID, if it names a valid one. The JWT claim (when
+/// configured and the token verifies) takes precedence over the header, which
+/// is only believed from `trusted_cidrs`.
+pub fn tenant_id(
+    config: Option<&TenantConfig>,
+    peer: Option<IpAddr>,
+    headers: &HeaderMap,
+) -> Option<String> {
+    let config = config?;
+    let from_claim = config
+        .jwt_claim
+        .as_deref()
+        .zip(config.jwt_secret.as_deref())
+        .and_then(|(claim, secret)| jwt_claim(headers, claim, secret));
+    let trusted_peer = peer
+        .map(|ip| ip.to_canonical())
+        .is_some_and(|ip| config.trusted_cidrs.iter().any(|net| net.contains(&ip)));
+    let from_header = || {
+        headers
+            .get(config.header.as_str())
+            .filter(|_| trusted_peer)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.trim().to_string())
+    };
+    from_claim.or_else(from_header).filter(|id| is_valid(id))
+}
+
+/// A claim from the payload of the request's bearer JWT, if the token is an
+/// HS256 JWT signed with `secret` and not expired
+fn jwt_claim(headers: &HeaderMap, claim: &str, secret: &str) -> Option<String> {
+    let token = headers
+        .get(AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")?
+        .trim();
+    let decode = |part: &str| {
+        base64::engine::general_purpose::URL_SAFE_NO_PAD
+            .decode(part.trim_end_matches('='))
+            .ok()
+    };
+    let mut parts = token.split('.');
+    let (header, payload, signature) = (parts.next()?, parts.next()?, parts.next()?);
+    if parts.next().is_some() {
+        return None;
+    }
+
+    // Only the algorithm we check with, so a token can't pick "none"
+    let jose: serde_json::Value = serde_json::from_slice(&decode(header)?).ok()?;
+    if jose.get("alg")?.as_str()? != "HS256" {
+        return None;
+    }
+    let key = ring::hmac::Key::new(ring::hmac::HMAC_SHA256, secret.as_bytes());
+    let signed = format!("{}.{}", header, payload);
+    ring::hmac::verify(&key, signed.as_bytes(), &decode(signature)?).ok()?;
+
+    let claims: serde_json::Value = serde_json::from_slice(&decode(payload)?).ok()?;
+    if let Some(exp) = claims.get("exp") {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
+        if exp.as_u64()? <= now {
+            return None;
+        }
+    }
+    match claims.get(claim)? {
+        serde_json::Value::String(id) => Some(id.clone()),
+        serde_json::Value::Number(id) => Some(id.to_string()),
+        _ => None,
+    }
+}
+
+/// Tenant IDs end up in metric labels and log fields, so keep them short and plain
+fn is_valid(id: &str) -> bool {
+    (1..=64).contains(&id.len())
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn bearer(claims: serde_json::Value, secret: &str) -> HeaderValue {
+        let encode = |bytes: &[u8]| base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes);
+        let signed = format!(
+            "{}.{}",
+            encode(br#"{"alg":"HS256","typ":"JWT"}"#),
+            encode(claims.to_string().as_bytes())
+        );
+        let key = ring::hmac::Key::new(ring::hmac::HMAC_SHA256, secret.as_bytes());
+        let signature = encode(ring::hmac::sign(&key, signed.as_bytes()).as_ref());
+        HeaderValue::from_str(&format!("Bearer {}.{}", signed, signature)).unwrap()
+    }
+
+    #[test]
+    fn test_tenant_from_verified_claim_then_trusted_header() {
+        let config = TenantConfig {
+            header: "x-tenant-id".to_string(),
+            trusted_cidrs: vec!["10.0.0.0/8".parse().unwrap()],
+            jwt_claim: Some("org".to_string()),
+            jwt_secret: Some("s3cret".to_string()),
+        };
+        let gateway = Some(IpAddr::from([10, 0, 0, 7]));
+        let outsider = Some(IpAddr::from([203, 0, 113, 5]));
+        let mut headers = HeaderMap::new();
+        assert_eq!(tenant_id(None, gateway, &headers), None);
+
+        // The header is only believed from a trusted proxy
+        headers.insert("x-tenant-id", HeaderValue::from_static("acme"));
+        assert_eq!(
+            tenant_id(Some(&config), gateway, &headers).as_deref(),
+            Some("acme")
+        );
+        assert_eq!(tenant_id(Some(&config), outsider, &headers), None);
+        assert_eq!(tenant_id(Some(&config), None, &headers), None);
+        assert_eq!(tenant_id(None, gateway, &headers), None);
+
+        // A verified claim wins over the header, from anywhere; a token
+        // without it falls back
+        let claims = serde_json::json!({"org": "globex"});
+        headers.insert(AUTHORIZATION, bearer(claims.clone(), "s3cret"));
+        assert_eq!(
+            tenant_id(Some(&config), gateway, &headers).as_deref(),
+            Some("globex")
+        );
+        assert_eq!(
+            tenant_id(Some(&config), outsider, &headers).as_deref(),
+            Some("globex")
+        );
+        headers.insert(
+            AUTHORIZATION,
+            bearer(serde_json::json!({"sub": "ada"}), "s3cret"),
+        );
+        assert_eq!(
+            tenant_id(Some(&config), gateway, &headers).as_deref(),
+            Some("acme")
+        );
+
+        // Tokens signed with another key, unsigned, or expired are ignored
+        let mut headers = HeaderMap::new();
+        headers.insert(AUTHORIZATION, bearer(claims, "guessed"));
+        assert_eq!(tenant_id(Some(&config), outsider, &headers), None);
+        let encode = |bytes: &[u8]| base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes);
+        let unsigned = format!(
+            "Bearer {}.{}.",
+            encode(br#"{"alg":"none"}"#),
+            encode(br#"{"org":"globex"}"#)
+        );
+        headers.insert(AUTHORIZATION, HeaderValue::from_str(&unsigned).unwrap());
+        assert_eq!(tenant_id(Some(&config), outsider, &headers), None);
+        headers.insert(
+            AUTHORIZATION,
+            bearer(serde_json::json!({"org": "globex", "exp": 1}), "s3cret"),
+        );
+        assert_eq!(tenant_id(Some(&config), outsider, &headers), None);
+
+        // IDs unfit for metric labels are ignored
+        headers.insert("x-tenant-id", HeaderValue::from_static("acme\"} 1"));
+        assert_eq!(tenant_id(Some(&config), gateway, &headers), None);
+    }
+}
@@ -0,0 +1,149 @@
+//! Journal of accepted async tasks that haven't finished, so a restart (or
+//! crash) of the orchestrator doesn't silently lose them: whatever is still
+//! journaled at startup is dispatched again. Entries are written when a task
+//! is accepted and removed once its result is stored.
+
+use rusqlite::{params, Connection};
+use std::path::Path;
+use std::sync::Mutex;
+
+/// An accepted, unfinished task
+#[derive(Debug, Clone, PartialEq)]
+pub struct JournalEntry {
+    pub task_id: String,
+    pub handler: String,
+    /// The task's args as msgpack, as dispatched to the handler
+    pub args: Vec<u8>,
+    /// Unix time (seconds) the task was accepted
+    pub accepted_at: u64,
+    /// Times the task was recovered after a restart
+    pub recoveries: u32,
+}
+
+/// SQLite-backed task journal
+pub struct TaskJournal {
+    conn: Mutex<Connection>,
+}
+
+impl TaskJournal {
+    pub fn open<P: AsRef<Path>>(path: P) -> rusqlite::Result<Self> {
+        if let Some(parent) = path.as_ref().parent() {
+            std::fs::create_dir_all(parent).ok();
+        }
+
+        let conn = Connection::open(path.as_ref())?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS task_journal (
+                task_id TEXT PRIMARY KEY,
+                handler TEXT NOT NULL,
+                args BLOB NOT NULL,
+                accepted_at INTEGER NOT NULL,
+                recoveries INTEGER NOT NULL
+            )",
+            [],
+        )?;
+
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Journal a task, replacing its earlier entry if any
+    pub fn record(&self, entry: &JournalEntry) -> Result<(), String> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO task_journal (task_id, handler, args, accepted_at, recoveries)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                entry.task_id,
+                entry.handler,
+                entry.args,
+                entry.accepted_at,
+                entry.recoveries
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    /// Drop a finished task's entry
+    pub fn finish(&self, task_id: &str) -> Result<(), String> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "DELETE FROM task_journal WHERE task_id = ?1",
+            params![task_id],
+        )
+        .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    /// Every journaled task, oldest first
+    pub fn unfinished(&self) -> Result<Vec<JournalEntry>, String> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare(
+                "SELECT task_id, handler, args, accepted_at, recoveries
+                 FROM task_journal ORDER BY accepted_at, rowid",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok(JournalEntry {
+                    task_id: row.get(0)?,
+                    handler: row.get(1)?,
+                    args: row.get(2)?,
+                    accepted_at: row.get(3)?,
+                    recoveries: row.get(4)?,
+                })
+            })
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(task_id: &str, accepted_at: u64) -> JournalEntry {
+        JournalEntry {
+            task_id: task_id.to_string(),
+            handler: "embed".to_string(),
+            args: vec![0x81, 0xa1, 0x6e, 0x01],
+            accepted_at,
+            recoveries: 0,
+        }
+    }
+
+    #[test]
+    fn test_unfinished_tasks_survive_reopen() {
+        let path =
+            std::env::temp_dir().join(format!("neutrino-journal-{}.db", uuid::Uuid::new_v4()));
+        let journal = TaskJournal::open(&path).unwrap();
+        journal.record(&entry("t2", 20)).unwrap();
+        journal.record(&entry("t1", 10)).unwrap();
+        journal.record(&entry("t3", 30)).unwrap();
+        journal.finish("t3").unwrap();
+        journal
+            .record(&JournalEntry {
+                recoveries: 1,
+                ..entry("t2", 20)
+            })
+            .unwrap();
+        drop(journal);
+
+        let reopened = TaskJournal::open(&path).unwrap();
+        assert_eq!(
+            reopened.unfinished().unwrap(),
+            vec![
+                entry("t1", 10),
+                JournalEntry {
+                    recoveries: 1,
+                    ..entry("t2", 20)
+                }
+            ]
+        );
+
+        let _ = std::fs::remove_file(path);
+    }
+}
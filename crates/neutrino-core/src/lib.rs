@@ -1,11 +1,27 @@
 pub mod asgi_manager;
+pub mod chaos;
 pub mod config;
+pub mod dag;
+pub mod dead_letters;
+pub mod fds;
+pub mod header_limits;
 pub mod http;
+pub mod journal;
+pub mod metrics;
 pub mod openapi;
 pub mod orchestrator;
 pub mod protocol;
+pub mod redact;
+pub mod results;
+pub mod schedule;
+pub mod serde_convert;
+pub mod snapshot;
+pub mod telemetry;
 pub mod worker;
 
+#[cfg(test)]
+mod testing;
+
 pub use asgi_manager::AsgiManager;
 pub use config::Config;
 pub use openapi::OpenApiSpec;
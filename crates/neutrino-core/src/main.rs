@@ -1,31 +1,27 @@
-use neutrino_core::{AsgiManager, Config, Orchestrator};
+use neutrino_core::http::handoff;
+use neutrino_core::{telemetry, AsgiManager, Config, Orchestrator};
 use std::sync::Arc;
 use tracing::{error, info, Level};
-use tracing_subscriber;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Initialize tracing
-    tracing_subscriber::fmt()
-        .with_max_level(Level::INFO)
-        .init();
+    // Get config path from command-line arguments or use default
+    let config_path = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "config.yaml".to_string());
 
-    info!("Starting Neutrino orchestrator");
+    // Load configuration (logged once tracing is up)
+    let loaded = Config::from_file(&config_path);
+    let config = loaded.as_ref().cloned().unwrap_or_default();
 
-    // Get config path from command-line arguments or use default
-    let config_path = std::env::args().nth(1).unwrap_or_else(|| "config.yaml".to_string());
+    // Initialize tracing, exporting to OpenTelemetry if configured
+    let _telemetry = telemetry::init(Level::INFO, config.orchestrator.otel.as_ref());
 
-    // Load configuration
-    let config = match Config::from_file(&config_path) {
-        Ok(cfg) => {
-            info!("Loaded configuration from {}", config_path);
-            cfg
-        }
-        Err(e) => {
-            info!("Could not load {}: {}, using defaults", config_path, e);
-            Config::default()
-        }
-    };
+    info!("Starting Neutrino orchestrator");
+    match &loaded {
+        Ok(_) => info!("Loaded configuration from {}", config_path),
+        Err(e) => info!("Could not load {}: {}, using defaults", config_path, e),
+    }
 
     // Create orchestrator
     let orchestrator = Arc::new(Orchestrator::new(config.clone()));
@@ -65,12 +61,13 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let server_host = http_host.clone();
     let server_asgi_config = asgi_config.clone();
     let server_handle = tokio::spawn(async move {
-        if let Err(e) = neutrino_core::http::start_server_with_openapi(
+        if let Err(e) = neutrino_core::http::start_server_with_shutdown(
             server_orchestrator,
             server_host,
             http_port,
             openapi_spec.as_deref(),
             server_asgi_config,
+            handoff::shutdown_signal(),
         )
         .await
         {
@@ -83,10 +80,13 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         http_host, http_port
     );
 
-    // Wait for shutdown signal (Ctrl+C)
-    tokio::signal::ctrl_c().await?;
+    // Serve until Ctrl+C / SIGTERM; the server stops accepting and lets
+    // in-flight requests finish before returning
+    if let Err(e) = server_handle.await {
+        error!("HTTP server task failed: {}", e);
+    }
 
-    info!("Received shutdown signal");
+    info!("HTTP server stopped, shutting down");
 
     // Shutdown ASGI manager first (if running)
     if let Some(mut manager) = asgi_manager {
@@ -99,9 +99,6 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Gracefully shutdown orchestrator
     orchestrator.shutdown().await?;
 
-    // Wait for server to finish
-    server_handle.abort();
-
     info!("Neutrino shutdown complete");
     Ok(())
 }
\ No newline at end of file
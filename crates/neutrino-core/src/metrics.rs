@@ -0,0 +1,538 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Histogram bucket upper bounds in seconds
+const LATENCY_BUCKETS: &[f64] = &[
+    0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0,
+];
+
+/// Cumulative histogram in the Prometheus style
+#[derive(Debug, Clone)]
+pub struct Histogram {
+    buckets: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self {
+            buckets: vec![0; LATENCY_BUCKETS.len()],
+            sum: 0.0,
+            count: 0,
+        }
+    }
+}
+
+impl Histogram {
+    /// Record a single observation (in seconds)
+    pub fn observe(&mut self, value: f64) {
+        for (idx, bound) in LATENCY_BUCKETS.iter().enumerate() {
+            if value <= *bound {
+                self.buckets[idx] += 1;
+            }
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn sum(&self) -> f64 {
+        self.sum
+    }
+
+    /// Append this histogram in Prometheus text format
+    fn render(&self, out: &mut String, name: &str, labels: &str) {
+        for (idx, bound) in LATENCY_BUCKETS.iter().enumerate() {
+            let _ = writeln!(
+                out,
+                "{}_bucket{{{},le=\"{}\"}} {}",
+                name, labels, bound, self.buckets[idx]
+            );
+        }
+        let _ = writeln!(
+            out,
+            "{}_bucket{{{},le=\"+Inf\"}} {}",
+            name, labels, self.count
+        );
+        let _ = writeln!(out, "{}_sum{{{}}} {}", name, labels, self.sum);
+        let _ = writeln!(out, "{}_count{{{}}} {}", name, labels, self.count);
+    }
+}
+
+/// Number of recent latencies kept per handler for percentile estimates
+const LATENCY_WINDOW: usize = 1024;
+
+/// Running call statistics for a single handler
+#[derive(Debug, Clone, Default)]
+struct HandlerCalls {
+    calls: u64,
+    successes: u64,
+    duration: Histogram,
+    /// Most recent latencies (seconds), bounded by LATENCY_WINDOW
+    recent: VecDeque<f64>,
+}
+
+/// Tenants tracked individually; later ones are counted under "other" so
+/// client-supplied tenant IDs can't grow the label set without bound
+const MAX_TENANTS: usize = 1000;
+
+/// Task outcomes and worker time for a single tenant
+#[derive(Debug, Clone, Default)]
+struct TenantCalls {
+    calls: u64,
+    successes: u64,
+    /// Total end-to-end task time, in seconds
+    seconds: f64,
+}
+
+/// Result cache lookups for a single handler
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CacheLookups {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+/// Aggregated statistics for a handler, as returned by /admin/stats
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HandlerStats {
+    pub handler: String,
+    pub calls: u64,
+    pub successes: u64,
+    pub failures: u64,
+    pub success_rate: f64,
+    pub avg_latency_ms: f64,
+    pub p95_latency_ms: f64,
+}
+
+/// In-memory metrics exposed at /metrics
+#[derive(Debug, Default)]
+pub struct Metrics {
+    /// Time tasks spend waiting before dispatch, keyed by handler
+    queue_wait: Mutex<BTreeMap<String, Histogram>>,
+    /// Task outcomes and end-to-end latency, keyed by handler
+    handlers: Mutex<BTreeMap<String, HandlerCalls>>,
+    /// Task outcomes and time, keyed by tenant
+    tenants: Mutex<BTreeMap<String, TenantCalls>>,
+    /// Result cache hits and misses, keyed by handler
+    cache_lookups: Mutex<BTreeMap<String, CacheLookups>>,
+    /// The same observations, recorded to the global OpenTelemetry meter
+    #[cfg(feature = "otel")]
+    otel: otel::Instruments,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record how long a task waited before being dispatched to a worker
+    pub fn observe_queue_wait(&self, handler: &str, wait: Duration) {
+        self.queue_wait
+            .lock()
+            .unwrap()
+            .entry(handler.to_string())
+            .or_default()
+            .observe(wait.as_secs_f64());
+
+        #[cfg(feature = "otel")]
+        self.otel.observe_queue_wait(handler, wait);
+    }
+
+    /// Get the queue wait histogram for a handler
+    pub fn queue_wait(&self, handler: &str) -> Option<Histogram> {
+        self.queue_wait.lock().unwrap().get(handler).cloned()
+    }
+
+    /// Record a completed task (success or failure) and its end-to-end latency
+    pub fn observe_task(&self, handler: &str, success: bool, latency: Duration) {
+        let mut handlers = self.handlers.lock().unwrap();
+        let calls = handlers.entry(handler.to_string()).or_default();
+
+        calls.calls += 1;
+        if success {
+            calls.successes += 1;
+        }
+        calls.duration.observe(latency.as_secs_f64());
+        if calls.recent.len() == LATENCY_WINDOW {
+            calls.recent.pop_front();
+        }
+        calls.recent.push_back(latency.as_secs_f64());
+
+        #[cfg(feature = "otel")]
+        self.otel.observe_task(handler, success, latency);
+    }
+
+    /// Record a completed task made for `tenant`
+    pub fn observe_tenant_task(&self, tenant: &str, success: bool, latency: Duration) {
+        let mut tenants = self.tenants.lock().unwrap();
+        let key = if tenants.contains_key(tenant) || tenants.len() < MAX_TENANTS {
+            tenant
+        } else {
+            "other"
+        };
+        let calls = tenants.entry(key.to_string()).or_default();
+
+        calls.calls += 1;
+        if success {
+            calls.successes += 1;
+        }
+        calls.seconds += latency.as_secs_f64();
+
+        #[cfg(feature = "otel")]
+        self.otel.observe_tenant_task(key, success, latency);
+    }
+
+    /// Record a result cache lookup for a route with x-neutrino-cache-ttl
+    pub fn observe_cache_lookup(&self, handler: &str, hit: bool) {
+        let mut lookups = self.cache_lookups.lock().unwrap();
+        let lookups = lookups.entry(handler.to_string()).or_default();
+        if hit {
+            lookups.hits += 1;
+        } else {
+            lookups.misses += 1;
+        }
+
+        #[cfg(feature = "otel")]
+        self.otel.observe_cache_lookup(handler, hit);
+    }
+
+    /// Get the result cache lookups for a handler
+    pub fn cache_lookups(&self, handler: &str) -> Option<CacheLookups> {
+        self.cache_lookups.lock().unwrap().get(handler).copied()
+    }
+
+    /// Per-handler statistics, sorted by call volume (highest first)
+    pub fn handler_stats(&self) -> Vec<HandlerStats> {
+        let handlers = self.handlers.lock().unwrap();
+
+        let mut stats: Vec<HandlerStats> = handlers
+            .iter()
+            .map(|(handler, calls)| {
+                let mut recent: Vec<f64> = calls.recent.iter().copied().collect();
+                recent.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+                let p95 = if recent.is_empty() {
+                    0.0
+                } else {
+                    let rank = ((recent.len() as f64) * 0.95).ceil() as usize;
+                    recent[rank.clamp(1, recent.len()) - 1]
+                };
+
+                HandlerStats {
+                    handler: handler.clone(),
+                    calls: calls.calls,
+                    successes: calls.successes,
+                    failures: calls.calls - calls.successes,
+                    success_rate: if calls.calls > 0 {
+                        calls.successes as f64 / calls.calls as f64
+                    } else {
+                        0.0
+                    },
+                    avg_latency_ms: if calls.calls > 0 {
+                        calls.duration.sum() / calls.calls as f64 * 1000.0
+                    } else {
+                        0.0
+                    },
+                    p95_latency_ms: p95 * 1000.0,
+                }
+            })
+            .collect();
+
+        stats.sort_by(|a, b| {
+            b.calls
+                .cmp(&a.calls)
+                .then_with(|| a.handler.cmp(&b.handler))
+        });
+        stats
+    }
+
+    /// Render all metrics in Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let handlers = self.handlers.lock().unwrap();
+
+        let _ = writeln!(
+            out,
+            "# HELP neutrino_tasks_total Tasks completed, by handler and outcome"
+        );
+        let _ = writeln!(out, "# TYPE neutrino_tasks_total counter");
+        for (handler, calls) in handlers.iter() {
+            let _ = writeln!(
+                out,
+                "neutrino_tasks_total{{handler=\"{}\",outcome=\"success\"}} {}",
+                handler, calls.successes
+            );
+            let _ = writeln!(
+                out,
+                "neutrino_tasks_total{{handler=\"{}\",outcome=\"failure\"}} {}",
+                handler,
+                calls.calls - calls.successes
+            );
+        }
+
+        let _ = writeln!(
+            out,
+            "# HELP neutrino_task_duration_seconds End-to-end task latency"
+        );
+        let _ = writeln!(out, "# TYPE neutrino_task_duration_seconds histogram");
+        for (handler, calls) in handlers.iter() {
+            calls.duration.render(
+                &mut out,
+                "neutrino_task_duration_seconds",
+                &format!("handler=\"{}\"", handler),
+            );
+        }
+        drop(handlers);
+
+        let _ = writeln!(
+            out,
+            "# HELP neutrino_task_queue_wait_seconds Time tasks spend waiting before dispatch to a worker"
+        );
+        let _ = writeln!(out, "# TYPE neutrino_task_queue_wait_seconds histogram");
+        for (handler, histogram) in self.queue_wait.lock().unwrap().iter() {
+            histogram.render(
+                &mut out,
+                "neutrino_task_queue_wait_seconds",
+                &format!("handler=\"{}\"", handler),
+            );
+        }
+
+        let tenants = self.tenants.lock().unwrap();
+        if !tenants.is_empty() {
+            let _ = writeln!(
+                out,
+                "# HELP neutrino_tenant_tasks_total Tasks completed, by tenant and outcome"
+            );
+            let _ = writeln!(out, "# TYPE neutrino_tenant_tasks_total counter");
+            for (tenant, calls) in tenants.iter() {
+                let _ = writeln!(
+                    out,
+                    "neutrino_tenant_tasks_total{{tenant=\"{}\",outcome=\"success\"}} {}",
+                    tenant, calls.successes
+                );
+                let _ = writeln!(
+                    out,
+                    "neutrino_tenant_tasks_total{{tenant=\"{}\",outcome=\"failure\"}} {}",
+                    tenant,
+                    calls.calls - calls.successes
+                );
+            }
+            let _ = writeln!(
+                out,
+                "# HELP neutrino_tenant_task_seconds_total Total end-to-end task time, by tenant"
+            );
+            let _ = writeln!(out, "# TYPE neutrino_tenant_task_seconds_total counter");
+            for (tenant, calls) in tenants.iter() {
+                let _ = writeln!(
+                    out,
+                    "neutrino_tenant_task_seconds_total{{tenant=\"{}\"}} {}",
+                    tenant, calls.seconds
+                );
+            }
+        }
+        drop(tenants);
+
+        let cache_lookups = self.cache_lookups.lock().unwrap();
+        if !cache_lookups.is_empty() {
+            let _ = writeln!(
+                out,
+                "# HELP neutrino_result_cache_lookups_total Result cache lookups, by handler and outcome"
+            );
+            let _ = writeln!(out, "# TYPE neutrino_result_cache_lookups_total counter");
+            for (handler, lookups) in cache_lookups.iter() {
+                for (outcome, count) in [("hit", lookups.hits), ("miss", lookups.misses)] {
+                    let _ = writeln!(
+                        out,
+                        "neutrino_result_cache_lookups_total{{handler=\"{}\",outcome=\"{}\"}} {}",
+                        handler, outcome, count
+                    );
+                }
+            }
+            let _ = writeln!(
+                out,
+                "# HELP neutrino_result_cache_hit_ratio Fraction of result cache lookups that hit"
+            );
+            let _ = writeln!(out, "# TYPE neutrino_result_cache_hit_ratio gauge");
+            for (handler, lookups) in cache_lookups.iter() {
+                let ratio = lookups.hits as f64 / (lookups.hits + lookups.misses) as f64;
+                let _ = writeln!(
+                    out,
+                    "neutrino_result_cache_hit_ratio{{handler=\"{}\"}} {}",
+                    handler, ratio
+                );
+            }
+        }
+        drop(cache_lookups);
+
+        if let Some(open) = crate::fds::open_fds() {
+            let _ = writeln!(
+                out,
+                "# HELP neutrino_open_fds File descriptors open in the orchestrator"
+            );
+            let _ = writeln!(out, "# TYPE neutrino_open_fds gauge");
+            let _ = writeln!(out, "neutrino_open_fds {}", open);
+        }
+        if let Some(limit) = crate::fds::fd_limit() {
+            let _ = writeln!(
+                out,
+                "# HELP neutrino_fd_limit Open file descriptor limit (RLIMIT_NOFILE)"
+            );
+            let _ = writeln!(out, "# TYPE neutrino_fd_limit gauge");
+            let _ = writeln!(out, "neutrino_fd_limit {}", limit);
+        }
+
+        out
+    }
+}
+
+#[cfg(feature = "otel")]
+mod otel {
+    use opentelemetry::metrics::{Counter, Histogram};
+    use opentelemetry::KeyValue;
+    use std::time::Duration;
+
+    /// OTel counterparts of the Prometheus series, named per OTel conventions
+    #[derive(Debug)]
+    pub struct Instruments {
+        tasks: Counter<u64>,
+        task_duration: Histogram<f64>,
+        queue_wait: Histogram<f64>,
+        tenant_tasks: Counter<u64>,
+        tenant_seconds: Counter<f64>,
+        cache_lookups: Counter<u64>,
+    }
+
+    impl Default for Instruments {
+        fn default() -> Self {
+            let meter = opentelemetry::global::meter("neutrino");
+            Self {
+                tasks: meter
+                    .u64_counter("neutrino.tasks")
+                    .with_description("Tasks completed, by handler and outcome")
+                    .build(),
+                task_duration: meter
+                    .f64_histogram("neutrino.task.duration")
+                    .with_description("End-to-end task latency")
+                    .with_unit("s")
+                    .build(),
+                queue_wait: meter
+                    .f64_histogram("neutrino.task.queue_wait")
+                    .with_description("Time tasks spend waiting before dispatch to a worker")
+                    .with_unit("s")
+                    .build(),
+                tenant_tasks: meter
+                    .u64_counter("neutrino.tenant.tasks")
+                    .with_description("Tasks completed, by tenant and outcome")
+                    .build(),
+                tenant_seconds: meter
+                    .f64_counter("neutrino.tenant.task_time")
+                    .with_description("Total end-to-end task time, by tenant")
+                    .with_unit("s")
+                    .build(),
+                cache_lookups: meter
+                    .u64_counter("neutrino.result_cache.lookups")
+                    .with_description("Result cache lookups, by handler and outcome")
+                    .build(),
+            }
+        }
+    }
+
+    impl Instruments {
+        pub fn observe_queue_wait(&self, handler: &str, wait: Duration) {
+            self.queue_wait.record(
+                wait.as_secs_f64(),
+                &[KeyValue::new("handler", handler.to_string())],
+            );
+        }
+
+        pub fn observe_task(&self, handler: &str, success: bool, latency: Duration) {
+            let handler = KeyValue::new("handler", handler.to_string());
+            let outcome = KeyValue::new("outcome", if success { "success" } else { "failure" });
+            self.tasks.add(1, &[handler.clone(), outcome]);
+            self.task_duration.record(latency.as_secs_f64(), &[handler]);
+        }
+
+        pub fn observe_tenant_task(&self, tenant: &str, success: bool, latency: Duration) {
+            let tenant = KeyValue::new("tenant", tenant.to_string());
+            let outcome = KeyValue::new("outcome", if success { "success" } else { "failure" });
+            self.tenant_tasks.add(1, &[tenant.clone(), outcome]);
+            self.tenant_seconds.add(latency.as_secs_f64(), &[tenant]);
+        }
+
+        pub fn observe_cache_lookup(&self, handler: &str, hit: bool) {
+            let handler = KeyValue::new("handler", handler.to_string());
+            let outcome = KeyValue::new("outcome", if hit { "hit" } else { "miss" });
+            self.cache_lookups.add(1, &[handler, outcome]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_handler_stats_sorted_by_volume() {
+        let metrics = Metrics::new();
+        for ms in 1..=20 {
+            metrics.observe_task("busy", ms != 20, Duration::from_millis(ms));
+        }
+        metrics.observe_task("quiet", true, Duration::from_millis(5));
+
+        let stats = metrics.handler_stats();
+        assert_eq!(stats[0].handler, "busy");
+        assert_eq!(stats[0].calls, 20);
+        assert_eq!(stats[0].failures, 1);
+        assert!((stats[0].success_rate - 0.95).abs() < 1e-9);
+        assert!((stats[0].avg_latency_ms - 10.5).abs() < 1e-6);
+        assert!((stats[0].p95_latency_ms - 19.0).abs() < 1e-6);
+        assert_eq!(stats[1].handler, "quiet");
+        assert_eq!(stats[1].calls, 1);
+    }
+
+    #[test]
+    fn test_histogram_buckets_are_cumulative() {
+        let metrics = Metrics::new();
+        metrics.observe_queue_wait("work", Duration::from_millis(3));
+        metrics.observe_queue_wait("work", Duration::from_millis(200));
+
+        let histogram = metrics.queue_wait("work").unwrap();
+        assert_eq!(histogram.count(), 2);
+        assert!((histogram.sum() - 0.203).abs() < 1e-9);
+
+        let rendered = metrics.render();
+        assert!(rendered
+            .contains("neutrino_task_queue_wait_seconds_bucket{handler=\"work\",le=\"0.001\"} 0"));
+        assert!(rendered
+            .contains("neutrino_task_queue_wait_seconds_bucket{handler=\"work\",le=\"0.005\"} 1"));
+        assert!(rendered
+            .contains("neutrino_task_queue_wait_seconds_bucket{handler=\"work\",le=\"0.25\"} 2"));
+        assert!(rendered
+            .contains("neutrino_task_queue_wait_seconds_bucket{handler=\"work\",le=\"+Inf\"} 2"));
+        assert!(rendered.contains("neutrino_task_queue_wait_seconds_count{handler=\"work\"} 2"));
+    }
+
+    #[test]
+    fn test_tenants_beyond_limit_counted_as_other() {
+        let metrics = Metrics::new();
+        for tenant in 0..MAX_TENANTS {
+            metrics.observe_tenant_task(&format!("t{}", tenant), true, Duration::from_millis(1));
+        }
+        metrics.observe_tenant_task("late", false, Duration::from_millis(1));
+        metrics.observe_tenant_task("t0", true, Duration::from_millis(1));
+
+        let rendered = metrics.render();
+        assert!(
+            rendered.contains("neutrino_tenant_tasks_total{tenant=\"t0\",outcome=\"success\"} 2")
+        );
+        assert!(rendered
+            .contains("neutrino_tenant_tasks_total{tenant=\"other\",outcome=\"failure\"} 1"));
+        assert!(!rendered.contains("tenant=\"late\""));
+    }
+}
@@ -0,0 +1,90 @@
+//! Reshaping of a request's args before dispatch (`x-neutrino-args-template`),
+//! so the API contract can differ from the handler's signature.
+//!
+//! The template is JSON giving the shape the handler receives. Strings of the
+//! form `"$"` (the whole body) or `"$.field.nested"` (array elements by index,
+//! e.g. `"$.items.0"`) are replaced by that part of the request's args;
+//! a leading `"$$"` escapes a literal `$`. Everything else is copied as is.
+//! Object fields whose reference is missing from the request are left out,
+//! so handler defaults apply.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct ArgsTemplate(Value);
+
+impl ArgsTemplate {
+    /// The handler's args for a request whose args are `args`
+    pub fn apply(&self, args: &Value) -> Value {
+        render(&self.0, args).unwrap_or(Value::Null)
+    }
+}
+
+/// `template` filled in from `args` (None for a missing reference)
+fn render(template: &Value, args: &Value) -> Option<Value> {
+    match template {
+        Value::String(s) => match s.strip_prefix('$') {
+            Some(escaped) if escaped.starts_with('$') => Some(Value::String(escaped.to_string())),
+            Some("") => Some(args.clone()),
+            Some(path) => match path.strip_prefix('.') {
+                Some(path) => lookup(args, path).cloned(),
+                None => Some(template.clone()),
+            },
+            None => Some(template.clone()),
+        },
+        Value::Object(fields) => Some(Value::Object(
+            fields
+                .iter()
+                .filter_map(|(name, field)| Some((name.clone(), render(field, args)?)))
+                .collect::<Map<_, _>>(),
+        )),
+        Value::Array(items) => Some(Value::Array(
+            items
+                .iter()
+                .map(|item| render(item, args).unwrap_or(Value::Null))
+                .collect(),
+        )),
+        _ => Some(template.clone()),
+    }
+}
+
+/// The value at a dotted path of object fields and array indices
+pub(crate) fn lookup<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.')
+        .try_fold(value, |value, segment| match value {
+            Value::Object(fields) => fields.get(segment),
+            Value::Array(items) => items.get(segment.parse::<usize>().ok()?),
+            _ => None,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_template_renames_nests_and_escapes() {
+        let template: ArgsTemplate = serde_json::from_value(json!({
+            "payload": {"text": "$.prompt", "first_tag": "$.tags.0", "user": "$.user.name"},
+            "raw": "$",
+            "currency": "$$USD",
+            "version": 2,
+            "missing": "$.not_sent"
+        }))
+        .unwrap();
+        let args = json!({"prompt": "hi", "tags": ["a", "b"], "user": {"name": "ada"}});
+
+        assert_eq!(
+            template.apply(&args),
+            json!({
+                "payload": {"text": "hi", "first_tag": "a", "user": "ada"},
+                "raw": args,
+                "currency": "$USD",
+                "version": 2
+            })
+        );
+    }
+}
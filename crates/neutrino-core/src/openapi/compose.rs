@@ -0,0 +1,238 @@
+//! Composition of routes from several OpenAPI specs (`openapi_spec` followed by
+//! `extra_specs`). When two specs declare the same method and path, the
+//! configured `SpecConflictPolicy` decides which is served; the conflict and
+//! its resolution are logged either way.
+
+use std::collections::HashMap;
+use tracing::{info, warn};
+
+use super::RouteInfo;
+use crate::config::SpecConflictPolicy;
+
+/// Routes extracted from one spec
+pub struct SpecRoutes {
+    /// Where the spec came from (e.g. its file), for log and error messages
+    pub source: String,
+    /// Prefix for routes that conflict with an earlier spec (`prefix-required`)
+    pub prefix: Option<String>,
+    pub routes: Vec<RouteInfo>,
+}
+
+/// Merge the routes of `specs`, in order, resolving duplicates per `policy`.
+/// Fails on a conflict under `error`, or under `prefix-required` when the
+/// later spec has no prefix.
+pub fn compose(
+    specs: Vec<SpecRoutes>,
+    policy: SpecConflictPolicy,
+) -> Result<Vec<RouteInfo>, String> {
+    let mut routes = Vec::new();
+    // Method and path shape -> source of the spec that owns the route
+    let mut owners: HashMap<(String, String), String> = HashMap::new();
+
+    for spec in specs {
+        for mut route in spec.routes {
+            let Some(owner) = owners.get(&route_key(&route)) else {
+                owners.insert(route_key(&route), spec.source.clone());
+                routes.push(route);
+                continue;
+            };
+            let conflict = format!(
+                "{} {} is declared by both {} and {}",
+                route.method, route.path, owner, spec.source
+            );
+
+            match policy {
+                SpecConflictPolicy::Error => {
+                    return Err(format!("{} (spec_conflicts: error)", conflict));
+                }
+                SpecConflictPolicy::FirstWins => {
+                    warn!(
+                        "{}; keeping the route from {} (spec_conflicts: first-wins)",
+                        conflict, owner
+                    );
+                }
+                SpecConflictPolicy::PrefixRequired => {
+                    let Some(prefix) = &spec.prefix else {
+                        return Err(format!(
+                            "{}; give {} a prefix to serve it (spec_conflicts: prefix-required)",
+                            conflict, spec.source
+                        ));
+                    };
+                    route.path = format!("{}{}", prefix.trim_end_matches('/'), route.path);
+                    if let Some(owner) = owners.get(&route_key(&route)) {
+                        return Err(format!(
+                            "{}; prefixed route {} {} is also declared by {}",
+                            conflict, route.method, route.path, owner
+                        ));
+                    }
+                    warn!(
+                        "{}; serving the route from {} at {} (spec_conflicts: prefix-required)",
+                        conflict, spec.source, route.path
+                    );
+                    owners.insert(route_key(&route), spec.source.clone());
+                    routes.push(route);
+                }
+            }
+        }
+    }
+
+    Ok(routes)
+}
+
+/// Route count past which a spec is reported as unusually large, since it is
+/// more often a generation bug than a real API
+pub const LARGE_SPEC_ROUTES: usize = 1000;
+
+/// Report how many routes will be served, warning when unusually many and
+/// failing past `max_routes`
+pub fn check_route_count(routes: &[RouteInfo], max_routes: Option<usize>) -> Result<(), String> {
+    let count = routes.len();
+    if let Some(max) = max_routes.filter(|&max| count > max) {
+        return Err(format!(
+            "Specs declare {} routes, more than http.max_routes ({}); check the spec generator",
+            count, max
+        ));
+    }
+    if count > LARGE_SPEC_ROUTES {
+        warn!(
+            route_count = count,
+            "Specs declare {} routes, unusually many; check the spec generator or set http.max_routes",
+            count
+        );
+    } else {
+        info!(
+            route_count = count,
+            "Serving {} routes from OpenAPI specs", count
+        );
+    }
+    Ok(())
+}
+
+/// Method and path with parameter names erased, since `/items/:id` and
+/// `/items/:item_id` match the same requests
+fn route_key(route: &RouteInfo) -> (String, String) {
+    let shape = route
+        .path
+        .split('/')
+        .map(|segment| match segment.chars().next() {
+            Some(marker @ (':' | '*')) => marker.to_string(),
+            _ => segment.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("/");
+    (route.method.clone(), shape)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{spec_with_routes, CapturedEvents};
+
+    fn specs() -> Vec<SpecRoutes> {
+        let main = spec_with_routes(&[
+            ("POST", "/predict", "post_predict"),
+            ("GET", "/items/{id}", "get_item"),
+        ]);
+        let billing = spec_with_routes(&[
+            ("POST", "/predict", "post_charge_predict"),
+            ("GET", "/items/{item_id}", "get_invoice"),
+            ("POST", "/charge", "post_charge"),
+        ]);
+        vec![
+            SpecRoutes {
+                source: "main.json".to_string(),
+                prefix: None,
+                routes: main.extract_routes(),
+            },
+            SpecRoutes {
+                source: "billing.json".to_string(),
+                prefix: Some("/billing/".to_string()),
+                routes: billing.extract_routes(),
+            },
+        ]
+    }
+
+    fn served(routes: &[RouteInfo]) -> Vec<(String, String, String)> {
+        let mut served: Vec<_> = routes
+            .iter()
+            .map(|r| (r.method.clone(), r.path.clone(), r.handler_name.clone()))
+            .collect();
+        served.sort();
+        served
+    }
+
+    fn route(method: &str, path: &str, handler: &str) -> (String, String, String) {
+        (method.to_string(), path.to_string(), handler.to_string())
+    }
+
+    #[test]
+    fn test_conflict_fails_under_error_policy() {
+        let err = compose(specs(), SpecConflictPolicy::Error).unwrap_err();
+        assert!(
+            err.contains("main.json") && err.contains("billing.json"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_first_spec_kept_under_first_wins() {
+        let routes = compose(specs(), SpecConflictPolicy::FirstWins).unwrap();
+        assert_eq!(
+            served(&routes),
+            vec![
+                route("GET", "/items/:id", "item"),
+                route("POST", "/charge", "charge"),
+                route("POST", "/predict", "predict"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_conflicting_routes_prefixed_under_prefix_required() {
+        let routes = compose(specs(), SpecConflictPolicy::PrefixRequired).unwrap();
+        assert_eq!(
+            served(&routes),
+            vec![
+                route("GET", "/billing/items/:item_id", "invoice"),
+                route("GET", "/items/:id", "item"),
+                route("POST", "/billing/predict", "charge_predict"),
+                route("POST", "/charge", "charge"),
+                route("POST", "/predict", "predict"),
+            ]
+        );
+
+        // Without a prefix the conflict can't be disambiguated
+        let mut unprefixed = specs();
+        unprefixed[1].prefix = None;
+        let err = compose(unprefixed, SpecConflictPolicy::PrefixRequired).unwrap_err();
+        assert!(err.contains("give billing.json a prefix"), "{}", err);
+    }
+
+    #[test]
+    fn test_route_count_limit() {
+        let paths: Vec<_> = (0..LARGE_SPEC_ROUTES + 1)
+            .map(|i| (format!("/generated/{}", i), format!("post_generated_{}", i)))
+            .collect();
+        let declared: Vec<_> = paths
+            .iter()
+            .map(|(path, op)| ("POST", path.as_str(), op.as_str()))
+            .collect();
+        let routes = spec_with_routes(&declared).extract_routes();
+
+        let err = check_route_count(&routes, Some(500)).unwrap_err();
+        assert!(
+            err.contains("1001 routes") && err.contains("(500)"),
+            "{}",
+            err
+        );
+
+        // Within the limit (or without one) it is served, with a warning
+        let events = CapturedEvents::default();
+        let _guard = events.install();
+        assert!(check_route_count(&routes, Some(2000)).is_ok());
+        assert!(check_route_count(&routes, None).is_ok());
+        assert_eq!(events.with_field("route_count").len(), 2);
+        assert!(check_route_count(&routes[..10], Some(10)).is_ok());
+    }
+}
@@ -2,9 +2,20 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
+use std::sync::Arc;
 
 use crate::protocol::ResourceRequirements;
 
+pub mod args_template;
+pub mod compose;
+pub mod policy;
+pub mod remote;
+pub mod validation;
+
+pub use args_template::ArgsTemplate;
+pub use policy::ResourcePolicy;
+pub use validation::{RequestSchema, ResponseSchema};
+
 /// OpenAPI 3.0 specification
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct OpenApiSpec {
@@ -58,8 +69,90 @@ pub struct Operation {
     #[serde(default)]
     pub responses: HashMap<String, Response>,
     /// Neutrino-specific resource requirements (OpenAPI extension field)
-    #[serde(rename = "x-neutrino-resources", skip_serializing_if = "Option::is_none")]
+    #[serde(
+        rename = "x-neutrino-resources",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub neutrino_resources: Option<ResourceRequirements>,
+    /// Per-route request body limit overriding `http.max_body_bytes`
+    #[serde(
+        rename = "x-neutrino-max-body-bytes",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub max_body_bytes: Option<usize>,
+    /// Content type for scalar results, returned raw instead of in the JSON envelope
+    #[serde(
+        rename = "x-neutrino-default-result-content-type",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub default_result_content_type: Option<String>,
+    /// Pool to run on, without the GPU requirement, when no worker can take the task
+    #[serde(
+        rename = "x-neutrino-overflow-pool",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub overflow_pool: Option<String>,
+    /// Pool the route's tasks run on; other pools' workers are never used
+    #[serde(rename = "x-neutrino-pool", skip_serializing_if = "Option::is_none")]
+    pub pool: Option<String>,
+    /// Most tasks of the handler running at once; more wait their turn
+    #[serde(
+        rename = "x-neutrino-max-concurrency",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub max_concurrency: Option<usize>,
+    /// On GET routes, milliseconds after which a duplicate of a task still
+    /// running is dispatched to another idle worker; the first result wins
+    #[serde(
+        rename = "x-neutrino-hedge-after-ms",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub hedge_after_ms: Option<u64>,
+    /// Seconds a successful result is kept and returned for identical requests
+    /// (same handler, args, and tenant) without dispatching a task
+    #[serde(
+        rename = "x-neutrino-cache-ttl",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub cache_ttl: Option<u64>,
+    /// Dispatch priority while waiting for a worker; higher goes first (default 0)
+    #[serde(
+        rename = "x-neutrino-priority",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub priority: Option<i32>,
+    /// Shape the handler's args are rebuilt into from the request's args
+    #[serde(
+        rename = "x-neutrino-args-template",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub args_template: Option<ArgsTemplate>,
+    /// Dedicated pool per tenant ID; that tenant's tasks run only there
+    #[serde(
+        rename = "x-neutrino-tenant-pools",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub tenant_pools: Option<HashMap<String, String>>,
+    /// Identical concurrent requests share one task (idempotent handlers only)
+    #[serde(
+        rename = "x-neutrino-coalesce",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub coalesce: Option<bool>,
+    /// Reject request bodies with fields the schema doesn't declare
+    /// (overrides http.reject_unknown_fields)
+    #[serde(
+        rename = "x-neutrino-reject-unknown-fields",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub reject_unknown_fields: Option<bool>,
+    /// Path/query parameter or top-level body field naming the request's
+    /// session; requests of one session prefer the same worker
+    #[serde(
+        rename = "x-neutrino-affinity-key",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub affinity_key: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -99,6 +192,34 @@ pub struct RouteInfo {
     pub operation_id: String,
     pub handler_name: String,
     pub resources: ResourceRequirements,
+    /// Combined path/query/body schema used for request validation
+    pub request_schema: RequestSchema,
+    /// Success response schema, used to filter undeclared result fields
+    pub response_schema: Option<ResponseSchema>,
+    /// Request body limit from x-neutrino-max-body-bytes (None = global limit)
+    pub max_body_bytes: Option<usize>,
+    /// Content type for raw scalar results, from x-neutrino-default-result-content-type
+    pub default_result_content_type: Option<String>,
+    /// Overflow pool from x-neutrino-overflow-pool
+    pub overflow_pool: Option<String>,
+    /// Pinned pool from x-neutrino-pool
+    pub pool: Option<String>,
+    /// Concurrency cap from x-neutrino-max-concurrency
+    pub max_concurrency: Option<usize>,
+    /// Hedging delay from x-neutrino-hedge-after-ms
+    pub hedge_after_ms: Option<u64>,
+    /// Result cache lifetime in seconds from x-neutrino-cache-ttl
+    pub cache_ttl: Option<u64>,
+    /// Dispatch priority from x-neutrino-priority
+    pub priority: i32,
+    /// Args reshaping from x-neutrino-args-template
+    pub args_template: Option<ArgsTemplate>,
+    /// Tenant ID to pool, from x-neutrino-tenant-pools
+    pub tenant_pools: HashMap<String, String>,
+    /// Request coalescing from x-neutrino-coalesce
+    pub coalesce: bool,
+    /// Session affinity arg from x-neutrino-affinity-key
+    pub affinity_key: Option<String>,
 }
 
 impl OpenApiSpec {
@@ -112,6 +233,7 @@ impl OpenApiSpec {
     /// Extract all routes from the OpenAPI spec
     pub fn extract_routes(&self) -> Vec<RouteInfo> {
         let mut routes = Vec::new();
+        let components = Arc::new(self.components.schemas.clone());
 
         for (path, path_item) in &self.paths {
             // Convert OpenAPI path format {param} to Axum format :param
@@ -124,6 +246,20 @@ impl OpenApiSpec {
                     operation_id: op.operation_id.clone(),
                     handler_name: extract_handler_name(&op.operation_id),
                     resources: op.neutrino_resources.clone().unwrap_or_default(),
+                    request_schema: RequestSchema::from_operation(op, Arc::clone(&components)),
+                    response_schema: ResponseSchema::from_operation(op, Arc::clone(&components)),
+                    max_body_bytes: op.max_body_bytes,
+                    default_result_content_type: op.default_result_content_type.clone(),
+                    overflow_pool: op.overflow_pool.clone(),
+                    pool: op.pool.clone(),
+                    max_concurrency: op.max_concurrency,
+                    hedge_after_ms: op.hedge_after_ms,
+                    cache_ttl: op.cache_ttl,
+                    priority: op.priority.unwrap_or_default(),
+                    args_template: op.args_template.clone(),
+                    tenant_pools: op.tenant_pools.clone().unwrap_or_default(),
+                    coalesce: op.coalesce.unwrap_or_default(),
+                    affinity_key: op.affinity_key.clone(),
                 });
             }
 
@@ -134,6 +270,20 @@ impl OpenApiSpec {
                     operation_id: op.operation_id.clone(),
                     handler_name: extract_handler_name(&op.operation_id),
                     resources: op.neutrino_resources.clone().unwrap_or_default(),
+                    request_schema: RequestSchema::from_operation(op, Arc::clone(&components)),
+                    response_schema: ResponseSchema::from_operation(op, Arc::clone(&components)),
+                    max_body_bytes: op.max_body_bytes,
+                    default_result_content_type: op.default_result_content_type.clone(),
+                    overflow_pool: op.overflow_pool.clone(),
+                    pool: op.pool.clone(),
+                    max_concurrency: op.max_concurrency,
+                    hedge_after_ms: op.hedge_after_ms,
+                    cache_ttl: op.cache_ttl,
+                    priority: op.priority.unwrap_or_default(),
+                    args_template: op.args_template.clone(),
+                    tenant_pools: op.tenant_pools.clone().unwrap_or_default(),
+                    coalesce: op.coalesce.unwrap_or_default(),
+                    affinity_key: op.affinity_key.clone(),
                 });
             }
 
@@ -144,6 +294,20 @@ impl OpenApiSpec {
                     operation_id: op.operation_id.clone(),
                     handler_name: extract_handler_name(&op.operation_id),
                     resources: op.neutrino_resources.clone().unwrap_or_default(),
+                    request_schema: RequestSchema::from_operation(op, Arc::clone(&components)),
+                    response_schema: ResponseSchema::from_operation(op, Arc::clone(&components)),
+                    max_body_bytes: op.max_body_bytes,
+                    default_result_content_type: op.default_result_content_type.clone(),
+                    overflow_pool: op.overflow_pool.clone(),
+                    pool: op.pool.clone(),
+                    max_concurrency: op.max_concurrency,
+                    hedge_after_ms: op.hedge_after_ms,
+                    cache_ttl: op.cache_ttl,
+                    priority: op.priority.unwrap_or_default(),
+                    args_template: op.args_template.clone(),
+                    tenant_pools: op.tenant_pools.clone().unwrap_or_default(),
+                    coalesce: op.coalesce.unwrap_or_default(),
+                    affinity_key: op.affinity_key.clone(),
                 });
             }
 
@@ -154,6 +318,20 @@ impl OpenApiSpec {
                     operation_id: op.operation_id.clone(),
                     handler_name: extract_handler_name(&op.operation_id),
                     resources: op.neutrino_resources.clone().unwrap_or_default(),
+                    request_schema: RequestSchema::from_operation(op, Arc::clone(&components)),
+                    response_schema: ResponseSchema::from_operation(op, Arc::clone(&components)),
+                    max_body_bytes: op.max_body_bytes,
+                    default_result_content_type: op.default_result_content_type.clone(),
+                    overflow_pool: op.overflow_pool.clone(),
+                    pool: op.pool.clone(),
+                    max_concurrency: op.max_concurrency,
+                    hedge_after_ms: op.hedge_after_ms,
+                    cache_ttl: op.cache_ttl,
+                    priority: op.priority.unwrap_or_default(),
+                    args_template: op.args_template.clone(),
+                    tenant_pools: op.tenant_pools.clone().unwrap_or_default(),
+                    coalesce: op.coalesce.unwrap_or_default(),
+                    affinity_key: op.affinity_key.clone(),
                 });
             }
 
@@ -164,6 +342,20 @@ impl OpenApiSpec {
                     operation_id: op.operation_id.clone(),
                     handler_name: extract_handler_name(&op.operation_id),
                     resources: op.neutrino_resources.clone().unwrap_or_default(),
+                    request_schema: RequestSchema::from_operation(op, Arc::clone(&components)),
+                    response_schema: ResponseSchema::from_operation(op, Arc::clone(&components)),
+                    max_body_bytes: op.max_body_bytes,
+                    default_result_content_type: op.default_result_content_type.clone(),
+                    overflow_pool: op.overflow_pool.clone(),
+                    pool: op.pool.clone(),
+                    max_concurrency: op.max_concurrency,
+                    hedge_after_ms: op.hedge_after_ms,
+                    cache_ttl: op.cache_ttl,
+                    priority: op.priority.unwrap_or_default(),
+                    args_template: op.args_template.clone(),
+                    tenant_pools: op.tenant_pools.clone().unwrap_or_default(),
+                    coalesce: op.coalesce.unwrap_or_default(),
+                    affinity_key: op.affinity_key.clone(),
                 });
             }
         }
@@ -174,7 +366,7 @@ impl OpenApiSpec {
 
 /// Convert OpenAPI path format to Axum path format
 /// Example: /users/{user_id} -> /users/:user_id
-fn convert_openapi_path_to_axum(path: &str) -> String {
+pub(crate) fn convert_openapi_path_to_axum(path: &str) -> String {
     let mut result = String::new();
     let mut in_param = false;
     let mut param_name = String::new();
@@ -271,6 +463,16 @@ impl ResourceRouter {
         Ok(Self::from_spec(&spec))
     }
 
+    /// Create a ResourceRouter from an OpenAPI spec file with a resource policy merged over it
+    pub fn from_files<P: AsRef<Path>, Q: AsRef<Path>>(
+        spec_path: P,
+        policy_path: Q,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut spec = OpenApiSpec::from_file(spec_path)?;
+        ResourcePolicy::from_file(policy_path)?.apply(&mut spec);
+        Ok(Self::from_spec(&spec))
+    }
+
     /// Get resource requirements for a route, returning default if not found
     pub fn get_requirements(&self, method: &str, path: &str) -> ResourceRequirements {
         let key = (method.to_uppercase(), path.to_string());
@@ -0,0 +1,180 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use tracing::warn;
+
+use super::OpenApiSpec;
+use crate::protocol::{quantity, ResourceRequirements};
+
+/// Partial resource requirements; unset fields keep the spec's (or default) value.
+/// CPUs and memory accept quantities like "500m" and "2Gi".
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq)]
+pub struct ResourceOverride {
+    #[serde(default, deserialize_with = "quantity::deserialize_optional_cpus")]
+    pub num_cpus: Option<f64>,
+    #[serde(default)]
+    pub num_gpus: Option<f64>,
+    #[serde(default, deserialize_with = "quantity::deserialize_optional_memory_gb")]
+    pub memory_gb: Option<f64>,
+    #[serde(default, deserialize_with = "quantity::deserialize_optional_memory_gb")]
+    pub gpu_memory_gb: Option<f64>,
+}
+
+impl ResourceOverride {
+    fn apply(&self, resources: &mut ResourceRequirements) {
+        if let Some(num_cpus) = self.num_cpus {
+            resources.num_cpus = num_cpus;
+        }
+        if let Some(num_gpus) = self.num_gpus {
+            resources.num_gpus = num_gpus;
+        }
+        if let Some(memory_gb) = self.memory_gb {
+            resources.memory_gb = memory_gb;
+        }
+        if let Some(gpu_memory_gb) = self.gpu_memory_gb {
+            resources.gpu_memory_gb = gpu_memory_gb;
+        }
+    }
+}
+
+/// Sidecar resource policy (e.g. `resources.yaml`), for specs that can't carry
+/// `x-neutrino-resources` themselves (such as ones generated by FastAPI).
+///
+/// ```yaml
+/// operations:
+///   post_train_model: {num_gpus: 1, memory_gb: 16Gi}
+/// routes:
+///   "GET /items/{item_id}": {num_cpus: 0.5}
+/// ```
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ResourcePolicy {
+    /// Overrides keyed by `operationId`
+    #[serde(default)]
+    pub operations: HashMap<String, ResourceOverride>,
+    /// Overrides keyed by "METHOD /path" (OpenAPI path format); these take
+    /// precedence over `operations`
+    #[serde(default)]
+    pub routes: HashMap<String, ResourceOverride>,
+}
+
+impl ResourcePolicy {
+    /// Load a resource policy from a YAML (or JSON) file
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn std::error::Error>> {
+        let content = fs::read_to_string(path)?;
+        let policy: ResourcePolicy = serde_yaml::from_str(&content)?;
+        Ok(policy)
+    }
+
+    /// Merge the policy over the spec's `x-neutrino-resources`
+    pub fn apply(&self, spec: &mut OpenApiSpec) {
+        let mut matched_operations = 0;
+        let mut matched_routes = 0;
+
+        for (path, path_item) in spec.paths.iter_mut() {
+            let operations = [
+                ("GET", path_item.get.as_mut()),
+                ("POST", path_item.post.as_mut()),
+                ("PUT", path_item.put.as_mut()),
+                ("PATCH", path_item.patch.as_mut()),
+                ("DELETE", path_item.delete.as_mut()),
+            ];
+
+            for (method, op) in operations {
+                let Some(op) = op else { continue };
+                let by_operation = self.operations.get(&op.operation_id);
+                let by_route = self.routes.get(&format!("{} {}", method, path));
+                if by_operation.is_none() && by_route.is_none() {
+                    continue;
+                }
+
+                let resources = op.neutrino_resources.get_or_insert_with(Default::default);
+                if let Some(overrides) = by_operation {
+                    overrides.apply(resources);
+                    matched_operations += 1;
+                }
+                if let Some(overrides) = by_route {
+                    overrides.apply(resources);
+                    matched_routes += 1;
+                }
+            }
+        }
+
+        if matched_operations < self.operations.len() || matched_routes < self.routes.len() {
+            warn!(
+                "Resource policy has entries that match no route ({} of {} operations, {} of {} routes matched)",
+                matched_operations,
+                self.operations.len(),
+                matched_routes,
+                self.routes.len()
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_policy_overrides_spec_resources() {
+        let mut spec: OpenApiSpec = serde_json::from_value(json!({
+            "openapi": "3.0.0",
+            "info": {"title": "test", "version": "1.0.0"},
+            "paths": {
+                "/train": {"post": {
+                    "operationId": "post_train",
+                    "x-neutrino-resources": {"num_cpus": 2.0, "num_gpus": 0.0, "memory_gb": 4.0}
+                }},
+                "/items/{item_id}": {
+                    "get": {"operationId": "get_item"},
+                    "delete": {"operationId": "delete_item"}
+                },
+                "/health_check": {"get": {"operationId": "get_health_check"}}
+            }
+        }))
+        .unwrap();
+
+        let policy: ResourcePolicy = serde_yaml::from_str(
+            r#"
+operations:
+  post_train: {num_gpus: 1, memory_gb: 16}
+  get_item: {num_cpus: 2}
+routes:
+  "GET /items/{item_id}": {num_cpus: 0.5}
+  "DELETE /items/{item_id}": {memory_gb: 512Mi}
+"#,
+        )
+        .unwrap();
+        policy.apply(&mut spec);
+
+        let routes = spec.extract_routes();
+        let resources = |method: &str, path: &str| {
+            routes
+                .iter()
+                .find(|r| r.method == method && r.path == path)
+                .unwrap()
+                .resources
+                .clone()
+        };
+
+        // Policy fields win; fields it doesn't set keep the spec's value
+        let train = resources("POST", "/train");
+        assert_eq!(
+            (train.num_cpus, train.num_gpus, train.memory_gb),
+            (2.0, 1.0, 16.0)
+        );
+
+        // Route entries take precedence over operation entries
+        assert_eq!(resources("GET", "/items/:item_id").num_cpus, 0.5);
+
+        let delete = resources("DELETE", "/items/:item_id");
+        assert_eq!((delete.num_cpus, delete.memory_gb), (1.0, 0.5));
+
+        assert_eq!(
+            resources("GET", "/health_check"),
+            ResourceRequirements::default()
+        );
+    }
+}
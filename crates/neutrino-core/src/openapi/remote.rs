@@ -0,0 +1,185 @@
+//! Specs served over HTTP (e.g. by a central API registry) instead of read
+//! from a file. A spec URL is fetched at startup with a per-attempt timeout and
+//! retries, and every successful fetch is cached on disk, so a registry outage
+//! during boot falls back to the last copy rather than serving no routes.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::{info, warn};
+
+use super::OpenApiSpec;
+use crate::config::SpecFetchConfig;
+
+/// Delay before the first retry, doubled for each one after
+const RETRY_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Whether a spec location is a URL rather than a file path
+pub fn is_url(location: &str) -> bool {
+    location.starts_with("http://") || location.starts_with("https://")
+}
+
+/// Fetch and parse the spec at `url`, falling back to its cached copy
+pub async fn fetch_spec(url: &str, config: &SpecFetchConfig) -> Result<OpenApiSpec, String> {
+    let cache_path = cache_path(url, config);
+    let error = match fetch_with_retries(url, config).await {
+        Ok((spec, body)) => {
+            if let Err(e) = write_cache(&cache_path, &body) {
+                warn!(
+                    "Failed to cache OpenAPI spec from {} at {}: {}",
+                    url,
+                    cache_path.display(),
+                    e
+                );
+            }
+            return Ok(spec);
+        }
+        Err(e) => e,
+    };
+
+    let cached = std::fs::read_to_string(&cache_path)
+        .map_err(|e| format!("{} (no cached copy: {})", error, e))?;
+    let spec = serde_json::from_str(&cached).map_err(|e| {
+        format!(
+            "{} (cached copy at {} is invalid: {})",
+            error,
+            cache_path.display(),
+            e
+        )
+    })?;
+    warn!(
+        "Failed to fetch OpenAPI spec from {}: {}. Using the copy cached at {}",
+        url,
+        error,
+        cache_path.display()
+    );
+    Ok(spec)
+}
+
+async fn fetch_with_retries(
+    url: &str,
+    config: &SpecFetchConfig,
+) -> Result<(OpenApiSpec, String), String> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(config.timeout_secs))
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    let mut backoff = RETRY_BACKOFF;
+    let mut attempt = 0;
+    loop {
+        match fetch_once(&client, url).await {
+            Ok(fetched) => {
+                info!("Fetched OpenAPI spec from {}", url);
+                return Ok(fetched);
+            }
+            Err(e) if attempt < config.retries => {
+                attempt += 1;
+                warn!(
+                    "Fetching OpenAPI spec from {} failed: {}; retrying in {:?} ({}/{})",
+                    url, e, backoff, attempt, config.retries
+                );
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// One attempt, returning the parsed spec and its raw body for the cache
+async fn fetch_once(client: &reqwest::Client, url: &str) -> Result<(OpenApiSpec, String), String> {
+    let body = client
+        .get(url)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| e.to_string())?
+        .text()
+        .await
+        .map_err(|e| e.to_string())?;
+    let spec = serde_json::from_str(&body).map_err(|e| format!("invalid spec: {}", e))?;
+    Ok((spec, body))
+}
+
+/// Cache file for `url`, named after it with unsafe characters replaced
+fn cache_path(url: &str, config: &SpecFetchConfig) -> PathBuf {
+    let dir = config
+        .cache_dir
+        .as_ref()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| std::env::temp_dir().join("neutrino-specs"));
+    let name: String = url
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    dir.join(format!("{}.json", name))
+}
+
+/// Write through a temporary file so a crash never leaves a partial cache
+fn write_cache(path: &Path, body: &str) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let partial = path.with_extension("json.partial");
+    std::fs::write(&partial, body)?;
+    std::fs::rename(partial, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::get, Router};
+
+    #[tokio::test]
+    async fn test_spec_fetched_from_url_and_cached() {
+        let spec = serde_json::json!({
+            "openapi": "3.0.0",
+            "info": {"title": "registry", "version": "1.0.0"},
+            "paths": {"/predict": {"post": {
+                "operationId": "post_predict",
+                "x-neutrino-resources": {"num_cpus": 2.0, "num_gpus": 1.0, "memory_gb": 8.0}
+            }}}
+        });
+        let app = Router::new().route(
+            "/specs/app.json",
+            get(move || async move { axum::Json(spec) }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/specs/app.json", listener.local_addr().unwrap());
+        let registry = tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let cache_dir =
+            std::env::temp_dir().join(format!("neutrino-spec-cache-{}", uuid::Uuid::new_v4()));
+        let config = SpecFetchConfig {
+            timeout_secs: 2,
+            retries: 1,
+            cache_dir: Some(cache_dir.to_string_lossy().into_owned()),
+        };
+
+        let routes = fetch_spec(&url, &config).await.unwrap().extract_routes();
+        assert_eq!(routes.len(), 1);
+        assert_eq!(
+            (routes[0].method.as_str(), routes[0].path.as_str()),
+            ("POST", "/predict")
+        );
+        assert_eq!(routes[0].handler_name, "predict");
+        assert_eq!(routes[0].resources.num_gpus, 1.0);
+
+        // With the registry down, the cached copy is used
+        registry.abort();
+        let _ = registry.await;
+        let cached = fetch_spec(&url, &config).await.unwrap();
+        assert_eq!(cached.extract_routes()[0].handler_name, "predict");
+
+        // Without one, startup can't get the spec
+        std::fs::remove_dir_all(&cache_dir).unwrap();
+        let err = fetch_spec(&url, &config).await.unwrap_err();
+        assert!(err.contains("no cached copy"), "{}", err);
+    }
+}
@@ -0,0 +1,626 @@
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::{Operation, Parameter};
+
+/// Combined request schema for a route: path params, query params, and body.
+/// Validation collects every violation so clients can fix them in one round trip.
+#[derive(Debug, Clone, Default)]
+pub struct RequestSchema {
+    pub path_params: Vec<Parameter>,
+    pub query_params: Vec<Parameter>,
+    /// JSON schema for the task args (the `requestBody` application/json schema)
+    pub body: Option<Value>,
+    pub body_required: bool,
+    /// Reject top-level body fields the schema doesn't declare, from
+    /// x-neutrino-reject-unknown-fields (None = `http.reject_unknown_fields`)
+    pub reject_unknown_fields: Option<bool>,
+    /// Shared `components.schemas` used to resolve `$ref`s
+    components: Arc<HashMap<String, Value>>,
+}
+
+impl RequestSchema {
+    /// Build the request schema for an operation
+    pub fn from_operation(op: &Operation, components: Arc<HashMap<String, Value>>) -> Self {
+        let path_params = op
+            .parameters
+            .iter()
+            .filter(|p| p.location == "path")
+            .cloned()
+            .collect();
+        let query_params = op
+            .parameters
+            .iter()
+            .filter(|p| p.location == "query")
+            .cloned()
+            .collect();
+
+        let (body, body_required) = match &op.request_body {
+            Some(request_body) => (
+                request_body
+                    .content
+                    .get("application/json")
+                    .map(|media| media.schema.clone()),
+                request_body.required,
+            ),
+            None => (None, false),
+        };
+
+        Self {
+            path_params,
+            query_params,
+            body,
+            body_required,
+            reject_unknown_fields: op.reject_unknown_fields,
+            components,
+        }
+    }
+
+    /// Validate path params, query params, and body together.
+    /// Returns every violation found (empty if the request is valid).
+    pub fn validate(
+        &self,
+        path: &HashMap<String, String>,
+        query: &HashMap<String, String>,
+        body: Option<&Value>,
+    ) -> Vec<String> {
+        let mut violations = Vec::new();
+
+        for param in &self.path_params {
+            match path.get(&param.name) {
+                // Path params are always required per the OpenAPI spec
+                None => violations.push(format!(
+                    "path.{}: missing required path parameter",
+                    param.name
+                )),
+                Some(raw) => self.validate_param("path", param, raw, &mut violations),
+            }
+        }
+
+        for param in &self.query_params {
+            match query.get(&param.name) {
+                None if param.required => violations.push(format!(
+                    "query.{}: missing required query parameter",
+                    param.name
+                )),
+                None => {}
+                Some(raw) => self.validate_param("query", param, raw, &mut violations),
+            }
+        }
+
+        if let Some(schema) = &self.body {
+            match body {
+                None | Some(Value::Null) if self.body_required => {
+                    violations.push("body: request body is required".to_string())
+                }
+                None => {}
+                Some(value) => {
+                    self.validate_value(value, schema, schema, "body", &mut violations);
+                    if self.reject_unknown_fields == Some(true) {
+                        self.validate_known_fields(value, schema, &mut violations);
+                    }
+                }
+            }
+        }
+
+        violations
+    }
+
+    /// Flag top-level body fields the schema doesn't declare, as if it set
+    /// `additionalProperties: false`. Schemas that admit other fields on their
+    /// own (anyOf/oneOf, or an explicit additionalProperties) are left as is.
+    fn validate_known_fields(&self, body: &Value, schema: &Value, violations: &mut Vec<String>) {
+        let Value::Object(obj) = body else { return };
+        let resolved = self.resolve(schema, schema).unwrap_or(schema);
+        let open = resolved.get("anyOf").is_some()
+            || resolved.get("oneOf").is_some()
+            || resolved
+                .get("additionalProperties")
+                .is_some_and(|additional| additional != &Value::Bool(false));
+        if open {
+            return;
+        }
+
+        let properties = declared_properties(&self.components, schema, schema).unwrap_or_default();
+        for name in obj
+            .keys()
+            .filter(|name| !properties.contains_key(name.as_str()))
+        {
+            violations.push(format!("body.{}: unknown field", name));
+        }
+    }
+
+    /// Coerce a raw string parameter to its schema type and validate it
+    fn validate_param(
+        &self,
+        location: &str,
+        param: &Parameter,
+        raw: &str,
+        violations: &mut Vec<String>,
+    ) {
+        let field = format!("{}.{}", location, param.name);
+        let schema = self
+            .resolve(&param.schema, &param.schema)
+            .unwrap_or(&param.schema);
+
+        let coerced = match schema.get("type").and_then(Value::as_str) {
+            Some("integer") => raw.parse::<i64>().map(Value::from).ok(),
+            Some("number") => raw.parse::<f64>().ok().map(Value::from),
+            Some("boolean") => raw.parse::<bool>().ok().map(Value::Bool),
+            _ => Some(Value::String(raw.to_string())),
+        };
+
+        match coerced {
+            Some(value) => self.validate_value(&value, schema, &param.schema, &field, violations),
+            None => violations.push(format!(
+                "{}: expected {}, got '{}'",
+                field,
+                schema
+                    .get("type")
+                    .and_then(Value::as_str)
+                    .unwrap_or("value"),
+                raw
+            )),
+        }
+    }
+
+    /// Resolve a `$ref` against `components.schemas` or the root schema's `$defs`
+    fn resolve<'a>(&'a self, schema: &'a Value, root: &'a Value) -> Option<&'a Value> {
+        resolve_ref(&self.components, schema, root)
+    }
+
+    /// Validate a JSON value against a (subset of) JSON schema
+    fn validate_value(
+        &self,
+        value: &Value,
+        schema: &Value,
+        root: &Value,
+        field: &str,
+        violations: &mut Vec<String>,
+    ) {
+        if schema.get("$ref").is_some() {
+            match self.resolve(schema, root) {
+                Some(resolved) => self.validate_value(value, resolved, root, field, violations),
+                None => violations.push(format!("{}: unresolvable schema reference", field)),
+            }
+            return;
+        }
+
+        for key in ["anyOf", "oneOf"] {
+            if let Some(options) = schema.get(key).and_then(Value::as_array) {
+                let matches = options.iter().any(|option| {
+                    let mut option_violations = Vec::new();
+                    self.validate_value(value, option, root, field, &mut option_violations);
+                    option_violations.is_empty()
+                });
+                if !matches {
+                    violations.push(format!("{}: does not match any allowed schema", field));
+                }
+                return;
+            }
+        }
+
+        if let Some(all) = schema.get("allOf").and_then(Value::as_array) {
+            for sub in all {
+                self.validate_value(value, sub, root, field, violations);
+            }
+        }
+
+        if let Some(expected) = schema.get("type") {
+            let allowed: Vec<&str> = match expected {
+                Value::String(t) => vec![t.as_str()],
+                Value::Array(types) => types.iter().filter_map(Value::as_str).collect(),
+                _ => vec![],
+            };
+            if !allowed.is_empty() && !allowed.iter().any(|t| matches_type(value, t)) {
+                violations.push(format!(
+                    "{}: expected {}, got {}",
+                    field,
+                    allowed.join(" | "),
+                    type_name(value)
+                ));
+                return;
+            }
+        }
+
+        if let Some(options) = schema.get("enum").and_then(Value::as_array) {
+            if !options.contains(value) {
+                violations.push(format!(
+                    "{}: value {} is not one of {:?}",
+                    field, value, options
+                ));
+            }
+        }
+
+        if let Value::Object(obj) = value {
+            if let Some(required) = schema.get("required").and_then(Value::as_array) {
+                for name in required.iter().filter_map(Value::as_str) {
+                    if !obj.contains_key(name) {
+                        violations.push(format!("{}.{}: missing required field", field, name));
+                    }
+                }
+            }
+            if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+                for (name, prop_schema) in properties {
+                    if let Some(prop_value) = obj.get(name) {
+                        let prop_field = format!("{}.{}", field, name);
+                        self.validate_value(prop_value, prop_schema, root, &prop_field, violations);
+                    }
+                }
+            }
+        }
+
+        if let (Value::Array(items), Some(item_schema)) = (value, schema.get("items")) {
+            for (idx, item) in items.iter().enumerate() {
+                let item_field = format!("{}[{}]", field, idx);
+                self.validate_value(item, item_schema, root, &item_field, violations);
+            }
+        }
+    }
+}
+
+/// Success response schema for a route (the handler result's JSON schema), used to
+/// drop fields the API contract doesn't declare
+#[derive(Debug, Clone)]
+pub struct ResponseSchema {
+    pub schema: Value,
+    /// Shared `components.schemas` used to resolve `$ref`s
+    components: Arc<HashMap<String, Value>>,
+}
+
+impl ResponseSchema {
+    /// The application/json schema of the operation's 200 response (or lowest 2xx), if declared
+    pub fn from_operation(op: &Operation, components: Arc<HashMap<String, Value>>) -> Option<Self> {
+        let mut success: Vec<&String> = op
+            .responses
+            .keys()
+            .filter(|code| code.starts_with('2'))
+            .collect();
+        success.sort();
+
+        let schema = success
+            .into_iter()
+            .filter_map(|code| op.responses[code].content.as_ref()?.get("application/json"))
+            .map(|media| media.schema.clone())
+            .next()?;
+
+        Some(Self { schema, components })
+    }
+
+    /// Remove object fields the schema doesn't declare, returning the removed field paths
+    pub fn filter(&self, value: &mut Value) -> Vec<String> {
+        let mut removed = Vec::new();
+        self.filter_value(value, &self.schema, &self.schema, "result", &mut removed);
+        removed
+    }
+
+    fn filter_value(
+        &self,
+        value: &mut Value,
+        schema: &Value,
+        root: &Value,
+        field: &str,
+        removed: &mut Vec<String>,
+    ) {
+        if schema.get("$ref").is_some() {
+            if let Some(resolved) = resolve_ref(&self.components, schema, root) {
+                self.filter_value(value, resolved, root, field, removed);
+            }
+            return;
+        }
+
+        match value {
+            Value::Object(obj) => {
+                // Only filter when every declared property is known; anyOf/oneOf or
+                // open objects (additionalProperties) are passed through as is
+                let open = schema.get("anyOf").is_some()
+                    || schema.get("oneOf").is_some()
+                    || schema
+                        .get("additionalProperties")
+                        .is_some_and(|additional| additional != &Value::Bool(false));
+                let Some(properties) = declared_properties(&self.components, schema, root) else {
+                    return;
+                };
+                if open {
+                    return;
+                }
+
+                obj.retain(|name, _| {
+                    let declared = properties.contains_key(name.as_str());
+                    if !declared {
+                        removed.push(format!("{}.{}", field, name));
+                    }
+                    declared
+                });
+                for (name, prop_value) in obj.iter_mut() {
+                    let prop_field = format!("{}.{}", field, name);
+                    self.filter_value(
+                        prop_value,
+                        properties[name.as_str()],
+                        root,
+                        &prop_field,
+                        removed,
+                    );
+                }
+            }
+            Value::Array(items) => {
+                if let Some(item_schema) = schema.get("items") {
+                    for (idx, item) in items.iter_mut().enumerate() {
+                        let item_field = format!("{}[{}]", field, idx);
+                        self.filter_value(item, item_schema, root, &item_field, removed);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Properties declared by a schema, including those from `allOf` members
+fn declared_properties<'a>(
+    components: &'a HashMap<String, Value>,
+    schema: &'a Value,
+    root: &'a Value,
+) -> Option<HashMap<&'a str, &'a Value>> {
+    let schema = resolve_ref(components, schema, root).unwrap_or(schema);
+    let mut properties: Option<HashMap<&str, &Value>> = schema
+        .get("properties")
+        .and_then(Value::as_object)
+        .map(|props| props.iter().map(|(k, v)| (k.as_str(), v)).collect());
+
+    for sub in schema
+        .get("allOf")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        if let Some(sub_properties) = declared_properties(components, sub, root) {
+            properties
+                .get_or_insert_with(HashMap::new)
+                .extend(sub_properties);
+        }
+    }
+    properties
+}
+
+/// Resolve a `$ref` against `components.schemas` or the root schema's `$defs`
+fn resolve_ref<'a>(
+    components: &'a HashMap<String, Value>,
+    schema: &'a Value,
+    root: &'a Value,
+) -> Option<&'a Value> {
+    let reference = schema.get("$ref")?.as_str()?;
+    if let Some(name) = reference.strip_prefix("#/components/schemas/") {
+        components.get(name)
+    } else if let Some(name) = reference.strip_prefix("#/$defs/") {
+        root.get("$defs")?.get(name)
+    } else if let Some(name) = reference.strip_prefix("#/definitions/") {
+        root.get("definitions")?.get(name)
+    } else {
+        None
+    }
+}
+
+fn matches_type(value: &Value, expected: &str) -> bool {
+    match expected {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "number" => value.is_number(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_f64() => "number",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema() -> RequestSchema {
+        let op: Operation = serde_json::from_value(json!({
+            "operationId": "post_update_item",
+            "parameters": [
+                {"name": "item_id", "in": "path", "required": true, "schema": {"type": "integer"}},
+                {"name": "limit", "in": "query", "required": false, "schema": {"type": "integer"}},
+                {"name": "mode", "in": "query", "required": true, "schema": {"type": "string", "enum": ["fast", "slow"]}}
+            ],
+            "requestBody": {
+                "required": true,
+                "content": {
+                    "application/json": {
+                        "schema": {"$ref": "#/components/schemas/UpdateItem"}
+                    }
+                }
+            }
+        }))
+        .unwrap();
+
+        let mut components = HashMap::new();
+        components.insert(
+            "UpdateItem".to_string(),
+            json!({
+                "type": "object",
+                "required": ["name", "price"],
+                "properties": {
+                    "name": {"type": "string"},
+                    "price": {"type": "number"},
+                    "tags": {"type": "array", "items": {"type": "string"}}
+                }
+            }),
+        );
+
+        RequestSchema::from_operation(&op, Arc::new(components))
+    }
+
+    fn params(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_valid_request_passes() {
+        let violations = schema().validate(
+            &params(&[("item_id", "42")]),
+            &params(&[("limit", "10"), ("mode", "fast")]),
+            Some(&json!({"name": "widget", "price": 9.99, "tags": ["a"]})),
+        );
+        assert!(violations.is_empty(), "{:?}", violations);
+    }
+
+    #[test]
+    fn test_reports_all_violations_together() {
+        let violations = schema().validate(
+            &params(&[("item_id", "abc")]),
+            &params(&[("limit", "ten")]),
+            Some(&json!({"price": "free", "tags": ["a", 1]})),
+        );
+
+        assert!(violations.contains(&"path.item_id: expected integer, got 'abc'".to_string()));
+        assert!(violations.contains(&"query.limit: expected integer, got 'ten'".to_string()));
+        assert!(violations.contains(&"query.mode: missing required query parameter".to_string()));
+        assert!(violations.contains(&"body.name: missing required field".to_string()));
+        assert!(violations.contains(&"body.price: expected number, got string".to_string()));
+        assert!(violations.contains(&"body.tags[1]: expected string, got integer".to_string()));
+        assert_eq!(violations.len(), 6, "{:?}", violations);
+    }
+
+    #[test]
+    fn test_unknown_body_fields_rejected_when_strict() {
+        let body = json!({"name": "widget", "price": 9.99, "discount": 0.5, "admin": true});
+        let query = params(&[("mode", "fast")]);
+        let mut strict = schema();
+        assert!(strict
+            .validate(&params(&[("item_id", "1")]), &query, Some(&body))
+            .is_empty());
+
+        strict.reject_unknown_fields = Some(true);
+        let mut violations = strict.validate(&params(&[("item_id", "1")]), &query, Some(&body));
+        violations.sort();
+        assert_eq!(
+            violations,
+            vec!["body.admin: unknown field", "body.discount: unknown field"]
+        );
+
+        // Only top-level fields are checked
+        let nested = json!({"name": "widget", "price": 1, "tags": ["a"]});
+        assert!(strict
+            .validate(&params(&[("item_id", "1")]), &query, Some(&nested))
+            .is_empty());
+    }
+
+    #[test]
+    fn test_missing_path_param_and_body() {
+        let violations = schema().validate(&params(&[]), &params(&[("mode", "slow")]), None);
+        assert_eq!(
+            violations,
+            vec![
+                "path.item_id: missing required path parameter".to_string(),
+                "body: request body is required".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_any_of_and_defs() {
+        let op: Operation = serde_json::from_value(json!({
+            "operationId": "post_create",
+            "requestBody": {
+                "required": true,
+                "content": {"application/json": {"schema": {
+                    "type": "object",
+                    "properties": {
+                        "nickname": {"anyOf": [{"type": "string"}, {"type": "null"}]},
+                        "owner": {"$ref": "#/$defs/Owner"}
+                    },
+                    "$defs": {"Owner": {"type": "object", "required": ["id"]}}
+                }}}
+            }
+        }))
+        .unwrap();
+        let schema = RequestSchema::from_operation(&op, Arc::new(HashMap::new()));
+
+        assert!(schema
+            .validate(
+                &params(&[]),
+                &params(&[]),
+                Some(&json!({"nickname": null, "owner": {"id": 1}}))
+            )
+            .is_empty());
+        assert_eq!(
+            schema.validate(
+                &params(&[]),
+                &params(&[]),
+                Some(&json!({"nickname": 3, "owner": {}}))
+            ),
+            vec![
+                "body.nickname: does not match any allowed schema".to_string(),
+                "body.owner.id: missing required field".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_response_filter_strips_undeclared_fields() {
+        let op: Operation = serde_json::from_value(json!({
+            "operationId": "post_predict",
+            "responses": {
+                "200": {
+                    "description": "ok",
+                    "content": {"application/json": {"schema": {"$ref": "#/components/schemas/Prediction"}}}
+                }
+            }
+        }))
+        .unwrap();
+        let components = HashMap::from([(
+            "Prediction".to_string(),
+            json!({
+                "type": "object",
+                "properties": {
+                    "label": {"type": "string"},
+                    "scores": {"type": "array", "items": {
+                        "type": "object",
+                        "properties": {"label": {"type": "string"}, "score": {"type": "number"}}
+                    }},
+                    "metadata": {"type": "object", "additionalProperties": true}
+                }
+            }),
+        )]);
+        let schema = ResponseSchema::from_operation(&op, Arc::new(components)).unwrap();
+
+        let mut result = json!({
+            "label": "cat",
+            "scores": [{"label": "cat", "score": 0.9, "logit": 2.2}],
+            "metadata": {"anything": 1},
+            "internal_cost": 0.02
+        });
+        let mut removed = schema.filter(&mut result);
+        removed.sort();
+
+        assert_eq!(
+            removed,
+            vec!["result.internal_cost", "result.scores[0].logit"]
+        );
+        assert_eq!(
+            result,
+            json!({"label": "cat", "scores": [{"label": "cat", "score": 0.9}], "metadata": {"anything": 1}})
+        );
+    }
+}
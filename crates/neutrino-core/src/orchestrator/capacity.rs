@@ -0,0 +1,233 @@
+use std::fs;
+use std::process::Command;
+
+use crate::config::WorkerPoolConfig;
+
+/// Resources detected on the host
+#[derive(Debug, Clone, PartialEq)]
+pub struct HostResources {
+    pub cpus: f64,
+    /// Total memory in GB (None if it couldn't be read)
+    pub memory_gb: Option<f64>,
+    /// GPU count (None if nvidia-smi isn't available)
+    pub gpus: Option<f64>,
+    /// Device indices listed by nvidia-smi (None if it isn't available)
+    pub gpu_indices: Option<Vec<usize>>,
+}
+
+impl HostResources {
+    /// Detect CPU count, total memory (/proc/meminfo), and GPUs (nvidia-smi)
+    pub fn detect() -> Self {
+        let cpus = std::thread::available_parallelism()
+            .map(|n| n.get() as f64)
+            .unwrap_or(1.0);
+
+        let gpu_indices = detect_gpu_indices();
+        Self {
+            cpus,
+            memory_gb: detect_memory_gb(),
+            gpus: gpu_indices.as_ref().map(|indices| indices.len() as f64),
+            gpu_indices,
+        }
+    }
+}
+
+/// Read MemTotal from /proc/meminfo
+fn detect_memory_gb() -> Option<f64> {
+    let meminfo = fs::read_to_string("/proc/meminfo").ok()?;
+    let line = meminfo.lines().find(|l| l.starts_with("MemTotal:"))?;
+    // Line format: "MemTotal:       16318412 kB"
+    let kb: f64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kb / (1024.0 * 1024.0))
+}
+
+/// Device indices of the GPUs reported by nvidia-smi
+fn detect_gpu_indices() -> Option<Vec<usize>> {
+    let output = Command::new("nvidia-smi")
+        .args(["--query-gpu=index", "--format=csv,noheader"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let indices = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|l| l.trim().parse().ok())
+        .collect();
+    Some(indices)
+}
+
+/// Check that every device a GPU pool is pinned to (`gpu_devices`) exists on
+/// the host. Returns one message per missing device; hosts without nvidia-smi
+/// can't be checked and return none.
+pub fn check_gpu_devices(pools: &[WorkerPoolConfig], host: &HostResources) -> Vec<String> {
+    let Some(indices) = &host.gpu_indices else {
+        return Vec::new();
+    };
+
+    let mut violations = Vec::new();
+    // CUDA_VISIBLE_DEVICES is only set for pools that request GPUs
+    for pool in pools.iter().filter(|p| p.resources.num_gpus > 0.0) {
+        for device in pool.gpu_devices.iter().filter(|d| !indices.contains(d)) {
+            violations.push(format!(
+                "pool '{}' uses GPU {}, but nvidia-smi lists {}",
+                pool.name,
+                device,
+                if indices.is_empty() {
+                    "no GPUs".to_string()
+                } else {
+                    format!(
+                        "GPUs {}",
+                        indices
+                            .iter()
+                            .map(|i| i.to_string())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )
+                }
+            ));
+        }
+    }
+    violations
+}
+
+/// Compare the total resources claimed by all pools (`count * resources`) against
+/// the host. Returns one message per over-committed resource.
+pub fn check_overcommit(
+    pools: &[WorkerPoolConfig],
+    host: &HostResources,
+    max_ratio: f64,
+) -> Vec<String> {
+    let claimed_cpus: f64 = pools
+        .iter()
+        .map(|p| p.count as f64 * p.resources.num_cpus)
+        .sum();
+    let claimed_memory_gb: f64 = pools
+        .iter()
+        .map(|p| p.count as f64 * p.resources.memory_gb)
+        .sum();
+    let claimed_gpus: f64 = pools
+        .iter()
+        .map(|p| p.count as f64 * p.resources.num_gpus)
+        .sum();
+
+    let mut violations = Vec::new();
+    let mut check = |name: &str, claimed: f64, available: Option<f64>| {
+        if let Some(available) = available {
+            if claimed > available * max_ratio {
+                violations.push(format!(
+                    "worker pools claim {} {} but the host has {:.1} (max ratio {})",
+                    claimed, name, available, max_ratio
+                ));
+            }
+        }
+    };
+
+    check("CPUs", claimed_cpus, Some(host.cpus));
+    check("GB of memory", claimed_memory_gb, host.memory_gb);
+    check("GPUs", claimed_gpus, host.gpus);
+
+    violations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::ResourceCapabilities;
+
+    fn pool(
+        name: &str,
+        count: usize,
+        num_cpus: f64,
+        memory_gb: f64,
+        num_gpus: f64,
+    ) -> WorkerPoolConfig {
+        WorkerPoolConfig {
+            name: name.to_string(),
+            count,
+            resources: ResourceCapabilities {
+                num_cpus,
+                num_gpus,
+                memory_gb,
+                gpu_memory_gb: None,
+            },
+            gpu_devices: vec![],
+            cpuset: None,
+            min_idle: 0,
+            max_count: None,
+            min_count: None,
+            scale_up_utilization: 0.8,
+            scale_down_utilization: 0.2,
+            max_total_memory_mb: None,
+            allow_overflow_from: None,
+            allow_overflow_to: None,
+            warmup_handler: None,
+            warmup_timeout_secs: 300,
+        }
+    }
+
+    fn host() -> HostResources {
+        HostResources {
+            cpus: 8.0,
+            memory_gb: Some(32.0),
+            gpus: None,
+            gpu_indices: None,
+        }
+    }
+
+    #[test]
+    fn test_overcommitted_pools_flagged() {
+        let pools = vec![pool("cpu", 100, 4.0, 0.25, 0.0)];
+        let violations = check_overcommit(&pools, &host(), 1.0);
+        assert_eq!(violations.len(), 1);
+        assert!(violations[0].contains("400 CPUs"), "{}", violations[0]);
+    }
+
+    #[test]
+    fn test_pools_within_capacity() {
+        let pools = vec![
+            pool("cpu", 2, 2.0, 8.0, 0.0),
+            pool("small", 4, 1.0, 4.0, 0.0),
+        ];
+        assert!(check_overcommit(&pools, &host(), 1.0).is_empty());
+    }
+
+    #[test]
+    fn test_ratio_and_gpus() {
+        let pools = vec![pool("gpu", 4, 4.0, 8.0, 1.0)];
+        let host = HostResources {
+            gpus: Some(2.0),
+            ..host()
+        };
+
+        // 16 CPUs on 8 cores and 4 GPUs on 2 are both allowed at 2x
+        let violations = check_overcommit(&pools, &host, 2.0);
+        assert_eq!(violations.len(), 0);
+        let violations = check_overcommit(&pools, &host, 1.0);
+        assert_eq!(violations.len(), 2);
+        assert!(violations.iter().any(|v| v.contains("4 GPUs")));
+    }
+
+    #[test]
+    fn test_missing_gpu_device_flagged() {
+        let mut gpu = pool("gpu", 4, 4.0, 8.0, 1.0);
+        gpu.gpu_devices = vec![0, 1, 3];
+        let mut cpu = pool("cpu", 2, 1.0, 1.0, 0.0);
+        cpu.gpu_devices = vec![7];
+        let pools = vec![gpu, cpu];
+
+        let two_gpus = HostResources {
+            gpus: Some(2.0),
+            gpu_indices: Some(vec![0, 1]),
+            ..host()
+        };
+        let violations = check_gpu_devices(&pools, &two_gpus);
+        assert_eq!(
+            violations,
+            vec!["pool 'gpu' uses GPU 3, but nvidia-smi lists GPUs 0, 1"]
+        );
+
+        // Without nvidia-smi there is nothing to check against
+        assert!(check_gpu_devices(&pools, &host()).is_empty());
+    }
+}
@@ -0,0 +1,142 @@
+//! Tasks waiting for a worker, counted against the pools that could run
+//! them, so the autoscaler grows only the pools that have work waiting.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::config::WorkerPoolConfig;
+use crate::protocol::ResourceRequirements;
+
+use super::Placement;
+
+/// Waiting tasks per pool
+#[derive(Default)]
+pub struct Demand(Mutex<HashMap<String, usize>>);
+
+/// A waiting task's count against its pools, taken back when dropped
+pub struct DemandGuard {
+    demand: Arc<Demand>,
+    pools: Vec<String>,
+}
+
+impl Demand {
+    /// Count a task waiting for a worker against the pools that could run it
+    pub fn register(
+        self: &Arc<Self>,
+        pools: &[WorkerPoolConfig],
+        requirements: &ResourceRequirements,
+        placement: &Placement<'_>,
+    ) -> DemandGuard {
+        let pools: Vec<String> = pools
+            .iter()
+            .filter(|pool| can_run(pool, requirements, placement))
+            .map(|pool| pool.name.clone())
+            .collect();
+        let mut counts = self.0.lock().unwrap();
+        for pool in &pools {
+            *counts.entry(pool.clone()).or_insert(0) += 1;
+        }
+        DemandGuard {
+            demand: Arc::clone(self),
+            pools,
+        }
+    }
+
+    /// Tasks waiting for a worker each pool could give them
+    pub fn by_pool(&self) -> HashMap<String, usize> {
+        self.0.lock().unwrap().clone()
+    }
+}
+
+impl Drop for DemandGuard {
+    fn drop(&mut self) {
+        let mut counts = self.demand.0.lock().unwrap();
+        for pool in &self.pools {
+            if let Some(count) = counts.get_mut(pool) {
+                *count -= 1;
+                if *count == 0 {
+                    counts.remove(pool);
+                }
+            }
+        }
+    }
+}
+
+/// Whether a worker of the pool, when free, could take the task
+fn can_run(
+    pool: &WorkerPoolConfig,
+    requirements: &ResourceRequirements,
+    placement: &Placement<'_>,
+) -> bool {
+    let resources = &pool.resources;
+    placement.pool.is_none_or(|name| name == pool.name)
+        && placement
+            .excluded_pools
+            .is_none_or(|excluded| !excluded.contains(&pool.name))
+        && placement
+            .gpu_device
+            .is_none_or(|device| pool.gpu_devices.contains(&device))
+        && resources.num_cpus >= requirements.num_cpus
+        && resources.num_gpus >= requirements.num_gpus
+        && resources.memory_gb >= requirements.memory_gb
+        && resources
+            .gpu_memory_gb
+            .is_none_or(|gpu_memory_gb| gpu_memory_gb >= requirements.gpu_memory_gb)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::ResourceCapabilities;
+
+    fn pool(name: &str, num_gpus: f64, gpu_devices: Vec<usize>) -> WorkerPoolConfig {
+        let mut pool = crate::config::Config::default().effective_worker_pools()[0].clone();
+        pool.name = name.to_string();
+        pool.resources = ResourceCapabilities {
+            num_gpus,
+            ..ResourceCapabilities::default()
+        };
+        pool.gpu_devices = gpu_devices;
+        pool
+    }
+
+    #[test]
+    fn test_waiting_tasks_counted_against_pools_that_fit() {
+        let pools = [pool("cpu", 0.0, vec![]), pool("gpu", 1.0, vec![0])];
+        let demand = Arc::new(Demand::default());
+        let gpu_task = ResourceRequirements {
+            num_gpus: 1.0,
+            ..ResourceRequirements::default()
+        };
+
+        let cpu = demand.register(
+            &pools,
+            &ResourceRequirements::default(),
+            &Placement::default(),
+        );
+        let gpu = demand.register(&pools, &gpu_task, &Placement::default());
+        let pinned = demand.register(
+            &pools,
+            &ResourceRequirements::default(),
+            &Placement {
+                pool: Some("cpu"),
+                ..Placement::default()
+            },
+        );
+        let elsewhere = demand.register(
+            &pools,
+            &gpu_task,
+            &Placement {
+                gpu_device: Some(3),
+                ..Placement::default()
+            },
+        );
+        assert_eq!(
+            demand.by_pool(),
+            HashMap::from([("cpu".to_string(), 2), ("gpu".to_string(), 2)])
+        );
+
+        drop((cpu, gpu, pinned, elsewhere));
+        assert!(demand.by_pool().is_empty());
+    }
+}
@@ -0,0 +1,255 @@
+//! Why each worker would or wouldn't take a task: the checks of
+//! `Orchestrator::find_worker_with_resources`, applied to every worker and
+//! reported instead of acted on. Nothing is leased and the round-robin
+//! position doesn't move, so explaining a task is safe under live traffic.
+
+use serde::Serialize;
+use std::collections::HashMap;
+
+use super::{idle_counts, pool_name, Orchestrator, Placement, SelectionPass};
+use crate::config::{SchedulingStrategy, WorkerPoolConfig};
+use crate::protocol::ResourceRequirements;
+use crate::worker::{Worker, WorkerState};
+
+/// How one worker fares for a task
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct WorkerVerdict {
+    pub worker_id: String,
+    pub pool: String,
+    pub state: String,
+    /// Free (cpus, gpus, memory_gb)
+    pub available: (f64, f64, f64),
+    /// The earliest selection pass that would take the worker (None = skipped)
+    pub pass: Option<&'static str>,
+    /// Why the worker is skipped, or held back to a later pass
+    pub reasons: Vec<String>,
+}
+
+impl Orchestrator {
+    /// A verdict for every worker on a task needing `requirements`
+    pub async fn explain_selection(
+        &self,
+        requirements: &ResourceRequirements,
+        overflow_pool: Option<&str>,
+        placement: &Placement<'_>,
+    ) -> Vec<WorkerVerdict> {
+        let workers = self.workers.read().await;
+        let strategy = self.config.orchestrator.scheduling;
+        let pools = self.config.effective_worker_pools();
+        let cpu_pools: Vec<&WorkerPoolConfig> = pools
+            .iter()
+            .filter(|p| p.resources.num_gpus == 0.0)
+            .collect();
+        let min_idle: HashMap<&str, usize> = pools
+            .iter()
+            .map(|p| (p.name.as_str(), p.min_idle))
+            .collect();
+        let idle = idle_counts(&workers);
+
+        let is_gpu_task = requirements.num_gpus > 0.0;
+        let has_cpu_workers = workers.iter().any(|handle| {
+            placement.allows(&handle.worker) && handle.worker.capabilities.num_gpus == 0.0
+        });
+
+        workers
+            .iter()
+            .map(|handle| {
+                let worker = &handle.worker;
+                let pool = pool_name(&worker.id);
+                let mut reasons = Vec::new();
+
+                if let Some(wanted) = placement.pool.filter(|&wanted| wanted != pool) {
+                    reasons.push(format!("not in pool {}", wanted));
+                }
+                if let Some(device) = placement
+                    .gpu_device
+                    .filter(|device| !worker.gpu_devices.contains(device))
+                {
+                    reasons.push(format!("not bound to GPU {}", device));
+                }
+                if handle.connection_busy() {
+                    reasons.push("exchanging messages for another task".to_string());
+                }
+                if worker.state == WorkerState::Stuck {
+                    reasons.push("stuck past a task's timeout".to_string());
+                }
+
+                let pass = if reasons.is_empty() {
+                    let reserve = min_idle.get(pool).copied().unwrap_or(0);
+                    let reserved = worker.state == WorkerState::Idle
+                        && reserve > 0
+                        && idle.get(pool).copied().unwrap_or(0) <= reserve;
+                    let accepts_overflow =
+                        pools.iter().find(|p| p.name == pool).is_none_or(|target| {
+                            cpu_pools.is_empty()
+                                || cpu_pools
+                                    .iter()
+                                    .any(|from| target.accepts_overflow_from(from))
+                        });
+                    let is_gpu_worker = worker.capabilities.num_gpus > 0.0;
+                    let matches_type = if is_gpu_task {
+                        is_gpu_worker
+                    } else {
+                        !has_cpu_workers || !is_gpu_worker
+                    };
+
+                    let mut pass = None;
+                    if !worker.has_capacity(requirements) {
+                        reasons.extend(shortfalls(worker, requirements));
+                    } else if matches_type && !reserved {
+                        pass = Some(match (worker.state, strategy) {
+                            (WorkerState::Idle, strategy)
+                                if strategy != SchedulingStrategy::Pack =>
+                            {
+                                SelectionPass::Idle
+                            }
+                            _ => SelectionPass::Busy,
+                        });
+                    } else if matches_type {
+                        reasons.push(format!(
+                            "held in pool {}'s min_idle reserve of {}",
+                            pool, reserve
+                        ));
+                        pass = Some(SelectionPass::Fallback);
+                    } else if is_gpu_task {
+                        reasons.push("GPU task on a CPU-only worker".to_string());
+                    } else if accepts_overflow {
+                        reasons.push("GPU worker, taken only when no CPU worker can".to_string());
+                        pass = Some(SelectionPass::Fallback);
+                    } else {
+                        reasons.push(format!("pool {} doesn't accept CPU task overflow", pool));
+                    }
+
+                    // The route's overflow pool takes the task without its GPUs
+                    let relaxed = requirements.without_gpus();
+                    if pass.is_none()
+                        && overflow_pool == Some(pool)
+                        && worker.has_capacity(&relaxed)
+                    {
+                        reasons.push(format!(
+                            "runs the task without GPUs as overflow pool {}",
+                            pool
+                        ));
+                        pass = Some(SelectionPass::Overflow);
+                    }
+                    pass
+                } else {
+                    None
+                };
+
+                WorkerVerdict {
+                    worker_id: worker.id.clone(),
+                    pool: pool.to_string(),
+                    state: format!("{:?}", worker.state).to_lowercase(),
+                    available: worker.available_resources(),
+                    pass: pass.map(|pass| pass.as_str()),
+                    reasons,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Each resource the worker has too little of, free vs needed
+fn shortfalls(worker: &Worker, needs: &ResourceRequirements) -> Vec<String> {
+    let (cpus, gpus, memory_gb) = worker.available_resources();
+    let mut shortfalls = Vec::new();
+    let resources = [
+        ("cpus", cpus, needs.num_cpus),
+        ("gpus", gpus, needs.num_gpus),
+        ("memory_gb", memory_gb, needs.memory_gb),
+    ];
+    for (name, free, needed) in resources {
+        if free < needed {
+            shortfalls.push(format!(
+                "insufficient {}: {} free, {} needed",
+                name, free, needed
+            ));
+        }
+    }
+    if let Some(free) = worker
+        .available_gpu_memory_gb()
+        .filter(|&free| free < needs.gpu_memory_gb)
+    {
+        shortfalls.push(format!(
+            "insufficient gpu_memory_gb: {} free, {} needed",
+            free, needs.gpu_memory_gb
+        ));
+    }
+    shortfalls
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::protocol::ResourceCapabilities;
+    use crate::testing::mock_worker_handle;
+
+    fn capabilities(num_cpus: f64, num_gpus: f64) -> ResourceCapabilities {
+        ResourceCapabilities {
+            num_cpus,
+            num_gpus,
+            memory_gb: 8.0,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_explains_why_workers_are_skipped() {
+        let orchestrator = Orchestrator::new(Config::default());
+        let mut busy = mock_worker_handle("cpu-0", capabilities(2.0, 0.0)).0;
+        busy.worker.state = WorkerState::Busy;
+        busy.worker.allocation.allocated_cpus = 2.0;
+        let handles = vec![
+            busy,
+            mock_worker_handle("cpu-1", capabilities(2.0, 0.0)).0,
+            mock_worker_handle("gpu-0", capabilities(4.0, 1.0)).0,
+        ];
+        orchestrator.workers().write().await.extend(handles);
+
+        let needs = ResourceRequirements {
+            num_cpus: 1.0,
+            num_gpus: 0.0,
+            memory_gb: 1.0,
+            gpu_memory_gb: 0.0,
+        };
+        let verdicts = orchestrator
+            .explain_selection(&needs, None, &Placement::default())
+            .await;
+        let summary: Vec<_> = verdicts
+            .iter()
+            .map(|v| (v.worker_id.as_str(), v.pass))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("cpu-0", None),
+                ("cpu-1", Some("idle")),
+                ("gpu-0", Some("fallback"))
+            ]
+        );
+        assert_eq!(
+            verdicts[0].reasons,
+            vec!["insufficient cpus: 0 free, 1 needed"]
+        );
+        assert_eq!(
+            verdicts[2].reasons,
+            vec!["GPU worker, taken only when no CPU worker can"]
+        );
+
+        let gpu_needs = ResourceRequirements {
+            num_gpus: 1.0,
+            ..needs
+        };
+        let placement = Placement {
+            pool: Some("gpu"),
+            ..Default::default()
+        };
+        let verdicts = orchestrator
+            .explain_selection(&gpu_needs, None, &placement)
+            .await;
+        assert_eq!(verdicts[1].reasons, vec!["not in pool gpu"]);
+        assert_eq!(verdicts[2].pass, Some("idle"));
+    }
+}
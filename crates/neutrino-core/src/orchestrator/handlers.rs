@@ -0,0 +1,71 @@
+use std::collections::HashSet;
+use std::sync::RwLock;
+
+use crate::worker::WorkerHandle;
+
+/// Handler names provided by the current workers, shared with the HTTP layer so
+/// requests for a handler no worker has are rejected without dispatching
+#[derive(Debug, Default)]
+pub struct HandlerRegistry {
+    /// None until every worker has reported its handlers; nothing is rejected then
+    available: RwLock<Option<HashSet<String>>>,
+}
+
+impl HandlerRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Rebuild the set from the workers' advertised handlers
+    pub fn refresh(&self, workers: &[WorkerHandle]) {
+        let available = if workers.is_empty() {
+            None
+        } else {
+            workers
+                .iter()
+                .map(|handle| handle.worker.handlers.as_ref())
+                .try_fold(HashSet::new(), |mut all, handlers| {
+                    all.extend(handlers?.iter().cloned());
+                    Some(all)
+                })
+        };
+
+        *self.available.write().unwrap() = available;
+    }
+
+    /// Whether some worker provides `handler` (always true while unknown)
+    pub fn is_available(&self, handler: &str) -> bool {
+        match &*self.available.read().unwrap() {
+            Some(available) => available.contains(handler),
+            None => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::ResourceCapabilities;
+    use crate::testing::mock_worker_handle;
+
+    fn worker(handlers: Option<&[&str]>) -> WorkerHandle {
+        let (mut handle, _) = mock_worker_handle("default-0", ResourceCapabilities::default());
+        handle.worker.handlers = handlers.map(|h| h.iter().map(|s| s.to_string()).collect());
+        handle
+    }
+
+    #[tokio::test]
+    async fn test_union_of_worker_handlers() {
+        let registry = HandlerRegistry::new();
+        assert!(registry.is_available("anything"));
+
+        registry.refresh(&[worker(Some(&["embed"])), worker(Some(&["train"]))]);
+        assert!(registry.is_available("embed"));
+        assert!(registry.is_available("train"));
+        assert!(!registry.is_available("missing"));
+
+        // A worker that didn't report its handlers might have any of them
+        registry.refresh(&[worker(Some(&["embed"])), worker(None)]);
+        assert!(registry.is_available("missing"));
+    }
+}
@@ -1,11 +1,412 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::RwLock;
-use tokio::task::JoinHandle;
-use tracing::{info, warn, debug};
+use tokio::task::{JoinHandle, JoinSet};
+use tracing::{debug, info, warn};
 
-use crate::config::Config;
-use crate::worker::{WorkerHandle, WorkerState, memory};
+use crate::config::{Config, OvercommitAction, SchedulingStrategy, WorkerPoolConfig};
+use crate::metrics::Metrics;
+use crate::protocol::ResourceRequirements;
+use crate::protocol::{self, WireFormat};
+use crate::snapshot::Snapshot;
+use crate::worker::history::AllocationEvent;
+use crate::worker::{memory, Leases, TaskLease, WorkerHandle, WorkerState, HEARTBEAT_TIMEOUT};
+
+/// How often a rolling restart rechecks a busy worker, besides on each lease release
+const RESTART_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+pub mod capacity;
+pub mod demand;
+pub mod explain;
+pub mod handlers;
+pub mod pending;
+pub mod queue;
+
+use capacity::HostResources;
+use demand::{Demand, DemandGuard};
+use handlers::HandlerRegistry;
+use pending::PendingQueue;
+use queue::TaskQueue;
+
+/// Pool a worker belongs to, from its ID (e.g., "gpu_workers-1" -> "gpu_workers").
+/// Pool names can't contain '-' (`WorkerPoolConfig::validate`).
+pub fn pool_name(worker_id: &str) -> &str {
+    worker_id.split('-').next().unwrap_or("default")
+}
+
+/// Worker counts for a pool, as reported by `/status`
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct PoolStatus {
+    pub name: String,
+    pub workers: usize,
+    pub idle: usize,
+    pub min_idle: usize,
+    pub min_count: Option<usize>,
+    pub max_count: Option<usize>,
+}
+
+/// A pool resized at runtime, as reported by `/admin/pools/{name}/scale`
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct PoolScale {
+    pub pool: String,
+    /// Workers before resizing
+    pub previous: usize,
+    pub count: usize,
+    /// Workers spawned, ready to take tasks
+    pub added: Vec<String>,
+    /// Workers taken out of service, draining in the background
+    pub retiring: Vec<String>,
+}
+
+/// IDs of workers being spawned to grow their pool. Growth that runs without
+/// the workers lock takes its IDs here, so growth running at the same time
+/// numbers workers apart.
+#[derive(Default)]
+struct Growing(std::sync::Mutex<HashSet<String>>);
+
+/// A worker ID taken for growing a pool, given back when dropped
+struct Reservation {
+    growing: Arc<Growing>,
+    worker_id: String,
+    pool_idx: usize,
+}
+
+impl Growing {
+    /// Take the ID after the highest of the pool's workers and reservations
+    fn reserve(self: &Arc<Self>, workers: &[WorkerHandle], pool: &str) -> Reservation {
+        let mut ids = self.0.lock().unwrap();
+        let pool_idx = workers
+            .iter()
+            .map(|h| h.worker.id.as_str())
+            .chain(ids.iter().map(String::as_str))
+            .filter(|id| pool_name(id) == pool)
+            .filter_map(|id| id.rsplit('-').next()?.parse::<usize>().ok())
+            .max()
+            .map_or(0, |max| max + 1);
+        let worker_id = format!("{}-{}", pool, pool_idx);
+        ids.insert(worker_id.clone());
+        Reservation {
+            growing: Arc::clone(self),
+            worker_id,
+            pool_idx,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.lock().unwrap().is_empty()
+    }
+}
+
+impl Drop for Reservation {
+    fn drop(&mut self) {
+        self.growing.0.lock().unwrap().remove(&self.worker_id);
+    }
+}
+
+/// Why a pool couldn't be resized
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScaleError {
+    UnknownPool(String),
+    /// The count is outside the pool's `min_count`/`max_count`
+    OutOfBounds(String),
+    /// A worker failed to start; those that did are kept
+    SpawnFailed(String),
+}
+
+/// Per-worker state, as reported by `/admin/workers`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WorkerStatus {
+    pub id: String,
+    pub pool: String,
+    pub pid: u32,
+    pub state: String,
+    pub tasks_completed: u32,
+    pub memory_mb: u64,
+    /// Tasks queued inside the worker, if it reports them
+    pub queue_depth: Option<u32>,
+}
+
+/// Which scheduling pass picked a worker
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelectionPass {
+    /// An idle worker of the right type (first fit)
+    Idle,
+    /// A busy worker with spare capacity; the task queues behind its current work
+    Busy,
+    /// A worker held in its pool's min_idle reserve, or a GPU worker taking a CPU
+    /// task. Frequent fallbacks suggest the pools are undersized.
+    Fallback,
+    /// A worker of the route's overflow pool, running the task without its GPUs
+    Overflow,
+}
+
+impl SelectionPass {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SelectionPass::Idle => "idle",
+            SelectionPass::Busy => "busy",
+            SelectionPass::Fallback => "fallback",
+            SelectionPass::Overflow => "overflow",
+        }
+    }
+}
+
+/// Hard constraints on the workers a task may run on, applied in every
+/// selection pass, and the session it would rather stay with
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Placement<'a> {
+    /// Only this pool's workers (a tenant's dedicated pool)
+    pub pool: Option<&'a str>,
+    /// Never these pools' workers (other tenants' dedicated pools)
+    pub excluded_pools: Option<&'a HashSet<String>>,
+    /// Only workers bound to this physical GPU (X-Neutrino-Gpu-Affinity)
+    pub gpu_device: Option<usize>,
+    /// Session affinity key: within a pass, the candidate ranked highest for
+    /// this key is picked instead of following the scheduling strategy
+    pub affinity: Option<&'a str>,
+}
+
+impl Placement<'_> {
+    fn allows(&self, worker: &crate::worker::Worker) -> bool {
+        self.pool.is_none_or(|pool| pool_name(&worker.id) == pool)
+            && self
+                .excluded_pools
+                .is_none_or(|excluded| !excluded.contains(pool_name(&worker.id)))
+            && self
+                .gpu_device
+                .is_none_or(|device| worker.gpu_devices.contains(&device))
+    }
+}
+
+/// Worker chosen for a task, and how
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorkerSelection {
+    pub index: usize,
+    pub pass: SelectionPass,
+    /// The worker's free (cpus, gpus, memory_gb) at selection time, before the task
+    pub headroom: (f64, f64, f64),
+}
+
+/// How many more tasks needing `needs` the worker has room for (fractionally),
+/// the weight of weighted random selection. Tasks needing nothing are weighed
+/// by the worker's free CPUs.
+fn capacity_weight(
+    worker: &crate::worker::Worker,
+    needs: &crate::protocol::ResourceRequirements,
+) -> f64 {
+    let (cpus, gpus, memory_gb) = worker.available_resources();
+    [
+        (cpus, needs.num_cpus),
+        (gpus, needs.num_gpus),
+        (memory_gb, needs.memory_gb),
+    ]
+    .into_iter()
+    .filter(|&(_, needed)| needed > 0.0)
+    .map(|(available, needed)| available / needed)
+    .reduce(f64::min)
+    .unwrap_or(cpus)
+    .max(0.0)
+}
+
+/// Fraction of a worker's most allocated resource in use (0.0 - 1.0)
+fn worker_load(worker: &crate::worker::Worker) -> f64 {
+    let allocation = &worker.allocation;
+    [
+        (allocation.allocated_cpus, worker.capabilities.num_cpus),
+        (allocation.allocated_gpus, worker.capabilities.num_gpus),
+        (
+            allocation.allocated_memory_gb,
+            worker.capabilities.memory_gb,
+        ),
+    ]
+    .into_iter()
+    .filter(|&(_, capacity)| capacity > 0.0)
+    .map(|(allocated, capacity)| allocated / capacity)
+    .fold(0.0, f64::max)
+}
+
+/// Rendezvous hash of a session key with a worker ID. The worker with the
+/// highest score for a key gets its tasks, so a session stays on one worker
+/// and only the sessions of a worker that goes away move elsewhere. Worker IDs
+/// survive recycling, so a replacement picks up its predecessor's sessions.
+fn affinity_score(key: &str, worker_id: &str) -> u64 {
+    use std::hash::{Hash, Hasher};
+    // Fixed keys, so every selection ranks workers the same way
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    key.hash(&mut hasher);
+    worker_id.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Pick one of the candidates with probability proportional to its weight
+/// (uniformly if every weight is zero)
+fn weighted_choice(candidates: impl Iterator<Item = (usize, f64)>) -> Option<usize> {
+    let candidates: Vec<(usize, f64)> = candidates.collect();
+    let mut rng = rand::thread_rng();
+    let total: f64 = candidates.iter().map(|&(_, weight)| weight).sum();
+    if total <= 0.0 {
+        return (!candidates.is_empty()).then(|| candidates[rng.gen_range(0..candidates.len())].0);
+    }
+
+    let mut point = rng.gen::<f64>() * total;
+    for &(current, weight) in &candidates {
+        if point < weight {
+            return Some(current);
+        }
+        point -= weight;
+    }
+    candidates.last().map(|&(current, _)| current)
+}
+
+/// Count idle workers per pool
+fn idle_counts(workers: &[WorkerHandle]) -> HashMap<&str, usize> {
+    let mut counts = HashMap::new();
+    for handle in workers {
+        let count = counts.entry(pool_name(&handle.worker.id)).or_insert(0);
+        if handle.worker.state == WorkerState::Idle {
+            *count += 1;
+        }
+    }
+    counts
+}
+
+/// Workers to add per pool so each autoscaled pool gets back to its `min_idle`
+/// reserve, without growing past `max_count`
+fn scale_up_plan(pools: &[WorkerPoolConfig], workers: &[WorkerHandle]) -> Vec<(String, usize)> {
+    let idle = idle_counts(workers);
+
+    pools
+        .iter()
+        .filter_map(|pool| {
+            let max_count = pool.max_count?;
+            let current = workers
+                .iter()
+                .filter(|h| pool_name(&h.worker.id) == pool.name)
+                .count();
+            let idle = idle.get(pool.name.as_str()).copied().unwrap_or(0);
+
+            let wanted = pool.min_idle.saturating_sub(idle);
+            let allowed = max_count.saturating_sub(current);
+            let add = wanted.min(allowed);
+            (add > 0).then(|| (pool.name.clone(), add))
+        })
+        .collect()
+}
+
+/// A pool resize decided from its load
+#[derive(Debug, PartialEq)]
+enum ScaleStep {
+    /// Add a worker to the pool
+    Add(String),
+    /// Retire the idle worker at this index
+    Retire(usize),
+}
+
+/// One step per autoscaled pool from its load: a worker is added while the
+/// pool's average utilization is at `scale_up_utilization` or work is queued
+/// for it (inside its workers, or `waiting` for a worker the pool could give
+/// it when none of the pool's is idle), up to `max_count`. A pool at or below
+/// `scale_down_utilization` with nothing queued or waiting retires its highest-numbered
+/// idle worker, down to `min_count` and without dipping into `min_idle`.
+fn load_scale_plan(
+    pools: &[WorkerPoolConfig],
+    workers: &[WorkerHandle],
+    waiting: &HashMap<String, usize>,
+) -> Vec<ScaleStep> {
+    let idle = idle_counts(workers);
+
+    pools
+        .iter()
+        .filter_map(|pool| {
+            let members: Vec<(usize, &crate::worker::Worker)> = workers
+                .iter()
+                .enumerate()
+                .filter(|(_, h)| pool_name(&h.worker.id) == pool.name)
+                .map(|(idx, h)| (idx, &h.worker))
+                .collect();
+            let current = members.len();
+            let idle = idle.get(pool.name.as_str()).copied().unwrap_or(0);
+            let waiting = waiting.get(&pool.name).copied().unwrap_or(0);
+
+            let utilization = if current == 0 {
+                0.0
+            } else {
+                members.iter().map(|(_, w)| worker_load(w)).sum::<f64>() / current as f64
+            };
+            let queued = members
+                .iter()
+                .map(|(_, w)| w.queue_depth.unwrap_or(0) as usize)
+                .sum::<usize>()
+                + if idle == 0 { waiting } else { 0 };
+
+            if let Some(max_count) = pool.max_count {
+                if current < max_count && (utilization >= pool.scale_up_utilization || queued > 0) {
+                    return Some(ScaleStep::Add(pool.name.clone()));
+                }
+            }
+
+            let min_count = pool.min_count?;
+            if current <= min_count
+                || utilization > pool.scale_down_utilization
+                || queued > 0
+                || waiting > 0
+                || idle <= pool.min_idle
+            {
+                return None;
+            }
+            members
+                .iter()
+                .filter(|(_, w)| w.state == WorkerState::Idle)
+                .max_by_key(|(_, w)| {
+                    w.id.rsplit('-')
+                        .next()
+                        .and_then(|n| n.parse::<usize>().ok())
+                })
+                .map(|&(idx, _)| ScaleStep::Retire(idx))
+        })
+        .collect()
+}
+
+/// Indices of idle workers to recycle because their pool's summed memory exceeds
+/// `max_total_memory_mb`: the highest-memory idle worker of each such pool.
+/// Workers already in `marked` are being recycled and don't count toward the total.
+fn pool_memory_recycle_plan(
+    pools: &[WorkerPoolConfig],
+    workers: &[WorkerHandle],
+    marked: &[usize],
+) -> Vec<usize> {
+    pools
+        .iter()
+        .filter_map(|pool| {
+            let cap = pool.max_total_memory_mb?;
+            let members = workers
+                .iter()
+                .enumerate()
+                .filter(|(idx, h)| pool_name(&h.worker.id) == pool.name && !marked.contains(idx));
+
+            let total: u64 = members
+                .clone()
+                .map(|(_, h)| h.worker.current_memory_mb)
+                .sum();
+            if total <= cap {
+                return None;
+            }
+
+            let (idx, handle) = members
+                .filter(|(_, h)| h.worker.state == WorkerState::Idle)
+                .max_by_key(|(_, h)| h.worker.current_memory_mb)?;
+            info!(
+                "Pool '{}' uses {} MB (cap {} MB), recycling its largest idle worker {} ({} MB)",
+                pool.name, total, cap, handle.worker.id, handle.worker.current_memory_mb
+            );
+            Some(idx)
+        })
+        .collect()
+}
 
 /// Orchestrator manages a pool of worker processes and distributes tasks
 pub struct Orchestrator {
@@ -13,16 +414,56 @@ pub struct Orchestrator {
     workers: Arc<RwLock<Vec<WorkerHandle>>>,
     next_worker_index: Arc<RwLock<usize>>,
     monitoring_task: Arc<RwLock<Option<JoinHandle<()>>>>,
+    metrics: Arc<Metrics>,
+    handlers: Arc<HandlerRegistry>,
+    task_queue: Arc<TaskQueue>,
+    /// Tasks holding worker resources
+    leases: Arc<Leases>,
+    /// Tasks waiting for capacity, when configured
+    pending_tasks: Option<Arc<PendingQueue>>,
+    /// State the previous run saved on shutdown, when snapshots are configured
+    last_snapshot: Option<Snapshot>,
+    /// A rolling restart is in progress
+    restarting: Arc<AtomicBool>,
+    /// Workers being spawned to grow their pool
+    growing: Arc<Growing>,
+    /// Tasks waiting for a worker, by the pools that could run them
+    demand: Arc<Demand>,
 }
 
 impl Orchestrator {
     /// Create a new orchestrator with the given configuration
     pub fn new(config: Config) -> Self {
         Self {
-            config,
             workers: Arc::new(RwLock::new(Vec::new())),
             next_worker_index: Arc::new(RwLock::new(0)),
             monitoring_task: Arc::new(RwLock::new(None)),
+            metrics: Arc::new(Metrics::new()),
+            handlers: Arc::new(HandlerRegistry::new()),
+            task_queue: Arc::new(TaskQueue::new(Some(Duration::from_secs(
+                config.orchestrator.tasks.priority_aging_secs,
+            )))),
+            leases: Arc::new(Leases::default()),
+            pending_tasks: config
+                .orchestrator
+                .tasks
+                .pending_queue
+                .as_ref()
+                .map(|pending| {
+                    Arc::new(PendingQueue::new(
+                        pending.max_depth,
+                        Duration::from_secs(pending.max_wait_secs),
+                    ))
+                }),
+            last_snapshot: config
+                .orchestrator
+                .snapshot_path
+                .as_deref()
+                .and_then(|path| Snapshot::load(path.as_ref())),
+            restarting: Arc::new(AtomicBool::new(false)),
+            growing: Arc::new(Growing::default()),
+            demand: Arc::new(Demand::default()),
+            config,
         }
     }
 
@@ -31,48 +472,51 @@ impl Orchestrator {
         let worker_pools = self.config.effective_worker_pools();
         let total_workers: usize = worker_pools.iter().map(|p| p.count).sum();
 
-        info!("Starting orchestrator with {} workers across {} pools",
-              total_workers, worker_pools.len());
+        info!(
+            "Starting orchestrator with {} workers across {} pools",
+            total_workers,
+            worker_pools.len()
+        );
+
+        let host = HostResources::detect();
+        self.check_host_capacity(&host)?;
+        self.check_gpu_devices(&host)?;
+
+        let wire_format = match std::env::var(WireFormat::ENV_VAR) {
+            Ok(value) => value.parse()?,
+            Err(_) => self.config.orchestrator.worker.wire_format,
+        };
+        if wire_format != WireFormat::Msgpack {
+            warn!(
+                "Worker protocol uses {} framing (debugging only)",
+                wire_format.as_str()
+            );
+        }
+        protocol::set_wire_format(wire_format);
 
         let mut workers = self.workers.write().await;
 
         // Spawn workers for each pool
         for pool in &worker_pools {
-            info!("Spawning pool '{}': {} workers with cpus={}, gpus={}, mem={}GB",
-                  pool.name, pool.count,
-                  pool.resources.num_cpus, pool.resources.num_gpus, pool.resources.memory_gb);
+            info!(
+                "Spawning pool '{}': {} workers with cpus={}, gpus={}, mem={}GB",
+                pool.name,
+                pool.count,
+                pool.resources.num_cpus,
+                pool.resources.num_gpus,
+                pool.resources.memory_gb
+            );
 
             for pool_idx in 0..pool.count {
                 let worker_id = format!("{}-{}", pool.name, pool_idx);
                 info!("Spawning worker {}", worker_id);
 
-                // Assign GPU devices for this worker
-                let gpu_devices = if !pool.gpu_devices.is_empty() && pool.resources.num_gpus > 0.0 {
-                    // Round-robin assignment if multiple GPUs available
-                    let gpu_idx = pool_idx % pool.gpu_devices.len();
-                    vec![pool.gpu_devices[gpu_idx]]
-                } else {
-                    vec![]
-                };
-
-                match WorkerHandle::spawn(
-                    worker_id.clone(),
-                    &self.config.orchestrator.app_module,
-                    pool.resources.clone(),
-                    &gpu_devices,
-                ).await {
-                    Ok(mut handle) => {
-                        // Wait for worker to be ready
-                        if let Err(e) = handle.wait_ready().await {
-                            warn!("Worker {} failed to become ready: {}", worker_id, e);
-                            continue;
-                        }
-                        info!("Worker {} is ready", worker_id);
-                        workers.push(handle);
-                    }
-                    Err(e) => {
-                        warn!("Failed to spawn worker {}: {}", worker_id, e);
-                    }
+                // Failures are logged by spawn_pool_worker
+                if let Ok(handle) =
+                    Self::spawn_pool_worker(&worker_id, pool_idx, pool, &self.config).await
+                {
+                    info!("Worker {} is ready", worker_id);
+                    workers.push(handle);
                 }
             }
         }
@@ -86,6 +530,8 @@ impl Orchestrator {
             return Err("No workers could be started".into());
         }
 
+        self.handlers.refresh(&workers);
+
         // Drop the write lock before starting monitoring
         drop(workers);
 
@@ -124,80 +570,185 @@ impl Orchestrator {
 
     /// Find a worker with sufficient resources for the given task requirements.
     /// Uses round-robin starting point but checks resource capacity.
-    /// Prioritizes workers with matching resource profiles (GPU vs CPU).
+    /// Prioritizes workers with matching resource profiles (GPU vs CPU), then
+    /// workers with shorter internal queues (or, per the scheduling strategy,
+    /// picks at random by spare capacity, spreads, or packs). When no worker can take the task,
+    /// falls back to `overflow_pool` (if given) without the GPU requirement.
+    /// Workers outside `placement` are never considered.
     pub async fn find_worker_with_resources(
         &self,
         requirements: &crate::protocol::ResourceRequirements,
-    ) -> Option<usize> {
+        overflow_pool: Option<&str>,
+        placement: &Placement<'_>,
+    ) -> Option<WorkerSelection> {
         let workers = self.workers.read().await;
         if workers.is_empty() {
             return None;
         }
 
-        let mut index = self.next_worker_index.write().await;
+        // Weighted random selection keeps no shared position, so concurrent
+        // selections don't contend on it
+        let strategy = self.config.orchestrator.scheduling;
+        let mut index = match strategy {
+            SchedulingStrategy::RoundRobin | SchedulingStrategy::Spread => {
+                Some(self.next_worker_index.write().await)
+            }
+            SchedulingStrategy::WeightedRandom | SchedulingStrategy::Pack => None,
+        };
         let worker_count = workers.len();
-        let start_index = *index;
+        let start_index = index.as_deref().copied().unwrap_or(0);
+
+        let mut select = |current: usize, pass: SelectionPass| {
+            if let Some(index) = index.as_mut() {
+                **index = (current + 1) % worker_count;
+            }
+            Some(WorkerSelection {
+                index: current,
+                pass,
+                headroom: workers[current].worker.available_resources(),
+            })
+        };
 
         // Determine if this is a GPU task
         let is_gpu_task = requirements.num_gpus > 0.0;
 
-        // First pass: Look for idle workers with sufficient resources
-        for offset in 0..worker_count {
-            let current = (start_index + offset) % worker_count;
-            let worker = &workers[current].worker;
-
-            // Skip workers that don't match resource type
-            // GPU tasks should only go to GPU workers, CPU tasks prefer CPU workers
-            let is_gpu_worker = worker.capabilities.num_gpus > 0.0;
-            if is_gpu_task && !is_gpu_worker {
-                continue; // GPU task needs GPU worker
+        // GPU tasks should only go to GPU workers, and CPU tasks to CPU workers
+        // while the placement has any; GPU workers take them only as overflow
+        let has_cpu_workers = workers.iter().any(|handle| {
+            placement.allows(&handle.worker) && handle.worker.capabilities.num_gpus == 0.0
+        });
+        let matches_type = |worker: &crate::worker::Worker| {
+            if is_gpu_task {
+                worker.capabilities.num_gpus > 0.0
+            } else {
+                !has_cpu_workers || worker.capabilities.num_gpus == 0.0
             }
+        };
 
-            // Check if worker is idle and has capacity
-            if worker.state == WorkerState::Idle && worker.has_capacity(requirements) {
-                *index = (current + 1) % worker_count;
-                return Some(current);
-            }
-        }
+        // Overflow onto a GPU pool needs the consent of its allow_overflow_from
+        // and of some CPU pool's allow_overflow_to
+        let pools = self.config.effective_worker_pools();
+        let cpu_pools: Vec<&WorkerPoolConfig> = pools
+            .iter()
+            .filter(|p| p.resources.num_gpus == 0.0)
+            .collect();
+        let accepts_overflow = |worker: &crate::worker::Worker| {
+            let pool = pool_name(&worker.id);
+            pools.iter().find(|p| p.name == pool).is_none_or(|target| {
+                cpu_pools.is_empty()
+                    || cpu_pools
+                        .iter()
+                        .any(|from| target.accepts_overflow_from(from))
+            })
+        };
 
-        // Second pass: If no idle workers, check busy workers with capacity
-        // (task will be queued, but we ensure capacity exists)
-        for offset in 0..worker_count {
-            let current = (start_index + offset) % worker_count;
-            let worker = &workers[current].worker;
+        // Idle workers in pools at or below their min_idle reserve are held back
+        // until no other worker can take the task
+        let min_idle: HashMap<&str, usize> = pools
+            .iter()
+            .map(|p| (p.name.as_str(), p.min_idle))
+            .collect();
+        let idle = idle_counts(&workers);
+        let is_reserved = |worker: &crate::worker::Worker| {
+            let pool = pool_name(&worker.id);
+            let reserve = min_idle.get(pool).copied().unwrap_or(0);
+            worker.state == WorkerState::Idle
+                && reserve > 0
+                && idle.get(pool).copied().unwrap_or(0) <= reserve
+        };
 
-            let is_gpu_worker = worker.capabilities.num_gpus > 0.0;
-            if is_gpu_task && !is_gpu_worker {
-                continue;
+        // Among the workers a pass accepts, prefer the shortest internal queue
+        // (as reported in heartbeats); ties go to round-robin order
+        let pick_for = |needs: &crate::protocol::ResourceRequirements,
+                        eligible: &dyn Fn(&crate::worker::Worker) -> bool| {
+            let candidates = (0..worker_count)
+                .map(|offset| (start_index + offset) % worker_count)
+                .filter(|&current| placement.allows(&workers[current].worker))
+                // A worker talks to one task at a time
+                .filter(|&current| !workers[current].connection_busy())
+                .filter(|&current| workers[current].worker.state != WorkerState::Stuck)
+                .filter(|&current| eligible(&workers[current].worker));
+            if let Some(key) = placement.affinity {
+                return candidates
+                    .max_by_key(|&current| affinity_score(key, &workers[current].worker.id));
             }
+            match strategy {
+                SchedulingStrategy::RoundRobin => candidates
+                    .min_by_key(|&current| workers[current].worker.queue_depth.unwrap_or(0)),
+                SchedulingStrategy::WeightedRandom => weighted_choice(
+                    candidates
+                        .map(|current| (current, capacity_weight(&workers[current].worker, needs))),
+                ),
+                SchedulingStrategy::Spread => candidates.min_by(|&a, &b| {
+                    let (a, b) = (&workers[a].worker, &workers[b].worker);
+                    worker_load(a)
+                        .total_cmp(&worker_load(b))
+                        .then(a.queue_depth.unwrap_or(0).cmp(&b.queue_depth.unwrap_or(0)))
+                }),
+                // Ties go to the lowest index, so the same workers fill up first
+                SchedulingStrategy::Pack => candidates.min_by(|&a, &b| {
+                    worker_load(&workers[b].worker)
+                        .total_cmp(&worker_load(&workers[a].worker))
+                        .then(a.cmp(&b))
+                }),
+            }
+        };
+        let pick =
+            |eligible: &dyn Fn(&crate::worker::Worker) -> bool| pick_for(requirements, eligible);
 
-            if worker.has_capacity(requirements) {
-                *index = (current + 1) % worker_count;
-                return Some(current);
+        // First pass: Look for idle workers with sufficient resources (packing
+        // skips it, filling partly used workers before idle ones)
+        if strategy != SchedulingStrategy::Pack {
+            if let Some(current) = pick(&|worker| {
+                matches_type(worker)
+                    && worker.state == WorkerState::Idle
+                    && worker.has_capacity(requirements)
+                    && !is_reserved(worker)
+            }) {
+                return select(current, SelectionPass::Idle);
             }
         }
 
-        // Third pass: If strict GPU matching failed, allow CPU-only tasks on GPU workers
-        // (GPU workers can handle CPU tasks, just not optimal)
-        if !is_gpu_task {
-            for offset in 0..worker_count {
-                let current = (start_index + offset) % worker_count;
-                let worker = &workers[current].worker;
+        // Second pass: If no idle workers, check busy workers with capacity
+        // (task will be queued, but we ensure capacity exists). Reserved idle
+        // workers are only used once nothing else has capacity.
+        if let Some(current) = pick(&|worker| {
+            matches_type(worker) && worker.has_capacity(requirements) && !is_reserved(worker)
+        }) {
+            return select(current, SelectionPass::Busy);
+        }
+        if let Some(current) =
+            pick(&|worker| matches_type(worker) && worker.has_capacity(requirements))
+        {
+            return select(current, SelectionPass::Fallback);
+        }
 
-                if worker.state == WorkerState::Idle && worker.has_capacity(requirements) {
-                    *index = (current + 1) % worker_count;
-                    return Some(current);
-                }
+        // Third pass: If no CPU worker can take a CPU-only task, overflow onto GPU
+        // workers where the pools allow it (GPU workers can handle CPU tasks,
+        // just not optimal)
+        if !is_gpu_task {
+            if let Some(current) = pick(&|worker| {
+                worker.state == WorkerState::Idle
+                    && worker.has_capacity(requirements)
+                    && accepts_overflow(worker)
+            }) {
+                return select(current, SelectionPass::Fallback);
             }
+            if let Some(current) =
+                pick(&|worker| worker.has_capacity(requirements) && accepts_overflow(worker))
+            {
+                return select(current, SelectionPass::Fallback);
+            }
+        }
 
-            for offset in 0..worker_count {
-                let current = (start_index + offset) % worker_count;
-                let worker = &workers[current].worker;
-
-                if worker.has_capacity(requirements) {
-                    *index = (current + 1) % worker_count;
-                    return Some(current);
-                }
+        // Last resort: the route's general-purpose overflow pool, where the task
+        // runs slower without GPUs instead of failing
+        if let Some(overflow_pool) = overflow_pool {
+            let relaxed = requirements.without_gpus();
+            if let Some(current) = pick_for(&relaxed, &|worker| {
+                pool_name(&worker.id) == overflow_pool && worker.has_capacity(&relaxed)
+            }) {
+                return select(current, SelectionPass::Overflow);
             }
         }
 
@@ -205,16 +756,408 @@ impl Orchestrator {
         None
     }
 
+    /// Get the orchestrator configuration
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Get the in-memory metrics registry
+    pub fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
     /// Get a reference to the worker pool
     pub fn workers(&self) -> Arc<RwLock<Vec<WorkerHandle>>> {
         Arc::clone(&self.workers)
     }
 
+    /// Queue ordering tasks waiting for a worker by priority
+    pub fn task_queue(&self) -> &TaskQueue {
+        &self.task_queue
+    }
+
+    /// Tasks holding worker resources
+    pub fn leases(&self) -> &Leases {
+        &self.leases
+    }
+
+    /// Allocate a task's resources on the selected worker, which is marked busy
+    /// until the lease is released (None if the worker has left the pool or
+    /// is running another task)
+    pub async fn lease_worker(
+        &self,
+        index: usize,
+        task_id: &str,
+        resources: ResourceRequirements,
+    ) -> Option<TaskLease> {
+        let mut workers = self.workers.write().await;
+        workers
+            .get_mut(index)?
+            .lease(&self.workers, &self.leases, task_id, resources)
+    }
+
+    /// Queue of tasks waiting for a worker with capacity, when configured
+    pub fn pending_tasks(&self) -> Option<&PendingQueue> {
+        self.pending_tasks.as_deref()
+    }
+
+    /// Handlers available across workers, kept current as workers are replaced
+    pub fn handler_registry(&self) -> Arc<HandlerRegistry> {
+        Arc::clone(&self.handlers)
+    }
+
+    /// Metrics and worker state saved by the previous run's shutdown
+    pub fn last_snapshot(&self) -> Option<&Snapshot> {
+        self.last_snapshot.as_ref()
+    }
+
+    /// Rebuild the handler registry from the current workers
+    pub async fn refresh_handlers(&self) {
+        self.handlers.refresh(&self.workers.read().await);
+    }
+
+    /// Check that worker pools don't claim more than the host has, per the
+    /// configured overcommit policy
+    fn check_host_capacity(&self, host: &HostResources) -> Result<(), Box<dyn std::error::Error>> {
+        let overcommit = &self.config.orchestrator.overcommit;
+        if overcommit.action == OvercommitAction::Ignore {
+            return Ok(());
+        }
+
+        let violations = capacity::check_overcommit(
+            &self.config.effective_worker_pools(),
+            host,
+            overcommit.max_ratio,
+        );
+        if violations.is_empty() {
+            return Ok(());
+        }
+
+        match overcommit.action {
+            OvercommitAction::Error => Err(format!(
+                "Worker pools over-commit host resources: {}",
+                violations.join("; ")
+            )
+            .into()),
+            _ => {
+                for violation in &violations {
+                    warn!("Over-commitment: {}", violation);
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Check that the GPUs pools are pinned to exist, so a bad `gpu_devices`
+    /// fails here rather than as a CUDA error when a worker loads its model
+    fn check_gpu_devices(&self, host: &HostResources) -> Result<(), Box<dyn std::error::Error>> {
+        if !self.config.orchestrator.validate_gpu_devices {
+            return Ok(());
+        }
+        let pools = self.config.effective_worker_pools();
+        let pins_gpus = pools
+            .iter()
+            .any(|p| p.resources.num_gpus > 0.0 && !p.gpu_devices.is_empty());
+        if host.gpu_indices.is_none() && pins_gpus {
+            warn!("nvidia-smi is not available, so the pools' gpu_devices can't be checked");
+        }
+
+        let violations = capacity::check_gpu_devices(&pools, host);
+        if violations.is_empty() {
+            return Ok(());
+        }
+        Err(format!(
+            "Worker pools reference GPUs the host doesn't have: {}",
+            violations.join("; ")
+        )
+        .into())
+    }
+
+    /// Whether a task would fit on a worker in its placement once `freed`,
+    /// the resources of a task running there, are released. Only workers that
+    /// stop tasks on `CancelTask` qualify; on others the task runs to the end.
+    pub async fn fits_once_released(
+        &self,
+        worker_id: &str,
+        freed: &ResourceRequirements,
+        requirements: &ResourceRequirements,
+        placement: &Placement<'_>,
+    ) -> bool {
+        let workers = self.workers.read().await;
+        let Some(handle) = workers.iter().find(|h| h.worker.id == worker_id) else {
+            return false;
+        };
+        // What it needs beyond what's free now
+        let beyond_free = ResourceRequirements {
+            num_cpus: requirements.num_cpus - freed.num_cpus,
+            num_gpus: requirements.num_gpus - freed.num_gpus,
+            memory_gb: requirements.memory_gb - freed.memory_gb,
+            gpu_memory_gb: requirements.gpu_memory_gb - freed.gpu_memory_gb,
+        };
+        handle.worker.cancellable
+            && placement.allows(&handle.worker)
+            && handle.worker.state != WorkerState::Stuck
+            && handle.worker.has_capacity(&beyond_free)
+    }
+
+    /// Count a task waiting for a worker against the pools that could run it,
+    /// for the autoscaler, until the returned guard is dropped
+    pub fn wait_for_capacity(
+        &self,
+        requirements: &ResourceRequirements,
+        placement: &Placement<'_>,
+    ) -> DemandGuard {
+        let pools = self.config.effective_worker_pools();
+        self.demand.register(&pools, requirements, placement)
+    }
+
     /// Get the number of active workers
     pub async fn worker_count(&self) -> usize {
         self.workers.read().await.len()
     }
 
+    /// Per-pool worker and idle counts alongside each pool's reserve
+    pub async fn pool_status(&self) -> Vec<PoolStatus> {
+        let workers = self.workers.read().await;
+        let idle = idle_counts(&workers);
+
+        self.config
+            .effective_worker_pools()
+            .into_iter()
+            .map(|pool| PoolStatus {
+                workers: workers
+                    .iter()
+                    .filter(|h| pool_name(&h.worker.id) == pool.name)
+                    .count(),
+                idle: idle.get(pool.name.as_str()).copied().unwrap_or(0),
+                min_idle: pool.min_idle,
+                min_count: pool.min_count,
+                max_count: pool.max_count,
+                name: pool.name,
+            })
+            .collect()
+    }
+
+    /// State of every worker, including its reported internal queue depth
+    pub async fn worker_status(&self) -> Vec<WorkerStatus> {
+        self.workers
+            .read()
+            .await
+            .iter()
+            .map(|handle| {
+                let worker = &handle.worker;
+                WorkerStatus {
+                    id: worker.id.clone(),
+                    pool: pool_name(&worker.id).to_string(),
+                    pid: worker.pid,
+                    state: format!("{:?}", worker.state).to_lowercase(),
+                    tasks_completed: worker.tasks_completed,
+                    memory_mb: worker.current_memory_mb,
+                    queue_depth: worker.queue_depth,
+                }
+            })
+            .collect()
+    }
+
+    /// Recent allocation changes of a worker, oldest first (None = no such worker)
+    pub async fn allocation_history(&self, worker_id: &str) -> Option<Vec<AllocationEvent>> {
+        let workers = self.workers.read().await;
+        let handle = workers
+            .iter()
+            .find(|handle| handle.worker.id == worker_id)?;
+        Some(handle.worker.allocation_history.events().cloned().collect())
+    }
+
+    /// Resize a pool to `count` workers at runtime. Added workers are spawned
+    /// together, without holding the workers lock, and are ready when this
+    /// returns; removed ones (idle first, highest-numbered first) are drained
+    /// and shut down in the background like recycled workers.
+    pub async fn scale_pool(&self, name: &str, count: usize) -> Result<PoolScale, ScaleError> {
+        let pools = self.config.effective_worker_pools();
+        let pool = pools
+            .iter()
+            .find(|p| p.name == name)
+            .ok_or_else(|| ScaleError::UnknownPool(name.to_string()))?;
+        if pool.min_count.is_some_and(|min| count < min)
+            || pool.max_count.is_some_and(|max| count > max)
+        {
+            return Err(ScaleError::OutOfBounds(format!(
+                "pool '{}' is bounded to {}..={} workers",
+                name,
+                pool.min_count
+                    .map_or("0".to_string(), |min| min.to_string()),
+                pool.max_count
+                    .map_or("unbounded".to_string(), |max| max.to_string()),
+            )));
+        }
+
+        let mut workers = self.workers.write().await;
+        let members = |workers: &[WorkerHandle]| {
+            workers
+                .iter()
+                .filter(|h| pool_name(&h.worker.id) == name)
+                .count()
+        };
+        let previous = members(&workers);
+        let mut scale = PoolScale {
+            pool: name.to_string(),
+            previous,
+            count: previous,
+            added: Vec::new(),
+            retiring: Vec::new(),
+        };
+
+        if count > previous {
+            info!(
+                "Scaling pool '{}' up from {} to {} workers",
+                name, previous, count
+            );
+            let reserved: Vec<_> = (previous..count)
+                .map(|_| self.growing.reserve(&workers, name))
+                .collect();
+            drop(workers);
+
+            // Spawned all at once and without the workers lock, so tasks keep
+            // being served meanwhile
+            let added = futures_util::future::join_all(reserved.into_iter().map(|reservation| {
+                Self::grow_pool(
+                    reservation,
+                    pool,
+                    &self.workers,
+                    &self.handlers,
+                    &self.config,
+                )
+            }))
+            .await;
+            scale.added = added.into_iter().flatten().collect();
+            scale.count = members(&self.workers.read().await);
+            if scale.added.len() < count - previous {
+                return Err(ScaleError::SpawnFailed(format!(
+                    "pool '{}' reached {} of {} workers",
+                    name, scale.count, count
+                )));
+            }
+            return Ok(scale);
+        }
+        if count < previous {
+            info!(
+                "Scaling pool '{}' down from {} to {} workers",
+                name, previous, count
+            );
+            let mut retire: Vec<usize> = workers
+                .iter()
+                .enumerate()
+                .filter(|(_, h)| pool_name(&h.worker.id) == name)
+                .map(|(idx, _)| idx)
+                .collect();
+            retire.sort_by_key(|&idx| {
+                let worker = &workers[idx].worker;
+                let number = worker
+                    .id
+                    .rsplit('-')
+                    .next()
+                    .and_then(|n| n.parse::<usize>().ok());
+                // Kept from the front: busy workers, then idle, then stuck
+                let rank = match worker.state {
+                    WorkerState::Stuck => 2,
+                    WorkerState::Idle => 1,
+                    _ => 0,
+                };
+                (rank, number)
+            });
+            retire.drain(..count);
+            retire.sort_unstable();
+
+            for idx in retire.into_iter().rev() {
+                let retired = workers.remove(idx);
+                scale.retiring.push(retired.worker.id.clone());
+                let worker_config = self.config.orchestrator.worker.clone();
+                tokio::spawn(async move { Self::retire_worker(retired, &worker_config).await });
+            }
+            scale.retiring.reverse();
+        }
+
+        scale.count = members(&workers);
+        self.handlers.refresh(&workers);
+        Ok(scale)
+    }
+
+    /// Start replacing every worker, one at a time, each once it's idle, so
+    /// new application code rolls out without downtime. Returns the workers to
+    /// be restarted, or None if a rolling restart is already in progress.
+    /// The rollout stops if a replacement fails to start.
+    pub async fn rolling_restart(self: &Arc<Self>) -> Option<Vec<String>> {
+        if self.restarting.swap(true, Ordering::SeqCst) {
+            return None;
+        }
+
+        let worker_ids: Vec<String> = self
+            .workers
+            .read()
+            .await
+            .iter()
+            .map(|h| h.worker.id.clone())
+            .collect();
+        info!("Starting rolling restart of {} worker(s)", worker_ids.len());
+
+        let orchestrator = Arc::clone(self);
+        let restart_ids = worker_ids.clone();
+        tokio::spawn(async move {
+            for worker_id in &restart_ids {
+                if let Err(e) = orchestrator.restart_worker(worker_id).await {
+                    warn!("Rolling restart stopped: {}", e);
+                    break;
+                }
+            }
+            orchestrator.restarting.store(false, Ordering::SeqCst);
+            info!("Rolling restart finished");
+        });
+
+        Some(worker_ids)
+    }
+
+    /// Start a replacement for a worker under the same ID, then once the
+    /// worker is idle, swap the replacement in and retire it. A worker that's
+    /// gone (e.g. recycled meanwhile) is skipped.
+    async fn restart_worker(&self, worker_id: &str) -> Result<(), String> {
+        if !self
+            .workers
+            .read()
+            .await
+            .iter()
+            .any(|h| h.worker.id == worker_id)
+        {
+            debug!("Worker {} left service before its restart", worker_id);
+            return Ok(());
+        }
+        let replacement = Self::spawn_replacement(worker_id, &self.config).await?;
+
+        let old_worker = loop {
+            let released = self.leases.released();
+            tokio::pin!(released);
+            released.as_mut().enable();
+            {
+                let mut workers = self.workers.write().await;
+                let Some(idx) = workers.iter().position(|h| h.worker.id == worker_id) else {
+                    drop(workers);
+                    debug!("Worker {} left service before its restart", worker_id);
+                    Self::retire_worker(replacement, &self.config.orchestrator.worker).await;
+                    return Ok(());
+                };
+                let handle = &workers[idx];
+                if handle.worker.state == WorkerState::Idle && !handle.connection_busy() {
+                    let old_worker = std::mem::replace(&mut workers[idx], replacement);
+                    self.handlers.refresh(&workers);
+                    break old_worker;
+                }
+            }
+            let _ = tokio::time::timeout(RESTART_POLL_INTERVAL, released).await;
+        };
+
+        Self::retire_worker(old_worker, &self.config.orchestrator.worker).await;
+        Ok(())
+    }
+
     /// Shutdown all workers gracefully
     pub async fn shutdown(&self) -> Result<(), Box<dyn std::error::Error>> {
         info!("Shutting down orchestrator");
@@ -227,6 +1170,15 @@ impl Orchestrator {
         }
         drop(monitoring_task);
 
+        // Save what's only in memory while the workers are still listed
+        if let Some(path) = &self.config.orchestrator.snapshot_path {
+            let snapshot = Snapshot::new(self.metrics.handler_stats(), self.worker_status().await);
+            match snapshot.write(path.as_ref()) {
+                Ok(()) => info!("Snapshot written to {}", path),
+                Err(e) => warn!("Failed to write snapshot to {}: {}", path, e),
+            }
+        }
+
         let mut workers = self.workers.write().await;
 
         for worker in workers.iter_mut() {
@@ -244,8 +1196,13 @@ impl Orchestrator {
     /// Start background memory monitoring and worker recycling task
     async fn start_monitoring(&self) {
         let workers = Arc::clone(&self.workers);
+        let handlers = Arc::clone(&self.handlers);
+        let leases = Arc::clone(&self.leases);
+        let growing = Arc::clone(&self.growing);
+        let demand = Arc::clone(&self.demand);
         let config = self.config.clone();
-        let check_interval = Duration::from_secs(config.orchestrator.worker.memory_check_interval_secs);
+        let check_interval =
+            Duration::from_secs(config.orchestrator.worker.memory_check_interval_secs);
 
         info!(
             "Starting memory monitoring task (interval: {} seconds)",
@@ -253,16 +1210,84 @@ impl Orchestrator {
         );
 
         let handle = tokio::spawn(async move {
+            // Recycles in progress; dropped (and so aborted) with the monitor
+            let mut recycles = JoinSet::new();
+            let max_recycles = config.orchestrator.worker.max_concurrent_recycles.max(1);
+            // Connections of workers left in service until their replacement is
+            // ready, by the recycle replacing them
+            let mut replacing = HashMap::new();
+
             loop {
                 tokio::time::sleep(check_interval).await;
+                while let Some(result) = recycles.try_join_next_with_id() {
+                    let id = match result {
+                        Ok((id, ())) => id,
+                        Err(e) => {
+                            warn!("Recycle task failed: {}", e);
+                            e.id()
+                        }
+                    };
+                    replacing.remove(&id);
+                }
+
+                // Refresh internal queue depths of idle workers (busy ones report
+                // while their task runs), all at once and without the workers
+                // lock, so a hung worker holds up neither the others nor tasks
+                let probes: Vec<_> = workers
+                    .read()
+                    .await
+                    .iter()
+                    .filter(|h| h.worker.state == WorkerState::Idle)
+                    .filter_map(|h| h.probe(&leases))
+                    .collect();
+                let heartbeats = futures_util::future::join_all(
+                    probes
+                        .into_iter()
+                        .map(|probe| probe.send(HEARTBEAT_TIMEOUT)),
+                )
+                .await;
 
                 let mut workers_guard = workers.write().await;
                 let mut workers_to_recycle = Vec::new();
+                let being_replaced: Vec<usize> = workers_guard
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, h)| {
+                        replacing
+                            .values()
+                            .any(|connection| Arc::ptr_eq(connection, &h.stream))
+                    })
+                    .map(|(idx, _)| idx)
+                    .collect();
 
                 // Check each worker's memory and recycling thresholds
                 for (idx, worker_handle) in workers_guard.iter_mut().enumerate() {
+                    // Its recycle retires it once the replacement is in
+                    if being_replaced.contains(&idx) {
+                        continue;
+                    }
+                    // A worker whose process died is replaced in its pool
+                    if let Some(status) = worker_handle.exit_status() {
+                        warn!(
+                            "Worker {} exited unexpectedly ({}), replacing it",
+                            worker_handle.worker.id, status
+                        );
+                        worker_handle.worker.state = WorkerState::Stuck;
+                        workers_to_recycle.push(idx);
+                        continue;
+                    }
                     let worker = &mut worker_handle.worker;
 
+                    // Workers left running a timed-out task are replaced right away
+                    if worker.state == WorkerState::Stuck {
+                        info!(
+                            "Worker {} is stuck on a timed-out task, replacing it",
+                            worker.id
+                        );
+                        workers_to_recycle.push(idx);
+                        continue;
+                    }
+
                     // Update memory usage
                     match memory::get_process_memory_mb(worker.pid) {
                         Ok(memory_mb) => {
@@ -302,12 +1327,126 @@ impl Orchestrator {
                     }
                 }
 
-                // Recycle workers (in reverse order to maintain indices)
+                // Keep each pool under its total memory cap
+                let pools = config.effective_worker_pools();
+                let marked = [workers_to_recycle.as_slice(), &being_replaced].concat();
+                workers_to_recycle.extend(pool_memory_recycle_plan(
+                    &pools,
+                    &workers_guard,
+                    &marked,
+                ));
+
+                // An idle worker missing heartbeats in a row is hung (e.g.
+                // deadlocked in a C extension) and replaced, unless a task has
+                // taken it since
+                let max_missed = config.orchestrator.worker.max_missed_heartbeats;
+                for heartbeat in heartbeats {
+                    let Some(idx) = workers_guard
+                        .iter()
+                        .position(|h| Arc::ptr_eq(&h.stream, &heartbeat.connection))
+                    else {
+                        continue;
+                    };
+                    let worker_handle = &mut workers_guard[idx];
+                    let e = match heartbeat.reply {
+                        Ok(reply) => {
+                            reply.apply(&mut worker_handle.worker);
+                            continue;
+                        }
+                        Err(e) => e,
+                    };
+                    let busy = worker_handle.connection_busy();
+                    let worker = &mut worker_handle.worker;
+                    worker.missed_heartbeats += 1;
+                    debug!(
+                        "Worker {} heartbeat failed ({} in a row): {}",
+                        worker.id, worker.missed_heartbeats, e
+                    );
+                    if max_missed > 0
+                        && worker.missed_heartbeats >= max_missed
+                        && worker.state == WorkerState::Idle
+                        && !busy
+                        && !being_replaced.contains(&idx)
+                        && !workers_to_recycle.contains(&idx)
+                    {
+                        warn!(
+                            "Worker {} missed {} heartbeats in a row, replacing it",
+                            worker.id, worker.missed_heartbeats
+                        );
+                        worker.state = WorkerState::Stuck;
+                        workers_to_recycle.push(idx);
+                    }
+                }
+                workers_to_recycle.sort_unstable();
+
+                // Recycle workers in the background (in reverse order to maintain
+                // indices), so a slow respawn doesn't hold up sampling the others.
+                // Healthy workers keep serving until their replacement is ready;
+                // stuck or dead ones are taken out first. Workers past the cap
+                // stay in service until a later check.
                 for &idx in workers_to_recycle.iter().rev() {
-                    if let Err(e) = Self::recycle_worker_at_index(&mut workers_guard, idx, &config).await {
-                        warn!("Failed to recycle worker at index {}: {}", idx, e);
+                    if recycles.len() >= max_recycles {
+                        debug!(
+                            "{} recycle(s) in progress, deferring the rest",
+                            recycles.len()
+                        );
+                        break;
+                    }
+                    if workers_guard[idx].worker.state == WorkerState::Stuck {
+                        let old_worker = workers_guard.remove(idx);
+                        recycles.spawn(Self::recycle_worker(
+                            old_worker,
+                            Arc::clone(&workers),
+                            Arc::clone(&handlers),
+                            config.clone(),
+                        ));
+                    } else {
+                        let handle = &workers_guard[idx];
+                        let connection = Arc::clone(&handle.stream);
+                        let recycle = recycles.spawn(Self::replace_in_service(
+                            handle.worker.id.clone(),
+                            Arc::clone(&connection),
+                            Arc::clone(&workers),
+                            Arc::clone(&handlers),
+                            config.clone(),
+                        ));
+                        replacing.insert(recycle.id(), connection);
                     }
                 }
+
+                // Grow autoscaled pools whose idle workers fell below min_idle,
+                // spawning in the background like recycles. Workers out for
+                // recycling or being added are coming, so wait for them.
+                if recycles.is_empty() && growing.is_empty() {
+                    let mut growth = Self::scale_up_idle_reserve(&workers_guard, &config, &growing);
+
+                    // Then resize pools to their load, once the reserve is back
+                    if growth.is_empty() {
+                        let (added, retired) = Self::autoscale(
+                            &mut workers_guard,
+                            &config,
+                            &demand.by_pool(),
+                            &growing,
+                        );
+                        growth = added;
+                        for retired in retired {
+                            let worker_config = config.orchestrator.worker.clone();
+                            recycles.spawn(async move {
+                                Self::retire_worker(retired, &worker_config).await
+                            });
+                        }
+                    }
+                    for (reservation, pool) in growth {
+                        let (workers, handlers, config) =
+                            (Arc::clone(&workers), Arc::clone(&handlers), config.clone());
+                        recycles.spawn(async move {
+                            Self::grow_pool(reservation, &pool, &workers, &handlers, &config).await;
+                        });
+                    }
+                }
+
+                // Replacement or added workers may provide a different set of handlers
+                handlers.refresh(&workers_guard);
             }
         });
 
@@ -315,22 +1454,114 @@ impl Orchestrator {
         *monitoring_task = Some(handle);
     }
 
-    /// Recycle a worker at a specific index
-    async fn recycle_worker_at_index(
-        workers: &mut Vec<WorkerHandle>,
-        idx: usize,
-        config: &crate::config::Config,
-    ) -> Result<(), String> {
-        if idx >= workers.len() {
-            return Err("Invalid worker index".into());
+    /// Drain a worker's pending work (if configured), then shut it down.
+    /// A worker that fails to drain in time is shut down anyway; a stuck one
+    /// is killed.
+    async fn retire_worker(mut worker: WorkerHandle, config: &crate::config::WorkerConfig) {
+        let worker_id = worker.worker.id.clone();
+
+        // A stuck worker wouldn't answer a drain or shutdown request
+        if worker.worker.state == WorkerState::Stuck {
+            if let Err(e) = worker.kill() {
+                warn!("Error killing stuck worker {}: {}", worker_id, e);
+            }
+            return;
+        }
+
+        if config.drain_timeout_secs > 0 {
+            worker.worker.state = WorkerState::Recycling;
+            if let Err(e) = worker
+                .drain(Duration::from_secs(config.drain_timeout_secs))
+                .await
+            {
+                warn!("Error draining worker {}: {}", worker_id, e);
+            }
+        }
+
+        if let Err(e) = worker.shutdown().await {
+            warn!("Error shutting down old worker {}: {}", worker_id, e);
         }
+    }
 
-        // Get the worker to be recycled
-        let old_worker = workers.remove(idx);
+    /// Recycle a worker taken out of service, adding its replacement back
+    /// once ready. Runs without holding the workers lock.
+    async fn recycle_worker(
+        old_worker: WorkerHandle,
+        workers: Arc<RwLock<Vec<WorkerHandle>>>,
+        handlers: Arc<HandlerRegistry>,
+        config: crate::config::Config,
+    ) {
         let worker_id = old_worker.worker.id.clone();
-        let pool_name = worker_id.split('-').next().unwrap_or("default");
+        match Self::replace_worker(old_worker, &config).await {
+            Ok(new_worker) => {
+                let mut workers = workers.write().await;
+                workers.push(new_worker);
+                // The replacement may provide a different set of handlers
+                handlers.refresh(&workers);
+            }
+            Err(e) => warn!("Failed to recycle worker {}: {}", worker_id, e),
+        }
+    }
 
+    /// Replace a worker that is still serving: its replacement is spawned
+    /// first and swapped into its place once ready, and only then is the old
+    /// worker drained and shut down, so the pool never runs a worker short.
+    /// Runs without holding the workers lock.
+    async fn replace_in_service(
+        worker_id: String,
+        connection: Arc<tokio::sync::Mutex<tokio::net::UnixStream>>,
+        workers: Arc<RwLock<Vec<WorkerHandle>>>,
+        handlers: Arc<HandlerRegistry>,
+        config: crate::config::Config,
+    ) {
         info!("Recycling worker {}", worker_id);
+        let replacement = match Self::spawn_replacement(&worker_id, &config).await {
+            Ok(replacement) => replacement,
+            Err(e) => {
+                warn!("Failed to recycle worker {}: {}", worker_id, e);
+                return;
+            }
+        };
+
+        let mut workers = workers.write().await;
+        let Some(idx) = workers
+            .iter()
+            .position(|h| Arc::ptr_eq(&h.stream, &connection))
+        else {
+            drop(workers);
+            debug!(
+                "Worker {} left service before its replacement was ready",
+                worker_id
+            );
+            Self::retire_worker(replacement, &config.orchestrator.worker).await;
+            return;
+        };
+        let old_worker = std::mem::replace(&mut workers[idx], replacement);
+        // The replacement may provide a different set of handlers
+        handlers.refresh(&workers);
+        drop(workers);
+
+        Self::retire_worker(old_worker, &config.orchestrator.worker).await;
+    }
+
+    /// Retire a worker and spawn its replacement under the same ID
+    async fn replace_worker(
+        old_worker: WorkerHandle,
+        config: &crate::config::Config,
+    ) -> Result<WorkerHandle, String> {
+        info!("Recycling worker {}", old_worker.worker.id);
+        let worker_id = old_worker.worker.id.clone();
+        // Drain and gracefully shutdown old worker
+        Self::retire_worker(old_worker, &config.orchestrator.worker).await;
+        Self::spawn_replacement(&worker_id, config).await
+    }
+
+    /// Spawn a worker to take over `worker_id`, in the same pool and slot
+    async fn spawn_replacement(
+        worker_id: &str,
+        config: &crate::config::Config,
+    ) -> Result<WorkerHandle, String> {
+        let pool_name = pool_name(worker_id);
 
         // Find the pool configuration for this worker
         let worker_pools = config.effective_worker_pools();
@@ -339,19 +1570,29 @@ impl Orchestrator {
             .find(|p| p.name == pool_name)
             .ok_or_else(|| format!("Pool {} not found", pool_name))?;
 
-        // Gracefully shutdown old worker
-        let mut old_worker = old_worker;
-        if let Err(e) = old_worker.shutdown().await {
-            warn!("Error shutting down old worker {}: {}", worker_id, e);
-        }
-
         // Extract the pool index from the worker ID (e.g., "default-1" -> 1)
         let pool_idx: usize = worker_id
             .split('-')
-            .last()
+            .next_back()
             .and_then(|s| s.parse().ok())
             .unwrap_or(0);
 
+        info!("Spawning replacement worker {}", worker_id);
+        let new_worker = Self::spawn_pool_worker(worker_id, pool_idx, pool, config).await?;
+        info!("Replacement worker {} is ready", worker_id);
+        Ok(new_worker)
+    }
+
+    /// Spawn a worker for a pool and wait for it to become ready (and warmed
+    /// up, when the pool has a warm-up handler). Once serving, call it only
+    /// without holding the workers lock: a warm-up can take up to the pool's
+    /// `warmup_timeout_secs`, and dispatch would stall for all of it.
+    async fn spawn_pool_worker(
+        worker_id: &str,
+        pool_idx: usize,
+        pool: &WorkerPoolConfig,
+        config: &crate::config::Config,
+    ) -> Result<WorkerHandle, String> {
         // Determine GPU devices for the new worker
         let gpu_devices = if !pool.gpu_devices.is_empty() && pool.resources.num_gpus > 0.0 {
             let gpu_idx = pool_idx % pool.gpu_devices.len();
@@ -360,36 +1601,1083 @@ impl Orchestrator {
             vec![]
         };
 
-        // Spawn replacement worker with same configuration
-        info!("Spawning replacement worker {}", worker_id);
-        let mut new_worker = match WorkerHandle::spawn(
-            worker_id.clone(),
+        let mut new_worker = WorkerHandle::spawn(
+            worker_id.to_string(),
             &config.orchestrator.app_module,
             pool.resources.clone(),
             &gpu_devices,
+            pool.cpuset.as_deref(),
+            &config.orchestrator.worker,
         )
         .await
-        {
-            Ok(worker) => worker,
-            Err(e) => {
-                let err_msg = format!("Failed to spawn replacement worker {}: {}", worker_id, e);
+        .map_err(|e| {
+            let err_msg = format!("Failed to spawn worker {}: {}", worker_id, e);
+            warn!("{}", err_msg);
+            err_msg
+        })?;
+
+        new_worker.wait_ready().await.map_err(|e| {
+            let err_msg = format!("Worker {} failed to become ready: {}", worker_id, e);
+            warn!("{}", err_msg);
+            err_msg
+        })?;
+
+        if let Some(handler) = &pool.warmup_handler {
+            info!("Warming up worker {} with {}", worker_id, handler);
+            let timeout = Duration::from_secs(pool.warmup_timeout_secs);
+            if let Err(e) = new_worker.warm_up(handler, timeout).await {
+                let err_msg = format!("Worker {} failed to warm up: {}", worker_id, e);
                 warn!("{}", err_msg);
+                if new_worker.kill().is_err() {
+                    warn!(
+                        "Failed to kill worker {} after its failed warm-up",
+                        worker_id
+                    );
+                }
                 return Err(err_msg);
             }
+        }
+
+        Ok(new_worker)
+    }
+
+    /// Reserve workers for autoscaled pools whose idle count is below
+    /// `min_idle`, to be spawned without holding the workers lock
+    fn scale_up_idle_reserve(
+        workers: &[WorkerHandle],
+        config: &crate::config::Config,
+        growing: &Arc<Growing>,
+    ) -> Vec<(Reservation, WorkerPoolConfig)> {
+        let pools = config.effective_worker_pools();
+        let mut reserve = Vec::new();
+
+        for (name, add) in scale_up_plan(&pools, workers) {
+            let Some(pool) = pools.iter().find(|p| p.name == name) else {
+                continue;
+            };
+            info!(
+                "Pool '{}' is below its idle reserve of {}, adding {} worker(s)",
+                name, pool.min_idle, add
+            );
+            for _ in 0..add {
+                reserve.push((growing.reserve(workers, &pool.name), pool.clone()));
+            }
+        }
+        reserve
+    }
+
+    /// Add or remove a worker per autoscaled pool according to its load.
+    /// Returns the workers reserved to be spawned without holding the workers
+    /// lock, and those taken out of service, to be retired.
+    fn autoscale(
+        workers: &mut Vec<WorkerHandle>,
+        config: &crate::config::Config,
+        waiting: &HashMap<String, usize>,
+        growing: &Arc<Growing>,
+    ) -> (Vec<(Reservation, WorkerPoolConfig)>, Vec<WorkerHandle>) {
+        let pools = config.effective_worker_pools();
+        let mut add = Vec::new();
+        let mut retire = Vec::new();
+
+        for step in load_scale_plan(&pools, workers, waiting) {
+            match step {
+                ScaleStep::Add(name) => {
+                    let Some(pool) = pools.iter().find(|p| p.name == name) else {
+                        continue;
+                    };
+                    info!("Pool '{}' is under load, adding a worker", name);
+                    add.push((growing.reserve(workers, &pool.name), pool.clone()));
+                }
+                ScaleStep::Retire(idx) => retire.push(idx),
+            }
+        }
+
+        retire.sort_unstable();
+        let retired = retire
+            .into_iter()
+            .rev()
+            .map(|idx| {
+                let handle = workers.remove(idx);
+                info!(
+                    "Pool '{}' is underused, retiring worker {}",
+                    pool_name(&handle.worker.id),
+                    handle.worker.id
+                );
+                handle
+            })
+            .collect();
+        (add, retired)
+    }
+
+    /// Spawn the reserved worker for a pool and add it once ready. Runs
+    /// without holding the workers lock. Returns its ID, or None if it failed
+    /// to start.
+    async fn grow_pool(
+        reservation: Reservation,
+        pool: &WorkerPoolConfig,
+        workers: &RwLock<Vec<WorkerHandle>>,
+        handlers: &HandlerRegistry,
+        config: &crate::config::Config,
+    ) -> Option<String> {
+        let worker_id = &reservation.worker_id;
+        let handle = Self::spawn_pool_worker(worker_id, reservation.pool_idx, pool, config)
+            .await
+            .ok()?;
+        info!("Worker {} added to pool '{}'", worker_id, pool.name);
+        let mut workers = workers.write().await;
+        workers.push(handle);
+        // The new worker may provide handlers the others don't
+        handlers.refresh(&workers);
+        Some(worker_id.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{self, Message, ResourceCapabilities};
+    use crate::testing::{mock_worker_handle, spawn_queued_worker};
+    use std::time::Instant;
+
+    #[tokio::test]
+    async fn test_stuck_worker_killed_without_drain() {
+        let (mut handle, _worker_side) =
+            mock_worker_handle("default-0", ResourceCapabilities::default());
+        handle.process = std::process::Command::new("sleep")
+            .arg("30")
+            .spawn()
+            .unwrap();
+        handle.worker.state = WorkerState::Stuck;
+        let pid = handle.process.id();
+
+        // The worker never answers, so draining would wait out the whole timeout
+        let mut config = crate::config::Config::default().orchestrator.worker;
+        config.drain_timeout_secs = 30;
+        tokio::time::timeout(
+            Duration::from_secs(2),
+            Orchestrator::retire_worker(handle, &config),
+        )
+        .await
+        .expect("stuck worker not killed");
+        assert!(!std::path::Path::new(&format!("/proc/{}", pid)).exists());
+    }
+
+    #[tokio::test]
+    async fn test_recycle_waits_for_drain_complete() {
+        let (handle, mut worker_side) =
+            mock_worker_handle("default-0", ResourceCapabilities::default());
+
+        // Mock worker with queued Python-side work: reports it, finishes it, then drains
+        let mock = tokio::spawn(async move {
+            let mut drained_at = None;
+            let mut shutdown_at = None;
+            while let Ok(msg) = protocol::read_message(&mut worker_side).await {
+                match msg {
+                    Message::DrainRequest { .. } => {
+                        let status = Message::DrainStatus {
+                            worker_id: "default-0".to_string(),
+                            pending: 2,
+                        };
+                        protocol::write_message(&mut worker_side, &status)
+                            .await
+                            .unwrap();
+                        tokio::time::sleep(Duration::from_millis(200)).await;
+                        let complete = Message::DrainComplete {
+                            worker_id: "default-0".to_string(),
+                        };
+                        protocol::write_message(&mut worker_side, &complete)
+                            .await
+                            .unwrap();
+                        drained_at = Some(Instant::now());
+                    }
+                    Message::Shutdown { .. } => {
+                        shutdown_at = Some(Instant::now());
+                        break;
+                    }
+                    _ => {}
+                }
+            }
+            (drained_at, shutdown_at)
+        });
+
+        let start = Instant::now();
+        Orchestrator::retire_worker(handle, &Config::default().orchestrator.worker).await;
+        assert!(start.elapsed() >= Duration::from_millis(200));
+
+        let (drained_at, shutdown_at) = mock.await.unwrap();
+        let drained_at = drained_at.expect("worker should receive DrainRequest");
+        let shutdown_at = shutdown_at.expect("worker should receive Shutdown");
+        assert!(
+            shutdown_at >= drained_at,
+            "Shutdown sent before DrainComplete"
+        );
+    }
+
+    #[test]
+    fn test_pool_names_read_back_from_worker_ids() {
+        let mut pool = Config::default().effective_worker_pools().remove(0);
+        pool.name = "gpu_large".to_string();
+        assert!(pool.validate().is_ok());
+        assert_eq!(pool_name("gpu_large-3"), "gpu_large");
+
+        // "gpu-large-3" would read as pool "gpu"
+        pool.name = "gpu-large".to_string();
+        let err = pool.validate().unwrap_err();
+        assert!(err.contains("'gpu-large'"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_start_refuses_overcommitted_pools() {
+        let mut config = Config::default();
+        config.orchestrator.worker_count = Some(100_000);
+        config.orchestrator.overcommit.action = OvercommitAction::Error;
+        let orchestrator = Orchestrator::new(config);
+
+        let err = orchestrator.start().await.unwrap_err();
+        assert!(err.to_string().contains("over-commit"), "{}", err);
+        assert_eq!(orchestrator.worker_count().await, 0);
+    }
+
+    #[test]
+    fn test_overcommit_warn_does_not_fail() {
+        let mut config = Config::default();
+        config.orchestrator.worker_count = Some(100);
+        let orchestrator = Orchestrator::new(config);
+        let host = HostResources {
+            cpus: 8.0,
+            memory_gb: Some(16.0),
+            gpus: None,
+            gpu_indices: None,
         };
 
-        // Wait for worker to be ready
-        match new_worker.wait_ready().await {
-            Ok(()) => {
-                info!("Replacement worker {} is ready", worker_id);
-                workers.insert(idx, new_worker);
-                Ok(())
+        assert!(orchestrator.check_host_capacity(&host).is_ok());
+    }
+
+    #[test]
+    fn test_nonexistent_gpu_device_caught_at_startup() {
+        let mut pool = reserve_pool(0, None);
+        pool.resources.num_gpus = 1.0;
+        pool.gpu_devices = vec![0, 3];
+        let mut config = Config::default();
+        config.orchestrator.worker_pools = vec![pool];
+        let host = HostResources {
+            cpus: 8.0,
+            memory_gb: Some(16.0),
+            gpus: Some(2.0),
+            gpu_indices: Some(vec![0, 1]),
+        };
+
+        let err = Orchestrator::new(config.clone())
+            .check_gpu_devices(&host)
+            .unwrap_err();
+        assert!(err.to_string().contains("pool 'cpu' uses GPU 3"), "{}", err);
+
+        // CPU-only hosts (no nvidia-smi) and opted-out configs skip the check
+        let cpu_only = HostResources {
+            gpus: None,
+            gpu_indices: None,
+            ..host.clone()
+        };
+        assert!(Orchestrator::new(config.clone())
+            .check_gpu_devices(&cpu_only)
+            .is_ok());
+        config.orchestrator.validate_gpu_devices = false;
+        assert!(Orchestrator::new(config).check_gpu_devices(&host).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_drain_times_out() {
+        let (mut handle, _worker_side) =
+            mock_worker_handle("default-0", ResourceCapabilities::default());
+
+        // The mock never answers, so draining must give up after the timeout
+        let result = handle.drain(Duration::from_millis(50)).await;
+        assert!(result.is_err());
+    }
+
+    fn reserve_pool(min_idle: usize, max_count: Option<usize>) -> WorkerPoolConfig {
+        WorkerPoolConfig {
+            name: "cpu".to_string(),
+            count: 2,
+            resources: ResourceCapabilities::default(),
+            gpu_devices: vec![],
+            cpuset: None,
+            min_idle,
+            max_count,
+            min_count: None,
+            scale_up_utilization: 0.8,
+            scale_down_utilization: 0.2,
+            max_total_memory_mb: None,
+            allow_overflow_from: None,
+            allow_overflow_to: None,
+            warmup_handler: None,
+            warmup_timeout_secs: 300,
+        }
+    }
+
+    /// Mock workers for the "cpu" pool, `busy` of which are busy
+    fn pool_workers(count: usize, busy: usize) -> Vec<WorkerHandle> {
+        (0..count)
+            .map(|i| {
+                let (mut handle, _) =
+                    mock_worker_handle(&format!("cpu-{}", i), ResourceCapabilities::default());
+                if i < busy {
+                    handle.worker.state = WorkerState::Busy;
+                }
+                handle
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_pool_total_memory_cap_recycles_largest_idle_worker() {
+        let mut pool = reserve_pool(0, None);
+        pool.max_total_memory_mb = Some(1000);
+
+        // Each worker is far below max_memory_mb (4096), but together they're over the cap
+        let mut workers = pool_workers(3, 1);
+        for (handle, memory_mb) in workers.iter_mut().zip([600, 300, 400]) {
+            handle.worker.current_memory_mb = memory_mb;
+        }
+        let worker_config = Config::default().orchestrator.worker;
+        assert!(workers
+            .iter()
+            .all(|h| !h.worker.should_recycle(&worker_config)));
+
+        // cpu-0 uses the most but is busy, so the largest idle worker goes
+        assert_eq!(
+            pool_memory_recycle_plan(&[pool.clone()], &workers, &[]),
+            vec![2]
+        );
+
+        // Already recycling cpu-2 brings the pool back under the cap
+        assert!(pool_memory_recycle_plan(&[pool.clone()], &workers, &[2]).is_empty());
+
+        // Under the cap, or without one: nothing to do
+        workers[0].worker.current_memory_mb = 200;
+        assert!(pool_memory_recycle_plan(&[pool], &workers, &[]).is_empty());
+        assert!(
+            pool_memory_recycle_plan(&[reserve_pool(0, None)], &pool_workers(3, 0), &[]).is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_scale_up_below_min_idle() {
+        let pools = vec![reserve_pool(2, Some(4))];
+
+        // One of two workers idle: one short of the reserve
+        assert_eq!(
+            scale_up_plan(&pools, &pool_workers(2, 1)),
+            vec![("cpu".to_string(), 1)]
+        );
+
+        // At or above the reserve: no scale-up
+        assert!(scale_up_plan(&pools, &pool_workers(2, 0)).is_empty());
+        assert!(scale_up_plan(&pools, &pool_workers(3, 0)).is_empty());
+
+        // Capped by max_count, and disabled without it
+        assert!(scale_up_plan(&pools, &pool_workers(4, 4)).is_empty());
+        assert!(scale_up_plan(&[reserve_pool(2, None)], &pool_workers(2, 2)).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_growth_reservations_numbered_apart() {
+        let growing = Arc::new(Growing::default());
+        let workers = pool_workers(2, 0);
+
+        // Spawns in flight take the IDs after the pool's workers and each other's
+        let first = growing.reserve(&workers, "cpu");
+        let second = growing.reserve(&workers, "cpu");
+        assert_eq!(
+            (first.worker_id.as_str(), second.worker_id.as_str()),
+            ("cpu-2", "cpu-3")
+        );
+        assert_eq!(growing.reserve(&workers, "gpu").worker_id, "gpu-0");
+
+        drop(second);
+        assert_eq!(growing.reserve(&workers, "cpu").pool_idx, 3);
+        drop(first);
+        assert!(growing.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_load_scale_plan() {
+        let mut pool = reserve_pool(0, Some(4));
+        pool.min_count = Some(1);
+        let pools = vec![pool];
+        let waiting = |tasks: usize| HashMap::from([("cpu".to_string(), tasks)]);
+        let load = |workers: &mut Vec<WorkerHandle>, cpus: f64| {
+            for handle in workers.iter_mut() {
+                handle.worker.allocation.allocated_cpus = cpus;
             }
-            Err(e) => {
-                let err_msg = format!("Replacement worker {} failed to become ready: {}", worker_id, e);
-                warn!("{}", err_msg);
-                Err(err_msg)
+        };
+
+        // Half the CPU of both workers in use: steady
+        let mut workers = pool_workers(2, 0);
+        load(&mut workers, 0.5);
+        assert!(load_scale_plan(&pools, &workers, &waiting(0)).is_empty());
+
+        // Busy enough on average to add a worker, up to max_count
+        load(&mut workers, 0.9);
+        assert_eq!(
+            load_scale_plan(&pools, &workers, &waiting(0)),
+            vec![ScaleStep::Add("cpu".to_string())]
+        );
+        let mut full = pool_workers(4, 4);
+        load(&mut full, 1.0);
+        assert!(load_scale_plan(&pools, &full, &waiting(3)).is_empty());
+
+        // Tasks waiting for a worker count once none of the pool's is idle
+        let busy = pool_workers(2, 2);
+        assert_eq!(
+            load_scale_plan(&pools, &busy, &waiting(1)),
+            vec![ScaleStep::Add("cpu".to_string())]
+        );
+        assert!(load_scale_plan(&pools, &pool_workers(2, 1), &waiting(1)).is_empty());
+
+        // Tasks waiting for another pool's workers don't grow this one
+        let elsewhere = HashMap::from([("gpu".to_string(), 3)]);
+        assert!(load_scale_plan(&pools, &busy, &elsewhere).is_empty());
+
+        // Underused: the highest-numbered idle worker goes, down to min_count
+        assert_eq!(
+            load_scale_plan(&pools, &pool_workers(3, 0), &waiting(0)),
+            vec![ScaleStep::Retire(2)]
+        );
+        assert!(load_scale_plan(&pools, &pool_workers(1, 0), &waiting(0)).is_empty());
+
+        // Not while work is queued inside the workers
+        let mut queued = pool_workers(3, 0);
+        queued[0].worker.queue_depth = Some(2);
+        assert_eq!(
+            load_scale_plan(&pools, &queued, &waiting(0)),
+            vec![ScaleStep::Add("cpu".to_string())]
+        );
+
+        // Without bounds the pool keeps its size
+        assert!(
+            load_scale_plan(&[reserve_pool(0, None)], &pool_workers(3, 0), &waiting(0)).is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_scheduling_prefers_busy_worker_over_reserve() {
+        let mut config = Config::default();
+        config.orchestrator.worker_pools = vec![reserve_pool(1, None)];
+        let orchestrator = Orchestrator::new(config);
+        orchestrator
+            .workers()
+            .write()
+            .await
+            .extend(pool_workers(2, 1));
+
+        // cpu-1 is the pool's only idle worker, so it's held back while busy
+        // cpu-0 still has capacity
+        let requirements = crate::protocol::ResourceRequirements::default();
+        let selection = orchestrator
+            .find_worker_with_resources(&requirements, None, &Placement::default())
+            .await
+            .unwrap();
+        assert_eq!((selection.index, selection.pass), (0, SelectionPass::Busy));
+
+        let status = orchestrator.pool_status().await;
+        assert_eq!(
+            (status[0].workers, status[0].idle, status[0].min_idle),
+            (2, 1, 1)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_affinity_keeps_session_on_one_worker() {
+        let orchestrator = Orchestrator::new(Config::default());
+        orchestrator
+            .workers()
+            .write()
+            .await
+            .extend(pool_workers(4, 0));
+        let requirements = crate::protocol::ResourceRequirements::default();
+        async fn select(orchestrator: &Orchestrator, affinity: &str) -> usize {
+            let placement = Placement {
+                affinity: Some(affinity),
+                ..Placement::default()
+            };
+            let requirements = crate::protocol::ResourceRequirements::default();
+            orchestrator
+                .find_worker_with_resources(&requirements, None, &placement)
+                .await
+                .unwrap()
+                .index
+        }
+
+        // Round-robin would move on each time; the session stays put
+        let preferred = select(&orchestrator, "session-a").await;
+        for _ in 0..5 {
+            assert_eq!(select(&orchestrator, "session-a").await, preferred);
+        }
+
+        // Sessions spread over the workers
+        let mut used = std::collections::HashSet::new();
+        for n in 0..32 {
+            used.insert(select(&orchestrator, &format!("session-{}", n)).await);
+        }
+        assert!(used.len() > 1);
+
+        // While its worker is taken the session runs elsewhere, then returns
+        let lease = orchestrator
+            .lease_worker(preferred, "task-1", requirements)
+            .await
+            .unwrap();
+        assert_ne!(select(&orchestrator, "session-a").await, preferred);
+        drop(lease);
+        assert_eq!(select(&orchestrator, "session-a").await, preferred);
+    }
+
+    #[tokio::test]
+    async fn test_cpu_tasks_overflow_onto_gpu_pools_only_where_allowed() {
+        let gpu = ResourceCapabilities {
+            num_gpus: 1.0,
+            ..Default::default()
+        };
+        let selection = |allow_overflow_from: Option<Vec<String>>,
+                         allow_overflow_to: Option<Vec<String>>| {
+            let gpu = gpu.clone();
+            async move {
+                let mut config = Config::default();
+                config.orchestrator.worker_pools = vec![
+                    WorkerPoolConfig {
+                        name: "cpu".to_string(),
+                        count: 1,
+                        allow_overflow_to,
+                        ..reserve_pool(0, None)
+                    },
+                    WorkerPoolConfig {
+                        name: "gpu".to_string(),
+                        count: 1,
+                        resources: gpu.clone(),
+                        allow_overflow_from,
+                        ..reserve_pool(0, None)
+                    },
+                ];
+                let orchestrator = Orchestrator::new(config);
+                for (id, capabilities) in
+                    [("cpu-0", ResourceCapabilities::default()), ("gpu-0", gpu)]
+                {
+                    let (handle, _worker_side) = mock_worker_handle(id, capabilities);
+                    orchestrator.workers().write().await.push(handle);
+                }
+
+                let requirements = crate::protocol::ResourceRequirements::default();
+                let first = orchestrator
+                    .find_worker_with_resources(&requirements, None, &Placement::default())
+                    .await;
+                // The CPU worker comes first even though both are idle
+                assert_eq!(
+                    first.map(|s| (s.index, s.pass)),
+                    Some((0, SelectionPass::Idle))
+                );
+
+                // With the CPU worker full, the task overflows if both pools allow it
+                orchestrator.workers().write().await[0]
+                    .worker
+                    .allocation
+                    .allocated_cpus = 1.0;
+                orchestrator
+                    .find_worker_with_resources(&requirements, None, &Placement::default())
+                    .await
+                    .map(|s| (s.index, s.pass))
             }
+        };
+
+        let names = |names: &[&str]| Some(names.iter().map(|n| n.to_string()).collect::<Vec<_>>());
+        assert_eq!(
+            selection(None, None).await,
+            Some((1, SelectionPass::Fallback))
+        );
+        assert_eq!(
+            selection(names(&["cpu"]), names(&["gpu"])).await,
+            Some((1, SelectionPass::Fallback))
+        );
+        assert_eq!(selection(names(&[]), None).await, None);
+        assert_eq!(selection(None, names(&[])).await, None);
+        assert_eq!(selection(names(&["other"]), None).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_scheduling_prefers_shallower_worker_queue() {
+        let orchestrator = Orchestrator::new(Config::default());
+        for (id, queue_depth) in [("default-0", 5), ("default-1", 0)] {
+            let (mut handle, worker_side) = mock_worker_handle(id, ResourceCapabilities::default());
+            spawn_queued_worker(worker_side, queue_depth);
+            handle.heartbeat(Duration::from_secs(1)).await.unwrap();
+            assert_eq!(handle.worker.queue_depth, Some(queue_depth));
+            orchestrator.workers().write().await.push(handle);
+        }
+
+        // Both are idle with capacity; round-robin would start at default-0
+        let requirements = crate::protocol::ResourceRequirements::default();
+        for _ in 0..3 {
+            let selection = orchestrator
+                .find_worker_with_resources(&requirements, None, &Placement::default())
+                .await
+                .unwrap();
+            assert_eq!((selection.index, selection.pass), (1, SelectionPass::Idle));
+        }
+
+        let status = orchestrator.worker_status().await;
+        assert_eq!(status[0].queue_depth, Some(5));
+        assert_eq!(status[1].state, "idle");
+    }
+
+    #[tokio::test]
+    async fn test_weighted_random_selection_tracks_capacity() {
+        let mut config = Config::default();
+        config.orchestrator.scheduling = SchedulingStrategy::WeightedRandom;
+        let orchestrator = Orchestrator::new(config);
+        for (id, num_cpus) in [("default-0", 1.0), ("default-1", 3.0), ("default-2", 6.0)] {
+            let capabilities = ResourceCapabilities {
+                num_cpus,
+                memory_gb: 64.0,
+                ..Default::default()
+            };
+            let (handle, _worker_side) = mock_worker_handle(id, capabilities);
+            orchestrator.workers().write().await.push(handle);
         }
+
+        // One-CPU tasks: the workers have room for 1, 3, and 6 of them
+        let requirements = crate::protocol::ResourceRequirements::default();
+        let mut picks = [0usize; 3];
+        let selections = 5000;
+        for _ in 0..selections {
+            let selection = orchestrator
+                .find_worker_with_resources(&requirements, None, &Placement::default())
+                .await
+                .unwrap();
+            assert_eq!(selection.pass, SelectionPass::Idle);
+            picks[selection.index] += 1;
+        }
+
+        for (count, expected) in picks.iter().zip([0.1, 0.3, 0.6]) {
+            let share = *count as f64 / selections as f64;
+            assert!((share - expected).abs() < 0.04, "picks {:?}", picks);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_spread_and_pack_placement() {
+        let placements = |strategy| async move {
+            let requirements = crate::protocol::ResourceRequirements::default();
+            let mut config = Config::default();
+            config.orchestrator.scheduling = strategy;
+            let orchestrator = Orchestrator::new(config);
+            // default-1 has half its CPUs in use, default-2 a quarter
+            for (id, allocated_cpus) in [("default-0", 0.0), ("default-1", 2.0), ("default-2", 1.0)]
+            {
+                let capabilities = ResourceCapabilities {
+                    num_cpus: 4.0,
+                    memory_gb: 64.0,
+                    ..Default::default()
+                };
+                let (mut handle, _worker_side) = mock_worker_handle(id, capabilities);
+                handle.worker.allocation.allocated_cpus = allocated_cpus;
+                handle.worker.state = if allocated_cpus > 0.0 {
+                    WorkerState::Busy
+                } else {
+                    WorkerState::Idle
+                };
+                orchestrator.workers().write().await.push(handle);
+            }
+
+            // Place one-CPU tasks one after another, allocating each
+            let mut picks = Vec::new();
+            for _ in 0..4 {
+                let selection = orchestrator
+                    .find_worker_with_resources(&requirements, None, &Placement::default())
+                    .await
+                    .unwrap();
+                let mut workers = orchestrator.workers.write().await;
+                let worker = &mut workers[selection.index].worker;
+                worker.allocation.allocate(&requirements);
+                worker.state = WorkerState::Busy;
+                picks.push(selection.index);
+            }
+            picks
+        };
+
+        // Least loaded first: the idle worker, then evening out (ties in
+        // round-robin order)
+        assert_eq!(
+            placements(SchedulingStrategy::Spread).await,
+            vec![0, 2, 0, 1]
+        );
+        // Most loaded first, until it is full
+        assert_eq!(placements(SchedulingStrategy::Pack).await, vec![1, 1, 2, 2]);
+    }
+
+    #[tokio::test]
+    async fn test_memory_sampling_continues_during_recycle() {
+        let mut config = Config::default();
+        config.orchestrator.worker.memory_check_interval_secs = 1;
+        config.orchestrator.worker.max_tasks_per_worker = 5;
+        let orchestrator = Orchestrator::new(config);
+
+        // default-0 is due for recycling and never answers the drain request,
+        // so its recycle stays in progress for the whole drain timeout (30s)
+        let (mut due, _due_side) = mock_worker_handle("default-0", ResourceCapabilities::default());
+        due.worker.tasks_completed = 5;
+        due.worker.pid = std::process::id();
+        let (mut other, other_side) =
+            mock_worker_handle("default-1", ResourceCapabilities::default());
+        other.worker.pid = std::process::id();
+        // Still running, so it isn't replaced as crashed
+        other.process = std::process::Command::new("sleep")
+            .arg("30")
+            .spawn()
+            .unwrap();
+        spawn_queued_worker(other_side, 0);
+        orchestrator.workers().write().await.extend([due, other]);
+
+        orchestrator.start_monitoring().await;
+
+        let worker_ids = || async {
+            let workers = orchestrator.workers();
+            let workers = workers.read().await;
+            workers
+                .iter()
+                .map(|h| h.worker.id.clone())
+                .collect::<Vec<_>>()
+        };
+        // Bounded, since a recycle holding the workers lock would stall these reads
+        tokio::time::timeout(Duration::from_secs(10), async {
+            while worker_ids().await.contains(&"default-0".to_string()) {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+        })
+        .await
+        .expect("default-0 was never taken out for recycling");
+
+        // With default-0 mid-recycle, default-1 keeps being sampled
+        orchestrator.workers().write().await[0]
+            .worker
+            .current_memory_mb = 0;
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while orchestrator.workers().read().await[0]
+                .worker
+                .current_memory_mb
+                == 0
+            {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+        })
+        .await
+        .expect("default-1 was not sampled during the recycle");
+        assert_eq!(worker_ids().await, vec!["default-1".to_string()]);
+
+        let mut other = orchestrator.workers().write().await.remove(0);
+        other.kill().unwrap();
+        orchestrator.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_crashed_worker_taken_out_for_replacement() {
+        let mut config = Config::default();
+        config.orchestrator.worker.memory_check_interval_secs = 1;
+        let orchestrator = Orchestrator::new(config);
+
+        // The mock's process exits at once, as a crashed worker's would
+        let (mut crashed, _crashed_side) =
+            mock_worker_handle("default-0", ResourceCapabilities::default());
+        crashed.worker.pid = std::process::id();
+        let (mut alive, _alive_side) =
+            mock_worker_handle("default-1", ResourceCapabilities::default());
+        alive.worker.pid = std::process::id();
+        alive.process = std::process::Command::new("sleep")
+            .arg("30")
+            .spawn()
+            .unwrap();
+        orchestrator
+            .workers()
+            .write()
+            .await
+            .extend([crashed, alive]);
+
+        orchestrator.start_monitoring().await;
+        tokio::time::timeout(Duration::from_secs(10), async {
+            while orchestrator
+                .workers()
+                .read()
+                .await
+                .iter()
+                .any(|h| h.worker.id == "default-0")
+            {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+        })
+        .await
+        .expect("crashed default-0 was never taken out for replacement");
+
+        let mut alive = orchestrator.workers().write().await.remove(0);
+        assert_eq!(
+            (alive.worker.id.as_str(), alive.worker.state),
+            ("default-1", WorkerState::Idle)
+        );
+        alive.kill().unwrap();
+        orchestrator.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_hung_worker_replaced_after_missed_heartbeats() {
+        let mut config = Config::default();
+        config.orchestrator.worker.memory_check_interval_secs = 1;
+        config.orchestrator.worker.max_missed_heartbeats = 2;
+        let orchestrator = Orchestrator::new(config);
+
+        // default-0's process runs but never answers; default-1 answers every heartbeat
+        let (mut hung, _hung_side) =
+            mock_worker_handle("default-0", ResourceCapabilities::default());
+        hung.worker.pid = std::process::id();
+        hung.process = std::process::Command::new("sleep")
+            .arg("30")
+            .spawn()
+            .unwrap();
+        let (mut alive, mut alive_side) =
+            mock_worker_handle("default-1", ResourceCapabilities::default());
+        alive.worker.pid = std::process::id();
+        alive.process = std::process::Command::new("sleep")
+            .arg("30")
+            .spawn()
+            .unwrap();
+        tokio::spawn(async move {
+            while let Ok(Message::Heartbeat { worker_id, .. }) =
+                protocol::read_message(&mut alive_side).await
+            {
+                let reply = Message::Heartbeat {
+                    worker_id,
+                    queue_depth: Some(0),
+                };
+                protocol::write_message(&mut alive_side, &reply)
+                    .await
+                    .unwrap();
+            }
+        });
+        orchestrator.workers().write().await.extend([hung, alive]);
+
+        orchestrator.start_monitoring().await;
+        tokio::time::timeout(Duration::from_secs(15), async {
+            while orchestrator
+                .workers()
+                .read()
+                .await
+                .iter()
+                .any(|h| h.worker.id == "default-0")
+            {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+        })
+        .await
+        .expect("hung default-0 was never taken out for replacement");
+
+        let mut alive = orchestrator.workers().write().await.remove(0);
+        assert_eq!(
+            (alive.worker.id.as_str(), alive.worker.state),
+            ("default-1", WorkerState::Idle)
+        );
+        assert_eq!(alive.worker.missed_heartbeats, 0);
+        alive.kill().unwrap();
+        orchestrator.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_unanswered_heartbeat_does_not_hold_workers_lock() {
+        let mut config = Config::default();
+        config.orchestrator.worker.memory_check_interval_secs = 1;
+        let orchestrator = Orchestrator::new(config);
+
+        // Reads the heartbeat but never answers it
+        let (mut hung, mut hung_side) =
+            mock_worker_handle("default-0", ResourceCapabilities::default());
+        hung.worker.pid = std::process::id();
+        hung.process = std::process::Command::new("sleep")
+            .arg("30")
+            .spawn()
+            .unwrap();
+        orchestrator.workers().write().await.push(hung);
+
+        orchestrator.start_monitoring().await;
+        let msg = tokio::time::timeout(
+            Duration::from_secs(5),
+            protocol::read_message(&mut hung_side),
+        )
+        .await
+        .expect("no heartbeat sent")
+        .unwrap();
+        assert!(matches!(msg, Message::Heartbeat { .. }));
+
+        // While it waits for the answer, the workers lock is free and the
+        // heartbeat counts as holding the worker
+        let workers = orchestrator.workers();
+        let guard = tokio::time::timeout(HEARTBEAT_TIMEOUT / 4, workers.write())
+            .await
+            .expect("workers lock held during a heartbeat");
+        assert!(guard[0].connection_busy());
+        assert_eq!(orchestrator.leases().active(), 1);
+        drop(guard);
+
+        tokio::time::sleep(HEARTBEAT_TIMEOUT + Duration::from_millis(200)).await;
+        assert_eq!(orchestrator.leases().active(), 0);
+        let mut hung = orchestrator.workers().write().await.remove(0);
+        assert_eq!(hung.worker.missed_heartbeats, 1);
+        hung.kill().unwrap();
+        orchestrator.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_worker_finishing_cancelled_task_is_not_heartbeated() {
+        let mut config = Config::default();
+        config.orchestrator.worker.memory_check_interval_secs = 1;
+        config.orchestrator.worker.max_missed_heartbeats = 1;
+        let orchestrator = Orchestrator::new(config);
+
+        let (mut handle, mut worker_side) =
+            mock_worker_handle("default-0", ResourceCapabilities::default());
+        handle.worker.pid = std::process::id();
+        handle.process = std::process::Command::new("sleep")
+            .arg("30")
+            .spawn()
+            .unwrap();
+        orchestrator.workers().write().await.push(handle);
+
+        // The task is sent, then abandoned while the worker keeps running it
+        let mut lease = orchestrator
+            .lease_worker(0, "task-1", ResourceRequirements::default())
+            .await
+            .unwrap();
+        lease
+            .send(&Message::TaskAssignment {
+                task_id: "task-1".to_string(),
+                function_name: "slow".to_string(),
+                args: rmpv::Value::Nil,
+                resources: ResourceRequirements::default(),
+                deadline_ms_remaining: None,
+                priority: 0,
+            })
+            .await
+            .unwrap();
+        drop(lease);
+        assert!(matches!(
+            protocol::read_message(&mut worker_side).await.unwrap(),
+            Message::TaskAssignment { .. }
+        ));
+        assert!(matches!(
+            protocol::read_message(&mut worker_side).await.unwrap(),
+            Message::CancelTask { .. }
+        ));
+
+        // Rounds of monitoring pass without it being heartbeated or replaced
+        orchestrator.start_monitoring().await;
+        tokio::time::sleep(Duration::from_millis(2500)).await;
+        let workers = orchestrator.workers();
+        {
+            let workers = workers.read().await;
+            assert_eq!(workers.len(), 1);
+            assert_eq!(workers[0].worker.state, WorkerState::Busy);
+            assert_eq!(workers[0].worker.missed_heartbeats, 0);
+        }
+
+        let reply = Message::TaskResult {
+            task_id: "task-1".to_string(),
+            success: true,
+            result: rmpv::Value::Nil,
+        };
+        protocol::write_message(&mut worker_side, &reply)
+            .await
+            .unwrap();
+        crate::testing::wait_for_worker_state(&workers, 0, WorkerState::Idle).await;
+
+        let mut handle = workers.write().await.remove(0);
+        handle.kill().unwrap();
+        orchestrator.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_recycled_worker_serves_until_replacement_ready() {
+        let mut config = Config::default();
+        config.orchestrator.worker.memory_check_interval_secs = 1;
+        config.orchestrator.worker.max_tasks_per_worker = 1;
+        let orchestrator = Orchestrator::new(config);
+
+        // Due for recycling, in a pool with no config, so no replacement can start
+        let (mut handle, _worker_side) =
+            mock_worker_handle("orphan-0", ResourceCapabilities::default());
+        handle.worker.pid = std::process::id();
+        handle.worker.tasks_completed = 1;
+        handle.process = std::process::Command::new("sleep")
+            .arg("30")
+            .spawn()
+            .unwrap();
+        orchestrator.workers().write().await.push(handle);
+
+        orchestrator.start_monitoring().await;
+        for _ in 0..3 {
+            tokio::time::sleep(Duration::from_millis(700)).await;
+            let states: Vec<_> = orchestrator
+                .workers()
+                .read()
+                .await
+                .iter()
+                .map(|h| h.worker.state)
+                .collect();
+            assert_eq!(
+                states,
+                vec![WorkerState::Idle],
+                "worker left service without a replacement"
+            );
+        }
+
+        let mut handle = orchestrator.workers().write().await.remove(0);
+        handle.kill().unwrap();
+        orchestrator.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_written_on_shutdown_and_loaded_on_start() {
+        let dir = std::env::temp_dir().join(format!("neutrino-snapshot-{}", uuid::Uuid::new_v4()));
+        let path = dir.join("snapshot.json");
+        let mut config = Config::default();
+        config.orchestrator.snapshot_path = Some(path.to_string_lossy().into_owned());
+
+        let orchestrator = Orchestrator::new(config.clone());
+        assert!(orchestrator.last_snapshot().is_none());
+        let (mut handle, _worker_side) =
+            mock_worker_handle("default-0", ResourceCapabilities::default());
+        handle.worker.tasks_completed = 7;
+        orchestrator.workers().write().await.push(handle);
+        orchestrator
+            .metrics()
+            .observe_task("embed", true, Duration::from_millis(20));
+        orchestrator
+            .metrics()
+            .observe_task("embed", false, Duration::from_millis(40));
+
+        orchestrator.shutdown().await.unwrap();
+        assert!(path.exists());
+
+        // The next run sees what this one had in memory
+        let restarted = Orchestrator::new(config);
+        let snapshot = restarted.last_snapshot().expect("snapshot not loaded");
+        assert_eq!(snapshot.handlers.len(), 1);
+        assert_eq!(
+            (snapshot.handlers[0].calls, snapshot.handlers[0].failures),
+            (2, 1)
+        );
+        assert_eq!(snapshot.workers.len(), 1);
+        assert_eq!(
+            (
+                snapshot.workers[0].id.as_str(),
+                snapshot.workers[0].tasks_completed
+            ),
+            ("default-0", 7)
+        );
+
+        std::fs::remove_dir_all(dir).unwrap();
     }
 }
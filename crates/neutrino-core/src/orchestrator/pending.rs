@@ -0,0 +1,165 @@
+//! Tasks waiting for capacity. When no worker can take a task it waits here,
+//! up to `max_wait`, instead of failing at once; waiters retry in arrival
+//! order as resources are released. The queue is bounded so a long outage
+//! turns into 503s rather than an ever-growing backlog.
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+
+/// How often the front waiter re-checks without a release notification
+/// (workers rejoining after a restart aren't announced)
+const RECHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// FIFO of tasks waiting for a worker with capacity
+pub struct PendingQueue {
+    max_depth: usize,
+    max_wait: Duration,
+    state: Mutex<PendingState>,
+    /// Woken when resources are released or the front of the queue changes
+    released: Notify,
+}
+
+#[derive(Default)]
+struct PendingState {
+    waiting: VecDeque<u64>,
+    next_ticket: u64,
+}
+
+impl PendingQueue {
+    pub fn new(max_depth: usize, max_wait: Duration) -> Self {
+        Self {
+            max_depth,
+            max_wait,
+            state: Mutex::new(PendingState::default()),
+            released: Notify::new(),
+        }
+    }
+
+    pub fn max_wait(&self) -> Duration {
+        self.max_wait
+    }
+
+    /// Tasks currently waiting
+    pub fn depth(&self) -> usize {
+        self.state.lock().unwrap().waiting.len()
+    }
+
+    /// Join the back of the queue, or None if it is full. The task leaves the
+    /// queue when the returned ticket is dropped.
+    pub fn join(&self) -> Option<PendingTicket<'_>> {
+        let mut state = self.state.lock().unwrap();
+        if state.waiting.len() >= self.max_depth {
+            return None;
+        }
+        let ticket = state.next_ticket;
+        state.next_ticket += 1;
+        state.waiting.push_back(ticket);
+        Some(PendingTicket {
+            queue: self,
+            ticket,
+            deadline: Instant::now() + self.max_wait,
+        })
+    }
+
+    /// Resources were released: the task at the front should try again
+    pub fn released(&self) {
+        self.released.notify_waiters();
+    }
+}
+
+/// A task's place in the pending queue
+pub struct PendingTicket<'a> {
+    queue: &'a PendingQueue,
+    ticket: u64,
+    deadline: Instant,
+}
+
+impl PendingTicket<'_> {
+    /// Wait until this task is at the front of the queue and capacity may have
+    /// been freed. False once the task has waited `max_wait`.
+    pub async fn next_attempt(&self) -> bool {
+        loop {
+            let released = self.queue.released.notified();
+            tokio::pin!(released);
+            released.as_mut().enable();
+
+            let remaining = self.deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return false;
+            }
+            let _ = tokio::time::timeout(remaining.min(RECHECK_INTERVAL), released).await;
+            if self.is_front() {
+                return Instant::now() < self.deadline;
+            }
+        }
+    }
+
+    fn is_front(&self) -> bool {
+        self.queue.state.lock().unwrap().waiting.front() == Some(&self.ticket)
+    }
+}
+
+impl Drop for PendingTicket<'_> {
+    fn drop(&mut self) {
+        self.queue
+            .state
+            .lock()
+            .unwrap()
+            .waiting
+            .retain(|&ticket| ticket != self.ticket);
+        // The next task may now be at the front
+        self.queue.released();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_waiters_retry_in_arrival_order() {
+        let queue = Arc::new(PendingQueue::new(3, Duration::from_secs(5)));
+        let order = Arc::new(Mutex::new(Vec::new()));
+
+        let mut waiters = Vec::new();
+        for name in ["first", "second", "third"] {
+            let ticket_queue = Arc::clone(&queue);
+            let order = Arc::clone(&order);
+            let (joined_tx, joined_rx) = tokio::sync::oneshot::channel();
+            waiters.push(tokio::spawn(async move {
+                let ticket = ticket_queue.join().unwrap();
+                joined_tx.send(()).unwrap();
+                assert!(ticket.next_attempt().await);
+                order.lock().unwrap().push(name);
+            }));
+            joined_rx.await.unwrap();
+        }
+        assert_eq!(queue.depth(), 3);
+        assert!(queue.join().is_none(), "queue is full");
+
+        queue.released();
+        for waiter in waiters {
+            waiter.await.unwrap();
+        }
+        assert_eq!(*order.lock().unwrap(), ["first", "second", "third"]);
+        assert_eq!(queue.depth(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_waiter_gives_up_after_max_wait() {
+        let queue = PendingQueue::new(2, Duration::from_millis(150));
+        let front = queue.join().unwrap();
+        let behind = queue.join().unwrap();
+
+        // Stuck behind a task that never leaves
+        let start = Instant::now();
+        assert!(!behind.next_attempt().await);
+        assert!(start.elapsed() >= Duration::from_millis(150));
+
+        drop(front);
+        assert!(!behind.next_attempt().await, "already waited its limit");
+    }
+}
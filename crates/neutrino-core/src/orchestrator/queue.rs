@@ -0,0 +1,415 @@
+//! Order in which waiting tasks are dispatched. Tasks wait for a worker in a
+//! binary heap and the highest priority goes next; ties go to the task that
+//! has waited longest. Waiting also ages a task's priority upward (one level
+//! per `aging` interval) so a steady stream of high-priority work can't starve
+//! low-priority tasks forever. A task no worker can take yet parks: it gives
+//! the slot up, so tasks that fit elsewhere go ahead, and rejoins the order
+//! in its old place, together with every other parked task, once capacity
+//! frees up.
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
+
+/// Priority queue granting one task at a time the right to dispatch
+pub struct TaskQueue {
+    /// Wait that raises a task's priority by one level (None = no aging)
+    aging: Option<Duration>,
+    epoch: Instant,
+    state: Mutex<QueueState>,
+}
+
+#[derive(Default)]
+struct QueueState {
+    /// Whether a task currently holds the slot
+    busy: bool,
+    waiting: BinaryHeap<Waiting>,
+    /// Tasks out of the order until capacity frees up
+    parked: Vec<Waiting>,
+    next_seq: u64,
+}
+
+struct Waiting {
+    /// Priority with aging folded in; comparable across enqueue times
+    rank: i128,
+    seq: u64,
+    ready: oneshot::Sender<()>,
+}
+
+impl Ord for Waiting {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Higher rank first, then lower sequence number (FIFO)
+        self.rank
+            .cmp(&other.rank)
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+impl PartialOrd for Waiting {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Waiting {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Waiting {}
+
+impl TaskQueue {
+    pub fn new(aging: Option<Duration>) -> Self {
+        Self {
+            aging: aging.filter(|aging| !aging.is_zero()),
+            epoch: Instant::now(),
+            state: Mutex::new(QueueState::default()),
+        }
+    }
+
+    /// Wait until this task is the highest-ranked waiter and the slot is free.
+    /// The slot passes to the next waiter when the returned guard is dropped.
+    pub async fn acquire(&self, priority: i32) -> QueueSlot<'_> {
+        let rank = self.rank(priority);
+        let (seq, ready) = {
+            let mut state = self.state.lock().unwrap();
+            let seq = state.next_seq;
+            state.next_seq += 1;
+            if !state.busy && state.waiting.is_empty() {
+                state.busy = true;
+                return QueueSlot {
+                    queue: self,
+                    rank,
+                    seq,
+                };
+            }
+            let (ready, granted) = oneshot::channel();
+            state.waiting.push(Waiting { rank, seq, ready });
+            (seq, granted)
+        };
+
+        self.wait_for_grant(ready).await;
+        QueueSlot {
+            queue: self,
+            rank,
+            seq,
+        }
+    }
+
+    async fn wait_for_grant(&self, granted: oneshot::Receiver<()>) {
+        let mut pending = PendingSlot {
+            queue: self,
+            granted: Some(granted),
+        };
+        // The sender is only ever consumed by sending, so this can't fail
+        let _ = pending.granted.as_mut().unwrap().await;
+        pending.granted = None;
+    }
+
+    /// Tasks waiting for the slot, or parked until capacity frees up
+    pub fn waiting(&self) -> usize {
+        let state = self.state.lock().unwrap();
+        state.waiting.len() + state.parked.len()
+    }
+
+    /// Effective priority at `t` is `priority + (t - enqueued) / aging`. Every
+    /// waiter ages at the same rate, so ordering by `priority * aging - enqueued`
+    /// gives the same order at any `t` and the heap never needs rebuilding.
+    fn rank(&self, priority: i32) -> i128 {
+        match self.aging {
+            Some(aging) => {
+                let enqueued = self.epoch.elapsed().as_micros() as i128;
+                priority as i128 * aging.as_micros() as i128 - enqueued
+            }
+            None => priority as i128,
+        }
+    }
+
+    /// Hand the slot to the highest-ranked waiter still waiting, or free it
+    fn release(&self) {
+        grant_next(&mut self.state.lock().unwrap());
+    }
+}
+
+fn grant_next(state: &mut QueueState) {
+    while let Some(next) = state.waiting.pop() {
+        if next.ready.send(()).is_ok() {
+            return;
+        }
+    }
+    state.busy = false;
+}
+
+/// The right to dispatch, passed to the next waiter on drop
+pub struct QueueSlot<'a> {
+    queue: &'a TaskQueue,
+    rank: i128,
+    seq: u64,
+}
+
+impl<'a> QueueSlot<'a> {
+    /// Let waiters that outrank this task go first: if one is waiting, pass
+    /// it the slot and wait for the slot to come back, keeping this task's
+    /// place in the order
+    pub async fn yield_to_waiters(self) -> QueueSlot<'a> {
+        let (queue, rank, seq) = (self.queue, self.rank, self.seq);
+        let (ready, granted) = oneshot::channel();
+        let ours = Waiting { rank, seq, ready };
+        {
+            let mut state = queue.state.lock().unwrap();
+            if state.waiting.peek().is_none_or(|top| *top <= ours) {
+                return self;
+            }
+            state.waiting.push(ours);
+            grant_next(&mut state);
+        }
+        // Handed on above rather than released
+        std::mem::forget(self);
+
+        queue.wait_for_grant(granted).await;
+        QueueSlot { queue, rank, seq }
+    }
+    /// Give the slot up until `event` (e.g. a worker being released), then
+    /// wait for it again in this task's old place. Every task parked by then
+    /// rejoins at once, so the highest-ranked of them goes first.
+    pub async fn park_until<F: std::future::Future>(self, event: F) -> QueueSlot<'a> {
+        let (queue, rank, seq) = (self.queue, self.rank, self.seq);
+        let (ready, granted) = oneshot::channel();
+        {
+            let mut state = queue.state.lock().unwrap();
+            state.parked.push(Waiting { rank, seq, ready });
+            grant_next(&mut state);
+        }
+        // Handed on above rather than released
+        std::mem::forget(self);
+
+        // Once rejoined, a grant may arrive before this task waits for it
+        let mut pending = PendingSlot {
+            queue,
+            granted: Some(granted),
+        };
+        event.await;
+        {
+            let mut state = queue.state.lock().unwrap();
+            let parked = std::mem::take(&mut state.parked);
+            state.waiting.extend(parked);
+            if !state.busy {
+                state.busy = true;
+                grant_next(&mut state);
+            }
+        }
+        // The sender is only ever consumed by sending, so this can't fail
+        let _ = pending.granted.as_mut().unwrap().await;
+        pending.granted = None;
+        QueueSlot { queue, rank, seq }
+    }
+}
+
+impl Drop for QueueSlot<'_> {
+    fn drop(&mut self) {
+        self.queue.release();
+    }
+}
+
+/// A waiter whose request may be cancelled (timeout, client disconnect)
+/// before or just after being granted the slot
+struct PendingSlot<'a> {
+    queue: &'a TaskQueue,
+    granted: Option<oneshot::Receiver<()>>,
+}
+
+impl Drop for PendingSlot<'_> {
+    fn drop(&mut self) {
+        if let Some(mut granted) = self.granted.take() {
+            // Refuse any further grant; pass on one that already arrived
+            granted.close();
+            if granted.try_recv().is_ok() {
+                self.queue.release();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    /// Queue `tasks` (name, priority) behind a held slot, one millisecond
+    /// apart, and return the order they are granted the slot in
+    async fn dispatch_order(
+        queue: Arc<TaskQueue>,
+        tasks: &[(&'static str, i32)],
+    ) -> Vec<&'static str> {
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let held = queue.acquire(0).await;
+        let mut waiters = Vec::new();
+        for &(name, priority) in tasks {
+            let (queue, order) = (queue.clone(), order.clone());
+            waiters.push(tokio::spawn(async move {
+                let _slot = queue.acquire(priority).await;
+                order.lock().unwrap().push(name);
+            }));
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        drop(held);
+        for waiter in waiters {
+            waiter.await.unwrap();
+        }
+        Arc::try_unwrap(order).unwrap().into_inner().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_higher_priority_dispatched_first() {
+        let queue = Arc::new(TaskQueue::new(None));
+        let tasks = [
+            ("batch-1", 0),
+            ("interactive-1", 10),
+            ("batch-2", 0),
+            ("interactive-2", 10),
+        ];
+        let order = dispatch_order(queue, &tasks).await;
+        assert_eq!(
+            order,
+            vec!["interactive-1", "interactive-2", "batch-1", "batch-2"]
+        );
+    }
+
+    /// Where a batch task (priority 0) lands among a steady stream of 12
+    /// interactive tasks (priority 3) arriving every 20ms, each holding the
+    /// slot for 25ms so a backlog builds
+    async fn batch_position(aging: Option<Duration>) -> usize {
+        let queue = Arc::new(TaskQueue::new(aging));
+        let dispatched = Arc::new(Mutex::new(Vec::new()));
+
+        let mut held = Some(queue.acquire(0).await);
+        let batch = {
+            let (queue, dispatched) = (queue.clone(), dispatched.clone());
+            tokio::spawn(async move {
+                let _slot = queue.acquire(0).await;
+                dispatched.lock().unwrap().push("batch");
+            })
+        };
+        tokio::time::sleep(Duration::from_millis(5)).await;
+
+        let mut interactive = Vec::new();
+        for _ in 0..12 {
+            let (queue, dispatched) = (queue.clone(), dispatched.clone());
+            interactive.push(tokio::spawn(async move {
+                let _slot = queue.acquire(3).await;
+                dispatched.lock().unwrap().push("interactive");
+                tokio::time::sleep(Duration::from_millis(25)).await;
+            }));
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            held.take();
+        }
+        batch.await.unwrap();
+        for task in interactive {
+            task.await.unwrap();
+        }
+        assert_eq!(queue.waiting(), 0);
+
+        let dispatched = dispatched.lock().unwrap();
+        dispatched.iter().position(|&name| name == "batch").unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_low_priority_progresses_under_contention() {
+        // Without aging the batch task waits out the whole stream
+        assert_eq!(batch_position(None).await, 12);
+
+        // At one level per 50ms waited, it outranks arrivals ~150ms younger
+        let position = batch_position(Some(Duration::from_millis(50))).await;
+        assert!(
+            (3..11).contains(&position),
+            "batch task dispatched at position {}",
+            position
+        );
+    }
+
+    #[tokio::test]
+    async fn test_holder_yields_to_higher_priority_waiters() {
+        let queue = Arc::new(TaskQueue::new(None));
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let held = queue.acquire(1).await;
+
+        // Nobody waiting: the holder keeps the slot
+        let held = held.yield_to_waiters().await;
+
+        let mut waiters = Vec::new();
+        for (name, priority) in [("low", 0), ("high", 5)] {
+            let (queue, order) = (queue.clone(), order.clone());
+            waiters.push(tokio::spawn(async move {
+                let _slot = queue.acquire(priority).await;
+                order.lock().unwrap().push(name);
+            }));
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+
+        // Only the higher-priority waiter goes ahead of the holder
+        let held = held.yield_to_waiters().await;
+        order.lock().unwrap().push("holder");
+        drop(held);
+        for waiter in waiters {
+            waiter.await.unwrap();
+        }
+        assert_eq!(*order.lock().unwrap(), vec!["high", "holder", "low"]);
+    }
+
+    #[tokio::test]
+    async fn test_parked_tasks_rejoin_in_order() {
+        let queue = Arc::new(TaskQueue::new(None));
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let capacity = Arc::new(tokio::sync::Notify::new());
+
+        // Each takes the slot, finds no capacity and parks until some frees up
+        let mut parked = Vec::new();
+        for (name, priority) in [("low", 0), ("high", 5)] {
+            let (queue, order, capacity) = (queue.clone(), order.clone(), capacity.clone());
+            parked.push(tokio::spawn(async move {
+                let slot = queue.acquire(priority).await;
+                let freed = capacity.notified();
+                let _slot = slot.park_until(freed).await;
+                order.lock().unwrap().push(name);
+            }));
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+
+        // Meanwhile the slot is free for others
+        let other = tokio::time::timeout(Duration::from_secs(1), queue.acquire(0))
+            .await
+            .expect("slot held by a parked task");
+        order.lock().unwrap().push("other");
+        drop(other);
+
+        capacity.notify_waiters();
+        for task in parked {
+            task.await.unwrap();
+        }
+        assert_eq!(*order.lock().unwrap(), vec!["other", "high", "low"]);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_waiter_does_not_hold_the_slot() {
+        let queue = Arc::new(TaskQueue::new(None));
+        let held = queue.acquire(0).await;
+
+        let cancelled = tokio::spawn({
+            let queue = queue.clone();
+            async move {
+                let _slot = queue.acquire(5).await;
+            }
+        });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        cancelled.abort();
+        let _ = cancelled.await;
+        drop(held);
+
+        tokio::time::timeout(Duration::from_secs(1), queue.acquire(0))
+            .await
+            .expect("slot still held by a cancelled waiter");
+    }
+}
@@ -1,14 +1,24 @@
 use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU8, Ordering};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 
-/// Resource requirements for a task
+pub mod quantity;
+
+/// Resource requirements for a task. CPUs and memory also accept Kubernetes-style
+/// quantities ("500m", "512Mi", "2Gi") when deserialized.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct ResourceRequirements {
     /// CPUs required (logical cores, can be fractional)
+    #[serde(deserialize_with = "quantity::deserialize_cpus")]
     pub num_cpus: f64,
     /// GPUs required (devices, can be fractional)
     pub num_gpus: f64,
     /// Memory required in GB
+    #[serde(deserialize_with = "quantity::deserialize_memory_gb")]
     pub memory_gb: f64,
+    /// GPU memory (VRAM) required in GB (0.0 = no constraint)
+    #[serde(default, deserialize_with = "quantity::deserialize_memory_gb")]
+    pub gpu_memory_gb: f64,
 }
 
 impl Default for ResourceRequirements {
@@ -17,6 +27,18 @@ impl Default for ResourceRequirements {
             num_cpus: 1.0,
             num_gpus: 0.0,
             memory_gb: 1.0,
+            gpu_memory_gb: 0.0,
+        }
+    }
+}
+
+impl ResourceRequirements {
+    /// The same requirements without GPUs, for running on a CPU-only overflow pool
+    pub fn without_gpus(&self) -> Self {
+        Self {
+            num_gpus: 0.0,
+            gpu_memory_gb: 0.0,
+            ..self.clone()
         }
     }
 }
@@ -30,6 +52,9 @@ pub struct ResourceCapabilities {
     pub num_gpus: f64,
     /// Total memory in GB
     pub memory_gb: f64,
+    /// Total GPU memory (VRAM) in GB (None = not tracked, no constraint)
+    #[serde(default)]
+    pub gpu_memory_gb: Option<f64>,
 }
 
 impl Default for ResourceCapabilities {
@@ -38,12 +63,67 @@ impl Default for ResourceCapabilities {
             num_cpus: 1.0,
             num_gpus: 0.0,
             memory_gb: 4.0,
+            gpu_memory_gb: None,
+        }
+    }
+}
+
+/// Payload encoding on the worker socket. JSON makes the traffic readable
+/// (e.g. with `socat`) when debugging a worker; msgpack is the default.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WireFormat {
+    #[default]
+    Msgpack,
+    Json,
+}
+
+impl WireFormat {
+    /// Environment variable that overrides the configured format; workers are
+    /// spawned with it set so both sides agree
+    pub const ENV_VAR: &'static str = "NEUTRINO_WIRE_FORMAT";
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            WireFormat::Msgpack => "msgpack",
+            WireFormat::Json => "json",
         }
     }
 }
 
+impl std::str::FromStr for WireFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "msgpack" => Ok(WireFormat::Msgpack),
+            "json" => Ok(WireFormat::Json),
+            other => Err(format!(
+                "unknown wire format '{}' (expected msgpack or json)",
+                other
+            )),
+        }
+    }
+}
+
+static WIRE_FORMAT: AtomicU8 = AtomicU8::new(WireFormat::Msgpack as u8);
+
+/// Set the format used by `Message::to_bytes`/`from_bytes` for this process
+pub fn set_wire_format(format: WireFormat) {
+    WIRE_FORMAT.store(format as u8, Ordering::Relaxed);
+}
+
+/// The format used by `Message::to_bytes`/`from_bytes` for this process
+pub fn wire_format() -> WireFormat {
+    match WIRE_FORMAT.load(Ordering::Relaxed) {
+        x if x == WireFormat::Json as u8 => WireFormat::Json,
+        _ => WireFormat::Msgpack,
+    }
+}
+
 /// Messages exchanged between orchestrator and workers via Unix socket.
-/// Wire format: [4 bytes: big-endian length][N bytes: msgpack payload]
+/// Wire format: [4 bytes: big-endian length][N bytes: payload], where the payload
+/// is msgpack or, with `WireFormat::Json`, JSON
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Message {
     /// Worker announces it's ready to receive tasks
@@ -51,6 +131,10 @@ pub enum Message {
         worker_id: String,
         pid: u32,
         capabilities: ResourceCapabilities,
+        /// Whether the worker stops a task part-way on `CancelTask`; workers
+        /// that run every task to completion leave it false
+        #[serde(default)]
+        cancellable: bool,
     },
 
     /// Orchestrator assigns a task to a worker
@@ -59,6 +143,14 @@ pub enum Message {
         function_name: String,
         args: rmpv::Value, // Native msgpack value (encoded once with entire message)
         resources: ResourceRequirements,
+        /// Time left before the orchestrator times the task out (None = no timeout),
+        /// so handlers can stop early on their own terms
+        #[serde(default)]
+        deadline_ms_remaining: Option<u64>,
+        /// Priority the task was dispatched at (route default or request header),
+        /// for workers that queue tasks internally
+        #[serde(default)]
+        priority: i32,
     },
 
     /// Worker reports task completion
@@ -68,21 +160,166 @@ pub enum Message {
         result: rmpv::Value, // Native msgpack value (encoded once with entire message)
     },
 
+    /// Orchestrator abandons a task (cancelled, timed out, or its client went
+    /// away); workers able to stop it early should. The worker still answers
+    /// with a `TaskResult` for it (which is discarded), and isn't sent more
+    /// work until then.
+    CancelTask { task_id: String },
+
     /// Orchestrator requests worker shutdown
     Shutdown { graceful: bool },
 
-    /// Heartbeat for health checking
-    Heartbeat { worker_id: String },
+    /// Heartbeat for health checking. Workers reply with (or volunteer) their
+    /// internal queue depth: tasks accepted but not yet finished.
+    Heartbeat {
+        worker_id: String,
+        #[serde(default)]
+        queue_depth: Option<u32>,
+    },
+
+    /// Orchestrator asks the worker to finish any internally queued work before shutdown
+    DrainRequest { timeout_secs: u64 },
+
+    /// Worker reports work still pending while draining
+    DrainStatus { worker_id: String, pending: u32 },
+
+    /// Worker reports it has no pending work and is safe to shut down
+    DrainComplete { worker_id: String },
+
+    /// Orchestrator asks the worker which handlers its app module provides
+    ListHandlers {},
+
+    /// Worker reports the handler names it can execute
+    HandlerList {
+        worker_id: String,
+        handlers: Vec<String>,
+    },
+
+    /// Worker revises its capabilities at runtime (e.g. less free GPU memory
+    /// than at startup); capped at what the worker was provisioned with
+    UpdateCapabilities { capabilities: ResourceCapabilities },
 }
 
 impl Message {
-    /// Serialize message to msgpack bytes
-    pub fn to_bytes(&self) -> Result<Vec<u8>, rmp_serde::encode::Error> {
-        rmp_serde::to_vec(self)
+    /// Serialize message in the process's configured wire format
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+        self.encode(wire_format())
+    }
+
+    /// Deserialize message from the process's configured wire format
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        Self::decode(bytes, wire_format())
+    }
+
+    /// Serialize message in the given wire format
+    pub fn encode(
+        &self,
+        format: WireFormat,
+    ) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(match format {
+            WireFormat::Msgpack => rmp_serde::to_vec(self)?,
+            WireFormat::Json => serde_json::to_vec(self)?,
+        })
+    }
+
+    /// Deserialize message from the given wire format
+    pub fn decode(
+        bytes: &[u8],
+        format: WireFormat,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(match format {
+            WireFormat::Msgpack => rmp_serde::from_slice(bytes)?,
+            WireFormat::Json => serde_json::from_slice(bytes)?,
+        })
     }
+}
+
+/// Write a length-prefixed message to a stream
+pub async fn write_message<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    msg: &Message,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let payload = msg.to_bytes()?;
+    let len = (payload.len() as u32).to_be_bytes();
+
+    writer.write_all(&len).await?;
+    writer.write_all(&payload).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+/// Read a length-prefixed message from a stream
+pub async fn read_message<R: AsyncRead + Unpin>(
+    reader: &mut R,
+) -> Result<Message, Box<dyn std::error::Error + Send + Sync>> {
+    let mut len_buf = [0u8; 4];
+    reader.read_exact(&mut len_buf).await?;
+    let len = u32::from_be_bytes(len_buf) as usize;
+
+    let mut payload = vec![0u8; len];
+    reader.read_exact(&mut payload).await?;
+
+    Message::from_bytes(&payload)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_wire_format_round_trip() {
+        let msg = Message::TaskAssignment {
+            task_id: "task-1".to_string(),
+            function_name: "embed".to_string(),
+            args: rmpv::Value::Map(vec![(
+                rmpv::Value::from("text"),
+                rmpv::Value::from("hello"),
+            )]),
+            resources: ResourceRequirements::default(),
+            deadline_ms_remaining: Some(500),
+            priority: 5,
+        };
+
+        let bytes = msg.encode(WireFormat::Json).unwrap();
+        let text = std::str::from_utf8(&bytes).unwrap();
+        assert!(
+            text.starts_with(r#"{"TaskAssignment":{"task_id":"task-1""#),
+            "{}",
+            text
+        );
+
+        match Message::decode(&bytes, WireFormat::Json).unwrap() {
+            Message::TaskAssignment {
+                task_id,
+                function_name,
+                args,
+                resources,
+                deadline_ms_remaining,
+                priority,
+            } => {
+                assert_eq!(task_id, "task-1");
+                assert_eq!(function_name, "embed");
+                assert_eq!(
+                    args,
+                    rmpv::Value::Map(vec![("text".into(), "hello".into())])
+                );
+                assert_eq!(resources, ResourceRequirements::default());
+                assert_eq!(deadline_ms_remaining, Some(500));
+                assert_eq!(priority, 5);
+            }
+            other => panic!("unexpected message {:?}", other),
+        }
 
-    /// Deserialize message from msgpack bytes
-    pub fn from_bytes(bytes: &[u8]) -> Result<Self, rmp_serde::decode::Error> {
-        rmp_serde::from_slice(bytes)
+        // Python sends dicts in either format; msgpack stays the default
+        let heartbeat = br#"{"Heartbeat":{"worker_id":"default-0","queue_depth":2}}"#;
+        assert!(matches!(
+            Message::decode(heartbeat, WireFormat::Json).unwrap(),
+            Message::Heartbeat {
+                queue_depth: Some(2),
+                ..
+            }
+        ));
+        assert_eq!("JSON".parse::<WireFormat>(), Ok(WireFormat::Json));
+        assert_eq!(WireFormat::default(), WireFormat::Msgpack);
     }
 }
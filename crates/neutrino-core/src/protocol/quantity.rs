@@ -0,0 +1,175 @@
+//! Kubernetes-style resource quantities for resource requirements, so specs can
+//! say `memory_gb: "512Mi"` or `num_cpus: "500m"` as well as plain numbers.
+//!
+//! Memory is canonically GB as measured by the scheduler (GiB, 2^30 bytes), so
+//! `"2Gi"` is 2.0 and `"1G"` (10^9 bytes) is about 0.93.
+
+use serde::{Deserialize, Deserializer};
+
+const GIB: f64 = (1u64 << 30) as f64;
+
+/// Parse a memory quantity into GB: a plain number (already GB) or a number with
+/// a binary (Ki, Mi, Gi, Ti) or decimal (k, K, M, G, T) suffix
+pub fn parse_memory_gb(quantity: &str) -> Result<f64, String> {
+    let (number, suffix) = split_quantity(quantity)?;
+    let bytes_per_unit = match suffix {
+        "" => return Ok(number),
+        "Ki" => 1024.0,
+        "Mi" => 1024.0 * 1024.0,
+        "Gi" => GIB,
+        "Ti" => GIB * 1024.0,
+        "k" | "K" => 1e3,
+        "M" => 1e6,
+        "G" => 1e9,
+        "T" => 1e12,
+        _ => {
+            return Err(format!(
+                "unknown memory unit '{}' in '{}' (expected Ki, Mi, Gi, Ti, K, M, G or T)",
+                suffix, quantity
+            ))
+        }
+    };
+    Ok(number * bytes_per_unit / GIB)
+}
+
+/// Parse a CPU quantity: a plain number of cores or millicores (`"500m"`)
+pub fn parse_cpus(quantity: &str) -> Result<f64, String> {
+    let (number, suffix) = split_quantity(quantity)?;
+    match suffix {
+        "" => Ok(number),
+        "m" => Ok(number / 1000.0),
+        _ => Err(format!(
+            "unknown CPU unit '{}' in '{}' (expected cores or millicores like 500m)",
+            suffix, quantity
+        )),
+    }
+}
+
+/// Split `"1.5Gi"` into `(1.5, "Gi")`, rejecting missing, negative, or non-finite numbers
+fn split_quantity(quantity: &str) -> Result<(f64, &str), String> {
+    let quantity = quantity.trim();
+    let split = quantity
+        .find(|c: char| c.is_ascii_alphabetic())
+        .unwrap_or(quantity.len());
+    let (number, suffix) = quantity.split_at(split);
+
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("invalid quantity '{}'", quantity))?;
+    if !number.is_finite() || number < 0.0 {
+        return Err(format!("invalid quantity '{}'", quantity));
+    }
+    Ok((number, suffix))
+}
+
+/// A quantity as written in a spec: a number or a string with a unit suffix
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Quantity {
+    Number(f64),
+    Text(String),
+}
+
+/// serde `deserialize_with` for memory fields in GB
+pub fn deserialize_memory_gb<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    match Quantity::deserialize(deserializer)? {
+        Quantity::Number(gb) => Ok(gb),
+        Quantity::Text(text) => parse_memory_gb(&text).map_err(serde::de::Error::custom),
+    }
+}
+
+/// serde `deserialize_with` for CPU fields in cores
+pub fn deserialize_cpus<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    match Quantity::deserialize(deserializer)? {
+        Quantity::Number(cpus) => Ok(cpus),
+        Quantity::Text(text) => parse_cpus(&text).map_err(serde::de::Error::custom),
+    }
+}
+
+/// `deserialize_memory_gb` for optional fields (use with `#[serde(default)]`)
+pub fn deserialize_optional_memory_gb<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<f64>, D::Error> {
+    deserialize_memory_gb(deserializer).map(Some)
+}
+
+/// `deserialize_cpus` for optional fields (use with `#[serde(default)]`)
+pub fn deserialize_optional_cpus<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<f64>, D::Error> {
+    deserialize_cpus(deserializer).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::ResourceRequirements;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn test_memory_suffixes() {
+        assert_close(parse_memory_gb("512Mi").unwrap(), 0.5);
+        assert_close(parse_memory_gb("2Gi").unwrap(), 2.0);
+        assert_close(parse_memory_gb("1.5G").unwrap(), 1.5e9 / GIB);
+        assert_close(parse_memory_gb("4").unwrap(), 4.0);
+    }
+
+    #[test]
+    fn test_millicpu() {
+        assert_close(parse_cpus("500m").unwrap(), 0.5);
+        assert_close(parse_cpus("2").unwrap(), 2.0);
+        assert_close(parse_cpus("1.5").unwrap(), 1.5);
+    }
+
+    #[test]
+    fn test_garbage_rejected() {
+        for garbage in ["", "lots", "2Gb", "Gi", "-1Gi", "inf", "1.2.3Mi"] {
+            assert!(
+                parse_memory_gb(garbage).is_err(),
+                "accepted memory {:?}",
+                garbage
+            );
+        }
+        for garbage in ["", "2 cores", "500Mi", "m", "-500m"] {
+            assert!(parse_cpus(garbage).is_err(), "accepted cpus {:?}", garbage);
+        }
+
+        let spec = serde_json::json!({"num_cpus": 1, "num_gpus": 0, "memory_gb": "lots"});
+        assert!(serde_json::from_value::<ResourceRequirements>(spec).is_err());
+    }
+
+    #[test]
+    fn test_requirements_accept_quantities() {
+        let spec = serde_json::json!({
+            "num_cpus": "250m",
+            "num_gpus": 1,
+            "memory_gb": "512Mi",
+            "gpu_memory_gb": "8Gi",
+        });
+        let resources: ResourceRequirements = serde_json::from_value(spec).unwrap();
+        assert_eq!(
+            resources,
+            ResourceRequirements {
+                num_cpus: 0.25,
+                num_gpus: 1.0,
+                memory_gb: 0.5,
+                gpu_memory_gb: 8.0
+            }
+        );
+
+        // Still plain numbers on the wire to workers
+        let bytes = rmp_serde::to_vec(&resources).unwrap();
+        assert_eq!(
+            rmp_serde::from_slice::<ResourceRequirements>(&bytes).unwrap(),
+            resources
+        );
+    }
+}
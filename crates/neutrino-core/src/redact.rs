@@ -0,0 +1,113 @@
+//! Redaction of secret fields (tokens, passwords, PII) in JSON before it is logged.
+
+use serde_json::Value;
+
+/// Replacement for redacted values
+pub const REDACTED: &str = "***";
+
+/// Field names redacted when no list is configured
+pub fn default_redact_fields() -> Vec<String> {
+    ["password", "token", "secret", "api_key", "authorization"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect()
+}
+
+/// Replace the value of every object field named in `fields` (case-insensitive),
+/// at any depth, with `REDACTED`
+pub fn redact_json(value: &mut Value, fields: &[String]) {
+    match value {
+        Value::Object(map) => {
+            for (key, field) in map.iter_mut() {
+                if fields.iter().any(|f| f.eq_ignore_ascii_case(key)) {
+                    *field = Value::String(REDACTED.to_string());
+                } else {
+                    redact_json(field, fields);
+                }
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                redact_json(item, fields);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Redact a JSON document given as text; text that isn't JSON is returned as is
+pub fn redact_json_text(text: &str, fields: &[String]) -> String {
+    if fields.is_empty() {
+        return text.to_string();
+    }
+    match serde_json::from_str::<Value>(text) {
+        Ok(mut value) => {
+            redact_json(&mut value, fields);
+            value.to_string()
+        }
+        Err(_) => text.to_string(),
+    }
+}
+
+/// Redacted JSON rendering of `value`, cut to at most `max_chars` characters
+pub fn preview(value: &Value, fields: &[String], max_chars: usize) -> String {
+    let mut value = value.clone();
+    redact_json(&mut value, fields);
+    truncate_chars(&value.to_string(), max_chars)
+}
+
+/// Cut `text` to at most `max_chars` characters, marking the cut
+pub fn truncate_chars(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((end, _)) => format!("{}... (truncated)", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_secret_fields_redacted_recursively() {
+        let args = json!({
+            "prompt": "hello",
+            "API_KEY": "sk-123",
+            "user": {"name": "ada", "password": "hunter2"},
+            "history": [{"token": "abc", "text": "hi"}],
+        });
+
+        let preview = preview(&args, &default_redact_fields(), 1000);
+        let redacted: Value = serde_json::from_str(&preview).unwrap();
+        assert_eq!(
+            redacted,
+            json!({
+                "prompt": "hello",
+                "API_KEY": "***",
+                "user": {"name": "ada", "password": "***"},
+                "history": [{"token": "***", "text": "hi"}],
+            })
+        );
+        assert!(!preview.contains("hunter2") && !preview.contains("sk-123"));
+
+        let body = redact_json_text(
+            r#"{"secret": {"nested": 1}, "n": 2}"#,
+            &["secret".to_string()],
+        );
+        assert_eq!(
+            serde_json::from_str::<Value>(&body).unwrap(),
+            json!({"secret": "***", "n": 2})
+        );
+        assert_eq!(
+            redact_json_text("not json", &["secret".to_string()]),
+            "not json"
+        );
+    }
+
+    #[test]
+    fn test_preview_truncated_on_char_boundary() {
+        let preview = preview(&json!({"text": "héllo wörld"}), &[], 12);
+        assert_eq!(preview, r#"{"text":"hél... (truncated)"#);
+    }
+}
@@ -0,0 +1,278 @@
+//! Storage for async task results, polled via `GET /tasks/:task_id/result`.
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::info;
+
+/// Lifecycle of an async task
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResultStatus {
+    Pending,
+    Completed,
+    Failed,
+}
+
+impl ResultStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ResultStatus::Pending => "pending",
+            ResultStatus::Completed => "completed",
+            ResultStatus::Failed => "failed",
+        }
+    }
+
+    fn parse(s: &str) -> Self {
+        match s {
+            "pending" => ResultStatus::Pending,
+            "completed" => ResultStatus::Completed,
+            _ => ResultStatus::Failed,
+        }
+    }
+}
+
+/// A stored async task result
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoredResult {
+    pub status: ResultStatus,
+    /// The task response body (absent while pending)
+    pub response: Option<serde_json::Value>,
+}
+
+/// Pluggable storage for async task results. Entries expire after the store's TTL.
+pub trait ResultStore: Send + Sync {
+    fn put(&self, task_id: &str, result: StoredResult) -> Result<(), String>;
+    fn get(&self, task_id: &str) -> Result<Option<StoredResult>, String>;
+}
+
+/// Store a result from async code. Stores may do blocking disk I/O, so the
+/// call runs on the blocking thread pool.
+pub async fn put(
+    store: &Arc<dyn ResultStore>,
+    task_id: &str,
+    result: StoredResult,
+) -> Result<(), String> {
+    let store = Arc::clone(store);
+    let task_id = task_id.to_string();
+    tokio::task::spawn_blocking(move || store.put(&task_id, result))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Look up a result from async code, on the blocking thread pool like [`put`]
+pub async fn get(
+    store: &Arc<dyn ResultStore>,
+    task_id: &str,
+) -> Result<Option<StoredResult>, String> {
+    let store = Arc::clone(store);
+    let task_id = task_id.to_string();
+    tokio::task::spawn_blocking(move || store.get(&task_id))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// In-memory store; results are lost on restart
+pub struct MemoryResultStore {
+    ttl: Duration,
+    entries: Mutex<HashMap<String, (u64, StoredResult)>>,
+}
+
+impl MemoryResultStore {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl ResultStore for MemoryResultStore {
+    fn put(&self, task_id: &str, result: StoredResult) -> Result<(), String> {
+        let now = unix_now();
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, (expires_at, _)| *expires_at > now);
+        entries.insert(task_id.to_string(), (now + self.ttl.as_secs(), result));
+        Ok(())
+    }
+
+    fn get(&self, task_id: &str) -> Result<Option<StoredResult>, String> {
+        let entries = self.entries.lock().unwrap();
+        Ok(entries
+            .get(task_id)
+            .filter(|(expires_at, _)| *expires_at > unix_now())
+            .map(|(_, result)| result.clone()))
+    }
+}
+
+/// SQLite-backed store so results survive orchestrator restarts
+pub struct SqliteResultStore {
+    ttl: Duration,
+    conn: Mutex<Connection>,
+}
+
+impl SqliteResultStore {
+    /// Open (or create) the store. Tasks still pending from a previous run can
+    /// never complete, so they are marked failed.
+    pub fn open<P: AsRef<Path>>(path: P, ttl: Duration) -> rusqlite::Result<Self> {
+        if let Some(parent) = path.as_ref().parent() {
+            std::fs::create_dir_all(parent).ok();
+        }
+
+        let conn = Connection::open(path.as_ref())?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS task_results (
+                task_id TEXT PRIMARY KEY,
+                status TEXT NOT NULL,
+                response TEXT,
+                expires_at INTEGER NOT NULL
+            )",
+            [],
+        )?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_task_results_expires_at ON task_results(expires_at)",
+            [],
+        )?;
+
+        let interrupted = conn.execute(
+            "UPDATE task_results SET status = 'failed', response = ?1 WHERE status = 'pending'",
+            params![serde_json::json!({
+                "success": false,
+                "error": "Orchestrator restarted before the task completed",
+            })
+            .to_string()],
+        )?;
+        if interrupted > 0 {
+            info!("Marked {} interrupted async task(s) as failed", interrupted);
+        }
+
+        Ok(Self {
+            ttl,
+            conn: Mutex::new(conn),
+        })
+    }
+}
+
+impl ResultStore for SqliteResultStore {
+    fn put(&self, task_id: &str, result: StoredResult) -> Result<(), String> {
+        let now = unix_now();
+        let conn = self.conn.lock().unwrap();
+
+        conn.execute(
+            "DELETE FROM task_results WHERE expires_at <= ?1",
+            params![now],
+        )
+        .map_err(|e| e.to_string())?;
+        conn.execute(
+            "INSERT OR REPLACE INTO task_results (task_id, status, response, expires_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![
+                task_id,
+                result.status.as_str(),
+                result.response.map(|r| r.to_string()),
+                now + self.ttl.as_secs(),
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    fn get(&self, task_id: &str) -> Result<Option<StoredResult>, String> {
+        let conn = self.conn.lock().unwrap();
+        let row: Option<(String, Option<String>)> = conn
+            .query_row(
+                "SELECT status, response FROM task_results WHERE task_id = ?1 AND expires_at > ?2",
+                params![task_id, unix_now()],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+            .map_err(|e| e.to_string())?;
+
+        row.map(|(status, response)| {
+            let response = response
+                .map(|r| serde_json::from_str(&r))
+                .transpose()
+                .map_err(|e| e.to_string())?;
+            Ok(StoredResult {
+                status: ResultStatus::parse(&status),
+                response,
+            })
+        })
+        .transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_db() -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("neutrino-results-{}.db", uuid::Uuid::new_v4()))
+    }
+
+    fn completed(value: serde_json::Value) -> StoredResult {
+        StoredResult {
+            status: ResultStatus::Completed,
+            response: Some(serde_json::json!({"success": true, "result": value})),
+        }
+    }
+
+    #[test]
+    fn test_result_survives_restart() {
+        let path = temp_db();
+        let ttl = Duration::from_secs(60);
+
+        let store = SqliteResultStore::open(&path, ttl).unwrap();
+        store
+            .put("done", completed(serde_json::json!({"x": 1})))
+            .unwrap();
+        store
+            .put(
+                "in-flight",
+                StoredResult {
+                    status: ResultStatus::Pending,
+                    response: None,
+                },
+            )
+            .unwrap();
+        drop(store);
+
+        // "Restart": reopen the store from the same path
+        let store = SqliteResultStore::open(&path, ttl).unwrap();
+        assert_eq!(
+            store.get("done").unwrap(),
+            Some(completed(serde_json::json!({"x": 1})))
+        );
+        assert_eq!(
+            store.get("in-flight").unwrap().unwrap().status,
+            ResultStatus::Failed
+        );
+        assert_eq!(store.get("unknown").unwrap(), None);
+
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_expired_results_not_returned() {
+        let path = temp_db();
+        let store = SqliteResultStore::open(&path, Duration::ZERO).unwrap();
+        store.put("old", completed(serde_json::json!(1))).unwrap();
+        assert_eq!(store.get("old").unwrap(), None);
+
+        let memory = MemoryResultStore::new(Duration::ZERO);
+        memory.put("old", completed(serde_json::json!(1))).unwrap();
+        assert_eq!(memory.get("old").unwrap(), None);
+
+        let _ = std::fs::remove_file(path);
+    }
+}
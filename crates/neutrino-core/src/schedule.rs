@@ -0,0 +1,210 @@
+//! Cron expressions for scheduled handler calls. Five fields, evaluated in
+//! UTC: minute (0-59), hour (0-23), day of month (1-31), month (1-12) and day
+//! of week (0-6 from Sunday; 7 is Sunday too). Each field is `*`, a value, a
+//! range `a-b`, any of those with a step (`*/15`, `1-10/3`), or a
+//! comma-separated list of them. As in cron, when both day fields are
+//! restricted a day matching either one runs.
+
+use std::fmt;
+
+/// A parsed cron expression
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cron {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    /// Whether the day of month / day of week fields were `*`
+    any_day: bool,
+    any_weekday: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CronError(String);
+
+impl fmt::Display for CronError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid cron expression: {}", self.0)
+    }
+}
+
+/// Give up looking for a matching minute after this many days (covers
+/// expressions like "0 0 29 2 *" that match once in four years)
+const MAX_SEARCH_DAYS: i64 = 366 * 5;
+
+impl Cron {
+    pub fn parse(expression: &str) -> Result<Self, CronError> {
+        let expression = match expression.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            other => other,
+        };
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(CronError(format!(
+                "expected 5 fields, got {}",
+                fields.len()
+            )));
+        };
+
+        let mut weekdays = parse_field(weekday, 0, 7)?;
+        if weekdays & (1 << 7) != 0 {
+            weekdays = (weekdays | 1) & !(1 << 7);
+        }
+        Ok(Self {
+            minutes: parse_field(minute, 0, 59)?,
+            hours: parse_field(hour, 0, 23)?,
+            days: parse_field(day, 1, 31)?,
+            months: parse_field(month, 1, 12)?,
+            weekdays,
+            any_day: day == "*",
+            any_weekday: weekday == "*",
+        })
+    }
+
+    /// The first matching minute strictly after `unix_secs`, in Unix seconds
+    pub fn next_after(&self, unix_secs: u64) -> Option<u64> {
+        let mut minute = (unix_secs / 60 + 1) as i64;
+        let last_day = minute / 1440 + MAX_SEARCH_DAYS;
+        while minute / 1440 <= last_day {
+            let days = minute / 1440;
+            if !self.day_matches(days) {
+                minute = (days + 1) * 1440;
+                continue;
+            }
+            let hour = minute % 1440 / 60;
+            if self.hours & (1 << hour) == 0 {
+                minute = (minute / 60 + 1) * 60;
+                continue;
+            }
+            if self.minutes & (1 << (minute % 60)) != 0 {
+                return Some(minute as u64 * 60);
+            }
+            minute += 1;
+        }
+        None
+    }
+
+    fn day_matches(&self, days: i64) -> bool {
+        let (_, month, day) = civil_from_days(days);
+        if self.months & (1 << month) == 0 {
+            return false;
+        }
+        let weekday = (days + 4).rem_euclid(7); // 1970-01-01 was a Thursday
+        let day_ok = self.days & (1 << day) != 0;
+        let weekday_ok = self.weekdays & (1 << weekday) != 0;
+        match (self.any_day, self.any_weekday) {
+            (false, false) => day_ok || weekday_ok,
+            _ => day_ok && weekday_ok,
+        }
+    }
+}
+
+/// Bitset of the values a field allows
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64, CronError> {
+    let invalid = || CronError(format!("bad field '{}' (values {}-{})", field, min, max));
+    let value = |s: &str| {
+        s.parse::<u32>()
+            .ok()
+            .filter(|v| (min..=max).contains(v))
+            .ok_or_else(invalid)
+    };
+
+    let mut allowed = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (
+                range,
+                step.parse::<u32>()
+                    .ok()
+                    .filter(|&s| s > 0)
+                    .ok_or_else(invalid)?,
+            ),
+            None => (part, 1),
+        };
+        let (first, last) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((first, last)) => (value(first)?, value(last)?),
+                // "5/10" means from 5 to the end in steps of 10
+                None if step > 1 => (value(range)?, max),
+                None => (value(range)?, value(range)?),
+            },
+        };
+        if first > last {
+            return Err(invalid());
+        }
+        for v in (first..=last).step_by(step as usize) {
+            allowed |= 1 << v;
+        }
+    }
+    Ok(allowed)
+}
+
+/// (year, month, day) of a count of days since 1970-01-01 (Howard Hinnant's
+/// days-to-civil algorithm)
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2026-10-18T10:07:30Z, a Sunday
+    const NOW: u64 = 1_792_318_050;
+
+    #[test]
+    fn test_civil_from_days() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days((NOW / 86_400) as i64), (2026, 10, 18));
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+    }
+
+    #[test]
+    fn test_next_after() {
+        let next = |expression: &str| Cron::parse(expression).unwrap().next_after(NOW).unwrap();
+        let at = |day: u64, hour: u64, minute: u64| {
+            NOW - NOW % 86_400 + day * 86_400 + hour * 3600 + minute * 60
+        };
+
+        assert_eq!(next("* * * * *"), at(0, 10, 8));
+        assert_eq!(next("*/15 * * * *"), at(0, 10, 15));
+        assert_eq!(next("5 * * * *"), at(0, 11, 5));
+        assert_eq!(next("@daily"), at(1, 0, 0));
+        assert_eq!(next("30 9 * * 1-5"), at(1, 9, 30));
+        assert_eq!(next("0 12 * * 7"), at(0, 12, 0));
+        // Either day field matches: the 20th or a Wednesday, whichever is first
+        assert_eq!(next("0 0 20 * 3"), at(2, 0, 0));
+        assert_eq!(next("0 0 1 1 *"), at(75, 0, 0));
+        assert_eq!(Cron::parse("0 0 31 2 *").unwrap().next_after(NOW), None);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for expression in [
+            "* * * *",
+            "60 * * * *",
+            "* * 0 * *",
+            "*/0 * * * *",
+            "5-1 * * * *",
+            "a * * * *",
+        ] {
+            assert!(Cron::parse(expression).is_err(), "{}", expression);
+        }
+        assert!(Cron::parse("0,30 8-18/2 1,15 */3 0").is_ok());
+    }
+}
@@ -0,0 +1,122 @@
+//! Conversion between JSON (HTTP requests and responses) and msgpack values
+//! (the worker protocol). JSON has no NaN or Infinity, so how non-finite
+//! floats in worker results are rendered is configurable
+//! (`tasks.non_finite_floats`); request bodies can't contain them.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+/// How NaN and ±Infinity in msgpack values are written as JSON
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum NonFiniteFloats {
+    /// `null`
+    #[default]
+    Null,
+    /// The strings "NaN", "Infinity", and "-Infinity" (the names Python's
+    /// `float()` accepts back)
+    String,
+    /// Fail the conversion, leaving the result to `unconvertible_results`
+    Error,
+}
+
+/// Convert serde_json::Value to rmpv::Value
+pub fn json_to_msgpack_value(json: &JsonValue) -> Result<rmpv::Value, String> {
+    match json {
+        JsonValue::Null => Ok(rmpv::Value::Nil),
+        JsonValue::Bool(b) => Ok(rmpv::Value::Boolean(*b)),
+        JsonValue::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                Ok(rmpv::Value::Integer(i.into()))
+            } else if let Some(f) = n.as_f64() {
+                Ok(rmpv::Value::F64(f))
+            } else {
+                Err("Invalid number".to_string())
+            }
+        }
+        JsonValue::String(s) => Ok(rmpv::Value::String(s.clone().into())),
+        JsonValue::Array(arr) => {
+            let values: Result<Vec<_>, _> = arr.iter().map(json_to_msgpack_value).collect();
+            Ok(rmpv::Value::Array(values?))
+        }
+        JsonValue::Object(obj) => {
+            let pairs: Result<Vec<(rmpv::Value, rmpv::Value)>, String> = obj
+                .iter()
+                .map(|(k, v)| {
+                    Ok((
+                        rmpv::Value::String(k.clone().into()),
+                        json_to_msgpack_value(v)?,
+                    ))
+                })
+                .collect();
+            Ok(rmpv::Value::Map(pairs?))
+        }
+    }
+}
+
+/// Convert rmpv::Value to serde_json::Value
+pub fn msgpack_value_to_json(
+    msgpack: &rmpv::Value,
+    non_finite: NonFiniteFloats,
+) -> Result<JsonValue, String> {
+    match msgpack {
+        rmpv::Value::Nil => Ok(JsonValue::Null),
+        rmpv::Value::Boolean(b) => Ok(JsonValue::Bool(*b)),
+        rmpv::Value::Integer(i) => {
+            if let Some(val) = i.as_i64() {
+                Ok(serde_json::json!(val))
+            } else if let Some(val) = i.as_u64() {
+                Ok(serde_json::json!(val))
+            } else {
+                Err("Integer out of range".to_string())
+            }
+        }
+        rmpv::Value::F32(f) if f.is_finite() => Ok(serde_json::json!(*f)),
+        rmpv::Value::F32(f) => non_finite_to_json(*f as f64, non_finite),
+        rmpv::Value::F64(f) if f.is_finite() => Ok(serde_json::json!(*f)),
+        rmpv::Value::F64(f) => non_finite_to_json(*f, non_finite),
+        rmpv::Value::String(s) => Ok(JsonValue::String(
+            s.as_str().ok_or("Invalid UTF-8")?.to_string(),
+        )),
+        rmpv::Value::Binary(b) => {
+            // Convert binary to array of numbers for JSON compatibility
+            Ok(JsonValue::Array(
+                b.iter().map(|&byte| serde_json::json!(byte)).collect(),
+            ))
+        }
+        rmpv::Value::Array(arr) => {
+            let values: Result<Vec<_>, _> = arr
+                .iter()
+                .map(|value| msgpack_value_to_json(value, non_finite))
+                .collect();
+            Ok(JsonValue::Array(values?))
+        }
+        rmpv::Value::Map(map) => {
+            let mut obj = serde_json::Map::new();
+            for (k, v) in map {
+                let key = match k {
+                    rmpv::Value::String(s) => s.as_str().ok_or("Invalid UTF-8")?.to_string(),
+                    _ => return Err("Map keys must be strings".to_string()),
+                };
+                obj.insert(key, msgpack_value_to_json(v, non_finite)?);
+            }
+            Ok(JsonValue::Object(obj))
+        }
+        rmpv::Value::Ext(_, _) => Err("Extension types not supported".to_string()),
+    }
+}
+
+fn non_finite_to_json(f: f64, non_finite: NonFiniteFloats) -> Result<JsonValue, String> {
+    let name = if f.is_nan() {
+        "NaN"
+    } else if f > 0.0 {
+        "Infinity"
+    } else {
+        "-Infinity"
+    };
+    match non_finite {
+        NonFiniteFloats::Null => Ok(JsonValue::Null),
+        NonFiniteFloats::String => Ok(JsonValue::String(name.to_string())),
+        NonFiniteFloats::Error => Err(format!("{} has no JSON representation", name)),
+    }
+}
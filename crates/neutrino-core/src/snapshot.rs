@@ -0,0 +1,69 @@
+//! Post-mortem snapshot of in-memory state. Metrics and worker status live
+//! only in memory, so with `orchestrator.snapshot_path` set they are written
+//! to a JSON file on graceful shutdown. The next run loads the file at startup
+//! and serves it at `GET /admin/snapshot`, so a restart doesn't erase what the
+//! previous run saw before it went down.
+//! The task registry (`GET /tasks/{task_id}`) isn't included: shutdown lets
+//! requests in flight finish first (up to `http.shutdown_timeout_secs`), so
+//! their outcomes are already in the handler stats, and tasks still running
+//! after that are dropped unrecorded.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::warn;
+
+use crate::metrics::HandlerStats;
+use crate::orchestrator::WorkerStatus;
+
+/// Aggregate metrics and worker state at the moment of shutdown
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Snapshot {
+    /// Unix time (seconds) the snapshot was taken
+    pub taken_at: u64,
+    pub handlers: Vec<HandlerStats>,
+    pub workers: Vec<WorkerStatus>,
+}
+
+impl Snapshot {
+    pub fn new(handlers: Vec<HandlerStats>, workers: Vec<WorkerStatus>) -> Self {
+        let taken_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+        Self {
+            taken_at,
+            handlers,
+            workers,
+        }
+    }
+
+    /// Write the snapshot, replacing any previous one only once fully written
+    pub fn write(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let partial = path.with_extension("json.partial");
+        std::fs::write(&partial, serde_json::to_vec_pretty(self)?)?;
+        std::fs::rename(partial, path)
+    }
+
+    /// The snapshot at `path`, if one was written and is readable
+    pub fn load(path: &Path) -> Option<Self> {
+        let content = match std::fs::read(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return None,
+            Err(e) => {
+                warn!("Failed to read snapshot {}: {}", path.display(), e);
+                return None;
+            }
+        };
+        match serde_json::from_slice(&content) {
+            Ok(snapshot) => Some(snapshot),
+            Err(e) => {
+                warn!("Ignoring unreadable snapshot {}: {}", path.display(), e);
+                None
+            }
+        }
+    }
+}
@@ -0,0 +1,207 @@
+//! Log output and optional OpenTelemetry export.
+//!
+//! Built with the `otel` feature and given an `otel` config section, tracing
+//! spans (one per task) and the task metrics are also exported over OTLP/HTTP
+//! to a collector. Without the feature, a configured `otel` section is ignored
+//! with a warning.
+
+use tracing::Level;
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::Layer;
+
+use crate::config::OtelConfig;
+
+/// Installed exporters; dropping this flushes pending spans and metrics
+#[derive(Default)]
+pub struct Telemetry {
+    #[cfg(feature = "otel")]
+    _providers: Option<export::Providers>,
+}
+
+/// Install the global subscriber: logs at `level`, plus OTLP export when configured
+pub fn init(level: Level, otel: Option<&OtelConfig>) -> Telemetry {
+    let logs = tracing_subscriber::fmt::layer().with_filter(LevelFilter::from_level(level));
+
+    #[cfg(feature = "otel")]
+    {
+        let providers = otel.map(|config| export::Providers::install(config).map(|p| (config, p)));
+        let export_layer = match &providers {
+            Some(Ok((_, providers))) => Some(providers.tracing_layer(level)),
+            _ => None,
+        };
+        tracing_subscriber::registry()
+            .with(logs)
+            .with(export_layer)
+            .init();
+
+        match providers {
+            Some(Ok((config, providers))) => {
+                tracing::info!(
+                    "Exporting spans and metrics to OTLP collector at {}",
+                    config.endpoint
+                );
+                Telemetry {
+                    _providers: Some(providers),
+                }
+            }
+            Some(Err(e)) => {
+                tracing::warn!("OpenTelemetry export disabled: {}", e);
+                Telemetry::default()
+            }
+            None => Telemetry::default(),
+        }
+    }
+
+    #[cfg(not(feature = "otel"))]
+    {
+        tracing_subscriber::registry().with(logs).init();
+        if otel.is_some() {
+            tracing::warn!(
+                "otel is configured but this build lacks the `otel` feature; not exporting"
+            );
+        }
+        Telemetry::default()
+    }
+}
+
+#[cfg(feature = "otel")]
+mod export {
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry_otlp::{MetricExporter, SpanExporter, WithExportConfig};
+    use opentelemetry_sdk::metrics::SdkMeterProvider;
+    use opentelemetry_sdk::trace::SdkTracerProvider;
+    use opentelemetry_sdk::Resource;
+    use tracing::{Level, Subscriber};
+    use tracing_subscriber::filter::LevelFilter;
+    use tracing_subscriber::registry::LookupSpan;
+    use tracing_subscriber::Layer;
+
+    use crate::config::OtelConfig;
+
+    pub struct Providers {
+        tracer_provider: SdkTracerProvider,
+        meter_provider: SdkMeterProvider,
+    }
+
+    impl Providers {
+        /// Build OTLP exporters and make the meter provider global, which the
+        /// task metrics record to
+        pub fn install(config: &OtelConfig) -> Result<Self, String> {
+            let endpoint = config.endpoint.trim_end_matches('/');
+            let resource = Resource::builder()
+                .with_service_name(config.service_name.clone())
+                .build();
+
+            let span_exporter = SpanExporter::builder()
+                .with_http()
+                .with_endpoint(format!("{}/v1/traces", endpoint))
+                .build()
+                .map_err(|e| format!("span exporter: {}", e))?;
+            let metric_exporter = MetricExporter::builder()
+                .with_http()
+                .with_endpoint(format!("{}/v1/metrics", endpoint))
+                .build()
+                .map_err(|e| format!("metric exporter: {}", e))?;
+
+            let tracer_provider = SdkTracerProvider::builder()
+                .with_batch_exporter(span_exporter)
+                .with_resource(resource.clone())
+                .build();
+            let meter_provider = SdkMeterProvider::builder()
+                .with_periodic_exporter(metric_exporter)
+                .with_resource(resource)
+                .build();
+            opentelemetry::global::set_meter_provider(meter_provider.clone());
+
+            Ok(Self {
+                tracer_provider,
+                meter_provider,
+            })
+        }
+
+        /// Layer exporting spans at or above `level`
+        pub fn tracing_layer<S>(&self, level: Level) -> impl Layer<S>
+        where
+            S: Subscriber + for<'a> LookupSpan<'a>,
+        {
+            tracing_opentelemetry::layer()
+                .with_tracer(self.tracer_provider.tracer("neutrino"))
+                .with_filter(LevelFilter::from_level(level))
+        }
+    }
+
+    impl Drop for Providers {
+        fn drop(&mut self) {
+            if let Err(e) = self.tracer_provider.shutdown() {
+                eprintln!("Failed to flush spans: {}", e);
+            }
+            if let Err(e) = self.meter_provider.shutdown() {
+                eprintln!("Failed to flush metrics: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "otel"))]
+mod tests {
+    use axum::body::Body;
+    use axum::extract::Request;
+    use axum::http::StatusCode;
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry::Value;
+    use opentelemetry_sdk::trace::{InMemorySpanExporter, SdkTracerProvider};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tower::ServiceExt;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use crate::config::Config;
+    use crate::http::create_router_with_openapi;
+    use crate::protocol::ResourceCapabilities;
+    use crate::testing::{mock_worker_handle, spawn_echo_worker, spec_with_routes};
+    use crate::Orchestrator;
+
+    #[tokio::test]
+    async fn test_task_span_exported() {
+        let exporter = InMemorySpanExporter::default();
+        let provider = SdkTracerProvider::builder()
+            .with_simple_exporter(exporter.clone())
+            .build();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let orchestrator = Arc::new(Orchestrator::new(Config::default()));
+        let (handle, worker_side) =
+            mock_worker_handle("default-0", ResourceCapabilities::default());
+        orchestrator.workers().write().await.push(handle);
+        spawn_echo_worker(worker_side, Duration::ZERO);
+
+        let spec = spec_with_routes(&[("POST", "/work", "post_work")]);
+        let router = create_router_with_openapi(orchestrator, Some(spec), None, None);
+        let req = Request::builder()
+            .method("POST")
+            .uri("/work")
+            .header("content-type", "application/json")
+            .header(crate::http::TASK_ID_HEADER, "task-1")
+            .body(Body::from(r#"{"args": {}}"#))
+            .unwrap();
+        assert_eq!(router.oneshot(req).await.unwrap().status(), StatusCode::OK);
+
+        let spans = exporter.get_finished_spans().unwrap();
+        let span = spans
+            .iter()
+            .find(|span| span.name == "task")
+            .expect("no task span exported");
+        let attribute = |key: &str| {
+            span.attributes
+                .iter()
+                .find(|kv| kv.key.as_str() == key)
+                .map(|kv| kv.value.clone())
+        };
+        assert_eq!(attribute("task_id"), Some(Value::from("task-1")));
+        assert_eq!(attribute("handler"), Some(Value::from("work")));
+    }
+}
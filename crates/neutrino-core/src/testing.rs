@@ -0,0 +1,191 @@
+//! Test helpers: in-process mock workers and OpenAPI specs for exercising the
+//! orchestrator and HTTP layer without spawning Python.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::net::UnixStream;
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+use tracing::field::{Field, Visit};
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::Layer;
+
+use crate::openapi::OpenApiSpec;
+use crate::protocol::{self, Message, ResourceCapabilities};
+use crate::worker::{ResourceAllocation, Worker, WorkerHandle, WorkerState};
+
+/// Build a `WorkerHandle` connected to an in-process socket pair.
+/// Returns the handle and the worker side of the socket.
+pub fn mock_worker_handle(
+    id: &str,
+    capabilities: ResourceCapabilities,
+) -> (WorkerHandle, UnixStream) {
+    let (orchestrator_side, worker_side) = UnixStream::pair().unwrap();
+    // A process that exits immediately so shutdown's wait() returns
+    let process = Command::new("true").spawn().unwrap();
+
+    let worker = Worker {
+        id: id.to_string(),
+        pid: process.id(),
+        state: WorkerState::Idle,
+        socket_path: PathBuf::from(format!("/tmp/neutrino-mock-{}.sock", uuid::Uuid::new_v4())),
+        provisioned: capabilities.clone(),
+        capabilities,
+        allocation: ResourceAllocation::default(),
+        tasks_completed: 0,
+        spawn_time: Instant::now(),
+        current_memory_mb: 0,
+        handlers: None,
+        queue_depth: None,
+        missed_heartbeats: 0,
+        allocation_history: Default::default(),
+        gpu_devices: Vec::new(),
+        cancellable: false,
+    };
+
+    let handle = WorkerHandle {
+        worker,
+        stream: Arc::new(tokio::sync::Mutex::new(orchestrator_side)),
+        process,
+    };
+    (handle, worker_side)
+}
+
+/// Run a mock worker that answers each task with its args after `delay`.
+/// Tasks whose args contain `"fail": true` are reported as failed.
+pub fn spawn_echo_worker(mut stream: UnixStream, delay: Duration) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        while let Ok(msg) = protocol::read_message(&mut stream).await {
+            match msg {
+                Message::TaskAssignment { task_id, args, .. } => {
+                    tokio::time::sleep(delay).await;
+                    let fail = args
+                        .as_map()
+                        .map(|entries| {
+                            entries.iter().any(|(k, v)| {
+                                k.as_str() == Some("fail") && v.as_bool() == Some(true)
+                            })
+                        })
+                        .unwrap_or(false);
+                    let reply = Message::TaskResult {
+                        task_id,
+                        success: !fail,
+                        result: args,
+                    };
+                    if protocol::write_message(&mut stream, &reply).await.is_err() {
+                        break;
+                    }
+                }
+                Message::Shutdown { .. } => break,
+                _ => {}
+            }
+        }
+    })
+}
+
+/// Wait up to two seconds for the worker at `index` to reach `state`, e.g.
+/// for a worker told to cancel a task to be released once it answers
+pub async fn wait_for_worker_state(
+    workers: &RwLock<Vec<WorkerHandle>>,
+    index: usize,
+    state: WorkerState,
+) {
+    let deadline = Instant::now() + Duration::from_secs(2);
+    loop {
+        let current = workers.read().await[index].worker.state;
+        if current == state {
+            return;
+        }
+        assert!(
+            Instant::now() < deadline,
+            "worker {} stayed {:?}, expected {:?}",
+            index,
+            current,
+            state
+        );
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+}
+
+/// Run a mock worker that answers heartbeats reporting `queue_depth` queued tasks
+pub fn spawn_queued_worker(mut stream: UnixStream, queue_depth: u32) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        while let Ok(msg) = protocol::read_message(&mut stream).await {
+            if let Message::Heartbeat { worker_id, .. } = msg {
+                let reply = Message::Heartbeat {
+                    worker_id,
+                    queue_depth: Some(queue_depth),
+                };
+                if protocol::write_message(&mut stream, &reply).await.is_err() {
+                    break;
+                }
+            }
+        }
+    })
+}
+
+/// Build an OpenAPI spec with one route per (method, path, operation_id)
+pub fn spec_with_routes(routes: &[(&str, &str, &str)]) -> OpenApiSpec {
+    let mut paths = serde_json::Map::new();
+    for (method, path, operation_id) in routes {
+        let item = paths
+            .entry(path.to_string())
+            .or_insert_with(|| serde_json::json!({}));
+        item[method.to_lowercase()] = serde_json::json!({ "operationId": operation_id });
+    }
+
+    serde_json::from_value(serde_json::json!({
+        "openapi": "3.0.0",
+        "info": {"title": "test", "version": "1.0.0"},
+        "paths": paths,
+    }))
+    .unwrap()
+}
+
+/// Records the fields of tracing events, for asserting on structured logs
+#[derive(Clone, Default)]
+pub struct CapturedEvents(Arc<Mutex<Vec<HashMap<String, String>>>>);
+
+impl CapturedEvents {
+    /// Capture events emitted on this thread until the guard is dropped
+    pub fn install(&self) -> tracing::subscriber::DefaultGuard {
+        tracing::subscriber::set_default(tracing_subscriber::registry().with(self.clone()))
+    }
+
+    /// Events that have a field named `field`
+    pub fn with_field(&self, field: &str) -> Vec<HashMap<String, String>> {
+        self.0
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|fields| fields.contains_key(field))
+            .cloned()
+            .collect()
+    }
+}
+
+impl<S: tracing::Subscriber> Layer<S> for CapturedEvents {
+    fn on_event(&self, event: &tracing::Event<'_>, _ctx: Context<'_, S>) {
+        let mut fields = FieldMap::default();
+        event.record(&mut fields);
+        self.0.lock().unwrap().push(fields.0);
+    }
+}
+
+#[derive(Default)]
+struct FieldMap(HashMap<String, String>);
+
+impl Visit for FieldMap {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.to_string());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0
+            .insert(field.name().to_string(), format!("{:?}", value));
+    }
+}
@@ -0,0 +1,134 @@
+use std::io;
+use std::process::Command;
+
+/// Parse a cpuset string (e.g. "0-7", "0,2,4-6") into a sorted list of CPU indices
+pub fn parse_cpuset(cpuset: &str) -> Result<Vec<usize>, String> {
+    let mut cpus = Vec::new();
+
+    for part in cpuset.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        match part.split_once('-') {
+            Some((start, end)) => {
+                let start: usize = start
+                    .trim()
+                    .parse()
+                    .map_err(|_| format!("Invalid cpuset range start in '{}'", part))?;
+                let end: usize = end
+                    .trim()
+                    .parse()
+                    .map_err(|_| format!("Invalid cpuset range end in '{}'", part))?;
+                if start > end {
+                    return Err(format!("Invalid cpuset range '{}': start > end", part));
+                }
+                cpus.extend(start..=end);
+            }
+            None => {
+                let cpu: usize = part
+                    .parse()
+                    .map_err(|_| format!("Invalid CPU index '{}'", part))?;
+                cpus.push(cpu);
+            }
+        }
+    }
+
+    if cpus.is_empty() {
+        return Err(format!("cpuset '{}' contains no CPUs", cpuset));
+    }
+
+    cpus.sort_unstable();
+    cpus.dedup();
+    Ok(cpus)
+}
+
+/// Configure the command so the spawned child is pinned to the given CPUs.
+/// Affinity is set in the child between fork and exec, so it is inherited by the
+/// Python interpreter and any threads it starts.
+#[cfg(target_os = "linux")]
+pub fn apply_cpu_affinity(cmd: &mut Command, cpus: &[usize]) -> io::Result<()> {
+    use std::os::unix::process::CommandExt;
+
+    let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+    for &cpu in cpus {
+        if cpu >= libc::CPU_SETSIZE as usize {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("CPU index {} exceeds CPU_SETSIZE", cpu),
+            ));
+        }
+        unsafe { libc::CPU_SET(cpu, &mut set) };
+    }
+
+    unsafe {
+        cmd.pre_exec(move || {
+            if libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+
+    Ok(())
+}
+
+/// CPU pinning is only supported on Linux
+#[cfg(not(target_os = "linux"))]
+pub fn apply_cpu_affinity(_cmd: &mut Command, _cpus: &[usize]) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "CPU pinning is only supported on Linux",
+    ))
+}
+
+/// Get the CPUs a process is allowed to run on
+#[cfg(target_os = "linux")]
+pub fn get_cpu_affinity(pid: u32) -> io::Result<Vec<usize>> {
+    let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+    let ret = unsafe {
+        libc::sched_getaffinity(
+            pid as libc::pid_t,
+            std::mem::size_of::<libc::cpu_set_t>(),
+            &mut set,
+        )
+    };
+    if ret != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok((0..libc::CPU_SETSIZE as usize)
+        .filter(|&cpu| unsafe { libc::CPU_ISSET(cpu, &set) })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cpuset() {
+        assert_eq!(parse_cpuset("0-3").unwrap(), vec![0, 1, 2, 3]);
+        assert_eq!(parse_cpuset("0,2,4-6").unwrap(), vec![0, 2, 4, 5, 6]);
+        assert_eq!(parse_cpuset("3, 1, 1-2").unwrap(), vec![1, 2, 3]);
+        assert!(parse_cpuset("").is_err());
+        assert!(parse_cpuset("7-3").is_err());
+        assert!(parse_cpuset("a-b").is_err());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_child_affinity_matches_cpuset() {
+        // Pick a CPU we're actually allowed to use (containers may restrict the set)
+        let allowed = get_cpu_affinity(std::process::id()).unwrap();
+        let cpuset = allowed[0].to_string();
+        let cpus = parse_cpuset(&cpuset).unwrap();
+
+        let mut cmd = Command::new("sleep");
+        cmd.arg("5");
+        apply_cpu_affinity(&mut cmd, &cpus).unwrap();
+        let mut child = cmd.spawn().unwrap();
+
+        let affinity = get_cpu_affinity(child.id());
+        let _ = child.kill();
+        let _ = child.wait();
+
+        assert_eq!(affinity.unwrap(), cpus);
+    }
+}
@@ -0,0 +1,68 @@
+//! Recent allocation changes of a worker, kept so "why does this worker report
+//! full?" can be answered after the fact: a leaked allocation shows up as an
+//! allocate without its deallocate, over-allocation as a running total past
+//! the worker's capabilities.
+
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::ResourceAllocation;
+use crate::protocol::ResourceRequirements;
+
+/// Allocation changes kept per worker; older ones are dropped
+pub const ALLOCATION_HISTORY_LEN: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AllocationChange {
+    Allocate,
+    Deallocate,
+}
+
+/// One allocation change and the allocation it left behind
+#[derive(Debug, Clone, Serialize)]
+pub struct AllocationEvent {
+    /// Unix time in milliseconds
+    pub timestamp_ms: u64,
+    pub task_id: String,
+    pub change: AllocationChange,
+    pub resources: ResourceRequirements,
+    /// The worker's allocation after the change
+    pub allocation: ResourceAllocation,
+}
+
+/// Ring buffer of a worker's most recent allocation changes, oldest first
+#[derive(Debug, Clone, Default)]
+pub struct AllocationHistory {
+    events: VecDeque<AllocationEvent>,
+}
+
+impl AllocationHistory {
+    pub fn record(
+        &mut self,
+        task_id: &str,
+        change: AllocationChange,
+        resources: &ResourceRequirements,
+        allocation: &ResourceAllocation,
+    ) {
+        if self.events.len() == ALLOCATION_HISTORY_LEN {
+            self.events.pop_front();
+        }
+        let timestamp_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_millis() as u64)
+            .unwrap_or_default();
+        self.events.push_back(AllocationEvent {
+            timestamp_ms,
+            task_id: task_id.to_string(),
+            change,
+            resources: resources.clone(),
+            allocation: allocation.clone(),
+        });
+    }
+
+    pub fn events(&self) -> impl Iterator<Item = &AllocationEvent> {
+        self.events.iter()
+    }
+}
@@ -1,21 +1,40 @@
-use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
 use std::process::{Child, Command};
-use std::time::Instant;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::net::{UnixListener, UnixStream};
-use tracing::{debug, error, info};
-use serde::{Deserialize, Serialize};
+use tokio::sync::futures::Notified;
+use tokio::sync::{Mutex, Notify, OwnedMutexGuard, RwLock};
+use tracing::{debug, error, info, warn};
 
-use crate::protocol::{Message, ResourceCapabilities};
+use crate::config::WorkerConfig;
+use crate::protocol::{self, Message, ResourceCapabilities, ResourceRequirements};
 
+pub mod affinity;
+pub mod history;
 pub mod memory;
 
+use history::{AllocationChange, AllocationHistory};
+
+/// How long to wait for a worker to answer `ListHandlers`
+const HANDLER_LIST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long the monitoring loop waits for a worker to answer a heartbeat
+pub const HEARTBEAT_TIMEOUT: Duration = Duration::from_secs(1);
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum WorkerState {
     Starting,
     Idle,
     Busy,
     Recycling,
+    /// Ran past a task's timeout, or its process died or stopped answering;
+    /// kept out of selection until replaced
+    Stuck,
 }
 
 /// Current resource allocation state of a worker
@@ -27,6 +46,8 @@ pub struct ResourceAllocation {
     pub allocated_gpus: f64,
     /// Memory currently allocated in GB
     pub allocated_memory_gb: f64,
+    /// GPU memory currently allocated in GB
+    pub allocated_gpu_memory_gb: f64,
 }
 
 impl Default for ResourceAllocation {
@@ -35,6 +56,7 @@ impl Default for ResourceAllocation {
             allocated_cpus: 0.0,
             allocated_gpus: 0.0,
             allocated_memory_gb: 0.0,
+            allocated_gpu_memory_gb: 0.0,
         }
     }
 }
@@ -45,6 +67,7 @@ impl ResourceAllocation {
         self.allocated_cpus += requirements.num_cpus;
         self.allocated_gpus += requirements.num_gpus;
         self.allocated_memory_gb += requirements.memory_gb;
+        self.allocated_gpu_memory_gb += requirements.gpu_memory_gb;
     }
 
     /// Deallocate resources after task completion
@@ -52,11 +75,13 @@ impl ResourceAllocation {
         self.allocated_cpus -= requirements.num_cpus;
         self.allocated_gpus -= requirements.num_gpus;
         self.allocated_memory_gb -= requirements.memory_gb;
+        self.allocated_gpu_memory_gb -= requirements.gpu_memory_gb;
 
         // Ensure no negative values due to floating point precision
         self.allocated_cpus = self.allocated_cpus.max(0.0);
         self.allocated_gpus = self.allocated_gpus.max(0.0);
         self.allocated_memory_gb = self.allocated_memory_gb.max(0.0);
+        self.allocated_gpu_memory_gb = self.allocated_gpu_memory_gb.max(0.0);
     }
 }
 
@@ -68,6 +93,8 @@ pub struct Worker {
     pub socket_path: PathBuf,
     /// Total resource capabilities of this worker
     pub capabilities: ResourceCapabilities,
+    /// Capabilities the worker was spawned with; runtime updates can't exceed them
+    pub provisioned: ResourceCapabilities,
     /// Current resource allocation
     pub allocation: ResourceAllocation,
     /// Number of tasks completed by this worker
@@ -76,6 +103,18 @@ pub struct Worker {
     pub spawn_time: Instant,
     /// Current memory usage in MB (cached, updated periodically)
     pub current_memory_mb: u64,
+    /// Handlers the worker advertised via `ListHandlers` (None = unknown)
+    pub handlers: Option<HashSet<String>>,
+    /// Tasks queued inside the worker, from its last heartbeat (None = never reported)
+    pub queue_depth: Option<u32>,
+    /// Heartbeats missed in a row
+    pub missed_heartbeats: u32,
+    /// Recent allocation changes, for /admin/workers/{id}/allocations
+    pub allocation_history: AllocationHistory,
+    /// Physical GPUs the worker was given via CUDA_VISIBLE_DEVICES
+    pub gpu_devices: Vec<usize>,
+    /// Whether the worker said it stops tasks part-way on `CancelTask`
+    pub cancellable: bool,
 }
 
 impl Worker {
@@ -85,9 +124,23 @@ impl Worker {
         let available_gpus = self.capabilities.num_gpus - self.allocation.allocated_gpus;
         let available_memory_gb = self.capabilities.memory_gb - self.allocation.allocated_memory_gb;
 
+        // GPU memory is only enforced when the worker advertises a VRAM budget
+        let has_gpu_memory = match self.available_gpu_memory_gb() {
+            Some(available) => available >= requirements.gpu_memory_gb,
+            None => true,
+        };
+
         available_cpus >= requirements.num_cpus
             && available_gpus >= requirements.num_gpus
             && available_memory_gb >= requirements.memory_gb
+            && has_gpu_memory
+    }
+
+    /// Get available GPU memory in GB, if this worker tracks GPU memory
+    pub fn available_gpu_memory_gb(&self) -> Option<f64> {
+        self.capabilities
+            .gpu_memory_gb
+            .map(|total| total - self.allocation.allocated_gpu_memory_gb)
     }
 
     /// Get available resources as a tuple (cpus, gpus, memory_gb)
@@ -120,6 +173,33 @@ impl Worker {
         false
     }
 
+    /// Apply capabilities reported by the worker at runtime, each capped at
+    /// what it was provisioned with. Tasks already allocated keep running; new
+    /// ones are only placed once they fit the revised capabilities.
+    pub fn update_capabilities(&mut self, reported: ResourceCapabilities) {
+        let provisioned = &self.provisioned;
+        let capped = ResourceCapabilities {
+            num_cpus: reported.num_cpus.min(provisioned.num_cpus),
+            num_gpus: reported.num_gpus.min(provisioned.num_gpus),
+            memory_gb: reported.memory_gb.min(provisioned.memory_gb),
+            gpu_memory_gb: match (reported.gpu_memory_gb, provisioned.gpu_memory_gb) {
+                (Some(reported), Some(provisioned)) => Some(reported.min(provisioned)),
+                (reported, provisioned) => reported.or(provisioned),
+            },
+        };
+        if capped != reported {
+            warn!(
+                "Worker {} reported capabilities {:?} beyond those provisioned; capped to {:?}",
+                self.id, reported, capped
+            );
+        }
+        info!(
+            "Worker {} capabilities updated: cpus={}, gpus={}, mem={}GB, gpu_mem={:?}GB",
+            self.id, capped.num_cpus, capped.num_gpus, capped.memory_gb, capped.gpu_memory_gb
+        );
+        self.capabilities = capped;
+    }
+
     /// Increment the task counter
     pub fn increment_task_count(&mut self) {
         self.tasks_completed += 1;
@@ -131,9 +211,69 @@ impl Worker {
     }
 }
 
+/// Socket path for a worker, in the configured socket directory (created if
+/// missing and restricted to `socket_dir_mode`) or else /tmp. A directory
+/// that isn't the orchestrator's, such as a shared one like /run, keeps its
+/// permissions.
+fn socket_path(worker_id: &str, config: &WorkerConfig) -> std::io::Result<PathBuf> {
+    let file_name = format!("neutrino-{}.sock", worker_id);
+    let Some(dir) = &config.socket_dir else {
+        return Ok(Path::new("/tmp").join(file_name));
+    };
+
+    let dir = Path::new(dir);
+    let existed = dir.exists();
+    std::fs::create_dir_all(dir)?;
+    if let Some(mode) = config.socket_dir_mode {
+        if existed && !is_socket_dir(dir)? {
+            warn!(
+                "Socket directory {:?} has another owner or other files; leaving its permissions as they are",
+                dir
+            );
+        } else {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(dir, std::fs::Permissions::from_mode(mode))?;
+            debug!("Set permissions {:o} on socket directory {:?}", mode, dir);
+        }
+    }
+    Ok(dir.join(file_name))
+}
+
+/// Whether an existing directory looks like one the orchestrator created:
+/// owned by its user and holding nothing but worker sockets
+fn is_socket_dir(dir: &Path) -> std::io::Result<bool> {
+    use std::os::unix::fs::MetadataExt;
+    if std::fs::metadata(dir)?.uid() != unsafe { libc::geteuid() } {
+        return Ok(false);
+    }
+    for entry in std::fs::read_dir(dir)? {
+        let name = entry?.file_name();
+        let name = name.to_string_lossy();
+        if !(name.starts_with("neutrino-") && name.ends_with(".sock")) {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Bind a Unix socket listener, restricting its permissions if a mode is given
+fn bind_socket(path: &std::path::Path, mode: Option<u32>) -> std::io::Result<UnixListener> {
+    let listener = UnixListener::bind(path)?;
+
+    if let Some(mode) = mode {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))?;
+        debug!("Set permissions {:o} on socket {:?}", mode, path);
+    }
+
+    Ok(listener)
+}
+
 pub struct WorkerHandle {
     pub worker: Worker,
-    pub stream: UnixStream,
+    /// Connection to the worker, locked per exchange so a task's round trip
+    /// doesn't need the workers lock
+    pub stream: Arc<Mutex<UnixStream>>,
     pub process: Child,
 }
 
@@ -144,8 +284,10 @@ impl WorkerHandle {
         app_module: &str,
         capabilities: ResourceCapabilities,
         gpu_devices: &[usize],
+        cpuset: Option<&str>,
+        config: &WorkerConfig,
     ) -> Result<Self, Box<dyn std::error::Error>> {
-        let socket_path = PathBuf::from(format!("/tmp/neutrino-{}.sock", worker_id));
+        let socket_path = socket_path(&worker_id, config)?;
 
         // Clean up old socket if it exists
         if socket_path.exists() {
@@ -153,7 +295,7 @@ impl WorkerHandle {
         }
 
         // Create Unix socket listener
-        let listener = UnixListener::bind(&socket_path)?;
+        let listener = bind_socket(&socket_path, config.socket_mode)?;
         info!("Created socket at {:?}", socket_path);
 
         // Spawn Python worker process
@@ -192,6 +334,10 @@ impl WorkerHandle {
             .arg(capabilities.num_gpus.to_string())
             .arg(capabilities.memory_gb.to_string())
             .env("PYTHONPATH", new_python_path)
+            .env(
+                protocol::WireFormat::ENV_VAR,
+                protocol::wire_format().as_str(),
+            )
             .current_dir(&cwd);
 
         // Set CUDA_VISIBLE_DEVICES for GPU isolation
@@ -209,6 +355,18 @@ impl WorkerHandle {
             cmd.env("CUDA_VISIBLE_DEVICES", "");
         }
 
+        // Pin to a CPU set (e.g. a single NUMA node) if configured
+        if let Some(cpuset) = cpuset {
+            let cpus = affinity::parse_cpuset(cpuset)?;
+            info!("Worker {} pinned to cpuset {}", worker_id, cpuset);
+            affinity::apply_cpu_affinity(&mut cmd, &cpus)?;
+        }
+
+        if let Some(log_dir) = &config.worker_log_dir {
+            let log_path = redirect_output(&mut cmd, Path::new(log_dir), &worker_id)?;
+            info!("Worker {} output written to {:?}", worker_id, log_path);
+        }
+
         let process = cmd.spawn()?;
 
         let pid = process.id();
@@ -216,11 +374,8 @@ impl WorkerHandle {
 
         // Wait for worker to connect (with timeout)
         info!("Waiting for worker to connect...");
-        let (stream, _addr) = tokio::time::timeout(
-            std::time::Duration::from_secs(10),
-            listener.accept(),
-        )
-        .await??;
+        let (stream, _addr) =
+            tokio::time::timeout(Duration::from_secs(10), listener.accept()).await??;
 
         info!("Worker {} connected", worker_id);
 
@@ -229,57 +384,79 @@ impl WorkerHandle {
             pid,
             state: WorkerState::Starting,
             socket_path,
+            provisioned: capabilities.clone(),
             capabilities,
             allocation: ResourceAllocation::default(),
             tasks_completed: 0,
             spawn_time: Instant::now(),
             current_memory_mb: 0,
+            handlers: None,
+            queue_depth: None,
+            missed_heartbeats: 0,
+            allocation_history: AllocationHistory::default(),
+            gpu_devices: gpu_devices.to_vec(),
+            cancellable: false,
         };
 
         Ok(Self {
             worker,
-            stream,
+            stream: Arc::new(Mutex::new(stream)),
             process,
         })
     }
 
     /// Send a message to the worker
     pub async fn send(&mut self, msg: &Message) -> Result<(), Box<dyn std::error::Error>> {
-        let payload = msg.to_bytes()?;
-        let len = (payload.len() as u32).to_be_bytes();
-
-        self.stream.write_all(&len).await?;
-        self.stream.write_all(&payload).await?;
-        self.stream.flush().await?;
-
-        debug!("Sent message: {:?}", msg);
-        Ok(())
+        write_message(&mut *self.stream.lock().await, msg).await
     }
 
-    /// Receive a message from the worker
+    /// Receive a message from the worker. Capability updates, which the worker
+    /// may send at any time, are applied here rather than returned.
     pub async fn recv(&mut self) -> Result<Message, Box<dyn std::error::Error>> {
-        let mut len_buf = [0u8; 4];
-        self.stream.read_exact(&mut len_buf).await?;
-        let len = u32::from_be_bytes(len_buf) as usize;
-
-        let mut payload = vec![0u8; len];
-        self.stream.read_exact(&mut payload).await?;
+        recv_applying(&mut *self.stream.lock().await, &mut self.worker).await
+    }
 
-        let msg = Message::from_bytes(&payload)?;
-        debug!("Received message: {:?}", msg);
-        Ok(msg)
+    /// Whether a task is using the connection right now
+    pub fn connection_busy(&self) -> bool {
+        self.stream.try_lock().is_err()
     }
 
     /// Wait for the worker to send a Ready message
     pub async fn wait_ready(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        match self.recv().await? {
-            Message::WorkerReady { worker_id, pid, capabilities } => {
+        let msg = self.recv().await?;
+        match msg {
+            Message::WorkerReady {
+                worker_id,
+                pid,
+                capabilities,
+                cancellable,
+            } => {
                 info!(
                     "Worker {} ready (pid={}, cpus={}, gpus={}, mem={}GB)",
-                    worker_id, pid, capabilities.num_cpus, capabilities.num_gpus, capabilities.memory_gb
+                    worker_id,
+                    pid,
+                    capabilities.num_cpus,
+                    capabilities.num_gpus,
+                    capabilities.memory_gb
                 );
                 self.worker.state = WorkerState::Idle;
-                self.worker.capabilities = capabilities;
+                self.worker.cancellable = cancellable;
+                // Workers don't report VRAM, so keep the configured GPU memory budget
+                let gpu_memory_gb = capabilities
+                    .gpu_memory_gb
+                    .or(self.worker.capabilities.gpu_memory_gb);
+                self.worker.capabilities = ResourceCapabilities {
+                    gpu_memory_gb,
+                    ..capabilities
+                };
+
+                // Without a handler list the worker is assumed to serve every route
+                if let Err(e) = self.list_handlers(HANDLER_LIST_TIMEOUT).await {
+                    warn!(
+                        "Worker {} did not report its handlers: {}",
+                        self.worker.id, e
+                    );
+                }
                 Ok(())
             }
             other => {
@@ -289,6 +466,210 @@ impl WorkerHandle {
         }
     }
 
+    /// Ask the worker for the handlers it provides and record them on the worker
+    pub async fn list_handlers(
+        &mut self,
+        timeout: Duration,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        // Held for the whole exchange so no task reads the reply
+        let connection = Arc::clone(&self.stream);
+        let mut stream = connection.lock().await;
+        write_message(&mut stream, &Message::ListHandlers {}).await?;
+
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            let msg = match tokio::time::timeout_at(
+                deadline,
+                recv_applying(&mut stream, &mut self.worker),
+            )
+            .await
+            {
+                Ok(msg) => msg?,
+                Err(_) => {
+                    return Err(format!("no HandlerList within {}s", timeout.as_secs()).into())
+                }
+            };
+
+            match msg {
+                Message::HandlerList { handlers, .. } => {
+                    debug!("Worker {} provides handlers {:?}", self.worker.id, handlers);
+                    self.worker.handlers = Some(handlers.into_iter().collect());
+                    return Ok(());
+                }
+                other => {
+                    debug!(
+                        "Ignoring message while listing handlers of worker {}: {:?}",
+                        self.worker.id, other
+                    );
+                }
+            }
+        }
+    }
+
+    /// Heartbeat the worker and apply its answer, waiting for the connection
+    /// if a task is using it
+    pub async fn heartbeat(&mut self, timeout: Duration) -> Result<(), Box<dyn std::error::Error>> {
+        let connection = Arc::clone(&self.stream);
+        let mut stream = connection.lock().await;
+        let reply = exchange_heartbeat(&mut stream, &self.worker.id, timeout).await?;
+        reply.apply(&mut self.worker);
+        Ok(())
+    }
+
+    /// Take the connection for a heartbeat unless a task is using it, so the
+    /// exchange can run after the workers lock is let go
+    pub fn probe(&self, leases: &Arc<Leases>) -> Option<HeartbeatProbe> {
+        let stream = Arc::clone(&self.stream).try_lock_owned().ok()?;
+        leases.active.fetch_add(1, Ordering::SeqCst);
+        Some(HeartbeatProbe {
+            worker_id: self.worker.id.clone(),
+            connection: Arc::clone(&self.stream),
+            stream,
+            _counted: CountedLease(Arc::clone(leases)),
+        })
+    }
+
+    /// Run `handler` on the worker (with no args) and wait for it to succeed,
+    /// e.g. to load a model before the worker is given tasks. The worker counts
+    /// as starting until then.
+    pub async fn warm_up(
+        &mut self,
+        handler: &str,
+        timeout: Duration,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let task_id = format!("warmup-{}", self.worker.id);
+        let state = std::mem::replace(&mut self.worker.state, WorkerState::Starting);
+        // Held for the whole exchange so no task reads the reply
+        let connection = Arc::clone(&self.stream);
+        let mut stream = connection.lock().await;
+        write_message(
+            &mut stream,
+            &Message::TaskAssignment {
+                task_id: task_id.clone(),
+                function_name: handler.to_string(),
+                args: rmpv::Value::Map(vec![]),
+                // Nothing else runs on the worker yet, so the warm-up may use all of it
+                resources: crate::protocol::ResourceRequirements {
+                    num_cpus: self.worker.capabilities.num_cpus,
+                    num_gpus: self.worker.capabilities.num_gpus,
+                    memory_gb: self.worker.capabilities.memory_gb,
+                    gpu_memory_gb: self.worker.capabilities.gpu_memory_gb.unwrap_or(0.0),
+                },
+                deadline_ms_remaining: Some(timeout.as_millis() as u64),
+                priority: 0,
+            },
+        )
+        .await?;
+
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            let msg = match tokio::time::timeout_at(
+                deadline,
+                recv_applying(&mut stream, &mut self.worker),
+            )
+            .await
+            {
+                Ok(msg) => msg?,
+                Err(_) => {
+                    return Err(format!(
+                        "warm-up {} did not finish within {}s",
+                        handler,
+                        timeout.as_secs()
+                    )
+                    .into())
+                }
+            };
+
+            match msg {
+                Message::TaskResult {
+                    task_id: id,
+                    success,
+                    result,
+                } if id == task_id => {
+                    if !success {
+                        return Err(format!("warm-up {} failed: {}", handler, result).into());
+                    }
+                    self.worker.state = state;
+                    return Ok(());
+                }
+                other => {
+                    debug!(
+                        "Ignoring message while warming up worker {}: {:?}",
+                        self.worker.id, other
+                    );
+                }
+            }
+        }
+    }
+
+    /// Ask the worker to finish internally queued work and wait for `DrainComplete`.
+    /// Fails if the worker doesn't finish draining within `timeout`.
+    pub async fn drain(&mut self, timeout: Duration) -> Result<(), Box<dyn std::error::Error>> {
+        // Held for the whole exchange so no task reads the reply
+        let connection = Arc::clone(&self.stream);
+        let mut stream = connection.lock().await;
+        write_message(
+            &mut stream,
+            &Message::DrainRequest {
+                timeout_secs: timeout.as_secs(),
+            },
+        )
+        .await?;
+
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            let msg = match tokio::time::timeout_at(
+                deadline,
+                recv_applying(&mut stream, &mut self.worker),
+            )
+            .await
+            {
+                Ok(msg) => msg?,
+                Err(_) => {
+                    return Err(format!(
+                        "Worker {} did not drain within {}s",
+                        self.worker.id,
+                        timeout.as_secs()
+                    )
+                    .into())
+                }
+            };
+
+            match msg {
+                Message::DrainComplete { .. } => {
+                    info!("Worker {} drained", self.worker.id);
+                    return Ok(());
+                }
+                Message::DrainStatus { pending, .. } => {
+                    info!("Worker {} draining ({} pending)", self.worker.id, pending);
+                }
+                other => {
+                    debug!(
+                        "Ignoring message while draining worker {}: {:?}",
+                        self.worker.id, other
+                    );
+                }
+            }
+        }
+    }
+
+    /// How the worker process ended, if it has
+    pub fn exit_status(&mut self) -> Option<std::process::ExitStatus> {
+        self.process.try_wait().ok().flatten()
+    }
+
+    /// Kill a worker that can't be shut down gracefully
+    pub fn kill(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        self.process.kill()?;
+        self.process.wait()?;
+
+        if self.worker.socket_path.exists() {
+            std::fs::remove_file(&self.worker.socket_path)?;
+        }
+
+        Ok(())
+    }
+
     /// Gracefully shutdown the worker
     pub async fn shutdown(&mut self) -> Result<(), Box<dyn std::error::Error>> {
         self.send(&Message::Shutdown { graceful: true }).await?;
@@ -311,3 +692,739 @@ impl Drop for WorkerHandle {
         }
     }
 }
+
+impl WorkerHandle {
+    /// Allocate a task's resources on this worker and take its connection,
+    /// marking the worker busy until the returned lease is released. None if
+    /// another task is using the connection.
+    pub fn lease(
+        &mut self,
+        workers: &Arc<RwLock<Vec<WorkerHandle>>>,
+        leases: &Arc<Leases>,
+        task_id: &str,
+        resources: ResourceRequirements,
+    ) -> Option<TaskLease> {
+        let stream = Arc::clone(&self.stream).try_lock_owned().ok()?;
+        let worker = &mut self.worker;
+        worker.allocation.allocate(&resources);
+        worker.allocation_history.record(
+            task_id,
+            AllocationChange::Allocate,
+            &resources,
+            &worker.allocation,
+        );
+        worker.state = WorkerState::Busy;
+        leases.active.fetch_add(1, Ordering::SeqCst);
+
+        Some(TaskLease {
+            worker_id: worker.id.clone(),
+            stream: Some(stream),
+            release: Some(Release {
+                workers: Arc::clone(workers),
+                leases: Arc::clone(leases),
+                connection: Arc::clone(&self.stream),
+                task_id: task_id.to_string(),
+                resources,
+                capabilities: None,
+                queue_depth: None,
+                assigned: false,
+                completed: false,
+                stuck: false,
+            }),
+        })
+    }
+}
+
+/// Count of tasks holding worker resources (and heartbeats holding a worker's
+/// connection), and a signal for when one lets go
+#[derive(Default)]
+pub struct Leases {
+    active: AtomicUsize,
+    released: Notify,
+}
+
+impl Leases {
+    /// Tasks holding worker resources (or heartbeats a connection) right now
+    pub fn active(&self) -> usize {
+        self.active.load(Ordering::SeqCst)
+    }
+
+    /// Resolves when the next lease is released. Enable it before checking
+    /// for capacity so a release in between isn't missed.
+    pub fn released(&self) -> Notified<'_> {
+        self.released.notified()
+    }
+}
+
+/// A task's resources held on a worker, with exclusive use of the worker's
+/// connection until released. The round trip runs without
+/// the workers lock, so tasks on different workers proceed in parallel.
+/// Resources are deallocated and the worker marked idle exactly once however
+/// the task ends (error, timeout, or the request being cancelled).
+/// A task abandoned after it was sent holds the worker until the worker
+/// answers for it, so a worker still running it isn't handed more work.
+pub struct TaskLease {
+    worker_id: String,
+    /// None once released
+    stream: Option<OwnedMutexGuard<UnixStream>>,
+    release: Option<Release>,
+}
+
+/// What releasing a lease needs, taken out once it is released
+struct Release {
+    workers: Arc<RwLock<Vec<WorkerHandle>>>,
+    leases: Arc<Leases>,
+    connection: Arc<Mutex<UnixStream>>,
+    task_id: String,
+    resources: ResourceRequirements,
+    /// Reported by the worker during the task, applied on release
+    capabilities: Option<ResourceCapabilities>,
+    queue_depth: Option<u32>,
+    /// The worker has been sent the task, so abandoning it cancels it there
+    assigned: bool,
+    completed: bool,
+    /// Leave the worker stuck rather than idle, for the monitor to replace
+    stuck: bool,
+}
+
+impl TaskLease {
+    pub fn worker_id(&self) -> &str {
+        &self.worker_id
+    }
+
+    fn stream(&mut self) -> &mut UnixStream {
+        self.stream.as_mut().expect("lease already released")
+    }
+
+    /// Send a message to the worker
+    pub async fn send(&mut self, msg: &Message) -> Result<(), Box<dyn std::error::Error>> {
+        write_message(self.stream(), msg).await?;
+        if let (Message::TaskAssignment { .. }, Some(release)) = (msg, self.release.as_mut()) {
+            release.assigned = true;
+        }
+        Ok(())
+    }
+
+    /// Receive a message from the worker. Capability updates and queue depth
+    /// reports are kept for the worker's record on release rather than returned.
+    pub async fn recv(&mut self) -> Result<Message, Box<dyn std::error::Error>> {
+        loop {
+            let msg = read_message(self.stream()).await?;
+            let release = self.release.as_mut().expect("lease already released");
+            match msg {
+                Message::UpdateCapabilities { capabilities } => {
+                    release.capabilities = Some(capabilities)
+                }
+                Message::Heartbeat { queue_depth, .. } => {
+                    if queue_depth.is_some() {
+                        release.queue_depth = queue_depth;
+                    }
+                }
+                msg => return Ok(msg),
+            }
+        }
+    }
+
+    /// Take the worker out of service when the lease is released, e.g. when
+    /// the task timed out and the worker may never answer
+    pub fn mark_stuck(&mut self) {
+        if let Some(release) = self.release.as_mut() {
+            release.stuck = true;
+        }
+    }
+
+    /// Release the lease for a task the worker finished, counting it toward
+    /// the worker's completed tasks
+    pub async fn finish(mut self) {
+        self.stream = None;
+        if let Some(mut release) = self.release.take() {
+            release.completed = true;
+            let (workers, leases) = (Arc::clone(&release.workers), Arc::clone(&release.leases));
+            release.apply(&mut workers.write().await);
+            leases.settle();
+        }
+    }
+}
+
+impl Leases {
+    /// A lease is gone and its worker's connection free
+    fn settle(&self) {
+        self.active.fetch_sub(1, Ordering::SeqCst);
+        self.released.notify_waiters();
+    }
+}
+
+/// A heartbeat with exclusive use of an idle worker's connection, taken under
+/// the workers lock and sent without it. Until it ends it counts as a lease,
+/// so a task finding the connection in use waits for it instead of giving up.
+pub struct HeartbeatProbe {
+    worker_id: String,
+    connection: Arc<Mutex<UnixStream>>,
+    stream: OwnedMutexGuard<UnixStream>,
+    /// Dropped after `stream`, so the tasks it wakes find the connection free
+    _counted: CountedLease,
+}
+
+/// One count in `Leases::active`, settled when dropped
+struct CountedLease(Arc<Leases>);
+
+impl Drop for CountedLease {
+    fn drop(&mut self) {
+        self.0.settle();
+    }
+}
+
+/// How a worker answered a heartbeat, to apply under the workers lock
+pub struct Heartbeat {
+    /// Connection of the worker it was sent to
+    pub connection: Arc<Mutex<UnixStream>>,
+    pub reply: Result<HeartbeatReply, String>,
+}
+
+/// What a worker reported while answering a heartbeat
+#[derive(Debug, Default)]
+pub struct HeartbeatReply {
+    queue_depth: Option<u32>,
+    capabilities: Option<ResourceCapabilities>,
+}
+
+impl HeartbeatProbe {
+    /// Send the heartbeat and wait up to `timeout` for the answer
+    pub async fn send(mut self, timeout: Duration) -> Heartbeat {
+        let reply = exchange_heartbeat(&mut self.stream, &self.worker_id, timeout).await;
+        Heartbeat {
+            connection: Arc::clone(&self.connection),
+            reply,
+        }
+    }
+}
+
+impl HeartbeatReply {
+    /// Record the answer on the worker
+    pub fn apply(self, worker: &mut Worker) {
+        if let Some(capabilities) = self.capabilities {
+            worker.update_capabilities(capabilities);
+        }
+        if self.queue_depth.is_some() {
+            worker.queue_depth = self.queue_depth;
+        }
+        worker.missed_heartbeats = 0;
+    }
+}
+
+impl Release {
+    /// Deallocate the task's resources and mark the worker idle. A worker that
+    /// has since left the pool is skipped.
+    fn apply(self, workers: &mut [WorkerHandle]) {
+        if let Some(handle) = workers
+            .iter_mut()
+            .find(|handle| Arc::ptr_eq(&handle.stream, &self.connection))
+        {
+            let worker = &mut handle.worker;
+            worker.allocation.deallocate(&self.resources);
+            worker.allocation_history.record(
+                &self.task_id,
+                AllocationChange::Deallocate,
+                &self.resources,
+                &worker.allocation,
+            );
+            worker.state = if self.stuck {
+                WorkerState::Stuck
+            } else {
+                WorkerState::Idle
+            };
+            if let Some(capabilities) = self.capabilities {
+                worker.update_capabilities(capabilities);
+            }
+            if self.queue_depth.is_some() {
+                worker.queue_depth = self.queue_depth;
+            }
+            if self.completed {
+                worker.increment_task_count();
+            }
+        }
+    }
+}
+
+impl Drop for TaskLease {
+    fn drop(&mut self) {
+        let Some(release) = self.release.take() else {
+            return;
+        };
+        let stream = self.stream.take();
+        let cancel = release.assigned.then(|| release.task_id.clone());
+        // A worker that was sent the task may keep running it after being told
+        // to cancel (the bundled Python worker can't stop part-way), so it stays
+        // busy until its result for the task is read, unless it's being taken
+        // out of service anyway
+        let await_result = cancel.is_some() && !release.stuck;
+        let (workers, leases) = (Arc::clone(&release.workers), Arc::clone(&release.leases));
+
+        // Free the resources now unless the lock is busy, in which case they
+        // are freed once it's free rather than blocking here
+        let unreleased = if await_result {
+            Some(release)
+        } else {
+            match workers.try_write() {
+                Ok(mut workers) => {
+                    release.apply(&mut workers);
+                    None
+                }
+                Err(_) => Some(release),
+            }
+        };
+        if unreleased.is_none() && cancel.is_none() {
+            drop(stream);
+            leases.settle();
+            return;
+        }
+
+        tokio::spawn(async move {
+            let mut unreleased = unreleased;
+            // The connection stays taken until the worker has been told, and
+            // until it has answered when the worker stays in service
+            if let (Some(task_id), Some(mut stream)) = (cancel, stream) {
+                let msg = Message::CancelTask {
+                    task_id: task_id.clone(),
+                };
+                let mut cancelled = write_message(&mut stream, &msg)
+                    .await
+                    .map_err(|e| e.to_string());
+                if let (Ok(()), true) = (&cancelled, await_result) {
+                    let release = unreleased.as_mut().expect("kept until answered");
+                    cancelled = release.await_cancelled(&mut stream).await;
+                }
+                if let Err(e) = cancelled {
+                    debug!("Failed to cancel task {} on its worker: {}", task_id, e);
+                    if let Some(release) = unreleased.as_mut() {
+                        release.stuck = true;
+                    }
+                }
+            }
+            if let Some(release) = unreleased {
+                release.apply(&mut workers.write().await);
+            }
+            leases.settle();
+        });
+    }
+}
+
+impl Release {
+    /// Read from a worker told to cancel the task until its result for the
+    /// task arrives, keeping any capability or queue depth reports
+    async fn await_cancelled(&mut self, stream: &mut UnixStream) -> Result<(), String> {
+        loop {
+            match read_message(stream).await.map_err(|e| e.to_string())? {
+                Message::TaskResult { task_id, .. } if task_id == self.task_id => return Ok(()),
+                Message::UpdateCapabilities { capabilities } => {
+                    self.capabilities = Some(capabilities)
+                }
+                Message::Heartbeat {
+                    queue_depth: Some(depth),
+                    ..
+                } => self.queue_depth = Some(depth),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Write a message to a worker's connection
+async fn write_message(
+    stream: &mut UnixStream,
+    msg: &Message,
+) -> Result<(), Box<dyn std::error::Error>> {
+    protocol::write_message(stream, msg)
+        .await
+        .map_err(|e| e as Box<dyn std::error::Error>)?;
+    debug!("Sent message: {:?}", msg);
+    Ok(())
+}
+
+/// Read the next message from a worker's connection
+async fn read_message(stream: &mut UnixStream) -> Result<Message, Box<dyn std::error::Error>> {
+    let msg = protocol::read_message(stream)
+        .await
+        .map_err(|e| e as Box<dyn std::error::Error>)?;
+    debug!("Received message: {:?}", msg);
+    Ok(msg)
+}
+
+/// Send a heartbeat on a connection held for the whole exchange (so no task
+/// reads the reply) and wait for the answer
+async fn exchange_heartbeat(
+    stream: &mut UnixStream,
+    worker_id: &str,
+    timeout: Duration,
+) -> Result<HeartbeatReply, String> {
+    write_message(
+        stream,
+        &Message::Heartbeat {
+            worker_id: worker_id.to_string(),
+            queue_depth: None,
+        },
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let deadline = tokio::time::Instant::now() + timeout;
+    let mut reply = HeartbeatReply::default();
+    loop {
+        let msg = match tokio::time::timeout_at(deadline, read_message(stream)).await {
+            Ok(msg) => msg.map_err(|e| e.to_string())?,
+            Err(_) => return Err(format!("no Heartbeat reply within {}s", timeout.as_secs())),
+        };
+
+        match msg {
+            Message::Heartbeat { queue_depth, .. } => {
+                reply.queue_depth = queue_depth;
+                return Ok(reply);
+            }
+            Message::UpdateCapabilities { capabilities } => {
+                reply.capabilities = Some(capabilities);
+            }
+            other => {
+                debug!(
+                    "Ignoring message while waiting for heartbeat of worker {}: {:?}",
+                    worker_id, other
+                );
+            }
+        }
+    }
+}
+
+/// Read the next message, applying capability updates (which the worker may
+/// send at any time) to `worker` rather than returning them
+async fn recv_applying(
+    stream: &mut UnixStream,
+    worker: &mut Worker,
+) -> Result<Message, Box<dyn std::error::Error>> {
+    loop {
+        match read_message(stream).await? {
+            Message::UpdateCapabilities { capabilities } => {
+                worker.update_capabilities(capabilities)
+            }
+            msg => return Ok(msg),
+        }
+    }
+}
+
+/// Send a worker's stdout and stderr to `<log_dir>/<worker_id>.log`. The previous
+/// file for the same worker ID (e.g. before a recycle) is kept as `<worker_id>.log.1`.
+fn redirect_output(cmd: &mut Command, log_dir: &Path, worker_id: &str) -> std::io::Result<PathBuf> {
+    fs::create_dir_all(log_dir)?;
+
+    let log_path = log_dir.join(format!("{}.log", worker_id));
+    if log_path.exists() {
+        fs::rename(&log_path, log_dir.join(format!("{}.log.1", worker_id)))?;
+    }
+
+    let log_file = File::create(&log_path)?;
+    cmd.stderr(log_file.try_clone()?)
+        .stdout(log_file)
+        // Flush prints as they happen so a crash doesn't lose the last lines
+        .env("PYTHONUNBUFFERED", "1");
+    Ok(log_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::ResourceRequirements;
+
+    fn test_worker(capabilities: ResourceCapabilities) -> Worker {
+        Worker {
+            id: "test-0".to_string(),
+            pid: 0,
+            state: WorkerState::Idle,
+            socket_path: PathBuf::from("/tmp/neutrino-test-0.sock"),
+            provisioned: capabilities.clone(),
+            capabilities,
+            allocation: ResourceAllocation::default(),
+            tasks_completed: 0,
+            spawn_time: Instant::now(),
+            current_memory_mb: 0,
+            handlers: None,
+            queue_depth: None,
+            missed_heartbeats: 0,
+            allocation_history: AllocationHistory::default(),
+            gpu_devices: Vec::new(),
+            cancellable: false,
+        }
+    }
+
+    #[test]
+    fn test_gpu_memory_rejects_second_task() {
+        let mut worker = test_worker(ResourceCapabilities {
+            num_cpus: 4.0,
+            num_gpus: 1.0,
+            memory_gb: 32.0,
+            gpu_memory_gb: Some(16.0),
+        });
+        let task = ResourceRequirements {
+            num_cpus: 1.0,
+            num_gpus: 0.5,
+            memory_gb: 4.0,
+            gpu_memory_gb: 10.0,
+        };
+
+        assert!(worker.has_capacity(&task));
+        worker.allocation.allocate(&task);
+
+        // Device count still fits (0.5 + 0.5), but 10GB + 10GB exceeds 16GB VRAM
+        assert!(!worker.has_capacity(&task));
+
+        worker.allocation.deallocate(&task);
+        assert!(worker.has_capacity(&task));
+    }
+
+    #[tokio::test]
+    async fn test_socket_mode_applied() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path =
+            std::env::temp_dir().join(format!("neutrino-test-{}.sock", uuid::Uuid::new_v4()));
+        let _listener = bind_socket(&path, Some(0o600)).unwrap();
+
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    #[test]
+    fn test_socket_dir_created_with_restricted_mode() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let parent = std::env::temp_dir().join(format!("neutrino-test-{}", uuid::Uuid::new_v4()));
+        let mut config = crate::config::Config::default().orchestrator.worker;
+        assert_eq!(
+            socket_path("default-0", &config).unwrap(),
+            Path::new("/tmp/neutrino-default-0.sock")
+        );
+
+        config.socket_dir = Some(parent.join("sockets").to_string_lossy().into_owned());
+        let path = socket_path("default-0", &config).unwrap();
+        assert_eq!(path, parent.join("sockets").join("neutrino-default-0.sock"));
+        let mode = std::fs::metadata(parent.join("sockets"))
+            .unwrap()
+            .permissions()
+            .mode();
+
+        // An existing directory is tightened too
+        std::fs::set_permissions(
+            parent.join("sockets"),
+            std::fs::Permissions::from_mode(0o777),
+        )
+        .unwrap();
+        socket_path("default-1", &config).unwrap();
+        let tightened = std::fs::metadata(parent.join("sockets"))
+            .unwrap()
+            .permissions()
+            .mode();
+
+        // ...unless it holds files that aren't worker sockets
+        std::fs::set_permissions(
+            parent.join("sockets"),
+            std::fs::Permissions::from_mode(0o755),
+        )
+        .unwrap();
+        std::fs::write(parent.join("sockets").join("other.txt"), "").unwrap();
+        socket_path("default-2", &config).unwrap();
+        let shared = std::fs::metadata(parent.join("sockets"))
+            .unwrap()
+            .permissions()
+            .mode();
+        std::fs::remove_dir_all(&parent).unwrap();
+
+        assert_eq!(
+            (mode & 0o777, tightened & 0o777, shared & 0o777),
+            (0o700, 0o700, 0o755)
+        );
+    }
+
+    #[test]
+    fn test_socket_mode_parsing() {
+        let parse = |yaml: &str| -> Option<u32> {
+            let config: WorkerConfig = serde_yaml::from_str(&format!(
+                "max_tasks_per_worker: 1\nmax_memory_mb: 1\nstartup_timeout_secs: 1\n{}",
+                yaml
+            ))
+            .unwrap();
+            config.socket_mode
+        };
+
+        assert_eq!(parse(""), None);
+        assert_eq!(parse("socket_mode: \"0600\""), Some(0o600));
+        assert_eq!(parse("socket_mode: \"0o660\""), Some(0o660));
+        assert_eq!(parse("socket_mode: 0600"), Some(0o600));
+
+        // Modes are written back as octal strings, so configs round-trip
+        let config = crate::config::Config::default().orchestrator.worker;
+        let yaml = serde_yaml::to_string(&config).unwrap();
+        assert!(yaml.contains("socket_dir_mode: '0700'"), "{}", yaml);
+        let reread: WorkerConfig = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(reread.socket_dir_mode, Some(0o700));
+    }
+
+    #[test]
+    fn test_gpu_memory_untracked_is_unconstrained() {
+        let mut worker = test_worker(ResourceCapabilities {
+            num_cpus: 4.0,
+            num_gpus: 1.0,
+            memory_gb: 32.0,
+            gpu_memory_gb: None,
+        });
+        let task = ResourceRequirements {
+            num_cpus: 1.0,
+            num_gpus: 0.5,
+            memory_gb: 4.0,
+            gpu_memory_gb: 10.0,
+        };
+
+        worker.allocation.allocate(&task);
+        assert!(worker.has_capacity(&task));
+    }
+
+    #[test]
+    fn test_worker_output_written_to_per_worker_log() {
+        let log_dir =
+            std::env::temp_dir().join(format!("neutrino-worker-logs-{}", uuid::Uuid::new_v4()));
+
+        let run_stub = |message: &str| {
+            let mut cmd = Command::new("sh");
+            cmd.arg("-c")
+                .arg(format!("echo '{}'; echo 'Traceback: boom' >&2", message));
+            let log_path = redirect_output(&mut cmd, &log_dir, "cpu-3").unwrap();
+            cmd.spawn().unwrap().wait().unwrap();
+            log_path
+        };
+
+        let log_path = run_stub("first run");
+        assert_eq!(log_path, log_dir.join("cpu-3.log"));
+        let output = fs::read_to_string(&log_path).unwrap();
+        assert!(
+            output.contains("first run") && output.contains("Traceback: boom"),
+            "{}",
+            output
+        );
+
+        // A recycled worker starts a fresh file and keeps the previous one
+        run_stub("second run");
+        assert!(fs::read_to_string(&log_path)
+            .unwrap()
+            .contains("second run"));
+        let previous = fs::read_to_string(log_dir.join("cpu-3.log.1")).unwrap();
+        assert!(previous.contains("first run"));
+
+        let _ = fs::remove_dir_all(log_dir);
+    }
+
+    #[test]
+    fn test_capability_update_capped_at_provisioned() {
+        let mut worker = test_worker(ResourceCapabilities {
+            num_cpus: 4.0,
+            num_gpus: 1.0,
+            memory_gb: 32.0,
+            gpu_memory_gb: Some(16.0),
+        });
+
+        // Less free VRAM than at startup
+        worker.update_capabilities(ResourceCapabilities {
+            num_cpus: 4.0,
+            num_gpus: 1.0,
+            memory_gb: 32.0,
+            gpu_memory_gb: Some(6.0),
+        });
+        assert_eq!(worker.capabilities.gpu_memory_gb, Some(6.0));
+
+        // Claims beyond the provisioned resources are capped
+        worker.update_capabilities(ResourceCapabilities {
+            num_cpus: 16.0,
+            num_gpus: 2.0,
+            memory_gb: 8.0,
+            gpu_memory_gb: None,
+        });
+        assert_eq!(
+            worker.capabilities,
+            ResourceCapabilities {
+                num_cpus: 4.0,
+                num_gpus: 1.0,
+                memory_gb: 8.0,
+                gpu_memory_gb: Some(16.0)
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_warm_up_waits_for_handler_with_whole_worker() {
+        use crate::testing::mock_worker_handle;
+
+        let capabilities = ResourceCapabilities {
+            num_cpus: 4.0,
+            num_gpus: 1.0,
+            memory_gb: 16.0,
+            gpu_memory_gb: None,
+        };
+        let (mut handle, mut worker_side) = mock_worker_handle("gpu-0", capabilities);
+        let worker = tokio::spawn(async move {
+            let Message::TaskAssignment {
+                task_id,
+                function_name,
+                resources,
+                ..
+            } = read_message(&mut worker_side).await.unwrap()
+            else {
+                panic!("expected a task");
+            };
+            let reply = Message::TaskResult {
+                task_id,
+                success: true,
+                result: rmpv::Value::Nil,
+            };
+            write_message(&mut worker_side, &reply).await.unwrap();
+            (
+                function_name,
+                resources.num_cpus,
+                resources.num_gpus,
+                worker_side,
+            )
+        });
+
+        handle
+            .warm_up("load_model", Duration::from_secs(1))
+            .await
+            .unwrap();
+        let (function_name, num_cpus, num_gpus, mut worker_side) = worker.await.unwrap();
+        assert_eq!(
+            (function_name.as_str(), num_cpus, num_gpus),
+            ("load_model", 4.0, 1.0)
+        );
+        assert_eq!(handle.worker.state, WorkerState::Idle);
+
+        // A failed warm-up is an error, and the worker stays starting
+        tokio::spawn(async move {
+            let Message::TaskAssignment { task_id, .. } =
+                read_message(&mut worker_side).await.unwrap()
+            else {
+                panic!("expected a task");
+            };
+            let reply = Message::TaskResult {
+                task_id,
+                success: false,
+                result: "CUDA out of memory".into(),
+            };
+            write_message(&mut worker_side, &reply).await.unwrap();
+        });
+        let error = handle
+            .warm_up("load_model", Duration::from_secs(1))
+            .await
+            .unwrap_err();
+        assert!(
+            error.to_string().contains("CUDA out of memory"),
+            "{}",
+            error
+        );
+        assert_eq!(handle.worker.state, WorkerState::Starting);
+    }
+}
@@ -0,0 +1,128 @@
+//! Zero-downtime upgrade: a new instance binds the same port with SO_REUSEPORT,
+//! the old one stops accepting and drains, and clients keep being served.
+
+use axum::{routing::get, Router};
+use neutrino_core::http::handoff;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+/// Like any client of a handoff, retry a request whose connection was reset
+/// (queued on the old socket when it closed)
+async fn get_with_retry(client: &reqwest::Client, url: &str) -> Result<String, String> {
+    let mut last_error = String::new();
+    for _ in 0..3 {
+        match client.get(url).send().await {
+            Ok(response) => return response.text().await.map_err(|e| e.to_string()),
+            Err(e) => last_error = e.to_string(),
+        }
+    }
+    Err(last_error)
+}
+
+fn instance(name: &'static str) -> Router {
+    Router::new()
+        .route("/whoami", get(move || async move { name }))
+        .route(
+            "/slow",
+            get(move || async move {
+                tokio::time::sleep(Duration::from_millis(500)).await;
+                name
+            }),
+        )
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_requests_served_throughout_handoff() {
+    let old_listener = handoff::bind_listener("127.0.0.1:0", true).await.unwrap();
+    let addr = old_listener.local_addr().unwrap();
+    let (stop_old, old_stop_signal) = oneshot::channel::<()>();
+    let old = tokio::spawn(handoff::serve(
+        old_listener,
+        instance("old"),
+        async {
+            old_stop_signal.await.ok();
+        },
+        Duration::from_secs(5),
+    ));
+
+    // No connection reuse, so every request is a new connection the kernel balances
+    let client = reqwest::Client::builder()
+        .pool_max_idle_per_host(0)
+        .build()
+        .unwrap();
+    let base = format!("http://{}", addr);
+
+    // In flight on the old instance (the only one listening yet) during the handoff
+    let slow = tokio::spawn({
+        let client = client.clone();
+        let url = format!("{}/slow", base);
+        async move { client.get(url).send().await?.text().await }
+    });
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    // Continuous traffic for the whole handoff
+    let running = Arc::new(AtomicBool::new(true));
+    let traffic = tokio::spawn({
+        let running = Arc::clone(&running);
+        let url = format!("{}/whoami", base);
+        async move {
+            let mut results = Vec::new();
+            while running.load(Ordering::Relaxed) {
+                results.push(get_with_retry(&client, &url).await);
+                tokio::time::sleep(Duration::from_millis(2)).await;
+            }
+            results
+        }
+    });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // The new instance binds the same port and reports readiness
+    let new_listener = handoff::bind_listener(&addr.to_string(), true)
+        .await
+        .unwrap();
+    let ready_path = std::env::temp_dir().join(format!("neutrino-ready-{}", uuid::Uuid::new_v4()));
+    let ready_file = handoff::ReadyFile::write(&ready_path).unwrap();
+    let (stop_new, new_stop_signal) = oneshot::channel::<()>();
+    let new = tokio::spawn(handoff::serve(
+        new_listener,
+        instance("new"),
+        async {
+            new_stop_signal.await.ok();
+        },
+        Duration::from_secs(5),
+    ));
+
+    // Supervisor: once the new instance is ready, stop the old one
+    assert_eq!(
+        std::fs::read_to_string(&ready_path).unwrap(),
+        std::process::id().to_string()
+    );
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    stop_old.send(()).unwrap();
+
+    // The old instance only exits once its in-flight request has finished
+    old.await.unwrap().unwrap();
+    assert_eq!(slow.await.unwrap().unwrap(), "old");
+
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    running.store(false, Ordering::Relaxed);
+    let results = traffic.await.unwrap();
+
+    let failures: Vec<_> = results.iter().filter_map(|r| r.as_ref().err()).collect();
+    assert!(
+        failures.is_empty(),
+        "requests failed during handoff: {:?}",
+        failures
+    );
+    let served_by = |name: &str| results.iter().filter(|r| r.as_deref() == Ok(name)).count();
+    assert!(served_by("old") > 0);
+    assert!(served_by("new") > 0);
+    assert_eq!(results.last().unwrap().as_deref(), Ok("new"));
+
+    stop_new.send(()).unwrap();
+    new.await.unwrap().unwrap();
+    drop(ready_file);
+    assert!(!ready_path.exists());
+}
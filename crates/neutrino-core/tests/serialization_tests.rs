@@ -1,90 +1,17 @@
-/// Tests for JSON <-> msgpack serialization used in HTTP handlers
-///
-/// These tests ensure that data can be correctly converted between:
-/// - HTTP JSON requests -> msgpack (for sending to Python workers)
-/// - msgpack results -> HTTP JSON responses (for returning to clients)
+//! Tests for JSON <-> msgpack serialization used in HTTP handlers
+//!
+//! These tests ensure that data can be correctly converted between:
+//! - HTTP JSON requests -> msgpack (for sending to Python workers)
+//! - msgpack results -> HTTP JSON responses (for returning to clients)
 
 #[cfg(test)]
 mod json_msgpack_conversion {
-    use serde_json;
+    use neutrino_core::serde_convert::{self, json_to_msgpack_value, NonFiniteFloats};
     use rmpv::Value as MsgpackValue;
 
-    /// Convert serde_json::Value to rmpv::Value (same as in http/mod.rs)
-    fn json_to_msgpack_value(json: &serde_json::Value) -> Result<MsgpackValue, String> {
-        match json {
-            serde_json::Value::Null => Ok(MsgpackValue::Nil),
-            serde_json::Value::Bool(b) => Ok(MsgpackValue::Boolean(*b)),
-            serde_json::Value::Number(n) => {
-                if let Some(i) = n.as_i64() {
-                    Ok(MsgpackValue::Integer(i.into()))
-                } else if let Some(f) = n.as_f64() {
-                    Ok(MsgpackValue::F64(f))
-                } else {
-                    Err("Invalid number".to_string())
-                }
-            }
-            serde_json::Value::String(s) => Ok(MsgpackValue::String(s.clone().into())),
-            serde_json::Value::Array(arr) => {
-                let values: Result<Vec<_>, _> = arr.iter().map(json_to_msgpack_value).collect();
-                Ok(MsgpackValue::Array(values?))
-            }
-            serde_json::Value::Object(obj) => {
-                let pairs: Result<Vec<(MsgpackValue, MsgpackValue)>, String> = obj
-                    .iter()
-                    .map(|(k, v)| {
-                        Ok((
-                            MsgpackValue::String(k.clone().into()),
-                            json_to_msgpack_value(v)?,
-                        ))
-                    })
-                    .collect();
-                Ok(MsgpackValue::Map(pairs?))
-            }
-        }
-    }
-
-    /// Convert rmpv::Value to serde_json::Value (same as in http/mod.rs)
+    /// Results as the HTTP handlers convert them by default
     fn msgpack_value_to_json(msgpack: &MsgpackValue) -> Result<serde_json::Value, String> {
-        match msgpack {
-            MsgpackValue::Nil => Ok(serde_json::Value::Null),
-            MsgpackValue::Boolean(b) => Ok(serde_json::Value::Bool(*b)),
-            MsgpackValue::Integer(i) => {
-                if let Some(val) = i.as_i64() {
-                    Ok(serde_json::json!(val))
-                } else if let Some(val) = i.as_u64() {
-                    Ok(serde_json::json!(val))
-                } else {
-                    Err("Integer out of range".to_string())
-                }
-            }
-            MsgpackValue::F32(f) => Ok(serde_json::json!(*f)),
-            MsgpackValue::F64(f) => Ok(serde_json::json!(*f)),
-            MsgpackValue::String(s) => Ok(serde_json::Value::String(
-                s.as_str().ok_or("Invalid UTF-8")?.to_string(),
-            )),
-            MsgpackValue::Binary(b) => {
-                // Convert binary to array of numbers for JSON compatibility
-                Ok(serde_json::Value::Array(
-                    b.iter().map(|&byte| serde_json::json!(byte)).collect(),
-                ))
-            }
-            MsgpackValue::Array(arr) => {
-                let values: Result<Vec<_>, _> = arr.iter().map(msgpack_value_to_json).collect();
-                Ok(serde_json::Value::Array(values?))
-            }
-            MsgpackValue::Map(map) => {
-                let mut obj = serde_json::Map::new();
-                for (k, v) in map {
-                    let key = match k {
-                        MsgpackValue::String(s) => s.as_str().ok_or("Invalid UTF-8")?.to_string(),
-                        _ => return Err("Map keys must be strings".to_string()),
-                    };
-                    obj.insert(key, msgpack_value_to_json(v)?);
-                }
-                Ok(serde_json::Value::Object(obj))
-            }
-            MsgpackValue::Ext(_, _) => Err("Extension types not supported".to_string()),
-        }
+        serde_convert::msgpack_value_to_json(msgpack, NonFiniteFloats::default())
     }
 
     #[test]
@@ -119,7 +46,7 @@ mod json_msgpack_conversion {
     #[test]
     fn test_float_roundtrip() {
         let json = serde_json::json!({
-            "pi": 3.14159,
+            "pi": std::f64::consts::PI,
             "negative": -2.5,
             "scientific": 1.23e-4
         });
@@ -128,7 +55,7 @@ mod json_msgpack_conversion {
 
         // Compare floats with epsilon
         let obj = back_to_json.as_object().unwrap();
-        assert!((obj["pi"].as_f64().unwrap() - 3.14159).abs() < 0.00001);
+        assert!((obj["pi"].as_f64().unwrap() - std::f64::consts::PI).abs() < 0.00001);
         assert_eq!(obj["negative"].as_f64().unwrap(), -2.5);
     }
 
@@ -223,7 +150,10 @@ mod json_msgpack_conversion {
         let back_to_json = msgpack_value_to_json(&msgpack).unwrap();
 
         assert_eq!(back_to_json["type"], "ValueError");
-        assert!(back_to_json["error"].as_str().unwrap().contains("ValueError"));
+        assert!(back_to_json["error"]
+            .as_str()
+            .unwrap()
+            .contains("ValueError"));
     }
 
     #[test]
@@ -277,26 +207,77 @@ mod json_msgpack_conversion {
 
     #[test]
     fn test_special_floats() {
-        // Test infinity - note that serde_json may serialize infinity as null
+        // Infinity has no JSON form; by default it becomes null
         let msgpack_inf = MsgpackValue::F64(f64::INFINITY);
-        let json_inf = msgpack_value_to_json(&msgpack_inf).unwrap();
-        // JSON doesn't have a standard representation for infinity, so it may be null
-        // or the f64 value depending on serde_json settings
-        if let Some(f) = json_inf.as_f64() {
-            assert!(f.is_infinite());
-        }
+        assert_eq!(
+            msgpack_value_to_json(&msgpack_inf).unwrap(),
+            serde_json::Value::Null
+        );
 
         let msgpack_small = MsgpackValue::F64(1e-308);
         let json_small = msgpack_value_to_json(&msgpack_small).unwrap();
         assert_eq!(json_small.as_f64().unwrap(), 1e-308);
     }
 
+    #[test]
+    fn test_non_finite_floats_per_policy() {
+        let msgpack = MsgpackValue::Map(vec![
+            ("nan".into(), MsgpackValue::F64(f64::NAN)),
+            ("inf".into(), MsgpackValue::F64(f64::INFINITY)),
+            ("neg_inf".into(), MsgpackValue::F32(f32::NEG_INFINITY)),
+            ("finite".into(), MsgpackValue::F64(2.5)),
+        ]);
+
+        let nulls = serde_convert::msgpack_value_to_json(&msgpack, NonFiniteFloats::Null).unwrap();
+        assert_eq!(
+            nulls,
+            serde_json::json!({"nan": null, "inf": null, "neg_inf": null, "finite": 2.5})
+        );
+
+        let strings =
+            serde_convert::msgpack_value_to_json(&msgpack, NonFiniteFloats::String).unwrap();
+        assert_eq!(
+            strings,
+            serde_json::json!({"nan": "NaN", "inf": "Infinity", "neg_inf": "-Infinity", "finite": 2.5})
+        );
+
+        let error =
+            serde_convert::msgpack_value_to_json(&msgpack, NonFiniteFloats::Error).unwrap_err();
+        assert!(error.contains("NaN"), "{}", error);
+
+        // Finite floats are unaffected by the policy, nested or not
+        let nested = MsgpackValue::Array(vec![MsgpackValue::F32(0.5), MsgpackValue::F64(-1.0)]);
+        for policy in [
+            NonFiniteFloats::Null,
+            NonFiniteFloats::String,
+            NonFiniteFloats::Error,
+        ] {
+            let json = serde_convert::msgpack_value_to_json(&nested, policy).unwrap();
+            assert_eq!(json, serde_json::json!([0.5, -1.0]));
+        }
+    }
+
+    #[test]
+    fn test_non_finite_floats_policy_names() {
+        for (name, policy) in [
+            ("null", NonFiniteFloats::Null),
+            ("string", NonFiniteFloats::String),
+            ("error", NonFiniteFloats::Error),
+        ] {
+            assert_eq!(
+                serde_yaml::from_str::<NonFiniteFloats>(name).unwrap(),
+                policy
+            );
+        }
+    }
+
     #[test]
     fn test_invalid_map_key() {
         // Map keys must be strings in JSON
-        let invalid_map = MsgpackValue::Map(vec![
-            (MsgpackValue::Integer(123.into()), MsgpackValue::String("value".into()))
-        ]);
+        let invalid_map = MsgpackValue::Map(vec![(
+            MsgpackValue::Integer(123.into()),
+            MsgpackValue::String("value".into()),
+        )]);
 
         let result = msgpack_value_to_json(&invalid_map);
         assert!(result.is_err());
@@ -311,7 +292,10 @@ mod json_msgpack_conversion {
 
         for i in 2..50 {
             let new_level = serde_json::json!({"level": i});
-            current.as_object_mut().unwrap().insert("nested".to_string(), new_level);
+            current
+                .as_object_mut()
+                .unwrap()
+                .insert("nested".to_string(), new_level);
             current = &mut current["nested"];
         }
 
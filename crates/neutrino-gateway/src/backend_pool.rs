@@ -26,9 +26,18 @@ pub struct Backend {
     pub total_cpus: f64,
     pub total_gpus: f64,
     pub total_memory_gb: f64,
+    /// Free resources of each worker, when the backend reports them. A task must
+    /// fit on one worker, so the aggregate alone can overstate what is servable.
+    pub workers: Vec<WorkerAvailability>,
     pub last_updated: Instant,
     pub healthy: bool,
     pub error_count: u32,
+    /// Drained by an operator: still monitored, but not selected for new requests
+    pub draining: bool,
+    /// When the gateway started tracking this backend
+    pub discovered_at: Instant,
+    /// Past its warmup: `/ready` succeeded or the warmup window elapsed
+    pub warm: bool,
 }
 
 impl Backend {
@@ -41,18 +50,37 @@ impl Backend {
             total_cpus: 0.0,
             total_gpus: 0.0,
             total_memory_gb: 0.0,
+            workers: Vec::new(),
             last_updated: Instant::now(),
             healthy: false,
             error_count: 0,
+            draining: false,
+            discovered_at: Instant::now(),
+            warm: false,
         }
     }
 
-    /// Check if this backend has sufficient resources
+    /// Check if this backend has sufficient resources: in aggregate and, when
+    /// per-worker availability is known, on a single worker
     pub fn has_capacity(&self, cpus: f64, gpus: f64, memory_gb: f64) -> bool {
         self.healthy
+            && !self.draining
             && self.available_cpus >= cpus
             && self.available_gpus >= gpus
             && self.available_memory_gb >= memory_gb
+            && (self.workers.is_empty()
+                || self.workers.iter().any(|w| w.fits(cpus, gpus, memory_gb)))
+    }
+
+    /// Whether the backend may still be warming up: tracked for less than
+    /// `warmup` and not yet reporting ready (never, without a warmup)
+    pub fn is_warming(&self, warmup: Option<Duration>) -> bool {
+        warmup.is_some_and(|warmup| !self.warm && self.discovered_at.elapsed() < warmup)
+    }
+
+    /// Whether the capacity data is older than `limit` (never, without a limit)
+    pub fn is_stale(&self, limit: Option<Duration>) -> bool {
+        limit.is_some_and(|limit| self.last_updated.elapsed() > limit)
     }
 
     /// Get utilization percentage (0.0 - 1.0)
@@ -78,6 +106,20 @@ impl Backend {
     }
 }
 
+/// Free resources on one of a backend's workers
+#[derive(Debug, Clone, Deserialize)]
+pub struct WorkerAvailability {
+    pub cpus: f64,
+    pub gpus: f64,
+    pub memory_gb: f64,
+}
+
+impl WorkerAvailability {
+    fn fits(&self, cpus: f64, gpus: f64, memory_gb: f64) -> bool {
+        self.cpus >= cpus && self.gpus >= gpus && self.memory_gb >= memory_gb
+    }
+}
+
 /// Capacity response from /capacity endpoint
 #[derive(Debug, Deserialize)]
 struct CapacityResponse {
@@ -86,6 +128,13 @@ struct CapacityResponse {
     available_memory_gb: f64,
     #[serde(default)]
     total: Option<TotalCapacity>,
+    #[serde(default)]
+    workers: Vec<WorkerCapacity>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WorkerCapacity {
+    available: WorkerAvailability,
 }
 
 #[derive(Debug, Deserialize)]
@@ -95,12 +144,36 @@ struct TotalCapacity {
     memory_gb: f64,
 }
 
+/// What one poll of a backend found, gathered before taking the pool lock
+struct Probe {
+    url: String,
+    /// `/ready` succeeded (only asked while warming up)
+    ready: bool,
+    /// The health probe's result, when one is configured
+    health: Option<Result<(), String>>,
+    capacity: Result<CapacityResponse, String>,
+}
+
+/// Liveness probe polled separately from `/capacity`
+#[derive(Debug, Clone)]
+pub struct HealthCheck {
+    pub path: String,
+    pub method: reqwest::Method,
+}
+
 /// Pool of backend task pods with resource tracking
 pub struct BackendPool {
     backends: Arc<RwLock<Vec<Backend>>>,
     http_client: reqwest::Client,
     discovery_mode: DiscoveryMode,
     update_interval: Duration,
+    /// When set, health comes from this probe and `/capacity` only supplies resource data
+    health_check: Option<HealthCheck>,
+    /// Backends whose capacity data is older than this aren't selected
+    capacity_staleness_limit: Option<Duration>,
+    /// New backends aren't selected for this long, unless `/ready` succeeds first
+    warmup: Option<Duration>,
+    #[allow(dead_code)]
     capacity_timeout: Duration,
 }
 
@@ -120,10 +193,34 @@ impl BackendPool {
             http_client,
             discovery_mode,
             update_interval: Duration::from_secs(update_interval_secs),
+            health_check: None,
+            capacity_staleness_limit: None,
+            warmup: None,
             capacity_timeout: Duration::from_secs(capacity_timeout_secs),
         }
     }
 
+    /// Judge backend health by a separate probe instead of by `/capacity`, so a
+    /// backend that is up but can't report capacity keeps its last known capacity
+    pub fn with_health_check(mut self, health_check: HealthCheck) -> Self {
+        self.health_check = Some(health_check);
+        self
+    }
+
+    /// Stop selecting a backend whose capacity hasn't been refreshed within
+    /// `limit` (e.g. polling has stalled) until fresh data arrives
+    pub fn with_capacity_staleness_limit(mut self, limit: Duration) -> Self {
+        self.capacity_staleness_limit = Some(limit);
+        self
+    }
+
+    /// Treat a newly discovered backend as unavailable for `warmup` (its workers
+    /// may still be loading models), or until its `/ready` reports ready
+    pub fn with_warmup(mut self, warmup: Duration) -> Self {
+        self.warmup = Some(warmup);
+        self
+    }
+
     /// Initialize the pool and start background monitoring
     pub async fn start(&self) -> Result<(), String> {
         // Initialize backends based on discovery mode
@@ -138,6 +235,9 @@ impl BackendPool {
             }
         }
 
+        // Poll once up front so backends are routable before the first interval elapses
+        self.refresh().await;
+
         // Start background monitoring task
         self.start_monitoring().await;
 
@@ -148,6 +248,8 @@ impl BackendPool {
     async fn start_monitoring(&self) {
         let backends = Arc::clone(&self.backends);
         let http_client = self.http_client.clone();
+        let health_check = self.health_check.clone();
+        let warmup = self.warmup;
         let update_interval = self.update_interval;
 
         tokio::spawn(async move {
@@ -159,53 +261,194 @@ impl BackendPool {
             loop {
                 tokio::time::sleep(update_interval).await;
 
-                let mut backends_guard = backends.write().await;
-
-                for backend in backends_guard.iter_mut() {
-                    match Self::fetch_capacity(&http_client, &backend.url).await {
-                        Ok(capacity) => {
-                            backend.available_cpus = capacity.available_cpus;
-                            backend.available_gpus = capacity.available_gpus;
-                            backend.available_memory_gb = capacity.available_memory_gb;
-
-                            // Update totals if provided
-                            if let Some(total) = capacity.total {
-                                backend.total_cpus = total.cpus;
-                                backend.total_gpus = total.gpus;
-                                backend.total_memory_gb = total.memory_gb;
-                            }
-
-                            backend.last_updated = Instant::now();
-                            backend.healthy = true;
-                            backend.error_count = 0;
-
-                            debug!(
-                                "Backend {} capacity: CPU={:.1}/{:.1}, GPU={:.1}/{:.1}, MEM={:.1}/{:.1}GB",
-                                backend.url,
-                                backend.total_cpus - backend.available_cpus,
-                                backend.total_cpus,
-                                backend.total_gpus - backend.available_gpus,
-                                backend.total_gpus,
-                                backend.total_memory_gb - backend.available_memory_gb,
-                                backend.total_memory_gb
-                            );
-                        }
-                        Err(e) => {
-                            backend.error_count += 1;
-                            if backend.error_count >= 3 {
-                                if backend.healthy {
-                                    warn!("Backend {} marked unhealthy after {} errors", backend.url, backend.error_count);
-                                }
-                                backend.healthy = false;
-                            }
-                            error!("Failed to fetch capacity from {}: {}", backend.url, e);
-                        }
-                    }
-                }
+                Self::refresh_backends(&backends, &http_client, health_check.as_ref(), warmup)
+                    .await;
             }
         });
     }
 
+    /// Poll every backend's capacity once
+    pub async fn refresh(&self) {
+        Self::refresh_backends(
+            &self.backends,
+            &self.http_client,
+            self.health_check.as_ref(),
+            self.warmup,
+        )
+        .await;
+    }
+
+    /// Probe every backend without holding the pool lock, so selection isn't
+    /// blocked on slow backends, then take the write lock to apply the results
+    async fn refresh_backends(
+        backends: &RwLock<Vec<Backend>>,
+        http_client: &reqwest::Client,
+        health_check: Option<&HealthCheck>,
+        warmup: Option<Duration>,
+    ) {
+        let targets: Vec<(String, bool)> = backends
+            .read()
+            .await
+            .iter()
+            .map(|backend| (backend.url.clone(), backend.is_warming(warmup)))
+            .collect();
+
+        let mut probes = Vec::with_capacity(targets.len());
+        for (url, warming) in targets {
+            let ready = warming && Self::probe_ready(http_client, &url).await;
+            let health = match health_check {
+                Some(health_check) => {
+                    Some(Self::probe_health(http_client, &url, health_check).await)
+                }
+                None => None,
+            };
+            let capacity = Self::fetch_capacity(http_client, &url).await;
+            probes.push(Probe {
+                url,
+                ready,
+                health,
+                capacity,
+            });
+        }
+
+        let mut backends_guard = backends.write().await;
+        for probe in probes {
+            // Skip a backend removed while it was being probed
+            let Some(backend) = backends_guard.iter_mut().find(|b| b.url == probe.url) else {
+                continue;
+            };
+
+            if warmup.is_some() && !backend.warm {
+                Self::check_warm(backend, probe.ready, warmup);
+            }
+
+            let Some(health) = probe.health else {
+                // Without a health probe, capacity fetch failures count against health
+                let success = probe.capacity.is_ok();
+                match probe.capacity {
+                    Ok(capacity) => Self::apply_capacity(backend, capacity),
+                    Err(e) => error!("Failed to fetch capacity from {}: {}", backend.url, e),
+                }
+                Self::record_health(backend, success);
+                continue;
+            };
+
+            match health {
+                Ok(()) => Self::record_health(backend, true),
+                Err(e) => {
+                    error!("Health check failed for {}: {}", backend.url, e);
+                    Self::record_health(backend, false);
+                }
+            }
+            match probe.capacity {
+                Ok(capacity) => Self::apply_capacity(backend, capacity),
+                Err(e) => warn!(
+                    "Failed to fetch capacity from {}: {} (keeping capacity from {:.0}s ago)",
+                    backend.url,
+                    e,
+                    backend.last_updated.elapsed().as_secs_f64()
+                ),
+            }
+        }
+    }
+
+    /// End a backend's warmup once its window has elapsed or `/ready` succeeded
+    fn check_warm(backend: &mut Backend, ready: bool, warmup: Option<Duration>) {
+        let reason = if !backend.is_warming(warmup) {
+            "warmup window elapsed"
+        } else if ready {
+            "reported ready"
+        } else {
+            return;
+        };
+        info!(
+            "Backend {} warm after {:.0}s ({}): eligible for selection",
+            backend.url,
+            backend.discovered_at.elapsed().as_secs_f64(),
+            reason
+        );
+        backend.warm = true;
+    }
+
+    /// Whether a backend's `/ready` reports its workers ready
+    async fn probe_ready(client: &reqwest::Client, backend_url: &str) -> bool {
+        let url = format!("{}/ready", backend_url);
+        match client.get(&url).send().await {
+            Ok(response) => response.status().is_success(),
+            Err(e) => {
+                debug!("Readiness probe failed for {}: {}", backend_url, e);
+                false
+            }
+        }
+    }
+
+    /// Record the latest capacity report
+    fn apply_capacity(backend: &mut Backend, capacity: CapacityResponse) {
+        backend.available_cpus = capacity.available_cpus;
+        backend.available_gpus = capacity.available_gpus;
+        backend.available_memory_gb = capacity.available_memory_gb;
+
+        // Update totals if provided
+        if let Some(total) = capacity.total {
+            backend.total_cpus = total.cpus;
+            backend.total_gpus = total.gpus;
+            backend.total_memory_gb = total.memory_gb;
+        }
+        backend.workers = capacity.workers.into_iter().map(|w| w.available).collect();
+        backend.last_updated = Instant::now();
+
+        debug!(
+            "Backend {} capacity: CPU={:.1}/{:.1}, GPU={:.1}/{:.1}, MEM={:.1}/{:.1}GB",
+            backend.url,
+            backend.total_cpus - backend.available_cpus,
+            backend.total_cpus,
+            backend.total_gpus - backend.available_gpus,
+            backend.total_gpus,
+            backend.total_memory_gb - backend.available_memory_gb,
+            backend.total_memory_gb
+        );
+    }
+
+    /// A success marks the backend healthy; three failures in a row mark it unhealthy
+    fn record_health(backend: &mut Backend, success: bool) {
+        if success {
+            backend.healthy = true;
+            backend.error_count = 0;
+            return;
+        }
+
+        backend.error_count += 1;
+        if backend.error_count >= 3 {
+            if backend.healthy {
+                warn!(
+                    "Backend {} marked unhealthy after {} errors",
+                    backend.url, backend.error_count
+                );
+            }
+            backend.healthy = false;
+        }
+    }
+
+    /// Probe a backend's health endpoint; any 2xx counts as healthy
+    async fn probe_health(
+        client: &reqwest::Client,
+        backend_url: &str,
+        health_check: &HealthCheck,
+    ) -> Result<(), String> {
+        let url = format!("{}{}", backend_url, health_check.path);
+        let response = client
+            .request(health_check.method.clone(), &url)
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(format!("HTTP {}", response.status()))
+        }
+    }
+
     /// Fetch capacity from a backend
     async fn fetch_capacity(
         client: &reqwest::Client,
@@ -229,20 +472,44 @@ impl BackendPool {
             .map_err(|e| format!("Failed to parse JSON: {}", e))
     }
 
-    /// Find a backend with sufficient resources
+    /// Find a backend with sufficient resources, skipping the URLs in `exclude`
+    /// (backends already tried for this request).
     /// Uses least-utilized backend among those with capacity (load balancing)
     pub async fn find_backend_with_resources(
         &self,
         cpus: f64,
         gpus: f64,
         memory_gb: f64,
+        exclude: &[String],
     ) -> Option<Backend> {
         let backends = self.backends.read().await;
 
-        // Find all backends with sufficient capacity
+        // Find all backends with sufficient, recently reported capacity
         let mut candidates: Vec<&Backend> = backends
             .iter()
-            .filter(|b| b.has_capacity(cpus, gpus, memory_gb))
+            .filter(|b| b.has_capacity(cpus, gpus, memory_gb) && !exclude.contains(&b.url))
+            .filter(|b| {
+                let warming = b.is_warming(self.warmup);
+                if warming {
+                    debug!(
+                        "Skipping backend {}: warming up ({:.0}s since discovery)",
+                        b.url,
+                        b.discovered_at.elapsed().as_secs_f64()
+                    );
+                }
+                !warming
+            })
+            .filter(|b| {
+                let stale = b.is_stale(self.capacity_staleness_limit);
+                if stale {
+                    debug!(
+                        "Skipping backend {}: capacity data is {:.0}s old",
+                        b.url,
+                        b.last_updated.elapsed().as_secs_f64()
+                    );
+                }
+                !stale
+            })
             .collect();
 
         if candidates.is_empty() {
@@ -273,14 +540,44 @@ impl BackendPool {
         Some(selected)
     }
 
+    /// Set whether a backend is draining; returns false if no backend has that URL
+    pub async fn set_draining(&self, url: &str, draining: bool) -> bool {
+        let mut backends = self.backends.write().await;
+        match backends.iter_mut().find(|b| b.url == url) {
+            Some(backend) => {
+                if backend.draining != draining {
+                    info!(
+                        "Backend {} {}",
+                        url,
+                        if draining {
+                            "draining: excluded from selection"
+                        } else {
+                            "undrained: eligible for selection"
+                        }
+                    );
+                }
+                backend.draining = draining;
+                true
+            }
+            None => false,
+        }
+    }
+
     /// Get all backends (for monitoring/debugging)
+    #[allow(dead_code)]
     pub async fn get_backends(&self) -> Vec<Backend> {
         self.backends.read().await.clone()
     }
 
     /// Get count of healthy backends
+    #[allow(dead_code)]
     pub async fn healthy_count(&self) -> usize {
-        self.backends.read().await.iter().filter(|b| b.healthy).count()
+        self.backends
+            .read()
+            .await
+            .iter()
+            .filter(|b| b.healthy)
+            .count()
     }
 }
 
@@ -315,4 +612,362 @@ mod tests {
         backend.available_gpus = 0.0; // 100% used
         assert_eq!(backend.utilization(), 1.0);
     }
+
+    #[tokio::test]
+    async fn test_drained_backend_excluded_from_selection() {
+        let pool = BackendPool::new(DiscoveryMode::Static(vec![]), 60, 5);
+        for url in ["http://a:8080", "http://b:8080"] {
+            let mut backend = Backend::new(url.to_string());
+            backend.available_cpus = 4.0;
+            backend.available_memory_gb = 8.0;
+            backend.total_cpus = 4.0;
+            backend.total_memory_gb = 8.0;
+            backend.healthy = true;
+            pool.backends.write().await.push(backend);
+        }
+
+        assert!(pool.set_draining("http://a:8080", true).await);
+        for _ in 0..3 {
+            let selected = pool
+                .find_backend_with_resources(1.0, 0.0, 1.0, &[])
+                .await
+                .unwrap();
+            assert_eq!(selected.url, "http://b:8080");
+        }
+
+        // Still monitored, just not selectable
+        let backends = pool.get_backends().await;
+        assert_eq!(backends.len(), 2);
+        assert!(backends[0].draining && backends[0].healthy);
+
+        assert!(pool.set_draining("http://b:8080", true).await);
+        assert!(pool
+            .find_backend_with_resources(1.0, 0.0, 1.0, &[])
+            .await
+            .is_none());
+
+        assert!(pool.set_draining("http://a:8080", false).await);
+        let selected = pool
+            .find_backend_with_resources(1.0, 0.0, 1.0, &[])
+            .await
+            .unwrap();
+        assert_eq!(selected.url, "http://a:8080");
+
+        assert!(!pool.set_draining("http://unknown:8080", true).await);
+    }
+
+    #[tokio::test]
+    async fn test_gpu_task_needs_a_single_worker_with_free_gpu() {
+        use axum::{routing::get, Json, Router};
+
+        // Aggregate: 8 CPUs and 1 GPU free, but the CPU worker has no GPU and
+        // the GPU worker's CPUs are all allocated
+        let capacity = serde_json::json!({
+            "available_cpus": 8.0,
+            "available_gpus": 1.0,
+            "available_memory_gb": 16.0,
+            "total": {"cpus": 12.0, "gpus": 1.0, "memory_gb": 16.0},
+            "workers": [
+                {"worker_id": "cpu-0", "available": {"cpus": 8.0, "gpus": 0.0, "memory_gb": 8.0}},
+                {"worker_id": "gpu-0", "available": {"cpus": 0.0, "gpus": 1.0, "memory_gb": 8.0}},
+            ],
+        });
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let router = Router::new().route("/capacity", get(move || async move { Json(capacity) }));
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        let pool = BackendPool::new(DiscoveryMode::Static(vec![url.clone()]), 60, 5);
+        pool.start().await.unwrap();
+        assert_eq!(pool.get_backends().await[0].workers.len(), 2);
+
+        assert!(pool
+            .find_backend_with_resources(1.0, 1.0, 1.0, &[])
+            .await
+            .is_none());
+        assert!(pool
+            .find_backend_with_resources(0.0, 1.0, 1.0, &[])
+            .await
+            .is_some());
+        assert_eq!(
+            pool.find_backend_with_resources(4.0, 0.0, 4.0, &[])
+                .await
+                .unwrap()
+                .url,
+            url
+        );
+
+        // Backends that don't report workers are judged on the aggregate
+        let mut aggregate_only = Backend::new("http://legacy:8080".to_string());
+        aggregate_only.available_cpus = 8.0;
+        aggregate_only.available_gpus = 1.0;
+        aggregate_only.available_memory_gb = 16.0;
+        aggregate_only.healthy = true;
+        assert!(aggregate_only.has_capacity(1.0, 1.0, 1.0));
+    }
+
+    #[tokio::test]
+    async fn test_capacity_failure_keeps_backend_healthy_with_stale_capacity() {
+        use axum::{
+            http::StatusCode,
+            routing::{get, post},
+            Json, Router,
+        };
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        let capacity_up = Arc::new(AtomicBool::new(true));
+        let router = Router::new()
+            .route(
+                "/capacity",
+                get({
+                    let capacity_up = Arc::clone(&capacity_up);
+                    move || async move {
+                        if !capacity_up.load(Ordering::SeqCst) {
+                            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+                        }
+                        Ok(Json(serde_json::json!({
+                            "available_cpus": 3.0,
+                            "available_gpus": 0.0,
+                            "available_memory_gb": 6.0,
+                            "total": {"cpus": 4.0, "gpus": 0.0, "memory_gb": 8.0},
+                        })))
+                    }
+                }),
+            )
+            .route("/internal/live", post(|| async { "ok" }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        let health_check = HealthCheck {
+            path: "/internal/live".to_string(),
+            method: reqwest::Method::POST,
+        };
+        let pool = BackendPool::new(DiscoveryMode::Static(vec![url]), 60, 5)
+            .with_health_check(health_check);
+        pool.start().await.unwrap();
+        let fetched_at = pool.get_backends().await[0].last_updated;
+
+        // Well past the three failures that would mark it unhealthy without the probe
+        capacity_up.store(false, Ordering::SeqCst);
+        for _ in 0..4 {
+            pool.refresh().await;
+        }
+
+        let backend = &pool.get_backends().await[0];
+        assert!(backend.healthy);
+        assert_eq!(backend.error_count, 0);
+        assert_eq!(
+            (backend.available_cpus, backend.available_memory_gb),
+            (3.0, 6.0)
+        );
+        assert_eq!(backend.last_updated, fetched_at);
+        assert!(pool
+            .find_backend_with_resources(1.0, 0.0, 1.0, &[])
+            .await
+            .is_some());
+    }
+
+    #[tokio::test]
+    async fn test_selection_not_blocked_while_backends_are_probed() {
+        use axum::{routing::get, Json, Router};
+        use tokio::sync::Notify;
+
+        let probed = Arc::new(Notify::new());
+        let release = Arc::new(Notify::new());
+        let router = Router::new().route(
+            "/capacity",
+            get({
+                let (probed, release) = (Arc::clone(&probed), Arc::clone(&release));
+                move || async move {
+                    probed.notify_one();
+                    release.notified().await;
+                    Json(serde_json::json!({
+                        "available_cpus": 2.0,
+                        "available_gpus": 0.0,
+                        "available_memory_gb": 4.0,
+                    }))
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        let pool = Arc::new(BackendPool::new(DiscoveryMode::Static(vec![]), 60, 5));
+        let mut backend = Backend::new(url);
+        backend.available_cpus = 4.0;
+        backend.available_memory_gb = 8.0;
+        backend.healthy = true;
+        pool.backends.write().await.push(backend);
+
+        let refresh = tokio::spawn({
+            let pool = Arc::clone(&pool);
+            async move { pool.refresh().await }
+        });
+        probed.notified().await;
+
+        // The backend is still answering, and the last known capacity is served meanwhile
+        let selected = tokio::time::timeout(
+            Duration::from_secs(1),
+            pool.find_backend_with_resources(1.0, 0.0, 1.0, &[]),
+        )
+        .await
+        .expect("selection waited on the probe");
+        assert!(selected.is_some());
+
+        release.notify_one();
+        refresh.await.unwrap();
+        let backend = &pool.get_backends().await[0];
+        assert_eq!(
+            (backend.available_cpus, backend.available_memory_gb),
+            (2.0, 4.0)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_backend_with_stale_capacity_excluded() {
+        let pool = BackendPool::new(DiscoveryMode::Static(vec![]), 60, 5)
+            .with_capacity_staleness_limit(Duration::from_secs(30));
+        for url in ["http://a:8080", "http://b:8080"] {
+            let mut backend = Backend::new(url.to_string());
+            backend.available_cpus = 4.0;
+            backend.available_memory_gb = 8.0;
+            backend.total_cpus = 4.0;
+            backend.total_memory_gb = 8.0;
+            backend.healthy = true;
+            pool.backends.write().await.push(backend);
+        }
+        // b is busier, so a is preferred while its data is fresh
+        pool.backends.write().await[1].available_cpus = 1.0;
+        let selected = pool
+            .find_backend_with_resources(1.0, 0.0, 1.0, &[])
+            .await
+            .unwrap();
+        assert_eq!(selected.url, "http://a:8080");
+
+        // a's polling stalls past the limit
+        let last_fetch = Instant::now().checked_sub(Duration::from_secs(31)).unwrap();
+        pool.backends.write().await[0].last_updated = last_fetch;
+        assert!(pool.get_backends().await[0].is_stale(pool.capacity_staleness_limit));
+        let selected = pool
+            .find_backend_with_resources(1.0, 0.0, 1.0, &[])
+            .await
+            .unwrap();
+        assert_eq!(selected.url, "http://b:8080");
+
+        pool.backends.write().await[1].last_updated = last_fetch;
+        assert!(pool
+            .find_backend_with_resources(1.0, 0.0, 1.0, &[])
+            .await
+            .is_none());
+
+        // Fresh data makes a selectable again
+        let capacity: CapacityResponse = serde_json::from_value(serde_json::json!({
+            "available_cpus": 4.0,
+            "available_gpus": 0.0,
+            "available_memory_gb": 8.0,
+            "total": {"cpus": 4.0, "gpus": 0.0, "memory_gb": 8.0},
+        }))
+        .unwrap();
+        BackendPool::apply_capacity(&mut pool.backends.write().await[0], capacity);
+        let selected = pool
+            .find_backend_with_resources(1.0, 0.0, 1.0, &[])
+            .await
+            .unwrap();
+        assert_eq!(selected.url, "http://a:8080");
+
+        // Without a limit, old data is still used
+        let unlimited = BackendPool::new(DiscoveryMode::Static(vec![]), 60, 5);
+        let mut old = Backend::new("http://c:8080".to_string());
+        old.available_cpus = 4.0;
+        old.available_memory_gb = 8.0;
+        old.healthy = true;
+        old.last_updated = last_fetch;
+        unlimited.backends.write().await.push(old);
+        assert!(unlimited
+            .find_backend_with_resources(1.0, 0.0, 1.0, &[])
+            .await
+            .is_some());
+    }
+
+    #[tokio::test]
+    async fn test_new_backend_not_selected_until_warm() {
+        use axum::{http::StatusCode, routing::get, Json, Router};
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        let ready = Arc::new(AtomicBool::new(false));
+        let router = Router::new()
+            .route(
+                "/capacity",
+                get(|| async {
+                    Json(serde_json::json!({
+                        "available_cpus": 4.0,
+                        "available_gpus": 0.0,
+                        "available_memory_gb": 8.0,
+                        "total": {"cpus": 4.0, "gpus": 0.0, "memory_gb": 8.0},
+                    }))
+                }),
+            )
+            .route(
+                "/ready",
+                get({
+                    let ready = Arc::clone(&ready);
+                    move || async move {
+                        if ready.load(Ordering::SeqCst) {
+                            StatusCode::OK
+                        } else {
+                            StatusCode::SERVICE_UNAVAILABLE
+                        }
+                    }
+                }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        let pool = BackendPool::new(DiscoveryMode::Static(vec![url.clone()]), 60, 5)
+            .with_warmup(Duration::from_secs(60));
+        pool.start().await.unwrap();
+
+        // Healthy with capacity, but its workers are still warming up
+        let backend = &pool.get_backends().await[0];
+        assert!(backend.healthy && !backend.warm);
+        assert!(pool
+            .find_backend_with_resources(1.0, 0.0, 1.0, &[])
+            .await
+            .is_none());
+
+        // Reporting ready ends the warmup early
+        ready.store(true, Ordering::SeqCst);
+        pool.refresh().await;
+        assert!(pool.get_backends().await[0].warm);
+        assert_eq!(
+            pool.find_backend_with_resources(1.0, 0.0, 1.0, &[])
+                .await
+                .unwrap()
+                .url,
+            url
+        );
+
+        // A backend that never reports ready is selected once the window elapses
+        let mut fresh = Backend::new("http://fresh:8080".to_string());
+        fresh.available_cpus = 4.0;
+        fresh.available_memory_gb = 8.0;
+        fresh.healthy = true;
+        pool.backends.write().await.push(fresh);
+        let exclude = [url];
+        assert!(pool
+            .find_backend_with_resources(1.0, 0.0, 1.0, &exclude)
+            .await
+            .is_none());
+
+        let discovered_at = Instant::now().checked_sub(Duration::from_secs(61)).unwrap();
+        pool.backends.write().await[1].discovered_at = discovered_at;
+        let selected = pool
+            .find_backend_with_resources(1.0, 0.0, 1.0, &exclude)
+            .await
+            .unwrap();
+        assert_eq!(selected.url, "http://fresh:8080");
+    }
 }
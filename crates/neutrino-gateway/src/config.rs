@@ -1,20 +1,61 @@
+use axum::http::StatusCode;
+use neutrino_core::config::OtelConfig;
+use neutrino_core::header_limits::{
+    HeaderLimits, DEFAULT_MAX_RESPONSE_HEADERS, DEFAULT_MAX_RESPONSE_HEADER_BYTES,
+};
+use std::collections::HashMap;
 use std::env;
+use tracing::warn;
+
+use crate::db_logger::BodyCompression;
 
 #[derive(Debug, Clone)]
 pub struct GatewayConfig {
     pub port: u16,
     pub database_path: String,
+    pub body_compression: BodyCompression, // Codec for stored request/response bodies
 
     // Backend discovery
-    pub discovery_mode: String,  // "static" | "kubernetes"
-    pub static_backends: Vec<String>,  // Comma-separated URLs for static mode
+    pub discovery_mode: String,       // "static" | "kubernetes"
+    pub static_backends: Vec<String>, // Comma-separated URLs for static mode
 
     // Capacity monitoring
     pub capacity_update_interval_secs: u64,
     pub capacity_timeout_secs: u64,
+    pub health_path: Option<String>, // Liveness endpoint polled instead of judging health by /capacity
+    pub health_method: reqwest::Method, // HTTP method for health_path
+    pub capacity_staleness_limit_secs: Option<u64>, // Backends with older capacity data aren't selected
+    pub backend_warmup_secs: Option<u64>, // New backends aren't selected until this long has passed or /ready succeeds
 
     // OpenAPI spec for resource-aware routing
     pub openapi_spec_path: String,
+    pub resource_policy_path: Option<String>, // Sidecar resources.yaml merged over the spec
+
+    // Shadow traffic mirroring
+    pub shadow_backend: Option<String>, // URL of the shadow backend
+    pub shadow_percent: f64,            // Percentage of requests mirrored (0-100)
+    pub shadow_max_in_flight: usize,    // Mirrors beyond this many in flight are dropped
+    pub shadow_timeout_secs: u64,       // Time allowed for a shadow response and its comparison
+
+    // Failover
+    pub retryable_statuses: Vec<StatusCode>, // Backend statuses that idempotent requests retry on another backend
+
+    // Rate limiting
+    pub rate_limits: HashMap<String, u64>, // Requests allowed per window, by handler
+    pub rate_limit_window_secs: u64,
+    pub rate_limit_redis_url: Option<String>, // Shares counts across replicas (needs the `redis` build feature)
+
+    // Shutdown
+    pub shutdown_timeout_secs: u64, // Time allowed for in-flight requests and log writes on SIGTERM
+
+    // Request logging
+    pub log_redact_fields: Vec<String>, // JSON body fields stored as "***" in the request log
+
+    // Response relaying
+    pub response_header_limits: HeaderLimits, // Backend response headers beyond these are dropped
+
+    // Telemetry
+    pub otel: Option<OtelConfig>, // OTLP collector for request spans (needs the `otel` build feature)
 }
 
 impl GatewayConfig {
@@ -39,6 +80,19 @@ impl GatewayConfig {
                 .unwrap_or(8080),
             database_path: env::var("DATABASE_PATH")
                 .unwrap_or_else(|_| "/data/neutrino.db".to_string()),
+            body_compression: env::var("BODY_COMPRESSION")
+                .ok()
+                .and_then(|s| {
+                    let parsed = BodyCompression::parse(&s);
+                    if parsed.is_none() {
+                        warn!(
+                            "Unsupported BODY_COMPRESSION '{}' (expected none, zstd or gzip), storing bodies uncompressed",
+                            s
+                        );
+                    }
+                    parsed
+                })
+                .unwrap_or_default(),
             discovery_mode,
             static_backends,
             capacity_update_interval_secs: env::var("CAPACITY_UPDATE_INTERVAL")
@@ -49,7 +103,182 @@ impl GatewayConfig {
                 .unwrap_or_else(|_| "5".to_string())
                 .parse()
                 .unwrap_or(5),
+            health_path: env::var("HEALTH_PATH")
+                .ok()
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .map(|s| {
+                    if s.starts_with('/') {
+                        s
+                    } else {
+                        format!("/{}", s)
+                    }
+                }),
+            health_method: env::var("HEALTH_METHOD")
+                .ok()
+                .and_then(|s| {
+                    let parsed = s.trim().to_ascii_uppercase().parse().ok();
+                    if parsed.is_none() {
+                        warn!("Invalid HEALTH_METHOD '{}', using GET", s);
+                    }
+                    parsed
+                })
+                .unwrap_or(reqwest::Method::GET),
+            capacity_staleness_limit_secs: env::var("CAPACITY_STALENESS_LIMIT")
+                .ok()
+                .and_then(|s| s.trim().parse().ok())
+                .filter(|&secs| secs > 0),
+            backend_warmup_secs: env::var("BACKEND_WARMUP_SECS")
+                .ok()
+                .and_then(|s| s.trim().parse().ok())
+                .filter(|&secs| secs > 0),
             openapi_spec_path,
+            resource_policy_path: env::var("RESOURCE_POLICY_PATH")
+                .ok()
+                .filter(|s| !s.trim().is_empty()),
+            shadow_backend: env::var("SHADOW_BACKEND")
+                .ok()
+                .map(|s| s.trim().trim_end_matches('/').to_string())
+                .filter(|s| !s.is_empty()),
+            shadow_percent: env::var("SHADOW_PERCENT")
+                .unwrap_or_else(|_| "100".to_string())
+                .parse::<f64>()
+                .unwrap_or(100.0)
+                .clamp(0.0, 100.0),
+            shadow_max_in_flight: env::var("SHADOW_MAX_IN_FLIGHT")
+                .ok()
+                .and_then(|s| s.trim().parse().ok())
+                .unwrap_or(32),
+            shadow_timeout_secs: env::var("SHADOW_TIMEOUT_SECS")
+                .ok()
+                .and_then(|s| s.trim().parse().ok())
+                .filter(|&secs| secs > 0)
+                .unwrap_or(10),
+            retryable_statuses: parse_status_list(
+                &env::var("RETRYABLE_STATUSES").unwrap_or_default(),
+            )
+            .unwrap_or_else(|e| {
+                warn!("Invalid RETRYABLE_STATUSES ({}), failover disabled", e);
+                Vec::new()
+            }),
+            rate_limits: parse_rate_limits(&env::var("RATE_LIMITS").unwrap_or_default())
+                .unwrap_or_else(|e| {
+                    warn!("Invalid RATE_LIMITS ({}), running without rate limits", e);
+                    HashMap::new()
+                }),
+            rate_limit_window_secs: env::var("RATE_LIMIT_WINDOW_SECS")
+                .ok()
+                .and_then(|s| s.trim().parse().ok())
+                .filter(|&secs| secs > 0)
+                .unwrap_or(1),
+            rate_limit_redis_url: env::var("RATE_LIMIT_REDIS_URL")
+                .ok()
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty()),
+            shutdown_timeout_secs: env::var("SHUTDOWN_TIMEOUT_SECS")
+                .unwrap_or_else(|_| "30".to_string())
+                .parse()
+                .unwrap_or(30),
+            log_redact_fields: env::var("LOG_REDACT_FIELDS")
+                .unwrap_or_default()
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect(),
+            response_header_limits: HeaderLimits {
+                max_count: env::var("MAX_RESPONSE_HEADERS")
+                    .ok()
+                    .and_then(|s| s.trim().parse().ok())
+                    .unwrap_or(DEFAULT_MAX_RESPONSE_HEADERS),
+                max_bytes: env::var("MAX_RESPONSE_HEADER_BYTES")
+                    .ok()
+                    .and_then(|s| s.trim().parse().ok())
+                    .unwrap_or(DEFAULT_MAX_RESPONSE_HEADER_BYTES),
+            },
+            otel: otel_from_env(),
         }
     }
 }
+
+/// OTLP collector settings, read on their own so tracing can be set up before
+/// the rest of the config is loaded (and its warnings logged)
+pub fn otel_from_env() -> Option<OtelConfig> {
+    env::var("OTEL_EXPORTER_OTLP_ENDPOINT")
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .map(|endpoint| OtelConfig {
+            endpoint,
+            service_name: env::var("OTEL_SERVICE_NAME")
+                .unwrap_or_else(|_| "neutrino-gateway".to_string()),
+        })
+}
+
+/// Parse a comma-separated list of HTTP status codes (e.g., "429,503")
+pub fn parse_status_list(list: &str) -> Result<Vec<StatusCode>, String> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            s.parse::<u16>()
+                .ok()
+                .filter(|code| (100..=599).contains(code))
+                .and_then(|code| StatusCode::from_u16(code).ok())
+                .ok_or_else(|| format!("'{}' is not an HTTP status code (100-599)", s))
+        })
+        .collect()
+}
+
+/// Parse comma-separated per-handler limits (e.g., "generate=10,embed=100")
+pub fn parse_rate_limits(list: &str) -> Result<HashMap<String, u64>, String> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            s.split_once('=')
+                .and_then(|(handler, limit)| {
+                    Some((handler.trim(), limit.trim().parse::<u64>().ok()?))
+                })
+                .filter(|(handler, _)| !handler.is_empty())
+                .map(|(handler, limit)| (handler.to_string(), limit))
+                .ok_or_else(|| format!("'{}' is not handler=limit", s))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_status_list() {
+        assert_eq!(
+            parse_status_list("429, 503,").unwrap(),
+            vec![
+                StatusCode::TOO_MANY_REQUESTS,
+                StatusCode::SERVICE_UNAVAILABLE
+            ]
+        );
+        assert!(parse_status_list("").unwrap().is_empty());
+
+        for invalid in ["abc", "42", "700", "503,5o3"] {
+            assert!(
+                parse_status_list(invalid).is_err(),
+                "accepted {:?}",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_rate_limits() {
+        let limits = parse_rate_limits("generate=10, embed = 100,").unwrap();
+        assert_eq!(
+            limits,
+            HashMap::from([("generate".to_string(), 10), ("embed".to_string(), 100)])
+        );
+        assert!(parse_rate_limits("").unwrap().is_empty());
+        assert!(parse_rate_limits("generate").is_err());
+        assert!(parse_rate_limits("generate=fast").is_err());
+    }
+}
@@ -1,11 +1,13 @@
-use rusqlite::{Connection, params};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use rusqlite::{params, types::Value, Connection, OptionalExtension};
 use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
 use std::path::Path;
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, oneshot};
 use tokio::time::{sleep, Duration};
 use tracing::{error, info, warn};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct LogEntry {
     pub id: String,
     pub function_name: Option<String>,
@@ -21,55 +23,168 @@ pub struct LogEntry {
     pub error: Option<String>,
 }
 
-impl Default for LogEntry {
-    fn default() -> Self {
-        Self {
-            id: String::new(),
-            function_name: None,
-            method: String::new(),
-            path: String::new(),
-            status: String::new(),
-            created_at: None,
-            completed_at: None,
-            duration_ms: None,
-            status_code: None,
-            request_body: None,
-            response_body: None,
-            error: None,
+/// Final outcome of a task, applied to the row written by `log_start`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LogCompletion {
+    pub status: String,
+    pub completed_at: Option<String>,
+    pub duration_ms: Option<f64>,
+    pub status_code: Option<u16>,
+    pub response_body: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Codec applied to stored request/response bodies, recorded per row in the
+/// `body_compression` column so readers can decode rows written under any setting
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BodyCompression {
+    #[default]
+    None,
+    Zstd,
+    Gzip,
+}
+
+impl BodyCompression {
+    /// Parse a codec name ("none", "zstd", "gzip")
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "" | "none" => Some(BodyCompression::None),
+            "zstd" => Some(BodyCompression::Zstd),
+            "gzip" => Some(BodyCompression::Gzip),
+            _ => None,
         }
     }
+
+    /// Value stored in the `body_compression` column (NULL for uncompressed rows)
+    fn column_value(&self) -> Option<&'static str> {
+        match self {
+            BodyCompression::None => None,
+            BodyCompression::Zstd => Some("zstd"),
+            BodyCompression::Gzip => Some("gzip"),
+        }
+    }
+
+    /// Encode a body for storage: TEXT when uncompressed, a BLOB otherwise
+    fn encode(&self, body: &Option<String>) -> std::io::Result<Value> {
+        let Some(body) = body else {
+            return Ok(Value::Null);
+        };
+        let bytes = match self {
+            BodyCompression::None => return Ok(Value::Text(body.clone())),
+            BodyCompression::Zstd => zstd::encode_all(body.as_bytes(), 3)?,
+            BodyCompression::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(body.as_bytes())?;
+                encoder.finish()?
+            }
+        };
+        Ok(Value::Blob(bytes))
+    }
+
+    /// Decode a stored body written with the codec named in `column`
+    fn decode(column: Option<&str>, stored: Value) -> std::io::Result<Option<String>> {
+        let bytes = match stored {
+            Value::Null => return Ok(None),
+            Value::Text(text) => return Ok(Some(text)),
+            Value::Blob(bytes) => bytes,
+            other => return Ok(Some(format!("{:?}", other))),
+        };
+
+        let decoded = match column {
+            Some("zstd") => zstd::decode_all(bytes.as_slice())?,
+            Some("gzip") => {
+                let mut decoded = Vec::new();
+                GzDecoder::new(bytes.as_slice()).read_to_end(&mut decoded)?;
+                decoded
+            }
+            _ => bytes,
+        };
+        Ok(Some(String::from_utf8_lossy(&decoded).into_owned()))
+    }
+}
+
+fn to_sql_error(e: std::io::Error) -> rusqlite::Error {
+    rusqlite::Error::ToSqlConversionFailure(Box::new(e))
+}
+
+/// A pending database write
+#[derive(Debug, Clone)]
+enum LogOp {
+    Start(LogEntry),
+    Complete(String, LogCompletion),
+}
+
+impl LogOp {
+    fn id(&self) -> &str {
+        match self {
+            LogOp::Start(entry) => &entry.id,
+            LogOp::Complete(id, _) => id,
+        }
+    }
+}
+
+/// Work for the background writer, processed in order
+#[derive(Debug)]
+enum WriterMsg {
+    Op(Box<LogOp>),
+    /// Acknowledged once every earlier op has been written (or given up on)
+    Flush(oneshot::Sender<()>),
 }
 
 /// Non-blocking database logger with retry logic
 pub struct DbLogger {
-    sender: mpsc::UnboundedSender<LogEntry>,
+    sender: mpsc::UnboundedSender<WriterMsg>,
 }
 
 impl DbLogger {
     /// Create a new database logger
-    /// Spawns a background task that processes log entries
-    pub fn new(db_path: String) -> Self {
+    /// Spawns a background task that processes log entries, storing bodies with
+    /// the given compression
+    pub fn new(db_path: String, compression: BodyCompression) -> Self {
         let (tx, rx) = mpsc::unbounded_channel();
 
         // Spawn background writer task
         tokio::spawn(async move {
-            db_writer_task(rx, db_path).await;
+            db_writer_task(rx, db_path, compression).await;
         });
 
         Self { sender: tx }
     }
 
-    /// Log a request (non-blocking)
+    /// Record the start of a request (non-blocking)
     /// If the channel is closed, this will silently fail
-    pub fn log(&self, entry: LogEntry) {
-        if let Err(e) = self.sender.send(entry) {
+    pub fn log_start(&self, entry: LogEntry) {
+        self.send(LogOp::Start(entry));
+    }
+
+    /// Record the outcome of a request started with `log_start` (non-blocking).
+    /// Only the completion fields are updated; the rest of the row is left as is.
+    pub fn log_complete(&self, id: String, completion: LogCompletion) {
+        self.send(LogOp::Complete(id, completion));
+    }
+
+    /// Wait until every entry logged so far has been written. Returns at once
+    /// if the background task has stopped.
+    pub async fn flush(&self) {
+        let (done, flushed) = oneshot::channel();
+        if self.sender.send(WriterMsg::Flush(done)).is_ok() {
+            let _ = flushed.await;
+        }
+    }
+
+    fn send(&self, op: LogOp) {
+        if let Err(e) = self.sender.send(WriterMsg::Op(Box::new(op))) {
             error!("Failed to send log entry to background task: {}", e);
         }
     }
 }
 
 /// Background task that processes log entries with retry logic
-async fn db_writer_task(mut rx: mpsc::UnboundedReceiver<LogEntry>, db_path: String) {
+async fn db_writer_task(
+    mut rx: mpsc::UnboundedReceiver<WriterMsg>,
+    db_path: String,
+    compression: BodyCompression,
+) {
     info!("Database writer task started");
 
     // Initialize database
@@ -78,11 +193,19 @@ async fn db_writer_task(mut rx: mpsc::UnboundedReceiver<LogEntry>, db_path: Stri
         return;
     }
 
-    while let Some(entry) = rx.recv().await {
+    while let Some(msg) = rx.recv().await {
+        let op = match msg {
+            WriterMsg::Op(op) => *op,
+            WriterMsg::Flush(done) => {
+                let _ = done.send(());
+                continue;
+            }
+        };
+
         // Retry up to 3 times with exponential backoff
         let mut success = false;
         for attempt in 0..3 {
-            match write_log_entry(&db_path, &entry) {
+            match write_log_op(&db_path, &op, compression) {
                 Ok(_) => {
                     success = true;
                     break;
@@ -100,7 +223,8 @@ async fn db_writer_task(mut rx: mpsc::UnboundedReceiver<LogEntry>, db_path: Stri
                     } else {
                         error!(
                             "Failed to write log entry after 3 attempts: {}. Entry ID: {}",
-                            e, entry.id
+                            e,
+                            op.id()
                         );
                     }
                 }
@@ -108,7 +232,7 @@ async fn db_writer_task(mut rx: mpsc::UnboundedReceiver<LogEntry>, db_path: Stri
         }
 
         if !success {
-            warn!("Giving up on log entry: {}", entry.id);
+            warn!("Giving up on log entry: {}", op.id());
         }
     }
 
@@ -137,15 +261,21 @@ fn init_database(db_path: &str) -> rusqlite::Result<()> {
             status_code INTEGER,
             request_body TEXT,
             response_body TEXT,
-            error TEXT
+            error TEXT,
+            body_compression TEXT
         )",
         [],
     )?;
 
-    conn.execute(
-        "CREATE INDEX IF NOT EXISTS idx_status ON tasks(status)",
-        [],
-    )?;
+    // Databases created before body compression lack the codec column
+    let has_compression_column = conn
+        .prepare("SELECT 1 FROM pragma_table_info('tasks') WHERE name = 'body_compression'")?
+        .exists([])?;
+    if !has_compression_column {
+        conn.execute("ALTER TABLE tasks ADD COLUMN body_compression TEXT", [])?;
+    }
+
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_status ON tasks(status)", [])?;
 
     conn.execute(
         "CREATE INDEX IF NOT EXISTS idx_created_at ON tasks(created_at)",
@@ -161,16 +291,33 @@ fn init_database(db_path: &str) -> rusqlite::Result<()> {
     Ok(())
 }
 
-/// Write a log entry to the database
-fn write_log_entry(db_path: &str, entry: &LogEntry) -> rusqlite::Result<()> {
+/// Apply a log operation to the database
+fn write_log_op(db_path: &str, op: &LogOp, compression: BodyCompression) -> rusqlite::Result<()> {
     let conn = Connection::open(db_path)?;
 
-    // Use INSERT OR REPLACE to handle both new entries and updates
+    match op {
+        LogOp::Start(entry) => insert_log_entry(&conn, entry, compression),
+        LogOp::Complete(id, completion) => {
+            let updated = update_log_entry(&conn, id, completion, compression)?;
+            if updated == 0 {
+                warn!("No started log entry to complete: {}", id);
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Insert the initial row for a request
+fn insert_log_entry(
+    conn: &Connection,
+    entry: &LogEntry,
+    compression: BodyCompression,
+) -> rusqlite::Result<()> {
     conn.execute(
-        "INSERT OR REPLACE INTO tasks (
+        "INSERT INTO tasks (
             id, function_name, method, path, status, created_at, completed_at,
-            duration_ms, status_code, request_body, response_body, error
-        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+            duration_ms, status_code, request_body, response_body, error, body_compression
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
         params![
             entry.id,
             entry.function_name,
@@ -181,11 +328,209 @@ fn write_log_entry(db_path: &str, entry: &LogEntry) -> rusqlite::Result<()> {
             entry.completed_at,
             entry.duration_ms,
             entry.status_code,
-            entry.request_body,
-            entry.response_body,
+            compression
+                .encode(&entry.request_body)
+                .map_err(to_sql_error)?,
+            compression
+                .encode(&entry.response_body)
+                .map_err(to_sql_error)?,
             entry.error,
+            compression.column_value(),
         ],
     )?;
 
     Ok(())
 }
+
+/// Update an existing row with the request's outcome, returning the number of rows changed.
+/// The response body uses the codec the row was started with.
+fn update_log_entry(
+    conn: &Connection,
+    id: &str,
+    completion: &LogCompletion,
+    compression: BodyCompression,
+) -> rusqlite::Result<usize> {
+    let row_codec: Option<Option<String>> = conn
+        .query_row(
+            "SELECT body_compression FROM tasks WHERE id = ?1",
+            params![id],
+            |row| row.get(0),
+        )
+        .optional()?;
+    let compression = match row_codec {
+        Some(codec) => codec
+            .as_deref()
+            .and_then(BodyCompression::parse)
+            .unwrap_or_default(),
+        None => compression,
+    };
+
+    conn.execute(
+        "UPDATE tasks SET
+            status = ?2, completed_at = ?3, duration_ms = ?4,
+            status_code = ?5, response_body = ?6, error = ?7
+        WHERE id = ?1",
+        params![
+            id,
+            completion.status,
+            completion.completed_at,
+            completion.duration_ms,
+            completion.status_code,
+            compression
+                .encode(&completion.response_body)
+                .map_err(to_sql_error)?,
+            completion.error,
+        ],
+    )
+}
+
+/// Read a logged request, decompressing its bodies
+pub fn read_log_entry(conn: &Connection, id: &str) -> rusqlite::Result<Option<LogEntry>> {
+    conn.query_row(
+        "SELECT id, function_name, method, path, status, created_at, completed_at,
+            duration_ms, status_code, request_body, response_body, error, body_compression
+        FROM tasks WHERE id = ?1",
+        params![id],
+        |row| {
+            let codec: Option<String> = row.get(12)?;
+            let decode = |idx: usize| -> rusqlite::Result<Option<String>> {
+                BodyCompression::decode(codec.as_deref(), row.get(idx)?).map_err(|e| {
+                    rusqlite::Error::FromSqlConversionFailure(
+                        idx,
+                        rusqlite::types::Type::Blob,
+                        Box::new(e),
+                    )
+                })
+            };
+
+            Ok(LogEntry {
+                id: row.get(0)?,
+                function_name: row.get(1)?,
+                method: row.get(2)?,
+                path: row.get(3)?,
+                status: row.get(4)?,
+                created_at: row.get(5)?,
+                completed_at: row.get(6)?,
+                duration_ms: row.get(7)?,
+                status_code: row.get(8)?,
+                request_body: decode(9)?,
+                response_body: decode(10)?,
+                error: row.get(11)?,
+            })
+        },
+    )
+    .optional()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_completion_updates_started_row() {
+        let db_path =
+            std::env::temp_dir().join(format!("neutrino-db-logger-{}.db", uuid::Uuid::new_v4()));
+        let db_path = db_path.to_string_lossy().to_string();
+        init_database(&db_path).unwrap();
+
+        let start = LogOp::Start(LogEntry {
+            id: "task-1".to_string(),
+            function_name: Some("echo".to_string()),
+            method: "POST".to_string(),
+            path: "/api/echo".to_string(),
+            status: "started".to_string(),
+            created_at: Some("2024-01-01T00:00:00+00:00".to_string()),
+            request_body: Some("{}".to_string()),
+            ..Default::default()
+        });
+        write_log_op(&db_path, &start, BodyCompression::None).unwrap();
+
+        let complete = LogOp::Complete(
+            "task-1".to_string(),
+            LogCompletion {
+                status: "completed".to_string(),
+                completed_at: Some("2024-01-01T00:00:01+00:00".to_string()),
+                duration_ms: Some(1000.0),
+                status_code: Some(200),
+                response_body: Some("ok".to_string()),
+                error: None,
+            },
+        );
+        write_log_op(&db_path, &complete, BodyCompression::None).unwrap();
+
+        let conn = Connection::open(&db_path).unwrap();
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM tasks", [], |row| row.get(0))
+            .unwrap();
+        let (status, created_at, request_body, status_code): (String, String, String, u16) = conn
+            .query_row(
+                "SELECT status, created_at, request_body, status_code FROM tasks WHERE id = 'task-1'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )
+            .unwrap();
+
+        assert_eq!(count, 1);
+        assert_eq!(status, "completed");
+        assert_eq!(created_at, "2024-01-01T00:00:00+00:00");
+        assert_eq!(request_body, "{}");
+        assert_eq!(status_code, 200);
+
+        let _ = std::fs::remove_file(db_path);
+    }
+
+    #[test]
+    fn test_compressed_bodies_round_trip() {
+        let request_body = "{\"text\": \"".to_string() + &"lorem ipsum ".repeat(800) + "\"}";
+        let response_body = "dolor sit amet ".repeat(600);
+
+        for compression in [BodyCompression::Zstd, BodyCompression::Gzip] {
+            let db_path = std::env::temp_dir()
+                .join(format!("neutrino-db-logger-{}.db", uuid::Uuid::new_v4()));
+            let db_path = db_path.to_string_lossy().to_string();
+            init_database(&db_path).unwrap();
+
+            let start = LogOp::Start(LogEntry {
+                id: "task-1".to_string(),
+                method: "POST".to_string(),
+                path: "/api/summarize".to_string(),
+                status: "started".to_string(),
+                request_body: Some(request_body.clone()),
+                ..Default::default()
+            });
+            write_log_op(&db_path, &start, compression).unwrap();
+            let complete = LogOp::Complete(
+                "task-1".to_string(),
+                LogCompletion {
+                    status: "completed".to_string(),
+                    response_body: Some(response_body.clone()),
+                    ..Default::default()
+                },
+            );
+            write_log_op(&db_path, &complete, compression).unwrap();
+
+            let conn = Connection::open(&db_path).unwrap();
+            let (codec, stored_len): (String, usize) = conn
+                .query_row(
+                    "SELECT body_compression, length(request_body) FROM tasks WHERE id = 'task-1'",
+                    [],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .unwrap();
+            assert_eq!(Some(codec.as_str()), compression.column_value());
+            assert!(
+                stored_len < request_body.len() / 10,
+                "{:?} stored {} bytes",
+                compression,
+                stored_len
+            );
+
+            let entry = read_log_entry(&conn, "task-1").unwrap().unwrap();
+            assert_eq!(entry.request_body.as_deref(), Some(request_body.as_str()));
+            assert_eq!(entry.response_body.as_deref(), Some(response_body.as_str()));
+            assert!(read_log_entry(&conn, "missing").unwrap().is_none());
+
+            let _ = std::fs::remove_file(db_path);
+        }
+    }
+}
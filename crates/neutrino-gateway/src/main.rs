@@ -2,28 +2,44 @@ mod backend_pool;
 mod config;
 mod db_logger;
 mod proxy;
+mod rate_limit;
+mod shadow;
 
-use axum::{routing::any, Router};
+use axum::{
+    middleware,
+    routing::{any, get, post},
+    Router,
+};
+use neutrino_core::http::handoff;
 use neutrino_core::openapi::ResourceRouter;
+use neutrino_core::telemetry;
+use std::future::Future;
 use std::sync::Arc;
-use tracing::{info, Level};
-use tracing_subscriber;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tracing::{info, warn, Level};
 
-use crate::backend_pool::{BackendPool, DiscoveryMode};
+use crate::backend_pool::{BackendPool, DiscoveryMode, HealthCheck};
 use crate::config::GatewayConfig;
 use crate::db_logger::DbLogger;
-use crate::proxy::{proxy_handler, AppState};
+use crate::proxy::{
+    drain_handler, proxy_handler, request_span, shadow_stats_handler, task_log_handler,
+    undrain_handler, AppState,
+};
+use crate::rate_limit::{CounterStore, InMemoryStore, RateLimiter};
+use crate::shadow::ShadowMirror;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Initialize tracing
-    tracing_subscriber::fmt().with_max_level(Level::INFO).init();
-
-    info!("Starting Neutrino Gateway");
+    // Initialize tracing first so config warnings are logged, exporting to
+    // OpenTelemetry if configured
+    let _telemetry = telemetry::init(Level::INFO, config::otel_from_env().as_ref());
 
     // Load configuration
     let config = GatewayConfig::from_env();
 
+    info!("Starting Neutrino Gateway");
+
     info!("Configuration:");
     info!("  Port: {}", config.port);
     info!("  Discovery mode: {}", config.discovery_mode);
@@ -31,10 +47,51 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         info!("  Static backends: {:?}", config.static_backends);
     }
     info!("  Database path: {}", config.database_path);
-    info!("  Capacity update interval: {}s", config.capacity_update_interval_secs);
+    info!("  Body compression: {:?}", config.body_compression);
+    info!(
+        "  Capacity update interval: {}s",
+        config.capacity_update_interval_secs
+    );
+    if let Some(ref health_path) = config.health_path {
+        info!("  Health check: {} {}", config.health_method, health_path);
+    }
+    if let Some(ref shadow_backend) = config.shadow_backend {
+        info!(
+            "  Shadow backend: {} ({}% of requests, at most {} in flight, {}s timeout)",
+            shadow_backend,
+            config.shadow_percent,
+            config.shadow_max_in_flight,
+            config.shadow_timeout_secs
+        );
+    }
+    info!("  Retryable statuses: {:?}", config.retryable_statuses);
+    if !config.rate_limits.is_empty() {
+        info!(
+            "  Rate limits: {:?} per {}s ({})",
+            config.rate_limits,
+            config.rate_limit_window_secs,
+            config
+                .rate_limit_redis_url
+                .as_deref()
+                .unwrap_or("this instance only")
+        );
+    }
+    info!("  Shutdown timeout: {}s", config.shutdown_timeout_secs);
+    if !config.log_redact_fields.is_empty() {
+        info!("  Redacted log fields: {:?}", config.log_redact_fields);
+    }
+    if let Some(ref otel) = config.otel {
+        info!(
+            "  OpenTelemetry: {} (service {})",
+            otel.endpoint, otel.service_name
+        );
+    }
 
     // Initialize database logger
-    let db_logger = Arc::new(DbLogger::new(config.database_path.clone()));
+    let db_logger = Arc::new(DbLogger::new(
+        config.database_path.clone(),
+        config.body_compression,
+    ));
 
     // Create HTTP client for proxying
     let http_client = reqwest::Client::builder()
@@ -49,39 +106,250 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     };
 
-    let backend_pool = Arc::new(BackendPool::new(
+    let mut backend_pool = BackendPool::new(
         discovery_mode,
         config.capacity_update_interval_secs,
         config.capacity_timeout_secs,
-    ));
+    );
+    if let Some(path) = &config.health_path {
+        backend_pool = backend_pool.with_health_check(HealthCheck {
+            path: path.clone(),
+            method: config.health_method.clone(),
+        });
+    }
+    if let Some(secs) = config.capacity_staleness_limit_secs {
+        backend_pool = backend_pool.with_capacity_staleness_limit(Duration::from_secs(secs));
+    }
+    if let Some(secs) = config.backend_warmup_secs {
+        backend_pool = backend_pool.with_warmup(Duration::from_secs(secs));
+    }
+    let backend_pool = Arc::new(backend_pool);
 
     // Start backend pool monitoring
     backend_pool.start().await?;
 
     // Load OpenAPI spec for resource-aware routing
     info!("Loading OpenAPI spec from: {}", config.openapi_spec_path);
-    let resource_router = Arc::new(ResourceRouter::from_file(&config.openapi_spec_path)?);
+    let resource_router = match &config.resource_policy_path {
+        Some(policy_path) => {
+            info!("Applying resource policy from: {}", policy_path);
+            Arc::new(ResourceRouter::from_files(
+                &config.openapi_spec_path,
+                policy_path,
+            )?)
+        }
+        None => Arc::new(ResourceRouter::from_file(&config.openapi_spec_path)?),
+    };
     info!("OpenAPI spec loaded successfully");
 
+    // Optional shadow traffic mirroring
+    let shadow = config.shadow_backend.clone().map(|url| {
+        ShadowMirror::new(
+            url,
+            config.shadow_percent,
+            config.shadow_max_in_flight,
+            Duration::from_secs(config.shadow_timeout_secs),
+        )
+    });
+
+    // Optional per-handler rate limits
+    let rate_limiter = if config.rate_limits.is_empty() {
+        None
+    } else {
+        Some(Arc::new(RateLimiter::new(
+            config.rate_limits.clone(),
+            Duration::from_secs(config.rate_limit_window_secs),
+            rate_limit_store(config.rate_limit_redis_url.as_deref()).await?,
+        )))
+    };
+
     // Create app state
     let state = AppState {
         backend_pool,
         http_client,
         db_logger,
+        database_path: config.database_path.clone(),
         resource_router,
+        shadow,
+        retryable_statuses: Arc::new(config.retryable_statuses.clone()),
+        log_redact_fields: Arc::new(config.log_redact_fields.clone()),
+        rate_limiter,
+        response_header_limits: config.response_header_limits,
     };
 
-    // Create router - catch all requests and proxy them
-    let app = Router::new()
-        .fallback(any(proxy_handler))
-        .with_state(state);
-
     // Start server
     let addr = format!("0.0.0.0:{}", config.port);
     info!("Gateway listening on {}", addr);
 
-    let listener = tokio::net::TcpListener::bind(&addr).await?;
-    axum::serve(listener, app).await?;
+    let listener = TcpListener::bind(&addr).await?;
+    serve(
+        listener,
+        state,
+        handoff::shutdown_signal(),
+        Duration::from_secs(config.shutdown_timeout_secs),
+    )
+    .await?;
+
+    info!("Gateway shutdown complete");
+    Ok(())
+}
+
+/// Store for rate limit counters: Redis when configured, else this process
+async fn rate_limit_store(
+    redis_url: Option<&str>,
+) -> Result<Arc<dyn CounterStore>, Box<dyn std::error::Error>> {
+    let Some(url) = redis_url else {
+        return Ok(Arc::new(InMemoryStore::default()));
+    };
+
+    #[cfg(feature = "redis")]
+    {
+        info!("Sharing rate limit counters through Redis at {}", url);
+        Ok(Arc::new(rate_limit::RedisStore::connect(url).await?))
+    }
+
+    #[cfg(not(feature = "redis"))]
+    {
+        warn!(
+            "RATE_LIMIT_REDIS_URL is {} but this build lacks the `redis` feature; limits apply per instance",
+            url
+        );
+        Ok(Arc::new(InMemoryStore::default()))
+    }
+}
+
+/// Gateway log queries and backend admin are served by the gateway itself,
+/// everything else is proxied
+fn router(state: AppState) -> Router {
+    Router::new()
+        .route("/_gateway/tasks/:task_id", get(task_log_handler))
+        .route("/_gateway/shadow", get(shadow_stats_handler))
+        .route("/gateway/backends/drain", post(drain_handler))
+        .route("/gateway/backends/undrain", post(undrain_handler))
+        .fallback(any(proxy_handler).layer(middleware::from_fn(request_span)))
+        .with_state(state)
+}
+
+/// Serve until `shutdown` resolves (SIGINT/SIGTERM in production), then stop
+/// accepting, give in-flight proxies up to `drain_timeout` to finish, and flush
+/// pending log writes (also bounded by `drain_timeout`)
+async fn serve(
+    listener: TcpListener,
+    state: AppState,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+    drain_timeout: Duration,
+) -> std::io::Result<()> {
+    let db_logger = Arc::clone(&state.db_logger);
+    handoff::serve(listener, router(state), shutdown, drain_timeout).await?;
 
+    info!("Flushing pending request logs");
+    if tokio::time::timeout(drain_timeout, db_logger.flush())
+        .await
+        .is_err()
+    {
+        warn!(
+            "Request logs not flushed within {}s, exiting anyway",
+            drain_timeout.as_secs()
+        );
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use neutrino_core::OpenApiSpec;
+    use tokio::sync::oneshot;
+
+    #[tokio::test]
+    async fn test_shutdown_finishes_in_flight_request_and_flushes_log() {
+        let backend = Router::new()
+            .route(
+                "/capacity",
+                get(|| async {
+                    axum::Json(serde_json::json!({
+                        "available_cpus": 4.0,
+                        "available_gpus": 0.0,
+                        "available_memory_gb": 8.0,
+                        "total": {"cpus": 4.0, "gpus": 0.0, "memory_gb": 8.0},
+                    }))
+                }),
+            )
+            .route(
+                "/api/work",
+                post(|| async {
+                    tokio::time::sleep(Duration::from_millis(300)).await;
+                    "done"
+                }),
+            );
+        let backend_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let backend_url = format!("http://{}", backend_listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(backend_listener, backend).await.unwrap() });
+
+        let backend_pool = Arc::new(BackendPool::new(
+            DiscoveryMode::Static(vec![backend_url]),
+            60,
+            5,
+        ));
+        backend_pool.start().await.unwrap();
+        let spec: OpenApiSpec = serde_json::from_value(serde_json::json!({
+            "openapi": "3.0.0",
+            "info": {"title": "test", "version": "1.0.0"},
+            "paths": {},
+        }))
+        .unwrap();
+        let db_path =
+            std::env::temp_dir().join(format!("neutrino-gateway-test-{}.db", uuid::Uuid::new_v4()));
+        let db_path = db_path.to_string_lossy().to_string();
+        let state = AppState {
+            backend_pool,
+            http_client: reqwest::Client::new(),
+            db_logger: Arc::new(DbLogger::new(db_path.clone(), Default::default())),
+            database_path: db_path.clone(),
+            resource_router: Arc::new(ResourceRouter::from_spec(&spec)),
+            shadow: None,
+            retryable_statuses: Arc::new(vec![]),
+            log_redact_fields: Arc::new(vec![]),
+            rate_limiter: None,
+            response_header_limits: Default::default(),
+        };
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/api/work", listener.local_addr().unwrap());
+        let (stop, stop_signal) = oneshot::channel::<()>();
+        let server = tokio::spawn(serve(
+            listener,
+            state,
+            async {
+                stop_signal.await.ok();
+            },
+            Duration::from_secs(5),
+        ));
+
+        // Shut down while the request is waiting on the backend
+        let request = tokio::spawn(reqwest::Client::new().post(url).body("{}").send());
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        stop.send(()).unwrap();
+
+        let response = request.await.unwrap().unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        assert_eq!(response.text().await.unwrap(), "done");
+        server.await.unwrap().unwrap();
+
+        // The completion was written before serve returned
+        let conn = rusqlite::Connection::open(&db_path).unwrap();
+        let (status, status_code, response_body): (String, u16, String) = conn
+            .query_row(
+                "SELECT status, status_code, response_body FROM tasks WHERE path = '/api/work'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!(
+            (status.as_str(), status_code, response_body.as_str()),
+            ("completed", 200, "done")
+        );
+
+        let _ = std::fs::remove_file(db_path);
+    }
+}
@@ -1,24 +1,59 @@
 use axum::{
-    body::Body,
-    extract::State,
+    body::{Body, BodyDataStream, Bytes},
+    extract::{Path, State},
     http::{Request, Response, StatusCode},
+    middleware::Next,
     response::IntoResponse,
+    Json,
 };
+use futures_util::{Stream, StreamExt};
+use neutrino_core::header_limits::HeaderLimits;
+use neutrino_core::http::TASK_ID_HEADER;
 use neutrino_core::openapi::ResourceRouter;
+use neutrino_core::redact::redact_json_text;
+use serde::Deserialize;
+use std::pin::Pin;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
-use std::time::Instant;
-use tracing::{error, info};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
+use tracing::{error, info, info_span, warn, Instrument};
 use uuid::Uuid;
 
 use crate::backend_pool::BackendPool;
-use crate::db_logger::{DbLogger, LogEntry};
+use crate::db_logger::{self, DbLogger, LogCompletion, LogEntry};
+use crate::rate_limit::RateLimiter;
+use crate::shadow::{BodyDigest, PrimaryOutcome, ShadowMirror};
 
 #[derive(Clone)]
 pub struct AppState {
     pub backend_pool: Arc<BackendPool>,
     pub http_client: reqwest::Client,
     pub db_logger: Arc<DbLogger>,
+    pub database_path: String,
     pub resource_router: Arc<ResourceRouter>,
+    pub shadow: Option<ShadowMirror>,
+    /// Backend statuses that fail over to another backend with capacity
+    /// (idempotent methods only, since the first backend may have run the request)
+    pub retryable_statuses: Arc<Vec<StatusCode>>,
+    /// JSON body fields redacted before bodies are written to the request log
+    pub log_redact_fields: Arc<Vec<String>>,
+    /// Per-handler request limits, shared across gateway replicas
+    pub rate_limiter: Option<Arc<RateLimiter>>,
+    /// Bounds on the backend response headers relayed to the client
+    pub response_header_limits: HeaderLimits,
+}
+
+/// Run each proxied request in its own span (exported when OpenTelemetry is configured)
+pub async fn request_span(req: Request<Body>, next: Next) -> axum::response::Response {
+    let span = info_span!(
+        "gateway_request",
+        method = %req.method(),
+        path = %req.uri().path(),
+        task_id = tracing::field::Empty,
+    );
+    next.run(req).instrument(span).await
 }
 
 /// Proxy handler that forwards requests to the backend and logs to database
@@ -27,6 +62,7 @@ pub async fn proxy_handler(
     req: Request<Body>,
 ) -> Result<Response<Body>, ProxyError> {
     let task_id = Uuid::new_v4().to_string();
+    tracing::Span::current().record("task_id", task_id.as_str());
     let method = req.method().clone();
     let path = req.uri().path().to_string();
     let query = req
@@ -43,198 +79,495 @@ pub async fn proxy_handler(
         method, path, task_id
     );
 
-    // Capture request body
+    if let Some(rate_limiter) = &state.rate_limiter {
+        if let Err(retry_after) = rate_limiter.check(&function_name).await {
+            warn!(
+                "Rate limit exceeded for {} (task_id: {})",
+                function_name, task_id
+            );
+            return Err(ProxyError::RateLimited(function_name, retry_after));
+        }
+    }
+
+    // Buffer small bodies so they can be replayed on failover and mirrored;
+    // larger ones are streamed through to a single backend
     let (parts, body) = req.into_parts();
-    let body_bytes = match axum::body::to_bytes(body, usize::MAX).await {
-        Ok(bytes) => bytes,
+    let mut body = match RequestBody::read(body).await {
+        Ok(body) => body,
         Err(e) => {
             error!("Failed to read request body: {}", e);
             return Err(ProxyError::BodyReadError(e.to_string()));
         }
     };
-
-    let request_body = String::from_utf8_lossy(&body_bytes).to_string();
-    let created_at = chrono::Utc::now().to_rfc3339();
+    if let RequestBody::Streamed { .. } = body {
+        info!(
+            "Streaming request body over {} bytes to the backend (task_id: {}); no failover or shadow",
+            REPLAY_BUFFER_BYTES, task_id
+        );
+    }
 
     // Log request start (non-blocking)
-    state.db_logger.log(LogEntry {
+    state.db_logger.log_start(LogEntry {
         id: task_id.clone(),
-        function_name: Some(function_name.clone()),
+        function_name: Some(function_name),
         method: method.to_string(),
         path: path.clone(),
         status: "started".to_string(),
-        created_at: Some(created_at.clone()),
-        request_body: Some(truncate_body(&request_body, 10000)),
+        created_at: Some(chrono::Utc::now().to_rfc3339()),
+        request_body: Some(loggable_body(
+            body.prefix(),
+            body.is_buffered(),
+            &state.log_redact_fields,
+        )),
         ..Default::default()
     });
 
     let start = Instant::now();
 
     // Extract resource requirements from OpenAPI spec
-    let requirements = state.resource_router.get_requirements(
-        &method.to_string(),
-        &path
-    );
+    let requirements = state
+        .resource_router
+        .get_requirements(method.as_ref(), &path);
     let cpus = requirements.num_cpus;
     let gpus = requirements.num_gpus;
     let memory_gb = requirements.memory_gb;
 
-    // Find backend with sufficient resources
-    let backend = state
-        .backend_pool
-        .find_backend_with_resources(cpus, gpus, memory_gb)
-        .await;
+    // Try backends until one answers with a status that isn't retryable; each
+    // backend is tried at most once
+    let mut tried: Vec<String> = Vec::new();
+    let mut retryable_response = None;
+    let mut shadow_comparison = None;
+    let proxy_resp = loop {
+        let backend = state
+            .backend_pool
+            .find_backend_with_resources(cpus, gpus, memory_gb, &tried)
+            .await;
 
-    let backend_url = match backend {
-        Some(b) => {
-            info!(
-                "Routing {} to backend {} (requires: cpus={}, gpus={}, mem={}GB)",
-                path, b.url, cpus, gpus, memory_gb
-            );
-            b.url
-        }
-        None => {
-            error!(
-                "No backends available with required resources (cpus={}, gpus={}, mem={}GB)",
-                cpus, gpus, memory_gb
-            );
+        let backend_url = match (backend, retryable_response.take()) {
+            (Some(b), _) => {
+                info!(
+                    "Routing {} to backend {} (requires: cpus={}, gpus={}, mem={}GB)",
+                    path, b.url, cpus, gpus, memory_gb
+                );
+                b.url
+            }
+            // Every backend was tried; pass the last retryable response through
+            (None, Some(resp)) => break resp,
+            (None, None) => {
+                error!(
+                    "No backends available with required resources (cpus={}, gpus={}, mem={}GB)",
+                    cpus, gpus, memory_gb
+                );
 
-            let duration_ms = start.elapsed().as_millis() as f64;
+                let duration_ms = start.elapsed().as_millis() as f64;
 
-            // Log failure - preserve created_at from initial log
-            state.db_logger.log(LogEntry {
-                id: task_id,
-                function_name: Some(function_name),
-                method: method.to_string(),
-                path,
-                status: "failed".to_string(),
-                created_at: Some(created_at),
-                completed_at: Some(chrono::Utc::now().to_rfc3339()),
-                duration_ms: Some(duration_ms),
-                request_body: Some(truncate_body(&request_body, 10000)),
-                error: Some(format!(
-                    "No backends available with resources: cpus={}, gpus={}, mem={}GB",
+                // Log failure (non-blocking)
+                state.db_logger.log_complete(
+                    task_id,
+                    LogCompletion {
+                        status: "failed".to_string(),
+                        completed_at: Some(chrono::Utc::now().to_rfc3339()),
+                        duration_ms: Some(duration_ms),
+                        error: Some(format!(
+                            "No backends available with resources: cpus={}, gpus={}, mem={}GB",
+                            cpus, gpus, memory_gb
+                        )),
+                        ..Default::default()
+                    },
+                );
+
+                return Err(ProxyError::NoCapacity(format!(
+                    "No backends available with required resources: cpus={}, gpus={}, mem={}GB",
                     cpus, gpus, memory_gb
-                )),
-                ..Default::default()
-            });
+                )));
+            }
+        };
 
-            return Err(ProxyError::NoCapacity(format!(
-                "No backends available with required resources: cpus={}, gpus={}, mem={}GB",
-                cpus, gpus, memory_gb
-            )));
+        // Mirror a copy to the shadow backend (fire-and-forget, never blocks the primary)
+        if let RequestBody::Buffered(bytes) = &body {
+            if let Some(shadow) = state
+                .shadow
+                .as_ref()
+                .filter(|s| tried.is_empty() && s.should_mirror())
+            {
+                shadow_comparison = shadow.mirror(
+                    task_id.clone(),
+                    method.clone(),
+                    format!("{}{}", path, query),
+                    parts.headers.clone(),
+                    bytes.to_vec(),
+                );
+            }
         }
-    };
 
-    // Build target URL
-    let target_url = format!("{}{}{}", backend_url, path, query);
+        // Build target URL
+        let target_url = format!("{}{}{}", backend_url, path, query);
 
-    // Build proxy request
-    let mut proxy_req = state
-        .http_client
-        .request(method.clone(), &target_url)
-        .body(body_bytes.to_vec());
+        // Build proxy request
+        let mut proxy_req = state
+            .http_client
+            .request(method.clone(), &target_url)
+            .body(body.take_for_send());
 
-    // Forward headers (except host and content-length which reqwest handles)
-    for (key, value) in parts.headers.iter() {
-        let key_str = key.as_str();
-        if key_str != "host" && key_str != "content-length" {
-            proxy_req = proxy_req.header(key, value);
+        // Forward headers (except host and content-length which reqwest handles),
+        // tagging the request with our task ID so the orchestrator logs under it
+        for (key, value) in parts.headers.iter() {
+            let key_str = key.as_str();
+            if key_str != "host" && key_str != "content-length" && key_str != TASK_ID_HEADER {
+                proxy_req = proxy_req.header(key, value);
+            }
         }
-    }
+        proxy_req = proxy_req.header(TASK_ID_HEADER, &task_id);
 
-    // Send request to backend
-    let proxy_resp = match proxy_req.send().await {
-        Ok(resp) => resp,
-        Err(e) => {
-            error!("Failed to send request to backend: {}", e);
-
-            let duration_ms = start.elapsed().as_millis() as f64;
-
-            // Log failure - preserve created_at from initial log
-            state.db_logger.log(LogEntry {
-                id: task_id,
-                function_name: Some(function_name),
-                method: method.to_string(),
-                path,
-                status: "failed".to_string(),
-                created_at: Some(created_at),
-                completed_at: Some(chrono::Utc::now().to_rfc3339()),
-                duration_ms: Some(duration_ms),
-                request_body: Some(truncate_body(&request_body, 10000)),
-                error: Some(format!("Backend error: {}", e)),
-                ..Default::default()
-            });
+        // Send request to backend
+        match proxy_req.send().await {
+            // A streamed body has been consumed and can't be replayed elsewhere, and
+            // a non-idempotent request may already have taken effect on this backend
+            Ok(resp)
+                if state.retryable_statuses.contains(&resp.status())
+                    && body.is_buffered()
+                    && method.is_idempotent() =>
+            {
+                warn!(
+                    "Backend {} returned {} for {} (task_id: {}), failing over",
+                    backend_url,
+                    resp.status(),
+                    path,
+                    task_id
+                );
+                tried.push(backend_url);
+                retryable_response = Some(resp);
+            }
+            Ok(resp) => break resp,
+            Err(e) => {
+                error!("Failed to send request to backend: {}", e);
 
-            return Err(ProxyError::BackendError(e.to_string()));
-        }
-    };
+                let duration_ms = start.elapsed().as_millis() as f64;
 
-    // Capture response
-    let status = proxy_resp.status();
-    let headers = proxy_resp.headers().clone();
-    let resp_bytes = match proxy_resp.bytes().await {
-        Ok(bytes) => bytes,
-        Err(e) => {
-            error!("Failed to read response body: {}", e);
-            return Err(ProxyError::BodyReadError(e.to_string()));
+                // Log failure (non-blocking)
+                state.db_logger.log_complete(
+                    task_id,
+                    LogCompletion {
+                        status: "failed".to_string(),
+                        completed_at: Some(chrono::Utc::now().to_rfc3339()),
+                        duration_ms: Some(duration_ms),
+                        error: Some(format!("Backend error: {}", e)),
+                        ..Default::default()
+                    },
+                );
+
+                return Err(ProxyError::BackendError(e.to_string()));
+            }
         }
     };
 
-    let response_body = String::from_utf8_lossy(&resp_bytes).to_string();
-    let duration_ms = start.elapsed().as_millis() as f64;
-
-    // Log completion (non-blocking) - preserve created_at from initial log
-    state.db_logger.log(LogEntry {
-        id: task_id.clone(),
-        function_name: Some(function_name),
-        method: method.to_string(),
-        path,
-        status: if status.is_success() {
-            "completed".to_string()
-        } else {
-            "failed".to_string()
-        },
-        created_at: Some(created_at.clone()),
-        completed_at: Some(chrono::Utc::now().to_rfc3339()),
-        duration_ms: Some(duration_ms),
-        status_code: Some(status.as_u16()),
-        request_body: Some(truncate_body(&request_body, 10000)),
-        response_body: Some(truncate_body(&response_body, 10000)),
-        error: if !status.is_success() {
-            Some(format!("HTTP {}", status.as_u16()))
-        } else {
-            None
-        },
-        ..Default::default()
-    });
-
-    info!(
-        "Request completed: {} (status: {}, duration: {:.2}ms)",
-        task_id.clone(), status, duration_ms
-    );
-
     // Build response
+    let status = proxy_resp.status();
     let mut response = Response::builder().status(status);
 
-    // Copy headers from backend response
+    // Copy headers from backend response, within the configured bounds
+    let (headers, dropped) = state.response_header_limits.apply(proxy_resp.headers());
+    if dropped > 0 {
+        warn!(
+            "Dropped {} of {} response headers from backend for {} (task_id: {}, limits: {} headers, {} bytes)",
+            dropped,
+            proxy_resp.headers().len(),
+            path,
+            task_id,
+            state.response_header_limits.max_count,
+            state.response_header_limits.max_bytes
+        );
+    }
     for (key, value) in headers.iter() {
         response = response.header(key, value);
     }
 
+    // Stream the body back; completion is logged once it has been sent
+    let body = LoggedResponseBody {
+        remaining: proxy_resp.content_length(),
+        inner: Box::pin(proxy_resp.bytes_stream()),
+        prefix: Vec::new(),
+        truncated: false,
+        shadow: shadow_comparison.map(|sender| (sender, BodyDigest::default())),
+        completion: Some(ResponseCompletion {
+            db_logger: Arc::clone(&state.db_logger),
+            redact_fields: Arc::clone(&state.log_redact_fields),
+            task_id,
+            status,
+            start,
+        }),
+    };
     let response = response
-        .body(Body::from(resp_bytes.to_vec()))
+        .body(Body::from_stream(body))
         .map_err(|e| ProxyError::ResponseBuildError(e.to_string()))?;
 
     Ok(response)
 }
 
+/// Request bodies up to this size are buffered, so they can be retried on
+/// another backend and mirrored to the shadow; larger ones are streamed
+const REPLAY_BUFFER_BYTES: usize = 1024 * 1024;
+
+/// Bytes of each body kept for the request log
+const LOG_BODY_BYTES: usize = 10000;
+
+/// A client request body, buffered if small enough to replay
+enum RequestBody {
+    Buffered(Bytes),
+    /// Chunks read so far, then the rest of the body (taken on send)
+    Streamed {
+        prefix: Vec<u8>,
+        rest: Option<(Vec<Bytes>, BodyDataStream)>,
+    },
+}
+
+impl RequestBody {
+    /// Read until the body ends or exceeds `REPLAY_BUFFER_BYTES`
+    async fn read(body: Body) -> Result<Self, axum::Error> {
+        let mut stream = body.into_data_stream();
+        let mut chunks = Vec::new();
+        let mut len = 0;
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk?;
+            len += chunk.len();
+            chunks.push(chunk);
+            if len > REPLAY_BUFFER_BYTES {
+                let mut prefix = Vec::with_capacity(LOG_BODY_BYTES);
+                for chunk in &chunks {
+                    let take = (LOG_BODY_BYTES - prefix.len()).min(chunk.len());
+                    prefix.extend_from_slice(&chunk[..take]);
+                }
+                return Ok(RequestBody::Streamed {
+                    prefix,
+                    rest: Some((chunks, stream)),
+                });
+            }
+        }
+        Ok(RequestBody::Buffered(chunks.concat().into()))
+    }
+
+    fn is_buffered(&self) -> bool {
+        matches!(self, RequestBody::Buffered(_))
+    }
+
+    /// The start of the body, for logging
+    fn prefix(&self) -> &[u8] {
+        match self {
+            RequestBody::Buffered(bytes) => bytes,
+            RequestBody::Streamed { prefix, .. } => prefix,
+        }
+    }
+
+    /// Body for a backend request. A streamed body is handed over on the first
+    /// call; it is never sent twice because it isn't retried.
+    fn take_for_send(&mut self) -> reqwest::Body {
+        match self {
+            RequestBody::Buffered(bytes) => reqwest::Body::from(bytes.clone()),
+            RequestBody::Streamed { rest, .. } => match rest.take() {
+                Some((read, remaining)) => reqwest::Body::wrap_stream(
+                    futures_util::stream::iter(read.into_iter().map(Ok)).chain(remaining),
+                ),
+                None => reqwest::Body::from(Bytes::new()),
+            },
+        }
+    }
+}
+
+/// Body text for the request log. Redaction needs the whole JSON document, so
+/// a partial body is only logged when nothing is to be redacted.
+fn loggable_body(prefix: &[u8], complete: bool, redact_fields: &[String]) -> String {
+    let text = String::from_utf8_lossy(prefix);
+    if complete {
+        truncate_body(&redact_json_text(&text, redact_fields), LOG_BODY_BYTES)
+    } else if redact_fields.is_empty() {
+        format!("{}... (truncated)", text)
+    } else {
+        "(streamed body not logged: too large to redact)".to_string()
+    }
+}
+
+/// Backend response body streamed to the client, keeping a prefix for the log
+struct LoggedResponseBody {
+    inner: Pin<Box<dyn Stream<Item = reqwest::Result<Bytes>> + Send>>,
+    prefix: Vec<u8>,
+    /// Whether more was sent than the prefix holds
+    truncated: bool,
+    /// Bytes left to send when the length is known; the body may be dropped
+    /// rather than polled to its end once they are sent
+    remaining: Option<u64>,
+    /// Taken when the completion is logged
+    completion: Option<ResponseCompletion>,
+    /// Where the whole body's digest goes for comparing with the shadow's
+    shadow: Option<(oneshot::Sender<PrimaryOutcome>, BodyDigest)>,
+}
+
+struct ResponseCompletion {
+    db_logger: Arc<DbLogger>,
+    redact_fields: Arc<Vec<String>>,
+    task_id: String,
+    status: StatusCode,
+    start: Instant,
+}
+
+impl LoggedResponseBody {
+    /// Log the request's completion (non-blocking); `complete` is false when
+    /// the body ended early
+    fn finish(&mut self, complete: bool, error: Option<String>) {
+        let Some(completion) = self.completion.take() else {
+            return;
+        };
+        let status = completion.status;
+        // An incomplete body isn't compared; dropping the sender tells the shadow
+        if let Some((sender, digest)) = self.shadow.take().filter(|_| complete) {
+            let _ = sender.send(digest.finish(status));
+        }
+        let duration_ms = completion.start.elapsed().as_millis() as f64;
+        let error =
+            error.or_else(|| (!status.is_success()).then(|| format!("HTTP {}", status.as_u16())));
+
+        completion.db_logger.log_complete(
+            completion.task_id.clone(),
+            LogCompletion {
+                status: if error.is_none() {
+                    "completed".to_string()
+                } else {
+                    "failed".to_string()
+                },
+                completed_at: Some(chrono::Utc::now().to_rfc3339()),
+                duration_ms: Some(duration_ms),
+                status_code: Some(status.as_u16()),
+                response_body: Some(loggable_body(
+                    &self.prefix,
+                    complete && !self.truncated,
+                    &completion.redact_fields,
+                )),
+                error,
+            },
+        );
+
+        info!(
+            "Request completed: {} (status: {}, duration: {:.2}ms)",
+            completion.task_id, status, duration_ms
+        );
+    }
+}
+
+impl Stream for LoggedResponseBody {
+    type Item = reqwest::Result<Bytes>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let polled = self.inner.as_mut().poll_next(cx);
+        match &polled {
+            Poll::Ready(Some(Ok(chunk))) => {
+                let take = (LOG_BODY_BYTES - self.prefix.len()).min(chunk.len());
+                self.prefix.extend_from_slice(&chunk[..take]);
+                if let Some((_, digest)) = &mut self.shadow {
+                    digest.update(chunk);
+                }
+                self.truncated |= take < chunk.len();
+                if let Some(remaining) = &mut self.remaining {
+                    *remaining = remaining.saturating_sub(chunk.len() as u64);
+                    if *remaining == 0 {
+                        self.finish(true, None);
+                    }
+                }
+            }
+            Poll::Ready(Some(Err(e))) => {
+                error!("Failed to read response body: {}", e);
+                let error = format!("Failed to read response body: {}", e);
+                self.finish(false, Some(error));
+            }
+            Poll::Ready(None) => self.finish(true, None),
+            Poll::Pending => {}
+        }
+        polled
+    }
+}
+
+impl Drop for LoggedResponseBody {
+    fn drop(&mut self) {
+        // The client went away before the whole body was sent
+        self.finish(
+            false,
+            Some("Client disconnected before the response was sent".to_string()),
+        );
+    }
+}
+
+/// Look up a logged request by task ID, with its bodies decompressed
+pub async fn task_log_handler(
+    State(state): State<AppState>,
+    Path(task_id): Path<String>,
+) -> Result<Json<LogEntry>, ProxyError> {
+    let database_path = state.database_path.clone();
+    let id = task_id.clone();
+    let entry = tokio::task::spawn_blocking(move || {
+        let conn = rusqlite::Connection::open(&database_path)?;
+        db_logger::read_log_entry(&conn, &id)
+    })
+    .await
+    .map_err(|e| ProxyError::DatabaseError(e.to_string()))?
+    .map_err(|e| ProxyError::DatabaseError(e.to_string()))?;
+
+    entry.map(Json).ok_or(ProxyError::TaskNotFound(task_id))
+}
+
+/// Counts of mirrored requests by how they compared with the primary
+pub async fn shadow_stats_handler(
+    State(state): State<AppState>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let shadow = state.shadow.as_ref().ok_or(StatusCode::NOT_FOUND)?;
+    let stats = shadow.stats();
+    Ok(Json(serde_json::json!({
+        "matched": stats.matched.load(Ordering::Relaxed),
+        "mismatched": stats.mismatched.load(Ordering::Relaxed),
+        "dropped": stats.dropped.load(Ordering::Relaxed),
+        "failed": stats.failed.load(Ordering::Relaxed),
+    })))
+}
+
+/// Body of the drain/undrain admin requests
+#[derive(Debug, Deserialize)]
+pub struct DrainRequest {
+    pub url: String,
+}
+
+/// Stop selecting a backend for new requests while it stays monitored
+pub async fn drain_handler(
+    State(state): State<AppState>,
+    Json(request): Json<DrainRequest>,
+) -> Result<Json<serde_json::Value>, ProxyError> {
+    set_draining(&state, request.url, true).await
+}
+
+/// Make a drained backend eligible for selection again
+pub async fn undrain_handler(
+    State(state): State<AppState>,
+    Json(request): Json<DrainRequest>,
+) -> Result<Json<serde_json::Value>, ProxyError> {
+    set_draining(&state, request.url, false).await
+}
+
+async fn set_draining(
+    state: &AppState,
+    url: String,
+    draining: bool,
+) -> Result<Json<serde_json::Value>, ProxyError> {
+    let url = url.trim_end_matches('/').to_string();
+    if !state.backend_pool.set_draining(&url, draining).await {
+        return Err(ProxyError::BackendNotFound(url));
+    }
+    Ok(Json(
+        serde_json::json!({ "url": url, "draining": draining }),
+    ))
+}
+
 /// Extract function name from path
 /// E.g., /api/function_name -> function_name
 fn extract_function_name(path: &str) -> String {
     path.trim_start_matches('/')
         .split('/')
-        .last()
+        .next_back()
         .unwrap_or("unknown")
         .to_string()
 }
@@ -242,7 +575,12 @@ fn extract_function_name(path: &str) -> String {
 /// Truncate body for storage (to avoid storing huge responses)
 fn truncate_body(body: &str, max_len: usize) -> String {
     if body.len() > max_len {
-        format!("{}... (truncated)", &body[..max_len])
+        // Cut at a character boundary, not inside a multibyte character
+        let mut end = max_len;
+        while !body.is_char_boundary(end) {
+            end -= 1;
+        }
+        format!("{}... (truncated)", &body[..end])
     } else {
         body.to_string()
     }
@@ -255,10 +593,21 @@ pub enum ProxyError {
     BackendError(String),
     ResponseBuildError(String),
     NoCapacity(String),
+    TaskNotFound(String),
+    BackendNotFound(String),
+    DatabaseError(String),
+    /// Handler over its rate limit, retryable after the duration
+    RateLimited(String, Duration),
 }
 
 impl IntoResponse for ProxyError {
     fn into_response(self) -> Response<Body> {
+        let retry_after = match &self {
+            ProxyError::RateLimited(_, retry_after) => {
+                Some(retry_after.as_secs_f64().ceil().max(1.0) as u64)
+            }
+            _ => None,
+        };
         let (status, message) = match self {
             ProxyError::BodyReadError(e) => (
                 StatusCode::BAD_REQUEST,
@@ -276,12 +625,600 @@ impl IntoResponse for ProxyError {
                 StatusCode::SERVICE_UNAVAILABLE,
                 format!("No capacity available: {}", e),
             ),
+            ProxyError::TaskNotFound(id) => {
+                (StatusCode::NOT_FOUND, format!("Task not found: {}", id))
+            }
+            ProxyError::BackendNotFound(url) => {
+                (StatusCode::NOT_FOUND, format!("Backend not found: {}", url))
+            }
+            ProxyError::DatabaseError(e) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Database error: {}", e),
+            ),
+            ProxyError::RateLimited(handler, _) => (
+                StatusCode::TOO_MANY_REQUESTS,
+                format!("Rate limit exceeded for {}", handler),
+            ),
         };
 
         let body = serde_json::json!({
             "error": message,
         });
 
-        (status, axum::Json(body)).into_response()
+        let mut response = (status, axum::Json(body)).into_response();
+        if let Some(secs) = retry_after {
+            response
+                .headers_mut()
+                .insert(axum::http::header::RETRY_AFTER, secs.into());
+        }
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend_pool::DiscoveryMode;
+    use crate::db_logger::BodyCompression;
+    use axum::{
+        routing::{any, get, post},
+        Router,
+    };
+    use neutrino_core::OpenApiSpec;
+    use std::sync::atomic::AtomicUsize;
+    use std::time::Duration;
+    use tokio::sync::mpsc;
+
+    /// Serve a router on an ephemeral local port and return its base URL
+    async fn serve(router: Router) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, router).await.unwrap();
+        });
+        format!("http://{}", addr)
+    }
+
+    fn capacity_json() -> axum::Json<serde_json::Value> {
+        axum::Json(serde_json::json!({
+            "available_cpus": 4.0,
+            "available_gpus": 0.0,
+            "available_memory_gb": 8.0,
+            "total": {"cpus": 4.0, "gpus": 0.0, "memory_gb": 8.0},
+        }))
+    }
+
+    #[test]
+    fn test_logged_body_cut_on_char_boundary() {
+        // One ASCII byte puts the limit inside a two-byte character
+        let body = format!("a{}", "é".repeat(LOG_BODY_BYTES));
+        let logged = loggable_body(body.as_bytes(), true, &[]);
+        assert!(logged.ends_with("... (truncated)"));
+        assert_eq!(logged.len(), LOG_BODY_BYTES - 1 + "... (truncated)".len());
+    }
+
+    #[tokio::test]
+    async fn test_shadow_receives_mirror_but_client_sees_primary() {
+        let primary_url = serve(
+            Router::new()
+                .route("/capacity", get(|| async { capacity_json() }))
+                .route("/api/echo", post(|| async { "primary" })),
+        )
+        .await;
+
+        let (shadow_tx, mut shadow_rx) = mpsc::unbounded_channel::<(String, String)>();
+        let shadow_url = serve(Router::new().route(
+            "/api/echo",
+            post(move |body: String| {
+                let shadow_tx = shadow_tx.clone();
+                async move {
+                    shadow_tx.send(("/api/echo".to_string(), body)).unwrap();
+                    "shadow"
+                }
+            }),
+        ))
+        .await;
+
+        let backend_pool = Arc::new(BackendPool::new(
+            DiscoveryMode::Static(vec![primary_url]),
+            60,
+            5,
+        ));
+        backend_pool.start().await.unwrap();
+
+        let spec: OpenApiSpec = serde_json::from_value(serde_json::json!({
+            "openapi": "3.0.0",
+            "info": {"title": "test", "version": "1.0.0"},
+            "paths": {},
+        }))
+        .unwrap();
+
+        let db_path =
+            std::env::temp_dir().join(format!("neutrino-gateway-test-{}.db", Uuid::new_v4()));
+        let shadow = ShadowMirror::new(shadow_url, 100.0, 4, Duration::from_secs(5));
+        let state = AppState {
+            backend_pool,
+            http_client: reqwest::Client::new(),
+            db_logger: Arc::new(DbLogger::new(
+                db_path.to_string_lossy().to_string(),
+                Default::default(),
+            )),
+            database_path: db_path.to_string_lossy().to_string(),
+            resource_router: Arc::new(ResourceRouter::from_spec(&spec)),
+            shadow: Some(shadow.clone()),
+            retryable_statuses: Arc::new(vec![]),
+            log_redact_fields: Arc::new(vec![]),
+            rate_limiter: None,
+            response_header_limits: HeaderLimits::default(),
+        };
+
+        let req = Request::builder()
+            .method("POST")
+            .uri("/api/echo")
+            .body(Body::from("{\"args\": {\"x\": 1}}"))
+            .unwrap();
+        let response = proxy_handler(State(state), req).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"primary");
+
+        let (path, mirrored_body) = tokio::time::timeout(Duration::from_secs(5), shadow_rx.recv())
+            .await
+            .expect("shadow backend never received the mirrored request")
+            .unwrap();
+        assert_eq!(path, "/api/echo");
+        assert_eq!(mirrored_body, "{\"args\": {\"x\": 1}}");
+
+        // The shadow answered differently, which is recorded once the client
+        // has read the primary's body
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while shadow.stats().mismatched.load(Ordering::Relaxed) == 0 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("shadow comparison was never recorded");
+        assert_eq!(shadow.stats().matched.load(Ordering::Relaxed), 0);
+
+        let _ = std::fs::remove_file(db_path);
+    }
+
+    #[tokio::test]
+    async fn test_shadow_mirrors_dropped_when_full() {
+        let shadow = ShadowMirror::new(
+            "http://127.0.0.1:9".to_string(),
+            100.0,
+            0,
+            Duration::from_secs(1),
+        );
+        let sender = shadow.mirror(
+            "task-1".to_string(),
+            axum::http::Method::POST,
+            "/api/echo".to_string(),
+            axum::http::HeaderMap::new(),
+            Vec::new(),
+        );
+        assert!(sender.is_none());
+        assert_eq!(shadow.stats().dropped.load(Ordering::Relaxed), 1);
+    }
+
+    #[tokio::test]
+    async fn test_task_log_returns_decompressed_body() {
+        let db_path =
+            std::env::temp_dir().join(format!("neutrino-gateway-test-{}.db", Uuid::new_v4()));
+        let db_path = db_path.to_string_lossy().to_string();
+        let spec: OpenApiSpec = serde_json::from_value(serde_json::json!({
+            "openapi": "3.0.0",
+            "info": {"title": "test", "version": "1.0.0"},
+            "paths": {},
+        }))
+        .unwrap();
+
+        let state = AppState {
+            backend_pool: Arc::new(BackendPool::new(DiscoveryMode::Static(vec![]), 60, 5)),
+            http_client: reqwest::Client::new(),
+            db_logger: Arc::new(DbLogger::new(db_path.clone(), BodyCompression::Zstd)),
+            database_path: db_path.clone(),
+            resource_router: Arc::new(ResourceRouter::from_spec(&spec)),
+            shadow: None,
+            retryable_statuses: Arc::new(vec![]),
+            log_redact_fields: Arc::new(vec![]),
+            rate_limiter: None,
+            response_header_limits: HeaderLimits::default(),
+        };
+
+        let request_body = "the quick brown fox ".repeat(500);
+        state.db_logger.log_start(LogEntry {
+            id: "task-1".to_string(),
+            method: "POST".to_string(),
+            path: "/api/echo".to_string(),
+            status: "started".to_string(),
+            request_body: Some(request_body.clone()),
+            ..Default::default()
+        });
+
+        // The logger writes in the background; poll until the row lands
+        let mut entry = None;
+        for _ in 0..50 {
+            if let Ok(Json(found)) =
+                task_log_handler(State(state.clone()), Path("task-1".to_string())).await
+            {
+                entry = Some(found);
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        let entry = entry.expect("log entry was never written");
+        assert_eq!(entry.request_body, Some(request_body));
+
+        let missing = task_log_handler(State(state), Path("missing".to_string())).await;
+        assert!(matches!(missing, Err(ProxyError::TaskNotFound(_))));
+
+        let _ = std::fs::remove_file(db_path);
+    }
+
+    #[tokio::test]
+    async fn test_configured_retryable_status_fails_over() {
+        // The overloaded backend is idle, so it is always picked first
+        let overloaded_url = serve(
+            Router::new()
+                .route("/capacity", get(|| async { capacity_json() }))
+                .route(
+                    "/api/work",
+                    any(|| async { (StatusCode::SERVICE_UNAVAILABLE, "overloaded") }),
+                ),
+        )
+        .await;
+        let healthy_url = serve(
+            Router::new()
+                .route(
+                    "/capacity",
+                    get(|| async {
+                        axum::Json(serde_json::json!({
+                            "available_cpus": 2.0,
+                            "available_gpus": 0.0,
+                            "available_memory_gb": 8.0,
+                            "total": {"cpus": 4.0, "gpus": 0.0, "memory_gb": 8.0},
+                        }))
+                    }),
+                )
+                .route("/api/work", any(|| async { "healthy" })),
+        )
+        .await;
+
+        let backend_pool = Arc::new(BackendPool::new(
+            DiscoveryMode::Static(vec![overloaded_url, healthy_url]),
+            60,
+            5,
+        ));
+        backend_pool.start().await.unwrap();
+
+        let spec: OpenApiSpec = serde_json::from_value(serde_json::json!({
+            "openapi": "3.0.0",
+            "info": {"title": "test", "version": "1.0.0"},
+            "paths": {},
+        }))
+        .unwrap();
+        let db_path =
+            std::env::temp_dir().join(format!("neutrino-gateway-test-{}.db", Uuid::new_v4()));
+        let db_path = db_path.to_string_lossy().to_string();
+
+        for (method, retryable, expected_status, expected_body) in [
+            (
+                "PUT",
+                vec![StatusCode::SERVICE_UNAVAILABLE],
+                StatusCode::OK,
+                "healthy",
+            ),
+            (
+                "PUT",
+                vec![StatusCode::TOO_MANY_REQUESTS],
+                StatusCode::SERVICE_UNAVAILABLE,
+                "overloaded",
+            ),
+            // The overloaded backend may have acted on a POST, so it isn't retried
+            (
+                "POST",
+                vec![StatusCode::SERVICE_UNAVAILABLE],
+                StatusCode::SERVICE_UNAVAILABLE,
+                "overloaded",
+            ),
+        ] {
+            let state = AppState {
+                backend_pool: Arc::clone(&backend_pool),
+                http_client: reqwest::Client::new(),
+                db_logger: Arc::new(DbLogger::new(db_path.clone(), Default::default())),
+                database_path: db_path.clone(),
+                resource_router: Arc::new(ResourceRouter::from_spec(&spec)),
+                shadow: None,
+                retryable_statuses: Arc::new(retryable.clone()),
+                log_redact_fields: Arc::new(vec![]),
+                rate_limiter: None,
+                response_header_limits: HeaderLimits::default(),
+            };
+
+            let req = Request::builder()
+                .method(method)
+                .uri("/api/work")
+                .body(Body::from("{}"))
+                .unwrap();
+            let response = proxy_handler(State(state), req).await.unwrap();
+
+            assert_eq!(
+                response.status(),
+                expected_status,
+                "{} with retryable={:?}",
+                method,
+                retryable
+            );
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            assert_eq!(&body[..], expected_body.as_bytes());
+        }
+
+        let _ = std::fs::remove_file(db_path);
+    }
+
+    #[tokio::test]
+    async fn test_redacted_fields_not_stored_in_request_log() {
+        let backend_url = serve(
+            Router::new()
+                .route("/capacity", get(|| async { capacity_json() }))
+                .route(
+                    "/api/login",
+                    post(|| async { r#"{"session": {"token": "tok-456"}, "ok": true}"# }),
+                ),
+        )
+        .await;
+        let backend_pool = Arc::new(BackendPool::new(
+            DiscoveryMode::Static(vec![backend_url]),
+            60,
+            5,
+        ));
+        backend_pool.start().await.unwrap();
+
+        let spec: OpenApiSpec = serde_json::from_value(serde_json::json!({
+            "openapi": "3.0.0",
+            "info": {"title": "test", "version": "1.0.0"},
+            "paths": {},
+        }))
+        .unwrap();
+        let db_path =
+            std::env::temp_dir().join(format!("neutrino-gateway-test-{}.db", Uuid::new_v4()));
+        let db_path = db_path.to_string_lossy().to_string();
+        let state = AppState {
+            backend_pool,
+            http_client: reqwest::Client::new(),
+            db_logger: Arc::new(DbLogger::new(db_path.clone(), Default::default())),
+            database_path: db_path.clone(),
+            resource_router: Arc::new(ResourceRouter::from_spec(&spec)),
+            shadow: None,
+            retryable_statuses: Arc::new(vec![]),
+            log_redact_fields: Arc::new(vec!["password".to_string(), "token".to_string()]),
+            rate_limiter: None,
+            response_header_limits: HeaderLimits::default(),
+        };
+
+        let req = Request::builder()
+            .method("POST")
+            .uri("/api/login")
+            .body(Body::from(
+                r#"{"args": {"user": "ada", "password": "hunter2"}}"#,
+            ))
+            .unwrap();
+        let response = proxy_handler(State(state.clone()), req).await.unwrap();
+
+        // The client still gets the unredacted response
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert!(String::from_utf8_lossy(&body).contains("tok-456"));
+
+        state.db_logger.flush().await;
+        let conn = rusqlite::Connection::open(&db_path).unwrap();
+        let id: String = conn
+            .query_row(
+                "SELECT id FROM tasks WHERE path = '/api/login'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        let entry = db_logger::read_log_entry(&conn, &id).unwrap().unwrap();
+        let request: serde_json::Value =
+            serde_json::from_str(&entry.request_body.unwrap()).unwrap();
+        let response: serde_json::Value =
+            serde_json::from_str(&entry.response_body.unwrap()).unwrap();
+        assert_eq!(
+            request,
+            serde_json::json!({"args": {"user": "ada", "password": "***"}})
+        );
+        assert_eq!(
+            response,
+            serde_json::json!({"session": {"token": "***"}, "ok": true})
+        );
+
+        let _ = std::fs::remove_file(db_path);
+    }
+
+    #[tokio::test]
+    async fn test_backend_response_headers_bounded() {
+        // Backend answering with one oversized header followed by many small ones
+        let backend_url = serve(
+            Router::new()
+                .route("/capacity", get(|| async { capacity_json() }))
+                .route(
+                    "/api/work",
+                    post(|| async {
+                        let mut headers = axum::http::HeaderMap::new();
+                        headers.insert("x-huge", "a".repeat(8192).parse().unwrap());
+                        for i in 0..50 {
+                            let name: axum::http::HeaderName =
+                                format!("x-filler-{}", i).parse().unwrap();
+                            headers.insert(name, "1".parse().unwrap());
+                        }
+                        (headers, "ok")
+                    }),
+                ),
+        )
+        .await;
+        let backend_pool = Arc::new(BackendPool::new(
+            DiscoveryMode::Static(vec![backend_url]),
+            60,
+            5,
+        ));
+        backend_pool.start().await.unwrap();
+
+        let spec: OpenApiSpec = serde_json::from_value(serde_json::json!({
+            "openapi": "3.0.0",
+            "info": {"title": "test", "version": "1.0.0"},
+            "paths": {},
+        }))
+        .unwrap();
+        let db_path =
+            std::env::temp_dir().join(format!("neutrino-gateway-test-{}.db", Uuid::new_v4()));
+        let db_path = db_path.to_string_lossy().to_string();
+        let state = AppState {
+            backend_pool,
+            http_client: reqwest::Client::new(),
+            db_logger: Arc::new(DbLogger::new(db_path.clone(), Default::default())),
+            database_path: db_path.clone(),
+            resource_router: Arc::new(ResourceRouter::from_spec(&spec)),
+            shadow: None,
+            retryable_statuses: Arc::new(vec![]),
+            log_redact_fields: Arc::new(vec![]),
+            rate_limiter: None,
+            response_header_limits: HeaderLimits {
+                max_count: 10,
+                max_bytes: 4096,
+            },
+        };
+
+        let req = Request::builder()
+            .method("POST")
+            .uri("/api/work")
+            .body(Body::from("{}"))
+            .unwrap();
+        let response = proxy_handler(State(state), req).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        // The oversized header is skipped; the rest stop at the count limit
+        assert!(response.headers().get("x-huge").is_none());
+        assert_eq!(response.headers().len(), 10);
+        assert!(response.headers().contains_key("content-type"));
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"ok");
+
+        let _ = std::fs::remove_file(db_path);
+    }
+
+    #[tokio::test]
+    async fn test_large_upload_streamed_without_buffering() {
+        const CHUNK: usize = 64 * 1024;
+        const TOTAL: usize = 256 * 1024 * 1024;
+        static DATA: [u8; CHUNK] = [b'x'; CHUNK];
+
+        // Bytes the client has produced but the backend hasn't yet received
+        // bound what the gateway holds in memory
+        let produced = Arc::new(AtomicUsize::new(0));
+        let max_in_flight = Arc::new(AtomicUsize::new(0));
+        let backend_url = serve(
+            Router::new()
+                .route("/capacity", get(|| async { capacity_json() }))
+                .route(
+                    "/api/upload",
+                    post({
+                        let produced = Arc::clone(&produced);
+                        let max_in_flight = Arc::clone(&max_in_flight);
+                        move |body: Body| async move {
+                            let mut stream = body.into_data_stream();
+                            let mut received = 0;
+                            while let Some(chunk) = stream.next().await {
+                                received += chunk.unwrap().len();
+                                let in_flight = produced.load(Ordering::SeqCst) - received;
+                                max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+                            }
+                            received.to_string()
+                        }
+                    }),
+                ),
+        )
+        .await;
+        let backend_pool = Arc::new(BackendPool::new(
+            DiscoveryMode::Static(vec![backend_url]),
+            60,
+            5,
+        ));
+        backend_pool.start().await.unwrap();
+
+        let spec: OpenApiSpec = serde_json::from_value(serde_json::json!({
+            "openapi": "3.0.0",
+            "info": {"title": "test", "version": "1.0.0"},
+            "paths": {},
+        }))
+        .unwrap();
+        let db_path =
+            std::env::temp_dir().join(format!("neutrino-gateway-test-{}.db", Uuid::new_v4()));
+        let db_path = db_path.to_string_lossy().to_string();
+        let state = AppState {
+            backend_pool,
+            http_client: reqwest::Client::new(),
+            db_logger: Arc::new(DbLogger::new(db_path.clone(), Default::default())),
+            database_path: db_path.clone(),
+            resource_router: Arc::new(ResourceRouter::from_spec(&spec)),
+            shadow: None,
+            retryable_statuses: Arc::new(vec![]),
+            log_redact_fields: Arc::new(vec![]),
+            rate_limiter: None,
+            response_header_limits: HeaderLimits::default(),
+        };
+
+        let upload = futures_util::stream::iter(0..TOTAL / CHUNK).map({
+            let produced = Arc::clone(&produced);
+            move |_| {
+                produced.fetch_add(CHUNK, Ordering::SeqCst);
+                Ok::<_, std::convert::Infallible>(Bytes::from_static(&DATA))
+            }
+        });
+        let req = Request::builder()
+            .method("POST")
+            .uri("/api/upload")
+            .body(Body::from_stream(upload))
+            .unwrap();
+        let response = proxy_handler(State(state.clone()), req).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(String::from_utf8_lossy(&body), TOTAL.to_string());
+        let max_in_flight = max_in_flight.load(Ordering::SeqCst);
+        assert!(
+            max_in_flight < TOTAL / 4,
+            "{} of {} bytes were held in flight",
+            max_in_flight,
+            TOTAL
+        );
+
+        // Only a bounded prefix of the upload is logged
+        state.db_logger.flush().await;
+        let conn = rusqlite::Connection::open(&db_path).unwrap();
+        let id: String = conn
+            .query_row(
+                "SELECT id FROM tasks WHERE path = '/api/upload'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        let entry = db_logger::read_log_entry(&conn, &id).unwrap().unwrap();
+        let logged = entry.request_body.unwrap();
+        assert!(logged.len() < LOG_BODY_BYTES + 100 && logged.ends_with("(truncated)"));
+
+        let _ = std::fs::remove_file(db_path);
     }
 }
@@ -0,0 +1,198 @@
+//! Per-handler rate limits enforced across every gateway replica.
+//!
+//! Requests are counted in fixed windows (aligned to wall-clock time, so all
+//! replicas agree on window boundaries) in a store shared by the replicas,
+//! which caps the aggregate rate no matter how many gateways front a backend.
+//! The in-memory store only sees one gateway's traffic; Redis (the `redis`
+//! build feature) shares the counts. If the store is unreachable, requests are
+//! let through rather than failing all traffic.
+
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tracing::warn;
+
+/// Key prefix of the counters in a shared store
+const KEY_PREFIX: &str = "neutrino:ratelimit";
+
+/// Counters shared by the gateways enforcing a limit
+#[async_trait]
+pub trait CounterStore: Send + Sync {
+    /// Increment `key`, which expires `ttl` after it is created, and return its new value
+    async fn increment(&self, key: &str, ttl: Duration) -> Result<u64, String>;
+}
+
+/// Counters local to this process, for a single gateway instance
+#[derive(Default)]
+pub struct InMemoryStore {
+    counters: Mutex<HashMap<String, (u64, Instant)>>,
+}
+
+#[async_trait]
+impl CounterStore for InMemoryStore {
+    async fn increment(&self, key: &str, ttl: Duration) -> Result<u64, String> {
+        let now = Instant::now();
+        let mut counters = self.counters.lock().unwrap();
+        counters.retain(|_, (_, expires)| *expires > now);
+        let (count, _) = counters.entry(key.to_string()).or_insert((0, now + ttl));
+        *count += 1;
+        Ok(*count)
+    }
+}
+
+/// Counters in Redis, shared by every gateway using the same server
+#[cfg(feature = "redis")]
+pub struct RedisStore {
+    connection: redis::aio::ConnectionManager,
+}
+
+#[cfg(feature = "redis")]
+impl RedisStore {
+    pub async fn connect(url: &str) -> Result<Self, String> {
+        let client = redis::Client::open(url).map_err(|e| e.to_string())?;
+        let connection = client
+            .get_connection_manager()
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(Self { connection })
+    }
+}
+
+#[cfg(feature = "redis")]
+#[async_trait]
+impl CounterStore for RedisStore {
+    async fn increment(&self, key: &str, ttl: Duration) -> Result<u64, String> {
+        let mut connection = self.connection.clone();
+        let (count,): (u64,) = redis::pipe()
+            .atomic()
+            .incr(key, 1)
+            .expire(key, ttl.as_secs().max(1) as i64)
+            .ignore()
+            .query_async(&mut connection)
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(count)
+    }
+}
+
+/// Requests allowed per window, by handler
+pub struct RateLimiter {
+    limits: HashMap<String, u64>,
+    window: Duration,
+    store: Arc<dyn CounterStore>,
+}
+
+impl RateLimiter {
+    pub fn new(
+        limits: HashMap<String, u64>,
+        window: Duration,
+        store: Arc<dyn CounterStore>,
+    ) -> Self {
+        Self {
+            limits,
+            window: window.max(Duration::from_secs(1)),
+            store,
+        }
+    }
+
+    /// Count a request to `handler`; Err carries the time until the window
+    /// resets when the handler is over its limit
+    pub async fn check(&self, handler: &str) -> Result<(), Duration> {
+        self.check_at(handler, SystemTime::now()).await
+    }
+
+    async fn check_at(&self, handler: &str, now: SystemTime) -> Result<(), Duration> {
+        let Some(&limit) = self.limits.get(handler) else {
+            return Ok(());
+        };
+        let now = now.duration_since(UNIX_EPOCH).unwrap_or_default();
+        let window_secs = self.window.as_secs();
+        let window = now.as_secs() / window_secs;
+        let key = format!("{}:{}:{}", KEY_PREFIX, handler, window);
+
+        match self.store.increment(&key, self.window).await {
+            Ok(count) if count > limit => {
+                let reset = Duration::from_secs((window + 1) * window_secs);
+                Err(reset.saturating_sub(now))
+            }
+            Ok(_) => Ok(()),
+            Err(e) => {
+                warn!(
+                    "Rate limit store unavailable, allowing {} request: {}",
+                    handler, e
+                );
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Stand-in for a shared store such as Redis, recording every key it is asked for
+    #[derive(Default)]
+    struct MockSharedStore {
+        counters: Mutex<HashMap<String, u64>>,
+        calls: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl CounterStore for MockSharedStore {
+        async fn increment(&self, key: &str, _ttl: Duration) -> Result<u64, String> {
+            self.calls.lock().unwrap().push(key.to_string());
+            let mut counters = self.counters.lock().unwrap();
+            let count = counters.entry(key.to_string()).or_insert(0);
+            *count += 1;
+            Ok(*count)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_limit_shared_across_gateway_instances() {
+        let store = Arc::new(MockSharedStore::default());
+        let limits = HashMap::from([("generate".to_string(), 5)]);
+        let gateways = [
+            RateLimiter::new(limits.clone(), Duration::from_secs(10), store.clone()),
+            RateLimiter::new(limits, Duration::from_secs(10), store.clone()),
+        ];
+        let now = UNIX_EPOCH + Duration::from_secs(1_000_003);
+
+        // Alternating between the two gateways, only 5 requests get through in total
+        let mut allowed = 0;
+        for i in 0..10 {
+            if gateways[i % 2].check_at("generate", now).await.is_ok() {
+                allowed += 1;
+            }
+        }
+        assert_eq!(allowed, 5);
+        assert_eq!(
+            gateways[1].check_at("generate", now).await,
+            Err(Duration::from_secs(7)),
+            "retry when the window resets"
+        );
+        assert!(store
+            .calls
+            .lock()
+            .unwrap()
+            .iter()
+            .all(|key| key == "neutrino:ratelimit:generate:100000"));
+
+        // Unlimited handlers aren't counted; the next window starts afresh
+        assert!(gateways[0].check_at("embed", now).await.is_ok());
+        assert_eq!(store.calls.lock().unwrap().len(), 11);
+        let next_window = now + Duration::from_secs(7);
+        assert!(gateways[0].check_at("generate", next_window).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_store_expires_counters() {
+        let store = InMemoryStore::default();
+        assert_eq!(store.increment("a", Duration::from_millis(20)).await, Ok(1));
+        assert_eq!(store.increment("a", Duration::from_millis(20)).await, Ok(2));
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert_eq!(store.increment("a", Duration::from_millis(20)).await, Ok(1));
+    }
+}
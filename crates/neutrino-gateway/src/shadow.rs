@@ -0,0 +1,202 @@
+use axum::http::{HeaderMap, Method, StatusCode};
+use futures_util::StreamExt;
+use rand::Rng;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{oneshot, Semaphore};
+use tracing::{debug, info, warn};
+
+/// What the client received from the primary backend, compared against the
+/// shadow's response
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PrimaryOutcome {
+    pub status: StatusCode,
+    pub bytes: u64,
+    pub crc32: u32,
+}
+
+/// Counts of mirrored requests by how their comparison went
+#[derive(Debug, Default)]
+pub struct ShadowStats {
+    /// Shadow response had the primary's status and body
+    pub matched: AtomicU64,
+    /// Shadow response differed in status or body
+    pub mismatched: AtomicU64,
+    /// Not mirrored because too many mirrors were in flight
+    pub dropped: AtomicU64,
+    /// Shadow failed, timed out, or the primary response wasn't completed
+    pub failed: AtomicU64,
+}
+
+/// Mirrors a fraction of requests to a shadow backend (fire-and-forget) and
+/// logs how each shadow response compares with the primary's
+#[derive(Clone)]
+pub struct ShadowMirror {
+    http_client: reqwest::Client,
+    url: String,
+    percent: f64,
+    timeout: Duration,
+    /// Free slots for mirrors in flight; requests aren't mirrored when none are left
+    slots: Arc<Semaphore>,
+    stats: Arc<ShadowStats>,
+}
+
+impl ShadowMirror {
+    pub fn new(url: String, percent: f64, max_in_flight: usize, timeout: Duration) -> Self {
+        let http_client = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .expect("Failed to create HTTP client");
+
+        Self {
+            http_client,
+            url,
+            percent,
+            timeout,
+            slots: Arc::new(Semaphore::new(max_in_flight)),
+            stats: Arc::new(ShadowStats::default()),
+        }
+    }
+
+    /// Decide whether the current request should be mirrored
+    pub fn should_mirror(&self) -> bool {
+        self.percent > 0.0 && rand::thread_rng().gen::<f64>() * 100.0 < self.percent
+    }
+
+    pub fn stats(&self) -> &ShadowStats {
+        &self.stats
+    }
+
+    /// Send a copy of the request to the shadow backend in the background.
+    /// Returns where to report the primary's response for the comparison, or
+    /// None when the mirror is dropped because too many are in flight. The
+    /// shadow never affects the primary response.
+    pub fn mirror(
+        &self,
+        task_id: String,
+        method: Method,
+        path_and_query: String,
+        headers: HeaderMap,
+        body: Vec<u8>,
+    ) -> Option<oneshot::Sender<PrimaryOutcome>> {
+        let Ok(permit) = Arc::clone(&self.slots).try_acquire_owned() else {
+            self.stats.dropped.fetch_add(1, Ordering::Relaxed);
+            debug!(
+                "Shadow mirror dropped, too many in flight (task_id: {})",
+                task_id
+            );
+            return None;
+        };
+        let (primary_tx, primary_rx) = oneshot::channel();
+        let client = self.http_client.clone();
+        let target_url = format!("{}{}", self.url, path_and_query);
+        let stats = Arc::clone(&self.stats);
+        let timeout = self.timeout;
+
+        tokio::spawn(async move {
+            let _permit = permit;
+            let start = Instant::now();
+
+            let mut shadow_req = client.request(method.clone(), &target_url).body(body);
+            for (key, value) in headers.iter() {
+                let key_str = key.as_str();
+                if key_str != "host" && key_str != "content-length" {
+                    shadow_req = shadow_req.header(key, value);
+                }
+            }
+            shadow_req = shadow_req.header("x-neutrino-shadow", "true");
+
+            let compared = tokio::time::timeout(timeout, async {
+                let shadow = read_outcome(shadow_req.send().await?).await?;
+                Ok::<_, reqwest::Error>((shadow, primary_rx.await.ok()))
+            })
+            .await;
+
+            let duration_ms = start.elapsed().as_secs_f64() * 1000.0;
+            match compared {
+                Ok(Ok((shadow, Some(primary)))) if shadow == primary => {
+                    stats.matched.fetch_add(1, Ordering::Relaxed);
+                    info!(
+                        "Shadow matched primary: {} {} (task_id: {}, status: {}, bytes: {}, duration: {:.2}ms)",
+                        method, target_url, task_id, shadow.status, shadow.bytes, duration_ms
+                    );
+                }
+                Ok(Ok((shadow, Some(primary)))) => {
+                    stats.mismatched.fetch_add(1, Ordering::Relaxed);
+                    warn!(
+                        "Shadow differs from primary: {} {} (task_id: {}, status: {} vs {}, bytes: {} vs {}, body {}, duration: {:.2}ms)",
+                        method,
+                        target_url,
+                        task_id,
+                        shadow.status,
+                        primary.status,
+                        shadow.bytes,
+                        primary.bytes,
+                        if shadow.crc32 == primary.crc32 { "equal" } else { "differs" },
+                        duration_ms
+                    );
+                }
+                Ok(Ok((shadow, None))) => {
+                    stats.failed.fetch_add(1, Ordering::Relaxed);
+                    info!(
+                        "Shadow response not compared, primary response incomplete: {} {} (task_id: {}, status: {})",
+                        method, target_url, task_id, shadow.status
+                    );
+                }
+                Ok(Err(e)) => {
+                    stats.failed.fetch_add(1, Ordering::Relaxed);
+                    warn!(
+                        "Shadow request failed: {} {} (task_id: {}): {}",
+                        method, target_url, task_id, e
+                    );
+                }
+                Err(_) => {
+                    stats.failed.fetch_add(1, Ordering::Relaxed);
+                    warn!(
+                        "Shadow comparison timed out after {}s: {} {} (task_id: {})",
+                        timeout.as_secs(),
+                        method,
+                        target_url,
+                        task_id
+                    );
+                }
+            }
+        });
+
+        Some(primary_tx)
+    }
+}
+
+/// Status, length and checksum of a response body, read to its end
+async fn read_outcome(resp: reqwest::Response) -> Result<PrimaryOutcome, reqwest::Error> {
+    let status = resp.status();
+    let mut digest = BodyDigest::default();
+    let mut stream = resp.bytes_stream();
+    while let Some(chunk) = stream.next().await {
+        digest.update(&chunk?);
+    }
+    Ok(digest.finish(status))
+}
+
+/// Running length and checksum of a streamed body
+#[derive(Default)]
+pub struct BodyDigest {
+    bytes: u64,
+    crc: crc32fast::Hasher,
+}
+
+impl BodyDigest {
+    pub fn update(&mut self, chunk: &[u8]) {
+        self.bytes += chunk.len() as u64;
+        self.crc.update(chunk);
+    }
+
+    pub fn finish(self, status: StatusCode) -> PrimaryOutcome {
+        PrimaryOutcome {
+            status,
+            bytes: self.bytes,
+            crc32: self.crc.finalize(),
+        }
+    }
+}
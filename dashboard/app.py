@@ -11,6 +11,12 @@ from fastapi.responses import HTMLResponse
 from pydantic import BaseModel
 import sqlite3
 import json
+import gzip
+
+try:
+    import zstandard
+except ImportError:  # Only needed when the gateway runs with BODY_COMPRESSION=zstd
+    zstandard = None
 
 
 # Database models
@@ -67,6 +73,26 @@ def get_db():
     return conn
 
 
+def decode_body(value, codec: Optional[str]) -> Optional[str]:
+    """Decompress a request/response body stored by the gateway with BODY_COMPRESSION."""
+    if not isinstance(value, bytes):
+        return value
+    if codec == "gzip":
+        value = gzip.decompress(value)
+    elif codec == "zstd":
+        if zstandard is None:
+            return "<zstd-compressed body; install zstandard to view>"
+        value = zstandard.ZstdDecompressor().decompressobj().decompress(value)
+    return value.decode("utf-8", errors="replace")
+
+
+def decode_bodies(task_dict: dict) -> None:
+    """Replace compressed bodies in a task row with their text."""
+    codec = task_dict.pop("body_compression", None)
+    for field in ("request_body", "response_body"):
+        task_dict[field] = decode_body(task_dict.get(field), codec)
+
+
 def init_db():
     """Initialize database schema."""
     conn = get_db()
@@ -90,7 +116,8 @@ def init_db():
             args TEXT,
             result TEXT,
             error TEXT,
-            duration_ms REAL
+            duration_ms REAL,
+            body_compression TEXT
         )
     """)
 
@@ -485,6 +512,7 @@ async def get_tasks(
     tasks = []
     for row in rows:
         task_dict = dict(row)
+        decode_bodies(task_dict)
         # Parse JSON fields if present
         if task_dict.get("args"):
             try:
@@ -526,6 +554,7 @@ async def get_task(task_id: str):
         raise HTTPException(status_code=404, detail="Task not found")
 
     task_dict = dict(row)
+    decode_bodies(task_dict)
     if task_dict.get("args"):
         task_dict["args"] = json.loads(task_dict["args"])
     if task_dict.get("result"):
@@ -1,6 +1,7 @@
 # Neutrino - High-performance distributed orchestration framework
 __version__ = "0.1.0"
 
+import time
 from typing import Any, Callable, Type
 from neutrino.exceptions import (
     ModelError,
@@ -18,6 +19,10 @@ from neutrino.route import Route
 _global_route_registry: dict[str, Route] = {}
 _global_model_registry: dict[str, Model] = {}
 _global_asgi_app: Any | None = None
+# Monotonic deadline of the task currently running in this worker, if any
+_current_task_deadline: float | None = None
+# Dispatch priority of that task
+_current_task_priority: int | None = None
 
 
 def route(
@@ -31,6 +36,19 @@ def route(
     num_cpus: float = 1.0,
     num_gpus: float = 0.0,
     memory_gb: float = 1.0,
+    gpu_memory_gb: float = 0.0,
+    max_body_bytes: int | None = None,
+    overflow_pool: str | None = None,
+    pool: str | None = None,
+    max_concurrency: int | None = None,
+    hedge_after_ms: int | None = None,
+    cache_ttl: int | None = None,
+    priority: int = 0,
+    args_template: dict[str, Any] | None = None,
+    tenant_pools: dict[str, str] | None = None,
+    coalesce: bool = False,
+    reject_unknown_fields: bool | None = None,
+    affinity_key: str | None = None,
 ) -> Callable[[Callable[..., Any]], Route]:
     """Decorator to register a function as an orchestrated route.
 
@@ -45,6 +63,43 @@ def route(
         num_cpus: CPUs required (logical cores, can be fractional). Defaults to 1.0.
         num_gpus: GPUs required (devices, can be fractional). Defaults to 0.0.
         memory_gb: Memory required in GB. Defaults to 1.0.
+        gpu_memory_gb: GPU memory (VRAM) required in GB. Defaults to 0.0 (no constraint).
+        max_body_bytes: Largest request body accepted for this route, overriding the
+            orchestrator's http.max_body_bytes. Defaults to None (global limit).
+        overflow_pool: Worker pool to run on, without the GPU requirement, when no
+            worker can take the task (slower instead of a 503). Defaults to None.
+        pool: Worker pool the route's tasks are pinned to, e.g. "gpu_workers";
+            workers of other pools are never used, even if they fit. A tenant's
+            pool from tenant_pools takes precedence. Defaults to None.
+        max_concurrency: Most tasks of this handler running at once across all
+            workers (e.g. 1 for a model that can only be loaded once); further
+            requests wait their turn. Defaults to None (no limit).
+        hedge_after_ms: For GET routes, after this many milliseconds without a
+            result, run a duplicate task on another idle worker and answer with
+            whichever finishes first (the other is cancelled). Only for
+            idempotent handlers. Defaults to None (no hedging).
+        cache_ttl: Seconds to keep a successful result and return it for
+            identical requests (same args and tenant) without running the
+            handler. Only for deterministic handlers. Defaults to None (no caching).
+        priority: Dispatch priority while waiting for a worker; higher goes first.
+            Requests may override it with an X-Neutrino-Priority header. Defaults to 0.
+        args_template: Shape the handler's args are rebuilt into from the request's
+            args, e.g. {"payload": {"text": "$.prompt"}}; "$.field" strings are
+            replaced by request fields. Defaults to None (args passed as sent).
+        tenant_pools: Dedicated worker pool per tenant ID, e.g. {"acme": "acme-gpu"};
+            that tenant's tasks run only there (needs http.tenants configured).
+            Defaults to None.
+        coalesce: Let identical concurrent requests (same args) share one task
+            instead of each dispatching its own. Only for idempotent handlers.
+            Defaults to False.
+        reject_unknown_fields: Return 400 for request bodies with fields the request
+            model doesn't declare, instead of passing them to the handler (needs
+            http.validate_requests). Defaults to None (http.reject_unknown_fields).
+        affinity_key: Name of the path/query parameter or top-level body field
+            identifying the request's session; requests with the same value go to
+            the same worker while it is free (for per-session caches or loaded
+            state). An X-Neutrino-Affinity-Key header takes precedence.
+            Defaults to None.
 
     Returns:
         Decorator function that registers the route.
@@ -74,6 +129,19 @@ def route(
             num_cpus,
             num_gpus,
             memory_gb,
+            gpu_memory_gb,
+            max_body_bytes,
+            overflow_pool,
+            pool,
+            max_concurrency,
+            hedge_after_ms,
+            cache_ttl,
+            priority,
+            args_template,
+            tenant_pools,
+            coalesce,
+            reject_unknown_fields,
+            affinity_key,
         )
         _global_route_registry[path] = route_obj
         return route_obj
@@ -208,6 +276,33 @@ def get_asgi_app() -> Any | None:
     return _global_asgi_app
 
 
+def deadline_ms_remaining() -> int | None:
+    """Get the time left before the orchestrator times out the current task.
+
+    Handlers with their own internal timeouts (e.g. early stopping) can use this
+    to finish gracefully instead of being cut off.
+
+    Returns:
+        Milliseconds remaining (0 once passed), or None outside a task or when
+        the orchestrator has no task timeout configured.
+    """
+    if _current_task_deadline is None:
+        return None
+    return max(0, int((_current_task_deadline - time.monotonic()) * 1000))
+
+
+def task_priority() -> int | None:
+    """Get the priority the current task was dispatched at.
+
+    This is the route's priority, or the request's X-Neutrino-Priority header
+    when it set one.
+
+    Returns:
+        The task's priority, or None outside a task.
+    """
+    return _current_task_priority
+
+
 def generate_openapi(title: str = "Neutrino API", version: str = "1.0.0") -> dict[str, Any]:
     """Generate OpenAPI 3.0 specification from registered routes.
 
@@ -241,6 +336,9 @@ __all__ = [
     "list_models",
     # ASGI app access
     "get_asgi_app",
+    # Current task
+    "deadline_ms_remaining",
+    "task_priority",
     # OpenAPI generation
     "generate_openapi",
     # Exceptions
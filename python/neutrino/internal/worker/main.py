@@ -8,12 +8,16 @@ The worker:
 1. Connects to Unix socket at socket_path
 2. Sends WorkerReady message
 3. Enters main loop waiting for tasks
-4. Exits on Shutdown message
+4. Answers ListHandlers with the handler names it provides
+5. Answers DrainRequest with DrainComplete once no work is pending
+6. Ignores CancelTask for tasks it has already finished
+7. Exits on Shutdown message
 """
 
 import os
 import socket
 import sys
+import time
 from typing import NoReturn
 import importlib
 import asyncio
@@ -98,11 +102,15 @@ def main() -> NoReturn:
                     task_id = task_data["task_id"]
                     func_name = task_data["function_name"]
                     args = task_data["args"]  # Already decoded as native structure
+                    deadline_ms = task_data.get("deadline_ms_remaining")
+                    priority = task_data.get("priority", 0)
                 elif isinstance(task_data, (list, tuple)):
-                    # Rust serializes as tuple: [task_id, function_name, args]
+                    # Rust serializes as tuple: [task_id, function_name, args, resources, deadline_ms_remaining, priority]
                     task_id = task_data[0]
                     func_name = task_data[1]
                     args = task_data[2]  # Already decoded as native structure
+                    deadline_ms = task_data[4] if len(task_data) > 4 else None
+                    priority = task_data[5] if len(task_data) > 5 else 0
                 else:
                     print(f"[Worker {worker_id}] Error: unexpected TaskAssignment format: {type(task_data)}")
                     protocol.send_task_result(task_id, False, {"error": "Invalid task format"})
@@ -110,6 +118,12 @@ def main() -> NoReturn:
 
                 print(f"[Worker {worker_id}] Task {task_id}: {func_name}({args})")
 
+                # Expose the client's remaining deadline via neutrino.deadline_ms_remaining()
+                neutrino._current_task_deadline = (
+                    time.monotonic() + deadline_ms / 1000 if deadline_ms is not None else None
+                )
+                neutrino._current_task_priority = priority
+
                 # Execute the task using pre-loaded routes
                 try:
                     # Find the route handler by function name in global registry
@@ -161,11 +175,35 @@ def main() -> NoReturn:
                     print(f"[Worker {worker_id}] Task {task_id} failed: {e}", file=sys.stderr)
                     import traceback
                     traceback.print_exc()
-                    error_msg = {"error": str(e), "type": type(e).__name__}
+                    # The orchestrator returns the traceback only to trusted clients
+                    error_msg = {
+                        "error": str(e),
+                        "type": type(e).__name__,
+                        "traceback": traceback.format_exc(),
+                    }
                     protocol.send_task_result(task_id, False, error_msg)
+                finally:
+                    neutrino._current_task_deadline = None
+                    neutrino._current_task_priority = None
+            elif "CancelTask" in message:
+                # Tasks run synchronously in this loop, so a cancelled task has
+                # already finished; the orchestrator discards its result
+                cancel_data = message["CancelTask"]
+                task_id = cancel_data["task_id"] if isinstance(cancel_data, dict) else cancel_data[0]
+                print(f"[Worker {worker_id}] Task {task_id} cancelled after it finished")
+            elif "DrainRequest" in message:
+                # Tasks run synchronously in this loop, so by the time the drain
+                # request is read there is no queued work left
+                print(f"[Worker {worker_id}] Drained")
+                protocol.send_drain_complete(worker_id)
+            elif "ListHandlers" in message:
+                # Lets the orchestrator reject requests for handlers this app doesn't define
+                handlers = sorted({route.handler.__name__ for route in route_registry.values()})
+                protocol.send_handler_list(worker_id, handlers)
             elif "Heartbeat" in message:
-                # Respond to heartbeat
-                protocol.send_heartbeat(worker_id)
+                # Respond to heartbeat. Tasks run synchronously in this loop, so
+                # nothing is ever queued internally.
+                protocol.send_heartbeat(worker_id, queue_depth=0)
             else:
                 print(f"[Worker {worker_id}] Unknown message: {message}")
 
@@ -1,8 +1,13 @@
 """
 Protocol handler for communication with Rust orchestrator.
 Wire format: [4 bytes: big-endian length][N bytes: msgpack payload]
+
+With NEUTRINO_WIRE_FORMAT=json (set by the orchestrator when its worker
+wire_format is "json") the payload is JSON instead, for debugging.
 """
 
+import json
+import os
 import socket
 import struct
 from typing import Any
@@ -10,15 +15,28 @@ from typing import Any
 import msgpack
 
 
+def _json_default(value: Any) -> Any:
+    """Encode bytes the way serde does (a list of ints)."""
+    if isinstance(value, (bytes, bytearray)):
+        return list(value)
+    raise TypeError(f"Object of type {type(value).__name__} is not JSON serializable")
+
+
 class ProtocolHandler:
-    """Handles msgpack communication over Unix socket."""
+    """Handles msgpack (or JSON) communication over Unix socket."""
 
-    def __init__(self, sock: socket.socket):
+    def __init__(self, sock: socket.socket, wire_format: str | None = None):
         self.sock = sock
+        self.wire_format = (wire_format or os.environ.get("NEUTRINO_WIRE_FORMAT", "msgpack")).lower()
+        if self.wire_format not in ("msgpack", "json"):
+            raise ValueError(f"Unknown wire format {self.wire_format!r} (expected msgpack or json)")
 
     def send(self, message: dict[str, Any]) -> None:
         """Send a message to the orchestrator."""
-        payload = msgpack.packb(message, use_bin_type=True)
+        if self.wire_format == "json":
+            payload = json.dumps(message, default=_json_default).encode()
+        else:
+            payload = msgpack.packb(message, use_bin_type=True)
         length = struct.pack(">I", len(payload))  # Big-endian u32
         self.sock.sendall(length + payload)
 
@@ -30,6 +48,8 @@ class ProtocolHandler:
 
         # Read payload
         payload = self._recv_exact(length)
+        if self.wire_format == "json":
+            return json.loads(payload)
         return msgpack.unpackb(payload, raw=False)
 
     def _recv_exact(self, n: int) -> bytes:
@@ -53,7 +73,9 @@ class ProtocolHandler:
                     "num_cpus": num_cpus,
                     "num_gpus": num_gpus,
                     "memory_gb": memory_gb
-                }
+                },
+                # Tasks run to completion; CancelTask arrives after they finish
+                "cancellable": False
             }
         })
 
@@ -77,6 +99,40 @@ class ProtocolHandler:
             }
         )
 
-    def send_heartbeat(self, worker_id: str) -> None:
-        """Send Heartbeat message."""
-        self.send({"Heartbeat": {"worker_id": worker_id}})
\ No newline at end of file
+    def send_heartbeat(self, worker_id: str, queue_depth: int | None = None) -> None:
+        """Send Heartbeat message, optionally with the number of internally queued tasks."""
+        self.send({"Heartbeat": {"worker_id": worker_id, "queue_depth": queue_depth}})
+
+    def send_drain_status(self, worker_id: str, pending: int) -> None:
+        """Send DrainStatus message (work still pending while draining)."""
+        self.send({"DrainStatus": {"worker_id": worker_id, "pending": pending}})
+
+    def send_drain_complete(self, worker_id: str) -> None:
+        """Send DrainComplete message (no pending work, safe to shut down)."""
+        self.send({"DrainComplete": {"worker_id": worker_id}})
+
+    def send_handler_list(self, worker_id: str, handlers: list[str]) -> None:
+        """Send HandlerList message with the handler names this worker can execute."""
+        self.send({"HandlerList": {"worker_id": worker_id, "handlers": handlers}})
+
+    def send_update_capabilities(
+        self,
+        num_cpus: float,
+        num_gpus: float,
+        memory_gb: float,
+        gpu_memory_gb: float | None = None,
+    ) -> None:
+        """Send UpdateCapabilities message, e.g. after losing GPU memory to another process.
+
+        The orchestrator caps each value at what the worker was provisioned with.
+        """
+        self.send({
+            "UpdateCapabilities": {
+                "capabilities": {
+                    "num_cpus": num_cpus,
+                    "num_gpus": num_gpus,
+                    "memory_gb": memory_gb,
+                    "gpu_memory_gb": gpu_memory_gb,
+                }
+            }
+        })
@@ -110,8 +110,57 @@ def generate_operation(route: Any, method: str) -> dict[str, Any]:
             "num_cpus": getattr(route, 'num_cpus', 1.0),
             "num_gpus": getattr(route, 'num_gpus', 0.0),
             "memory_gb": getattr(route, 'memory_gb', 1.0),
+            "gpu_memory_gb": getattr(route, 'gpu_memory_gb', 0.0),
         }
 
+    # Per-route request body limit (overrides http.max_body_bytes)
+    if getattr(route, 'max_body_bytes', None) is not None:
+        operation["x-neutrino-max-body-bytes"] = route.max_body_bytes
+
+    # Pool to overflow to when no worker can take the task
+    if getattr(route, 'overflow_pool', None) is not None:
+        operation["x-neutrino-overflow-pool"] = route.overflow_pool
+
+    # Pool the route's tasks are pinned to
+    if getattr(route, 'pool', None) is not None:
+        operation["x-neutrino-pool"] = route.pool
+
+    # Most tasks of the handler running at once
+    if getattr(route, 'max_concurrency', None) is not None:
+        operation["x-neutrino-max-concurrency"] = route.max_concurrency
+
+    # Duplicate a straggling task on a second worker (honored on GET only)
+    if getattr(route, 'hedge_after_ms', None) is not None:
+        operation["x-neutrino-hedge-after-ms"] = route.hedge_after_ms
+
+    # Serve identical requests from cache for this many seconds
+    if getattr(route, 'cache_ttl', None) is not None:
+        operation["x-neutrino-cache-ttl"] = route.cache_ttl
+
+    # Dispatch priority while waiting for a worker (default 0 is omitted)
+    if getattr(route, 'priority', 0):
+        operation["x-neutrino-priority"] = route.priority
+
+    # Reshaping of the request's args into the handler's signature
+    if getattr(route, 'args_template', None) is not None:
+        operation["x-neutrino-args-template"] = route.args_template
+
+    # Dedicated pools for tenants' tasks
+    if getattr(route, 'tenant_pools', None):
+        operation["x-neutrino-tenant-pools"] = route.tenant_pools
+
+    # Identical concurrent requests share one task
+    if getattr(route, 'coalesce', False):
+        operation["x-neutrino-coalesce"] = True
+
+    # Strict request bodies (unset follows http.reject_unknown_fields)
+    if getattr(route, 'reject_unknown_fields', None) is not None:
+        operation["x-neutrino-reject-unknown-fields"] = route.reject_unknown_fields
+
+    # Arg identifying the request's session, for worker affinity
+    if getattr(route, 'affinity_key', None) is not None:
+        operation["x-neutrino-affinity-key"] = route.affinity_key
+
     # Parameters (path params)
     openapi_path = convert_path_to_openapi(route.path)
     path_params = extract_path_parameters(openapi_path)
@@ -29,6 +29,19 @@ class Route:
         num_cpus: float = 1.0,
         num_gpus: float = 0.0,
         memory_gb: float = 1.0,
+        gpu_memory_gb: float = 0.0,
+        max_body_bytes: int | None = None,
+        overflow_pool: str | None = None,
+        pool: str | None = None,
+        max_concurrency: int | None = None,
+        hedge_after_ms: int | None = None,
+        cache_ttl: int | None = None,
+        priority: int = 0,
+        args_template: dict[str, Any] | None = None,
+        tenant_pools: dict[str, str] | None = None,
+        coalesce: bool = False,
+        reject_unknown_fields: bool | None = None,
+        affinity_key: str | None = None,
     ):
         self.handler = handler
         self.path = path
@@ -41,6 +54,19 @@ class Route:
         self.num_cpus = num_cpus
         self.num_gpus = num_gpus
         self.memory_gb = memory_gb
+        self.gpu_memory_gb = gpu_memory_gb
+        self.max_body_bytes = max_body_bytes
+        self.overflow_pool = overflow_pool
+        self.pool = pool
+        self.max_concurrency = max_concurrency
+        self.hedge_after_ms = hedge_after_ms
+        self.cache_ttl = cache_ttl
+        self.priority = priority
+        self.args_template = args_template
+        self.tenant_pools = tenant_pools or {}
+        self.coalesce = coalesce
+        self.reject_unknown_fields = reject_unknown_fields
+        self.affinity_key = affinity_key
         self.__name__ = handler.__name__
         self.__doc__ = handler.__doc__
 